@@ -0,0 +1,103 @@
+//! Render path benchmarks.
+//!
+//! Each group draws frames on the stub LCD and reports the I2C bytes a frame takes as its
+//! throughput, so `cargo bench` shows both the CPU time and the bus cost of a full page change,
+//! a scroll step and the status overlays. A jump in the bytes per frame is a regression even
+//! when the timings hold.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use lifelinetty::{
+    display::{
+        icon_bank::IconBank,
+        overlays::{render_frame_with_scroll, StatusMarks},
+    },
+    lcd::Lcd,
+    RenderFrame,
+};
+
+fn frame(json: &str) -> RenderFrame {
+    RenderFrame::from_payload_json(json).expect("bench payload parses")
+}
+
+/// Draw step `step` of `frames`: pages in turn, scrolled one cell per step when `scroll` is set.
+fn draw(
+    lcd: &mut Lcd,
+    bank: &mut IconBank,
+    frames: &[RenderFrame],
+    step: usize,
+    scroll: bool,
+    status: StatusMarks<'_>,
+) {
+    let frame = &frames[step % frames.len()];
+    let offsets = if scroll { [step, step] } else { [0, 0] };
+    render_frame_with_scroll(
+        lcd,
+        frame,
+        &offsets,
+        step.is_multiple_of(2),
+        status,
+        None,
+        bank,
+    )
+    .expect("stub render");
+}
+
+/// I2C bytes per draw, averaged over a warmed-up cycle so the first paint is not counted.
+fn bytes_per_draw(frames: &[RenderFrame], scroll: bool, status: StatusMarks<'_>) -> u64 {
+    const DRAWS: usize = 64;
+    let mut lcd = Lcd::new_stub(16, 2);
+    let mut bank = IconBank::new();
+    draw(&mut lcd, &mut bank, frames, 0, scroll, status);
+    let before = lcd.bus_bytes();
+    for step in 1..=DRAWS {
+        draw(&mut lcd, &mut bank, frames, step, scroll, status);
+    }
+    (lcd.bus_bytes() - before) / DRAWS as u64
+}
+
+fn bench_case(
+    c: &mut Criterion,
+    name: &str,
+    frames: &[RenderFrame],
+    scroll: bool,
+    status: StatusMarks<'_>,
+) {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(bytes_per_draw(frames, scroll, status)));
+    group.bench_function("stub_16x2", |b| {
+        let mut lcd = Lcd::new_stub(16, 2);
+        let mut bank = IconBank::new();
+        let mut step = 0;
+        b.iter(|| {
+            step += 1;
+            draw(&mut lcd, &mut bank, black_box(frames), step, scroll, status);
+        });
+    });
+    group.finish();
+}
+
+fn render(c: &mut Criterion) {
+    let pages = [
+        frame(r#"{"schema_version":1,"line1":"CPU 42% up 3d","line2":"mem 61% swap 2%"}"#),
+        frame(r#"{"schema_version":1,"line1":"eth0 12.4 Mb/s","line2":"wlan0 down"}"#),
+    ];
+    bench_case(c, "full_redraw", &pages, false, StatusMarks::default());
+
+    let ticker = [frame(
+        r#"{"schema_version":1,"line1":"backup 3 of 7: /srv/media/photos","line2":"eta 00:14:32 at 38 MB/s","scroll":true}"#,
+    )];
+    bench_case(c, "scroll_step", &ticker, true, StatusMarks::default());
+
+    let bar = [frame(
+        r#"{"schema_version":1,"line1":"disk","line2":"","bar":64,"bar_label":"/srv"}"#,
+    )];
+    let status = StatusMarks {
+        glyph: Some('~'),
+        badge: Some("!3"),
+        fold_row: 0,
+    };
+    bench_case(c, "overlays", &bar, false, status);
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
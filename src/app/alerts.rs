@@ -0,0 +1,188 @@
+use crate::{
+    config::BadgesConfig,
+    display::overlays::StatusMarks,
+    payload::{RenderFrame, Severity},
+};
+
+/// Counts alerts and errors that arrived since the operator last acknowledged the display, and
+/// renders them as the compact badge drawn on the status row.
+#[derive(Debug, Clone)]
+pub struct AlertBadges {
+    enabled: bool,
+    width: usize,
+    alerts: u32,
+    errors: u32,
+    rejected_seen: u64,
+    label: Option<String>,
+    dirty: bool,
+}
+
+impl AlertBadges {
+    pub fn new(config: &BadgesConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            width: config.width as usize,
+            alerts: 0,
+            errors: 0,
+            rejected_seen: 0,
+            label: None,
+            dirty: false,
+        }
+    }
+
+    /// Apply a reloaded `[badges]` section, keeping any counts already collected.
+    pub fn reconfigure(&mut self, config: &BadgesConfig) {
+        self.enabled = config.enabled;
+        self.width = config.width as usize;
+        self.refresh();
+        self.dirty = true;
+    }
+
+    /// Count an accepted frame that carries warn or crit severity.
+    pub fn on_frame(&mut self, frame: &RenderFrame) {
+        if frame.severity >= Some(Severity::Warn) {
+            self.on_alerts(1);
+        }
+    }
+
+    pub fn on_alerts(&mut self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.alerts = self.alerts.saturating_add(count as u32);
+        self.refresh();
+    }
+
+    /// Follow the running rejected-frame total; anything new since the last call is an error.
+    pub fn sync_rejected(&mut self, total: u64) {
+        let fresh = total.saturating_sub(self.rejected_seen);
+        self.rejected_seen = total;
+        if fresh > 0 {
+            self.errors = self
+                .errors
+                .saturating_add(fresh.min(u32::MAX as u64) as u32);
+            self.refresh();
+        }
+    }
+
+    /// Acknowledge everything counted so far. Returns `true` when a badge was showing.
+    pub fn clear(&mut self) -> bool {
+        let showing = self.label.is_some();
+        self.alerts = 0;
+        self.errors = 0;
+        self.refresh();
+        showing
+    }
+
+    /// Whether the badge text changed since the last call.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Status-row marks for a render: the badge alongside the modem glyph.
+    pub fn marks(&self, glyph: Option<char>) -> StatusMarks<'_> {
+        StatusMarks {
+            glyph,
+            badge: self.label(),
+            fold_row: 0,
+        }
+    }
+
+    fn refresh(&mut self) {
+        let label = if self.enabled {
+            badge_label(self.alerts, self.errors, self.width)
+        } else {
+            None
+        };
+        if label != self.label {
+            self.label = label;
+            self.dirty = true;
+        }
+    }
+}
+
+/// `!3E2` when both counts fit, otherwise the combined total, saturating as `!9+`.
+fn badge_label(alerts: u32, errors: u32, width: usize) -> Option<String> {
+    if alerts == 0 && errors == 0 {
+        return None;
+    }
+    let mut label = String::new();
+    if alerts > 0 {
+        label.push_str(&format!("!{alerts}"));
+    }
+    if errors > 0 {
+        label.push_str(&format!("E{errors}"));
+    }
+    if label.len() <= width {
+        return Some(label);
+    }
+    let total = format!("!{}", alerts.saturating_add(errors));
+    if total.len() <= width {
+        return Some(total);
+    }
+    Some(format!("!{}+", "9".repeat(width.saturating_sub(2))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn badges(width: u8) -> AlertBadges {
+        AlertBadges::new(&BadgesConfig {
+            enabled: true,
+            width,
+        })
+    }
+
+    #[test]
+    fn labels_split_alerts_and_errors_until_they_overflow() {
+        assert_eq!(badge_label(0, 0, 3), None);
+        assert_eq!(badge_label(2, 0, 3).as_deref(), Some("!2"));
+        assert_eq!(badge_label(0, 4, 3).as_deref(), Some("E4"));
+        assert_eq!(badge_label(1, 1, 4).as_deref(), Some("!1E1"));
+        assert_eq!(badge_label(1, 1, 3).as_deref(), Some("!2"));
+        assert_eq!(badge_label(60, 50, 3).as_deref(), Some("!9+"));
+        assert_eq!(badge_label(5, 5, 2).as_deref(), Some("!+"));
+    }
+
+    #[test]
+    fn rejected_totals_count_only_new_errors() {
+        let mut badges = badges(3);
+        badges.sync_rejected(2);
+        assert_eq!(badges.label(), Some("E2"));
+        assert!(badges.take_dirty());
+        badges.sync_rejected(2);
+        assert!(!badges.take_dirty());
+        badges.sync_rejected(3);
+        assert_eq!(badges.label(), Some("E3"));
+    }
+
+    #[test]
+    fn clearing_acknowledges_the_badge() {
+        let mut badges = badges(4);
+        badges.on_alerts(1);
+        badges.sync_rejected(1);
+        assert_eq!(badges.label(), Some("!1E1"));
+        assert!(badges.clear());
+        assert_eq!(badges.label(), None);
+        assert!(badges.take_dirty());
+        assert!(!badges.clear());
+    }
+
+    #[test]
+    fn disabled_badges_count_without_showing() {
+        let mut badges = AlertBadges::new(&BadgesConfig::default());
+        badges.on_alerts(3);
+        assert_eq!(badges.label(), None);
+        assert!(!badges.take_dirty());
+        badges.reconfigure(&BadgesConfig {
+            enabled: true,
+            width: 3,
+        });
+        assert_eq!(badges.label(), Some("!3"));
+    }
+}
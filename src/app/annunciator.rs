@@ -0,0 +1,333 @@
+//! Frame-to-GPIO annunciators: drive an external lamp or relay from what the LCD is showing.
+
+use crate::{
+    config::AnnunciatorConfig,
+    payload::{Icon, RenderFrame, Severity},
+    Error, Result,
+};
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// What a rule looks for in the frame currently on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    SeverityAtLeast(Severity),
+    SeverityIs(Severity),
+    Icon(Icon),
+    BarAtLeast(u8),
+}
+
+/// One `PIN:CONDITION` entry from `[annunciators].rules`, e.g. `17:severity>=warn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnnunciatorRule {
+    pub pin: u8,
+    pub condition: Condition,
+}
+
+impl FromStr for AnnunciatorRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (pin, condition) = s
+            .split_once(':')
+            .ok_or_else(|| format!("annunciator rule '{s}' must look like PIN:CONDITION"))?;
+        let pin = pin
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid GPIO pin in annunciator rule '{s}'"))?;
+        let condition = condition.trim();
+        let severity = |name: &str| {
+            Severity::from_name(name)
+                .ok_or_else(|| format!("unknown severity '{name}', expected info|warn|crit"))
+        };
+        let condition = if let Some(name) = condition.strip_prefix("severity>=") {
+            Condition::SeverityAtLeast(severity(name)?)
+        } else if let Some(name) = condition.strip_prefix("severity=") {
+            Condition::SeverityIs(severity(name)?)
+        } else if let Some(name) = condition.strip_prefix("icon=") {
+            Condition::Icon(Icon::from_name(name).ok_or_else(|| format!("unknown icon '{name}'"))?)
+        } else if let Some(percent) = condition.strip_prefix("bar>=") {
+            match percent.trim().parse::<u8>() {
+                Ok(percent) if percent <= 100 => Condition::BarAtLeast(percent),
+                _ => return Err(format!("bar threshold '{percent}' must be 0-100")),
+            }
+        } else {
+            return Err(format!(
+                "unknown annunciator condition '{condition}', expected severity>=, severity=, icon= or bar>="
+            ));
+        };
+        Ok(Self { pin, condition })
+    }
+}
+
+#[derive(Debug)]
+struct RuleState {
+    rule: AnnunciatorRule,
+    on: bool,
+    changed_at: Option<Instant>,
+}
+
+/// Rule evaluation with hysteresis; kept apart from the pins so it can be tested off-target.
+#[derive(Debug)]
+pub struct AnnunciatorRules {
+    states: Vec<RuleState>,
+    bar_hysteresis: u8,
+    hold: Duration,
+}
+
+impl AnnunciatorRules {
+    pub fn new(config: &AnnunciatorConfig) -> Result<Self> {
+        let states = config
+            .rules
+            .iter()
+            .map(|raw| {
+                raw.parse::<AnnunciatorRule>()
+                    .map(|rule| RuleState {
+                        rule,
+                        on: false,
+                        changed_at: None,
+                    })
+                    .map_err(Error::InvalidArgs)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            states,
+            bar_hysteresis: config.bar_hysteresis,
+            hold: Duration::from_millis(config.hold_ms),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Distinct pins named by the rules, in first-seen order.
+    pub fn pins(&self) -> Vec<u8> {
+        let mut pins = Vec::new();
+        for state in &self.states {
+            if !pins.contains(&state.rule.pin) {
+                pins.push(state.rule.pin);
+            }
+        }
+        pins
+    }
+
+    /// Re-evaluate against the frame on screen (`None` for local pages and status screens)
+    /// and return each pin's logical state; rules sharing a pin are OR-ed.
+    pub fn evaluate(&mut self, frame: Option<&RenderFrame>, now: Instant) -> Vec<(u8, bool)> {
+        for state in &mut self.states {
+            let wanted = matches(state.rule.condition, state.on, self.bar_hysteresis, frame);
+            let settled = state
+                .changed_at
+                .is_none_or(|at| now.saturating_duration_since(at) >= self.hold);
+            if wanted != state.on && settled {
+                state.on = wanted;
+                state.changed_at = Some(now);
+            }
+        }
+        self.pins()
+            .into_iter()
+            .map(|pin| {
+                let on = self
+                    .states
+                    .iter()
+                    .any(|state| state.rule.pin == pin && state.on);
+                (pin, on)
+            })
+            .collect()
+    }
+}
+
+fn matches(
+    condition: Condition,
+    currently_on: bool,
+    bar_hysteresis: u8,
+    frame: Option<&RenderFrame>,
+) -> bool {
+    let Some(frame) = frame else {
+        return false;
+    };
+    match condition {
+        Condition::SeverityAtLeast(level) => frame.severity.is_some_and(|s| s >= level),
+        Condition::SeverityIs(level) => frame.severity == Some(level),
+        Condition::Icon(icon) => frame.icons.contains(&icon),
+        Condition::BarAtLeast(threshold) => {
+            // Once lit, stay lit until the bar falls a full band below the threshold.
+            let release = if currently_on {
+                threshold.saturating_sub(bar_hysteresis)
+            } else {
+                threshold
+            };
+            frame.bar_percent.is_some_and(|percent| percent >= release)
+        }
+    }
+}
+
+/// Rules plus the output pins they drive.
+pub struct Annunciators {
+    rules: AnnunciatorRules,
+    pins: OutputPins,
+    active_low: bool,
+    levels: Vec<(u8, bool)>,
+}
+
+impl Annunciators {
+    /// `Ok(None)` when no rules are configured.
+    pub fn new(config: &AnnunciatorConfig) -> Result<Option<Self>> {
+        let rules = AnnunciatorRules::new(config)?;
+        if rules.is_empty() {
+            return Ok(None);
+        }
+        let pins = OutputPins::open(&rules.pins(), config.active_low)?;
+        Ok(Some(Self {
+            rules,
+            pins,
+            active_low: config.active_low,
+            levels: Vec::new(),
+        }))
+    }
+
+    /// Returns the pins whose state changed, for logging.
+    pub fn update(&mut self, frame: Option<&RenderFrame>, now: Instant) -> Vec<(u8, bool)> {
+        let levels = self.rules.evaluate(frame, now);
+        let changed: Vec<(u8, bool)> = levels
+            .iter()
+            .filter(|level| !self.levels.contains(level))
+            .copied()
+            .collect();
+        for (pin, on) in &changed {
+            self.pins.write(*pin, *on != self.active_low);
+        }
+        self.levels = levels;
+        changed
+    }
+
+    /// Drive every output inactive, e.g. on shutdown.
+    pub fn release(&mut self) {
+        for (pin, _) in &self.levels {
+            self.pins.write(*pin, self.active_low);
+        }
+        self.levels.clear();
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct OutputPins {
+    pins: Vec<(u8, rppal::gpio::OutputPin)>,
+}
+
+#[cfg(target_os = "linux")]
+impl OutputPins {
+    fn open(pins: &[u8], active_low: bool) -> Result<Self> {
+        let gpio = rppal::gpio::Gpio::new().map_err(|e| Error::Io(std::io::Error::other(e)))?;
+        let pins = pins
+            .iter()
+            .map(|pin| {
+                let pin_handle = gpio
+                    .get(*pin)
+                    .map_err(|e| Error::Io(std::io::Error::other(e)))?;
+                let output = if active_low {
+                    pin_handle.into_output_high()
+                } else {
+                    pin_handle.into_output_low()
+                };
+                Ok((*pin, output))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { pins })
+    }
+
+    fn write(&mut self, pin: u8, high: bool) {
+        if let Some((_, output)) = self.pins.iter_mut().find(|(p, _)| *p == pin) {
+            if high {
+                output.set_high();
+            } else {
+                output.set_low();
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+struct OutputPins;
+
+#[cfg(not(target_os = "linux"))]
+impl OutputPins {
+    fn open(_pins: &[u8], _active_low: bool) -> Result<Self> {
+        Err(Error::InvalidArgs(
+            "GPIO annunciators unsupported on this platform".into(),
+        ))
+    }
+
+    fn write(&mut self, _pin: u8, _high: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(entries: &[&str], hold_ms: u64) -> AnnunciatorRules {
+        AnnunciatorRules::new(&AnnunciatorConfig {
+            rules: entries.iter().map(|e| e.to_string()).collect(),
+            bar_hysteresis: 10,
+            hold_ms,
+            active_low: false,
+        })
+        .unwrap()
+    }
+
+    fn frame(raw: &str) -> RenderFrame {
+        RenderFrame::from_payload_json(raw).unwrap()
+    }
+
+    #[test]
+    fn parses_rule_syntax() {
+        let rule: AnnunciatorRule = "17:severity>=warn".parse().unwrap();
+        assert_eq!(rule.pin, 17);
+        assert_eq!(rule.condition, Condition::SeverityAtLeast(Severity::Warn));
+        assert_eq!(
+            "27:icon=bell".parse::<AnnunciatorRule>().unwrap().condition,
+            Condition::Icon(Icon::Bell)
+        );
+        assert!("22:bar>=101".parse::<AnnunciatorRule>().is_err());
+        assert!("x:icon=bell".parse::<AnnunciatorRule>().is_err());
+        assert!("5:colour=red".parse::<AnnunciatorRule>().is_err());
+    }
+
+    #[test]
+    fn severity_and_icon_rules_share_a_pin() {
+        let mut set = rules(&["17:severity>=warn", "17:icon=bell"], 0);
+        let now = Instant::now();
+        let crit = frame(r#"{"schema_version":1,"line1":"a","line2":"b","severity":"crit"}"#);
+        let info = frame(r#"{"schema_version":1,"line1":"a","line2":"b","severity":"info"}"#);
+        let bell = frame(r#"{"schema_version":1,"line1":"a","line2":"b","icons":["bell"]}"#);
+        assert_eq!(set.evaluate(Some(&crit), now), vec![(17, true)]);
+        assert_eq!(set.evaluate(Some(&info), now), vec![(17, false)]);
+        assert_eq!(set.evaluate(Some(&bell), now), vec![(17, true)]);
+        assert_eq!(set.evaluate(None, now), vec![(17, false)]);
+    }
+
+    #[test]
+    fn bar_rule_uses_hysteresis_band_and_hold() {
+        let mut set = rules(&["22:bar>=80"], 1_000);
+        let start = Instant::now();
+        let bar = |pct: u8| {
+            frame(&format!(
+                r#"{{"schema_version":1,"line1":"a","line2":"b","bar":{pct}}}"#
+            ))
+        };
+        assert_eq!(set.evaluate(Some(&bar(85)), start), vec![(22, true)]);
+        // Inside the band, and then below it but within the hold time: stays lit.
+        let later = start + Duration::from_millis(1_500);
+        assert_eq!(set.evaluate(Some(&bar(75)), later), vec![(22, true)]);
+        let mut set_held = rules(&["22:bar>=80"], 1_000);
+        set_held.evaluate(Some(&bar(85)), start);
+        assert_eq!(
+            set_held.evaluate(Some(&bar(10)), start + Duration::from_millis(200)),
+            vec![(22, true)]
+        );
+        assert_eq!(set.evaluate(Some(&bar(65)), later), vec![(22, false)]);
+    }
+}
@@ -0,0 +1,242 @@
+//! Anomaly detectors fed by the loop's telemetry counters, and the page a peer's summary becomes.
+//!
+//! Reconnect attempts and polled temperatures are counted over `[anomaly].window_ms`. When one
+//! crosses its threshold the detector produces an [`AnomalySummary`]: it is logged locally and,
+//! with `[anomaly].push` and a peer that advertises support, sent as an `anomaly_summary` control
+//! frame once the link is up. Each kind then stays quiet for one window so a storm yields one
+//! summary, not one per reconnect. The receiving end logs the summary and queues it as a warning
+//! page.
+
+use crate::{config::AnomalyConfig, negotiation::AnomalySummary, payload::Payload};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Longest text either LCD line of a peer summary page may carry.
+const MAX_PAGE_LINE_CHARS: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnomalyKind {
+    ReconnectStorm,
+    TemperatureSpike,
+}
+
+impl AnomalyKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnomalyKind::ReconnectStorm => "reconnect_storm",
+            AnomalyKind::TemperatureSpike => "temperature_spike",
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            AnomalyKind::ReconnectStorm => 0,
+            AnomalyKind::TemperatureSpike => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AnomalyDetector {
+    window: Duration,
+    reconnect_limit: u32,
+    temp_rise_c: f32,
+    push: bool,
+    reconnects: VecDeque<Instant>,
+    temperatures: VecDeque<(Instant, f32)>,
+    last_fired: [Option<Instant>; 2],
+    /// Summaries waiting for the link, at most one per kind.
+    pending: Vec<AnomalySummary>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: &AnomalyConfig) -> Self {
+        Self {
+            window: Duration::from_millis(config.window_ms),
+            reconnect_limit: config.reconnects,
+            temp_rise_c: config.temp_rise_c as f32,
+            push: config.push,
+            reconnects: VecDeque::new(),
+            temperatures: VecDeque::new(),
+            last_fired: [None; 2],
+            pending: Vec::new(),
+        }
+    }
+
+    /// Count a reconnect attempt; `Some` when it completes a storm.
+    pub fn record_reconnect(&mut self, now: Instant) -> Option<AnomalySummary> {
+        if self.reconnect_limit == 0 {
+            return None;
+        }
+        self.reconnects.push_back(now);
+        while self
+            .reconnects
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > self.window)
+        {
+            self.reconnects.pop_front();
+        }
+        let count = self.reconnects.len();
+        if count < self.reconnect_limit as usize {
+            return None;
+        }
+        let summary = format!("{count} reconnects in {}", window_label(self.window));
+        self.fire(AnomalyKind::ReconnectStorm, summary, now)
+    }
+
+    /// Track a polled temperature; `Some` when it has climbed `temp_rise_c` above the window's low.
+    pub fn record_temperature(&mut self, celsius: f32, now: Instant) -> Option<AnomalySummary> {
+        if self.temp_rise_c <= 0.0 || !celsius.is_finite() {
+            return None;
+        }
+        self.temperatures.push_back((now, celsius));
+        while self
+            .temperatures
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > self.window)
+        {
+            self.temperatures.pop_front();
+        }
+        let low = self
+            .temperatures
+            .iter()
+            .map(|(_, reading)| *reading)
+            .fold(celsius, f32::min);
+        let rise = celsius - low;
+        if rise < self.temp_rise_c {
+            return None;
+        }
+        let summary = format!(
+            "temp {celsius:.0}C, up {rise:.0}C in {}",
+            window_label(self.window)
+        );
+        self.fire(AnomalyKind::TemperatureSpike, summary, now)
+    }
+
+    /// Summaries still to be sent to the peer, oldest first.
+    pub fn take_pending(&mut self) -> Vec<AnomalySummary> {
+        std::mem::take(&mut self.pending)
+    }
+
+    fn fire(&mut self, kind: AnomalyKind, summary: String, now: Instant) -> Option<AnomalySummary> {
+        let last = &mut self.last_fired[kind.index()];
+        if last.is_some_and(|at| now.duration_since(at) < self.window) {
+            return None;
+        }
+        *last = Some(now);
+        let summary = AnomalySummary {
+            kind: kind.as_str().to_string(),
+            summary,
+            window_ms: self.window.as_millis() as u64,
+        };
+        if self.push {
+            self.pending.retain(|queued| queued.kind != summary.kind);
+            self.pending.push(summary.clone());
+        }
+        Some(summary)
+    }
+}
+
+/// `5m` or `90s`, for summary text.
+fn window_label(window: Duration) -> String {
+    let secs = window.as_secs();
+    if secs >= 60 && secs.is_multiple_of(60) {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// Payload JSON for a summary received from the peer: `PEER RECONNECT STORM` over the summary,
+/// with warning severity.
+pub fn peer_page(summary: &AnomalySummary) -> String {
+    let title = format!("PEER {}", summary.kind.replace('_', " ").to_uppercase());
+    let mut payload = Payload::new(
+        title.chars().take(MAX_PAGE_LINE_CHARS).collect::<String>(),
+        summary
+            .summary
+            .chars()
+            .filter(|ch| !ch.is_control())
+            .take(MAX_PAGE_LINE_CHARS)
+            .collect::<String>(),
+    );
+    payload.severity = Some("warn".into());
+    serde_json::to_string(&payload).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::{RenderFrame, Severity};
+
+    fn config(reconnects: u32, temp_rise_c: u32) -> AnomalyConfig {
+        AnomalyConfig {
+            push: true,
+            window_ms: 60_000,
+            reconnects,
+            temp_rise_c,
+        }
+    }
+
+    #[test]
+    fn reconnect_storm_fires_once_per_window_and_queues_for_the_peer() {
+        let start = Instant::now();
+        let mut detector = AnomalyDetector::new(&config(3, 0));
+        assert!(detector.record_reconnect(start).is_none());
+        assert!(detector
+            .record_reconnect(start + Duration::from_secs(10))
+            .is_none());
+        let storm = detector
+            .record_reconnect(start + Duration::from_secs(20))
+            .unwrap();
+        assert_eq!(storm.kind, "reconnect_storm");
+        assert_eq!(storm.summary, "3 reconnects in 1m");
+        assert!(detector
+            .record_reconnect(start + Duration::from_secs(30))
+            .is_none());
+        assert_eq!(detector.take_pending(), vec![storm]);
+        assert!(detector.take_pending().is_empty());
+
+        // By the time the cooldown ends, the earlier attempts have left the window too.
+        assert!(detector
+            .record_reconnect(start + Duration::from_secs(95))
+            .is_none());
+    }
+
+    #[test]
+    fn temperature_spike_measures_the_rise_within_the_window() {
+        let start = Instant::now();
+        let mut detector = AnomalyDetector::new(&config(0, 10));
+        assert!(detector.record_reconnect(start).is_none());
+        assert!(detector.record_temperature(50.0, start).is_none());
+        assert!(detector
+            .record_temperature(58.0, start + Duration::from_secs(30))
+            .is_none());
+        let spike = detector
+            .record_temperature(61.0, start + Duration::from_secs(50))
+            .unwrap();
+        assert_eq!(spike.summary, "temp 61C, up 11C in 1m");
+        // The 50C reading has left the window, so a later climb is measured from 58C.
+        let mut later = AnomalyDetector::new(&config(0, 10));
+        later.record_temperature(50.0, start);
+        later.record_temperature(58.0, start + Duration::from_secs(30));
+        assert!(later
+            .record_temperature(61.0, start + Duration::from_secs(70))
+            .is_none());
+    }
+
+    #[test]
+    fn peer_summary_becomes_a_warning_page() {
+        let page = peer_page(&AnomalySummary {
+            kind: "reconnect_storm".into(),
+            summary: "7 reconnects in 5m".into(),
+            window_ms: 300_000,
+        });
+        let frame = RenderFrame::from_payload_json(&page).unwrap();
+        assert_eq!(frame.line1, "PEER RECONNECT STORM");
+        assert_eq!(frame.line2, "7 reconnects in 5m");
+        assert_eq!(frame.severity, Some(Severity::Warn));
+    }
+}
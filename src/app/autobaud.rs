@@ -0,0 +1,87 @@
+use super::{AppConfig, Logger};
+use crate::{
+    cli::RunOptions,
+    config::{Config, MIN_BAUD},
+    serial::autobaud::{best_guess, probe, BaudScore, CANDIDATE_BAUDS, DEFAULT_SAMPLE_WINDOW_MS},
+    Error, ExitCode, Result,
+};
+use std::path::Path;
+
+/// Listen-only probe behind `--autobaud-probe`: print per-rate scores and the suggested baud.
+pub fn run_probe(opts: RunOptions) -> Result<()> {
+    let cfg = if let Some(path) = opts.config_file.as_deref() {
+        Config::load_from_path(Path::new(path))
+    } else {
+        Config::load_or_default()
+    }
+    .map_err(|err| err.exit_with(ExitCode::Config))?;
+    let merged = AppConfig::from_sources(cfg, opts);
+    println!(
+        "autobaud: listening on {} ({}ms per rate, nothing is transmitted)",
+        merged.device, DEFAULT_SAMPLE_WINDOW_MS
+    );
+    let scores = probe(
+        &merged.device,
+        merged.serial_options(),
+        &CANDIDATE_BAUDS,
+        DEFAULT_SAMPLE_WINDOW_MS,
+    )
+    .map_err(|err: Error| err.exit_with(ExitCode::SerialUnavailable))?;
+    for score in &scores {
+        println!("  {}", format_score(score));
+    }
+    match best_guess(&scores) {
+        Some(best) if best.baud == merged.baud => {
+            println!("autobaud: configured baud {} matches the peer", merged.baud);
+        }
+        Some(best) => println!(
+            "autobaud: peer looks like {} baud (configured {}); set `baud = {}` or run with --autobaud",
+            best.baud, merged.baud, best.baud
+        ),
+        None => println!("autobaud: no confident match; is the peer sending?"),
+    }
+    Ok(())
+}
+
+/// Startup hook for `autobaud = true`: switch `config.baud` to the detected rate when confident.
+pub(super) fn apply(config: &mut AppConfig, logger: &Logger) {
+    let scores = match probe(
+        &config.device,
+        config.serial_options(),
+        &CANDIDATE_BAUDS,
+        DEFAULT_SAMPLE_WINDOW_MS,
+    ) {
+        Ok(scores) => scores,
+        Err(err) => {
+            logger.warn(format!(
+                "autobaud probe failed: {err}; keeping {}",
+                config.baud
+            ));
+            return;
+        }
+    };
+    for score in &scores {
+        logger.debug(format!("autobaud {}", format_score(score)));
+    }
+    match best_guess(&scores) {
+        Some(best) if best.baud != config.baud && best.baud >= MIN_BAUD => {
+            logger.info(format!(
+                "autobaud: switching baud {} -> {} (score={:.2})",
+                config.baud, best.baud, best.score
+            ));
+            config.baud = best.baud;
+        }
+        Some(_) => logger.info(format!("autobaud: keeping baud {}", config.baud)),
+        None => logger.info(format!(
+            "autobaud: no confident match; keeping baud {}",
+            config.baud
+        )),
+    }
+}
+
+fn format_score(score: &BaudScore) -> String {
+    format!(
+        "baud={:<6} bytes={:<3} printable={:.2} breaks={} json={}",
+        score.baud, score.bytes, score.score, score.breaks, score.json_hint
+    )
+}
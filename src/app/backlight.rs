@@ -0,0 +1,486 @@
+//! Central backlight policy: idle timeout, night schedule and frame patterns.
+//!
+//! Frames still say whether they want the backlight, and blink toggles it, but every write goes
+//! through [`BacklightGate`], which holds the light off while [`BacklightSchedule`] says the panel
+//! is idle or it is night. A frame or a button press counts as activity; at night only a press
+//! wakes the panel, for [`NIGHT_WAKE_MS`] or the idle timeout when one is set.
+//!
+//! A frame's `backlight_pattern` is played by the render loop through [`pattern_level`]. With
+//! `[backlight].pwm_channel` the gate also drives a hardware PWM output, so `breathe` fades
+//! smoothly; without one, levels of 50% and up light the panel and the rest leave it dark.
+
+use crate::{
+    config::{parse_time_of_day, BacklightConfig},
+    display::backend::DisplayBackend,
+    payload::BacklightPattern,
+    Error, Result,
+};
+use std::time::{Duration, Instant};
+
+/// How long a press lights the panel at night when no idle timeout is configured.
+pub const NIGHT_WAKE_MS: u64 = 30_000;
+/// How often the render loop steps a playing pattern.
+pub const PATTERN_TICK_MS: u64 = 40;
+const BREATHE_PERIOD_MS: u64 = 4_000;
+const DOUBLE_PULSE_PERIOD_MS: u64 = 1_500;
+const DOUBLE_PULSE_FLASH_MS: u64 = 150;
+/// Morse dot length for `sos`.
+const SOS_UNIT_MS: u64 = 200;
+/// `(lit, units)` steps of one SOS cycle, word gap included.
+const SOS_STEPS: [(bool, u64); 18] = [
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 3),
+    (true, 3),
+    (false, 1),
+    (true, 3),
+    (false, 1),
+    (true, 3),
+    (false, 3),
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 7),
+];
+/// Hardware PWM frequency; well above visible flicker for LED backlights.
+#[cfg(target_os = "linux")]
+const PWM_FREQUENCY_HZ: f64 = 1_000.0;
+
+/// Why the backlight is being held off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimmed {
+    Idle,
+    Night,
+}
+
+#[derive(Debug, Clone)]
+pub struct BacklightSchedule {
+    idle_timeout: Option<Duration>,
+    /// Minutes since local midnight; the window may wrap past midnight.
+    night: Option<(u16, u16)>,
+    last_activity: Instant,
+    last_input: Option<Instant>,
+}
+
+impl BacklightSchedule {
+    pub fn new(config: &BacklightConfig, now: Instant) -> Self {
+        let mut schedule = Self {
+            idle_timeout: None,
+            night: None,
+            last_activity: now,
+            last_input: None,
+        };
+        schedule.reconfigure(config);
+        schedule
+    }
+
+    pub fn reconfigure(&mut self, config: &BacklightConfig) {
+        self.idle_timeout =
+            (config.idle_timeout_ms > 0).then(|| Duration::from_millis(config.idle_timeout_ms));
+        self.night = parse_time_of_day(&config.night_start)
+            .zip(parse_time_of_day(&config.night_end))
+            .filter(|(start, end)| start != end);
+    }
+
+    /// A frame was accepted.
+    pub fn on_frame(&mut self, now: Instant) {
+        self.last_activity = now;
+    }
+
+    /// Button, encoder or control-socket input; the only thing that wakes the panel at night.
+    pub fn on_input(&mut self, now: Instant) {
+        self.last_activity = now;
+        self.last_input = Some(now);
+    }
+
+    /// `Some` while the backlight must stay off. `minute_of_day` is local time.
+    pub fn dimmed(&self, now: Instant, minute_of_day: u16) -> Option<Dimmed> {
+        if self.is_night(minute_of_day) {
+            let wake = self
+                .idle_timeout
+                .unwrap_or(Duration::from_millis(NIGHT_WAKE_MS));
+            let awake = self
+                .last_input
+                .is_some_and(|input| now.duration_since(input) < wake);
+            if !awake {
+                return Some(Dimmed::Night);
+            }
+        }
+        self.idle_timeout
+            .filter(|timeout| now.duration_since(self.last_activity) >= *timeout)
+            .map(|_| Dimmed::Idle)
+    }
+
+    fn is_night(&self, minute: u16) -> bool {
+        match self.night {
+            Some((start, end)) if start < end => (start..end).contains(&minute),
+            Some((start, end)) => minute >= start || minute < end,
+            None => false,
+        }
+    }
+}
+
+/// Minutes since local midnight, for [`BacklightSchedule::dimmed`].
+pub fn local_minute_of_day() -> u16 {
+    use chrono::Timelike;
+    let now = chrono::Local::now();
+    (now.hour() * 60 + now.minute()) as u16
+}
+
+/// Brightness in percent of `pattern`, `elapsed` after it started.
+pub fn pattern_level(pattern: BacklightPattern, elapsed: Duration) -> u8 {
+    let ms = elapsed.as_millis() as u64;
+    let lit = |on: bool| if on { 100 } else { 0 };
+    match pattern {
+        BacklightPattern::Breathe => {
+            let phase = (ms % BREATHE_PERIOD_MS) as f64 / BREATHE_PERIOD_MS as f64;
+            ((1.0 + (std::f64::consts::TAU * phase).cos()) * 50.0).round() as u8
+        }
+        BacklightPattern::DoublePulse => {
+            let at = ms % DOUBLE_PULSE_PERIOD_MS;
+            lit(at < DOUBLE_PULSE_FLASH_MS
+                || (2 * DOUBLE_PULSE_FLASH_MS..3 * DOUBLE_PULSE_FLASH_MS).contains(&at))
+        }
+        BacklightPattern::Sos => {
+            let cycle: u64 = SOS_STEPS.iter().map(|(_, units)| units).sum();
+            let mut unit = (ms / SOS_UNIT_MS) % cycle;
+            for (on, units) in SOS_STEPS {
+                if unit < units {
+                    return lit(on);
+                }
+                unit -= units;
+            }
+            0
+        }
+    }
+}
+
+/// Hardware PWM output wired to the backlight LED, for smooth dimming.
+#[cfg(target_os = "linux")]
+pub struct BacklightPwm {
+    pwm: rppal::pwm::Pwm,
+}
+
+#[cfg(target_os = "linux")]
+impl BacklightPwm {
+    /// Claim PWM `channel` (0 is GPIO18, 1 is GPIO19 with the `pwm-2chan` overlay) at full
+    /// brightness.
+    pub fn open(channel: u8) -> Result<Self> {
+        use rppal::pwm::{Channel, Polarity, Pwm};
+        let channel = match channel {
+            0 => Channel::Pwm0,
+            1 => Channel::Pwm1,
+            other => {
+                return Err(Error::InvalidArgs(format!(
+                    "no hardware PWM channel {other}"
+                )))
+            }
+        };
+        let pwm = Pwm::with_frequency(channel, PWM_FREQUENCY_HZ, 1.0, Polarity::Normal, true)
+            .map_err(|e| Error::Io(std::io::Error::other(e)))?;
+        Ok(Self { pwm })
+    }
+
+    pub fn set_percent(&mut self, percent: u8) -> Result<()> {
+        self.pwm
+            .set_duty_cycle(f64::from(percent.min(100)) / 100.0)
+            .map_err(|e| Error::Io(std::io::Error::other(e)))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub struct BacklightPwm;
+
+#[cfg(not(target_os = "linux"))]
+impl BacklightPwm {
+    pub fn open(_channel: u8) -> Result<Self> {
+        Err(Error::InvalidArgs(
+            "hardware PWM backlight unsupported on this platform".into(),
+        ))
+    }
+
+    pub fn set_percent(&mut self, _percent: u8) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Display wrapper that remembers the backlight level callers ask for and only passes it on
+/// while the schedule allows.
+pub struct BacklightGate<'a, D: DisplayBackend> {
+    inner: &'a mut D,
+    /// Level asked for in percent; plain on/off is 100 or 0.
+    wanted: u8,
+    held_off: bool,
+    pwm: Option<BacklightPwm>,
+    /// Level last put on the hardware.
+    shown: u8,
+}
+
+impl<'a, D: DisplayBackend> BacklightGate<'a, D> {
+    pub fn new(inner: &'a mut D) -> Self {
+        Self {
+            inner,
+            wanted: 100,
+            held_off: false,
+            pwm: None,
+            shown: 100,
+        }
+    }
+
+    /// Drive a PWM output as well as the display's own backlight switch, or stop doing so.
+    pub fn set_pwm(&mut self, pwm: Option<BacklightPwm>) -> Result<()> {
+        self.pwm = pwm;
+        let level = self.effective();
+        if let Some(pwm) = self.pwm.as_mut() {
+            pwm.set_percent(level)?;
+        }
+        Ok(())
+    }
+
+    /// Hold the backlight off, or hand it back to the last requested level.
+    pub fn hold_off(&mut self, held_off: bool) -> Result<()> {
+        if held_off != self.held_off {
+            self.held_off = held_off;
+            let level = self.effective();
+            self.show(level, true)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_held_off(&self) -> bool {
+        self.held_off
+    }
+
+    /// The wrapped display, for calls only it understands.
+    pub fn inner_mut(&mut self) -> &mut D {
+        self.inner
+    }
+
+    fn effective(&self) -> u8 {
+        if self.held_off {
+            0
+        } else {
+            self.wanted
+        }
+    }
+
+    /// Whether the display's own switch should be on at `level`.
+    fn lit(&self, level: u8) -> bool {
+        if self.pwm.is_some() {
+            level > 0
+        } else {
+            level >= 50
+        }
+    }
+
+    /// Put `level` on the PWM output and the display switch; unless `force`, only what changed
+    /// is written.
+    fn show(&mut self, level: u8, force: bool) -> Result<()> {
+        if let Some(pwm) = self.pwm.as_mut() {
+            if force || level != self.shown {
+                pwm.set_percent(level)?;
+            }
+        }
+        let lit = self.lit(level);
+        if force || lit != self.lit(self.shown) {
+            self.inner.set_backlight(lit)?;
+        }
+        self.shown = level;
+        Ok(())
+    }
+}
+
+impl<D: DisplayBackend> DisplayBackend for BacklightGate<'_, D> {
+    fn cols(&self) -> u8 {
+        self.inner.cols()
+    }
+
+    fn rows(&self) -> u8 {
+        self.inner.rows()
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.inner.clear()
+    }
+
+    fn write_line(&mut self, row: u8, content: &str) -> Result<()> {
+        self.inner.write_line(row, content)
+    }
+
+    fn set_backlight(&mut self, on: bool) -> Result<()> {
+        self.wanted = if on { 100 } else { 0 };
+        self.show(self.effective(), true)
+    }
+
+    fn set_backlight_level(&mut self, percent: u8) -> Result<()> {
+        self.wanted = percent.min(100);
+        self.show(self.effective(), false)
+    }
+
+    fn set_blink(&mut self, on: bool) -> Result<()> {
+        self.inner.set_blink(on)
+    }
+
+    fn custom_char(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<()> {
+        self.inner.custom_char(slot, bitmap)
+    }
+
+    fn write_lines(&mut self, lines: &[&str]) -> Result<()> {
+        self.inner.write_lines(lines)
+    }
+
+    fn write_row_diff(&mut self, row: u8, content: &str) -> Result<usize> {
+        self.inner.write_row_diff(row, content)
+    }
+
+    fn prepare_redraw(&mut self) -> Result<()> {
+        self.inner.prepare_redraw()
+    }
+
+    fn take_write_time(&mut self) -> Option<Duration> {
+        self.inner.take_write_time()
+    }
+
+    fn take_write_bytes(&mut self) -> Option<u64> {
+        self.inner.take_write_bytes()
+    }
+
+    fn bus_bytes(&self) -> u64 {
+        self.inner.bus_bytes()
+    }
+
+    fn screen(&self) -> Vec<String> {
+        self.inner.screen()
+    }
+
+    fn reconfigure(&mut self, cols: u8, rows: u8) -> Result<()> {
+        self.inner.reconfigure(cols, rows)
+    }
+
+    fn recover(&mut self, now: Instant) -> bool {
+        self.inner.recover(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::backend::recording::{DisplayCall, RecordingDisplay};
+
+    fn config(idle_timeout_ms: u64, night: (&str, &str)) -> BacklightConfig {
+        BacklightConfig {
+            idle_timeout_ms,
+            night_start: night.0.into(),
+            night_end: night.1.into(),
+            pwm_channel: None,
+        }
+    }
+
+    #[test]
+    fn idle_timeout_dims_until_the_next_frame() {
+        let start = Instant::now();
+        let mut schedule = BacklightSchedule::new(&config(60_000, ("", "")), start);
+        let noon = 12 * 60;
+        assert_eq!(schedule.dimmed(start + Duration::from_secs(59), noon), None);
+        let idle = start + Duration::from_secs(60);
+        assert_eq!(schedule.dimmed(idle, noon), Some(Dimmed::Idle));
+        schedule.on_frame(idle);
+        assert_eq!(schedule.dimmed(idle, noon), None);
+    }
+
+    #[test]
+    fn night_window_wraps_midnight_and_only_input_wakes_it() {
+        let start = Instant::now();
+        let mut schedule = BacklightSchedule::new(&config(0, ("22:00", "06:30")), start);
+        assert_eq!(schedule.dimmed(start, 21 * 60 + 59), None);
+        assert_eq!(schedule.dimmed(start, 23 * 60), Some(Dimmed::Night));
+        assert_eq!(schedule.dimmed(start, 6 * 60 + 29), Some(Dimmed::Night));
+        assert_eq!(schedule.dimmed(start, 6 * 60 + 30), None);
+
+        schedule.on_frame(start);
+        assert_eq!(schedule.dimmed(start, 23 * 60), Some(Dimmed::Night));
+        schedule.on_input(start);
+        assert_eq!(schedule.dimmed(start, 23 * 60), None);
+        let later = start + Duration::from_millis(NIGHT_WAKE_MS);
+        assert_eq!(schedule.dimmed(later, 23 * 60), Some(Dimmed::Night));
+    }
+
+    #[test]
+    fn patterns_follow_their_timing() {
+        let at = |ms| Duration::from_millis(ms);
+        let breathe = BacklightPattern::Breathe;
+        assert_eq!(pattern_level(breathe, at(0)), 100);
+        assert_eq!(pattern_level(breathe, at(1_000)), 50);
+        assert_eq!(pattern_level(breathe, at(2_000)), 0);
+        assert_eq!(pattern_level(breathe, at(4_000)), 100);
+
+        let pulse = BacklightPattern::DoublePulse;
+        let lit: Vec<u8> = [0, 150, 300, 450, 1_500]
+            .into_iter()
+            .map(|ms| pattern_level(pulse, at(ms)))
+            .collect();
+        assert_eq!(lit, vec![100, 0, 100, 0, 100]);
+
+        // S is three 200 ms dots, then a three-unit gap before O's first dash.
+        let sos = BacklightPattern::Sos;
+        let lit: Vec<u8> = [0, 200, 400, 1_000, 1_600, 2_000, 2_200]
+            .into_iter()
+            .map(|ms| pattern_level(sos, at(ms)))
+            .collect();
+        assert_eq!(lit, vec![100, 0, 100, 0, 100, 100, 0]);
+        assert_eq!(pattern_level(sos, at(34 * SOS_UNIT_MS)), 100);
+    }
+
+    #[test]
+    fn levels_switch_at_half_without_pwm() {
+        let mut display = RecordingDisplay::new(16, 2);
+        {
+            let mut gate = BacklightGate::new(&mut display);
+            gate.set_backlight_level(80).unwrap();
+            gate.set_backlight_level(40).unwrap();
+            gate.set_backlight_level(10).unwrap();
+            gate.hold_off(true).unwrap();
+            gate.set_backlight_level(90).unwrap();
+            gate.hold_off(false).unwrap();
+        }
+        assert_eq!(
+            display.calls,
+            vec![
+                DisplayCall::Backlight(false),
+                DisplayCall::Backlight(false),
+                DisplayCall::Backlight(true),
+            ]
+        );
+    }
+
+    #[test]
+    fn gate_holds_the_light_off_and_restores_the_requested_state() {
+        let mut display = RecordingDisplay::new(16, 2);
+        {
+            let mut gate = BacklightGate::new(&mut display);
+            gate.hold_off(true).unwrap();
+            gate.set_backlight(true).unwrap();
+            gate.hold_off(true).unwrap();
+            gate.hold_off(false).unwrap();
+            gate.set_backlight(false).unwrap();
+            gate.hold_off(true).unwrap();
+            gate.hold_off(false).unwrap();
+        }
+        assert_eq!(
+            display.calls,
+            vec![
+                DisplayCall::Backlight(false),
+                DisplayCall::Backlight(false),
+                DisplayCall::Backlight(true),
+                DisplayCall::Backlight(false),
+                DisplayCall::Backlight(false),
+                DisplayCall::Backlight(false),
+            ]
+        );
+    }
+}
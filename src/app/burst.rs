@@ -0,0 +1,432 @@
+//! Temporary baud upshift for large transfers.
+//!
+//! The link normally runs at the configured `baud`. Once this side pushes `threshold_bytes`
+//! within a second it asks the peer for `[burst].baud`; after the peer acks, both ends switch
+//! and the initiator sends a probe that must come back at the new rate before the burst
+//! counts as up. The initiator drops back after `idle_ms` without another busy second, and
+//! either side aborts to the base baud when line errors reach `max_errors`. Failures start a
+//! cooldown so a link that cannot hold the faster rate is not retried every busy second; if
+//! the two ends lose each other anyway, the watchdog reconnect reopens at the base baud.
+
+use crate::{config::BurstConfig, negotiation::ControlFrame};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long a request or probe may go unanswered before the burst is abandoned.
+const BURST_REPLY_TIMEOUT: Duration = Duration::from_millis(1_000);
+/// Wait after a failed or aborted burst before asking (or agreeing) again.
+const BURST_COOLDOWN: Duration = Duration::from_secs(30);
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Action the render loop must carry out, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BurstStep {
+    Send(ControlFrame),
+    SetBaud { baud: u32, reason: &'static str },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Base,
+    /// Request sent at the base rate; waiting for `burst_ack`.
+    Requested {
+        nonce: u32,
+        since: Instant,
+    },
+    /// Switched as initiator; waiting for the probe echo.
+    Probing {
+        nonce: u32,
+        since: Instant,
+    },
+    /// Switched as responder; waiting for the initiator's probe.
+    AwaitProbe {
+        since: Instant,
+    },
+    Active {
+        initiator: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct BurstController {
+    base_baud: u32,
+    config: BurstConfig,
+    /// Config and peer both allow this side to ask for bursts.
+    initiate: bool,
+    phase: Phase,
+    nonce: u32,
+    window_start: Instant,
+    window_bytes: u64,
+    last_busy: Instant,
+    errors: u32,
+    cooldown_until: Option<Instant>,
+}
+
+impl BurstController {
+    pub fn new(base_baud: u32, config: &BurstConfig, now: Instant) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0)
+            ^ std::process::id();
+        Self {
+            base_baud,
+            config: *config,
+            initiate: false,
+            phase: Phase::Base,
+            nonce: seed.max(1),
+            window_start: now,
+            window_bytes: 0,
+            last_busy: now,
+            errors: 0,
+            cooldown_until: None,
+        }
+    }
+
+    /// Allow or forbid requests from this side; bursts the peer asks for are always followed.
+    pub fn set_initiate(&mut self, initiate: bool) {
+        self.initiate = initiate && self.config.enabled;
+    }
+
+    /// The port is currently running above the base baud.
+    pub fn switched(&self) -> bool {
+        matches!(
+            self.phase,
+            Phase::Probing { .. } | Phase::AwaitProbe { .. } | Phase::Active { .. }
+        )
+    }
+
+    /// Forget any burst after the port was reopened at the base baud.
+    pub fn reset(&mut self, now: Instant) {
+        self.phase = Phase::Base;
+        self.errors = 0;
+        self.window_start = now;
+        self.window_bytes = 0;
+    }
+
+    fn next_nonce(&mut self) -> u32 {
+        // xorshift32: cheap and never yields zero from a non-zero state.
+        let mut x = self.nonce;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.nonce = x;
+        x
+    }
+
+    fn cooling(&self, now: Instant) -> bool {
+        self.cooldown_until.is_some_and(|until| now < until)
+    }
+
+    fn fall_back(&mut self, now: Instant, reason: &'static str, cooldown: bool) -> Vec<BurstStep> {
+        let was_switched = self.switched();
+        self.phase = Phase::Base;
+        self.errors = 0;
+        if cooldown {
+            self.cooldown_until = Some(now + BURST_COOLDOWN);
+        }
+        if was_switched {
+            vec![BurstStep::SetBaud {
+                baud: self.base_baud,
+                reason,
+            }]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn decline(&mut self, baud: u32) -> Vec<BurstStep> {
+        self.phase = Phase::Base;
+        vec![BurstStep::Send(ControlFrame::BurstAck {
+            baud,
+            accepted: false,
+        })]
+    }
+
+    /// Feed the port's running TX byte count; may start a request.
+    pub fn observe_tx(&mut self, total: u64, now: Instant) -> Vec<BurstStep> {
+        if total < self.window_bytes {
+            // Counter restarted with a new port.
+            self.window_bytes = total;
+        }
+        let busy = total - self.window_bytes >= self.config.threshold_bytes;
+        if busy || now.saturating_duration_since(self.window_start) >= RATE_WINDOW {
+            self.window_start = now;
+            self.window_bytes = total;
+        }
+        if !busy {
+            return Vec::new();
+        }
+        self.last_busy = now;
+        if self.phase != Phase::Base || !self.initiate || self.cooling(now) {
+            return Vec::new();
+        }
+        let nonce = self.next_nonce();
+        self.phase = Phase::Requested { nonce, since: now };
+        vec![BurstStep::Send(ControlFrame::Burst {
+            baud: self.config.baud,
+            nonce,
+        })]
+    }
+
+    /// Timeouts and the idle drop-back.
+    pub fn poll(&mut self, now: Instant) -> Vec<BurstStep> {
+        match self.phase {
+            Phase::Requested { since, .. } if now - since >= BURST_REPLY_TIMEOUT => {
+                self.fall_back(now, "request unanswered", true)
+            }
+            Phase::Probing { since, .. } if now - since >= BURST_REPLY_TIMEOUT => {
+                self.fall_back(now, "probe not echoed", true)
+            }
+            Phase::AwaitProbe { since } if now - since >= BURST_REPLY_TIMEOUT * 2 => {
+                self.fall_back(now, "probe never arrived", true)
+            }
+            Phase::Active { initiator: true }
+                if now - self.last_busy >= Duration::from_millis(self.config.idle_ms) =>
+            {
+                let mut steps = vec![BurstStep::Send(ControlFrame::BurstEnd)];
+                steps.extend(self.fall_back(now, "transfer finished", false));
+                steps
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Handle a burst control frame from the peer.
+    pub fn on_frame(&mut self, frame: &ControlFrame, now: Instant) -> Vec<BurstStep> {
+        match *frame {
+            ControlFrame::Burst { baud, nonce } => {
+                match self.phase {
+                    Phase::Base => {}
+                    // Crossing requests: the lower nonce yields and follows the peer.
+                    Phase::Requested { nonce: ours, .. } if ours < nonce => {}
+                    Phase::Requested { nonce: ours, .. } if ours > nonce => return Vec::new(),
+                    Phase::Requested { .. } => return self.decline(baud),
+                    _ => return Vec::new(),
+                }
+                if baud <= self.base_baud || self.cooling(now) {
+                    return self.decline(baud);
+                }
+                self.phase = Phase::AwaitProbe { since: now };
+                vec![
+                    BurstStep::Send(ControlFrame::BurstAck {
+                        baud,
+                        accepted: true,
+                    }),
+                    BurstStep::SetBaud {
+                        baud,
+                        reason: "peer asked for a burst",
+                    },
+                ]
+            }
+            ControlFrame::BurstAck { baud, accepted } => {
+                let Phase::Requested { nonce, .. } = self.phase else {
+                    return Vec::new();
+                };
+                if !accepted || baud != self.config.baud {
+                    return self.fall_back(now, "peer declined", true);
+                }
+                self.phase = Phase::Probing { nonce, since: now };
+                vec![
+                    BurstStep::SetBaud {
+                        baud,
+                        reason: "peer accepted the burst",
+                    },
+                    BurstStep::Send(ControlFrame::BurstProbe { nonce }),
+                ]
+            }
+            ControlFrame::BurstProbe { nonce } => match self.phase {
+                Phase::AwaitProbe { .. } => {
+                    self.phase = Phase::Active { initiator: false };
+                    self.errors = 0;
+                    vec![BurstStep::Send(ControlFrame::BurstProbe { nonce })]
+                }
+                Phase::Probing { nonce: ours, .. } if ours == nonce => {
+                    self.phase = Phase::Active { initiator: true };
+                    self.errors = 0;
+                    self.last_busy = now;
+                    Vec::new()
+                }
+                _ => Vec::new(),
+            },
+            ControlFrame::BurstEnd => self.fall_back(now, "peer ended the burst", false),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Count a line error; enough of them while switched abort the burst.
+    pub fn on_error(&mut self, now: Instant) -> Vec<BurstStep> {
+        if !self.switched() {
+            return Vec::new();
+        }
+        self.errors += 1;
+        if self.errors < self.config.max_errors {
+            return Vec::new();
+        }
+        let mut steps = vec![BurstStep::Send(ControlFrame::BurstEnd)];
+        steps.extend(self.fall_back(now, "too many line errors", true));
+        steps
+    }
+
+    /// Leave a running burst on purpose (config reload, shutdown).
+    pub fn end(&mut self, now: Instant, reason: &'static str) -> Vec<BurstStep> {
+        if !self.switched() {
+            self.phase = Phase::Base;
+            return Vec::new();
+        }
+        let mut steps = vec![BurstStep::Send(ControlFrame::BurstEnd)];
+        steps.extend(self.fall_back(now, reason, false));
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller(now: Instant) -> BurstController {
+        let mut burst = BurstController::new(
+            9_600,
+            &BurstConfig {
+                enabled: true,
+                baud: 115_200,
+                threshold_bytes: 1_000,
+                idle_ms: 2_000,
+                max_errors: 2,
+            },
+            now,
+        );
+        burst.set_initiate(true);
+        burst
+    }
+
+    fn sent(steps: &[BurstStep]) -> Vec<ControlFrame> {
+        steps
+            .iter()
+            .filter_map(|step| match step {
+                BurstStep::Send(frame) => Some(frame.clone()),
+                BurstStep::SetBaud { .. } => None,
+            })
+            .collect()
+    }
+
+    fn bauds(steps: &[BurstStep]) -> Vec<u32> {
+        steps
+            .iter()
+            .filter_map(|step| match step {
+                BurstStep::SetBaud { baud, .. } => Some(*baud),
+                BurstStep::Send(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn full_burst_between_two_controllers() {
+        let now = Instant::now();
+        let mut host = controller(now);
+        let mut peer = controller(now);
+        peer.set_initiate(false);
+
+        assert!(host.observe_tx(400, now).is_empty());
+        let request = sent(&host.observe_tx(1_200, now + Duration::from_millis(500)));
+        assert!(matches!(
+            request[..],
+            [ControlFrame::Burst { baud: 115_200, .. }]
+        ));
+
+        let reply = peer.on_frame(&request[0], now);
+        assert_eq!(bauds(&reply), vec![115_200]);
+        assert!(peer.switched());
+
+        let switch = host.on_frame(&sent(&reply)[0], now);
+        assert_eq!(bauds(&switch), vec![115_200]);
+        let echo = peer.on_frame(&sent(&switch)[0], now);
+        assert!(host.on_frame(&sent(&echo)[0], now).is_empty());
+        assert_eq!(host.phase, Phase::Active { initiator: true });
+
+        // Quiet traffic eventually drops both ends back.
+        let later = now + Duration::from_millis(2_500);
+        let end = host.poll(later);
+        assert_eq!(sent(&end), vec![ControlFrame::BurstEnd]);
+        assert_eq!(bauds(&end), vec![9_600]);
+        assert_eq!(
+            bauds(&peer.on_frame(&ControlFrame::BurstEnd, later)),
+            vec![9_600]
+        );
+        assert!(!host.switched() && !peer.switched());
+    }
+
+    #[test]
+    fn missing_echo_falls_back_and_cools_down() {
+        let now = Instant::now();
+        let mut host = controller(now);
+        let request = sent(&host.observe_tx(1_000, now));
+        let ControlFrame::Burst { baud, .. } = request[0] else {
+            panic!("expected a burst request");
+        };
+        host.on_frame(
+            &ControlFrame::BurstAck {
+                baud,
+                accepted: true,
+            },
+            now,
+        );
+        assert!(host.switched());
+
+        let steps = host.poll(now + BURST_REPLY_TIMEOUT);
+        assert_eq!(bauds(&steps), vec![9_600]);
+        assert!(host
+            .observe_tx(5_000, now + Duration::from_secs(5))
+            .is_empty());
+        let after = now + BURST_COOLDOWN + Duration::from_secs(2);
+        assert_eq!(sent(&host.observe_tx(9_000, after)).len(), 1);
+    }
+
+    #[test]
+    fn error_spike_aborts_the_burst() {
+        let now = Instant::now();
+        let mut peer = controller(now);
+        peer.on_frame(
+            &ControlFrame::Burst {
+                baud: 115_200,
+                nonce: 7,
+            },
+            now,
+        );
+        peer.on_frame(&ControlFrame::BurstProbe { nonce: 7 }, now);
+        assert!(peer.on_error(now).is_empty());
+        let steps = peer.on_error(now);
+        assert_eq!(sent(&steps), vec![ControlFrame::BurstEnd]);
+        assert_eq!(bauds(&steps), vec![9_600]);
+        // Still cooling down, so the next request is declined.
+        let reply = peer.on_frame(
+            &ControlFrame::Burst {
+                baud: 115_200,
+                nonce: 9,
+            },
+            now,
+        );
+        assert_eq!(
+            sent(&reply),
+            vec![ControlFrame::BurstAck {
+                baud: 115_200,
+                accepted: false
+            }]
+        );
+    }
+
+    #[test]
+    fn crossing_requests_leave_one_initiator() {
+        let now = Instant::now();
+        let mut a = controller(now);
+        let mut b = controller(now);
+        a.nonce = 1;
+        b.nonce = 2;
+        let from_a = sent(&a.observe_tx(1_000, now)).remove(0);
+        let from_b = sent(&b.observe_tx(1_000, now)).remove(0);
+        let a_reply = a.on_frame(&from_b, now);
+        let b_reply = b.on_frame(&from_a, now);
+        // Exactly one side yields and acks; the other keeps waiting for that ack.
+        assert_eq!(a_reply.is_empty() as u8 + b_reply.is_empty() as u8, 1);
+        assert_eq!(a.switched() as u8 + b.switched() as u8, 1);
+    }
+}
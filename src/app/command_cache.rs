@@ -0,0 +1,198 @@
+//! Cached results of tunnel commands.
+//!
+//! A `cmd_request` with `cache_ttl_ms` keeps the command's output for that long, keyed by the
+//! command string. A repeat of the same command with a TTL is answered from the cache instead of
+//! running again, which keeps hosts that poll `df -h` every few seconds from loading a weak SBC.
+//! `no_cache` skips the lookup and refreshes the entry. Only runs that exit 0 with
+//! output under [`MAX_CACHED_OUTPUT_BYTES`] are kept.
+
+use crate::payload::CommandStream;
+use std::time::{Duration, Instant};
+
+/// Longest TTL a request may ask for; longer ones are clamped.
+pub const MAX_CACHE_TTL_MS: u64 = 10 * 60 * 1000;
+/// Output larger than this runs every time.
+pub const MAX_CACHED_OUTPUT_BYTES: usize = 16 * 1024;
+const MAX_ENTRIES: usize = 16;
+
+/// What a `cmd_request` asked of the cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheRequest {
+    /// Keep a clean run's output this long; `None` or 0 leaves it uncached.
+    pub ttl_ms: Option<u64>,
+    /// Run the command even when a cached result exists.
+    pub bypass: bool,
+}
+
+impl CacheRequest {
+    /// Only requests that opt in with a TTL are answered from the cache, so plain clients
+    /// always see a fresh run.
+    pub fn reads(&self) -> bool {
+        !self.bypass && self.ttl_ms.is_some_and(|ttl| ttl > 0)
+    }
+}
+
+/// Output of one run, in the order the streams produced it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CachedRun {
+    pub chunks: Vec<(CommandStream, Vec<u8>)>,
+    pub code: i32,
+}
+
+impl CachedRun {
+    fn len(&self) -> usize {
+        self.chunks.iter().map(|(_, data)| data.len()).sum()
+    }
+}
+
+struct Entry {
+    cmd: String,
+    run: CachedRun,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+pub struct CommandCache {
+    entries: Vec<Entry>,
+}
+
+impl CommandCache {
+    /// The cached run of `cmd`, unless it expired.
+    pub fn get(&mut self, cmd: &str, now: Instant) -> Option<&CachedRun> {
+        self.entries.retain(|entry| entry.expires_at > now);
+        self.entries
+            .iter()
+            .find(|entry| entry.cmd == cmd)
+            .map(|entry| &entry.run)
+    }
+
+    /// Keep `run` for `ttl_ms`, replacing any older result for `cmd`. When full, the entry
+    /// closest to expiry makes room.
+    pub fn insert(&mut self, cmd: String, run: CachedRun, ttl_ms: u64, now: Instant) {
+        if ttl_ms == 0 || run.code != 0 || run.len() > MAX_CACHED_OUTPUT_BYTES {
+            return;
+        }
+        self.entries
+            .retain(|entry| entry.expires_at > now && entry.cmd != cmd);
+        if self.entries.len() >= MAX_ENTRIES {
+            if let Some(idx) = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(idx, _)| idx)
+            {
+                self.entries.remove(idx);
+            }
+        }
+        self.entries.push(Entry {
+            cmd,
+            run,
+            expires_at: now + Duration::from_millis(ttl_ms.min(MAX_CACHE_TTL_MS)),
+        });
+    }
+
+    /// Forget every result, e.g. when the output format changes with the client's terminal.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Output of the running command, collected for [`CommandCache::insert`] when it exits.
+pub struct Recording {
+    pub request_id: u32,
+    cmd: String,
+    ttl_ms: u64,
+    run: CachedRun,
+    /// Set once the output outgrew the cache; the run finishes uncached.
+    overflowed: bool,
+}
+
+impl Recording {
+    pub fn new(request_id: u32, cmd: String, ttl_ms: u64) -> Self {
+        Self {
+            request_id,
+            cmd,
+            ttl_ms,
+            run: CachedRun::default(),
+            overflowed: false,
+        }
+    }
+
+    pub fn push(&mut self, stream: CommandStream, data: &[u8]) {
+        if self.overflowed {
+            return;
+        }
+        if self.run.len() + data.len() > MAX_CACHED_OUTPUT_BYTES {
+            self.overflowed = true;
+            self.run.chunks.clear();
+            return;
+        }
+        self.run.chunks.push((stream, data.to_vec()));
+    }
+
+    /// The finished run, unless it overflowed.
+    pub fn finish(mut self, code: i32) -> Option<(String, CachedRun, u64)> {
+        if self.overflowed {
+            return None;
+        }
+        self.run.code = code;
+        Some((self.cmd, self.run, self.ttl_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(out: &str, code: i32) -> CachedRun {
+        CachedRun {
+            chunks: vec![(CommandStream::Stdout, out.as_bytes().to_vec())],
+            code,
+        }
+    }
+
+    #[test]
+    fn entries_expire_and_only_clean_runs_are_kept() {
+        let start = Instant::now();
+        let mut cache = CommandCache::default();
+        cache.insert("df -h".into(), run("/ 42%", 0), 30_000, start);
+        cache.insert("false".into(), run("", 1), 30_000, start);
+        cache.insert("uptime".into(), run("up", 0), 0, start);
+        assert_eq!(cache.get("df -h", start), Some(&run("/ 42%", 0)));
+        assert_eq!(cache.get("false", start), None);
+        assert_eq!(cache.get("uptime", start), None);
+        assert_eq!(cache.get("df -h", start + Duration::from_secs(30)), None);
+    }
+
+    #[test]
+    fn ttls_are_clamped_and_a_full_cache_drops_the_soonest_expiry() {
+        let start = Instant::now();
+        let mut cache = CommandCache::default();
+        cache.insert("ps".into(), run("1 init", 0), u64::MAX, start);
+        let later = start + Duration::from_millis(MAX_CACHE_TTL_MS);
+        assert_eq!(cache.get("ps", later), None);
+
+        for idx in 0..MAX_ENTRIES as u64 {
+            cache.insert(format!("cmd {idx}"), run("x", 0), 1_000 + idx, start);
+        }
+        cache.insert("newest".into(), run("y", 0), 5_000, start);
+        assert_eq!(cache.get("cmd 0", start), None);
+        assert!(cache.get("cmd 1", start).is_some());
+        assert!(cache.get("newest", start).is_some());
+    }
+
+    #[test]
+    fn recordings_give_up_past_the_size_cap() {
+        let mut small = Recording::new(1, "echo hi".into(), 1_000);
+        small.push(CommandStream::Stdout, b"hi\n");
+        small.push(CommandStream::Stderr, b"warn\n");
+        let (cmd, run, ttl) = small.finish(0).unwrap();
+        assert_eq!((cmd.as_str(), run.chunks.len(), ttl), ("echo hi", 2, 1_000));
+
+        let mut large = Recording::new(2, "cat big".into(), 1_000);
+        large.push(CommandStream::Stdout, &vec![b'x'; MAX_CACHED_OUTPUT_BYTES]);
+        large.push(CommandStream::Stdout, b"y");
+        assert!(large.finish(0).is_none());
+    }
+}
@@ -0,0 +1,113 @@
+//! `lifelinetty completions <shell>`: shell completion scripts built from the CLI table.
+//!
+//! The parser in `cli` is hand-rolled, so its flag table is translated into a `clap::Command`
+//! only as input for `clap_complete`; nothing here parses arguments.
+
+use crate::{
+    cli::{FlagSpec, FlagValue, SubcommandSpec, GLOBAL_FLAGS, RUN_FLAGS, SUBCOMMANDS},
+    Result,
+};
+use clap::{builder::PossibleValuesParser, Arg, ArgAction, ValueHint};
+use clap_complete::{generate, Shell};
+use std::io::{self, Write};
+
+const BIN_NAME: &str = "lifelinetty";
+
+pub fn run(shell: Shell) -> Result<()> {
+    let mut out = io::stdout().lock();
+    write_completions(shell, &mut out);
+    out.flush()?;
+    Ok(())
+}
+
+pub fn write_completions(shell: Shell, out: &mut dyn Write) {
+    generate(shell, &mut command_tree(), BIN_NAME, out);
+}
+
+fn command_tree() -> clap::Command {
+    // `lifelinetty --device ...` runs without the `run` keyword, so the root takes its flags too.
+    let root = clap::Command::new(BIN_NAME)
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Serial-to-LCD daemon");
+    let root = GLOBAL_FLAGS
+        .iter()
+        .fold(root, |cmd, flag| cmd.arg(flag_arg(flag).global(true)));
+    let root = RUN_FLAGS
+        .iter()
+        .fold(root, |cmd, flag| cmd.arg(flag_arg(flag)));
+    SUBCOMMANDS
+        .iter()
+        .fold(root, |cmd, sub| cmd.subcommand(subcommand(sub)))
+}
+
+fn subcommand(spec: &SubcommandSpec) -> clap::Command {
+    let cmd = clap::Command::new(spec.name).about(spec.about);
+    let cmd = spec
+        .flags
+        .iter()
+        .fold(cmd, |cmd, flag| cmd.arg(flag_arg(flag)));
+    let cmd = spec
+        .args
+        .iter()
+        .enumerate()
+        .fold(cmd, |cmd, (idx, arg)| cmd.arg(positional(arg, idx + 1)));
+    spec.subcommands
+        .iter()
+        .fold(cmd, |cmd, sub| cmd.subcommand(subcommand(sub)))
+}
+
+fn flag_arg(spec: &FlagSpec) -> Arg {
+    let long = spec.name.trim_start_matches("--");
+    with_value(Arg::new(long).long(long).help(spec.help), spec.value)
+}
+
+fn positional(spec: &FlagSpec, index: usize) -> Arg {
+    let arg = Arg::new(spec.name)
+        .index(index)
+        .required(true)
+        .help(spec.help);
+    with_value(arg, spec.value)
+}
+
+fn with_value(arg: Arg, value: FlagValue) -> Arg {
+    match value {
+        FlagValue::Switch => arg.action(ArgAction::SetTrue),
+        FlagValue::Value(placeholder) => arg.value_name(placeholder),
+        FlagValue::Path => arg.value_name("path").value_hint(ValueHint::FilePath),
+        FlagValue::Choice(choices) => {
+            arg.value_parser(PossibleValuesParser::new(choices.iter().copied()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(shell: Shell) -> String {
+        let mut out = Vec::new();
+        write_completions(shell, &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn command_tree_is_consistent() {
+        command_tree().debug_assert();
+    }
+
+    #[test]
+    fn bash_and_zsh_cover_the_table() {
+        for shell in [Shell::Bash, Shell::Zsh] {
+            let script = script(shell);
+            for flag in RUN_FLAGS {
+                assert!(script.contains(flag.name), "{shell} missing {}", flag.name);
+            }
+            for sub in SUBCOMMANDS {
+                assert!(script.contains(sub.name), "{shell} missing {}", sub.name);
+            }
+            for value in ["hd44780-driver", "zstd", "even", "powershell"] {
+                assert!(script.contains(value), "{shell} missing value {value}");
+            }
+        }
+    }
+}
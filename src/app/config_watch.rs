@@ -0,0 +1,172 @@
+//! Reload `config.toml` when it changes on disk.
+//!
+//! On Linux a background thread watches the config directory with inotify. Watching the
+//! directory rather than the file catches editors that save by renaming a temp file over the
+//! original (`IN_MOVED_TO`) as well as in-place writes (`IN_CLOSE_WRITE`). Saves arrive as a
+//! burst of events, so [`ConfigWatcher::poll`] only reports a change once the file has been
+//! quiet for [`CONFIG_WATCH_SETTLE_MS`]. Other platforms get no watcher and keep relying on
+//! `config_reload` frames.
+
+use super::Logger;
+use std::{
+    path::PathBuf,
+    sync::mpsc::Receiver,
+    time::{Duration, Instant},
+};
+
+/// Quiet period after the last filesystem event before a reload is triggered.
+pub const CONFIG_WATCH_SETTLE_MS: u64 = 250;
+
+/// Receives change notifications from the watcher thread.
+pub struct ConfigWatcher {
+    events: Receiver<()>,
+    pending_since: Option<Instant>,
+}
+
+impl ConfigWatcher {
+    /// Start watching `path`. Returns `None` (after logging why) when the directory cannot be
+    /// watched or the platform has no inotify.
+    pub fn spawn(path: PathBuf, logger: &Logger) -> Option<Self> {
+        match imp::spawn(&path) {
+            Ok(events) => {
+                logger.info(format!("config: watching {} for changes", path.display()));
+                Some(Self {
+                    events,
+                    pending_since: None,
+                })
+            }
+            Err(err) => {
+                logger.warn(format!(
+                    "config: not watching {} ({err}); use a config_reload frame to reload",
+                    path.display()
+                ));
+                None
+            }
+        }
+    }
+
+    /// True once per settled burst of changes.
+    pub fn poll(&mut self, now: Instant) -> bool {
+        while self.events.try_recv().is_ok() {
+            self.pending_since = Some(now);
+        }
+        match self.pending_since {
+            Some(since)
+                if now.duration_since(since) >= Duration::from_millis(CONFIG_WATCH_SETTLE_MS) =>
+            {
+                self.pending_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use rustix::fs::inotify::{self, CreateFlags, ReadFlags, WatchFlags};
+    use std::{
+        ffi::OsStr,
+        io,
+        mem::MaybeUninit,
+        os::unix::ffi::OsStrExt,
+        path::Path,
+        sync::mpsc::{self, Receiver},
+        thread,
+    };
+
+    pub fn spawn(path: &Path) -> io::Result<Receiver<()>> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?
+            .to_os_string();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let fd = inotify::init(CreateFlags::CLOEXEC)?;
+        inotify::add_watch(
+            &fd,
+            dir,
+            WatchFlags::CLOSE_WRITE | WatchFlags::MOVED_TO | WatchFlags::CREATE,
+        )?;
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("lifelinetty-config-watch".into())
+            .spawn(move || {
+                let mut buf = [MaybeUninit::uninit(); 1024];
+                let mut reader = inotify::Reader::new(&fd, &mut buf);
+                loop {
+                    let event = match reader.next() {
+                        Ok(event) => event,
+                        Err(rustix::io::Errno::INTR) => continue,
+                        Err(_) => return,
+                    };
+                    let ours = event
+                        .file_name()
+                        .is_some_and(|file| OsStr::from_bytes(file.to_bytes()) == name);
+                    let written = event.events().intersects(
+                        ReadFlags::CLOSE_WRITE | ReadFlags::MOVED_TO | ReadFlags::CREATE,
+                    );
+                    // The render loop went away; nobody is left to reload.
+                    if ours && written && tx.send(()).is_err() {
+                        return;
+                    }
+                }
+            })?;
+        Ok(rx)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use std::{io, path::Path, sync::mpsc::Receiver};
+
+    pub fn spawn(_path: &Path) -> io::Result<Receiver<()>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "file watching needs inotify",
+        ))
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::app::logger::LogLevel;
+    use std::{fs, thread};
+
+    fn wait_for_change(watcher: &mut ConfigWatcher) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if watcher.poll(Instant::now()) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn saves_and_renames_trigger_one_settled_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "baud = 9600\n").unwrap();
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let mut watcher = ConfigWatcher::spawn(path.clone(), &logger).unwrap();
+
+        fs::write(dir.path().join("other.toml"), "ignored\n").unwrap();
+        thread::sleep(Duration::from_millis(CONFIG_WATCH_SETTLE_MS + 50));
+        assert!(!watcher.poll(Instant::now()));
+
+        fs::write(&path, "baud = 19200\n").unwrap();
+        fs::write(&path, "baud = 38400\n").unwrap();
+        assert!(wait_for_change(&mut watcher));
+        assert!(!watcher.poll(Instant::now()));
+
+        let tmp = dir.path().join(".config.toml.swp");
+        fs::write(&tmp, "baud = 115200\n").unwrap();
+        fs::rename(&tmp, &path).unwrap();
+        assert!(wait_for_change(&mut watcher));
+    }
+}
@@ -1,33 +1,62 @@
 use super::Logger;
 use crate::{
-    app::negotiation::{NegotiationLog, Negotiator},
+    app::handshake::{millis, HandshakeAudit, HandshakeOutcome, HandshakeReport},
+    app::negotiation::{agree_keepalive_ms, NegotiationLog, Negotiator, SessionCache},
     config::NegotiationConfig,
     negotiation::{Capabilities, ControlCaps, ControlFrame, Role},
-    serial::{classify_error, LineIo, SerialFailureKind, SerialOptions, SerialPort},
+    serial::{
+        cipher::LinkKeys, classify_error, tcp::tcp_addr, LineIo, SerialFailureKind, SerialOptions,
+        SerialPort,
+    },
 };
 use serde_json;
 use std::str::FromStr;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 struct NegotiationResult {
     role: Role,
     remote_caps: Option<Capabilities>,
     fallback: bool,
+    resumed: bool,
+    /// Heartbeat interval agreed for this session.
+    keepalive_ms: u64,
+    /// When the peer's `hello` arrived, counted from the start of the handshake.
+    peer_hello: Option<Duration>,
+    /// When the `hello_ack` or accepted `resume_ack` arrived.
+    ack: Option<Duration>,
+    /// Session keys when both sides proved they hold `negotiation.psk`.
+    link_keys: Option<LinkKeys>,
+}
+
+/// Outcome of the optional `resume` exchange that precedes a full hello.
+enum ResumeAttempt {
+    Resumed(NegotiationResult),
+    /// Run the full handshake; carries a frame read during the attempt that it must still see.
+    Renegotiate(Option<String>),
 }
 
 pub(crate) struct ConnectOutcome {
     pub port: SerialPort,
     pub remote_caps: Option<Capabilities>,
+    /// The previous session was restored with a single `resume` frame.
+    pub resumed: bool,
+    /// Heartbeat interval agreed with the peer, or our own proposal without one.
+    pub keepalive_ms: u64,
 }
 
-/// Attempt to open the serial port, send the INIT handshake, and log outcomes.
+/// Attempt to open the serial port, send the INIT handshake, and log outcomes. `keepalive_ms`
+/// is the heartbeat interval to propose in `hello`.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn attempt_serial_connect(
     logger: &Logger,
     device: &str,
     options: SerialOptions,
     negotiation: &NegotiationConfig,
     compression_enabled: bool,
+    keepalive_ms: u64,
     log: &mut NegotiationLog,
+    session: &mut SessionCache,
+    audit: &mut HandshakeAudit,
 ) -> Result<ConnectOutcome, SerialFailureKind> {
     attempt_serial_connect_with(
         logger,
@@ -35,25 +64,50 @@ pub(crate) fn attempt_serial_connect(
         options,
         negotiation,
         compression_enabled,
+        keepalive_ms,
         log,
+        session,
+        audit,
         SerialPort::connect,
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 fn attempt_serial_connect_with<F>(
     logger: &Logger,
     device: &str,
     options: SerialOptions,
     negotiation: &NegotiationConfig,
     compression_enabled: bool,
+    keepalive_ms: u64,
     log: &mut NegotiationLog,
+    session: &mut SessionCache,
+    audit: &mut HandshakeAudit,
     connect: F,
 ) -> Result<ConnectOutcome, SerialFailureKind>
 where
     F: FnOnce(&str, SerialOptions) -> crate::Result<SerialPort>,
 {
+    let started = Instant::now();
+    let at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let baud = options.baud;
     match connect(device, options) {
+        Ok(serial_connection) if serial_connection.is_fifo() => {
+            // A FIFO only carries payloads in: no INIT, no handshake, legacy LCD-only mode.
+            logger.info(format!("fifo input: reading payload lines from {device}"));
+            log.record("negotiation: fifo input, no handshake");
+            Ok(ConnectOutcome {
+                port: serial_connection,
+                remote_caps: None,
+                resumed: false,
+                keepalive_ms,
+            })
+        }
         Ok(mut serial_connection) => {
+            let opened = started.elapsed();
             if let Err(err) = serial_connection.send_command_line("INIT") {
                 let reason = classify_error(&err);
                 let hint = connect_failure_hint(reason, device);
@@ -63,18 +117,35 @@ where
                 ));
                 return Err(reason);
             }
-            logger.info("serial connected");
+            match serial_connection.peer() {
+                Some(peer) => {
+                    logger.info(format!("tcp input: client {peer} connected on {device}"))
+                }
+                None => logger.info("serial connected"),
+            }
+            if let Some(warning) = serial_connection.tuning_warning() {
+                logger.warn(warning);
+            }
             log.record("negotiation: serial connected");
             let negotiation_result = negotiate_handshake(
                 &mut serial_connection,
                 logger,
                 negotiation,
                 compression_enabled,
+                keepalive_ms,
                 log,
+                session,
             );
             if negotiation_result.fallback {
+                session.forget();
                 logger.info("negotiation: falling back to legacy LCD-only mode");
                 log.record("negotiation: falling back to legacy mode");
+            } else if negotiation_result.resumed {
+                logger.info(format!(
+                    "negotiation: previous session resumed as {} keepalive={}ms",
+                    negotiation_result.role.as_str(),
+                    negotiation_result.keepalive_ms
+                ));
             } else {
                 let caps_bits = negotiation_result
                     .remote_caps
@@ -82,19 +153,82 @@ where
                     .map(|caps| caps.bits())
                     .unwrap_or(0);
                 logger.info(format!(
-                    "negotiation: role decided as {} remote_caps=0x{caps_bits:08x}",
-                    negotiation_result.role.as_str()
+                    "negotiation: role decided as {} remote_caps=0x{caps_bits:08x} keepalive={}ms",
+                    negotiation_result.role.as_str(),
+                    negotiation_result.keepalive_ms
                 ));
                 log.record(format!(
-                    "negotiation: role={} remote_caps=0x{caps_bits:08x}",
-                    negotiation_result.role.as_str()
+                    "negotiation: role={} remote_caps=0x{caps_bits:08x} keepalive={}ms",
+                    negotiation_result.role.as_str(),
+                    negotiation_result.keepalive_ms
                 ));
             }
+            audit.record(
+                HandshakeReport {
+                    at,
+                    device: device.to_string(),
+                    baud,
+                    open_ms: millis(opened),
+                    hello_ms: negotiation_result.peer_hello.map(millis),
+                    ack_ms: negotiation_result.ack.map(millis),
+                    total_ms: millis(started.elapsed()),
+                    outcome: if negotiation_result.fallback {
+                        HandshakeOutcome::Legacy
+                    } else if negotiation_result.resumed {
+                        HandshakeOutcome::Resumed
+                    } else {
+                        HandshakeOutcome::Negotiated
+                    },
+                    role: (!negotiation_result.fallback).then(|| negotiation_result.role.as_str()),
+                    caps: negotiation_result
+                        .remote_caps
+                        .as_ref()
+                        .map(|caps| caps.bits()),
+                    keepalive_ms: negotiation_result.keepalive_ms,
+                },
+                logger,
+            );
+            match negotiation_result.link_keys.clone() {
+                Some(keys) => {
+                    serial_connection.attach_cipher(keys);
+                    logger.info("negotiation: link encryption on");
+                    log.record("negotiation: link encryption on");
+                }
+                None if !negotiation.psk.is_empty() => {
+                    // Never fall back to plaintext when a key is configured.
+                    logger.warn(
+                        "negotiation: negotiation.psk is set but the peer did not agree on link encryption; dropping the link",
+                    );
+                    log.record("negotiation: refused unencrypted link");
+                    return Err(SerialFailureKind::Config);
+                }
+                None => {}
+            }
+            let peer_arq = negotiation_result
+                .remote_caps
+                .as_ref()
+                .is_some_and(|caps| caps.supports_arq);
+            if peer_arq {
+                serial_connection.attach_arq();
+                if serial_connection.arq_active() {
+                    logger.info("arq: stop-and-wait framing on");
+                    log.record("negotiation: arq enabled");
+                }
+            }
             Ok(ConnectOutcome {
                 port: serial_connection,
                 remote_caps: negotiation_result.remote_caps,
+                resumed: negotiation_result.resumed,
+                keepalive_ms: negotiation_result.keepalive_ms,
             })
         }
+        Err(err)
+            if tcp_addr(device).is_some() && classify_error(&err) == SerialFailureKind::Timeout =>
+        {
+            // An idle listener is the normal state, not a fault worth a warning each retry.
+            logger.debug(format!("tcp input: {err}"));
+            Err(SerialFailureKind::Timeout)
+        }
         Err(err) => {
             let reason = classify_error(&err);
             let hint = connect_failure_hint(reason, device);
@@ -121,25 +255,55 @@ fn negotiate_handshake<IO>(
     logger: &Logger,
     config: &NegotiationConfig,
     compression_enabled: bool,
+    keepalive_ms: u64,
     log: &mut NegotiationLog,
+    session: &mut SessionCache,
 ) -> NegotiationResult
 where
     IO: LineIo,
 {
-    let negotiator = Negotiator::new(config, compression_enabled);
+    let started = Instant::now();
+    let mut pending = None;
+    if let Some(token) = session.resume_token(Instant::now()).map(str::to_string) {
+        match try_resume(io, logger, config, &token, log, session, started) {
+            ResumeAttempt::Resumed(result) => return result,
+            ResumeAttempt::Renegotiate(line) => pending = line,
+        }
+    }
+
+    let negotiator = match Negotiator::new(config, compression_enabled, Some(keepalive_ms)) {
+        Ok(negotiator) => negotiator,
+        Err(err) => {
+            // Without randomness there is no safe nonce or token; `psk` links are then refused.
+            logger.warn(format!("negotiation: cannot start a handshake: {err}"));
+            log.record("negotiation: no OS randomness for the handshake");
+            return fallback_result(keepalive_ms);
+        }
+    };
     let hello_frame = negotiator.hello_frame();
     log.record("negotiation: sending hello");
     if !send_control_frame(io, &hello_frame, "hello", logger, log) {
         logger.warn("negotiation: failed to send hello frame");
         log.record("negotiation: failed to send hello frame");
-        return fallback_result();
+        return fallback_result(keepalive_ms);
     }
 
     let deadline = Instant::now() + Duration::from_millis(config.timeout_ms);
     let mut buffer = String::new();
+    let mut peer_token: Option<String> = None;
+    let mut peer_keepalive: Option<u64> = None;
+    let mut peer_hello: Option<Duration> = None;
+    let mut peer_link_nonce: Option<String> = None;
 
     while Instant::now() < deadline {
-        match io.read_message_line(&mut buffer) {
+        let read = match pending.take() {
+            Some(line) => {
+                buffer = line;
+                Ok(buffer.len())
+            }
+            None => io.read_message_line(&mut buffer),
+        };
+        match read {
             Ok(0) => continue,
             Ok(_) => {
                 let trimmed = buffer.trim();
@@ -151,11 +315,25 @@ where
                         node_id,
                         caps,
                         pref,
+                        session: token,
+                        keepalive_ms: proposed,
+                        install_id: peer_install_id,
+                        link_nonce,
                         ..
                     }) => {
+                        peer_hello.get_or_insert_with(|| started.elapsed());
+                        peer_token = token;
+                        peer_keepalive = proposed;
+                        peer_link_nonce = link_nonce;
                         let (remote, pref_err) = crate::app::negotiation::RemoteHello::from_parts(
                             node_id, &pref, caps.bits,
                         );
+                        if let Some(warning) =
+                            negotiator.identity_clash(remote.node_id, peer_install_id.as_deref())
+                        {
+                            logger.warn(format!("negotiation: {warning}"));
+                            log.record(format!("negotiation: {warning}"));
+                        }
                         if let Some(reason) = pref_err {
                             logger.warn(format!(
                                 "negotiation: invalid preference '{pref}': {reason}"
@@ -176,11 +354,14 @@ where
                             peer_caps: ControlCaps {
                                 bits: negotiator.local_caps().bits(),
                             },
+                            link_proof: peer_link_nonce
+                                .as_deref()
+                                .and_then(|nonce| negotiator.link_proof(nonce)),
                         };
                         if !send_control_frame(io, &ack, "hello_ack", logger, log) {
                             logger.warn("negotiation: failed to send hello_ack");
                             log.record("negotiation: failed to send hello_ack");
-                            return fallback_result();
+                            return fallback_result(keepalive_ms);
                         }
                         log.record(format!(
                             "negotiation: sent hello_ack remote_role={} local_role={}",
@@ -192,6 +373,7 @@ where
                     Ok(ControlFrame::HelloAck {
                         chosen_role,
                         peer_caps,
+                        link_proof,
                     }) => {
                         let role = Role::from_str(&chosen_role).unwrap_or(Role::Server);
                         log.record(format!(
@@ -199,21 +381,74 @@ where
                             role.as_str(),
                             peer_caps.bits
                         ));
+                        let remote_caps = Capabilities::from_bits(peer_caps.bits);
+                        let agreed = agree_keepalive_ms(keepalive_ms, peer_keepalive);
+                        log.record(format!(
+                            "negotiation: keepalive {agreed}ms (local {keepalive_ms}ms, peer {})",
+                            peer_keepalive.map_or("none".to_string(), |ms| format!("{ms}ms"))
+                        ));
+                        match (negotiator.session_token(), peer_token.as_deref()) {
+                            (Some(local), Some(peer)) => session.store(
+                                local,
+                                peer,
+                                role.clone(),
+                                remote_caps.clone(),
+                                agreed,
+                            ),
+                            _ => session.forget(),
+                        }
+                        let link_keys = match negotiator
+                            .link_keys(peer_link_nonce.as_deref(), link_proof.as_deref())
+                        {
+                            Ok(keys) => keys,
+                            Err(reason) => {
+                                logger
+                                    .warn(format!("negotiation: link encryption failed: {reason}"));
+                                log.record(format!(
+                                    "negotiation: link encryption failed: {reason}"
+                                ));
+                                None
+                            }
+                        };
                         return NegotiationResult {
                             role,
-                            remote_caps: Some(Capabilities::from_bits(peer_caps.bits)),
+                            remote_caps: Some(remote_caps),
                             fallback: false,
+                            resumed: false,
+                            keepalive_ms: agreed,
+                            peer_hello,
+                            ack: Some(started.elapsed()),
+                            link_keys,
                         };
                     }
+                    Ok(ControlFrame::Resume { .. }) => {
+                        // Already renegotiating; the peer will follow with a hello.
+                        let reject = ControlFrame::ResumeAck { accepted: false };
+                        send_control_frame(io, &reject, "resume_ack", logger, log);
+                        continue;
+                    }
+                    Ok(
+                        ControlFrame::ResumeAck { .. }
+                        | ControlFrame::Burst { .. }
+                        | ControlFrame::BurstAck { .. }
+                        | ControlFrame::BurstProbe { .. }
+                        | ControlFrame::BurstEnd
+                        | ControlFrame::Goodbye { .. }
+                        | ControlFrame::RenderDefaults(_)
+                        | ControlFrame::AnomalySummary(_),
+                    ) => continue,
                     Ok(ControlFrame::LegacyFallback) => {
                         log.record("negotiation: legacy_fallback received");
-                        return fallback_result();
+                        return NegotiationResult {
+                            peer_hello,
+                            ..fallback_result(keepalive_ms)
+                        };
                     }
                     Err(_) => {
                         log.record(format!(
                             "negotiation: ignoring non-control frame during handshake: {trimmed}"
                         ));
-                        return fallback_result();
+                        return fallback_result(keepalive_ms);
                     }
                 }
             }
@@ -233,14 +468,102 @@ where
         logger,
         log,
     );
-    fallback_result()
+    NegotiationResult {
+        peer_hello,
+        ..fallback_result(keepalive_ms)
+    }
+}
+
+/// Offer the peer the token it issued last session. Both sides may send `resume` at once, so
+/// a peer `resume` is answered here too; anything else hands over to the full handshake.
+fn try_resume<IO>(
+    io: &mut IO,
+    logger: &Logger,
+    config: &NegotiationConfig,
+    token: &str,
+    log: &mut NegotiationLog,
+    session: &mut SessionCache,
+    started: Instant,
+) -> ResumeAttempt
+where
+    IO: LineIo,
+{
+    let resume = ControlFrame::Resume {
+        token: token.to_string(),
+    };
+    if !send_control_frame(io, &resume, "resume", logger, log) {
+        return ResumeAttempt::Renegotiate(None);
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(config.timeout_ms);
+    let mut buffer = String::new();
+    while Instant::now() < deadline {
+        match io.read_message_line(&mut buffer) {
+            Ok(0) => continue,
+            Ok(_) => {
+                let trimmed = buffer.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<ControlFrame>(trimmed) {
+                    Ok(ControlFrame::ResumeAck { accepted: true }) => {
+                        let Some((role, remote_caps, resumes, keepalive_ms)) = session.restore()
+                        else {
+                            return ResumeAttempt::Renegotiate(None);
+                        };
+                        log.record(format!(
+                            "negotiation: session resumed role={} resumes={resumes} keepalive={keepalive_ms}ms",
+                            role.as_str()
+                        ));
+                        return ResumeAttempt::Resumed(NegotiationResult {
+                            role,
+                            remote_caps: Some(remote_caps),
+                            fallback: false,
+                            resumed: true,
+                            keepalive_ms,
+                            peer_hello: None,
+                            ack: Some(started.elapsed()),
+                            link_keys: None,
+                        });
+                    }
+                    Ok(ControlFrame::ResumeAck { accepted: false }) => {
+                        log.record("negotiation: resume rejected by peer");
+                        return ResumeAttempt::Renegotiate(None);
+                    }
+                    Ok(ControlFrame::Resume { token: theirs }) => {
+                        let accepted = session.accepts(&theirs, Instant::now());
+                        let ack = ControlFrame::ResumeAck { accepted };
+                        send_control_frame(io, &ack, "resume_ack", logger, log);
+                        if !accepted {
+                            log.record("negotiation: rejected peer resume for unknown session");
+                            return ResumeAttempt::Renegotiate(None);
+                        }
+                        continue;
+                    }
+                    _ => return ResumeAttempt::Renegotiate(Some(trimmed.to_string())),
+                }
+            }
+            Err(err) => {
+                logger.warn(format!("negotiation: read failed during resume: {err}"));
+                log.record(format!("negotiation: read failed during resume: {err}"));
+                return ResumeAttempt::Renegotiate(None);
+            }
+        }
+    }
+    log.record("negotiation: resume timed out");
+    ResumeAttempt::Renegotiate(None)
 }
 
-fn fallback_result() -> NegotiationResult {
+fn fallback_result(keepalive_ms: u64) -> NegotiationResult {
     NegotiationResult {
         role: Role::Server,
         remote_caps: None,
         fallback: true,
+        resumed: false,
+        keepalive_ms,
+        peer_hello: None,
+        ack: None,
+        link_keys: None,
     }
 }
 
@@ -345,12 +668,42 @@ mod tests {
             SerialOptions::default(),
             &NegotiationConfig::default(),
             false,
+            4_000,
             &mut log,
+            &mut SessionCache::new(0),
+            &mut HandshakeAudit::disabled(),
             |_device, _options| Err(Error::Io(io::Error::new(ErrorKind::PermissionDenied, "no"))),
         );
         assert!(matches!(result, Err(SerialFailureKind::PermissionDenied)));
     }
 
+    #[test]
+    fn fifo_input_connects_without_a_handshake() {
+        let dir = tempfile::tempdir().unwrap();
+        let device = format!("fifo:{}", dir.path().join("frames.fifo").display());
+        let logger = new_logger();
+        let mut log = NegotiationLog::disabled();
+        let started = Instant::now();
+        let outcome = attempt_serial_connect(
+            &logger,
+            &device,
+            SerialOptions::default(),
+            &NegotiationConfig::default(),
+            false,
+            4_000,
+            &mut log,
+            &mut SessionCache::new(0),
+            &mut HandshakeAudit::disabled(),
+        )
+        .unwrap_or_else(|_| panic!("fifo connect failed"));
+        assert!(outcome.port.is_fifo());
+        assert!(outcome.remote_caps.is_none());
+        assert!(!outcome.resumed);
+        assert_eq!(outcome.keepalive_ms, 4_000);
+        // No hello round trip to wait out.
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
     #[test]
     fn negotiation_success_sets_role() {
         let ack = r#"{"type":"hello_ack","chosen_role":"client","peer_caps":{"bits":3}}"#;
@@ -362,7 +715,9 @@ mod tests {
             &logger,
             &NegotiationConfig::default(),
             false,
+            4_000,
             &mut log,
+            &mut SessionCache::new(0),
         );
         assert!(!result.fallback);
         assert_eq!(result.role, Role::Client);
@@ -388,13 +743,18 @@ mod tests {
             &logger,
             &NegotiationConfig::default(),
             false,
+            4_000,
             &mut log,
+            &mut SessionCache::new(0),
         );
         assert!(!result.fallback);
         assert!(io
             .sent()
             .iter()
             .any(|line| line.contains("\"type\":\"hello_ack\"")));
+        // Both steps are timed for the handshake audit, the peer's hello first.
+        let (hello, ack) = (result.peer_hello.unwrap(), result.ack.unwrap());
+        assert!(hello <= ack);
     }
 
     #[test]
@@ -408,8 +768,149 @@ mod tests {
             &logger,
             &NegotiationConfig::default(),
             false,
+            4_000,
             &mut log,
+            &mut SessionCache::new(0),
         );
         assert!(result.fallback);
     }
+
+    fn cached_session() -> SessionCache {
+        let mut session = SessionCache::new(10_000);
+        let caps = Capabilities::from_bits(3);
+        session.store("local-token", "peer-token", Role::Client, caps, 3_000);
+        session.mark_lost(Instant::now());
+        session
+    }
+
+    #[test]
+    fn hello_with_session_tokens_is_cached() {
+        let hello = r#"{"type":"hello","proto_version":1,"node_id":99,"caps":{"bits":2},"pref":"prefer_server","session":"peer-token"}"#;
+        let ack = r#"{"type":"hello_ack","chosen_role":"client","peer_caps":{"bits":2}}"#;
+        let mut io = FakeLineIo::with_responses(vec![hello, ack]);
+        let logger = new_logger();
+        let mut log = NegotiationLog::disabled();
+        let mut session = SessionCache::new(10_000);
+        let result = negotiate_handshake(
+            &mut io,
+            &logger,
+            &NegotiationConfig::default(),
+            false,
+            4_000,
+            &mut log,
+            &mut session,
+        );
+        assert!(!result.resumed);
+        assert_eq!(session.resume_token(Instant::now()), Some("peer-token"));
+        assert!(io.sent()[0].contains("\"session\":"));
+    }
+
+    #[test]
+    fn accepted_resume_skips_hello() {
+        let ack = r#"{"type":"resume_ack","accepted":true}"#;
+        let mut io = FakeLineIo::with_responses(vec![ack]);
+        let logger = new_logger();
+        let mut log = NegotiationLog::disabled();
+        let mut session = cached_session();
+        let result = negotiate_handshake(
+            &mut io,
+            &logger,
+            &NegotiationConfig::default(),
+            false,
+            4_000,
+            &mut log,
+            &mut session,
+        );
+        assert!(result.resumed);
+        assert_eq!(result.role, Role::Client);
+        assert_eq!(result.remote_caps.map(|caps| caps.bits()), Some(3));
+        assert_eq!(result.keepalive_ms, 3_000);
+        assert_eq!(io.sent().len(), 1);
+        assert!(io.sent()[0].contains("\"token\":\"peer-token\""));
+    }
+
+    #[test]
+    fn crossed_resumes_are_answered_and_accepted() {
+        let resume = r#"{"type":"resume","token":"local-token"}"#;
+        let ack = r#"{"type":"resume_ack","accepted":true}"#;
+        let mut io = FakeLineIo::with_responses(vec![resume, ack]);
+        let logger = new_logger();
+        let mut log = NegotiationLog::disabled();
+        let mut session = cached_session();
+        let result = negotiate_handshake(
+            &mut io,
+            &logger,
+            &NegotiationConfig::default(),
+            false,
+            4_000,
+            &mut log,
+            &mut session,
+        );
+        assert!(result.resumed);
+        assert!(io
+            .sent()
+            .iter()
+            .any(|line| line.contains("\"accepted\":true")));
+    }
+
+    #[test]
+    fn peer_hello_during_resume_falls_through_to_handshake() {
+        let hello = r#"{"type":"hello","proto_version":1,"node_id":99,"caps":{"bits":2},"pref":"prefer_server"}"#;
+        let ack = r#"{"type":"hello_ack","chosen_role":"server","peer_caps":{"bits":2}}"#;
+        let mut io = FakeLineIo::with_responses(vec![hello, ack]);
+        let logger = new_logger();
+        let mut log = NegotiationLog::disabled();
+        let mut session = cached_session();
+        let result = negotiate_handshake(
+            &mut io,
+            &logger,
+            &NegotiationConfig::default(),
+            false,
+            4_000,
+            &mut log,
+            &mut session,
+        );
+        assert!(!result.resumed);
+        assert!(!result.fallback);
+        assert_eq!(result.role, Role::Server);
+        // The peer sent no session token, so nothing is left to resume.
+        assert!(session.resume_token(Instant::now()).is_none());
+        let sent = io.sent();
+        assert!(sent[0].contains("\"type\":\"resume\""));
+        assert!(sent[1].contains("\"type\":\"hello\""));
+        assert!(sent[2].contains("\"type\":\"hello_ack\""));
+    }
+
+    #[test]
+    fn keepalive_is_proposed_and_the_smaller_one_wins() {
+        let hello = r#"{"type":"hello","proto_version":1,"node_id":99,"caps":{"bits":2},"pref":"prefer_server","keepalive_ms":2500}"#;
+        let ack = r#"{"type":"hello_ack","chosen_role":"client","peer_caps":{"bits":2}}"#;
+        let mut io = FakeLineIo::with_responses(vec![hello, ack]);
+        let logger = new_logger();
+        let mut log = NegotiationLog::disabled();
+        let result = negotiate_handshake(
+            &mut io,
+            &logger,
+            &NegotiationConfig::default(),
+            false,
+            4_000,
+            &mut log,
+            &mut SessionCache::new(0),
+        );
+        assert!(io.sent()[0].contains("\"keepalive_ms\":4000"));
+        assert_eq!(result.keepalive_ms, 2_500);
+
+        // A peer without the field leaves our proposal in place.
+        let mut io = FakeLineIo::with_responses(vec![ack]);
+        let result = negotiate_handshake(
+            &mut io,
+            &logger,
+            &NegotiationConfig::default(),
+            false,
+            4_000,
+            &mut log,
+            &mut SessionCache::new(0),
+        );
+        assert_eq!(result.keepalive_ms, 4_000);
+    }
 }
@@ -0,0 +1,346 @@
+//! Local control socket: lets scripts on the same host drive the LCD without the serial link.
+//!
+//! Clients connect to `[control].socket` and send one JSON command per line. Each command gets
+//! exactly one JSON reply line, `{"ok":true,...}` or `{"ok":false,"error":"..."}`:
+//!
+//! ```text
+//! {"cmd":"frame","payload":{"schema_version":1,"line1":"Hello","line2":"World"}}
+//! {"cmd":"clear"}
+//! {"cmd":"backlight","on":false}
+//! {"cmd":"stats"}
+//! {"cmd":"snapshot"}
+//! {"cmd":"handshakes"}
+//! {"cmd":"hud","on":true}
+//! ```
+//!
+//! Connections are served on their own threads, but every command is handed to the render loop
+//! over a channel, so the LCD and the page queue are only ever touched from there.
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::idle::LoopWaker;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+const ACCEPT_POLL_MS: u64 = 100;
+/// How long a connection waits for the render loop before answering for it.
+const REPLY_TIMEOUT_MS: u64 = 2_000;
+/// Longest command line accepted; comfortably above the largest payload frame.
+const MAX_COMMAND_BYTES: u64 = 16 * 1024;
+/// Owner and group may connect; the group is how non-root scripts get access.
+const SOCKET_MODE: u32 = 0o660;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Queue a page exactly as if it had arrived over serial. `payload` is a payload object,
+    /// or a string holding JSON or `key=value` text.
+    Frame { payload: Value },
+    /// Drop every queued page and blank the display.
+    Clear,
+    /// Switch the backlight until the next page sets its own.
+    Backlight { on: bool },
+    /// Render loop counters.
+    Stats,
+    /// Everything `lifelinetty monitor` shows: the counters, the rows on the glass, the page
+    /// queue, the link state and the recent log lines.
+    Snapshot,
+    /// Timing reports for the most recent serial handshakes.
+    Handshakes,
+    /// Draw live frame rate and latency figures over row 2, or stop drawing them.
+    Hud { on: bool },
+}
+
+impl ControlCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        serde_json::from_str(line).map_err(|err| format!("invalid command: {err}"))
+    }
+}
+
+/// Payload text for [`ControlCommand::Frame`], as `RenderState::ingest` expects it.
+pub fn payload_text(payload: &Value) -> String {
+    match payload {
+        Value::String(raw) => raw.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A command waiting for the render loop, with the way back to its connection.
+#[derive(Debug)]
+pub struct ControlRequest {
+    command: ControlCommand,
+    reply: Sender<Value>,
+}
+
+impl ControlRequest {
+    pub fn command(&self) -> &ControlCommand {
+        &self.command
+    }
+
+    /// Answer the client; a connection that already gave up is ignored.
+    pub fn respond(self, reply: Value) {
+        let _ = self.reply.send(reply);
+    }
+}
+
+/// Success reply carrying the fields of `extra` (an object) alongside `"ok":true`.
+pub fn ok_reply(extra: Value) -> Value {
+    let mut reply = json!({ "ok": true });
+    if let (Value::Object(out), Value::Object(fields)) = (&mut reply, extra) {
+        out.extend(fields);
+    }
+    reply
+}
+
+pub fn error_reply(error: impl std::fmt::Display) -> Value {
+    json!({ "ok": false, "error": error.to_string() })
+}
+
+/// Listening socket plus the channel its connections feed. Dropping it stops accepting and
+/// removes the socket file.
+pub struct ControlServer {
+    path: PathBuf,
+    receiver: Receiver<ControlRequest>,
+    running: Arc<AtomicBool>,
+}
+
+impl ControlServer {
+    /// Bind `path`, replacing a stale socket left by a crashed daemon but never a live one.
+    /// Every queued command wakes the render loop through `waker`.
+    pub fn bind(
+        path: impl Into<PathBuf>,
+        app_running: Arc<AtomicBool>,
+        waker: LoopWaker,
+    ) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{} is already served by another process", path.display()),
+                ));
+            }
+            fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(SOCKET_MODE))?;
+        listener.set_nonblocking(true)?;
+
+        let (tx, rx) = mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+        let running_clone = running.clone();
+        thread::Builder::new()
+            .name("lifelinetty-control".into())
+            .spawn(move || {
+                while app_running.load(Ordering::SeqCst) && running_clone.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let tx = tx.clone();
+                            let waker = waker.clone();
+                            let _ = thread::Builder::new()
+                                .name("lifelinetty-control-conn".into())
+                                .spawn(move || serve_connection(stream, &tx, &waker));
+                        }
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                            thread::sleep(Duration::from_millis(ACCEPT_POLL_MS));
+                        }
+                        Err(_) => thread::sleep(Duration::from_millis(ACCEPT_POLL_MS)),
+                    }
+                }
+            })?;
+        Ok(Self {
+            path,
+            receiver: rx,
+            running,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Next command waiting for the render loop, if any.
+    pub fn try_recv(&self) -> Option<ControlRequest> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn serve_connection(stream: UnixStream, requests: &Sender<ControlRequest>, waker: &LoopWaker) {
+    if stream.set_nonblocking(false).is_err() {
+        return;
+    }
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match (&mut reader)
+            .take(MAX_COMMAND_BYTES + 1)
+            .read_line(&mut line)
+        {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        if line.len() as u64 > MAX_COMMAND_BYTES {
+            let _ = write_reply(
+                &mut writer,
+                &error_reply(format!("command exceeds {MAX_COMMAND_BYTES} bytes")),
+            );
+            return;
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let reply = match ControlCommand::parse(trimmed) {
+            Ok(command) => dispatch(command, requests, waker),
+            Err(err) => error_reply(err),
+        };
+        if write_reply(&mut writer, &reply).is_err() {
+            return;
+        }
+    }
+}
+
+fn dispatch(
+    command: ControlCommand,
+    requests: &Sender<ControlRequest>,
+    waker: &LoopWaker,
+) -> Value {
+    let (tx, rx) = mpsc::channel();
+    let request = ControlRequest { command, reply: tx };
+    if requests.send(request).is_err() {
+        return error_reply("daemon is shutting down");
+    }
+    waker.wake();
+    rx.recv_timeout(Duration::from_millis(REPLY_TIMEOUT_MS))
+        .unwrap_or_else(|_| error_reply("daemon did not answer in time"))
+}
+
+fn write_reply(writer: &mut UnixStream, reply: &Value) -> io::Result<()> {
+    writeln!(writer, "{reply}")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_parse_from_tagged_json() {
+        assert_eq!(
+            ControlCommand::parse(r#"{"cmd":"backlight","on":false}"#),
+            Ok(ControlCommand::Backlight { on: false })
+        );
+        assert_eq!(
+            ControlCommand::parse(r#"{"cmd":"clear"}"#),
+            Ok(ControlCommand::Clear)
+        );
+        assert_eq!(
+            ControlCommand::parse(r#"{"cmd":"handshakes"}"#),
+            Ok(ControlCommand::Handshakes)
+        );
+        assert_eq!(
+            ControlCommand::parse(r#"{"cmd":"snapshot"}"#),
+            Ok(ControlCommand::Snapshot)
+        );
+        assert_eq!(
+            ControlCommand::parse(r#"{"cmd":"hud","on":true}"#),
+            Ok(ControlCommand::Hud { on: true })
+        );
+        let Ok(ControlCommand::Frame { payload }) =
+            ControlCommand::parse(r#"{"cmd":"frame","payload":{"line1":"A","line2":"B"}}"#)
+        else {
+            panic!("frame command did not parse");
+        };
+        assert_eq!(payload_text(&payload), r#"{"line1":"A","line2":"B"}"#);
+        assert_eq!(payload_text(&json!("line1=A line2=B")), "line1=A line2=B");
+        assert!(ControlCommand::parse(r#"{"cmd":"reboot"}"#).is_err());
+        assert!(ControlCommand::parse(r#"{"cmd":"backlight"}"#).is_err());
+    }
+
+    #[test]
+    fn replies_merge_fields_after_ok() {
+        assert_eq!(
+            ok_reply(json!({ "pages": 2 })),
+            json!({ "ok": true, "pages": 2 })
+        );
+        assert_eq!(error_reply("nope"), json!({ "ok": false, "error": "nope" }));
+    }
+
+    #[test]
+    fn socket_round_trips_commands_through_the_channel() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ctl").join("control.sock");
+        let server =
+            ControlServer::bind(&path, Arc::new(AtomicBool::new(true)), LoopWaker::default())
+                .unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            SOCKET_MODE
+        );
+        assert_eq!(
+            ControlServer::bind(&path, Arc::new(AtomicBool::new(true)), LoopWaker::default())
+                .err()
+                .map(|err| err.kind()),
+            Some(io::ErrorKind::AddrInUse)
+        );
+
+        let handle = thread::spawn(move || {
+            let request = loop {
+                if let Some(request) = server.try_recv() {
+                    break request;
+                }
+                thread::sleep(Duration::from_millis(5));
+            };
+            assert_eq!(request.command(), &ControlCommand::Stats);
+            request.respond(ok_reply(json!({ "pages": 0 })));
+            server
+        });
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client
+            .write_all(b"not json\n{\"cmd\":\"stats\"}\n")
+            .unwrap();
+        let mut lines = BufReader::new(client.try_clone().unwrap()).lines();
+        let first: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+        assert_eq!(first["ok"], json!(false));
+        let second: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+        assert_eq!(second, json!({ "ok": true, "pages": 0 }));
+
+        drop(handle.join().unwrap());
+        assert!(!path.exists());
+        // The socket file is gone, so a new daemon can bind the same path.
+        drop(
+            ControlServer::bind(&path, Arc::new(AtomicBool::new(true)), LoopWaker::default())
+                .unwrap(),
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Crash reporting.
+//!
+//! The daemon installs a panic hook that writes `crash/crash-<unix secs>.txt` under the cache
+//! dir: the panic message and location, a backtrace and the last [`RECENT_LINES`] log lines,
+//! which the logger keeps in memory for this and `lifelinetty monitor` also shows. A panic in
+//! the render loop is then caught in [`super::App::run`], which puts [`render_crash_screen`] on
+//! the LCD and exits with [`crate::ExitCode::Crashed`] so systemd restarts the unit instead of
+//! leaving stale content on the glass. A panic on a background thread (tunnel, metrics, relay)
+//! is fatal too: the hook records it, and the render loop unwinds into the same path on its next
+//! pass rather than running on without the thread.
+
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fmt::Write as _,
+    fs, io,
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::display::backend::DisplayBackend;
+
+/// Directory under the cache dir the reports are written to.
+pub const CRASH_DIR: &str = "crash";
+/// Log lines kept for the next crash report.
+pub const RECENT_LINES: usize = 64;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Set by the panic hook; the render loop checks it every pass.
+static PANICKED: AtomicBool = AtomicBool::new(false);
+
+/// Whether any thread has panicked since the hook was installed.
+pub(crate) fn panicked() -> bool {
+    PANICKED.load(Ordering::SeqCst)
+}
+
+/// Keep `line` for the next crash report, dropping the oldest past [`RECENT_LINES`].
+pub(crate) fn remember(line: &str) {
+    let Ok(mut recent) = RECENT.lock() else {
+        return;
+    };
+    if recent.len() == RECENT_LINES {
+        recent.pop_front();
+    }
+    recent.push_back(line.to_string());
+}
+
+/// The log lines kept for the next crash report, oldest first.
+pub(crate) fn recent_log() -> Vec<String> {
+    RECENT
+        .lock()
+        .map(|recent| recent.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Write a crash report for every panic, after the default message on stderr.
+pub(crate) fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        PANICKED.store(true, Ordering::SeqCst);
+        default_hook(info);
+        // A panic while the logger held the ring must not deadlock the hook.
+        let recent = RECENT
+            .try_lock()
+            .map(|recent| recent.iter().cloned().collect())
+            .unwrap_or_default();
+        let report = CrashReport {
+            summary: describe(info),
+            backtrace: Backtrace::force_capture().to_string(),
+            recent,
+        };
+        match report.write(&crate::cache_dir().join(CRASH_DIR), SystemTime::now()) {
+            Ok(path) => eprintln!("crash report written to {}", path.display()),
+            Err(err) => eprintln!("crash report could not be written: {err}"),
+        }
+    }));
+}
+
+/// Best effort: tell whoever looks at the display that the daemon died. Errors are ignored,
+/// since the bus may be what failed.
+pub(crate) fn render_crash_screen<D: DisplayBackend>(lcd: &mut D) {
+    let _ = lcd.clear();
+    let _ = lcd.set_blink(false);
+    let _ = lcd.set_backlight(true);
+    if lcd.rows() > 1 {
+        let _ = lcd.write_line(0, "DAEMON CRASH");
+        let _ = lcd.write_line(1, "see log");
+    } else {
+        let _ = lcd.write_line(0, "CRASH see log");
+    }
+}
+
+struct CrashReport {
+    summary: String,
+    backtrace: String,
+    recent: Vec<String>,
+}
+
+impl CrashReport {
+    fn write(&self, dir: &Path, now: SystemTime) -> io::Result<PathBuf> {
+        let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut text = format!(
+            "lifelinetty {} crashed at {secs} (unix)\n{}\n\nbacktrace:\n{}\n\nrecent log:\n",
+            env!("CARGO_PKG_VERSION"),
+            self.summary,
+            self.backtrace.trim_end()
+        );
+        for line in &self.recent {
+            let _ = writeln!(text, "{line}");
+        }
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("crash-{secs}.txt"));
+        fs::write(&path, text)?;
+        Ok(path)
+    }
+}
+
+/// Thread, message and location of a panic, on one line.
+fn describe(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("(no message)");
+    let thread = std::thread::current();
+    let mut line = format!(
+        "thread '{}' panicked: {message}",
+        thread.name().unwrap_or("<unnamed>")
+    );
+    if let Some(location) = info.location() {
+        let _ = write!(line, " at {location}");
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn report_carries_the_panic_backtrace_and_recent_log() {
+        let cache = tempdir().unwrap();
+        let dir = cache.path().join(CRASH_DIR);
+        let report = CrashReport {
+            summary: "thread 'main' panicked: boom at src/app/render_loop.rs:1:1".into(),
+            backtrace: "   0: lifelinetty::app::render_loop\n".into(),
+            recent: vec!["[1.000] [Info] daemon start".into()],
+        };
+        let path = report
+            .write(&dir, UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .unwrap();
+        assert_eq!(path, dir.join("crash-1700000000.txt"));
+        let text = fs::read_to_string(path).unwrap();
+        assert!(text.contains("panicked: boom"));
+        assert!(text.contains("backtrace:\n   0: lifelinetty::app::render_loop\n"));
+        assert!(text.ends_with("recent log:\n[1.000] [Info] daemon start\n"));
+    }
+}
@@ -1,22 +1,25 @@
 use super::{lifecycle::create_shutdown_flag, AppConfig, Logger};
 use crate::{
     display::{
+        backend::DisplayBackend,
+        capture::Capture,
         icon_bank::{IconBank, IconPalette},
-        overlays::{advance_offset, line_needs_scroll, render_if_allowed, render_offline_message},
+        lcd::Lcd,
+        overlays::{
+            frame_needs_scroll, render_frame_with_scroll, render_if_allowed,
+            render_offline_message, StatusMarks,
+        },
     },
-    lcd::Lcd,
     payload::{Defaults as PayloadDefaults, RenderFrame},
     Error, Result,
 };
 use serde_json::Value;
 use std::{
+    path::Path,
     thread,
     time::{Duration, Instant},
 };
 
-const MIN_RENDER_MS: u64 = 200;
-const BLINK_INTERVAL_MS: u64 = 500;
-
 const DEMO_PAYLOADS: [&str; 26] = [
     r#"{"schema_version":1,"line1":"Up 12:34 CPU 42%","line2":"RAM 73%","bar_value":73,"bar_max":100,"bar_label":"RAM","mode":"dashboard","page_timeout_ms":4000}"#,
     r#"{"schema_version":1,"line1":"CPU LOAD","line2":"Cores busy","bar":68,"bar_label":"CPU","page_timeout_ms":3500}"#,
@@ -46,7 +49,11 @@ const DEMO_PAYLOADS: [&str; 26] = [
     r#"{"schema_version":1,"line1":"Ping-pong alert","line2":"Blinking wifi guard","icons":["wifi"],"blink":true,"backlight":true,"page_timeout_ms":3000}"#,
 ];
 
-pub fn run_demo(lcd: &mut Lcd, config: &mut AppConfig, logger: &Logger) -> Result<()> {
+pub fn run_demo<D: DisplayBackend>(
+    lcd: &mut D,
+    config: &mut AppConfig,
+    logger: &Logger,
+) -> Result<()> {
     let defaults = PayloadDefaults {
         scroll_speed_ms: config.scroll_speed_ms,
         page_timeout_ms: config.page_timeout_ms,
@@ -63,14 +70,15 @@ pub fn run_demo(lcd: &mut Lcd, config: &mut AppConfig, logger: &Logger) -> Resul
     let mut current_frame = frames[idx].clone();
     logger.info(format!("demo payload: {}", DEMO_PAYLOADS[idx]));
     let mut last_render = Instant::now();
-    let min_render_interval = Duration::from_millis(MIN_RENDER_MS);
+    let min_render_interval = Duration::from_millis(config.render.min_interval_ms);
     let mut scroll_offsets = super::events::ScrollOffsets::zero();
     let mut next_scroll = Instant::now();
     let mut next_page = Instant::now() + Duration::from_millis(current_frame.page_timeout_ms);
     let mut backlight_state = current_frame.backlight_on;
-    let blink_interval = Duration::from_millis(BLINK_INTERVAL_MS);
+    let blink_interval = Duration::from_millis(config.render.blink_ms);
     let mut next_blink = Instant::now() + blink_interval;
     let mut icon_bank = IconBank::new();
+    icon_bank.set_bar_style(config.render.bar_style);
 
     lcd.clear()?;
     lcd.set_backlight(current_frame.backlight_on)?;
@@ -80,8 +88,10 @@ pub fn run_demo(lcd: &mut Lcd, config: &mut AppConfig, logger: &Logger) -> Resul
         &current_frame,
         &mut last_render,
         min_render_interval,
-        (scroll_offsets.top, scroll_offsets.bottom),
+        &scroll_offsets.rows,
         false,
+        StatusMarks::default(),
+        None,
         &mut icon_bank,
     )?;
     log_demo_icon_fallbacks(logger, palette);
@@ -106,8 +116,10 @@ pub fn run_demo(lcd: &mut Lcd, config: &mut AppConfig, logger: &Logger) -> Resul
                 &current_frame,
                 &mut last_render,
                 min_render_interval,
-                (scroll_offsets.top, scroll_offsets.bottom),
+                &scroll_offsets.rows,
                 false,
+                StatusMarks::default(),
+                None,
                 &mut icon_bank,
             )?;
             log_demo_icon_fallbacks(logger, palette);
@@ -115,40 +127,19 @@ pub fn run_demo(lcd: &mut Lcd, config: &mut AppConfig, logger: &Logger) -> Resul
 
         // Scrolling
         let width = lcd.cols() as usize;
-        let needs_scroll = match current_frame.bar_row {
-            Some(0) => {
-                current_frame.scroll_enabled && line_needs_scroll(&current_frame.line2, width)
-            }
-            Some(1) => {
-                current_frame.scroll_enabled && line_needs_scroll(&current_frame.line1, width)
-            }
-            _ => {
-                current_frame.scroll_enabled
-                    && (line_needs_scroll(&current_frame.line1, width)
-                        || line_needs_scroll(&current_frame.line2, width))
-            }
-        };
+        let needs_scroll = frame_needs_scroll(&current_frame, lcd.rows(), width);
         if needs_scroll && now >= next_scroll {
-            scroll_offsets = scroll_offsets.update(
-                advance_offset(
-                    &current_frame.line1,
-                    lcd.cols() as usize,
-                    scroll_offsets.top,
-                ),
-                advance_offset(
-                    &current_frame.line2,
-                    lcd.cols() as usize,
-                    scroll_offsets.bottom,
-                ),
-            );
+            scroll_offsets = scroll_offsets.advance(&current_frame, width);
             next_scroll = now + Duration::from_millis(current_frame.scroll_speed_ms);
             let palette = render_if_allowed(
                 lcd,
                 &current_frame,
                 &mut last_render,
                 min_render_interval,
-                (scroll_offsets.top, scroll_offsets.bottom),
+                &scroll_offsets.rows,
                 false,
+                StatusMarks::default(),
+                None,
                 &mut icon_bank,
             )?;
             log_demo_icon_fallbacks(logger, palette);
@@ -168,6 +159,44 @@ pub fn run_demo(lcd: &mut Lcd, config: &mut AppConfig, logger: &Logger) -> Resul
     Ok(())
 }
 
+/// Draw every demo page once on an in-memory display of the configured size and save a
+/// snapshot of each into `dir`. Pages are drawn unscrolled, without the heartbeat, and in a
+/// fixed order, so the same build always writes the same files.
+pub fn capture_demo(config: &AppConfig, logger: &Logger, dir: &Path) -> Result<()> {
+    let defaults = PayloadDefaults {
+        scroll_speed_ms: config.scroll_speed_ms,
+        page_timeout_ms: config.page_timeout_ms,
+    };
+    let frames = build_demo_frames(defaults, usize::from(config.cols).max(1))?;
+    let mut lcd = Lcd::new_stub(config.cols, config.rows);
+    lcd.set_charmap(config.charmap.build()?);
+    let mut icon_bank = IconBank::new();
+    icon_bank.set_bar_style(config.render.bar_style);
+    let mut capture = Capture::new(dir, config.capture_format)?;
+    for frame in &frames {
+        lcd.clear()?;
+        lcd.set_backlight(frame.backlight_on)?;
+        render_frame_with_scroll(
+            &mut lcd,
+            frame,
+            &[],
+            false,
+            StatusMarks::default(),
+            None,
+            &mut icon_bank,
+        )?;
+        let path = capture.save(&lcd, &frame.line1)?;
+        logger.debug(format!("demo capture: wrote {}", path.display()));
+    }
+    logger.info(format!(
+        "demo capture: {} {} snapshots in {}",
+        frames.len(),
+        config.capture_format,
+        dir.display()
+    ));
+    Ok(())
+}
+
 fn log_demo_icon_fallbacks(logger: &Logger, palette: Option<IconPalette>) {
     let Some(palette) = palette else {
         return;
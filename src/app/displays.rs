@@ -0,0 +1,240 @@
+//! Extra LCDs from `[[displays]]`, sharing the I2C bus with the main display.
+//!
+//! The top-level display keeps the page rotation. Each extra display either mirrors it, cut or
+//! padded to its own size, shows the polling stats all the time, or shows the `[[zones]]`
+//! placed on it. Rows are only written when
+//! their text changes, so an idle mirror costs no bus traffic.
+
+use super::lifecycle::render_shutdown;
+use super::polling::PollSnapshot;
+use super::render_loop::{format_polling_lines, PollLayout};
+use super::zones::ZoneRows;
+use super::{AppConfig, Logger};
+use crate::{
+    config::{DisplayConfig, DisplayRole, LcdGpioConfig, Pcf8574Addr},
+    lcd::Lcd,
+    Result,
+};
+
+/// What the extra displays draw from on one pass of the render loop.
+pub(super) struct Sources<'a> {
+    /// Rows on the main display after this pass.
+    pub screen: &'a [String],
+    pub backlight: bool,
+    pub polling: Option<(&'a PollSnapshot, &'a PollLayout)>,
+    pub serial_active: bool,
+    pub zones: &'a [ZoneRows],
+}
+
+struct ExtraDisplay {
+    lcd: Lcd,
+    addr: u8,
+    role: DisplayRole,
+    shown: Vec<String>,
+    backlight: Option<bool>,
+    failing: bool,
+}
+
+#[derive(Default)]
+pub(super) struct ExtraDisplays {
+    displays: Vec<ExtraDisplay>,
+}
+
+impl ExtraDisplays {
+    /// Open every `[[displays]]` entry. One that fails to initialise is logged and left out so
+    /// the main display keeps running.
+    pub fn open(config: &AppConfig, logger: &Logger) -> Self {
+        let mut displays = Vec::new();
+        for entry in &config.displays {
+            let Pcf8574Addr::Addr(addr) = entry.addr else {
+                continue; // validation rejects auto
+            };
+            match open_lcd(config, entry) {
+                Ok(lcd) => {
+                    logger.info(format!(
+                        "display {addr:#04x}: {}x{} {}",
+                        entry.cols, entry.rows, entry.role
+                    ));
+                    displays.push(ExtraDisplay {
+                        lcd,
+                        addr,
+                        role: entry.role,
+                        shown: Vec::new(),
+                        backlight: None,
+                        failing: false,
+                    });
+                }
+                Err(err) => logger.warn(format!("display {addr:#04x} disabled: {err}")),
+            }
+        }
+        Self { displays }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.displays.is_empty()
+    }
+
+    pub fn render(&mut self, sources: &Sources<'_>, logger: &Logger) {
+        for display in &mut self.displays {
+            let lines = display.lines(sources);
+            match display.draw(&lines, sources.backlight) {
+                Ok(()) if display.failing => {
+                    display.failing = false;
+                    logger.info(format!("display {:#04x}: writes recovered", display.addr));
+                }
+                Ok(()) => {}
+                Err(err) => {
+                    // Redraw everything once the display answers again.
+                    display.shown.clear();
+                    display.backlight = None;
+                    if !std::mem::replace(&mut display.failing, true) {
+                        logger.warn(format!(
+                            "display {:#04x}: write failed: {err}",
+                            display.addr
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// I2C bytes written to the extra displays so far.
+    pub fn bus_bytes(&self) -> u64 {
+        self.displays
+            .iter()
+            .map(|display| display.lcd.bus_bytes())
+            .sum()
+    }
+
+    pub fn shutdown(&mut self) {
+        for display in &mut self.displays {
+            let _ = render_shutdown(&mut display.lcd);
+        }
+    }
+}
+
+fn open_lcd(config: &AppConfig, entry: &DisplayConfig) -> Result<Lcd> {
+    let mut lcd = if config.dry_run || !config.lcd_present {
+        // --dry-run already draws the main display in the terminal; keep the extras quiet.
+        Lcd::new_stub(entry.cols, entry.rows)
+    } else {
+        Lcd::new(
+            entry.cols,
+            entry.rows,
+            entry.addr.clone(),
+            config.display_driver,
+            LcdGpioConfig::default(),
+        )?
+    };
+    lcd.set_charmap(config.charmap.build()?);
+    lcd.clear()?;
+    Ok(lcd)
+}
+
+impl ExtraDisplay {
+    fn lines(&self, sources: &Sources<'_>) -> Vec<String> {
+        let cols = self.lcd.cols() as usize;
+        let mut lines: Vec<String> = match (self.role, sources.polling) {
+            (DisplayRole::Mirror, _) => sources.screen.to_vec(),
+            (DisplayRole::Polling, Some((snapshot, layout))) => {
+                let (line1, line2) =
+                    format_polling_lines(snapshot, layout, cols, sources.serial_active);
+                vec![line1, line2]
+            }
+            (DisplayRole::Polling, None) => vec!["polling...".into()],
+            (DisplayRole::Zones, _) => {
+                let mut lines = vec![String::new(); self.lcd.rows() as usize];
+                for zone in sources.zones {
+                    if zone.display != Some(self.addr) {
+                        continue;
+                    }
+                    for (row, line) in (zone.row as usize..).zip(&zone.lines) {
+                        if let Some(slot) = lines.get_mut(row) {
+                            slot.clone_from(line);
+                        }
+                    }
+                }
+                lines
+            }
+        };
+        lines.resize(self.lcd.rows() as usize, String::new());
+        for line in &mut lines {
+            *line = line.chars().take(cols).collect();
+        }
+        lines
+    }
+
+    fn draw(&mut self, lines: &[String], backlight: bool) -> Result<()> {
+        // The polling and zones displays stay lit; the mirror follows the main backlight.
+        let backlight = backlight || self.role != DisplayRole::Mirror;
+        if self.backlight != Some(backlight) {
+            self.lcd.set_backlight(backlight)?;
+            self.backlight = Some(backlight);
+        }
+        if self.shown == lines {
+            return Ok(());
+        }
+        for (row, line) in lines.iter().enumerate() {
+            if self.shown.get(row) != Some(line) {
+                self.lcd.write_line(row as u8, line)?;
+            }
+        }
+        self.shown = lines.to_vec();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::LogLevel;
+
+    fn stub_display(role: DisplayRole, cols: u8, rows: u8) -> ExtraDisplay {
+        ExtraDisplay {
+            lcd: Lcd::new_stub(cols, rows),
+            addr: 0x26,
+            role,
+            shown: Vec::new(),
+            backlight: None,
+            failing: false,
+        }
+    }
+
+    #[test]
+    fn mirror_fits_the_main_screen_to_its_size_and_skips_unchanged_rows() {
+        let screen = vec![
+            "CPU 42%  load 0.31 ".to_string(),
+            "up 3d 4h           ".to_string(),
+            "eth0 12 Mb/s       ".to_string(),
+            "disk 61%           ".to_string(),
+        ];
+        let sources = Sources {
+            screen: &screen,
+            backlight: true,
+            polling: None,
+            serial_active: true,
+            zones: &[],
+        };
+        let mut displays = ExtraDisplays {
+            displays: vec![
+                stub_display(DisplayRole::Mirror, 16, 2),
+                stub_display(DisplayRole::Polling, 16, 2),
+            ],
+        };
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        displays.render(&sources, &logger);
+        let mirror = &displays.displays[0];
+        assert_eq!(
+            mirror.lcd.screen(),
+            ["CPU 42%  load 0.", "up 3d 4h        "]
+        );
+        assert_eq!(
+            displays.displays[1].lcd.screen()[0].trim_end(),
+            "polling..."
+        );
+
+        let written = displays.bus_bytes();
+        displays.render(&sources, &logger);
+        assert_eq!(displays.bus_bytes(), written);
+    }
+}
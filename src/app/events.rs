@@ -1,5 +1,11 @@
+use super::command_cache::{CacheRequest, CommandCache, Recording};
+use super::idle::LoopWaker;
+use super::interlock::unix_ms;
+use super::tunnel_auth::{CommandAuth, SignedRequest};
 use crate::{
-    payload::{decode_command_frame, CommandMessage, CommandStream},
+    config::{COMMAND_ALLOWLIST_OFF, MAX_ROWS},
+    display::overlays::advance_offset,
+    payload::{decode_command_frame_with_seq, CommandMessage, CommandStream, RenderFrame},
     Result,
 };
 use serde_bytes::ByteBuf;
@@ -12,21 +18,34 @@ use std::sync::{
     Arc,
 };
 use std::thread;
+use std::time::{Instant, SystemTime};
 
-/// Stores scroll offsets for the two LCD lines to avoid ad-hoc tuples.
+/// Stores the marquee offset of each LCD row to avoid ad-hoc tuples.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ScrollOffsets {
-    pub top: usize,
-    pub bottom: usize,
+    pub rows: [usize; MAX_ROWS as usize],
 }
 
 impl ScrollOffsets {
     pub fn zero() -> Self {
-        Self { top: 0, bottom: 0 }
+        Self {
+            rows: [0; MAX_ROWS as usize],
+        }
     }
 
-    pub fn update(self, top: usize, bottom: usize) -> Self {
-        Self { top, bottom }
+    /// Step every row of `frame` that is wider than `width` one cell along its marquee.
+    pub fn advance(self, frame: &RenderFrame, width: usize) -> Self {
+        let mut rows = self.rows;
+        for (row, offset) in rows.iter_mut().enumerate() {
+            *offset = advance_offset(frame.line(row as u8), width, *offset);
+        }
+        Self { rows }
+    }
+
+    pub fn reset_row(&mut self, row: u8) {
+        if let Some(offset) = self.rows.get_mut(row as usize) {
+            *offset = 0;
+        }
     }
 }
 
@@ -37,6 +56,8 @@ pub enum CommandEvent {
         request_id: u32,
         cmd: String,
         scratch_path: Option<String>,
+        otp: Option<String>,
+        sig: Option<String>,
     },
     Chunk {
         request_id: u32,
@@ -87,10 +108,14 @@ impl From<CommandMessage> for CommandEvent {
                 request_id,
                 cmd,
                 scratch_path,
+                otp,
+                sig,
             } => CommandEvent::Request {
                 request_id,
                 cmd,
                 scratch_path,
+                otp,
+                sig,
             },
             CommandMessage::Chunk {
                 request_id,
@@ -122,17 +147,20 @@ impl From<CommandMessage> for CommandEvent {
 #[derive(Default)]
 pub struct CommandBridge {
     last_seen_request: Option<u32>,
+    last_seq: Option<u64>,
 }
 
 impl CommandBridge {
     pub fn new() -> Self {
         Self {
             last_seen_request: None,
+            last_seq: None,
         }
     }
 
     pub fn ingest_line(&mut self, raw: &str) -> Result<Option<CommandEvent>> {
-        let message = decode_command_frame(raw)?;
+        let (message, seq) = decode_command_frame_with_seq(raw)?;
+        self.last_seq = seq;
         if let Some(request_id) = message_request_id(&message) {
             self.last_seen_request = Some(request_id);
         }
@@ -143,6 +171,11 @@ impl CommandBridge {
     pub fn last_request_id(&self) -> Option<u32> {
         self.last_seen_request
     }
+
+    /// Sequence number of the most recent frame, if the sender stamped one.
+    pub fn last_seq(&self) -> Option<u64> {
+        self.last_seq
+    }
 }
 
 fn message_request_id(msg: &CommandMessage) -> Option<u32> {
@@ -161,13 +194,25 @@ fn message_request_id(msg: &CommandMessage) -> Option<u32> {
 }
 
 const COMMAND_STREAM_CHUNK_SIZE: usize = 512;
+pub(crate) const INTERLOCK_REJECTION: &str = "command rejected: interlock is not armed";
+const TUNNEL_OFF_REJECTION: &str = "command rejected: command tunnel is disabled";
 
 pub struct CommandExecutor {
     allowlist: Vec<String>,
+    /// Set while the interlock is disarmed; every request is rejected.
+    locked: bool,
+    /// Per-request authorization from `[tunnel_auth]`.
+    auth: CommandAuth,
     session_active: bool,
     current_request: Option<u32>,
     outgoing_tx: Sender<CommandMessage>,
     outgoing_rx: Receiver<CommandMessage>,
+    waker: LoopWaker,
+    /// Client terminal as `(cols, rows)`, exported to commands as `COLUMNS` and `LINES`.
+    terminal: Option<(u16, u16)>,
+    cache: CommandCache,
+    /// Output of the running command when its request asked for caching.
+    recording: Option<Recording>,
 }
 
 impl CommandExecutor {
@@ -175,67 +220,113 @@ impl CommandExecutor {
         let (tx, rx) = mpsc::channel();
         Self {
             allowlist,
+            locked: false,
+            auth: CommandAuth::default(),
             session_active: false,
             current_request: None,
             outgoing_tx: tx,
             outgoing_rx: rx,
+            waker: LoopWaker::default(),
+            terminal: None,
+            cache: CommandCache::default(),
+            recording: None,
         }
     }
 
+    /// Size commands should format their output for from now on. Cached output was formatted
+    /// for the old size, so it is dropped.
+    pub fn set_terminal_size(&mut self, cols: u16, rows: u16) {
+        if self.terminal != Some((cols, rows)) {
+            self.cache.clear();
+        }
+        self.terminal = Some((cols, rows));
+    }
+
+    /// Wake the render loop whenever a running command has output or exits.
+    pub fn set_waker(&mut self, waker: LoopWaker) {
+        self.waker = waker;
+    }
+
+    pub fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
+    pub fn set_auth(&mut self, auth: CommandAuth) {
+        self.auth = auth;
+    }
+
     pub fn handle_event(&mut self, event: CommandEvent) -> Option<CommandMessage> {
+        self.handle_event_with_cache(event, CacheRequest::default())
+    }
+
+    /// [`handle_event`](Self::handle_event) for a request that may be answered from, or stored
+    /// in, the result cache. The lock, auth and allowlist checks run either way.
+    pub fn handle_event_with_cache(
+        &mut self,
+        event: CommandEvent,
+        cache: CacheRequest,
+    ) -> Option<CommandMessage> {
         match event {
             CommandEvent::Request {
                 request_id,
                 cmd,
-                scratch_path: _,
+                scratch_path,
+                otp,
+                sig,
             } => {
+                if self.locked {
+                    return self.reject(request_id, INTERLOCK_REJECTION.to_string());
+                }
+                if self.allowlist == [COMMAND_ALLOWLIST_OFF] {
+                    return self.reject(request_id, TUNNEL_OFF_REJECTION.to_string());
+                }
+                let request = SignedRequest {
+                    cmd: &cmd,
+                    scratch_path: scratch_path.as_deref(),
+                };
+                if let Err(message) = self.auth.check(
+                    &request,
+                    otp.as_deref(),
+                    sig.as_deref(),
+                    unix_ms(SystemTime::now()),
+                ) {
+                    return self.reject(request_id, message);
+                }
                 if self.session_active {
                     return Some(CommandMessage::Busy { request_id });
                 }
                 let tokens = match split_command_line(&cmd) {
                     Ok(tokens) => tokens,
                     Err(err) => {
-                        let msg = format!("command parse error: {err}");
-                        self.queue(CommandMessage::Error {
-                            request_id: Some(request_id),
-                            message: msg.clone(),
-                        });
-                        self.queue(CommandMessage::Exit {
-                            request_id,
-                            code: 1,
-                        });
-                        return Some(CommandMessage::Error {
-                            request_id: Some(request_id),
-                            message: msg,
-                        });
+                        return self.reject(request_id, format!("command parse error: {err}"));
                     }
                 };
                 let program = tokens[0].clone();
                 if !command_allowed(&program, &self.allowlist) {
-                    let msg = format!("command not allowed: {program}");
-                    self.queue(CommandMessage::Error {
-                        request_id: Some(request_id),
-                        message: msg.clone(),
-                    });
-                    self.queue(CommandMessage::Exit {
-                        request_id,
-                        code: 1,
-                    });
-                    return Some(CommandMessage::Error {
-                        request_id: Some(request_id),
-                        message: msg,
-                    });
+                    return self.reject(request_id, format!("command not allowed: {program}"));
                 }
-                match Command::new(&program)
+                if cache.reads() && self.replay_cached(request_id, &cmd) {
+                    return Some(CommandMessage::Ack { request_id });
+                }
+                let mut command = Command::new(&program);
+                command
                     .args(&tokens[1..])
                     .stdin(Stdio::null())
                     .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .spawn()
-                {
+                    .stderr(Stdio::piped());
+                if let Some((cols, rows)) = self.terminal {
+                    command
+                        .env("COLUMNS", cols.to_string())
+                        .env("LINES", rows.to_string());
+                }
+                match command.spawn() {
                     Ok(mut child) => {
                         self.session_active = true;
                         self.current_request = Some(request_id);
+                        self.recording = cache
+                            .ttl_ms
+                            .filter(|ttl| *ttl > 0)
+                            .map(|ttl| Recording::new(request_id, cmd.clone(), ttl));
                         let tx = self.outgoing_tx.clone();
                         let stdout_seq = Arc::new(AtomicU32::new(0));
                         let stderr_seq = Arc::new(AtomicU32::new(0));
@@ -246,6 +337,7 @@ impl CommandExecutor {
                                 request_id,
                                 stdout_seq,
                                 tx.clone(),
+                                self.waker.clone(),
                             )
                         });
                         let stderr_handle = child.stderr.take().map(|stderr| {
@@ -255,9 +347,11 @@ impl CommandExecutor {
                                 request_id,
                                 stderr_seq,
                                 tx.clone(),
+                                self.waker.clone(),
                             )
                         });
                         let tx_exit = self.outgoing_tx.clone();
+                        let waker = self.waker.clone();
                         thread::spawn(move || {
                             let code = match child.wait() {
                                 Ok(status) => status.code().unwrap_or(-1),
@@ -270,23 +364,12 @@ impl CommandExecutor {
                                 let _ = handle.join();
                             }
                             let _ = tx_exit.send(CommandMessage::Exit { request_id, code });
+                            waker.wake();
                         });
                         Some(CommandMessage::Ack { request_id })
                     }
                     Err(err) => {
-                        let msg = format!("failed to spawn '{program}': {err}");
-                        self.queue(CommandMessage::Error {
-                            request_id: Some(request_id),
-                            message: msg.clone(),
-                        });
-                        self.queue(CommandMessage::Exit {
-                            request_id,
-                            code: 1,
-                        });
-                        Some(CommandMessage::Error {
-                            request_id: Some(request_id),
-                            message: msg,
-                        })
+                        self.reject(request_id, format!("failed to spawn '{program}': {err}"))
                     }
                 }
             }
@@ -297,6 +380,7 @@ impl CommandExecutor {
     pub fn next_outgoing(&mut self) -> Option<CommandMessage> {
         match self.outgoing_rx.try_recv() {
             Ok(msg) => {
+                self.record(&msg);
                 if matches!(msg, CommandMessage::Exit { .. }) {
                     self.session_active = false;
                     self.current_request = None;
@@ -307,6 +391,72 @@ impl CommandExecutor {
         }
     }
 
+    /// Queue a cached run of `cmd` as if it had just executed; `false` on a miss.
+    fn replay_cached(&mut self, request_id: u32, cmd: &str) -> bool {
+        let Some(run) = self.cache.get(cmd, Instant::now()) else {
+            return false;
+        };
+        let (chunks, code) = (run.chunks.clone(), run.code);
+        let (mut stdout_seq, mut stderr_seq) = (0, 0);
+        for (stream, data) in chunks {
+            let seq = match stream {
+                CommandStream::Stdout => &mut stdout_seq,
+                CommandStream::Stderr => &mut stderr_seq,
+            };
+            self.queue(CommandMessage::Chunk {
+                request_id,
+                stream,
+                seq: *seq,
+                data: ByteBuf::from(data),
+            });
+            *seq += 1;
+        }
+        self.queue(CommandMessage::Exit { request_id, code });
+        // Held like a real run until the exit drains, so nothing interleaves with it.
+        self.session_active = true;
+        self.current_request = Some(request_id);
+        self.waker.wake();
+        true
+    }
+
+    fn record(&mut self, msg: &CommandMessage) {
+        let Some(recording) = self.recording.as_mut() else {
+            return;
+        };
+        match msg {
+            CommandMessage::Chunk {
+                request_id,
+                stream,
+                data,
+                ..
+            } if *request_id == recording.request_id => recording.push(*stream, data),
+            CommandMessage::Exit { request_id, code } if *request_id == recording.request_id => {
+                if let Some((cmd, run, ttl_ms)) =
+                    self.recording.take().and_then(|r| r.finish(*code))
+                {
+                    self.cache.insert(cmd, run, ttl_ms, Instant::now());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Answer a request with an error now and queue the error plus a failing exit behind it.
+    fn reject(&self, request_id: u32, message: String) -> Option<CommandMessage> {
+        self.queue(CommandMessage::Error {
+            request_id: Some(request_id),
+            message: message.clone(),
+        });
+        self.queue(CommandMessage::Exit {
+            request_id,
+            code: 1,
+        });
+        Some(CommandMessage::Error {
+            request_id: Some(request_id),
+            message,
+        })
+    }
+
     fn queue(&self, msg: CommandMessage) {
         let _ = self.outgoing_tx.send(msg);
     }
@@ -318,6 +468,7 @@ fn spawn_stream_reader<R>(
     request_id: u32,
     seq_counter: Arc<AtomicU32>,
     tx: Sender<CommandMessage>,
+    waker: LoopWaker,
 ) -> thread::JoinHandle<()>
 where
     R: Read + Send + 'static,
@@ -339,6 +490,7 @@ where
                     if tx.send(msg).is_err() {
                         break;
                     }
+                    waker.wake();
                 }
                 Err(_) => break,
             }
@@ -425,12 +577,27 @@ mod tests {
     use std::thread;
     use std::time::{Duration, Instant};
 
+    #[test]
+    fn bridge_records_frame_seq() {
+        let msg = CommandMessage::Heartbeat { request_id: None };
+        let mut bridge = CommandBridge::new();
+        let stamped = crate::payload::encode_command_frame_with_seq(&msg, Some(9)).unwrap();
+        bridge.ingest_line(&stamped).unwrap();
+        assert_eq!(bridge.last_seq(), Some(9));
+        bridge
+            .ingest_line(&encode_command_frame(&msg).unwrap())
+            .unwrap();
+        assert_eq!(bridge.last_seq(), None);
+    }
+
     #[test]
     fn bridge_parses_request() {
         let msg = CommandMessage::Request {
             request_id: 42,
             cmd: "uptime".into(),
             scratch_path: Some(format!("{}/tunnel/req42", crate::CACHE_DIR)),
+            otp: None,
+            sig: None,
         };
         let encoded = encode_command_frame(&msg).unwrap();
         let mut bridge = CommandBridge::default();
@@ -449,6 +616,8 @@ mod tests {
             request_id: 5,
             cmd: "whoami".into(),
             scratch_path: None,
+            otp: None,
+            sig: None,
         });
         assert!(matches!(
             response,
@@ -469,6 +638,137 @@ mod tests {
         assert!(saw_exit);
     }
 
+    #[test]
+    fn commands_see_the_client_terminal_size() {
+        let mut executor = CommandExecutor::new(vec!["printenv".into()]);
+        executor.set_terminal_size(132, 43);
+        let response = executor.handle_event(CommandEvent::Request {
+            request_id: 9,
+            cmd: "printenv COLUMNS LINES".into(),
+            scratch_path: None,
+            otp: None,
+            sig: None,
+        });
+        assert_eq!(response, Some(CommandMessage::Ack { request_id: 9 }));
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let mut output = Vec::new();
+        while std::time::Instant::now() < deadline {
+            match executor.next_outgoing() {
+                Some(CommandMessage::Chunk { data, .. }) => output.extend_from_slice(&data),
+                Some(CommandMessage::Exit { code, .. }) => {
+                    assert_eq!(code, 0);
+                    break;
+                }
+                Some(_) => {}
+                None => thread::sleep(std::time::Duration::from_millis(5)),
+            }
+        }
+        assert_eq!(String::from_utf8_lossy(&output), "132\n43\n");
+    }
+
+    #[test]
+    fn disabled_tunnel_rejects_every_request() {
+        let mut executor = CommandExecutor::new(vec![COMMAND_ALLOWLIST_OFF.into()]);
+        let response = executor.handle_event(CommandEvent::Request {
+            request_id: 5,
+            cmd: "true".into(),
+            scratch_path: None,
+            otp: None,
+            sig: None,
+        });
+        assert_eq!(
+            response,
+            Some(CommandMessage::Error {
+                request_id: Some(5),
+                message: TUNNEL_OFF_REJECTION.into(),
+            })
+        );
+        assert!(!executor.session_active);
+    }
+
+    #[test]
+    fn locked_executor_rejects_until_unlocked() {
+        let mut executor = CommandExecutor::new(Vec::new());
+        executor.set_locked(true);
+        let response = executor.handle_event(CommandEvent::Request {
+            request_id: 6,
+            cmd: "true".into(),
+            scratch_path: None,
+            otp: None,
+            sig: None,
+        });
+        assert_eq!(
+            response,
+            Some(CommandMessage::Error {
+                request_id: Some(6),
+                message: INTERLOCK_REJECTION.into(),
+            })
+        );
+        assert!(!executor.session_active);
+    }
+
+    #[test]
+    fn totp_executor_rejects_requests_without_a_valid_code() {
+        let mut executor = CommandExecutor::new(Vec::new());
+        executor.set_auth(CommandAuth::from_config(&crate::config::TunnelAuthConfig {
+            backend: crate::config::TunnelAuthBackend::Totp,
+            secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".into(),
+            ..Default::default()
+        }));
+        for (request_id, otp) in [(8, None), (9, Some("12345".to_string()))] {
+            let response = executor.handle_event(CommandEvent::Request {
+                request_id,
+                cmd: "true".into(),
+                scratch_path: None,
+                otp,
+                sig: None,
+            });
+            assert!(matches!(
+                response,
+                Some(CommandMessage::Error { message, .. }) if message.starts_with("command rejected: ")
+            ));
+        }
+        assert!(!executor.session_active);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hmac_executor_runs_signed_requests_and_rejects_altered_ones() {
+        let config = crate::config::TunnelAuthConfig {
+            backend: crate::config::TunnelAuthBackend::Hmac,
+            secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".into(),
+            ..Default::default()
+        };
+        let mut executor = CommandExecutor::new(Vec::new());
+        executor.set_auth(CommandAuth::from_config(&config));
+        let mut signer = super::super::tunnel_auth::HmacSigner::from_config(&config).unwrap();
+        let sig = signer.sign(
+            &SignedRequest {
+                cmd: "true",
+                scratch_path: None,
+            },
+            unix_ms(SystemTime::now()),
+        );
+        let request = |cmd: &str, sig: Option<String>| CommandEvent::Request {
+            request_id: 11,
+            cmd: cmd.into(),
+            scratch_path: None,
+            otp: None,
+            sig,
+        };
+
+        for event in [request("true", None), request("reboot", Some(sig.clone()))] {
+            assert!(matches!(
+                executor.handle_event(event),
+                Some(CommandMessage::Error { message, .. }) if message.starts_with("command rejected: ")
+            ));
+        }
+        assert!(matches!(
+            executor.handle_event(request("true", Some(sig))),
+            Some(CommandMessage::Ack { request_id: 11 })
+        ));
+    }
+
     #[cfg(unix)]
     #[test]
     fn command_executor_emits_exit_for_true() {
@@ -477,6 +777,8 @@ mod tests {
             request_id: 7,
             cmd: "true".into(),
             scratch_path: None,
+            otp: None,
+            sig: None,
         });
         assert!(matches!(
             response,
@@ -498,6 +800,71 @@ mod tests {
         assert!(exit_seen, "expected exit message");
     }
 
+    /// Stdout and exit code of `request_id`, once it exits.
+    fn drain(executor: &mut CommandExecutor, request_id: u32) -> (Vec<u8>, i32) {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut stdout = Vec::new();
+        while Instant::now() < deadline {
+            match executor.next_outgoing() {
+                Some(CommandMessage::Chunk {
+                    request_id: id,
+                    stream: CommandStream::Stdout,
+                    data,
+                    ..
+                }) if id == request_id => stdout.extend_from_slice(&data),
+                Some(CommandMessage::Exit {
+                    request_id: id,
+                    code,
+                }) if id == request_id => {
+                    return (stdout, code);
+                }
+                Some(_) => {}
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        panic!("request {request_id} did not exit");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cached_requests_replay_output_until_bypassed() {
+        let mut executor = CommandExecutor::new(Vec::new());
+        let request = |request_id| CommandEvent::Request {
+            request_id,
+            cmd: "date +%s%N".into(),
+            scratch_path: None,
+            otp: None,
+            sig: None,
+        };
+        let cached = CacheRequest {
+            ttl_ms: Some(60_000),
+            bypass: false,
+        };
+        executor.handle_event_with_cache(request(1), cached);
+        let (first, code) = drain(&mut executor, 1);
+        assert_eq!(code, 0);
+        assert!(!first.is_empty());
+
+        let response = executor.handle_event_with_cache(request(2), cached);
+        assert_eq!(response, Some(CommandMessage::Ack { request_id: 2 }));
+        assert_eq!(drain(&mut executor, 2), (first.clone(), 0));
+
+        let bypass = CacheRequest {
+            bypass: true,
+            ..cached
+        };
+        executor.handle_event_with_cache(request(3), bypass);
+        let (fresh, _) = drain(&mut executor, 3);
+        assert_ne!(fresh, first);
+        // The bypassing run refreshed the entry.
+        executor.handle_event_with_cache(request(4), cached);
+        assert_eq!(drain(&mut executor, 4).0, fresh);
+
+        // Without a TTL nothing is read from or written to the cache.
+        executor.handle_event(request(5));
+        assert_ne!(drain(&mut executor, 5).0, fresh);
+    }
+
     #[cfg(unix)]
     #[test]
     fn command_executor_returns_busy_when_active() {
@@ -506,11 +873,15 @@ mod tests {
             request_id: 8,
             cmd: "sleep 1".into(),
             scratch_path: None,
+            otp: None,
+            sig: None,
         });
         let busy = executor.handle_event(CommandEvent::Request {
             request_id: 9,
             cmd: "true".into(),
             scratch_path: None,
+            otp: None,
+            sig: None,
         });
         assert!(matches!(busy, Some(CommandMessage::Busy { request_id: 9 })));
         let deadline = Instant::now() + Duration::from_secs(2);
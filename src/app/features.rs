@@ -0,0 +1,264 @@
+//! Post-negotiation feature matrix: which optional features this session actually runs with.
+//!
+//! Built once per connect from the local config and the peer's capability bits, logged as a
+//! single line, and written to `/run/serial_lcd_cache/features.json` so external tooling can
+//! see why a feature is off without digging through the log.
+
+use super::{AppConfig, Logger};
+use crate::{cache_dir, negotiation::Capabilities, serial::arq::ArqMode};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const FEATURES_FILE: &str = "features.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    Compression,
+    Tunnel,
+    Heartbeat,
+    Arq,
+    Burst,
+    Anomaly,
+    Encryption,
+}
+
+impl Feature {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Feature::Compression => "compression",
+            Feature::Tunnel => "tunnel",
+            Feature::Heartbeat => "heartbeat",
+            Feature::Arq => "arq",
+            Feature::Burst => "burst",
+            Feature::Anomaly => "anomaly",
+            Feature::Encryption => "encryption",
+        }
+    }
+}
+
+/// One row of the matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FeatureState {
+    pub feature: Feature,
+    /// Config explicitly asks for this feature; losing it is worth a warning.
+    pub requested: bool,
+    /// Enabled on this side.
+    pub local: bool,
+    /// Advertised by the peer during negotiation.
+    pub peer: bool,
+    pub active: bool,
+}
+
+impl FeatureState {
+    fn new(feature: Feature, requested: bool, local: bool, peer: bool) -> Self {
+        Self {
+            feature,
+            requested,
+            local,
+            peer,
+            active: local && peer,
+        }
+    }
+
+    fn label(&self) -> String {
+        let name = self.feature.as_str();
+        match (self.local, self.peer) {
+            (true, true) => format!("{name}=on"),
+            (false, _) => format!("{name}=off[config]"),
+            (true, false) => format!("{name}=off[peer]"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeatureMatrix {
+    /// `false` when the peer fell back to legacy LCD-only mode without a handshake.
+    pub negotiated: bool,
+    /// This node's identity as sent in `hello`, so scripts can tell units apart.
+    pub node_id: u32,
+    pub install_id: String,
+    pub features: Vec<FeatureState>,
+}
+
+impl FeatureMatrix {
+    /// Combine local config with the peer's capabilities (`None` after a legacy fallback).
+    pub fn negotiate(config: &AppConfig, remote: Option<&Capabilities>) -> Self {
+        let peer = remote.cloned().unwrap_or_default();
+        let tunnel_requested = config.serialsh || !config.tail_allowlist.is_empty();
+        Self {
+            negotiated: remote.is_some(),
+            node_id: config.negotiation.effective_node_id(),
+            install_id: config.negotiation.install_id.clone(),
+            features: vec![
+                FeatureState::new(
+                    Feature::Compression,
+                    config.compression_enabled,
+                    config.compression_enabled,
+                    peer.supports_compression,
+                ),
+                FeatureState::new(
+                    Feature::Tunnel,
+                    tunnel_requested,
+                    true,
+                    peer.supports_tunnel,
+                ),
+                FeatureState::new(Feature::Heartbeat, false, true, peer.supports_heartbeat),
+                FeatureState::new(
+                    Feature::Arq,
+                    config.arq.mode == ArqMode::On,
+                    config.arq.mode != ArqMode::Off,
+                    peer.supports_arq,
+                ),
+                FeatureState::new(
+                    Feature::Burst,
+                    config.burst.enabled,
+                    config.burst.enabled,
+                    peer.supports_burst,
+                ),
+                FeatureState::new(
+                    Feature::Anomaly,
+                    config.anomaly.push,
+                    config.anomaly.push,
+                    peer.supports_anomaly,
+                ),
+                FeatureState::new(
+                    Feature::Encryption,
+                    !config.negotiation.psk.is_empty(),
+                    !config.negotiation.psk.is_empty(),
+                    peer.supports_crypto,
+                ),
+            ],
+        }
+    }
+
+    pub fn active(&self, feature: Feature) -> bool {
+        self.features
+            .iter()
+            .any(|state| state.feature == feature && state.active)
+    }
+
+    /// Whether the peer advertised `feature`, regardless of local config.
+    pub fn peer_supports(&self, feature: Feature) -> bool {
+        self.features
+            .iter()
+            .any(|state| state.feature == feature && state.peer)
+    }
+
+    /// Features the config asked for that the peer cannot provide.
+    pub fn downgrades(&self) -> impl Iterator<Item = &FeatureState> {
+        self.features
+            .iter()
+            .filter(|state| state.requested && state.local && !state.peer)
+    }
+
+    pub fn summary(&self) -> String {
+        let labels = self
+            .features
+            .iter()
+            .map(FeatureState::label)
+            .collect::<Vec<_>>()
+            .join(" ");
+        if self.negotiated {
+            format!("features: {labels}")
+        } else {
+            format!("features (legacy peer, no handshake): {labels}")
+        }
+    }
+
+    /// Log the matrix and any downgrades, then publish it for external tooling.
+    pub fn announce(&self, logger: &Logger) {
+        logger.info(self.summary());
+        for state in self.downgrades() {
+            logger.warn(format!(
+                "{}: enabled in config but the peer does not support it; running without it",
+                state.feature.as_str()
+            ));
+        }
+        if let Err(err) = self.write_snapshot(&features_path()) {
+            logger.debug(format!("feature matrix write failed: {err}"));
+        }
+    }
+
+    fn write_snapshot(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        fs::write(path, format!("{json}\n"))
+    }
+}
+
+fn features_path() -> PathBuf {
+    cache_dir().join(FEATURES_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caps(compression: bool, tunnel: bool) -> Capabilities {
+        Capabilities {
+            supports_tunnel: tunnel,
+            supports_compression: compression,
+            supports_heartbeat: true,
+            supports_arq: false,
+            supports_burst: false,
+            supports_anomaly: false,
+            supports_crypto: false,
+        }
+    }
+
+    #[test]
+    fn compression_requested_but_missing_is_a_downgrade() {
+        let config = AppConfig {
+            compression_enabled: true,
+            ..AppConfig::default()
+        };
+        let matrix = FeatureMatrix::negotiate(&config, Some(&caps(false, true)));
+        assert!(!matrix.active(Feature::Compression));
+        assert!(matrix.active(Feature::Tunnel));
+        assert!(matrix.active(Feature::Heartbeat));
+        let downgraded: Vec<_> = matrix.downgrades().map(|s| s.feature).collect();
+        assert_eq!(downgraded, vec![Feature::Compression]);
+        assert!(matrix.summary().contains("compression=off[peer]"));
+    }
+
+    #[test]
+    fn unrequested_features_do_not_warn() {
+        let matrix = FeatureMatrix::negotiate(&AppConfig::default(), Some(&caps(false, false)));
+        assert!(!matrix.active(Feature::Tunnel));
+        assert_eq!(matrix.downgrades().count(), 0);
+        assert!(matrix.summary().contains("compression=off[config]"));
+    }
+
+    #[test]
+    fn legacy_peer_disables_everything_and_flags_tunnel_requests() {
+        let config = AppConfig {
+            serialsh: true,
+            ..AppConfig::default()
+        };
+        let matrix = FeatureMatrix::negotiate(&config, None);
+        assert!(!matrix.negotiated);
+        assert!(matrix.features.iter().all(|state| !state.active));
+        let downgraded: Vec<_> = matrix.downgrades().map(|s| s.feature).collect();
+        assert_eq!(downgraded, vec![Feature::Tunnel]);
+        assert!(matrix.summary().starts_with("features (legacy peer"));
+    }
+
+    #[test]
+    fn snapshot_is_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FEATURES_FILE);
+        let matrix = FeatureMatrix::negotiate(&AppConfig::default(), Some(&caps(true, true)));
+        matrix.write_snapshot(&path).unwrap();
+        let parsed: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(parsed["negotiated"], true);
+        assert_eq!(parsed["node_id"], 1);
+        assert_eq!(parsed["features"][0]["feature"], "compression");
+    }
+}
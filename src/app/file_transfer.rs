@@ -1,63 +1,699 @@
-use crate::{Error, Result};
-use std::path::Path;
+//! File transfer over the serial link (`lifelinetty transfer`).
+//!
+//! The sender offers a file with a `file_offer` line carrying its name, size and CRC32, then
+//! streams it as hex `file_chunk` lines, each with its offset and a CRC-16. The receiver acks
+//! every chunk with the next offset it wants, or naks a damaged or misplaced one, and checks the
+//! whole file against the offer before moving it into place. Partial files are staged under
+//! `CACHE_DIR/transfers` next to a manifest of the offer, so re-running both ends after a dropped
+//! link resumes from the bytes already on disk. Peers without lifelinetty use YMODEM instead
+//! (see [`super::ymodem`]), which cannot resume.
 
-/// File transfer manager placeholder for Milestone C.
-/// This module provides a minimal in-repo API to build tests and further
-/// implementation around chunking, checksums and resume functionality.
+use super::{
+    ymodem::{self, crc16, YmodemHeader},
+    AppConfig,
+};
+use crate::{
+    cache_dir,
+    cli::{RunOptions, TransferDirection, TransferOptions, TransferProtocol},
+    config::Config,
+    serial::{LineIo, SerialPort},
+    Error, ExitCode, Result,
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write as _,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// File bytes per `file_chunk`; hex-encoded they keep the line under `MAX_FRAME_BYTES`.
+pub const CHUNK_BYTES: usize = 192;
+/// How long the sender waits for each reply before repeating its line.
+const REPLY_TIMEOUT_MS: u64 = 2_000;
+/// Repeats of one line, or naks in a row, before the transfer is abandoned.
+const MAX_RETRIES: u32 = 10;
+/// How long the receiver waits for the sender's next line.
+const IDLE_TIMEOUT_MS: u64 = 120_000;
+/// Per-byte timeout for YMODEM replies.
+const YMODEM_TIMEOUT_MS: u64 = 3_000;
+const STAGING_DIR: &str = "transfers";
+
+/// What the sender announces, and what a resume manifest records.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileOffer {
+    pub name: String,
+    /// Unknown for XMODEM.
+    pub size: Option<u64>,
+    /// Whole-file CRC32; YMODEM senders only provide per-block CRC-16s.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<u32>,
+}
+
+/// Lines of the chunk protocol.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)] // The variant names are the wire `type` tags.
+enum ChunkMessage {
+    FileOffer(FileOffer),
+    /// Reply to an offer: the offset to start from, non-zero when resuming.
+    FileAccept {
+        offset: u64,
+    },
+    FileChunk {
+        offset: u64,
+        data: String,
+        crc16: u16,
+    },
+    FileAck {
+        offset: u64,
+    },
+    /// The chunk was damaged or out of place; resend from `offset`.
+    FileNak {
+        offset: u64,
+    },
+    FileDone,
+    FileComplete,
+    FileError {
+        reason: String,
+    },
+}
+
+/// Stages incoming files and describes outgoing ones.
 pub struct FileTransferManager {
-    /// path to a cache directory (should be CACHE_DIR in production)
-    pub cache_dir: String,
+    staging: PathBuf,
 }
 
 impl FileTransferManager {
-    pub fn new(cache_dir: &str) -> Self {
+    /// `cache_dir` should be `CACHE_DIR` in production; files are staged in its `transfers` dir.
+    pub fn new(cache_dir: impl AsRef<Path>) -> Self {
         Self {
-            cache_dir: cache_dir.to_string(),
+            staging: cache_dir.as_ref().join(STAGING_DIR),
+        }
+    }
+
+    /// Describe a local file for the offer.
+    pub fn prepare_send(&self, path: &Path) -> Result<FileOffer> {
+        if !path.is_file() {
+            return Err(Error::Parse(format!("file not found: {}", path.display())));
+        }
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| Error::InvalidArgs(format!("{} has no usable name", path.display())))?;
+        let mut file = File::open(path)?;
+        let mut hasher = crc32fast::Hasher::new();
+        let mut buf = [0u8; 8192];
+        let mut size = 0u64;
+        loop {
+            let read = file.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+            size += read as u64;
+        }
+        Ok(FileOffer {
+            name: name.to_string(),
+            size: Some(size),
+            crc32: Some(hasher.finalize()),
+        })
+    }
+
+    /// Open the staging file for `offer`. With `resume`, a partial file left by the same offer
+    /// is kept and the returned file continues after it.
+    pub fn begin_receive(&self, offer: &FileOffer, resume: bool) -> Result<IncomingFile> {
+        let name = safe_file_name(&offer.name)?;
+        fs::create_dir_all(&self.staging)?;
+        let part = self.staging.join(format!("{name}.part"));
+        let manifest = self.staging.join(format!("{name}.manifest.json"));
+        let resumable = resume
+            && fs::read_to_string(&manifest)
+                .ok()
+                .and_then(|text| serde_json::from_str::<FileOffer>(&text).ok())
+                .is_some_and(|saved| saved == *offer);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(!resumable)
+            .open(&part)?;
+        let mut received = file.seek(SeekFrom::End(0))?;
+        if offer.size.is_some_and(|size| received > size) {
+            file.set_len(0)?;
+            received = file.seek(SeekFrom::Start(0))?;
+        }
+        if !resumable {
+            let text =
+                serde_json::to_string(offer).map_err(|err| Error::Parse(format!("json: {err}")))?;
+            fs::write(&manifest, text)?;
+        }
+        Ok(IncomingFile {
+            offer: offer.clone(),
+            name,
+            part,
+            manifest,
+            file,
+            received,
+        })
+    }
+}
+
+/// A file being received into the staging directory. Writes append; [`IncomingFile::finish`]
+/// verifies it and moves it out.
+pub struct IncomingFile {
+    offer: FileOffer,
+    name: String,
+    part: PathBuf,
+    manifest: PathBuf,
+    file: File,
+    received: u64,
+}
+
+impl IncomingFile {
+    /// Bytes on disk so far, which is also the offset of the next chunk.
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Check size and CRC32 against the offer and move the file into `dest_dir`. A short file
+    /// stays staged for a resume; a corrupt one is discarded.
+    pub fn finish(self, dest_dir: &Path) -> Result<PathBuf> {
+        self.file.sync_all()?;
+        if let Some(size) = self.offer.size.filter(|size| *size != self.received) {
+            return Err(Error::Parse(format!(
+                "{}: received {} of {size} bytes",
+                self.name, self.received
+            )));
+        }
+        if let Some(expected) = self.offer.crc32 {
+            let actual = crc32fast::hash(&fs::read(&self.part)?);
+            if actual != expected {
+                let _ = fs::remove_file(&self.part);
+                let _ = fs::remove_file(&self.manifest);
+                return Err(Error::ChecksumMismatch);
+            }
+        }
+        fs::create_dir_all(dest_dir)?;
+        let dest = dest_dir.join(&self.name);
+        // The staging dir is usually on tmpfs, so the rename may have to become a copy.
+        if fs::rename(&self.part, &dest).is_err() {
+            fs::copy(&self.part, &dest)?;
+            fs::remove_file(&self.part)?;
+        }
+        let _ = fs::remove_file(&self.manifest);
+        Ok(dest)
+    }
+}
+
+impl Write for IncomingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self
+            .offer
+            .size
+            .is_some_and(|size| self.received + buf.len() as u64 > size)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "more data than the offer announced",
+            ));
+        }
+        let written = self.file.write(buf)?;
+        self.received += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Offer `path` and stream it, starting wherever the receiver already is. `progress` sees the
+/// acknowledged offset and the file size.
+pub fn send_chunked<T: LineIo>(
+    io: &mut T,
+    manager: &FileTransferManager,
+    path: &Path,
+    progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<FileOffer> {
+    let offer = manager.prepare_send(path)?;
+    let size = offer.size.unwrap_or_default();
+    let mut file = File::open(path)?;
+    let mut offset = exchange(
+        io,
+        &ChunkMessage::FileOffer(offer.clone()),
+        |reply| match reply {
+            ChunkMessage::FileAccept { offset } => Some(offset),
+            _ => None,
+        },
+    )?;
+
+    let mut buf = [0u8; CHUNK_BYTES];
+    let mut naks = 0;
+    while offset < size {
+        progress(offset, offer.size);
+        file.seek(SeekFrom::Start(offset))?;
+        let len = CHUNK_BYTES.min((size - offset) as usize);
+        file.read_exact(&mut buf[..len])?;
+        let chunk = ChunkMessage::FileChunk {
+            offset,
+            data: to_hex(&buf[..len]),
+            crc16: crc16(&buf[..len]),
+        };
+        let next = exchange(io, &chunk, |reply| match reply {
+            ChunkMessage::FileAck { offset } | ChunkMessage::FileNak { offset } => Some(offset),
+            _ => None,
+        })?;
+        naks = if next > offset { 0 } else { naks + 1 };
+        if naks > MAX_RETRIES {
+            return Err(Error::Parse(format!(
+                "peer refused the chunk at {offset} {MAX_RETRIES} times"
+            )));
+        }
+        if next > size {
+            return Err(Error::Parse(format!("peer asked for offset {next}")));
+        }
+        offset = next;
+    }
+    progress(size, offer.size);
+    exchange(io, &ChunkMessage::FileDone, |reply| match reply {
+        ChunkMessage::FileComplete => Some(()),
+        _ => None,
+    })?;
+    Ok(offer)
+}
+
+/// Wait for one offered file and write it to `dest_dir`.
+pub fn receive_chunked<T: LineIo>(
+    io: &mut T,
+    manager: &FileTransferManager,
+    dest_dir: &Path,
+    idle_timeout: Duration,
+    progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<PathBuf> {
+    let mut incoming: Option<IncomingFile> = None;
+    let mut last_heard = Instant::now();
+    let mut line = String::new();
+    loop {
+        let message = match io.read_message_line(&mut line) {
+            Ok(0) | Err(Error::Parse(_)) => None,
+            Ok(_) => serde_json::from_str::<ChunkMessage>(line.trim()).ok(),
+            Err(err) => return Err(err),
+        };
+        let Some(message) = message else {
+            if last_heard.elapsed() > idle_timeout {
+                return Err(Error::Parse(format!(
+                    "no transfer traffic for {}s",
+                    idle_timeout.as_secs()
+                )));
+            }
+            continue;
+        };
+        last_heard = Instant::now();
+
+        let reply = match message {
+            ChunkMessage::FileOffer(offer) => {
+                // A repeated offer, or one from a restarted sender, reopens the staging file.
+                incoming = None;
+                match manager.begin_receive(&offer, true) {
+                    Ok(file) => {
+                        let offset = file.received();
+                        progress(offset, offer.size);
+                        incoming = Some(file);
+                        ChunkMessage::FileAccept { offset }
+                    }
+                    Err(err) => ChunkMessage::FileError {
+                        reason: err.to_string(),
+                    },
+                }
+            }
+            ChunkMessage::FileChunk {
+                offset,
+                data,
+                crc16: crc,
+            } => match incoming.as_mut() {
+                Some(file) => {
+                    let expected = file.received();
+                    match from_hex(&data) {
+                        Some(bytes) if offset == expected && crc16(&bytes) == crc => {
+                            match file.write_all(&bytes) {
+                                Ok(()) => {
+                                    progress(file.received(), file.offer.size);
+                                    ChunkMessage::FileAck {
+                                        offset: file.received(),
+                                    }
+                                }
+                                Err(err) => ChunkMessage::FileError {
+                                    reason: err.to_string(),
+                                },
+                            }
+                        }
+                        _ => ChunkMessage::FileNak { offset: expected },
+                    }
+                }
+                None => no_offer(),
+            },
+            ChunkMessage::FileDone => match incoming.take() {
+                Some(file) => match file.finish(dest_dir) {
+                    Ok(path) => {
+                        send_message(io, &ChunkMessage::FileComplete)?;
+                        return Ok(path);
+                    }
+                    Err(err) => ChunkMessage::FileError {
+                        reason: err.to_string(),
+                    },
+                },
+                None => no_offer(),
+            },
+            _ => continue,
+        };
+        send_message(io, &reply)?;
+    }
+}
+
+/// `lifelinetty transfer`: copy one file over the serial port.
+pub fn run(opts: TransferOptions) -> Result<()> {
+    let cfg = Config::load_or_default().map_err(|err| err.exit_with(ExitCode::Config))?;
+    let merged = AppConfig::from_sources(
+        cfg,
+        RunOptions {
+            device: opts.device.clone(),
+            baud: opts.baud,
+            ..RunOptions::default()
+        },
+    );
+    let mut serial = SerialPort::connect(&merged.device, merged.serial_options())
+        .map_err(|err| err.exit_with(ExitCode::SerialUnavailable))?;
+    let manager = FileTransferManager::new(cache_dir());
+    let dest_dir = PathBuf::from(opts.dir.as_deref().unwrap_or("."));
+    let bar = ProgressBar::new(0);
+    if let Ok(style) =
+        ProgressStyle::with_template("{msg} {bytes}/{total_bytes} [{wide_bar}] {eta}")
+    {
+        bar.set_style(style);
+    }
+    let mut progress = |done: u64, total: Option<u64>| {
+        if let Some(total) = total {
+            bar.set_length(total);
+        }
+        bar.set_position(done);
+    };
+    let ymodem_timeout = Duration::from_millis(YMODEM_TIMEOUT_MS);
+
+    match (&opts.direction, opts.protocol) {
+        (TransferDirection::Send { file }, TransferProtocol::Chunks) => {
+            bar.set_message(file.clone());
+            let offer = send_chunked(&mut serial, &manager, Path::new(file), &mut progress)?;
+            bar.finish();
+            eprintln!("sent {} ({} bytes)", offer.name, offer.size.unwrap_or(0));
+        }
+        (TransferDirection::Send { file }, TransferProtocol::Ymodem) => {
+            let offer = manager.prepare_send(Path::new(file))?;
+            bar.set_message(file.clone());
+            bar.set_length(offer.size.unwrap_or(0));
+            let header = YmodemHeader {
+                name: offer.name.clone(),
+                size: offer.size,
+            };
+            let mut data = File::open(file)?;
+            let mut stream = serial.borrow_stream()?;
+            ymodem::send(
+                &mut stream,
+                &header,
+                &mut data,
+                ymodem_timeout,
+                &mut |sent| progress(sent, None),
+            )?;
+            bar.finish();
+            eprintln!("sent {} over ymodem", offer.name);
+        }
+        (TransferDirection::Receive, TransferProtocol::Chunks) => {
+            let path = receive_chunked(
+                &mut serial,
+                &manager,
+                &dest_dir,
+                Duration::from_millis(IDLE_TIMEOUT_MS),
+                &mut progress,
+            )?;
+            bar.finish();
+            eprintln!("received {}", path.display());
+        }
+        (TransferDirection::Receive, TransferProtocol::Ymodem) => {
+            let mut stream = serial.borrow_stream()?;
+            let (_, incoming) = ymodem::receive(&mut stream, ymodem_timeout, |header| {
+                let offer = FileOffer {
+                    name: header.name.clone(),
+                    size: header.size,
+                    crc32: None,
+                };
+                manager.begin_receive(&offer, false)
+            })?;
+            let path = incoming.finish(&dest_dir)?;
+            eprintln!("received {}", path.display());
         }
     }
+    Ok(())
+}
 
-    /// Prepare sending a local file by validating it exists and returning a
-    /// transfer id (stubbed in this skeleton).
-    pub fn prepare_send(&self, path: &str) -> Result<String> {
-        if !Path::new(path).exists() {
-            return Err(Error::Parse(format!("file not found: {path}")));
+/// Send `message` until `pick` accepts a reply, resending after each silent `REPLY_TIMEOUT_MS`.
+/// A `file_error` from the peer ends the transfer.
+fn exchange<T: LineIo, R>(
+    io: &mut T,
+    message: &ChunkMessage,
+    pick: impl Fn(ChunkMessage) -> Option<R>,
+) -> Result<R> {
+    let mut line = String::new();
+    for _ in 0..=MAX_RETRIES {
+        send_message(io, message)?;
+        let deadline = Instant::now() + Duration::from_millis(REPLY_TIMEOUT_MS);
+        while Instant::now() < deadline {
+            let reply = match io.read_message_line(&mut line) {
+                Ok(0) | Err(Error::Parse(_)) => continue,
+                Ok(_) => serde_json::from_str::<ChunkMessage>(line.trim()),
+                Err(err) => return Err(err),
+            };
+            match reply {
+                Ok(ChunkMessage::FileError { reason }) => {
+                    return Err(Error::Parse(format!("peer refused the transfer: {reason}")))
+                }
+                Ok(reply) => {
+                    if let Some(picked) = pick(reply) {
+                        return Ok(picked);
+                    }
+                }
+                Err(_) => {}
+            }
         }
-        // Real implementation will stage the file into cache and create a resume manifest
-        Ok("transfer-id-stub".to_string())
     }
+    Err(Error::Parse(format!(
+        "peer did not answer after {MAX_RETRIES} retries"
+    )))
+}
 
-    /// Accept a chunk into the receive pipeline (stubbed). Real code will
-    /// validate chunk id, crc and append to a temporary file in the cache dir.
-    pub fn receive_chunk(
-        &self,
-        _transfer_id: &str,
-        _chunk_idx: u64,
-        _payload: &[u8],
-    ) -> Result<()> {
-        Ok(())
+fn send_message<T: LineIo>(io: &mut T, message: &ChunkMessage) -> Result<()> {
+    let line =
+        serde_json::to_string(message).map_err(|err| Error::Parse(format!("json: {err}")))?;
+    io.send_command_line(&line)
+}
+
+fn no_offer() -> ChunkMessage {
+    ChunkMessage::FileError {
+        reason: "no file has been offered".into(),
+    }
+}
+
+/// The offered name without any directory part; the sender does not choose where it lands.
+pub(super) fn safe_file_name(name: &str) -> Result<String> {
+    let base = Path::new(name)
+        .file_name()
+        .and_then(|base| base.to_str())
+        .filter(|base| !base.starts_with('.') && !base.contains('\0'))
+        .ok_or_else(|| Error::InvalidArgs(format!("refusing file name '{name}'")))?;
+    Ok(base.to_string())
+}
+
+pub(super) fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut text, byte| {
+            let _ = write!(text, "{byte:02x}");
+            text
+        })
+}
+
+pub(super) fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
     }
+    (0..text.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(text.get(idx..idx + 2)?, 16).ok())
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs::File;
+    use std::{
+        sync::mpsc::{channel, Receiver, Sender},
+        thread,
+    };
     use tempfile::tempdir;
 
+    /// One end of an in-memory line link.
+    struct LinePipe {
+        tx: Sender<String>,
+        rx: Receiver<String>,
+    }
+
+    impl LineIo for LinePipe {
+        fn send_command_line(&mut self, line: &str) -> Result<()> {
+            let _ = self.tx.send(line.to_string());
+            Ok(())
+        }
+
+        fn read_message_line(&mut self, buf: &mut String) -> Result<usize> {
+            match self.rx.recv_timeout(Duration::from_millis(10)) {
+                Ok(line) => {
+                    *buf = line;
+                    Ok(buf.len())
+                }
+                Err(_) => Ok(0),
+            }
+        }
+    }
+
+    fn pipe_pair() -> (LinePipe, LinePipe) {
+        let (a_tx, b_rx) = channel();
+        let (b_tx, a_rx) = channel();
+        (
+            LinePipe { tx: a_tx, rx: a_rx },
+            LinePipe { tx: b_tx, rx: b_rx },
+        )
+    }
+
     #[test]
     fn prepare_send_rejects_missing_file() {
         let m = FileTransferManager::new("/tmp");
-        let err = m.prepare_send("/path/does/not/exist").unwrap_err();
+        let err = m
+            .prepare_send(Path::new("/path/does/not/exist"))
+            .unwrap_err();
         assert!(format!("{err}").contains("file not found"));
     }
 
     #[test]
-    fn prepare_send_accepts_existing_file() {
+    fn prepare_send_describes_the_file() {
         let dir = tempdir().unwrap();
         let fpath = dir.path().join("f.txt");
-        File::create(&fpath).unwrap();
-        let m = FileTransferManager::new(dir.path().to_str().unwrap());
-        let id = m.prepare_send(fpath.to_str().unwrap()).unwrap();
-        assert_eq!(id, "transfer-id-stub");
+        fs::write(&fpath, b"123456789").unwrap();
+        let m = FileTransferManager::new(dir.path());
+        let offer = m.prepare_send(&fpath).unwrap();
+        assert_eq!(
+            offer,
+            FileOffer {
+                name: "f.txt".into(),
+                size: Some(9),
+                crc32: Some(0xcbf4_3926),
+            }
+        );
+    }
+
+    #[test]
+    fn chunked_transfer_resumes_from_the_staged_part() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("log.tar");
+        let data: Vec<u8> = (0..1_000u32).map(|n| (n * 7 % 256) as u8).collect();
+        fs::write(&source, &data).unwrap();
+        let manager = FileTransferManager::new(dir.path().join("cache"));
+
+        // An earlier attempt got the first 500 bytes across.
+        let offer = manager.prepare_send(&source).unwrap();
+        let mut partial = manager.begin_receive(&offer, true).unwrap();
+        partial.write_all(&data[..500]).unwrap();
+        drop(partial);
+
+        let (mut sender_end, mut receiver_end) = pipe_pair();
+        let cache = dir.path().join("cache");
+        let sent_from = source.clone();
+        let sender = thread::spawn(move || {
+            let manager = FileTransferManager::new(cache);
+            let mut offsets = Vec::new();
+            send_chunked(&mut sender_end, &manager, &sent_from, &mut |done, _| {
+                offsets.push(done)
+            })
+            .map(|_| offsets)
+        });
+
+        let dest = dir.path().join("inbox");
+        let path = receive_chunked(
+            &mut receiver_end,
+            &manager,
+            &dest,
+            Duration::from_secs(5),
+            &mut |_, _| {},
+        )
+        .unwrap();
+        let offsets = sender.join().unwrap().unwrap();
+        assert_eq!(offsets.first(), Some(&500));
+        assert_eq!(path, dest.join("log.tar"));
+        assert_eq!(fs::read(&path).unwrap(), data);
+        assert!(fs::read_dir(dir.path().join("cache/transfers"))
+            .unwrap()
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn damaged_chunks_are_nakked_and_bad_files_discarded() {
+        let dir = tempdir().unwrap();
+        let manager = FileTransferManager::new(dir.path());
+        let offer = FileOffer {
+            name: "../../etc/motd".into(),
+            size: Some(4),
+            crc32: Some(crc32fast::hash(b"good")),
+        };
+        let (mut peer, mut receiver_end) = pipe_pair();
+        let chunk = |data: &[u8], crc| ChunkMessage::FileChunk {
+            offset: 0,
+            data: to_hex(data),
+            crc16: crc,
+        };
+        for message in [
+            ChunkMessage::FileOffer(offer),
+            chunk(b"good", 0),
+            chunk(b"bad!", crc16(b"bad!")),
+            ChunkMessage::FileDone,
+        ] {
+            send_message(&mut peer, &message).unwrap();
+        }
+        let err = receive_chunked(
+            &mut receiver_end,
+            &manager,
+            dir.path(),
+            Duration::from_millis(100),
+            &mut |_, _| {},
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("no transfer traffic"));
+
+        let replies: Vec<ChunkMessage> = peer
+            .rx
+            .try_iter()
+            .map(|line| serde_json::from_str(&line).unwrap())
+            .collect();
+        assert_eq!(replies[0], ChunkMessage::FileAccept { offset: 0 });
+        assert_eq!(replies[1], ChunkMessage::FileNak { offset: 0 });
+        assert_eq!(replies[2], ChunkMessage::FileAck { offset: 4 });
+        assert_eq!(
+            replies[3],
+            ChunkMessage::FileError {
+                reason: "checksum mismatch".into()
+            }
+        );
+        // The name lost its directories, and the corrupt file was not kept for a resume.
+        assert!(!dir.path().join("transfers/motd.part").exists());
+        assert!(!dir.path().join("motd").exists());
     }
 }
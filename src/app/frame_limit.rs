@@ -0,0 +1,198 @@
+//! Incoming frame limit.
+//!
+//! `[render].max_frames_per_sec` caps how many payload frames per second reach the page queue.
+//! Up to one second's worth may arrive back to back; past that, frames wait for the budget to
+//! refill. While waiting, a newer frame replaces the held one with the same page key, so a sender
+//! that floods the link costs one clear and redraw per key instead of one per frame.
+
+use crate::payload::Priority;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// What a held frame is replaced by: a page set replaces the previous page set, and a single
+/// frame replaces the previous single frame of the same priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageKey {
+    PageSet,
+    Frame(Priority),
+}
+
+/// What [`FrameLimiter::admit`] did with a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    /// Ingest it now.
+    Now,
+    /// Held until [`FrameLimiter::release`] hands it back.
+    Held,
+    /// Held in place of an older frame with the same page key, which is dropped.
+    Replaced,
+}
+
+pub struct FrameLimiter {
+    /// Frames per second; 0 lets every frame through.
+    rate: u32,
+    tokens: f64,
+    refilled_at: Instant,
+    /// Frames over the limit, oldest first, at most one per key.
+    held: Vec<(PageKey, String)>,
+}
+
+impl FrameLimiter {
+    pub fn new(max_frames_per_sec: u32, now: Instant) -> Self {
+        Self {
+            rate: max_frames_per_sec,
+            tokens: f64::from(max_frames_per_sec),
+            refilled_at: now,
+            held: Vec::new(),
+        }
+    }
+
+    pub fn admit(&mut self, line: &str, now: Instant) -> Admission {
+        if self.rate == 0 {
+            return Admission::Now;
+        }
+        self.refill(now);
+        // Frames already waiting go first, so nothing overtakes them.
+        if self.held.is_empty() && self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Admission::Now;
+        }
+        let key = page_key(line);
+        match self.held.iter_mut().find(|(held, _)| *held == key) {
+            Some((_, held)) => {
+                *held = line.to_string();
+                Admission::Replaced
+            }
+            None => {
+                self.held.push((key, line.to_string()));
+                Admission::Held
+            }
+        }
+    }
+
+    /// The oldest held frame, once the budget allows another one.
+    pub fn release(&mut self, now: Instant) -> Option<String> {
+        if self.held.is_empty() {
+            return None;
+        }
+        if self.rate > 0 {
+            self.refill(now);
+            if self.tokens < 1.0 {
+                return None;
+            }
+            self.tokens -= 1.0;
+        }
+        Some(self.held.remove(0).1)
+    }
+
+    /// How long until a held frame can be released; `None` when nothing is held.
+    pub fn until_release(&self, now: Instant) -> Option<Duration> {
+        if self.held.is_empty() {
+            return None;
+        }
+        if self.rate == 0 {
+            return Some(Duration::ZERO);
+        }
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        let missing = 1.0 - (self.tokens + elapsed * f64::from(self.rate));
+        Some(Duration::from_secs_f64(
+            (missing / f64::from(self.rate)).max(0.0),
+        ))
+    }
+
+    /// Apply a new limit; held frames stay queued and drain at the new rate.
+    pub fn set_rate(&mut self, max_frames_per_sec: u32, now: Instant) {
+        if max_frames_per_sec == self.rate {
+            return;
+        }
+        let held = std::mem::take(&mut self.held);
+        *self = Self::new(max_frames_per_sec, now);
+        self.held = held;
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * f64::from(self.rate)).min(f64::from(self.rate));
+        self.refilled_at = now;
+    }
+}
+
+/// Frames that do not parse as JSON (key=value payloads, compressed envelopes) count as normal
+/// single frames.
+fn page_key(line: &str) -> PageKey {
+    let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(line) else {
+        return PageKey::Frame(Priority::Normal);
+    };
+    if fields.contains_key("pages") {
+        return PageKey::PageSet;
+    }
+    let priority = fields
+        .get("priority")
+        .and_then(Value::as_str)
+        .and_then(|name| name.parse().ok())
+        .unwrap_or_default();
+    PageKey::Frame(priority)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(line1: &str) -> String {
+        format!(r#"{{"schema_version":1,"line1":"{line1}","line2":""}}"#)
+    }
+
+    #[test]
+    fn bursts_past_the_budget_keep_only_the_newest_frame_per_key() {
+        let start = Instant::now();
+        let mut limiter = FrameLimiter::new(2, start);
+        assert_eq!(limiter.admit(&frame("a"), start), Admission::Now);
+        assert_eq!(limiter.admit(&frame("b"), start), Admission::Now);
+        assert_eq!(limiter.admit(&frame("c"), start), Admission::Held);
+        let alert = r#"{"schema_version":1,"line1":"DOWN","line2":"","priority":"urgent"}"#;
+        assert_eq!(limiter.admit(alert, start), Admission::Held);
+        assert_eq!(limiter.admit(&frame("d"), start), Admission::Replaced);
+
+        assert_eq!(limiter.release(start), None);
+        assert_eq!(
+            limiter.until_release(start),
+            Some(Duration::from_millis(500))
+        );
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.release(later), Some(frame("d")));
+        assert_eq!(limiter.release(later), None);
+        let later = later + Duration::from_millis(500);
+        assert_eq!(limiter.release(later).as_deref(), Some(alert));
+        assert_eq!(limiter.until_release(later), None);
+    }
+
+    #[test]
+    fn new_frames_queue_behind_held_ones() {
+        let start = Instant::now();
+        let mut limiter = FrameLimiter::new(1, start);
+        assert_eq!(limiter.admit(&frame("a"), start), Admission::Now);
+        assert_eq!(limiter.admit(&frame("b"), start), Admission::Held);
+        // The budget refilled, but "b" is still waiting, so "c" replaces it rather than jumping
+        // ahead.
+        let later = start + Duration::from_secs(1);
+        assert_eq!(limiter.admit(&frame("c"), later), Admission::Replaced);
+        assert_eq!(limiter.release(later), Some(frame("c")));
+    }
+
+    #[test]
+    fn zero_rate_lets_everything_through() {
+        let start = Instant::now();
+        let mut limiter = FrameLimiter::new(0, start);
+        for idx in 0..100 {
+            assert_eq!(
+                limiter.admit(&frame(&idx.to_string()), start),
+                Admission::Now
+            );
+        }
+        assert_eq!(limiter.until_release(start), None);
+    }
+}
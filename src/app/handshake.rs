@@ -0,0 +1,161 @@
+//! Per-connection handshake audit.
+//!
+//! Every time the serial port opens, the connect path records how long each step took (opening
+//! the port, the peer's `hello`, the `hello_ack` or `resume_ack`) and what was agreed. Reports
+//! are appended as JSON lines to `/run/serial_lcd_cache/serial_handshake.log` and the most
+//! recent ones are kept in memory for the control socket's `handshakes` command.
+
+use super::Logger;
+use crate::serial::telemetry::log_handshake_event;
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::VecDeque, time::Duration};
+
+/// Reports kept in memory for the control socket.
+pub const HANDSHAKE_HISTORY: usize = 8;
+
+/// How the handshake ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandshakeOutcome {
+    /// Full `hello` / `hello_ack` exchange.
+    Negotiated,
+    /// The previous session came back with a single `resume`.
+    Resumed,
+    /// No usable handshake; running in legacy LCD-only mode.
+    Legacy,
+}
+
+/// Timing breakdown and result of one connection's handshake. Times are milliseconds; the
+/// `hello_ms` and `ack_ms` steps count from the moment the port finished opening and are
+/// `None` when that frame never arrived.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HandshakeReport {
+    /// Unix seconds when the connect attempt started.
+    pub at: u64,
+    pub device: String,
+    pub baud: u32,
+    pub open_ms: u64,
+    pub hello_ms: Option<u64>,
+    pub ack_ms: Option<u64>,
+    pub total_ms: u64,
+    pub outcome: HandshakeOutcome,
+    pub role: Option<&'static str>,
+    /// Capability bits the peer advertised.
+    pub caps: Option<u32>,
+    pub keepalive_ms: u64,
+}
+
+/// Rolling window of recent handshake reports, mirrored to the telemetry log.
+pub struct HandshakeAudit {
+    recent: VecDeque<HandshakeReport>,
+    persist: bool,
+}
+
+impl HandshakeAudit {
+    pub fn new() -> Self {
+        Self {
+            recent: VecDeque::with_capacity(HANDSHAKE_HISTORY),
+            persist: true,
+        }
+    }
+
+    /// Keep reports in memory only, so tests never touch the cache directory.
+    #[cfg(test)]
+    pub fn disabled() -> Self {
+        Self {
+            recent: VecDeque::with_capacity(HANDSHAKE_HISTORY),
+            persist: false,
+        }
+    }
+
+    pub fn record(&mut self, report: HandshakeReport, logger: &Logger) {
+        logger.debug(format!(
+            "handshake: open={}ms hello={} ack={} total={}ms outcome={:?}",
+            report.open_ms,
+            optional_ms(report.hello_ms),
+            optional_ms(report.ack_ms),
+            report.total_ms,
+            report.outcome
+        ));
+        if self.persist {
+            if let Err(err) = log_handshake_event(&report) {
+                logger.debug(format!("handshake telemetry write failed: {err}"));
+            }
+        }
+        if self.recent.len() == HANDSHAKE_HISTORY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(report);
+    }
+
+    /// Kept reports as a JSON array, oldest first.
+    pub fn to_json(&self) -> Value {
+        Value::Array(
+            self.recent
+                .iter()
+                .filter_map(|report| serde_json::to_value(report).ok())
+                .collect(),
+        )
+    }
+}
+
+impl Default for HandshakeAudit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn millis(duration: Duration) -> u64 {
+    duration.as_millis().min(u64::MAX as u128) as u64
+}
+
+fn optional_ms(ms: Option<u64>) -> String {
+    ms.map_or_else(|| "-".to_string(), |ms| format!("{ms}ms"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::logger::LogLevel;
+
+    fn report(at: u64) -> HandshakeReport {
+        HandshakeReport {
+            at,
+            device: "/dev/ttyUSB0".into(),
+            baud: 9_600,
+            open_ms: 3,
+            hello_ms: Some(40),
+            ack_ms: None,
+            total_ms: 1_003,
+            outcome: HandshakeOutcome::Legacy,
+            role: None,
+            caps: None,
+            keepalive_ms: 4_000,
+        }
+    }
+
+    #[test]
+    fn audit_keeps_the_latest_reports() {
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let mut audit = HandshakeAudit::disabled();
+        for at in 0..HANDSHAKE_HISTORY as u64 + 2 {
+            audit.record(report(at), &logger);
+        }
+        let json = audit.to_json();
+        let kept: Vec<u64> = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|report| report["at"].as_u64().unwrap())
+            .collect();
+        assert_eq!(kept.len(), HANDSHAKE_HISTORY);
+        assert_eq!(kept.first(), Some(&2));
+        assert_eq!(kept.last(), Some(&(HANDSHAKE_HISTORY as u64 + 1)));
+
+        let last = &json[HANDSHAKE_HISTORY - 1];
+        assert_eq!(last["outcome"], "legacy");
+        assert_eq!(last["hello_ms"], 40);
+        assert!(last["ack_ms"].is_null());
+    }
+}
@@ -0,0 +1,226 @@
+//! Debug HUD: live render-loop timings drawn over row 2 while tuning baud, scroll and render
+//! settings in the field. Toggled from the control socket with `{"cmd":"hud","on":true}`.
+//!
+//! The row reads `F2.0 A0.4s L12 W9`: frames accepted per second over the last few seconds, age
+//! of the newest accepted frame, the slowest loop iteration and the last full LCD write, both in
+//! milliseconds.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Row the HUD takes over (row 2 on the glass).
+pub const HUD_ROW: u8 = 1;
+const REFRESH_MS: u64 = 500;
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+/// Timings above this are shown as the cap so the row keeps its shape.
+const MAX_SHOWN_MS: u128 = 999;
+
+#[derive(Debug)]
+pub struct FrameHud {
+    enabled: bool,
+    dirty: bool,
+    enabled_at: Instant,
+    accepted: VecDeque<Instant>,
+    seen_frames: u64,
+    last_frame: Option<Instant>,
+    last_tick: Option<Instant>,
+    loop_peak: Duration,
+    last_write: Option<Duration>,
+    text: String,
+    next_refresh: Instant,
+}
+
+impl FrameHud {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            enabled: false,
+            dirty: false,
+            enabled_at: now,
+            accepted: VecDeque::new(),
+            seen_frames: 0,
+            last_frame: None,
+            last_tick: None,
+            loop_peak: Duration::ZERO,
+            last_write: None,
+            text: String::new(),
+            next_refresh: now,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Switch the HUD; turning it off asks for the page under it to be drawn again.
+    pub fn set_enabled(&mut self, on: bool, now: Instant) {
+        if on && !self.enabled {
+            self.enabled_at = now;
+            self.accepted.clear();
+            self.loop_peak = Duration::ZERO;
+            self.next_refresh = now;
+        }
+        if !on && self.enabled {
+            self.dirty = true;
+        }
+        self.enabled = on;
+    }
+
+    /// True once after the HUD was switched off.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Call at the top of every loop iteration; the gap since the last call is the loop latency.
+    pub fn tick(&mut self, now: Instant) {
+        if let Some(last) = self.last_tick.replace(now) {
+            self.loop_peak = self.loop_peak.max(now.saturating_duration_since(last));
+        }
+    }
+
+    /// Follow the accepted-frame counter, whichever path accepted the frames.
+    pub fn observe_frames(&mut self, total: u64, now: Instant) {
+        let new = total.saturating_sub(self.seen_frames);
+        self.seen_frames = total;
+        if new == 0 {
+            return;
+        }
+        self.last_frame = Some(now);
+        if self.enabled {
+            self.accepted
+                .extend(std::iter::repeat_n(now, new.min(1_000) as usize));
+        }
+    }
+
+    pub fn record_write(&mut self, elapsed: Duration) {
+        self.last_write = Some(elapsed);
+    }
+
+    /// Row text to draw, refreshed every half second; `None` while the HUD is off.
+    pub fn line(&mut self, now: Instant, cols: usize) -> Option<&str> {
+        if !self.enabled {
+            return None;
+        }
+        if now >= self.next_refresh {
+            while self
+                .accepted
+                .front()
+                .is_some_and(|at| now.saturating_duration_since(*at) > RATE_WINDOW)
+            {
+                self.accepted.pop_front();
+            }
+            let window = now
+                .saturating_duration_since(self.enabled_at)
+                .clamp(Duration::from_secs(1), RATE_WINDOW);
+            let fps = self.accepted.len() as f64 / window.as_secs_f64();
+            let age = self
+                .last_frame
+                .map(|at| format_age(now.saturating_duration_since(at)))
+                .unwrap_or_else(|| "-".into());
+            let write = self.last_write.map(format_ms).unwrap_or_else(|| "-".into());
+            let text = format!(
+                "F{} A{age} L{} W{write}",
+                format_fps(fps),
+                format_ms(self.loop_peak)
+            );
+            self.text = if text.chars().count() > cols {
+                text.replace(' ', "")
+            } else {
+                text
+            };
+            self.loop_peak = Duration::ZERO;
+            self.next_refresh = now + Duration::from_millis(REFRESH_MS);
+        }
+        Some(&self.text)
+    }
+}
+
+fn format_fps(fps: f64) -> String {
+    if fps < 10.0 {
+        format!("{fps:.1}")
+    } else {
+        format!("{:.0}", fps.min(999.0))
+    }
+}
+
+fn format_ms(elapsed: Duration) -> String {
+    elapsed.as_millis().min(MAX_SHOWN_MS).to_string()
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs_f64();
+    if secs < 10.0 {
+        format!("{secs:.1}s")
+    } else if secs < 100.0 {
+        format!("{}s", age.as_secs())
+    } else if secs < 6_000.0 {
+        format!("{}m", age.as_secs() / 60)
+    } else {
+        format!("{}h", age.as_secs() / 3_600)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hud_stays_silent_until_enabled() {
+        let now = Instant::now();
+        let mut hud = FrameHud::new(now);
+        hud.observe_frames(3, now);
+        assert_eq!(hud.line(now, 20), None);
+        assert!(!hud.take_dirty());
+    }
+
+    #[test]
+    fn hud_reports_rate_age_loop_and_write_times() {
+        let start = Instant::now();
+        let mut hud = FrameHud::new(start);
+        hud.set_enabled(true, start);
+        hud.tick(start);
+        for step in 1..=10u64 {
+            let at = start + Duration::from_millis(step * 200);
+            hud.tick(at);
+            hud.observe_frames(step, at);
+        }
+        hud.tick(start + Duration::from_millis(2_100));
+        hud.record_write(Duration::from_millis(14));
+        let now = start + Duration::from_millis(2_400);
+        assert_eq!(hud.line(now, 20), Some("F4.2 A0.4s L200 W14"));
+
+        // Loop peaks reset once shown; the text holds until the next refresh.
+        assert_eq!(
+            hud.line(now + Duration::from_millis(100), 20),
+            Some("F4.2 A0.4s L200 W14")
+        );
+        let later = now + Duration::from_secs(30);
+        assert_eq!(hud.line(later, 20), Some("F0.0 A30s L0 W14"));
+    }
+
+    #[test]
+    fn hud_squeezes_onto_narrow_displays() {
+        let now = Instant::now();
+        let mut hud = FrameHud::new(now);
+        hud.set_enabled(true, now);
+        hud.tick(now);
+        hud.tick(now + Duration::from_secs(2));
+        hud.record_write(Duration::from_millis(120));
+        let line = hud.line(now + Duration::from_secs(2), 16).unwrap();
+        assert_eq!(line, "F0.0A-L999W120");
+        assert!(line.chars().count() <= 16);
+    }
+
+    #[test]
+    fn switching_off_asks_for_a_redraw() {
+        let now = Instant::now();
+        let mut hud = FrameHud::new(now);
+        hud.set_enabled(true, now);
+        assert!(!hud.take_dirty());
+        hud.set_enabled(false, now);
+        assert!(hud.take_dirty());
+        assert!(!hud.take_dirty());
+        assert_eq!(hud.line(now, 20), None);
+    }
+}
@@ -0,0 +1,258 @@
+//! How the render loop waits when it has nothing to do.
+//!
+//! The default build blocks in the serial read for the read timeout and sleeps 50ms between
+//! passes while the port is down. With the `async-serial` feature a single-threaded tokio
+//! runtime waits instead: it `select!`s on the serial descriptor turning readable, a
+//! [`LoopWaker`] from a background thread (control socket, polling, encoder, tunnel command
+//! output) and the loop's next deadline. The port is then read with a short timeout, since the
+//! wait already happened, and an idle Pi wakes far less often.
+
+use std::time::Duration;
+
+#[cfg(feature = "async-serial")]
+use std::sync::Arc;
+
+use super::Logger;
+use crate::serial::sync::SerialPort;
+
+/// Pause between passes while the serial port is down (blocking build).
+const OFFLINE_POLL_MS: u64 = 50;
+/// Port read timeout once the runtime has seen the descriptor turn readable. A line cut short
+/// is kept by the port and finished on a later pass.
+#[cfg(feature = "async-serial")]
+const READ_SLICE_MS: u64 = 10;
+
+/// Nudges the render loop out of its idle wait when a background thread hands it work. Does
+/// nothing unless the async runtime drives the loop.
+#[derive(Debug, Clone, Default)]
+pub struct LoopWaker {
+    #[cfg(feature = "async-serial")]
+    notify: Option<Arc<tokio::sync::Notify>>,
+}
+
+impl LoopWaker {
+    pub fn wake(&self) {
+        #[cfg(feature = "async-serial")]
+        if let Some(notify) = self.notify.as_ref() {
+            // Stores a permit when the loop is busy, so the next wait returns at once.
+            notify.notify_one();
+        }
+    }
+}
+
+pub struct LoopIdle {
+    #[cfg(feature = "async-serial")]
+    reactor: Option<Reactor>,
+}
+
+impl LoopIdle {
+    /// Use the async runtime when it is compiled in and starts; otherwise block as before.
+    pub fn new(logger: &Logger) -> Self {
+        #[cfg(feature = "async-serial")]
+        {
+            match Reactor::new() {
+                Ok(reactor) => {
+                    logger.info("render loop: waiting on the tokio runtime");
+                    Self {
+                        reactor: Some(reactor),
+                    }
+                }
+                Err(err) => {
+                    logger.warn(format!(
+                        "render loop: tokio runtime unavailable ({err}); using blocking reads"
+                    ));
+                    Self { reactor: None }
+                }
+            }
+        }
+        #[cfg(not(feature = "async-serial"))]
+        {
+            let _ = logger;
+            Self {}
+        }
+    }
+
+    pub fn waker(&self) -> LoopWaker {
+        #[cfg(feature = "async-serial")]
+        {
+            LoopWaker {
+                notify: self.reactor.as_ref().map(|reactor| reactor.notify.clone()),
+            }
+        }
+        #[cfg(not(feature = "async-serial"))]
+        {
+            LoopWaker::default()
+        }
+    }
+
+    /// Read timeout to program into the port for a pass that may idle for up to `budget`.
+    pub fn port_timeout(&self, budget: Duration) -> Duration {
+        #[cfg(feature = "async-serial")]
+        if self.reactor.is_some() {
+            return budget.min(Duration::from_millis(READ_SLICE_MS));
+        }
+        budget
+    }
+
+    /// Wait until the port has data, a background thread wakes the loop or `budget` runs out.
+    /// The blocking build leaves an open port's wait to the read itself. Input the port has
+    /// already buffered ends the wait at once, as the descriptor will not turn readable for it.
+    pub fn wait(&self, port: Option<&SerialPort>, budget: Duration) {
+        #[cfg(feature = "async-serial")]
+        if let Some(reactor) = self.reactor.as_ref() {
+            if port.is_some_and(SerialPort::has_pending_input) {
+                return;
+            }
+            reactor.wait(port.map(SerialPort::raw_fd), budget);
+            return;
+        }
+        if port.is_none() {
+            std::thread::sleep(Duration::from_millis(OFFLINE_POLL_MS).min(budget));
+        }
+    }
+}
+
+#[cfg(feature = "async-serial")]
+struct Reactor {
+    runtime: tokio::runtime::Runtime,
+    notify: Arc<tokio::sync::Notify>,
+}
+
+#[cfg(feature = "async-serial")]
+impl Reactor {
+    fn new() -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .enable_time()
+            .build()?;
+        Ok(Self {
+            runtime,
+            notify: Arc::new(tokio::sync::Notify::new()),
+        })
+    }
+
+    fn wait(&self, fd: Option<std::os::fd::RawFd>, budget: Duration) {
+        self.runtime.block_on(async {
+            let readable = async {
+                // Registered per wait: the descriptor changes on every reconnect.
+                match fd.map(|fd| {
+                    tokio::io::unix::AsyncFd::with_interest(
+                        PortFd(fd),
+                        tokio::io::Interest::READABLE,
+                    )
+                }) {
+                    Some(Ok(port)) => {
+                        let _ = port.readable().await;
+                    }
+                    _ => std::future::pending::<()>().await,
+                }
+            };
+            tokio::select! {
+                _ = readable => {}
+                _ = self.notify.notified() => {}
+                _ = tokio::time::sleep(budget) => {}
+            }
+        });
+    }
+}
+
+/// Borrowed port descriptor; registering it with the runtime never closes it.
+#[cfg(feature = "async-serial")]
+struct PortFd(std::os::fd::RawFd);
+
+#[cfg(feature = "async-serial")]
+impl std::os::fd::AsRawFd for PortFd {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::LogLevel;
+    use std::time::Instant;
+
+    #[cfg(not(feature = "async-serial"))]
+    #[test]
+    fn blocking_build_keeps_the_read_timeout_and_offline_poll() {
+        let idle = LoopIdle::new(&Logger::new(LogLevel::Error, None).unwrap());
+        assert_eq!(
+            idle.port_timeout(Duration::from_millis(500)),
+            Duration::from_millis(500)
+        );
+        let started = Instant::now();
+        idle.wait(None, Duration::from_secs(5));
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_millis(OFFLINE_POLL_MS));
+        assert!(waited < Duration::from_secs(1));
+        // Waking is harmless when nobody waits on it.
+        idle.waker().wake();
+    }
+
+    #[cfg(feature = "async-serial")]
+    #[test]
+    fn runtime_wait_ends_on_a_wake_or_the_deadline() {
+        let idle = LoopIdle::new(&Logger::new(LogLevel::Error, None).unwrap());
+        assert!(idle.reactor.is_some());
+        assert_eq!(
+            idle.port_timeout(Duration::from_millis(500)),
+            Duration::from_millis(READ_SLICE_MS)
+        );
+
+        let started = Instant::now();
+        idle.wait(None, Duration::from_millis(30));
+        assert!(started.elapsed() >= Duration::from_millis(30));
+
+        // A wake sent from another thread, even before the wait starts, ends it at once.
+        let waker = idle.waker();
+        std::thread::spawn(move || waker.wake()).join().unwrap();
+        let started = Instant::now();
+        idle.wait(None, Duration::from_secs(5));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[cfg(feature = "async-serial")]
+    #[test]
+    fn runtime_wait_ends_when_the_descriptor_turns_readable() {
+        use std::io::Write;
+        use std::os::fd::AsRawFd;
+        use std::os::unix::net::UnixStream;
+
+        let idle = LoopIdle::new(&Logger::new(LogLevel::Error, None).unwrap());
+        let reactor = idle.reactor.as_ref().unwrap();
+        let (mut writer, reader) = UnixStream::pair().unwrap();
+        writer.write_all(b"{}\n").unwrap();
+        let started = Instant::now();
+        reactor.wait(Some(reader.as_raw_fd()), Duration::from_secs(5));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[cfg(feature = "async-serial")]
+    #[test]
+    fn runtime_wait_skips_input_the_port_already_buffered() {
+        use crate::serial::SerialOptions;
+        use std::io::Write;
+
+        let idle = LoopIdle::new(&Logger::new(LogLevel::Error, None).unwrap());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frames.fifo");
+        let opts = SerialOptions {
+            timeout_ms: 20,
+            ..SerialOptions::default()
+        };
+        let mut port = SerialPort::connect(&format!("fifo:{}", path.display()), opts).unwrap();
+        let mut producer = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        producer
+            .write_all(b"{\"line1\":\"a\"}\n{\"line1\":\"b\"}\n")
+            .unwrap();
+
+        // The first read pulls both lines off the pipe, leaving the second in the port.
+        let mut line = String::new();
+        port.read_message_line(&mut line).unwrap();
+        assert!(port.has_pending_input());
+        let started = Instant::now();
+        idle.wait(Some(&port), Duration::from_secs(5));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
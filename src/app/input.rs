@@ -1,7 +1,15 @@
-#[cfg(target_os = "linux")]
-use std::time::{Duration, Instant};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
 
-use crate::{Error, Result};
+use super::idle::LoopWaker;
+use crate::{config::InputConfig, Error, Result};
 
 /// Hardware button wrapper; stubbed on non-Linux platforms.
 #[cfg(target_os = "linux")]
@@ -56,3 +64,240 @@ impl Button {
         false
     }
 }
+
+/// What the rotary encoder asks the render loop to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncoderEvent {
+    /// One detent clockwise: next page.
+    Forward,
+    /// One detent counter-clockwise: previous page.
+    Back,
+    /// Push switch released before the long-press time.
+    Press,
+    /// Push switch held for `long_press_ms`; fires once per hold.
+    LongPress,
+}
+
+/// Turns quadrature samples into one step per detent. Most encoders rest with both contacts
+/// open (high, with pull-ups) between detents and pass through all four states per click.
+#[derive(Debug, Clone)]
+pub struct QuadratureDecoder {
+    last: u8,
+    count: i8,
+}
+
+impl QuadratureDecoder {
+    /// Indexed by `previous << 2 | current`; invalid (skipped) transitions count as 0.
+    const TRANSITIONS: [i8; 16] = [0, -1, 1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, 1, -1, 0];
+    const REST: u8 = 0b11;
+
+    pub fn new(a: bool, b: bool) -> Self {
+        Self {
+            last: Self::state(a, b),
+            count: 0,
+        }
+    }
+
+    pub fn update(&mut self, a: bool, b: bool) -> Option<EncoderEvent> {
+        let next = Self::state(a, b);
+        if next == self.last {
+            return None;
+        }
+        self.count += Self::TRANSITIONS[usize::from((self.last << 2) | next)];
+        self.last = next;
+        if next != Self::REST {
+            return None;
+        }
+        // Half a cycle is enough to call the direction; contact bounce rarely gets that far.
+        match std::mem::take(&mut self.count) {
+            count if count >= 2 => Some(EncoderEvent::Forward),
+            count if count <= -2 => Some(EncoderEvent::Back),
+            _ => None,
+        }
+    }
+
+    fn state(a: bool, b: bool) -> u8 {
+        (u8::from(a) << 1) | u8::from(b)
+    }
+}
+
+/// Short and long presses of the encoder's push switch.
+#[derive(Debug, Clone)]
+pub struct PushSwitch {
+    long_press: Duration,
+    pressed_at: Option<Instant>,
+    long_fired: bool,
+}
+
+impl PushSwitch {
+    /// Presses shorter than this are contact bounce.
+    const DEBOUNCE: Duration = Duration::from_millis(20);
+
+    pub fn new(long_press: Duration) -> Self {
+        Self {
+            long_press,
+            pressed_at: None,
+            long_fired: false,
+        }
+    }
+
+    pub fn update(&mut self, down: bool, now: Instant) -> Option<EncoderEvent> {
+        match (down, self.pressed_at) {
+            (true, None) => {
+                self.pressed_at = Some(now);
+                self.long_fired = false;
+                None
+            }
+            (true, Some(since)) if !self.long_fired && now - since >= self.long_press => {
+                self.long_fired = true;
+                Some(EncoderEvent::LongPress)
+            }
+            (false, Some(since)) => {
+                self.pressed_at = None;
+                (!self.long_fired && now - since >= Self::DEBOUNCE).then_some(EncoderEvent::Press)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Rotary encoder sampled on its own thread so fast turns are not lost while the render loop
+/// waits on the serial port. Dropping it stops the thread and releases the pins.
+pub struct RotaryEncoder {
+    events: Receiver<EncoderEvent>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl RotaryEncoder {
+    /// How often the pins are read.
+    const SAMPLE_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// Events since the last call, oldest first.
+    pub fn events(&self) -> impl Iterator<Item = EncoderEvent> + '_ {
+        self.events.try_iter()
+    }
+}
+
+impl Drop for RotaryEncoder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl RotaryEncoder {
+    /// `Ok(None)` when `[input]` names no encoder pins.
+    pub fn open(config: &InputConfig, waker: LoopWaker) -> Result<Option<Self>> {
+        let (Some(a_pin), Some(b_pin)) = (config.encoder_a_pin, config.encoder_b_pin) else {
+            return Ok(None);
+        };
+        let gpio_err = |e: rppal::gpio::Error| Error::Io(std::io::Error::other(e));
+        let gpio = rppal::gpio::Gpio::new().map_err(gpio_err)?;
+        let a = gpio.get(a_pin).map_err(gpio_err)?.into_input_pullup();
+        let b = gpio.get(b_pin).map_err(gpio_err)?.into_input_pullup();
+        let push = match config.encoder_button_pin {
+            Some(pin) => Some(gpio.get(pin).map_err(gpio_err)?.into_input_pullup()),
+            None => None,
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_worker = stop.clone();
+        let long_press = Duration::from_millis(config.long_press_ms);
+        let worker = std::thread::Builder::new()
+            .name("lifelinetty-encoder".into())
+            .spawn(move || {
+                let mut decoder = QuadratureDecoder::new(a.is_high(), b.is_high());
+                let mut switch = PushSwitch::new(long_press);
+                while !stop_worker.load(Ordering::SeqCst) {
+                    let turned = decoder.update(a.is_high(), b.is_high());
+                    let pushed = push
+                        .as_ref()
+                        .and_then(|pin| switch.update(pin.is_low(), Instant::now()));
+                    for event in turned.into_iter().chain(pushed) {
+                        if tx.send(event).is_err() {
+                            return;
+                        }
+                        waker.wake();
+                    }
+                    std::thread::sleep(Self::SAMPLE_INTERVAL);
+                }
+            })?;
+        Ok(Some(Self {
+            events: rx,
+            stop,
+            worker: Some(worker),
+        }))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl RotaryEncoder {
+    pub fn open(config: &InputConfig, _waker: LoopWaker) -> Result<Option<Self>> {
+        if config.encoder_a_pin.is_none() {
+            return Ok(None);
+        }
+        Err(Error::InvalidArgs(
+            "rotary encoder unsupported on this platform".into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Levels of (A, B) for one detent clockwise, starting and ending at rest.
+    const CLOCKWISE: [(bool, bool); 4] =
+        [(false, true), (false, false), (true, false), (true, true)];
+
+    #[test]
+    fn a_full_cycle_is_one_step_and_reversing_it_steps_back() {
+        let mut decoder = QuadratureDecoder::new(true, true);
+        let forward: Vec<_> = CLOCKWISE
+            .iter()
+            .filter_map(|&(a, b)| decoder.update(a, b))
+            .collect();
+        assert_eq!(forward, [EncoderEvent::Forward]);
+
+        let back: Vec<_> = CLOCKWISE
+            .iter()
+            .rev()
+            .skip(1)
+            .chain([(true, true)].iter())
+            .filter_map(|&(a, b)| decoder.update(a, b))
+            .collect();
+        assert_eq!(back, [EncoderEvent::Back]);
+    }
+
+    #[test]
+    fn contact_bounce_at_rest_is_ignored() {
+        let mut decoder = QuadratureDecoder::new(true, true);
+        for _ in 0..5 {
+            assert_eq!(decoder.update(false, true), None);
+            assert_eq!(decoder.update(true, true), None);
+        }
+    }
+
+    #[test]
+    fn push_switch_tells_short_from_long_presses() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut switch = PushSwitch::new(Duration::from_millis(800));
+
+        assert_eq!(switch.update(true, at(0)), None);
+        assert_eq!(switch.update(false, at(5)), None);
+
+        assert_eq!(switch.update(true, at(100)), None);
+        assert_eq!(switch.update(false, at(250)), Some(EncoderEvent::Press));
+
+        assert_eq!(switch.update(true, at(1000)), None);
+        assert_eq!(switch.update(true, at(1900)), Some(EncoderEvent::LongPress));
+        assert_eq!(switch.update(true, at(2500)), None);
+        assert_eq!(switch.update(false, at(2600)), None);
+    }
+}
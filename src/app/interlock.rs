@@ -0,0 +1,302 @@
+//! Command interlock: a physical-presence gate in front of the command tunnel.
+//!
+//! While `[interlock].required` is set, tunnel commands, tail requests and command-channel
+//! requests are rejected unless the device is armed. Arming records an expiry (unix seconds) in
+//! `/run/serial_lcd_cache/armed`, so every path below shares one state and a reboot always
+//! disarms:
+//!
+//! - pressing the button `button_presses` times within a few seconds;
+//! - `lifelinetty arm [--ms <n>]` on the device itself (`lifelinetty disarm` ends it early);
+//! - a signed line from the peer, `{"type":"arm","expires":<unix secs>,"sig":"<hex>"}`, where
+//!   `sig` is HMAC-SHA256 of `arm:<expires>` keyed with `[interlock].key`. Running
+//!   `lifelinetty arm --sign` with the same key prints one.
+//!
+//! A signed message can never arm for longer than `arm_ms`, and it must expire later than the
+//! last one this daemon accepted, so replaying a captured line does nothing.
+
+use std::{
+    collections::VecDeque,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use humantime::format_rfc3339_seconds;
+use serde::{Deserialize, Serialize};
+
+use super::digest::{constant_time_eq, hmac_sha256, to_hex};
+use crate::{
+    cache_dir,
+    config::{Config, InterlockConfig},
+    Error, ExitCode, Result,
+};
+
+/// Every press of the arming sequence must land within this window.
+const BUTTON_WINDOW: Duration = Duration::from_secs(3);
+/// Slack for the peer's clock running ahead of ours when checking a signed expiry.
+const CLOCK_SKEW_SECS: u64 = 30;
+
+/// Wire form of a signed arm message.
+#[derive(Debug, Serialize, Deserialize)]
+struct ArmMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    expires: u64,
+    sig: String,
+}
+
+pub(crate) struct Interlock {
+    required: bool,
+    arm_secs: u64,
+    button_presses: usize,
+    key: Vec<u8>,
+    path: PathBuf,
+    presses: VecDeque<Instant>,
+    /// Expiry of the newest signed message accepted; older or equal ones are replays.
+    last_signed: u64,
+}
+
+impl Interlock {
+    pub(crate) fn new(config: &InterlockConfig) -> Self {
+        Self::with_path(config, arm_file_path())
+    }
+
+    fn with_path(config: &InterlockConfig, path: PathBuf) -> Self {
+        let mut interlock = Self {
+            required: false,
+            arm_secs: 0,
+            button_presses: 0,
+            key: Vec::new(),
+            path,
+            presses: VecDeque::new(),
+            last_signed: 0,
+        };
+        interlock.reconfigure(config);
+        interlock
+    }
+
+    /// Apply reloaded settings; an arming already in place keeps its expiry.
+    pub(crate) fn reconfigure(&mut self, config: &InterlockConfig) {
+        self.required = config.required;
+        self.arm_secs = config.arm_ms.div_ceil(1000);
+        self.button_presses = config.button_presses as usize;
+        self.key = config.key.as_bytes().to_vec();
+        self.presses.clear();
+    }
+
+    /// Whether commands may run at `now`.
+    pub(crate) fn permits(&self, now: SystemTime) -> bool {
+        !self.required || armed_until(&self.path).is_some_and(|until| until > unix_secs(now))
+    }
+
+    /// Count a button press; returns the new expiry when it completes the arming sequence.
+    pub(crate) fn on_button(&mut self, at: Instant, now: SystemTime) -> Result<Option<u64>> {
+        if !self.required || self.button_presses == 0 {
+            return Ok(None);
+        }
+        while self
+            .presses
+            .front()
+            .is_some_and(|first| at.saturating_duration_since(*first) > BUTTON_WINDOW)
+        {
+            self.presses.pop_front();
+        }
+        self.presses.push_back(at);
+        if self.presses.len() < self.button_presses {
+            return Ok(None);
+        }
+        self.presses.clear();
+        let until = unix_secs(now) + self.arm_secs;
+        arm_until(&self.path, until)?;
+        Ok(Some(until))
+    }
+
+    /// Verify a signed arm line and arm until the expiry it carries.
+    pub(crate) fn on_signed(
+        &mut self,
+        line: &str,
+        now: SystemTime,
+    ) -> std::result::Result<u64, String> {
+        if self.key.is_empty() {
+            return Err("signed arming is disabled (interlock.key is empty)".into());
+        }
+        let msg: ArmMessage =
+            serde_json::from_str(line).map_err(|err| format!("malformed arm message: {err}"))?;
+        if msg.kind != "arm" {
+            return Err(format!("unexpected message type '{}'", msg.kind));
+        }
+        let expected = sign(&self.key, msg.expires);
+        if !constant_time_eq(expected.as_bytes(), msg.sig.to_ascii_lowercase().as_bytes()) {
+            return Err("bad signature".into());
+        }
+        let now = unix_secs(now);
+        if msg.expires <= now {
+            return Err("arm message has already expired".into());
+        }
+        if msg.expires > now + self.arm_secs + CLOCK_SKEW_SECS {
+            return Err(format!(
+                "arm message expires more than {}s ahead",
+                self.arm_secs
+            ));
+        }
+        if msg.expires <= self.last_signed {
+            return Err("arm message replayed".into());
+        }
+        arm_until(&self.path, msg.expires).map_err(|err| format!("cannot record arming: {err}"))?;
+        self.last_signed = msg.expires;
+        Ok(msg.expires)
+    }
+}
+
+/// `lifelinetty arm`: arm this device, or with `sign` print a signed arm message for the peer.
+pub fn run_arm(ms: Option<u64>, sign_only: bool) -> Result<()> {
+    let config = Config::load_or_default().map_err(|err| err.exit_with(ExitCode::Config))?;
+    let secs = ms.unwrap_or(config.interlock.arm_ms).div_ceil(1000);
+    let until = unix_secs(SystemTime::now()) + secs;
+    if sign_only {
+        if config.interlock.key.is_empty() {
+            return Err(Error::InvalidArgs(
+                "interlock.key is empty; set the shared secret to sign arm messages".into(),
+            )
+            .exit_with(ExitCode::Config));
+        }
+        println!("{}", signed_message(config.interlock.key.as_bytes(), until));
+        return Ok(());
+    }
+    arm_until(&arm_file_path(), until)?;
+    println!("armed until {}", format_expiry(until));
+    if !config.interlock.required {
+        println!("note: interlock.required is off, so commands are not gated");
+    }
+    Ok(())
+}
+
+/// `lifelinetty disarm`: end any arming early.
+pub fn run_disarm() -> Result<()> {
+    match fs::remove_file(arm_file_path()) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    println!("disarmed");
+    Ok(())
+}
+
+/// Signed arm lines are JSON objects tagged `"type":"arm"`; no other frame uses that tag.
+pub(crate) fn looks_like_arm_frame(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('{') && trimmed.contains("\"type\":\"arm\"")
+}
+
+pub(crate) fn format_expiry(until: u64) -> String {
+    format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(until)).to_string()
+}
+
+fn arm_file_path() -> PathBuf {
+    cache_dir().join("armed")
+}
+
+fn armed_until(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn arm_until(path: &Path, until: u64) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, format!("{until}\n"))?;
+    Ok(())
+}
+
+pub(crate) fn unix_secs(now: SystemTime) -> u64 {
+    now.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub(crate) fn unix_ms(now: SystemTime) -> u64 {
+    now.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn signed_message(key: &[u8], expires: u64) -> String {
+    let msg = ArmMessage {
+        kind: "arm".into(),
+        expires,
+        sig: sign(key, expires),
+    };
+    serde_json::to_string(&msg).expect("arm message serializes")
+}
+
+fn sign(key: &[u8], expires: u64) -> String {
+    to_hex(&hmac_sha256(key, format!("arm:{expires}").as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> InterlockConfig {
+        InterlockConfig {
+            required: true,
+            arm_ms: 60_000,
+            button_presses: 3,
+            key: "correct horse battery staple".into(),
+        }
+    }
+
+    #[test]
+    fn signed_messages_arm_once_within_the_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut interlock = Interlock::with_path(&config(), dir.path().join("armed"));
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        assert!(!interlock.permits(now));
+
+        let key = config().key.into_bytes();
+        let line = signed_message(&key, 1_000_030);
+        assert!(looks_like_arm_frame(&line));
+        assert_eq!(interlock.on_signed(&line, now), Ok(1_000_030));
+        assert!(interlock.permits(now));
+        assert!(!interlock.permits(now + Duration::from_secs(30)));
+        assert!(interlock
+            .on_signed(&line, now)
+            .unwrap_err()
+            .contains("replayed"));
+
+        let forged = signed_message(b"some other secret key", 1_000_040);
+        assert_eq!(
+            interlock.on_signed(&forged, now).unwrap_err(),
+            "bad signature"
+        );
+        let too_long = signed_message(&key, 1_000_000 + 3_600);
+        assert!(interlock.on_signed(&too_long, now).is_err());
+        let expired = signed_message(&key, 999_999);
+        assert!(interlock.on_signed(&expired, now).is_err());
+    }
+
+    #[test]
+    fn button_sequence_must_finish_inside_the_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut interlock = Interlock::with_path(&config(), dir.path().join("armed"));
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let start = Instant::now();
+        let press = |secs: u64| start + Duration::from_secs(secs);
+
+        assert_eq!(interlock.on_button(press(0), now).unwrap(), None);
+        assert_eq!(interlock.on_button(press(1), now).unwrap(), None);
+        // Too slow: the first two presses age out and the count starts over.
+        assert_eq!(interlock.on_button(press(5), now).unwrap(), None);
+        assert_eq!(interlock.on_button(press(6), now).unwrap(), None);
+        assert!(!interlock.permits(now));
+        assert_eq!(interlock.on_button(press(7), now).unwrap(), Some(1_000_060));
+        assert!(interlock.permits(now));
+
+        interlock.reconfigure(&InterlockConfig {
+            required: false,
+            ..config()
+        });
+        assert!(interlock.permits(now + Duration::from_secs(120)));
+    }
+}
@@ -1,10 +1,14 @@
-use crate::{lcd::Lcd, Error, Result};
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use crate::{display::backend::DisplayBackend, Error, ExitCode, Result};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
 };
 
-/// Install a ctrl-c handler that flips the shared running flag instead of exiting immediately.
+/// Install a SIGINT/SIGTERM handler that flips the shared running flag instead of exiting immediately.
 pub(super) fn create_shutdown_flag() -> Result<Arc<AtomicBool>> {
     let running = Arc::new(AtomicBool::new(true));
     let running_handle = running.clone();
@@ -17,8 +21,43 @@ pub(super) fn create_shutdown_flag() -> Result<Arc<AtomicBool>> {
     Ok(running)
 }
 
+/// Hard limit on the shutdown drain. If the guard is still alive when `limit` runs out (a write
+/// blocked on flow control, a wedged I2C bus), the process exits without finishing the drain.
+pub(super) struct ShutdownDeadline {
+    done: Arc<AtomicBool>,
+}
+
+impl ShutdownDeadline {
+    pub(super) fn arm(limit: Duration) -> Self {
+        let done = Arc::new(AtomicBool::new(false));
+        let watched = done.clone();
+        let spawned = thread::Builder::new()
+            .name("lifelinetty-shutdown".into())
+            .spawn(move || {
+                thread::sleep(limit);
+                if !watched.load(Ordering::SeqCst) {
+                    eprintln!(
+                        "shutdown: drain still running after {}ms; exiting now",
+                        limit.as_millis()
+                    );
+                    std::process::exit(ExitCode::Failure.code());
+                }
+            });
+        if let Err(err) = spawned {
+            eprintln!("shutdown: no deadline thread ({err}); drain is unbounded");
+        }
+        Self { done }
+    }
+}
+
+impl Drop for ShutdownDeadline {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::SeqCst);
+    }
+}
+
 /// Show the shutdown message before exiting the daemon loop.
-pub(super) fn render_shutdown(lcd: &mut Lcd) -> Result<()> {
+pub(super) fn render_shutdown<D: DisplayBackend>(lcd: &mut D) -> Result<()> {
     lcd.clear()?;
     lcd.set_blink(false)?;
     lcd.write_line(0, "offline")?;
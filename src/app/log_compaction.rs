@@ -0,0 +1,323 @@
+//! Nightly compaction of the logs under the cache dir.
+//!
+//! Once a day, at `[log_compaction].at` local time, every live `*.log` under the cache dir is
+//! gzipped into `archive/` as `<path>.<YYYY-MM-DD>.gz` (slashes in the relative path become
+//! `-`) and truncated in place. Writers only ever append, so they carry on at the start of the
+//! emptied file. Rotated files (`lifelinetty.log.1`) are archived the same way and removed.
+//! Archives older than `keep_days`, then the oldest beyond `max_archive_bytes`, are deleted.
+//!
+//! The work runs on its own thread so a large log never stalls the render loop.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+};
+
+use chrono::{Datelike, NaiveDate};
+use flate2::{write::GzEncoder, Compression};
+
+use crate::config::{parse_time_of_day, LogCompactionConfig};
+
+use super::Logger;
+
+/// Directory under the cache dir the archives live in.
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// What one compaction pass did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CompactionReport {
+    pub archived: usize,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub pruned: usize,
+}
+
+/// Runs [`compact`] once a day from the render loop.
+pub struct LogCompactor {
+    settings: LogCompactionConfig,
+    /// Day of the last pass, so a restart after `at` compacts again but a loop tick does not.
+    last_run: Option<NaiveDate>,
+    running: Option<JoinHandle<io::Result<CompactionReport>>>,
+}
+
+impl LogCompactor {
+    pub fn new(settings: LogCompactionConfig) -> Self {
+        Self {
+            settings,
+            last_run: None,
+            running: None,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: LogCompactionConfig) {
+        self.settings = settings;
+    }
+
+    /// Start the day's pass once `minute_of_day` reaches `at`, and log the outcome of a
+    /// finished one. Cheap enough to call every loop iteration.
+    pub fn poll(&mut self, today: NaiveDate, minute_of_day: u16, logger: &Logger) {
+        if self.running.as_ref().is_some_and(JoinHandle::is_finished) {
+            match self.running.take().map(JoinHandle::join) {
+                Some(Ok(Ok(report))) => logger.info(format!(
+                    "log compaction: archived {} logs ({} -> {} bytes), pruned {} archives",
+                    report.archived, report.bytes_in, report.bytes_out, report.pruned
+                )),
+                Some(Ok(Err(err))) => logger.warn(format!("log compaction failed: {err}")),
+                Some(Err(_)) => logger.warn("log compaction thread panicked"),
+                None => {}
+            }
+        }
+        if !self.due(today, minute_of_day) {
+            return;
+        }
+        self.last_run = Some(today);
+        let settings = self.settings.clone();
+        let cache_dir = crate::cache_dir();
+        self.running = Some(std::thread::spawn(move || {
+            compact(&cache_dir, today, &settings)
+        }));
+    }
+
+    fn due(&self, today: NaiveDate, minute_of_day: u16) -> bool {
+        let Some(at) = parse_time_of_day(&self.settings.at) else {
+            return false;
+        };
+        self.settings.enabled
+            && self.running.is_none()
+            && self.last_run != Some(today)
+            && minute_of_day >= at
+    }
+}
+
+/// Archive the logs under `cache_dir` as of `today`, then apply the retention limits.
+pub fn compact(
+    cache_dir: &Path,
+    today: NaiveDate,
+    settings: &LogCompactionConfig,
+) -> io::Result<CompactionReport> {
+    let archive_dir = cache_dir.join(ARCHIVE_DIR);
+    let mut logs = Vec::new();
+    match collect_logs(cache_dir, &archive_dir, &mut logs) {
+        // Nothing has run since boot.
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+        other => other?,
+    }
+    logs.sort();
+    let mut report = CompactionReport::default();
+    if !logs.is_empty() {
+        fs::create_dir_all(&archive_dir)?;
+    }
+    for (path, rotated) in logs {
+        let rel = path.strip_prefix(cache_dir).unwrap_or(&path);
+        let name = rel.to_string_lossy().replace(['/', '\\'], "-");
+        let archive = archive_dir.join(format!("{name}.{}.gz", date_stamp(today)));
+        let (read, written) = archive_log(&path, &archive, rotated)?;
+        if read > 0 {
+            report.archived += 1;
+            report.bytes_in += read;
+            report.bytes_out += written;
+        }
+    }
+    report.pruned = prune(&archive_dir, today, settings)?;
+    Ok(report)
+}
+
+/// Live logs and rotated ones, skipping the archive dir; `true` marks a rotated file.
+fn collect_logs(dir: &Path, skip: &Path, out: &mut Vec<(PathBuf, bool)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let kind = entry.file_type()?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if kind.is_dir() && path != skip {
+            collect_logs(&path, skip, out)?;
+        } else if kind.is_file() {
+            if name.ends_with(".log") {
+                out.push((path, false));
+            } else if name.rsplit_once(".log.").is_some_and(|(_, slot)| {
+                !slot.is_empty() && slot.bytes().all(|b| b.is_ascii_digit())
+            }) {
+                out.push((path, true));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Gzip `path` onto the end of `archive` (a second pass the same day adds another gzip member,
+/// which readers treat as one stream), then empty or remove it. Returns bytes read and
+/// written.
+fn archive_log(path: &Path, archive: &Path, rotated: bool) -> io::Result<(u64, u64)> {
+    let mut source = File::open(path)?;
+    let mut data = Vec::new();
+    source.read_to_end(&mut data)?;
+    if data.is_empty() {
+        return Ok((0, 0));
+    }
+    let out = OpenOptions::new().create(true).append(true).open(archive)?;
+    let before = out.metadata()?.len();
+    let mut gz = GzEncoder::new(out, Compression::default());
+    gz.write_all(&data)?;
+    // Lines appended while compressing belong in this archive too, not in the truncated file.
+    loop {
+        let len = fs::metadata(path)?.len();
+        if len <= data.len() as u64 {
+            break;
+        }
+        source.seek(SeekFrom::Start(data.len() as u64))?;
+        let start = data.len();
+        source.read_to_end(&mut data)?;
+        gz.write_all(&data[start..])?;
+    }
+    let out = gz.finish()?;
+    out.sync_all()?;
+    let written = out.metadata()?.len() - before;
+    if rotated {
+        fs::remove_file(path)?;
+    } else {
+        OpenOptions::new().write(true).open(path)?.set_len(0)?;
+    }
+    Ok((data.len() as u64, written))
+}
+
+/// Delete archives past `keep_days`, then the oldest until the rest fit in
+/// `max_archive_bytes` (0 disables the size cap). Returns how many were deleted.
+fn prune(
+    archive_dir: &Path,
+    today: NaiveDate,
+    settings: &LogCompactionConfig,
+) -> io::Result<usize> {
+    let entries = match fs::read_dir(archive_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    let mut archives = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if let Some(date) = archive_date(&name) {
+            archives.push((date, name, entry.metadata()?.len()));
+        }
+    }
+    // Oldest first.
+    archives.sort();
+    let mut pruned = 0;
+    let mut total: u64 = archives.iter().map(|(_, _, len)| len).sum();
+    for (date, name, len) in &archives {
+        let expired = (today - *date).num_days() >= i64::from(settings.keep_days);
+        let over = settings.max_archive_bytes > 0 && total > settings.max_archive_bytes;
+        if !expired && !over {
+            continue;
+        }
+        fs::remove_file(archive_dir.join(name))?;
+        total -= len;
+        pruned += 1;
+    }
+    Ok(pruned)
+}
+
+/// Today's date in local time, for [`LogCompactor::poll`].
+pub fn local_today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+fn date_stamp(date: NaiveDate) -> String {
+    format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())
+}
+
+/// The date in an archive name made by [`compact`].
+fn archive_date(name: &str) -> Option<NaiveDate> {
+    let stem = name.strip_suffix(".gz")?;
+    let (_, date) = stem.rsplit_once('.')?;
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if (year.len(), month.len(), day.len()) != (4, 2, 2) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use tempfile::tempdir;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+    }
+
+    fn gunzip(path: &Path) -> String {
+        let mut out = String::new();
+        MultiGzDecoder::new(File::open(path).unwrap())
+            .read_to_string(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn archives_live_and_rotated_logs_and_empties_them() {
+        let cache = tempdir().unwrap();
+        let dir = cache.path();
+        fs::create_dir_all(dir.join("polling")).unwrap();
+        fs::write(dir.join("polling/events.log"), "poll 1\n").unwrap();
+        fs::write(dir.join("lifelinetty.log"), "start\n").unwrap();
+        fs::write(dir.join("lifelinetty.log.1"), "older\n").unwrap();
+        fs::write(dir.join("pages.json"), "{}").unwrap();
+        fs::write(dir.join("idle.log"), "").unwrap();
+        let settings = LogCompactionConfig::default();
+
+        let report = compact(dir, day(10), &settings).unwrap();
+        assert_eq!((report.archived, report.pruned), (3, 0));
+        let archive = dir.join(ARCHIVE_DIR);
+        assert_eq!(
+            gunzip(&archive.join("polling-events.log.2026-03-10.gz")),
+            "poll 1\n"
+        );
+        assert_eq!(
+            gunzip(&archive.join("lifelinetty.log.1.2026-03-10.gz")),
+            "older\n"
+        );
+        assert_eq!(fs::read_to_string(dir.join("lifelinetty.log")).unwrap(), "");
+        assert!(!dir.join("lifelinetty.log.1").exists());
+        assert_eq!(fs::read_to_string(dir.join("pages.json")).unwrap(), "{}");
+
+        // A second pass the same day appends to the archive instead of replacing it.
+        fs::write(dir.join("lifelinetty.log"), "again\n").unwrap();
+        compact(dir, day(10), &settings).unwrap();
+        assert_eq!(
+            gunzip(&archive.join("lifelinetty.log.2026-03-10.gz")),
+            "start\nagain\n"
+        );
+    }
+
+    #[test]
+    fn prunes_expired_archives_then_the_oldest_over_the_size_cap() {
+        let cache = tempdir().unwrap();
+        let archive = cache.path().join(ARCHIVE_DIR);
+        fs::create_dir_all(&archive).unwrap();
+        for d in [1, 5, 6, 7] {
+            fs::write(archive.join(format!("a.log.2026-03-0{d}.gz")), [0u8; 100]).unwrap();
+        }
+        fs::write(archive.join("notes.txt"), "kept").unwrap();
+        let settings = LogCompactionConfig {
+            keep_days: 7,
+            max_archive_bytes: 250,
+            ..LogCompactionConfig::default()
+        };
+        // The 1st is a week old; of the rest, the 5th goes to fit 250 bytes.
+        assert_eq!(prune(&archive, day(8), &settings).unwrap(), 2);
+        let mut left: Vec<_> = fs::read_dir(&archive)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            ["a.log.2026-03-06.gz", "a.log.2026-03-07.gz", "notes.txt"]
+        );
+    }
+}
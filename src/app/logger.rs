@@ -1,9 +1,16 @@
-use crate::{Error, Result as AppResult, CACHE_DIR};
+use crate::config::{LogFormat, LoggingConfig};
+use crate::{cache_dir, Error, Result as AppResult, CACHE_DIR};
+use serde_json::{Map, Value};
+use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Longest `name:` prefix read as the module of a JSON line.
+const MAX_MODULE_LEN: usize = 24;
+
 /// Log verbosity levels.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub enum LogLevel {
@@ -29,14 +36,37 @@ impl FromStr for LogLevel {
     }
 }
 
-/// Simple stderr/file logger with levels and optional file sink.
+impl LogLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+/// Simple stderr/file logger with levels, an optional file sink and optional JSON lines.
 pub struct Logger {
     level: LogLevel,
-    file: Option<std::fs::File>,
+    format: LogFormat,
+    file: Option<Mutex<LogFile>>,
+    /// `--instance` name, added to every line as an `instance` field.
+    instance: Option<&'static str>,
 }
 
 impl Logger {
     pub fn new(level: LogLevel, file_path: Option<String>) -> AppResult<Self> {
+        Self::with_settings(level, file_path, LoggingConfig::default())
+    }
+
+    pub fn with_settings(
+        level: LogLevel,
+        file_path: Option<String>,
+        settings: LoggingConfig,
+    ) -> AppResult<Self> {
         let env_level = std::env::var("LIFELINETTY_LOG_LEVEL")
             .ok()
             .and_then(|s| LogLevel::from_str(&s).ok());
@@ -44,17 +74,14 @@ impl Logger {
 
         let env_file = std::env::var("LIFELINETTY_LOG_PATH").ok();
         let resolved_path = resolve_log_path(file_path.or(env_file))?;
-        let file = match resolved_path {
-            Some(path) => std::fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&path)
-                .ok(),
-            None => None,
-        };
+        let file = resolved_path
+            .and_then(|path| LogFile::open(path, &settings))
+            .map(Mutex::new);
         Ok(Self {
             level: effective_level,
+            format: settings.format,
             file,
+            instance: crate::instance(),
         })
     }
 
@@ -63,18 +90,34 @@ impl Logger {
     }
 
     pub fn log(&self, level: LogLevel, msg: impl AsRef<str>) {
+        self.log_with(level, msg, &[]);
+    }
+
+    /// Log with extra key/value fields: JSON keys of their own, or ` key=value` after the text.
+    pub fn log_with(&self, level: LogLevel, msg: impl AsRef<str>, fields: &[(&str, Value)]) {
         if level > self.level {
             return;
         }
-        let ts = SystemTime::now()
+        let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs_f32())
-            .unwrap_or(0.0);
-        let line = format!("[{ts:.3}] [{level:?}] {}", msg.as_ref());
+            .unwrap_or_default();
+        let tagged;
+        let fields = match self.instance {
+            Some(name) => {
+                tagged = [fields, &[("instance", Value::from(name))]].concat();
+                &tagged[..]
+            }
+            None => fields,
+        };
+        let line = match self.format {
+            LogFormat::Text => text_line(now.as_secs_f32(), level, msg.as_ref(), fields),
+            LogFormat::Json => json_line(now.as_millis(), level, msg.as_ref(), fields),
+        };
         eprintln!("{line}");
+        super::crash::remember(&line);
         if let Some(file) = self.file.as_ref() {
-            if let Ok(mut clone) = file.try_clone() {
-                let _ = writeln!(clone, "{line}");
+            if let Ok(mut file) = file.lock() {
+                file.write_line(&line);
             }
         }
     }
@@ -102,6 +145,111 @@ impl Logger {
     }
 }
 
+fn text_line(ts: f32, level: LogLevel, msg: &str, fields: &[(&str, Value)]) -> String {
+    let mut line = format!("[{ts:.3}] [{level:?}] {msg}");
+    for (key, value) in fields {
+        match value {
+            Value::String(text) => line.push_str(&format!(" {key}={text}")),
+            other => line.push_str(&format!(" {key}={other}")),
+        }
+    }
+    line
+}
+
+fn json_line(ts_ms: u128, level: LogLevel, msg: &str, fields: &[(&str, Value)]) -> String {
+    let (module, msg) = split_module(msg);
+    let mut object = Map::new();
+    object.insert("ts".into(), Value::from(ts_ms as f64 / 1000.0));
+    object.insert("level".into(), Value::from(level.as_str()));
+    object.insert("module".into(), Value::from(module));
+    object.insert("msg".into(), Value::from(msg));
+    for (key, value) in fields {
+        object
+            .entry(key.to_string())
+            .or_insert_with(|| value.clone());
+    }
+    Value::Object(object).to_string()
+}
+
+/// Messages name their part of the daemon as a `name:` prefix (`tunnel: ...`,
+/// `render loop: ...`); lines without one belong to `lifelinetty`.
+fn split_module(msg: &str) -> (&str, &str) {
+    if let Some((prefix, rest)) = msg.split_once(": ") {
+        let is_name = !prefix.is_empty()
+            && prefix.len() <= MAX_MODULE_LEN
+            && prefix.split(' ').count() <= 2
+            && prefix
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || " _-".contains(c));
+        if is_name {
+            return (prefix, rest);
+        }
+    }
+    ("lifelinetty", msg)
+}
+
+/// Log file sink that rotates to `<path>.1` .. `<path>.<keep>` before passing `max_bytes`.
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    len: u64,
+    max_bytes: u64,
+    keep: u8,
+}
+
+impl LogFile {
+    fn open(path: PathBuf, settings: &LoggingConfig) -> Option<Self> {
+        let file = open_append(&path)?;
+        let len = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+        Some(Self {
+            path,
+            file,
+            len,
+            max_bytes: settings.max_bytes,
+            keep: settings.keep.max(1),
+        })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        let bytes = line.len() as u64 + 1;
+        if self.max_bytes > 0 && self.len > 0 && self.len + bytes > self.max_bytes {
+            self.rotate();
+        }
+        if writeln!(self.file, "{line}").is_ok() {
+            self.len += bytes;
+        }
+    }
+
+    /// Shift each rotated file up one slot, dropping the oldest, and start a fresh live file.
+    /// Failures keep appending to the current file rather than losing lines.
+    fn rotate(&mut self) {
+        for slot in (1..self.keep).rev() {
+            let _ = fs::rename(self.rotated(slot), self.rotated(slot + 1));
+        }
+        if fs::rename(&self.path, self.rotated(1)).is_err() {
+            return;
+        }
+        if let Some(file) = open_append(&self.path) {
+            self.file = file;
+            self.len = 0;
+        }
+    }
+
+    fn rotated(&self, slot: u8) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{slot}"));
+        PathBuf::from(name)
+    }
+}
+
+fn open_append(path: &Path) -> Option<File> {
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .ok()
+}
+
 fn resolve_log_path(raw: Option<String>) -> AppResult<Option<PathBuf>> {
     let Some(raw) = raw else {
         return Ok(None);
@@ -111,7 +259,7 @@ fn resolve_log_path(raw: Option<String>) -> AppResult<Option<PathBuf>> {
     let resolved = if candidate.is_absolute() {
         candidate
     } else {
-        Path::new(CACHE_DIR).join(candidate)
+        cache_dir().join(candidate)
     };
 
     validate_cache_path(&resolved)?;
@@ -157,6 +305,125 @@ mod tests {
         assert!(format!("{err}").contains(CACHE_DIR));
     }
 
+    fn temp_log(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "lifelinetty-log-{name}-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("lifelinetty.log")
+    }
+
+    #[test]
+    fn json_lines_carry_module_and_fields() {
+        let line = json_line(
+            1_700_000_000_250,
+            LogLevel::Warn,
+            "tunnel: push failed: disk full",
+            &[("path", Value::from("/tmp/a")), ("bytes", Value::from(42))],
+        );
+        let parsed: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["ts"], 1_700_000_000.25);
+        assert_eq!(parsed["level"], "warn");
+        assert_eq!(parsed["module"], "tunnel");
+        assert_eq!(parsed["msg"], "push failed: disk full");
+        assert_eq!(parsed["path"], "/tmp/a");
+        assert_eq!(parsed["bytes"], 42);
+    }
+
+    #[test]
+    fn module_comes_only_from_short_name_prefixes() {
+        assert_eq!(split_module("render loop: idle"), ("render loop", "idle"));
+        assert_eq!(
+            split_module("serial init failed [timeout]: gone"),
+            ("lifelinetty", "serial init failed [timeout]: gone")
+        );
+        assert_eq!(
+            split_module("serial connected"),
+            ("lifelinetty", "serial connected")
+        );
+    }
+
+    #[test]
+    fn text_lines_append_fields() {
+        let line = text_line(
+            1.5,
+            LogLevel::Info,
+            "tunnel: receiving push",
+            &[("path", Value::from("a.bin")), ("size", Value::from(10))],
+        );
+        assert_eq!(
+            line,
+            "[1.500] [Info] tunnel: receiving push path=a.bin size=10"
+        );
+    }
+
+    #[test]
+    fn log_file_rotates_and_keeps_the_newest_files() {
+        let path = temp_log("rotate");
+        let settings = LoggingConfig {
+            format: LogFormat::Text,
+            max_bytes: 20,
+            keep: 2,
+        };
+        let mut file = LogFile::open(path.clone(), &settings).unwrap();
+        for line in ["first line", "second line", "third line", "fourth line"] {
+            file.write_line(line);
+        }
+        let read = |slot: Option<u8>| {
+            let target = slot.map_or_else(|| path.clone(), |slot| file.rotated(slot));
+            fs::read_to_string(target).unwrap()
+        };
+        assert_eq!(read(None), "fourth line\n");
+        assert_eq!(read(Some(1)), "third line\n");
+        assert_eq!(read(Some(2)), "second line\n");
+        assert!(!file.rotated(3).exists());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn instance_name_is_added_to_every_line() {
+        let path = temp_log("instance");
+        let settings = LoggingConfig {
+            format: LogFormat::Json,
+            ..LoggingConfig::default()
+        };
+        let logger = Logger {
+            level: LogLevel::Info,
+            format: settings.format,
+            file: LogFile::open(path.clone(), &settings).map(Mutex::new),
+            instance: Some("left"),
+        };
+        logger.log_with(LogLevel::Info, "serial connected", &[("baud", 9600.into())]);
+        let line: Value = serde_json::from_str(fs::read_to_string(&path).unwrap().trim()).unwrap();
+        assert_eq!(line["instance"], "left");
+        assert_eq!(line["baud"], 9600);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn log_file_picks_up_the_size_of_an_existing_file() {
+        let path = temp_log("resume");
+        fs::write(&path, "0123456789012345\n").unwrap();
+        let settings = LoggingConfig {
+            format: LogFormat::Text,
+            max_bytes: 20,
+            keep: 1,
+        };
+        let mut file = LogFile::open(path.clone(), &settings).unwrap();
+        file.write_line("next");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "next\n");
+        assert_eq!(
+            fs::read_to_string(file.rotated(1)).unwrap(),
+            "0123456789012345\n"
+        );
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
     #[test]
     fn rejects_parent_dir_components() {
         let err = resolve_log_path(Some("../escape.log".into())).unwrap_err();
@@ -0,0 +1,342 @@
+//! Prometheus `/metrics` endpoint for fleet scraping.
+//!
+//! The render loop renders its counters, link state and the latest polling snapshot into
+//! [`Exposition`] text about once a second and hands it to [`MetricsExporter`]. A listener thread
+//! answers `GET /metrics` with the last text it was given, so a slow scraper never holds up the
+//! LCD. The HTTP side only exists in builds with the `metrics-http` feature; without it,
+//! [`MetricsExporter::start`] warns when `[metrics].enabled` is set and serves nothing.
+
+use super::polling::PollSnapshot;
+use std::fmt::Write as _;
+
+#[cfg(feature = "metrics-http")]
+pub use http::MetricsExporter;
+
+#[cfg(not(feature = "metrics-http"))]
+pub use disabled::MetricsExporter;
+
+const PREFIX: &str = "lifelinetty_";
+
+/// Prometheus text exposition format 0.0.4, one metric family at a time.
+#[derive(Debug, Default)]
+pub struct Exposition {
+    text: String,
+    /// `{instance="<name>"}` under `--instance`, so daemons sharing a host scrape apart.
+    labels: String,
+}
+
+impl Exposition {
+    pub fn new() -> Self {
+        Self::for_instance(crate::instance())
+    }
+
+    fn for_instance(instance: Option<&str>) -> Self {
+        Self {
+            text: String::new(),
+            labels: instance
+                .map(|name| format!("{{instance=\"{name}\"}}"))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// `name` without the `lifelinetty_` prefix; counters end in `_total` by convention.
+    pub fn counter(&mut self, name: &str, help: &str, value: u64) {
+        self.family(name, help, "counter", value as f64);
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, help, "gauge", value);
+    }
+
+    /// Host gauges from the polling thread; readings the host could not provide are left out.
+    pub fn poll_snapshot(&mut self, snapshot: &PollSnapshot) {
+        self.gauge(
+            "host_cpu_percent",
+            "CPU busy percentage at the last poll",
+            f64::from(snapshot.cpu_percent),
+        );
+        self.gauge(
+            "host_memory_used_bytes",
+            "Memory in use at the last poll",
+            (snapshot.mem_used_kb * 1024) as f64,
+        );
+        self.gauge(
+            "host_memory_total_bytes",
+            "Total memory",
+            (snapshot.mem_total_kb * 1024) as f64,
+        );
+        self.gauge(
+            "host_disk_used_percent",
+            "Root filesystem usage at the last poll",
+            f64::from(snapshot.disk_used_pct),
+        );
+        if let Some(kb) = snapshot.disk_available_kb {
+            self.gauge(
+                "host_disk_available_bytes",
+                "Root filesystem space available at the last poll",
+                (kb * 1024) as f64,
+            );
+        }
+        if let Some(celsius) = snapshot.temperature_c {
+            self.gauge(
+                "host_temperature_celsius",
+                "CPU temperature at the last poll",
+                f64::from(celsius),
+            );
+        }
+        if let Some([load1, load5, load15]) = snapshot.load_average {
+            self.gauge("host_load1", "1 minute load average", f64::from(load1));
+            self.gauge("host_load5", "5 minute load average", f64::from(load5));
+            self.gauge("host_load15", "15 minute load average", f64::from(load15));
+        }
+    }
+
+    pub fn into_text(self) -> String {
+        self.text
+    }
+
+    fn family(&mut self, name: &str, help: &str, kind: &str, value: f64) {
+        let _ = writeln!(self.text, "# HELP {PREFIX}{name} {help}");
+        let _ = writeln!(self.text, "# TYPE {PREFIX}{name} {kind}");
+        let _ = writeln!(self.text, "{PREFIX}{name}{} {value}", self.labels);
+    }
+}
+
+#[cfg(not(feature = "metrics-http"))]
+mod disabled {
+    use crate::{app::Logger, config::MetricsConfig};
+    use std::{
+        sync::{atomic::AtomicBool, Arc},
+        time::Instant,
+    };
+
+    pub struct MetricsExporter;
+
+    impl MetricsExporter {
+        pub fn start(
+            config: &MetricsConfig,
+            _running: Arc<AtomicBool>,
+            logger: &Logger,
+        ) -> Option<Self> {
+            if config.enabled {
+                logger.warn("metrics: [metrics] is enabled but this build lacks metrics-http");
+            }
+            None
+        }
+
+        #[inline]
+        pub fn due(&self, _now: Instant) -> bool {
+            false
+        }
+
+        #[inline]
+        pub fn publish(&mut self, _text: String, _now: Instant) {}
+    }
+}
+
+#[cfg(feature = "metrics-http")]
+mod http {
+    use crate::{app::Logger, config::MetricsConfig};
+    use std::{
+        io::{self, BufRead, BufReader, Read, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    };
+
+    const ACCEPT_POLL_MS: u64 = 100;
+    /// How often the render loop refreshes the text served to scrapers.
+    const METRICS_REFRESH_MS: u64 = 1_000;
+    /// Scrapes are served one at a time; a client that stalls is cut off after this long.
+    const CLIENT_TIMEOUT_MS: u64 = 2_000;
+    /// Request line plus headers; anything bigger is not a scraper.
+    const MAX_REQUEST_BYTES: u64 = 8 * 1024;
+    const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+    /// Listener thread plus the text it serves. Dropping it stops the thread and frees the port.
+    pub struct MetricsExporter {
+        body: Arc<Mutex<String>>,
+        running: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+        next_publish: Instant,
+    }
+
+    impl MetricsExporter {
+        /// Bind `[metrics].listen`; `None` when disabled or the address cannot be bound.
+        pub fn start(
+            config: &MetricsConfig,
+            app_running: Arc<AtomicBool>,
+            logger: &Logger,
+        ) -> Option<Self> {
+            if !config.enabled {
+                return None;
+            }
+            match Self::bind(&config.listen, app_running) {
+                Ok((exporter, addr)) => {
+                    logger.info(format!("metrics: serving http://{addr}/metrics"));
+                    Some(exporter)
+                }
+                Err(err) => {
+                    logger.warn(format!("metrics endpoint disabled: {err}"));
+                    None
+                }
+            }
+        }
+
+        fn bind(listen: &str, app_running: Arc<AtomicBool>) -> io::Result<(Self, SocketAddr)> {
+            let listener = TcpListener::bind(listen)?;
+            let addr = listener.local_addr()?;
+            listener.set_nonblocking(true)?;
+            let body = Arc::new(Mutex::new(String::new()));
+            let running = Arc::new(AtomicBool::new(true));
+            let (body_clone, running_clone) = (body.clone(), running.clone());
+            let thread = thread::Builder::new()
+                .name("lifelinetty-metrics".into())
+                .spawn(move || {
+                    while app_running.load(Ordering::SeqCst) && running_clone.load(Ordering::SeqCst)
+                    {
+                        match listener.accept() {
+                            Ok((stream, _)) => {
+                                let _ = serve_scrape(stream, &body_clone);
+                            }
+                            Err(_) => thread::sleep(Duration::from_millis(ACCEPT_POLL_MS)),
+                        }
+                    }
+                })?;
+            Ok((
+                Self {
+                    body,
+                    running,
+                    thread: Some(thread),
+                    next_publish: Instant::now(),
+                },
+                addr,
+            ))
+        }
+
+        /// Whether the served text is old enough to be rendered again.
+        pub fn due(&self, now: Instant) -> bool {
+            now >= self.next_publish
+        }
+
+        pub fn publish(&mut self, text: String, now: Instant) {
+            if let Ok(mut body) = self.body.lock() {
+                *body = text;
+            }
+            self.next_publish = now + Duration::from_millis(METRICS_REFRESH_MS);
+        }
+    }
+
+    impl Drop for MetricsExporter {
+        fn drop(&mut self) {
+            self.running.store(false, Ordering::SeqCst);
+            // Joining lets a reload rebind the same port straight away.
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    fn serve_scrape(mut stream: TcpStream, body: &Mutex<String>) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        let timeout = Some(Duration::from_millis(CLIENT_TIMEOUT_MS));
+        stream.set_read_timeout(timeout)?;
+        stream.set_write_timeout(timeout)?;
+        let mut reader = BufReader::new(stream.try_clone()?).take(MAX_REQUEST_BYTES);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut header = String::new();
+        loop {
+            header.clear();
+            if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+                break;
+            }
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (method, path) = (parts.next(), parts.next().unwrap_or(""));
+        let path = path.split('?').next().unwrap_or("");
+        let (status, text) = match method {
+            Some("GET") if path == "/metrics" => (
+                "200 OK",
+                body.lock().map(|body| body.clone()).unwrap_or_default(),
+            ),
+            Some("GET") => ("404 Not Found", "try /metrics\n".to_string()),
+            _ => ("405 Method Not Allowed", "only GET is served\n".to_string()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{text}",
+            text.len()
+        )?;
+        stream.flush()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn get(addr: SocketAddr, path: &str) -> String {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {path} HTTP/1.1\r\nHost: pi\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        }
+
+        #[test]
+        fn serves_the_last_published_text() {
+            let running = Arc::new(AtomicBool::new(true));
+            let (mut exporter, addr) = MetricsExporter::bind("127.0.0.1:0", running).unwrap();
+            exporter.publish("lifelinetty_up 1\n".into(), Instant::now());
+
+            let response = get(addr, "/metrics");
+            assert!(response.starts_with("HTTP/1.1 200 OK"));
+            assert!(response.contains(CONTENT_TYPE));
+            assert!(response.ends_with("\r\n\r\nlifelinetty_up 1\n"));
+            assert!(get(addr, "/").starts_with("HTTP/1.1 404"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposition_writes_help_type_and_sample_lines() {
+        let mut out = Exposition::new();
+        out.counter("frames_accepted_total", "Frames rendered", 42);
+        out.poll_snapshot(&PollSnapshot {
+            cpu_percent: 12.5,
+            mem_used_kb: 2,
+            mem_total_kb: 4,
+            disk_used_pct: 50.0,
+            disk_available_kb: None,
+            temperature_c: None,
+            load_average: None,
+        });
+        let text = out.into_text();
+        assert!(text.starts_with(
+            "# HELP lifelinetty_frames_accepted_total Frames rendered\n\
+             # TYPE lifelinetty_frames_accepted_total counter\n\
+             lifelinetty_frames_accepted_total 42\n"
+        ));
+        assert!(text.contains("lifelinetty_host_cpu_percent 12.5\n"));
+        assert!(text.contains("lifelinetty_host_memory_used_bytes 2048\n"));
+        assert!(!text.contains("temperature"));
+    }
+
+    #[test]
+    fn instances_label_every_sample() {
+        let mut out = Exposition::for_instance(Some("left"));
+        out.gauge("link_up", "Serial link state", 1.0);
+        assert!(out
+            .into_text()
+            .ends_with("\nlifelinetty_link_up{instance=\"left\"} 1\n"));
+    }
+}
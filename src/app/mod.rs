@@ -3,37 +3,100 @@ use crate::{
     compression::CompressionCodec,
     config::Pcf8574Addr,
     config::{
-        Config, DisplayDriver, NegotiationConfig, DEFAULT_BAUD, DEFAULT_COLS, DEFAULT_DEVICE,
-        DEFAULT_ROWS, DEFAULT_SERIAL_TIMEOUT_MS,
+        identity, Config, DisplayConfig, DisplayDriver, NegotiationConfig, TunnelAuthBackend,
+        ZoneConfig, DEFAULT_BAUD, DEFAULT_COLS, DEFAULT_DEVICE, DEFAULT_ROWS,
+        DEFAULT_SERIAL_LOW_LATENCY, DEFAULT_SERIAL_READ_GAP_DS, DEFAULT_SERIAL_READ_MIN_BYTES,
+        DEFAULT_SERIAL_TIMEOUT_MS, DEFAULT_SERIAL_TX_RATE_PERCENT,
     },
+    display::capture::CaptureFormat,
     lcd::Lcd,
     payload::{CompressionPolicy, Defaults as PayloadDefaults, RenderFrame},
     serial::{DtrBehavior, FlowControlMode, ParityMode, SerialOptions, StopBitsMode},
-    Result,
+    Error, ExitCode, Result,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Instant,
 };
-use std::{fs, path::Path, str::FromStr, time::Instant};
 
+mod alerts;
+pub mod annunciator;
+mod anomaly;
+pub mod autobaud;
+mod backlight;
+mod burst;
+pub mod command_cache;
+pub mod completions;
+mod config_watch;
 mod connection;
+mod control;
+mod crash;
 mod demo;
+mod digest;
+mod displays;
 mod events;
+mod features;
+pub mod file_transfer;
+mod frame_limit;
+mod handshake;
+mod hud;
+mod idle;
 mod input;
+pub mod interlock;
 mod lifecycle;
+mod log_compaction;
 mod logger;
+mod metrics;
+mod modem;
+pub mod monitor;
 mod negotiation;
+mod one_line;
+mod page_store;
+mod parse_errors;
+mod payload_watch;
+mod peer_defaults;
 mod polling;
+pub mod preview;
+pub mod provision;
+mod relay;
+pub mod remote_config;
 mod render_loop;
+mod replay;
+mod scheduler;
+pub mod send;
 pub mod serial_shell;
+mod shell_pager;
+pub mod slo;
+mod spool;
+pub mod support_bundle;
+mod systemd;
+mod tail;
+pub mod telemetry_export;
+mod throttle;
+mod trace;
+mod troubleshoot;
 mod tunnel;
+pub mod tunnel_auth;
+pub mod tunnel_files;
 mod watchdog;
 mod wizard;
+mod write_budget;
+mod ymodem;
+mod zones;
 
-use crate::display::overlays::{render_frame_once, render_reconnecting};
+use crate::display::overlays::render_frame_once;
 use crate::serial::backoff::BackoffController;
 use connection::attempt_serial_connect;
-use demo::run_demo;
+use demo::{capture_demo, run_demo};
+use features::FeatureMatrix;
+use handshake::HandshakeAudit;
 pub(crate) use logger::{LogLevel, Logger};
-use negotiation::NegotiationLog;
+use negotiation::{keepalive_proposal_ms, NegotiationLog, SessionCache};
+use payload_watch::run_payload_watch;
 use render_loop::run_render_loop;
+use troubleshoot::{Component, StartupDiagnostics};
 
 /// Config for the daemon.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -45,29 +108,79 @@ pub struct AppConfig {
     pub stop_bits: StopBitsMode,
     pub dtr_on_open: DtrBehavior,
     pub serial_timeout_ms: u64,
+    pub serial_read_min_bytes: u8,
+    pub serial_read_gap_ds: u8,
+    pub serial_low_latency: bool,
+    pub serial_tx_rate_percent: u8,
+    pub autobaud: bool,
     pub cols: u8,
     pub rows: u8,
     pub scroll_speed_ms: u64,
     pub page_timeout_ms: u64,
+    pub frame_cache_size: u16,
     pub polling_enabled: bool,
     pub poll_interval_ms: u64,
     pub button_gpio_pin: Option<u8>,
     pub payload_file: Option<String>,
+    pub payload_watch: Option<String>,
     pub backoff_initial_ms: u64,
     pub backoff_max_ms: u64,
     pub negotiation: NegotiationConfig,
     pub pcf8574_addr: Pcf8574Addr,
     pub display_driver: DisplayDriver,
     pub lcd_present: bool,
+    /// Extra LCDs on the same bus, from `[[displays]]`.
+    pub displays: Vec<DisplayConfig>,
+    /// Named blocks of rows that payloads address with `target`, from `[[zones]]`.
+    pub zones: Vec<ZoneConfig>,
     pub log_level: LogLevel,
     pub log_file: Option<String>,
     pub demo: bool,
+    /// `--capture`: snapshot each demo page into this directory and exit.
+    pub capture: Option<String>,
+    pub capture_format: CaptureFormat,
+    /// `--dry-run`: draw the LCD in the terminal instead of opening I2C.
+    pub dry_run: bool,
+    pub echo_frames: bool,
+    /// `--trace`: follow every inbound line through to the LCD in the log.
+    pub trace: bool,
     pub command_allowlist: Vec<String>,
+    pub tail_allowlist: Vec<String>,
     pub serialsh: bool,
     pub protocol_schema_version: u8,
     pub compression_enabled: bool,
     pub compression_codec: CompressionCodec,
+    pub ack_enabled: bool,
+    pub protocol_strict: bool,
     pub watchdog: crate::config::WatchdogConfig,
+    pub modem: crate::config::ModemConfig,
+    pub scheduler: crate::config::SchedulerConfig,
+    pub slo: crate::config::SloConfig,
+    pub anomaly: crate::config::AnomalyConfig,
+    pub arq: crate::serial::arq::ArqSettings,
+    pub annunciators: crate::config::AnnunciatorConfig,
+    pub parse_errors: crate::config::ParseErrorConfig,
+    pub replay: crate::config::ReplayConfig,
+    pub render: crate::config::RenderConfig,
+    pub accessibility: crate::config::AccessibilityConfig,
+    pub charmap: crate::config::CharmapConfig,
+    pub filters: crate::config::FilterConfig,
+    pub spool: crate::config::SpoolConfig,
+    pub burst: crate::config::BurstConfig,
+    pub troubleshoot: crate::config::TroubleshootConfig,
+    pub poll_overlay: crate::config::PollOverlayConfig,
+    pub interlock: crate::config::InterlockConfig,
+    pub input: crate::config::InputConfig,
+    pub lcd_gpio: crate::config::LcdGpioConfig,
+    pub backlight: crate::config::BacklightConfig,
+    pub control: crate::config::ControlConfig,
+    pub metrics: crate::config::MetricsConfig,
+    pub badges: crate::config::BadgesConfig,
+    pub peer_defaults: crate::config::PeerDefaultsConfig,
+    pub logging: crate::config::LoggingConfig,
+    pub log_compaction: crate::config::LogCompactionConfig,
+    pub tunnel_auth: crate::config::TunnelAuthConfig,
+    pub relay: crate::config::RelayConfig,
 }
 
 impl Default for AppConfig {
@@ -80,29 +193,74 @@ impl Default for AppConfig {
             stop_bits: StopBitsMode::default(),
             dtr_on_open: DtrBehavior::default(),
             serial_timeout_ms: DEFAULT_SERIAL_TIMEOUT_MS,
+            serial_read_min_bytes: DEFAULT_SERIAL_READ_MIN_BYTES,
+            serial_read_gap_ds: DEFAULT_SERIAL_READ_GAP_DS,
+            serial_low_latency: DEFAULT_SERIAL_LOW_LATENCY,
+            serial_tx_rate_percent: DEFAULT_SERIAL_TX_RATE_PERCENT,
+            autobaud: crate::config::DEFAULT_AUTOBAUD,
             cols: DEFAULT_COLS,
             rows: DEFAULT_ROWS,
             scroll_speed_ms: crate::payload::DEFAULT_SCROLL_MS,
             page_timeout_ms: crate::payload::DEFAULT_PAGE_TIMEOUT_MS,
+            frame_cache_size: crate::config::DEFAULT_FRAME_CACHE_SIZE,
             polling_enabled: crate::config::DEFAULT_POLLING_ENABLED,
             poll_interval_ms: crate::config::DEFAULT_POLL_INTERVAL_MS,
             button_gpio_pin: None,
             payload_file: None,
+            payload_watch: None,
             backoff_initial_ms: crate::config::DEFAULT_BACKOFF_INITIAL_MS,
             backoff_max_ms: crate::config::DEFAULT_BACKOFF_MAX_MS,
             negotiation: NegotiationConfig::default(),
             pcf8574_addr: crate::config::DEFAULT_PCF8574_ADDR,
             display_driver: crate::config::DEFAULT_DISPLAY_DRIVER,
             lcd_present: crate::config::DEFAULT_LCD_PRESENT,
+            displays: Vec::new(),
+            zones: Vec::new(),
             log_level: LogLevel::default(),
             log_file: None,
             demo: false,
+            capture: None,
+            capture_format: CaptureFormat::default(),
+            dry_run: false,
+            echo_frames: false,
+            trace: false,
             command_allowlist: Vec::new(),
+            tail_allowlist: Vec::new(),
             serialsh: false,
             protocol_schema_version: crate::config::DEFAULT_PROTOCOL_SCHEMA_VERSION,
             compression_enabled: crate::config::DEFAULT_PROTOCOL_COMPRESSION_ENABLED,
             compression_codec: crate::config::DEFAULT_PROTOCOL_COMPRESSION_CODEC,
+            ack_enabled: crate::config::DEFAULT_PROTOCOL_ACK_ENABLED,
+            protocol_strict: crate::config::DEFAULT_PROTOCOL_STRICT,
             watchdog: crate::config::WatchdogConfig::default(),
+            modem: crate::config::ModemConfig::default(),
+            scheduler: crate::config::SchedulerConfig::default(),
+            slo: crate::config::SloConfig::default(),
+            anomaly: crate::config::AnomalyConfig::default(),
+            arq: crate::serial::arq::ArqSettings::default(),
+            annunciators: crate::config::AnnunciatorConfig::default(),
+            parse_errors: crate::config::ParseErrorConfig::default(),
+            replay: crate::config::ReplayConfig::default(),
+            render: crate::config::RenderConfig::default(),
+            accessibility: crate::config::AccessibilityConfig::default(),
+            charmap: crate::config::CharmapConfig::default(),
+            filters: crate::config::FilterConfig::default(),
+            spool: crate::config::SpoolConfig::default(),
+            burst: crate::config::BurstConfig::default(),
+            troubleshoot: crate::config::TroubleshootConfig::default(),
+            poll_overlay: crate::config::PollOverlayConfig::default(),
+            interlock: crate::config::InterlockConfig::default(),
+            input: crate::config::InputConfig::default(),
+            lcd_gpio: crate::config::LcdGpioConfig::default(),
+            backlight: crate::config::BacklightConfig::default(),
+            control: crate::config::ControlConfig::default(),
+            metrics: crate::config::MetricsConfig::default(),
+            badges: crate::config::BadgesConfig::default(),
+            peer_defaults: crate::config::PeerDefaultsConfig::default(),
+            logging: crate::config::LoggingConfig::default(),
+            log_compaction: crate::config::LogCompactionConfig::default(),
+            tunnel_auth: crate::config::TunnelAuthConfig::default(),
+            relay: crate::config::RelayConfig::default(),
         }
     }
 }
@@ -114,40 +272,89 @@ pub struct App {
 
 impl App {
     pub fn new(config: AppConfig) -> Result<Self> {
-        let logger = Logger::new(config.log_level, config.log_file.clone())?;
+        let logger =
+            Logger::with_settings(config.log_level, config.log_file.clone(), config.logging)?;
         Ok(Self { config, logger })
     }
 
     pub fn from_options(opts: RunOptions) -> Result<Self> {
         wizard::maybe_run(&opts)?;
-        let cfg_file = if let Some(path) = opts.config_file.as_deref() {
-            Config::load_from_path(Path::new(path))?
+        let config_error = |err: crate::Error| err.exit_with(ExitCode::Config);
+        let (mut cfg_file, cfg_path) = if let Some(path) = opts.config_file.as_deref() {
+            (
+                Config::load_from_path(Path::new(path)).map_err(config_error)?,
+                PathBuf::from(path),
+            )
         } else {
-            Config::load_or_default()?
+            (
+                Config::load_or_default().map_err(config_error)?,
+                crate::config::loader::default_config_path()?,
+            )
         };
+        let identity = identity::ensure_install_id(&mut cfg_file, &cfg_path);
+        let lint_warnings = crate::config::lint::lint(&cfg_file);
         let merged = AppConfig::from_sources(cfg_file, opts);
-        crate::config::validate_baud(merged.baud)?;
-        Self::new(merged)
+        crate::config::validate_baud(merged.baud).map_err(config_error)?;
+        check_listen_exposure(&merged).map_err(config_error)?;
+        let app = Self::new(merged)?;
+        for warning in lint_warnings {
+            app.logger.warn(format!("config: {warning}"));
+        }
+        let install_id = &app.config.negotiation.install_id;
+        match identity {
+            Ok(true) => app.logger.info(format!(
+                "generated install_id {install_id} (saved to {})",
+                cfg_path.display()
+            )),
+            Ok(false) => {}
+            Err(err) => app.logger.warn(format!(
+                "install_id {install_id} could not be saved to {}: {err}; it will change on restart",
+                cfg_path.display()
+            )),
+        }
+        Ok(app)
     }
 
     /// Entry point for the daemon. Wire up serial + LCD here.
     pub fn run(&self) -> Result<()> {
         let mut config = self.config.clone();
+        crash::install_panic_hook();
+
+        if let Some(dir) = config.capture.as_deref() {
+            // Snapshots come from an in-memory display; no hardware is opened.
+            return capture_demo(&config, &self.logger, Path::new(dir));
+        }
 
-        let mut lcd = if config.lcd_present {
-            Lcd::new(
+        let mut diagnostics = StartupDiagnostics::new(&config.troubleshoot);
+        let mut lcd = if config.dry_run {
+            Lcd::new_terminal(config.cols, config.rows)
+        } else if config.lcd_present {
+            match Lcd::new(
                 config.cols,
                 config.rows,
                 config.pcf8574_addr.clone(),
                 config.display_driver,
-            )?
+                config.lcd_gpio,
+            ) {
+                Ok(lcd) => lcd,
+                Err(err) => {
+                    diagnostics.record_lcd_failure(&err, &config, &self.logger);
+                    return Err(err.exit_with(ExitCode::LcdInit));
+                }
+            }
         } else {
             Lcd::new_stub(config.cols, config.rows)
         };
+        lcd.set_echo_frames(config.echo_frames);
+        lcd.set_charmap(config.charmap.build()?);
         lcd.render_boot_message()?;
+        let node_id = config.negotiation.effective_node_id();
+        if config.rows > 1 {
+            lcd.write_line(1, &format!("node {node_id:08x}"))?;
+        }
         self.logger.info(format!(
-            "daemon start (device={}, baud={}, cols={}, rows={})",
-            config.device, config.baud, config.cols, config.rows
+            "daemon start (device={}, baud={}, cols={}, rows={}, node_id={node_id}, install_id={})",
+            config.device, config.baud, config.cols, config.rows, config.negotiation.install_id
         ));
 
         if config.demo {
@@ -156,6 +363,10 @@ impl App {
             return run_demo(&mut lcd, &mut config, &self.logger);
         }
 
+        if let Some(path) = config.payload_watch.clone() {
+            return run_payload_watch(&mut lcd, &config, &self.logger, &path);
+        }
+
         let mut backoff = BackoffController::new(config.backoff_initial_ms, config.backoff_max_ms);
 
         if let Some(path) = &config.payload_file {
@@ -171,10 +382,18 @@ impl App {
                 } else {
                     CompressionPolicy::disabled()
                 },
-            )?;
+            )
+            .map_err(|err| err.exit_with(ExitCode::InvalidPayload))?;
             lcd.set_backlight(frame.backlight_on)?;
             lcd.set_blink(frame.blink)?;
-            return render_frame_once(&mut lcd, &frame);
+            return render_frame_once(&mut lcd, &frame, config.render.bar_style);
+        }
+
+        if config.autobaud
+            && crate::serial::fifo::fifo_path(&config.device).is_none()
+            && crate::serial::tcp::tcp_addr(&config.device).is_none()
+        {
+            autobaud::apply(&mut config, &self.logger);
         }
 
         let mut negotiation_log = NegotiationLog::try_create().unwrap_or_else(|err| {
@@ -182,43 +401,66 @@ impl App {
                 .warn(format!("negotiation log unavailable: {err}"));
             NegotiationLog::disabled()
         });
+        let mut session = SessionCache::new(config.negotiation.resume_grace_ms);
+        let mut handshakes = HandshakeAudit::new();
 
-        let (serial_connection, initial_disconnect_reason, supports_heartbeat) =
-            match attempt_serial_connect(
-                &self.logger,
-                &config.device,
-                config.serial_options(),
-                &config.negotiation,
-                config.compression_enabled,
-                &mut negotiation_log,
-            ) {
-                Ok(outcome) => (
-                    Some(outcome.port),
-                    None,
-                    outcome
-                        .remote_caps
-                        .as_ref()
-                        .map(|caps| caps.supports_heartbeat)
-                        .unwrap_or(false),
-                ),
-                Err(reason) => (None, Some(reason), false),
-            };
+        let mut keepalive_ms =
+            keepalive_proposal_ms(config.baud, config.watchdog.serial_timeout_ms);
+        let (serial_connection, initial_disconnect_reason, features) = match attempt_serial_connect(
+            &self.logger,
+            &config.device,
+            config.serial_options(),
+            &config.negotiation,
+            config.compression_enabled,
+            keepalive_ms,
+            &mut negotiation_log,
+            &mut session,
+            &mut handshakes,
+        ) {
+            Ok(outcome) => {
+                diagnostics.record_success(&self.logger);
+                let features = FeatureMatrix::negotiate(&config, outcome.remote_caps.as_ref());
+                features.announce(&self.logger);
+                keepalive_ms = outcome.keepalive_ms;
+                (Some(outcome.port), None, features)
+            }
+            Err(reason) => {
+                diagnostics.record_failure(Component::Serial, reason, &config, &self.logger);
+                (None, Some(reason), FeatureMatrix::negotiate(&config, None))
+            }
+        };
         if serial_connection.is_none() {
             let now = Instant::now();
             backoff.mark_failure(now);
-            render_reconnecting(&mut lcd, config.cols)?;
+            diagnostics.render_link_down(&mut lcd, config.cols)?;
         }
 
-        run_render_loop(
-            &mut lcd,
-            &mut config,
-            &self.logger,
-            backoff,
-            serial_connection,
-            initial_disconnect_reason,
-            supports_heartbeat,
-            &mut negotiation_log,
-        )
+        // The hook has already written the crash report; make the failure visible and let
+        // systemd restart us.
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_render_loop(
+                &mut lcd,
+                &mut config,
+                &self.logger,
+                backoff,
+                serial_connection,
+                initial_disconnect_reason,
+                features,
+                &mut negotiation_log,
+                &mut session,
+                &mut handshakes,
+                keepalive_ms,
+                &mut diagnostics,
+            )
+        }))
+        .unwrap_or_else(|_| {
+            crash::render_crash_screen(&mut lcd);
+            Err(Error::Io(std::io::Error::other(format!(
+                "daemon panicked; report in {}",
+                crate::cache_dir().join(crash::CRASH_DIR).display()
+            )))
+            .exit_with(ExitCode::Crashed))
+        })
     }
 
     pub fn config(&self) -> &AppConfig {
@@ -226,32 +468,69 @@ impl App {
     }
 }
 
+/// A TCP listener other hosts can reach hands the command tunnel to whoever connects, so it
+/// needs something that stops a stranger running commands: tunnel auth, the interlock, or a
+/// pre-shared link key.
+fn check_listen_exposure(config: &AppConfig) -> Result<()> {
+    let Some(addr) = crate::serial::tcp::tcp_addr(&config.device) else {
+        return Ok(());
+    };
+    let loopback = addr
+        .parse::<std::net::SocketAddr>()
+        .is_ok_and(|addr| addr.ip().is_loopback());
+    let guarded = config.tunnel_auth.backend != TunnelAuthBackend::None
+        || config.interlock.required
+        || !config.negotiation.psk.is_empty();
+    if loopback || guarded {
+        return Ok(());
+    }
+    Err(Error::InvalidArgs(format!(
+        "--listen tcp:{addr} accepts clients from other hosts, and any of them could run commands; bind to 127.0.0.1 or [::1], or set a [tunnel_auth] backend, interlock.required or negotiation.psk"
+    )))
+}
+
 impl AppConfig {
     pub fn from_sources(config: Config, opts: RunOptions) -> Self {
         Self {
-            device: opts.device.unwrap_or_else(|| config.device.clone()),
+            device: opts
+                .fifo
+                .map(|path| format!("{}{path}", crate::serial::fifo::FIFO_PREFIX))
+                .or(opts
+                    .listen
+                    .map(|addr| format!("{}{addr}", crate::serial::tcp::TCP_PREFIX)))
+                .or(opts.device)
+                .unwrap_or_else(|| config.device.clone()),
             baud: opts.baud.unwrap_or(config.baud),
             flow_control: opts.flow_control.unwrap_or(config.flow_control),
             parity: opts.parity.unwrap_or(config.parity),
             stop_bits: opts.stop_bits.unwrap_or(config.stop_bits),
             dtr_on_open: opts.dtr_on_open.unwrap_or(config.dtr_on_open),
             serial_timeout_ms: opts.serial_timeout_ms.unwrap_or(config.serial_timeout_ms),
+            serial_read_min_bytes: config.serial_read_min_bytes,
+            serial_read_gap_ds: config.serial_read_gap_ds,
+            serial_low_latency: opts.low_latency.unwrap_or(config.serial_low_latency),
+            serial_tx_rate_percent: config.serial_tx_rate_percent,
+            autobaud: opts.autobaud.unwrap_or(config.autobaud),
             cols: opts.cols.unwrap_or(config.cols),
             rows: opts.rows.unwrap_or(config.rows),
             scroll_speed_ms: config.scroll_speed_ms,
             page_timeout_ms: config.page_timeout_ms,
+            frame_cache_size: config.frame_cache_size,
             polling_enabled: opts.polling_enabled.unwrap_or(config.polling_enabled),
             poll_interval_ms: opts.poll_interval_ms.unwrap_or(config.poll_interval_ms),
             button_gpio_pin: config.button_gpio_pin,
             payload_file: opts.payload_file,
+            payload_watch: opts.payload_watch,
             backoff_initial_ms: opts.backoff_initial_ms.unwrap_or(config.backoff_initial_ms),
             backoff_max_ms: opts.backoff_max_ms.unwrap_or(config.backoff_max_ms),
             negotiation: config.negotiation,
             pcf8574_addr: opts
                 .pcf8574_addr
                 .unwrap_or_else(|| config.pcf8574_addr.clone()),
-            display_driver: config.display_driver,
+            display_driver: opts.display_driver.unwrap_or(config.display_driver),
             lcd_present: config.lcd_present,
+            displays: config.displays,
+            zones: config.zones,
             log_level: opts
                 .log_level
                 .as_deref()
@@ -259,7 +538,13 @@ impl AppConfig {
                 .unwrap_or_default(),
             log_file: opts.log_file,
             demo: opts.demo,
+            capture: opts.capture,
+            capture_format: opts.capture_format.unwrap_or_default(),
+            dry_run: opts.dry_run,
+            echo_frames: opts.echo_frames,
+            trace: opts.trace,
             command_allowlist: config.command_allowlist.clone(),
+            tail_allowlist: config.tail_allowlist.clone(),
             serialsh: matches!(opts.mode, RunMode::SerialShell),
             protocol_schema_version: config.protocol.schema_version,
             compression_enabled: opts
@@ -268,7 +553,37 @@ impl AppConfig {
             compression_codec: opts
                 .compression_codec
                 .unwrap_or(config.protocol.compression_codec),
+            ack_enabled: config.protocol.ack_enabled,
+            protocol_strict: config.protocol.strict,
             watchdog: config.watchdog,
+            modem: config.modem,
+            scheduler: config.scheduler,
+            slo: config.slo.clone(),
+            anomaly: config.anomaly,
+            arq: config.arq,
+            annunciators: config.annunciators.clone(),
+            parse_errors: config.parse_errors,
+            replay: config.replay,
+            render: config.render,
+            accessibility: config.accessibility,
+            charmap: config.charmap,
+            filters: config.filters,
+            spool: config.spool.clone(),
+            burst: config.burst,
+            troubleshoot: config.troubleshoot,
+            poll_overlay: config.poll_overlay.clone(),
+            interlock: config.interlock.clone(),
+            input: config.input.clone(),
+            lcd_gpio: config.lcd_gpio,
+            backlight: config.backlight.clone(),
+            control: config.control.clone(),
+            metrics: config.metrics.clone(),
+            badges: config.badges.clone(),
+            peer_defaults: config.peer_defaults.clone(),
+            logging: config.logging,
+            log_compaction: config.log_compaction,
+            tunnel_auth: config.tunnel_auth,
+            relay: config.relay,
         }
     }
 
@@ -280,6 +595,11 @@ impl AppConfig {
             parity: self.parity,
             stop_bits: self.stop_bits,
             dtr: self.dtr_on_open,
+            read_min_bytes: self.serial_read_min_bytes,
+            read_gap_ds: self.serial_read_gap_ds,
+            low_latency: self.serial_low_latency,
+            arq: self.arq,
+            tx_rate_percent: self.serial_tx_rate_percent,
         }
     }
 }
@@ -290,7 +610,15 @@ fn load_payload_from_file(
     compression_policy: CompressionPolicy,
 ) -> Result<RenderFrame> {
     let raw = fs::read_to_string(path)?;
-    let normalized = crate::payload::normalize_payload_json_with_policy(&raw, compression_policy)?;
+    parse_payload(&raw, defaults, compression_policy)
+}
+
+fn parse_payload(
+    raw: &str,
+    defaults: PayloadDefaults,
+    compression_policy: CompressionPolicy,
+) -> Result<RenderFrame> {
+    let normalized = crate::payload::normalize_payload_json_with_policy(raw, compression_policy)?;
     RenderFrame::from_normalized_payload_with_defaults(&normalized, defaults)
 }
 
@@ -344,10 +672,16 @@ mod tests {
             stop_bits: StopBitsMode::default(),
             dtr_on_open: DtrBehavior::default(),
             serial_timeout_ms: DEFAULT_SERIAL_TIMEOUT_MS,
+            serial_read_min_bytes: DEFAULT_SERIAL_READ_MIN_BYTES,
+            serial_read_gap_ds: DEFAULT_SERIAL_READ_GAP_DS,
+            serial_low_latency: DEFAULT_SERIAL_LOW_LATENCY,
+            serial_tx_rate_percent: DEFAULT_SERIAL_TX_RATE_PERCENT,
+            autobaud: crate::config::DEFAULT_AUTOBAUD,
             cols: 16,
             rows: 2,
             scroll_speed_ms: crate::config::DEFAULT_SCROLL_MS,
             page_timeout_ms: crate::config::DEFAULT_PAGE_TIMEOUT_MS,
+            frame_cache_size: crate::config::DEFAULT_FRAME_CACHE_SIZE,
             polling_enabled: crate::config::DEFAULT_POLLING_ENABLED,
             poll_interval_ms: crate::config::DEFAULT_POLL_INTERVAL_MS,
             button_gpio_pin: None,
@@ -357,9 +691,40 @@ mod tests {
             pcf8574_addr: crate::config::DEFAULT_PCF8574_ADDR,
             display_driver: crate::config::DEFAULT_DISPLAY_DRIVER,
             lcd_present: crate::config::DEFAULT_LCD_PRESENT,
+            displays: Vec::new(),
+            zones: Vec::new(),
             command_allowlist: Vec::new(),
+            tail_allowlist: Vec::new(),
             protocol: crate::config::ProtocolConfig::default(),
             watchdog: crate::config::WatchdogConfig::default(),
+            modem: crate::config::ModemConfig::default(),
+            scheduler: crate::config::SchedulerConfig::default(),
+            slo: crate::config::SloConfig::default(),
+            anomaly: crate::config::AnomalyConfig::default(),
+            arq: crate::serial::arq::ArqSettings::default(),
+            annunciators: crate::config::AnnunciatorConfig::default(),
+            parse_errors: crate::config::ParseErrorConfig::default(),
+            replay: crate::config::ReplayConfig::default(),
+            render: crate::config::RenderConfig::default(),
+            accessibility: crate::config::AccessibilityConfig::default(),
+            charmap: crate::config::CharmapConfig::default(),
+            filters: crate::config::FilterConfig::default(),
+            spool: crate::config::SpoolConfig::default(),
+            burst: crate::config::BurstConfig::default(),
+            troubleshoot: crate::config::TroubleshootConfig::default(),
+            poll_overlay: crate::config::PollOverlayConfig::default(),
+            interlock: crate::config::InterlockConfig::default(),
+            control: crate::config::ControlConfig::default(),
+            metrics: crate::config::MetricsConfig::default(),
+            input: crate::config::InputConfig::default(),
+            lcd_gpio: crate::config::LcdGpioConfig::default(),
+            backlight: crate::config::BacklightConfig::default(),
+            badges: crate::config::BadgesConfig::default(),
+            peer_defaults: crate::config::PeerDefaultsConfig::default(),
+            logging: crate::config::LoggingConfig::default(),
+            log_compaction: crate::config::LogCompactionConfig::default(),
+            tunnel_auth: crate::config::TunnelAuthConfig::default(),
+            relay: crate::config::RelayConfig::default(),
         };
         let opts = RunOptions::default();
         let merged = AppConfig::from_sources(cfg_file.clone(), opts);
@@ -442,6 +807,31 @@ mod tests {
         assert_eq!(app.config().baud, 57_600);
     }
 
+    #[test]
+    fn network_listeners_need_a_command_guard() {
+        let listen = |addr: &str, cfg: Config| {
+            let opts = RunOptions {
+                listen: Some(addr.into()),
+                ..RunOptions::default()
+            };
+            check_listen_exposure(&AppConfig::from_sources(cfg, opts))
+        };
+        assert!(listen("127.0.0.1:7777", Config::default()).is_ok());
+        assert!(listen("[::1]:7777", Config::default()).is_ok());
+        let err = listen("0.0.0.0:7777", Config::default()).unwrap_err();
+        assert!(format!("{err}").contains("tunnel_auth"), "{err}");
+
+        let mut guarded = Config::default();
+        guarded.tunnel_auth.backend = TunnelAuthBackend::Totp;
+        assert!(listen("0.0.0.0:7777", guarded).is_ok());
+        let mut guarded = Config::default();
+        guarded.interlock.required = true;
+        assert!(listen("0.0.0.0:7777", guarded).is_ok());
+        let mut guarded = Config::default();
+        guarded.negotiation.psk = "rack 7 shared key!".into();
+        assert!(listen("[::]:7777", guarded).is_ok());
+    }
+
     #[test]
     fn rejects_cli_baud_below_minimum() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,90 @@
+use crate::serial::ModemLines;
+use std::time::{Duration, Instant};
+
+/// Periodic sampler for modem status lines; reports only transitions.
+#[derive(Debug, Clone)]
+pub struct ModemMonitor {
+    interval: Duration,
+    next_poll: Instant,
+    last: Option<ModemLines>,
+}
+
+impl ModemMonitor {
+    /// Build a monitor when sampling is enabled (`poll_ms > 0`).
+    pub fn new(poll_ms: u64) -> Option<Self> {
+        if poll_ms == 0 {
+            return None;
+        }
+        Some(Self {
+            interval: Duration::from_millis(poll_ms),
+            next_poll: Instant::now(),
+            last: None,
+        })
+    }
+
+    /// Whether the next sample is due; advances the schedule when it is.
+    pub fn due(&mut self, now: Instant) -> bool {
+        if now < self.next_poll {
+            return false;
+        }
+        self.next_poll = now + self.interval;
+        true
+    }
+
+    /// Record a sample. Returns `true` when it differs from the previous one.
+    pub fn observe(&mut self, lines: ModemLines) -> bool {
+        let changed = self.last != Some(lines);
+        self.last = Some(lines);
+        changed
+    }
+
+    /// Forget the last sample (e.g. after the port is reopened).
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.next_poll = Instant::now();
+    }
+
+    pub fn glyph(&self) -> Option<char> {
+        self.last.and_then(|lines| lines.glyph())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(cts: bool) -> ModemLines {
+        ModemLines {
+            cts,
+            dsr: true,
+            cd: true,
+            ri: false,
+        }
+    }
+
+    #[test]
+    fn disabled_when_interval_zero() {
+        assert!(ModemMonitor::new(0).is_none());
+    }
+
+    #[test]
+    fn reports_only_transitions() {
+        let mut monitor = ModemMonitor::new(250).unwrap();
+        assert!(monitor.observe(lines(true)));
+        assert!(!monitor.observe(lines(true)));
+        assert!(monitor.observe(lines(false)));
+        assert_eq!(monitor.glyph(), Some('c'));
+        monitor.reset();
+        assert_eq!(monitor.glyph(), None);
+        assert!(monitor.observe(lines(false)));
+    }
+
+    #[test]
+    fn due_respects_interval() {
+        let mut monitor = ModemMonitor::new(1_000).unwrap();
+        let now = Instant::now();
+        assert!(monitor.due(now));
+        assert!(!monitor.due(now + Duration::from_millis(10)));
+        assert!(monitor.due(now + Duration::from_millis(1_000)));
+    }
+}
@@ -0,0 +1,325 @@
+//! `lifelinetty monitor`: a live dashboard for operators SSHed into the Pi.
+//!
+//! The monitor sends `{"cmd":"snapshot"}` over the control socket every `--interval` and draws
+//! the reply full-screen. It shows the rows on the LCD, the page queue, the link state, the
+//! render loop counters and the most recent log lines. `q`, Esc or Ctrl-C quits and restores
+//! the terminal. When the daemon stops answering, the last snapshot stays up with the error in
+//! the header and the monitor keeps trying to reconnect.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+use serde_json::{json, Value};
+
+use crate::{config::Config, Error, ExitCode, Result};
+
+/// How long a snapshot may take before the daemon counts as unresponsive.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(3);
+/// Queued pages listed before the rest are summarised.
+const QUEUE_LINES: usize = 6;
+
+pub fn run(interval_ms: u64) -> Result<()> {
+    let config = Config::load_or_default().map_err(|err| err.exit_with(ExitCode::Config))?;
+    if !config.control.enabled {
+        return Err(Error::InvalidArgs(
+            "the control socket is off; set [control] enabled = true and restart the daemon".into(),
+        )
+        .exit_with(ExitCode::Config));
+    }
+    let socket = PathBuf::from(&config.control.socket);
+    // Fail before taking over the terminal when nothing is listening at all.
+    let client = ControlClient::connect(&socket).map_err(|err| unreachable(&socket, err))?;
+    let _screen = FullScreen::enter()?;
+    watch(client, &socket, Duration::from_millis(interval_ms))
+}
+
+fn watch(client: ControlClient, socket: &Path, interval: Duration) -> Result<()> {
+    let mut client = Some(client);
+    let mut last = Value::Null;
+    loop {
+        let connected = match client.take() {
+            Some(client) => Ok(client),
+            None => ControlClient::connect(socket),
+        };
+        let status =
+            match connected.and_then(|mut client| client.snapshot().map(|reply| (client, reply))) {
+                Ok((connected, reply)) => {
+                    client = Some(connected);
+                    last = reply;
+                    format!("updated {}", chrono::Local::now().format("%H:%M:%S"))
+                }
+                Err(err) => format!("daemon unreachable: {err}"),
+            };
+        let (width, height) = terminal::size()?;
+        let title = format!("lifelinetty monitor - {}", socket.display());
+        draw(&dashboard(&last, &title, &status, width, height))?;
+        if quit_requested(interval)? {
+            return Ok(());
+        }
+    }
+}
+
+fn unreachable(socket: &Path, err: io::Error) -> Error {
+    Error::Io(io::Error::new(
+        err.kind(),
+        format!(
+            "cannot reach the daemon at {}: {err}; is it running with [control] enabled?",
+            socket.display()
+        ),
+    ))
+}
+
+/// One connection to the control socket, reused for every snapshot.
+struct ControlClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl ControlClient {
+    fn connect(socket: &Path) -> io::Result<Self> {
+        let writer = UnixStream::connect(socket)?;
+        writer.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        Ok(Self {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    fn snapshot(&mut self) -> io::Result<Value> {
+        let mut request = json!({ "cmd": "snapshot" }).to_string();
+        request.push('\n');
+        self.writer.write_all(request.as_bytes())?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the daemon closed the connection",
+            ));
+        }
+        let reply: Value = serde_json::from_str(&line)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if reply["ok"] != json!(true) {
+            let error = reply["error"].as_str().unwrap_or("snapshot refused");
+            return Err(io::Error::other(error.to_string()));
+        }
+        Ok(reply)
+    }
+}
+
+/// Raw mode on the alternate screen for as long as it lives.
+struct FullScreen;
+
+impl FullScreen {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        let screen = Self;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for FullScreen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw(lines: &[String]) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    queue!(out, Clear(ClearType::All))?;
+    for (row, line) in lines.iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, row as u16), Print(line))?;
+    }
+    out.flush()
+}
+
+/// Wait out `interval`, returning early with `true` on `q`, Esc or Ctrl-C. A resize also ends
+/// the wait so the dashboard is redrawn at the new size.
+fn quit_requested(interval: Duration) -> io::Result<bool> {
+    let deadline = Instant::now() + interval;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() || !event::poll(left)? {
+            return Ok(false);
+        }
+        match event::read()? {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                let ctrl_c =
+                    code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL);
+                if ctrl_c || matches!(code, KeyCode::Char('q') | KeyCode::Esc) {
+                    return Ok(true);
+                }
+            }
+            Event::Resize(..) => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+/// The dashboard for `snapshot`, one string per terminal row, cut to `width` x `height`.
+fn dashboard(snapshot: &Value, title: &str, status: &str, width: u16, height: u16) -> Vec<String> {
+    let mut lines = vec![format!("{title}  [{status}]"), String::new()];
+    let stats = &snapshot["stats"];
+
+    let screen: Vec<&str> = snapshot["screen"]
+        .as_array()
+        .map(|rows| rows.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let cols = screen.first().map_or(0, |row| row.chars().count());
+    let on_off = |value: &Value| if value == &json!(true) { "on" } else { "off" };
+    lines.push(format!(
+        "LCD  backlight {}{}  hud {}",
+        on_off(&stats["backlight"]),
+        if stats["backlight_dimmed"] == json!(true) {
+            " (dimmed)"
+        } else {
+            ""
+        },
+        on_off(&stats["hud"]),
+    ));
+    lines.push(format!("  ┌{}┐", "─".repeat(cols)));
+    lines.extend(screen.iter().map(|row| format!("  │{row}│")));
+    lines.push(format!("  └{}┘", "─".repeat(cols)));
+
+    let link = &snapshot["link"];
+    let serial = if link["serial_connected"] == json!(true) {
+        "connected".to_string()
+    } else {
+        format!(
+            "down, retry in {}ms",
+            link["reconnect_in_ms"].as_u64().unwrap_or(0)
+        )
+    };
+    lines.push(format!(
+        "Link  {} @ {}  {serial}  tunnel {}",
+        link["device"].as_str().unwrap_or("?"),
+        link["baud"],
+        if link["tunnel_up"] == json!(true) {
+            "up"
+        } else {
+            "down"
+        }
+    ));
+
+    let queue = snapshot["queue"].as_array().map_or(&[][..], Vec::as_slice);
+    lines.push(format!("Queue  {} page(s)", queue.len()));
+    for (idx, page) in queue.iter().take(QUEUE_LINES).enumerate() {
+        let marker = if idx == 0 { '>' } else { ' ' };
+        lines.push(format!(
+            "  {marker} {} | {}",
+            page[0].as_str().unwrap_or(""),
+            page[1].as_str().unwrap_or("")
+        ));
+    }
+    if queue.len() > QUEUE_LINES {
+        lines.push(format!("    ... {} more", queue.len() - QUEUE_LINES));
+    }
+
+    lines.push("Stats".into());
+    let counters: Vec<String> = stats
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, value)| value.is_u64())
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    lines.extend(wrap(&counters, width as usize));
+
+    lines.push("Log".into());
+    let log: Vec<&str> = snapshot["log"]
+        .as_array()
+        .map(|lines| lines.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let room = (height as usize).saturating_sub(lines.len());
+    lines.extend(
+        log[log.len().saturating_sub(room)..]
+            .iter()
+            .map(|line| format!("  {line}")),
+    );
+
+    lines.truncate(height as usize);
+    for line in &mut lines {
+        if line.chars().count() > width as usize {
+            *line = line.chars().take(width as usize).collect();
+        }
+    }
+    lines
+}
+
+/// `items` packed into indented lines no wider than `width`.
+fn wrap(items: &[String], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::from(" ");
+    for item in items {
+        if line.len() > 1 && line.len() + 2 + item.len() > width {
+            lines.push(std::mem::replace(&mut line, String::from(" ")));
+        }
+        line.push_str("  ");
+        line.push_str(item);
+    }
+    if line.len() > 1 {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dashboard_lays_out_the_snapshot_and_fits_the_terminal() {
+        let snapshot = json!({
+            "ok": true,
+            "stats": {
+                "frames_accepted": 12,
+                "frames_rejected": 1,
+                "backlight": true,
+                "backlight_dimmed": false,
+                "hud": false,
+            },
+            "screen": ["CPU 42%         ", "up 3d          ♥"],
+            "queue": [["CPU 42%", "up 3d"], ["eth0", "12 Mb/s"]],
+            "link": {
+                "device": "/dev/ttyUSB0",
+                "baud": 115200,
+                "serial_connected": false,
+                "reconnect_in_ms": 800,
+                "tunnel_up": true,
+            },
+            "log": ["[1.0] [Info] one", "[2.0] [Info] two", "[3.0] [Warn] three"],
+        });
+        let lines = dashboard(&snapshot, "lifelinetty monitor", "updated", 80, 40);
+        let text = lines.join("\n");
+        assert!(text.contains("LCD  backlight on  hud off"));
+        assert!(text.contains("  │up 3d          ♥│"));
+        assert!(text.contains("Link  /dev/ttyUSB0 @ 115200  down, retry in 800ms  tunnel up"));
+        assert!(text.contains("  > CPU 42% | up 3d\n    eth0 | 12 Mb/s"));
+        assert!(text.contains("   frames_accepted=12  frames_rejected=1"));
+        assert!(text.ends_with("  [3.0] [Warn] three"));
+
+        // A short terminal keeps the newest log line that still fits.
+        let short = dashboard(&snapshot, "t", "s", 20, 16);
+        assert_eq!(short.len(), 16);
+        assert_eq!(short.last().unwrap(), "  [3.0] [Warn] three");
+        assert!(short.iter().all(|line| line.chars().count() <= 20));
+        assert_eq!(dashboard(&Value::Null, "t", "s", 80, 3).len(), 3);
+    }
+}
@@ -1,36 +1,111 @@
+use super::{
+    digest::{constant_time_eq, hmac_sha256, to_hex},
+    file_transfer::from_hex,
+};
 use crate::{
-    config::NegotiationConfig,
+    cache_dir,
+    config::{identity::fill_random, NegotiationConfig},
     negotiation::{
         Capabilities, ControlCaps, ControlFrame, Role, RolePreference, PROTOCOL_VERSION,
     },
-    CACHE_DIR,
+    serial::cipher::LinkKeys,
 };
 use std::{
     fs::{self, OpenOptions},
     io::Write,
-    path::Path,
     str::FromStr,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+/// Heartbeats never go out more often than this.
+pub const KEEPALIVE_MIN_MS: u64 = 500;
+/// Aim for three heartbeats per watchdog timeout.
+const KEEPALIVE_TIMEOUT_DIVISOR: u64 = 3;
+/// A heartbeat command frame is roughly 64 bytes, or 640 bits with 8N1 framing.
+const KEEPALIVE_FRAME_BITS: u64 = 640;
+/// Keep heartbeats under 1/50 (2%) of the link's airtime.
+const KEEPALIVE_AIRTIME_SHARE: u64 = 50;
+/// Bytes of randomness each side adds to the link keys.
+const LINK_NONCE_BYTES: usize = 16;
+/// Key schedule label; changing it makes every derived key incompatible with older peers.
+const LINK_KEY_LABEL: &[u8] = b"lifelinetty link v1";
+
+/// Heartbeat interval this node proposes for a link at `baud` watched by `timeout_ms`.
+///
+/// Slow links get sparser heartbeats so keepalives do not crowd out frames, but the interval
+/// never exceeds half the timeout so a single lost heartbeat cannot trip the watchdog.
+pub fn keepalive_proposal_ms(baud: u32, timeout_ms: u64) -> u64 {
+    let cadence = timeout_ms / KEEPALIVE_TIMEOUT_DIVISOR;
+    let airtime_ms = (KEEPALIVE_FRAME_BITS * 1_000).div_ceil(u64::from(baud.max(1)));
+    cadence
+        .max(airtime_ms * KEEPALIVE_AIRTIME_SHARE)
+        .min(timeout_ms / 2)
+        .max(KEEPALIVE_MIN_MS)
+}
+
+/// Interval both peers settle on: the smaller proposal, so neither watchdog starves. Peers
+/// that predate the field keep their own cadence, so ours stands alone.
+pub fn agree_keepalive_ms(local_ms: u64, peer_ms: Option<u64>) -> u64 {
+    peer_ms
+        .filter(|ms| *ms > 0)
+        .map_or(local_ms, |peer| peer.min(local_ms))
+        .max(KEEPALIVE_MIN_MS)
+}
+
 /// Tracks the local node's handshake capabilities, node ID, and preference.
 pub struct Negotiator {
     local_caps: Capabilities,
     preference: RolePreference,
     node_id: u32,
+    install_id: Option<String>,
+    session_token: Option<String>,
+    keepalive_ms: Option<u64>,
+    /// HMAC key derived from `negotiation.psk`, with this session's nonce.
+    link_secret: Option<([u8; 32], [u8; LINK_NONCE_BYTES])>,
 }
 
 impl Negotiator {
-    pub fn new(config: &NegotiationConfig, compression_enabled: bool) -> Self {
-        Self {
+    /// `keepalive_ms` is the heartbeat interval to propose in `hello`, if any. Fails when the
+    /// OS random source cannot supply the link nonce or session token.
+    pub fn new(
+        config: &NegotiationConfig,
+        compression_enabled: bool,
+        keepalive_ms: Option<u64>,
+    ) -> crate::Result<Self> {
+        let node_id = config.effective_node_id();
+        let link_secret = if config.psk.is_empty() {
+            None
+        } else {
+            let mut nonce = [0u8; LINK_NONCE_BYTES];
+            fill_random(&mut nonce)?;
+            Some((hmac_sha256(config.psk.as_bytes(), LINK_KEY_LABEL), nonce))
+        };
+        // A resumed session would have no link keys, so encrypted links always renegotiate.
+        let session_token = if config.resume_grace_ms > 0 && link_secret.is_none() {
+            Some(new_session_token(node_id)?)
+        } else {
+            None
+        };
+        Ok(Self {
             local_caps: Capabilities {
                 supports_tunnel: true,
                 supports_compression: compression_enabled,
                 supports_heartbeat: true,
+                // Receiving ARQ frames is always supported; sending follows `[arq].mode`.
+                supports_arq: true,
+                // Following a peer's burst is always supported; requesting one follows `[burst]`.
+                supports_burst: true,
+                // Receiving anomaly summaries is always supported; sending follows `[anomaly]`.
+                supports_anomaly: true,
+                supports_crypto: link_secret.is_some(),
             },
             preference: config.preference,
-            node_id: config.node_id,
-        }
+            node_id,
+            install_id: (!config.install_id.is_empty()).then(|| config.install_id.clone()),
+            session_token,
+            keepalive_ms,
+            link_secret,
+        })
     }
 
     pub fn hello_frame(&self) -> ControlFrame {
@@ -41,7 +116,69 @@ impl Negotiator {
                 bits: self.local_caps.bits(),
             },
             pref: self.preference.as_str().to_string(),
+            session: self.session_token.clone(),
+            keepalive_ms: self.keepalive_ms,
+            install_id: self.install_id.clone(),
+            link_nonce: self.link_secret.map(|(_, nonce)| to_hex(&nonce)),
+        }
+    }
+
+    /// The `link_proof` for our `hello_ack`, once the peer's `hello` brought its nonce.
+    pub fn link_proof(&self, peer_nonce: &str) -> Option<String> {
+        let (secret, local) = self.link_secret?;
+        let peer = parse_link_nonce(peer_nonce, &local)?;
+        Some(to_hex(&link_mac(&secret, b"proof", &local, &peer)))
+    }
+
+    /// Session keys for the link, or why there are none. `Ok(None)` when no PSK is
+    /// configured; an error when one is but the peer did not prove it holds the same one.
+    pub fn link_keys(
+        &self,
+        peer_nonce: Option<&str>,
+        peer_proof: Option<&str>,
+    ) -> Result<Option<LinkKeys>, &'static str> {
+        let Some((secret, local)) = self.link_secret else {
+            return Ok(None);
+        };
+        let (Some(peer_nonce), Some(peer_proof)) = (peer_nonce, peer_proof) else {
+            return Err("peer did not offer link encryption");
+        };
+        let peer = parse_link_nonce(peer_nonce, &local).ok_or("peer sent an invalid link_nonce")?;
+        let expected = link_mac(&secret, b"proof", &peer, &local);
+        let proof = from_hex(peer_proof).unwrap_or_default();
+        if !constant_time_eq(&expected, &proof) {
+            return Err("peer holds a different negotiation.psk");
+        }
+        Ok(Some(LinkKeys {
+            send: link_mac(&secret, b"key", &local, &peer),
+            recv: link_mac(&secret, b"key", &peer, &local),
+        }))
+    }
+
+    /// Warning to log when a peer's `hello` carries this node's ID.
+    pub fn identity_clash(
+        &self,
+        peer_node_id: u32,
+        peer_install_id: Option<&str>,
+    ) -> Option<String> {
+        if peer_node_id != self.node_id {
+            return None;
         }
+        Some(match (self.install_id.as_deref(), peer_install_id) {
+            (Some(local), Some(peer)) if local == peer => format!(
+                "peer reports our install_id {local}; this install was probably cloned. \
+                 Clear negotiation.install_id on one unit to generate a new identity"
+            ),
+            _ => format!(
+                "peer uses our node_id {peer_node_id}, so role election cannot break ties. \
+                 Set negotiation.node_id to 0 or a unique value on one unit"
+            ),
+        })
+    }
+
+    /// Token this node issues for the session being negotiated, when resume is enabled.
+    pub fn session_token(&self) -> Option<&str> {
+        self.session_token.as_deref()
     }
 
     pub fn local_caps(&self) -> &Capabilities {
@@ -79,6 +216,127 @@ pub struct NegotiationDecision {
     pub remote_role: Role,
 }
 
+/// Resume token: the node ID for readability, then 96 random bits a peer cannot guess.
+fn new_session_token(node_id: u32) -> crate::Result<String> {
+    let mut random = [0u8; 12];
+    fill_random(&mut random)?;
+    Ok(format!("{node_id:08x}{}", to_hex(&random)))
+}
+
+/// Decode a peer nonce. Our own nonce coming back means the link is looped or reflected.
+fn parse_link_nonce(raw: &str, local: &[u8; LINK_NONCE_BYTES]) -> Option<[u8; LINK_NONCE_BYTES]> {
+    let nonce: [u8; LINK_NONCE_BYTES] = from_hex(raw)?.try_into().ok()?;
+    (nonce != *local).then_some(nonce)
+}
+
+/// HMAC over `label`, then the nonce of the side the value speaks for, then the other one.
+fn link_mac(
+    secret: &[u8; 32],
+    label: &[u8],
+    from: &[u8; LINK_NONCE_BYTES],
+    to: &[u8; LINK_NONCE_BYTES],
+) -> [u8; 32] {
+    let mut message = label.to_vec();
+    message.extend_from_slice(from);
+    message.extend_from_slice(to);
+    hmac_sha256(secret, &message)
+}
+
+/// Negotiated session kept across reconnects so a brief glitch can resume it with one frame.
+#[derive(Debug, Clone)]
+struct CachedSession {
+    local_token: String,
+    peer_token: String,
+    role: Role,
+    remote_caps: Capabilities,
+    keepalive_ms: u64,
+    lost_at: Option<Instant>,
+    resumes: u32,
+}
+
+/// Holds the last negotiated session while the link is down.
+#[derive(Debug, Clone)]
+pub struct SessionCache {
+    grace: Duration,
+    session: Option<CachedSession>,
+}
+
+impl SessionCache {
+    pub fn new(resume_grace_ms: u64) -> Self {
+        Self {
+            grace: Duration::from_millis(resume_grace_ms),
+            session: None,
+        }
+    }
+
+    /// Remember a freshly negotiated session; both sides must have issued a token.
+    pub fn store(
+        &mut self,
+        local_token: &str,
+        peer_token: &str,
+        role: Role,
+        caps: Capabilities,
+        keepalive_ms: u64,
+    ) {
+        self.session = Some(CachedSession {
+            local_token: local_token.to_string(),
+            peer_token: peer_token.to_string(),
+            role,
+            remote_caps: caps,
+            keepalive_ms,
+            lost_at: None,
+            resumes: 0,
+        });
+    }
+
+    /// Start the grace window; called when the link drops.
+    pub fn mark_lost(&mut self, now: Instant) {
+        if let Some(session) = self.session.as_mut() {
+            session.lost_at.get_or_insert(now);
+        }
+    }
+
+    pub fn forget(&mut self) {
+        self.session = None;
+    }
+
+    fn live(&self, now: Instant) -> Option<&CachedSession> {
+        if self.grace.is_zero() {
+            return None;
+        }
+        self.session.as_ref().filter(|session| {
+            session
+                .lost_at
+                .is_none_or(|lost| now.saturating_duration_since(lost) <= self.grace)
+        })
+    }
+
+    /// Peer-issued token to present in a `resume` frame, while the grace window is open.
+    pub fn resume_token(&self, now: Instant) -> Option<&str> {
+        self.live(now).map(|session| session.peer_token.as_str())
+    }
+
+    /// Whether a peer's `resume` names the session this node issued.
+    pub fn accepts(&self, token: &str, now: Instant) -> bool {
+        self.live(now)
+            .is_some_and(|session| session.local_token == token)
+    }
+
+    /// Reopen the cached session; returns its role, peer capabilities, resume count and
+    /// keepalive interval.
+    pub fn restore(&mut self) -> Option<(Role, Capabilities, u32, u64)> {
+        let session = self.session.as_mut()?;
+        session.lost_at = None;
+        session.resumes += 1;
+        Some((
+            session.role.clone(),
+            session.remote_caps.clone(),
+            session.resumes,
+            session.keepalive_ms,
+        ))
+    }
+}
+
 /// A parsed hello frame from the remote peer.
 pub struct RemoteHello {
     pub node_id: u32,
@@ -116,7 +374,7 @@ pub struct NegotiationLog {
 
 impl NegotiationLog {
     pub fn try_create() -> std::io::Result<Self> {
-        let log_path = Path::new(CACHE_DIR).join("logs").join("negotiation.log");
+        let log_path = cache_dir().join("logs").join("negotiation.log");
         if let Some(parent) = log_path.parent() {
             fs::create_dir_all(parent)?;
         }
@@ -142,3 +400,185 @@ impl NegotiationLog {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_cache_expires_after_grace() {
+        let mut cache = SessionCache::new(1_000);
+        let now = Instant::now();
+        cache.store(
+            "mine",
+            "theirs",
+            Role::Server,
+            Capabilities::default(),
+            4_000,
+        );
+        cache.mark_lost(now);
+        assert_eq!(cache.resume_token(now), Some("theirs"));
+        assert!(cache.accepts("mine", now));
+        assert!(!cache.accepts("theirs", now));
+        assert!(cache
+            .resume_token(now + Duration::from_millis(1_001))
+            .is_none());
+    }
+
+    #[test]
+    fn restore_counts_resumes_and_reopens_the_window() {
+        let mut cache = SessionCache::new(1_000);
+        let now = Instant::now();
+        cache.store(
+            "mine",
+            "theirs",
+            Role::Client,
+            Capabilities::from_bits(1),
+            2_500,
+        );
+        cache.mark_lost(now);
+        let (role, caps, resumes, keepalive_ms) = cache.restore().unwrap();
+        assert_eq!(role, Role::Client);
+        assert_eq!(caps.bits(), 1);
+        assert_eq!(resumes, 1);
+        assert_eq!(keepalive_ms, 2_500);
+        cache.mark_lost(now + Duration::from_secs(5));
+        assert!(cache.resume_token(now + Duration::from_secs(5)).is_some());
+        assert_eq!(cache.restore().unwrap().2, 2);
+    }
+
+    #[test]
+    fn zero_grace_disables_resume() {
+        let mut cache = SessionCache::new(0);
+        cache.store(
+            "mine",
+            "theirs",
+            Role::Server,
+            Capabilities::default(),
+            4_000,
+        );
+        assert!(cache.resume_token(Instant::now()).is_none());
+        let negotiator = Negotiator::new(
+            &NegotiationConfig {
+                resume_grace_ms: 0,
+                ..NegotiationConfig::default()
+            },
+            false,
+            None,
+        )
+        .unwrap();
+        assert!(negotiator.session_token().is_none());
+    }
+
+    #[test]
+    fn session_tokens_are_random_past_the_node_id() {
+        let config = NegotiationConfig {
+            node_id: 0x1234,
+            ..NegotiationConfig::default()
+        };
+        let first = Negotiator::new(&config, false, None).unwrap();
+        let second = Negotiator::new(&config, false, None).unwrap();
+        let (first, second) = (
+            first.session_token().unwrap(),
+            second.session_token().unwrap(),
+        );
+        assert!(first.starts_with("00001234"));
+        assert_eq!(first.len(), 32);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn keepalive_scales_with_baud_and_timeout() {
+        // Fast links follow the watchdog: a third of the timeout.
+        assert_eq!(keepalive_proposal_ms(115_200, 12_000), 4_000);
+        assert_eq!(keepalive_proposal_ms(115_200, 1_000), KEEPALIVE_MIN_MS);
+        // 1200 baud needs ~534ms per heartbeat, so it backs off to half the timeout.
+        assert_eq!(keepalive_proposal_ms(1_200, 12_000), 6_000);
+        assert!(keepalive_proposal_ms(9_600, 6_000) > keepalive_proposal_ms(115_200, 6_000));
+    }
+
+    #[test]
+    fn agreed_keepalive_takes_the_smaller_proposal() {
+        assert_eq!(agree_keepalive_ms(4_000, Some(2_000)), 2_000);
+        assert_eq!(agree_keepalive_ms(2_000, Some(4_000)), 2_000);
+        assert_eq!(agree_keepalive_ms(4_000, None), 4_000);
+        assert_eq!(agree_keepalive_ms(4_000, Some(0)), 4_000);
+        assert_eq!(agree_keepalive_ms(4_000, Some(10)), KEEPALIVE_MIN_MS);
+    }
+
+    #[test]
+    fn hello_carries_the_derived_identity_and_flags_clashes() {
+        let install_id = "1a2b3c4d-0000-4000-8000-000000000000";
+        let negotiator = Negotiator::new(
+            &NegotiationConfig {
+                install_id: install_id.into(),
+                ..NegotiationConfig::default()
+            },
+            false,
+            None,
+        )
+        .unwrap();
+        match negotiator.hello_frame() {
+            ControlFrame::Hello {
+                node_id,
+                install_id: sent,
+                ..
+            } => {
+                assert_eq!(node_id, 0x1a2b_3c4d);
+                assert_eq!(sent.as_deref(), Some(install_id));
+            }
+            other => panic!("unexpected frame {other:?}"),
+        }
+        assert!(negotiator.identity_clash(7, Some(install_id)).is_none());
+        assert!(negotiator
+            .identity_clash(0x1a2b_3c4d, Some(install_id))
+            .unwrap()
+            .contains("cloned"));
+        assert!(negotiator
+            .identity_clash(0x1a2b_3c4d, None)
+            .unwrap()
+            .contains("node_id"));
+    }
+
+    #[test]
+    fn matching_psks_derive_mirrored_link_keys() {
+        let with_psk = |psk: &str| NegotiationConfig {
+            psk: psk.into(),
+            ..NegotiationConfig::default()
+        };
+        let nonce = |negotiator: &Negotiator| match negotiator.hello_frame() {
+            ControlFrame::Hello { link_nonce, .. } => link_nonce.unwrap(),
+            other => panic!("unexpected frame {other:?}"),
+        };
+        let a = Negotiator::new(&with_psk("rack 7 shared key!"), false, None).unwrap();
+        let b = Negotiator::new(&with_psk("rack 7 shared key!"), false, None).unwrap();
+        assert!(a.local_caps().supports_crypto);
+        assert!(a.session_token().is_none());
+        let (nonce_a, nonce_b) = (nonce(&a), nonce(&b));
+        let proof_a = a.link_proof(&nonce_b).unwrap();
+        let proof_b = b.link_proof(&nonce_a).unwrap();
+
+        let keys_a = a
+            .link_keys(Some(&nonce_b), Some(&proof_b))
+            .unwrap()
+            .unwrap();
+        let keys_b = b
+            .link_keys(Some(&nonce_a), Some(&proof_a))
+            .unwrap()
+            .unwrap();
+        assert_eq!(keys_a.send, keys_b.recv);
+        assert_eq!(keys_a.recv, keys_b.send);
+        assert_ne!(keys_a.send, keys_a.recv);
+
+        let other = Negotiator::new(&with_psk("a different passphrase"), false, None).unwrap();
+        let forged = other.link_proof(&nonce_a).unwrap();
+        assert!(a.link_keys(Some(&nonce(&other)), Some(&forged)).is_err());
+        assert!(a.link_keys(None, None).is_err());
+        // A looped-back hello carries our own nonce and must not pass.
+        assert!(a.link_proof(&nonce_a).is_none());
+
+        let plain = Negotiator::new(&NegotiationConfig::default(), false, None).unwrap();
+        assert!(!plain.local_caps().supports_crypto);
+        assert_eq!(plain.link_keys(Some(&nonce_a), Some(&proof_a)), Ok(None));
+    }
+}
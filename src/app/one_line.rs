@@ -0,0 +1,110 @@
+//! One-line fold: a display configured with `rows = 1` would silently drop `line2` and a bar on
+//! row 1, so the render loop alternates the single row between the frame's first and second
+//! rows on a timer instead.
+
+use std::time::{Duration, Instant};
+
+use crate::payload::RenderFrame;
+
+/// How long each frame row holds the glass before the other one takes over.
+const ALTERNATE_MS: u64 = 3_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct OneLineFold {
+    row: u8,
+    next_flip: Instant,
+}
+
+impl OneLineFold {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            row: 0,
+            next_flip: now + Duration::from_millis(ALTERNATE_MS),
+        }
+    }
+
+    /// Frame row the single display row shows; 0 on taller displays.
+    pub fn row(&self) -> u8 {
+        self.row
+    }
+
+    /// Start a newly shown frame on its first row.
+    pub fn reset(&mut self, now: Instant) {
+        *self = Self::new(now);
+    }
+
+    /// Flip to the other frame row when due. Only folds on one-row displays and only while the
+    /// frame has something on row 1; returns `true` when the shown row changed.
+    pub fn tick(&mut self, frame: &RenderFrame, rows: u8, now: Instant) -> bool {
+        if rows != 1 || !has_second_row(frame) {
+            let changed = self.row != 0;
+            self.reset(now);
+            return changed;
+        }
+        if now < self.next_flip {
+            return false;
+        }
+        self.row ^= 1;
+        self.next_flip = now + Duration::from_millis(ALTERNATE_MS);
+        true
+    }
+
+    /// Frame rows a display with `rows` rows can show: both folded rows on a one-row display.
+    pub fn frame_rows(rows: u8) -> u8 {
+        if rows == 1 {
+            2
+        } else {
+            rows
+        }
+    }
+}
+
+fn has_second_row(frame: &RenderFrame) -> bool {
+    !frame.line2.trim().is_empty() || (frame.bar_row == Some(1) && frame.bar_percent.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(raw: &str) -> RenderFrame {
+        RenderFrame::from_payload_json(raw).unwrap()
+    }
+
+    #[test]
+    fn alternates_rows_on_one_row_displays() {
+        let start = Instant::now();
+        let mut fold = OneLineFold::new(start);
+        let frame = frame(r#"{"schema_version":1,"line1":"CPU 12%","line2":"MEM 40%"}"#);
+        assert!(!fold.tick(&frame, 1, start));
+        assert_eq!(fold.row(), 0);
+        let due = start + Duration::from_millis(ALTERNATE_MS);
+        assert!(fold.tick(&frame, 1, due));
+        assert_eq!(fold.row(), 1);
+        assert!(fold.tick(&frame, 1, due + Duration::from_millis(ALTERNATE_MS)));
+        assert_eq!(fold.row(), 0);
+    }
+
+    #[test]
+    fn stays_on_line1_without_a_second_row_or_on_taller_displays() {
+        let start = Instant::now();
+        let late = start + Duration::from_millis(ALTERNATE_MS * 2);
+        let single = frame(r#"{"schema_version":1,"line1":"only","line2":"  "}"#);
+        let mut fold = OneLineFold::new(start);
+        assert!(!fold.tick(&single, 1, late));
+        assert_eq!(fold.row(), 0);
+
+        let double = frame(r#"{"schema_version":1,"line1":"a","line2":"b"}"#);
+        assert!(!fold.tick(&double, 2, late));
+        assert_eq!(fold.row(), 0);
+    }
+
+    #[test]
+    fn bar_on_row_one_counts_as_a_second_row() {
+        let start = Instant::now();
+        let mut fold = OneLineFold::new(start);
+        let frame = frame(r#"{"schema_version":1,"line1":"disk","line2":"","bar":50}"#);
+        assert!(fold.tick(&frame, 1, start + Duration::from_millis(ALTERNATE_MS)));
+        assert_eq!(fold.row(), 1);
+    }
+}
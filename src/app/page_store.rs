@@ -0,0 +1,222 @@
+//! Page queue snapshot.
+//!
+//! While `[render].persist_pages` is on, the queued pages are written to
+//! `/run/serial_lcd_cache/pages.json` as a schema_v1 page set: shortly after the queue changes,
+//! so a crash loses at most a second of updates, and once more on shutdown. At startup the
+//! snapshot is queued again, so a restart does not blank the display until the peer sends its
+//! next frame.
+
+use super::Logger;
+use crate::{cache_dir, config::RenderConfig, payload::RenderFrame, state::RenderState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+const PAGES_FILE: &str = "pages.json";
+/// Shortest gap between two snapshots while pages keep arriving.
+const SAVE_INTERVAL_MS: u64 = 1_000;
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    schema_version: u8,
+    pages: Vec<Value>,
+}
+
+pub struct PageStore {
+    path: PathBuf,
+    saved_revision: u64,
+    next_save: Instant,
+}
+
+impl PageStore {
+    /// `None` when persistence is disabled in config.
+    pub fn from_config(config: &RenderConfig, now: Instant) -> Option<Self> {
+        config
+            .persist_pages
+            .then(|| Self::at(cache_dir().join(PAGES_FILE), now))
+    }
+
+    fn at(path: PathBuf, now: Instant) -> Self {
+        Self {
+            path,
+            saved_revision: 0,
+            next_save: now,
+        }
+    }
+
+    /// Queue the pages from the last snapshot; returns how many came back. Pages that no
+    /// longer parse are skipped.
+    pub fn restore(&mut self, state: &mut RenderState, logger: &Logger) -> usize {
+        let raw = match fs::read_to_string(&self.path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return 0,
+            Err(err) => {
+                logger.warn(format!("pages: read {} failed: {err}", self.path.display()));
+                return 0;
+            }
+        };
+        let snapshot: Snapshot = match serde_json::from_str(&raw) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                logger.warn(format!("pages: ignoring {}: {err}", self.path.display()));
+                return 0;
+            }
+        };
+        let mut frames = Vec::with_capacity(snapshot.pages.len());
+        for (idx, page) in snapshot.pages.iter().enumerate() {
+            match RenderFrame::from_payload_json(&page.to_string()) {
+                Ok(frame) => frames.push(frame),
+                Err(err) => logger.warn(format!("pages: dropping saved page {idx}: {err}")),
+            }
+        }
+        let restored = frames.len();
+        if restored > 0 {
+            state.restore_pages(frames);
+        }
+        // The file already holds these pages; only later changes need writing.
+        self.saved_revision = state.revision();
+        restored
+    }
+
+    /// Write a snapshot when the queue changed and the last write is far enough back.
+    pub fn poll(&mut self, now: Instant, state: &RenderState, logger: &Logger) {
+        if now < self.next_save || state.revision() == self.saved_revision {
+            return;
+        }
+        self.next_save = now + Duration::from_millis(SAVE_INTERVAL_MS);
+        self.save(state, logger);
+    }
+
+    /// Write a snapshot now, whether or not anything changed.
+    pub fn save(&mut self, state: &RenderState, logger: &Logger) {
+        match write_snapshot(&self.path, &state.saved_pages()) {
+            Ok(()) => self.saved_revision = state.revision(),
+            Err(err) => logger.warn(format!(
+                "pages: write {} failed: {err}",
+                self.path.display()
+            )),
+        }
+    }
+}
+
+fn write_snapshot(path: &Path, frames: &[RenderFrame]) -> std::io::Result<()> {
+    let pages = frames
+        .iter()
+        .map(|frame| {
+            let mut page = serde_json::to_value(frame.to_payload())?;
+            if let Value::Object(fields) = &mut page {
+                // Unset fields would serialize as nulls; leave them out like a sender would.
+                fields.retain(|_, value| !value.is_null());
+            }
+            Ok(page)
+        })
+        .collect::<serde_json::Result<Vec<_>>>()
+        .map_err(std::io::Error::other)?;
+    let json = serde_json::to_string(&Snapshot {
+        schema_version: 1,
+        pages,
+    })
+    .map_err(std::io::Error::other)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write beside the target and rename, so a crash mid-write never leaves half a snapshot.
+    let partial = path.with_extension("json.tmp");
+    fs::write(&partial, format!("{json}\n"))?;
+    fs::rename(&partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logger() -> Logger {
+        Logger::new(crate::app::LogLevel::Error, None).unwrap()
+    }
+
+    #[test]
+    fn snapshot_restores_the_queue_starting_with_the_shown_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PAGES_FILE);
+        let now = Instant::now();
+        let mut state = RenderState::new(None);
+        for line1 in ["one", "two", "three"] {
+            state
+                .ingest(&format!(
+                    r#"{{"schema_version":1,"line1":"{line1}","line2":"x"}}"#
+                ))
+                .unwrap();
+        }
+        state
+            .ingest(r#"{"schema_version":1,"line1":"brief","line2":"","duration_ms":60000}"#)
+            .unwrap();
+        state.next_page();
+        state.next_page();
+        let mut store = PageStore::at(path.clone(), now);
+        store.poll(now, &state, &logger());
+
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["schema_version"], 1);
+        assert_eq!(saved["pages"][0]["line1"], "two");
+        assert!(saved["pages"][0].get("bar").is_none());
+
+        let mut restored = RenderState::new(None);
+        let mut store = PageStore::at(path, now);
+        assert_eq!(store.restore(&mut restored, &logger()), 3);
+        let order: Vec<_> = (0..3)
+            .filter_map(|_| restored.next_page())
+            .map(|frame| frame.line1)
+            .collect();
+        assert_eq!(order, ["two", "three", "one"]);
+    }
+
+    #[test]
+    fn saves_are_paced_and_skipped_when_nothing_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PAGES_FILE);
+        let now = Instant::now();
+        let mut state = RenderState::new(None);
+        let mut store = PageStore::at(path.clone(), now);
+        store.poll(now, &state, &logger());
+        assert!(!path.exists());
+
+        state
+            .ingest(r#"{"schema_version":1,"line1":"a","line2":"b"}"#)
+            .unwrap();
+        store.poll(now, &state, &logger());
+        assert!(path.exists());
+        state
+            .ingest(r#"{"schema_version":1,"line1":"c","line2":"d"}"#)
+            .unwrap();
+        store.poll(now + Duration::from_millis(10), &state, &logger());
+        assert!(!fs::read_to_string(&path).unwrap().contains("\"c\""));
+        store.poll(
+            now + Duration::from_millis(SAVE_INTERVAL_MS),
+            &state,
+            &logger(),
+        );
+        assert!(fs::read_to_string(&path).unwrap().contains("\"c\""));
+    }
+
+    #[test]
+    fn missing_or_broken_snapshots_restore_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PAGES_FILE);
+        let mut state = RenderState::new(None);
+        let mut store = PageStore::at(path.clone(), Instant::now());
+        assert_eq!(store.restore(&mut state, &logger()), 0);
+        fs::write(&path, "{not json").unwrap();
+        assert_eq!(store.restore(&mut state, &logger()), 0);
+        fs::write(
+            &path,
+            r#"{"schema_version":1,"pages":[{"schema_version":1,"line1":"ok","line2":""},{"line1":"no version","line2":""}]}"#,
+        )
+        .unwrap();
+        assert_eq!(store.restore(&mut state, &logger()), 1);
+        assert_eq!(state.saved_pages().len(), 1);
+    }
+}
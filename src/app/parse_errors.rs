@@ -0,0 +1,127 @@
+use crate::config::{ParseErrorConfig, ParseErrorDisplay};
+use std::time::{Duration, Instant};
+
+/// Decides when a rejected frame may replace the current page with `ERR PARSE`, and when the
+/// covered page should come back.
+#[derive(Debug, Clone)]
+pub struct ParseErrorGate {
+    display: ParseErrorDisplay,
+    after: u32,
+    hold: Option<Duration>,
+    streak: u32,
+    showing: bool,
+    restore_at: Option<Instant>,
+}
+
+impl ParseErrorGate {
+    pub fn new(config: &ParseErrorConfig) -> Self {
+        Self {
+            display: config.display,
+            after: config.after.max(1),
+            hold: (config.display_ms > 0).then(|| Duration::from_millis(config.display_ms)),
+            streak: 0,
+            showing: false,
+            restore_at: None,
+        }
+    }
+
+    /// Record a rejected frame. Returns `true` when the error page should be drawn now.
+    pub fn on_error(&mut self, now: Instant) -> bool {
+        self.streak = self.streak.saturating_add(1);
+        let show = match self.display {
+            ParseErrorDisplay::Always => true,
+            ParseErrorDisplay::After => self.streak >= self.after,
+            ParseErrorDisplay::Never => false,
+        };
+        if show {
+            self.showing = true;
+            self.restore_at = self.hold.map(|hold| now + hold);
+        }
+        show
+    }
+
+    /// A frame decoded cleanly, so the error streak starts over.
+    pub fn on_frame(&mut self) {
+        self.streak = 0;
+    }
+
+    /// Something else took the display; the error page no longer needs restoring.
+    pub fn dismiss(&mut self) {
+        self.showing = false;
+        self.restore_at = None;
+    }
+
+    pub fn showing(&self) -> bool {
+        self.showing
+    }
+
+    /// Whether the error page's display time just ran out; clears the page when it has.
+    pub fn restore_due(&mut self, now: Instant) -> bool {
+        match self.restore_at {
+            Some(at) if now >= at => {
+                self.dismiss();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate(display: ParseErrorDisplay, after: u32, display_ms: u64) -> ParseErrorGate {
+        ParseErrorGate::new(&ParseErrorConfig {
+            display,
+            after,
+            display_ms,
+        })
+    }
+
+    #[test]
+    fn always_shows_and_restores_after_hold() {
+        let mut gate = gate(ParseErrorDisplay::Always, 3, 1_000);
+        let now = Instant::now();
+        assert!(gate.on_error(now));
+        assert!(gate.showing());
+        assert!(!gate.restore_due(now + Duration::from_millis(999)));
+        assert!(gate.restore_due(now + Duration::from_millis(1_000)));
+        assert!(!gate.showing());
+        assert!(!gate.restore_due(now + Duration::from_millis(2_000)));
+    }
+
+    #[test]
+    fn after_waits_for_consecutive_errors() {
+        let mut gate = gate(ParseErrorDisplay::After, 3, 1_000);
+        let now = Instant::now();
+        assert!(!gate.on_error(now));
+        assert!(!gate.on_error(now));
+        gate.on_frame();
+        assert!(!gate.on_error(now));
+        assert!(!gate.on_error(now));
+        assert!(gate.on_error(now));
+        assert!(gate.showing());
+    }
+
+    #[test]
+    fn never_only_counts() {
+        let mut gate = gate(ParseErrorDisplay::Never, 1, 1_000);
+        let now = Instant::now();
+        for _ in 0..5 {
+            assert!(!gate.on_error(now));
+        }
+        assert!(!gate.showing());
+    }
+
+    #[test]
+    fn zero_display_ms_holds_until_dismissed() {
+        let mut gate = gate(ParseErrorDisplay::Always, 1, 0);
+        let now = Instant::now();
+        assert!(gate.on_error(now));
+        assert!(!gate.restore_due(now + Duration::from_secs(3_600)));
+        assert!(gate.showing());
+        gate.dismiss();
+        assert!(!gate.showing());
+    }
+}
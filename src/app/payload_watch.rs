@@ -0,0 +1,147 @@
+//! `--payload-watch`: redraw the LCD whenever a payload file changes on disk.
+//!
+//! The file is polled by modification time and size, so host scripts only need to rewrite
+//! it (ideally via write-then-rename). A payload that fails to parse is logged and the last
+//! good page stays on the glass, which also hides half-written files from non-atomic writers.
+
+use super::{lifecycle::create_shutdown_flag, lifecycle::render_shutdown, AppConfig, Logger};
+use crate::{
+    display::{backend::DisplayBackend, overlays::render_frame_once},
+    payload::{CompressionPolicy, Defaults as PayloadDefaults},
+    Result,
+};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::Ordering,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+const WATCH_POLL_MS: u64 = 250;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+/// Tracks the last seen version of the watched file.
+#[derive(Debug)]
+pub struct PayloadWatcher {
+    path: PathBuf,
+    last: Option<FileStamp>,
+}
+
+impl PayloadWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            last: None,
+        }
+    }
+
+    /// Contents of the file if it changed since the last poll. A missing file is an error and
+    /// makes its reappearance count as a change.
+    pub fn poll(&mut self) -> std::io::Result<Option<String>> {
+        let meta = match fs::metadata(&self.path) {
+            Ok(meta) => meta,
+            Err(err) => {
+                self.last = None;
+                return Err(err);
+            }
+        };
+        let stamp = FileStamp {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+        };
+        if self.last == Some(stamp) {
+            return Ok(None);
+        }
+        let raw = fs::read_to_string(&self.path)?;
+        self.last = Some(stamp);
+        Ok(Some(raw))
+    }
+}
+
+pub fn run_payload_watch<D: DisplayBackend>(
+    lcd: &mut D,
+    config: &AppConfig,
+    logger: &Logger,
+    path: &str,
+) -> Result<()> {
+    let defaults = PayloadDefaults {
+        scroll_speed_ms: config.scroll_speed_ms,
+        page_timeout_ms: config.page_timeout_ms,
+    };
+    let policy = if config.compression_enabled {
+        CompressionPolicy::only(config.compression_codec)
+    } else {
+        CompressionPolicy::disabled()
+    };
+    let running = create_shutdown_flag()?;
+    let mut watcher = PayloadWatcher::new(path);
+    let mut last_error: Option<String> = None;
+    logger.info(format!(
+        "payload watch: {path} (checked every {WATCH_POLL_MS}ms, ctrl-c to exit)"
+    ));
+
+    while running.load(Ordering::SeqCst) {
+        let problem = match watcher.poll() {
+            Ok(Some(raw)) => match super::parse_payload(&raw, defaults, policy) {
+                Ok(frame) => {
+                    lcd.set_backlight(frame.backlight_on)?;
+                    lcd.set_blink(frame.blink)?;
+                    render_frame_once(lcd, &frame, config.render.bar_style)?;
+                    logger.info(format!("payload watch: rendered {path}"));
+                    None
+                }
+                Err(err) => Some(format!("{path}: {err}; keeping the previous page")),
+            },
+            Ok(None) => last_error.clone(),
+            Err(err) => Some(format!("{path}: {err}")),
+        };
+        if problem != last_error {
+            if let Some(message) = &problem {
+                logger.warn(format!("payload watch: {message}"));
+            }
+            last_error = problem;
+        }
+        thread::sleep(Duration::from_millis(WATCH_POLL_MS));
+    }
+
+    render_shutdown(lcd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poll_reports_only_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("payload.json");
+        let mut watcher = PayloadWatcher::new(&path);
+        assert!(watcher.poll().is_err());
+
+        fs::write(&path, "first").unwrap();
+        assert_eq!(watcher.poll().unwrap().as_deref(), Some("first"));
+        assert_eq!(watcher.poll().unwrap(), None);
+
+        fs::write(&path, "second version").unwrap();
+        assert_eq!(watcher.poll().unwrap().as_deref(), Some("second version"));
+    }
+
+    #[test]
+    fn reappearing_file_counts_as_a_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("payload.json");
+        fs::write(&path, "same").unwrap();
+        let mut watcher = PayloadWatcher::new(&path);
+        assert!(watcher.poll().unwrap().is_some());
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.poll().is_err());
+        fs::write(&path, "same").unwrap();
+        assert_eq!(watcher.poll().unwrap().as_deref(), Some("same"));
+    }
+}
@@ -0,0 +1,189 @@
+//! Display defaults pushed by the peer with a `render_defaults` control frame.
+//!
+//! A fleet's server can tune scroll speed, page timeout, blink rate and how much of the rotation
+//! local status pages get without editing each panel's config. Every key must pass the local
+//! `[peer_defaults]` allow/deny lists and the same range checks as `config.toml`. Accepted values
+//! live in memory only: they are re-applied after a config reload, and a restart goes back to the
+//! file.
+
+use super::AppConfig;
+use crate::{
+    config::{
+        PeerDefaultsConfig, MAX_RENDER_BLINK_MS, MAX_SCHEDULER_WEIGHT, MIN_PAGE_TIMEOUT_MS,
+        MIN_RENDER_BLINK_MS, MIN_SCROLL_MS,
+    },
+    negotiation::RenderDefaults,
+};
+use std::{fmt::Display, ops::RangeInclusive};
+
+/// `config.toml` has no upper bound here, but one bad fleet-wide push should not freeze every
+/// panel on a single page.
+const MAX_PUSHED_SCROLL_MS: u64 = 10_000;
+const MAX_PUSHED_PAGE_TIMEOUT_MS: u64 = 600_000;
+
+/// Keys applied and refused from one push.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PushOutcome {
+    pub applied: Vec<&'static str>,
+    pub refused: Vec<(&'static str, String)>,
+}
+
+#[derive(Debug)]
+pub struct PeerDefaults {
+    policy: PeerDefaultsConfig,
+    pushed: RenderDefaults,
+}
+
+impl PeerDefaults {
+    pub fn new(policy: &PeerDefaultsConfig) -> Self {
+        Self {
+            policy: policy.clone(),
+            pushed: RenderDefaults::default(),
+        }
+    }
+
+    /// Adopt new lists; earlier pushes they no longer allow are dropped.
+    pub fn reconfigure(&mut self, policy: &PeerDefaultsConfig, remote_weight: u32) {
+        self.policy = policy.clone();
+        let pushed = std::mem::take(&mut self.pushed);
+        self.accept(&pushed, remote_weight);
+    }
+
+    /// Screen `push` against the lists and ranges and remember the keys that pass.
+    /// `remote_weight` keeps a pushed `local_weight = 0` from leaving the scheduler nothing to show.
+    pub fn accept(&mut self, push: &RenderDefaults, remote_weight: u32) -> PushOutcome {
+        let mut outcome = PushOutcome::default();
+        let policy = &self.policy;
+        let pushed = &mut self.pushed;
+        screen(
+            policy,
+            "scroll_speed_ms",
+            push.scroll_speed_ms,
+            MIN_SCROLL_MS..=MAX_PUSHED_SCROLL_MS,
+            &mut pushed.scroll_speed_ms,
+            &mut outcome,
+        );
+        screen(
+            policy,
+            "page_timeout_ms",
+            push.page_timeout_ms,
+            MIN_PAGE_TIMEOUT_MS..=MAX_PUSHED_PAGE_TIMEOUT_MS,
+            &mut pushed.page_timeout_ms,
+            &mut outcome,
+        );
+        screen(
+            policy,
+            "blink_ms",
+            push.blink_ms,
+            MIN_RENDER_BLINK_MS..=MAX_RENDER_BLINK_MS,
+            &mut pushed.blink_ms,
+            &mut outcome,
+        );
+        screen(
+            policy,
+            "local_weight",
+            push.local_weight,
+            u32::from(remote_weight == 0)..=MAX_SCHEDULER_WEIGHT,
+            &mut pushed.local_weight,
+            &mut outcome,
+        );
+        outcome
+    }
+
+    /// Overlay every accepted value onto `config`.
+    pub fn apply(&self, config: &mut AppConfig) {
+        if let Some(ms) = self.pushed.scroll_speed_ms {
+            config.scroll_speed_ms = ms;
+        }
+        if let Some(ms) = self.pushed.page_timeout_ms {
+            config.page_timeout_ms = ms;
+        }
+        if let Some(ms) = self.pushed.blink_ms {
+            config.render.blink_ms = ms;
+        }
+        if let Some(weight) = self.pushed.local_weight {
+            config.scheduler.local_weight = weight;
+        }
+    }
+}
+
+fn screen<T: Copy + PartialOrd + Display>(
+    policy: &PeerDefaultsConfig,
+    key: &'static str,
+    value: Option<T>,
+    range: RangeInclusive<T>,
+    slot: &mut Option<T>,
+    outcome: &mut PushOutcome,
+) {
+    let Some(value) = value else {
+        return;
+    };
+    if !policy.allows(key) {
+        outcome
+            .refused
+            .push((key, "not allowed by [peer_defaults]".into()));
+    } else if !range.contains(&value) {
+        outcome.refused.push((
+            key,
+            format!("{value} is outside {}..={}", range.start(), range.end()),
+        ));
+    } else {
+        *slot = Some(value);
+        outcome.applied.push(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allow: &[&str], deny: &[&str]) -> PeerDefaultsConfig {
+        PeerDefaultsConfig {
+            allow: allow.iter().map(|key| key.to_string()).collect(),
+            deny: deny.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn only_allowed_keys_in_range_are_applied() {
+        let mut defaults = PeerDefaults::new(&policy(&["*"], &["blink_ms"]));
+        let outcome = defaults.accept(
+            &RenderDefaults {
+                scroll_speed_ms: Some(400),
+                page_timeout_ms: Some(50),
+                blink_ms: Some(500),
+                local_weight: Some(3),
+            },
+            1,
+        );
+        assert_eq!(outcome.applied, vec!["scroll_speed_ms", "local_weight"]);
+        let refused: Vec<_> = outcome.refused.iter().map(|(key, _)| *key).collect();
+        assert_eq!(refused, vec!["page_timeout_ms", "blink_ms"]);
+
+        let mut config = AppConfig::default();
+        let page_timeout_ms = config.page_timeout_ms;
+        defaults.apply(&mut config);
+        assert_eq!(config.scroll_speed_ms, 400);
+        assert_eq!(config.page_timeout_ms, page_timeout_ms);
+        assert_eq!(config.scheduler.local_weight, 3);
+    }
+
+    #[test]
+    fn empty_lists_ignore_pushes_and_reconfigure_drops_revoked_keys() {
+        let push = RenderDefaults {
+            scroll_speed_ms: Some(400),
+            local_weight: Some(0),
+            ..RenderDefaults::default()
+        };
+        let mut defaults = PeerDefaults::new(&PeerDefaultsConfig::default());
+        assert!(defaults.accept(&push, 1).applied.is_empty());
+
+        defaults.reconfigure(&policy(&["*"], &[]), 1);
+        assert_eq!(defaults.accept(&push, 1).applied.len(), 2);
+        // With no remote weight left, local pages cannot be switched off as well.
+        defaults.reconfigure(&policy(&["scroll_speed_ms", "local_weight"], &[]), 0);
+        assert_eq!(defaults.pushed.local_weight, None);
+        defaults.reconfigure(&policy(&["local_weight"], &[]), 1);
+        assert_eq!(defaults.pushed.scroll_speed_ms, None);
+    }
+}
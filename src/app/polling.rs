@@ -7,6 +7,8 @@ use std::sync::{
 use std::thread;
 use std::time::{Duration, Instant};
 
+use super::idle::LoopWaker;
+use crate::display::metric_template::Metric;
 use sysinfo::{Disks, System as InfoSystem};
 use systemstat::{data::CPULoad, data::DelayedMeasurement, Platform, System as StatSystem};
 
@@ -19,6 +21,32 @@ pub struct PollSnapshot {
     pub disk_used_pct: f32,
     pub disk_available_kb: Option<u64>,
     pub temperature_c: Option<f32>,
+    /// 1, 5 and 15 minute load averages.
+    pub load_average: Option<[f32; 3]>,
+}
+
+impl PollSnapshot {
+    /// Value of one overlay template metric, in that metric's unit.
+    pub fn metric(&self, metric: Metric) -> Option<f64> {
+        let mem_mb = self.mem_used_kb as f64 / 1024.0;
+        let free_mb = self.disk_available_kb.map(|kb| kb as f64 / 1024.0);
+        let load = |idx: usize| self.load_average.map(|avg| f64::from(avg[idx]));
+        match metric {
+            Metric::Cpu => Some(f64::from(self.cpu_percent)),
+            Metric::Load1 => load(0),
+            Metric::Load5 => load(1),
+            Metric::Load15 => load(2),
+            Metric::Mem => (self.mem_total_kb > 0)
+                .then(|| self.mem_used_kb as f64 / self.mem_total_kb as f64 * 100.0),
+            Metric::MemMb => Some(mem_mb),
+            Metric::MemGb => Some(mem_mb / 1024.0),
+            Metric::Disk => Some(f64::from(self.disk_used_pct)),
+            Metric::FreeMb => free_mb,
+            Metric::FreeGb => free_mb.map(|mb| mb / 1024.0),
+            Metric::Temp => self.temperature_c.map(f64::from),
+            Metric::TempF => self.temperature_c.map(|c| f64::from(c) * 9.0 / 5.0 + 32.0),
+        }
+    }
 }
 
 /// Reports sent over the polling channel.
@@ -47,7 +75,11 @@ impl Drop for PollingHandle {
 }
 
 /// Spawn the background poller that pushes snapshots at roughly `interval_ms`.
-pub fn start_polling(interval_ms: u64, app_running: Arc<AtomicBool>) -> PollingHandle {
+pub fn start_polling(
+    interval_ms: u64,
+    app_running: Arc<AtomicBool>,
+    waker: LoopWaker,
+) -> PollingHandle {
     let interval = Duration::from_millis(interval_ms.max(1));
     let (tx, rx) = mpsc::channel();
     let running = Arc::new(AtomicBool::new(true));
@@ -63,6 +95,7 @@ pub fn start_polling(interval_ms: u64, app_running: Arc<AtomicBool>) -> PollingH
                         Err(err) => PollEvent::Error(err),
                     };
                     let _ = tx.send(event);
+                    waker.wake();
                     let elapsed = start.elapsed();
                     if elapsed < interval {
                         thread::sleep(interval - elapsed);
@@ -105,8 +138,8 @@ impl Poller {
         self.cpu_load = self.stats.cpu_load_aggregate().map_err(|e| e.to_string())?;
         self.sysinfo.refresh_memory();
         self.disks.refresh(true);
-        let mem_used = self.sysinfo.used_memory();
-        let mem_total = self.sysinfo.total_memory();
+        let mem_used = self.sysinfo.used_memory() / 1024;
+        let mem_total = self.sysinfo.total_memory() / 1024;
         let disks = self.disks.list();
         let disk = disks
             .iter()
@@ -125,6 +158,11 @@ impl Poller {
             (0.0, None)
         };
         let temperature_c = self.stats.cpu_temp().ok();
+        let load_average = self
+            .stats
+            .load_average()
+            .ok()
+            .map(|avg| [avg.one, avg.five, avg.fifteen]);
         Ok(PollSnapshot {
             cpu_percent,
             mem_used_kb: mem_used,
@@ -132,6 +170,7 @@ impl Poller {
             disk_used_pct,
             disk_available_kb,
             temperature_c,
+            load_average,
         })
     }
 }
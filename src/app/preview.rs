@@ -0,0 +1,154 @@
+use crate::{
+    config::{DEFAULT_PAGE_TIMEOUT_MS, DEFAULT_SCROLL_MS},
+    display::{
+        icon_bank::{IconBank, IconPalette},
+        overlays::{render_frame_with_scroll, StatusMarks},
+    },
+    lcd::Lcd,
+    payload::{CompressionPolicy, Defaults as PayloadDefaults, RenderFrame},
+    ExitCode, Result,
+};
+use std::fmt::Write as _;
+
+const PREVIEW_ROWS: u8 = 2;
+
+/// `lifelinetty render --payload <file>`: print the cells a payload would produce, without hardware.
+pub fn run(payload_path: &str, width: u8) -> Result<()> {
+    let frame = load_frame(payload_path).map_err(|err| err.exit_with(ExitCode::InvalidPayload))?;
+    print!("{}", render_preview(&frame, width)?);
+    Ok(())
+}
+
+fn load_frame(payload_path: &str) -> Result<RenderFrame> {
+    let raw = std::fs::read_to_string(payload_path)?;
+    let normalized =
+        crate::payload::normalize_payload_json_with_policy(&raw, CompressionPolicy::allow_any())?;
+    RenderFrame::from_normalized_payload_with_defaults(
+        &normalized,
+        PayloadDefaults {
+            scroll_speed_ms: DEFAULT_SCROLL_MS,
+            page_timeout_ms: DEFAULT_PAGE_TIMEOUT_MS,
+        },
+    )
+}
+
+/// Run the normal composition pipeline against a stub LCD and describe every cell.
+pub fn render_preview(frame: &RenderFrame, width: u8) -> Result<String> {
+    let mut lcd = Lcd::new_stub(width, PREVIEW_ROWS);
+    let mut icon_bank = IconBank::new();
+    let palette = render_frame_with_scroll(
+        &mut lcd,
+        frame,
+        &[],
+        false,
+        StatusMarks::default(),
+        None,
+        &mut icon_bank,
+    )?;
+    let (line1, line2) = lcd.last_lines();
+
+    let cols = width as usize;
+    let mut out = String::new();
+    let ruler: String = (0..cols)
+        .map(|col| char::from(b'0' + (col % 10) as u8))
+        .collect();
+    let _ = writeln!(out, "    {ruler}");
+    let mut used_slots = [false; 8];
+    let mut codes = Vec::new();
+    for (row, line) in [line1, line2].iter().enumerate() {
+        let mut cells: Vec<char> = line.chars().collect();
+        cells.resize(cols, ' ');
+        let mut visible = String::with_capacity(cols);
+        let mut row_codes = Vec::with_capacity(cols);
+        for ch in cells {
+            // Mirrors the driver, which sends each char's low byte to DDRAM.
+            let code = ch as u32 as u8;
+            row_codes.push(format!("{code:02x}"));
+            visible.push(match code {
+                0..=7 => {
+                    used_slots[code as usize] = true;
+                    char::from(b'0' + code)
+                }
+                0x20..=0x7e if ch.is_ascii() => ch,
+                _ => '?',
+            });
+        }
+        let _ = writeln!(out, "r{row} |{visible}|");
+        codes.push(format!("r{row} {}", row_codes.join(" ")));
+    }
+    for line in codes {
+        let _ = writeln!(out, "{line}");
+    }
+    for slot in (0..8u8).filter(|slot| used_slots[*slot as usize]) {
+        let _ = writeln!(
+            out,
+            "cgram {slot} = {}",
+            describe_slot(char::from(slot), frame, &palette)
+        );
+    }
+    if !palette.missing_icons.is_empty() {
+        let _ = writeln!(
+            out,
+            "missing icons (no free CGRAM slot): {:?}",
+            palette.missing_icons
+        );
+    }
+    Ok(out)
+}
+
+fn describe_slot(ch: char, frame: &RenderFrame, palette: &IconPalette) -> String {
+    let style = palette.bar_style();
+    let levels = style.levels() as usize;
+    if let Some(level) = (0..=levels).find(|level| palette.bar_char(*level) == Some(ch)) {
+        return format!("{style} bar level {level}/{levels}");
+    }
+    if palette.heartbeat_char() == Some(ch) {
+        return "heartbeat".to_string();
+    }
+    if let Some(glyph) = frame
+        .custom_glyphs
+        .iter()
+        .find(|glyph| palette.custom_char(&glyph.rows) == Some(ch))
+    {
+        return format!("payload glyph {}", glyph.name);
+    }
+    frame
+        .icons
+        .iter()
+        .find(|icon| palette.icon_char(**icon) == Some(ch))
+        .map(|icon| format!("icon {icon:?}"))
+        .unwrap_or_else(|| "custom glyph".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(raw: &str) -> RenderFrame {
+        RenderFrame::from_payload_json(raw).unwrap()
+    }
+
+    #[test]
+    fn preview_pads_cells_and_dumps_codes() {
+        let out = render_preview(
+            &frame(r#"{"schema_version":1,"line1":"Hi","line2":"there"}"#),
+            8,
+        )
+        .unwrap();
+        assert!(out.contains("r0 |Hi      |"));
+        assert!(out.contains("r1 |there   |"));
+        assert!(out.contains("r0 48 69 20 20 20 20 20 20"));
+    }
+
+    #[test]
+    fn preview_marks_bar_glyph_slots() {
+        let out = render_preview(
+            &frame(r#"{"schema_version":1,"line1":"CPU","line2":"","bar":50,"bar_line2":true}"#),
+            10,
+        )
+        .unwrap();
+        assert!(out.contains("r1 |"));
+        assert!(out.contains("cgram"));
+        assert!(out.contains("bar level"));
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{
+    cache_dir,
+    config::{loader, schema, Config},
+    lcd::Lcd,
+    serial::SerialPort,
+    Error, ExitCode, Result,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::Path,
+};
+
+const RESULT_FILE_NAME: &str = "provision_result.json";
+
+/// Non-interactive provisioning document consumed by `lifelinetty provision --from <file>`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProvisionDoc {
+    /// Config values keyed by schema path (`baud`, `modem.poll_ms`) or nested per section.
+    #[serde(default)]
+    pub config: Map<String, Value>,
+    /// Peer pairing values applied to the `[negotiation]` section.
+    #[serde(default)]
+    pub pairing: Option<PairingBlob>,
+    /// Base32 tunnel auth secret for `[tunnel_auth]`; at most one, since both ends share it.
+    #[serde(default)]
+    pub auth_keys: Vec<String>,
+    /// Open the serial device and LCD after writing the config.
+    #[serde(default = "default_check_hardware")]
+    pub check_hardware: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PairingBlob {
+    pub node_id: Option<u32>,
+    pub preference: Option<String>,
+}
+
+fn default_check_hardware() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProvisionStep {
+    pub name: &'static str,
+    pub ok: bool,
+    pub message: String,
+}
+
+/// Machine-readable outcome printed to stdout and mirrored under the cache dir.
+#[derive(Debug, Serialize)]
+pub struct ProvisionReport {
+    pub ok: bool,
+    pub config_path: String,
+    pub steps: Vec<ProvisionStep>,
+    pub warnings: Vec<String>,
+}
+
+impl ProvisionReport {
+    fn step(&mut self, name: &'static str, outcome: std::result::Result<String, String>) {
+        let (ok, message) = match outcome {
+            Ok(message) => (true, message),
+            Err(message) => (false, message),
+        };
+        self.ok &= ok;
+        self.steps.push(ProvisionStep { name, ok, message });
+    }
+
+    /// Exit status for the first failed step; `None` when every step passed.
+    pub fn exit_code(&self) -> Option<ExitCode> {
+        let failed = self.steps.iter().find(|step| !step.ok)?;
+        Some(match failed.name {
+            "validate" => ExitCode::Config,
+            "serial_device" => ExitCode::SerialUnavailable,
+            "lcd" => ExitCode::LcdInit,
+            _ => ExitCode::Failure,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|_| "{\"ok\":false}".to_string())
+    }
+}
+
+/// Run every wizard step from `doc_path` without prompting.
+pub fn run(doc_path: &Path) -> Result<ProvisionReport> {
+    let raw =
+        fs::read_to_string(doc_path).map_err(|e| Error::from(e).exit_with(ExitCode::Config))?;
+    let doc: ProvisionDoc = serde_json::from_str(&raw).map_err(|e| {
+        Error::InvalidArgs(format!("invalid provisioning document: {e}"))
+            .exit_with(ExitCode::Config)
+    })?;
+    let config_path = loader::default_config_path()?;
+    Ok(provision(&doc, &config_path))
+}
+
+fn provision(doc: &ProvisionDoc, config_path: &Path) -> ProvisionReport {
+    let mut report = ProvisionReport {
+        ok: true,
+        config_path: config_path.display().to_string(),
+        steps: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let cfg = match build_config(doc) {
+        Ok(cfg) => {
+            report.step("validate", Ok("provisioning document is valid".into()));
+            cfg
+        }
+        Err(err) => {
+            report.step("validate", Err(err.to_string()));
+            return report;
+        }
+    };
+
+    let owner = home_owner();
+    let config_dir = config_path.parent().unwrap_or(config_path).to_path_buf();
+    report.step(
+        "config_dir",
+        prepare_dir(&config_dir, 0o700, owner).map_err(|e| e.to_string()),
+    );
+    report.step(
+        "cache_dir",
+        prepare_dir(&cache_dir(), 0o755, owner).map_err(|e| e.to_string()),
+    );
+    report.step(
+        "write_config",
+        write_config(&cfg, config_path, owner).map_err(|e| e.to_string()),
+    );
+
+    if doc.check_hardware {
+        report.step("serial_device", check_serial(&cfg));
+        if cfg.lcd_present {
+            report.step("lcd", check_lcd(&cfg));
+        }
+    }
+
+    write_result(&report);
+    report
+}
+
+/// Apply the document on top of defaults and validate the merged config.
+pub fn build_config(doc: &ProvisionDoc) -> Result<Config> {
+    let mut entries: Vec<(&'static schema::KeySpec, Value)> = Vec::new();
+    flatten_into(&doc.config, None, &mut entries)?;
+    if let Some(pairing) = &doc.pairing {
+        if let Some(node_id) = pairing.node_id {
+            entries.push((lookup("negotiation.node_id")?, Value::from(node_id)));
+        }
+        if let Some(pref) = &pairing.preference {
+            entries.push((
+                lookup("negotiation.preference")?,
+                Value::from(pref.as_str()),
+            ));
+        }
+    }
+
+    match doc.auth_keys.as_slice() {
+        [] => {}
+        [key] => {
+            let set = |path: &str| entries.iter().any(|(spec, _)| spec.path() == path);
+            if set("tunnel_auth.secret") {
+                return Err(Error::InvalidArgs(
+                    "auth_keys and config tunnel_auth.secret both set the tunnel auth secret"
+                        .into(),
+                ));
+            }
+            if !set("tunnel_auth.backend") {
+                entries.push((lookup("tunnel_auth.backend")?, Value::from("totp")));
+            }
+            entries.push((lookup("tunnel_auth.secret")?, Value::from(key.as_str())));
+        }
+        keys => {
+            return Err(Error::InvalidArgs(format!(
+                "auth_keys holds {} keys but [tunnel_auth] takes a single shared secret",
+                keys.len()
+            )))
+        }
+    }
+
+    let mut top = String::new();
+    let mut sections: Vec<(&str, String)> = Vec::new();
+    for (spec, value) in entries {
+        let line = format!("{} = {}\n", spec.key, toml_value(spec, &value)?);
+        match spec.section {
+            None => top.push_str(&line),
+            Some(section) => match sections.iter_mut().find(|(name, _)| *name == section) {
+                Some((_, body)) => body.push_str(&line),
+                None => sections.push((section, line)),
+            },
+        }
+    }
+    for (section, body) in sections {
+        top.push_str(&format!("\n[{section}]\n{body}"));
+    }
+
+    let cfg = loader::parse(&top)?;
+    crate::config::validate(&cfg)?;
+    Ok(cfg)
+}
+
+fn flatten_into(
+    map: &Map<String, Value>,
+    section: Option<&str>,
+    out: &mut Vec<(&'static schema::KeySpec, Value)>,
+) -> Result<()> {
+    for (key, value) in map {
+        let path = match section {
+            Some(section) => format!("{section}.{key}"),
+            None => key.clone(),
+        };
+        match (schema::find(&path), value) {
+            (Some(spec), _) => out.push((spec, value.clone())),
+            (None, Value::Object(inner)) if section.is_none() => {
+                flatten_into(inner, Some(key), out)?
+            }
+            (None, _) => {
+                return Err(Error::InvalidArgs(format!(
+                    "unknown config key '{path}' (see `lifelinetty config schema`)"
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn lookup(path: &str) -> Result<&'static schema::KeySpec> {
+    schema::find(path).ok_or_else(|| Error::InvalidArgs(format!("unknown config key '{path}'")))
+}
+
+fn toml_value(spec: &schema::KeySpec, value: &Value) -> Result<String> {
+    let invalid = || Error::InvalidArgs(format!("invalid value for '{}'", spec.path()));
+    let rendered = match (spec.kind, value) {
+        (_, Value::Null) => "null".to_string(),
+        (schema::KeyType::Bool, Value::Bool(b)) => b.to_string(),
+        (schema::KeyType::Integer, Value::Number(n)) if n.is_u64() => n.to_string(),
+        (schema::KeyType::String | schema::KeyType::Enum, Value::String(s)) => toml_string(s),
+        (schema::KeyType::Enum, Value::Number(n)) => toml_string(&n.to_string()),
+        (schema::KeyType::StringArray, Value::Array(items)) => {
+            let items = items
+                .iter()
+                .map(|item| item.as_str().map(toml_string).ok_or_else(invalid))
+                .collect::<Result<Vec<_>>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        (schema::KeyType::InlineTable, Value::Object(fields)) => {
+            let mut parts = Vec::with_capacity(fields.len());
+            for (key, field) in fields {
+                let rendered = match field {
+                    Value::Bool(b) => b.to_string(),
+                    Value::String(s) => toml_string(s),
+                    _ => return Err(invalid()),
+                };
+                parts.push(format!("{key} = {rendered}"));
+            }
+            format!("{{ {} }}", parts.join(", "))
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(rendered)
+}
+
+fn toml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Owner of `$HOME`, so directories created as root during first boot belong to the daemon user.
+fn home_owner() -> Option<(u32, u32)> {
+    let home = std::env::var_os("HOME")?;
+    let meta = fs::metadata(home).ok()?;
+    Some((meta.uid(), meta.gid()))
+}
+
+fn prepare_dir(path: &Path, mode: u32, owner: Option<(u32, u32)>) -> Result<String> {
+    fs::create_dir_all(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    apply_owner(path, owner)?;
+    Ok(format!("{} ready (mode {mode:o})", path.display()))
+}
+
+fn write_config(cfg: &Config, path: &Path, owner: Option<(u32, u32)>) -> Result<String> {
+    cfg.save_to_path(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    apply_owner(path, owner)?;
+    Ok(format!("wrote {}", path.display()))
+}
+
+fn apply_owner(path: &Path, owner: Option<(u32, u32)>) -> Result<()> {
+    let Some((uid, gid)) = owner else {
+        return Ok(());
+    };
+    let meta = fs::metadata(path)?;
+    if meta.uid() != uid || meta.gid() != gid {
+        std::os::unix::fs::chown(path, Some(uid), Some(gid))?;
+    }
+    Ok(())
+}
+
+fn check_serial(cfg: &Config) -> std::result::Result<String, String> {
+    let options = crate::serial::SerialOptions {
+        baud: cfg.baud,
+        timeout_ms: cfg.serial_timeout_ms,
+        flow_control: cfg.flow_control,
+        parity: cfg.parity,
+        stop_bits: cfg.stop_bits,
+        dtr: cfg.dtr_on_open,
+        read_min_bytes: cfg.serial_read_min_bytes,
+        read_gap_ds: cfg.serial_read_gap_ds,
+        low_latency: cfg.serial_low_latency,
+        arq: cfg.arq,
+        tx_rate_percent: cfg.serial_tx_rate_percent,
+    };
+    SerialPort::connect(&cfg.device, options)
+        .map(|_| format!("{} opened at {} baud", cfg.device, cfg.baud))
+        .map_err(|e| format!("{}: {e}", cfg.device))
+}
+
+fn check_lcd(cfg: &Config) -> std::result::Result<String, String> {
+    Lcd::new(
+        cfg.cols,
+        cfg.rows,
+        cfg.pcf8574_addr.clone(),
+        cfg.display_driver,
+        cfg.lcd_gpio,
+    )
+    .map(|_| format!("{}x{} LCD initialized", cfg.cols, cfg.rows))
+    .map_err(|e| e.to_string())
+}
+
+fn write_result(report: &ProvisionReport) {
+    let path = cache_dir().join(RESULT_FILE_NAME);
+    if let Err(err) = fs::write(&path, report.to_json()) {
+        eprintln!(
+            "lifelinetty provision: failed to write {}: {err}",
+            path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::TunnelAuthBackend, negotiation::RolePreference};
+
+    fn doc(raw: &str) -> ProvisionDoc {
+        serde_json::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn applies_flat_and_nested_keys() {
+        let cfg = build_config(&doc(r#"{
+                "config": {
+                    "device": "/dev/ttyAMA0",
+                    "baud": 115200,
+                    "stop_bits": 2,
+                    "modem": { "poll_ms": 500 },
+                    "protocol.compression": { "enabled": true, "codec": "zstd" },
+                    "command_allowlist": ["uptime"]
+                },
+                "pairing": { "node_id": 7, "preference": "prefer_client" }
+            }"#))
+        .unwrap();
+        assert_eq!(cfg.device, "/dev/ttyAMA0");
+        assert_eq!(cfg.baud, 115_200);
+        assert_eq!(cfg.modem.poll_ms, 500);
+        assert!(cfg.protocol.compression_enabled);
+        assert_eq!(cfg.command_allowlist, vec!["uptime".to_string()]);
+        assert_eq!(cfg.negotiation.node_id, 7);
+        assert_eq!(cfg.negotiation.preference, RolePreference::PreferClient);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_types() {
+        assert!(build_config(&doc(r#"{"config":{"bogus":1}}"#)).is_err());
+        assert!(build_config(&doc(r#"{"config":{"baud":"fast"}}"#)).is_err());
+        assert!(build_config(&doc(r#"{"config":{"cols":2}}"#)).is_err());
+    }
+
+    #[test]
+    fn auth_keys_fill_the_tunnel_auth_section() {
+        let secret = "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP";
+        let cfg = build_config(&doc(&format!(r#"{{"auth_keys":["{secret}"]}}"#))).unwrap();
+        assert_eq!(cfg.tunnel_auth.backend, TunnelAuthBackend::Totp);
+        assert_eq!(cfg.tunnel_auth.secret, secret);
+
+        let cfg = build_config(&doc(&format!(
+            r#"{{"config":{{"tunnel_auth.backend":"hmac"}},"auth_keys":["{secret}"]}}"#
+        )))
+        .unwrap();
+        assert_eq!(cfg.tunnel_auth.backend, TunnelAuthBackend::Hmac);
+
+        assert!(
+            build_config(&doc(&format!(r#"{{"auth_keys":["{secret}","{secret}"]}}"#))).is_err()
+        );
+        assert!(build_config(&doc(r#"{"auth_keys":["too-short"]}"#)).is_err());
+        assert!(build_config(&doc(&format!(
+            r#"{{"config":{{"tunnel_auth":{{"secret":"{secret}"}}}},"auth_keys":["{secret}"]}}"#
+        )))
+        .is_err());
+    }
+
+    #[test]
+    fn invalid_doc_writes_nothing() {
+        let dir =
+            std::env::temp_dir().join(format!("lifelinetty_provision_{}", std::process::id()));
+        let path = dir.join("config.toml");
+        let report = provision(&doc(r#"{"config":{"rows":9}}"#), &path);
+        assert!(!report.ok);
+        assert_eq!(report.steps.len(), 1);
+        assert_eq!(report.exit_code(), Some(ExitCode::Config));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,238 @@
+//! Relay: forward accepted frames on a second serial port.
+//!
+//! A node in the middle of a chain renders what its upstream sends and passes each frame it
+//! accepted on to the next node, byte for byte. `[relay].rules` use the `[filters]` syntax and
+//! decide what goes downstream without changing what is shown here. The downstream port is
+//! opened with the same backoff as the main link; frames that arrive while it is down are not
+//! queued, but the last one forwarded is sent again once it reconnects so the far display is
+//! not left on a stale page.
+
+use std::time::Instant;
+
+use super::{AppConfig, Logger};
+use crate::{
+    payload::{FrameFilter, RenderFrame},
+    serial::{backoff::BackoffController, LineIo, SerialOptions, SerialPort},
+    Result,
+};
+
+/// Forwarding counters reported with the loop stats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RelayStats {
+    pub forwarded: u64,
+    /// Dropped by `[relay].rules`.
+    pub filtered: u64,
+    /// Lost because the downstream port was down or a write failed.
+    pub dropped: u64,
+}
+
+pub(super) struct Relay<P = SerialPort> {
+    device: String,
+    options: SerialOptions,
+    port: Option<P>,
+    backoff: BackoffController,
+    filter: FrameFilter,
+    /// Last line sent downstream, replayed after a reconnect.
+    last: Option<String>,
+    stats: RelayStats,
+}
+
+impl Relay {
+    /// The relay for `config`, or `None` when `[relay]` is off.
+    pub fn open(config: &AppConfig, logger: &Logger) -> Result<Option<Self>> {
+        if !config.relay.enabled {
+            return Ok(None);
+        }
+        let options = SerialOptions {
+            baud: config.relay.baud,
+            ..config.serial_options()
+        };
+        let mut relay = Self::with_port(
+            config.relay.device.clone(),
+            options,
+            config.relay.build()?,
+            BackoffController::new(config.backoff_initial_ms, config.backoff_max_ms),
+        );
+        relay.connect(Instant::now(), logger);
+        Ok(Some(relay))
+    }
+
+    /// Open the relay again after `[relay]` changed on reload. Settings that no longer open
+    /// disable it with a warning instead of stopping the daemon.
+    pub fn reopen(config: &AppConfig, minute: u16, logger: &Logger) -> Option<Self> {
+        let mut relay = Self::open(config, logger).unwrap_or_else(|err| {
+            logger.warn(format!("relay disabled: {err}"));
+            None
+        })?;
+        relay.set_minute_of_day(minute);
+        Some(relay)
+    }
+
+    fn connect(&mut self, now: Instant, logger: &Logger) {
+        match SerialPort::connect(&self.device, self.options) {
+            Ok(port) => {
+                logger.info(format!(
+                    "relay: forwarding to {} at {}",
+                    self.device, self.options.baud
+                ));
+                self.backoff.mark_success(now);
+                self.attach(port, logger);
+            }
+            Err(err) => {
+                self.backoff.mark_failure(now);
+                logger.warn(format!(
+                    "relay: cannot open {}: {err}; retrying in {}ms",
+                    self.device,
+                    self.backoff.current_delay_ms()
+                ));
+            }
+        }
+    }
+
+    /// Reopen the downstream port once the backoff allows it.
+    pub fn poll(&mut self, now: Instant, logger: &Logger) {
+        if self.port.is_none() && self.backoff.should_retry(now) {
+            self.connect(now, logger);
+        }
+    }
+}
+
+impl<P: LineIo> Relay<P> {
+    fn with_port(
+        device: String,
+        options: SerialOptions,
+        filter: FrameFilter,
+        backoff: BackoffController,
+    ) -> Self {
+        Self {
+            device,
+            options,
+            port: None,
+            backoff,
+            filter,
+            last: None,
+            stats: RelayStats::default(),
+        }
+    }
+
+    fn attach(&mut self, port: P, logger: &Logger) {
+        self.port = Some(port);
+        if let Some(line) = self.last.clone() {
+            self.send(&line, Instant::now(), logger);
+        }
+    }
+
+    /// Send `line`, which this node accepted as `frame`, downstream unless a rule drops it.
+    pub fn forward(&mut self, line: &str, frame: &RenderFrame, now: Instant, logger: &Logger) {
+        if !self.filter.admits(frame) {
+            self.stats.filtered += 1;
+            return;
+        }
+        self.last = Some(line.to_string());
+        if self.send(line, now, logger) {
+            self.stats.forwarded += 1;
+        } else {
+            self.stats.dropped += 1;
+        }
+    }
+
+    fn send(&mut self, line: &str, now: Instant, logger: &Logger) -> bool {
+        let Some(port) = self.port.as_mut() else {
+            return false;
+        };
+        match port.send_command_line(line) {
+            Ok(()) => true,
+            Err(err) => {
+                self.port = None;
+                self.backoff.mark_failure(now);
+                logger.warn(format!(
+                    "relay: write to {} failed: {err}; reconnecting in {}ms",
+                    self.device,
+                    self.backoff.current_delay_ms()
+                ));
+                false
+            }
+        }
+    }
+
+    pub fn set_minute_of_day(&mut self, minute: u16) {
+        self.filter.set_minute_of_day(minute);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.port.is_some()
+    }
+
+    pub fn stats(&self) -> RelayStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::LogLevel;
+    use crate::Error;
+
+    /// Records what was sent; fails every write once `broken` is set.
+    #[derive(Default)]
+    struct Wire {
+        sent: Vec<String>,
+        broken: bool,
+    }
+
+    impl LineIo for Wire {
+        fn send_command_line(&mut self, line: &str) -> Result<()> {
+            if self.broken {
+                return Err(Error::InvalidArgs("unplugged".into()));
+            }
+            self.sent.push(line.to_string());
+            Ok(())
+        }
+
+        fn read_message_line(&mut self, _buf: &mut String) -> Result<usize> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn forwards_what_the_rules_admit_and_replays_the_last_frame_after_a_reconnect() {
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let mut relay: Relay<Wire> = Relay::with_port(
+            "/dev/ttyAMA0".into(),
+            SerialOptions::new(9_600),
+            FrameFilter::new(&["drop:line1~^DEBUG".into()]).unwrap(),
+            BackoffController::new(500, 10_000),
+        );
+        relay.attach(Wire::default(), &logger);
+        let now = Instant::now();
+        let send = |relay: &mut Relay<Wire>, line: &str| {
+            let frame = RenderFrame::from_payload_json(line).unwrap();
+            relay.forward(line, &frame, now, &logger);
+        };
+
+        let cpu = r#"{"schema_version":1,"line1":"CPU 42%","line2":"up 3d"}"#;
+        send(&mut relay, cpu);
+        send(
+            &mut relay,
+            r#"{"schema_version":1,"line1":"DEBUG tick","line2":""}"#,
+        );
+        assert_eq!(relay.port.as_ref().unwrap().sent, [cpu]);
+
+        relay.port.as_mut().unwrap().broken = true;
+        send(&mut relay, cpu);
+        assert!(!relay.is_connected());
+        send(&mut relay, cpu);
+        assert_eq!(
+            relay.stats(),
+            RelayStats {
+                forwarded: 1,
+                filtered: 1,
+                dropped: 2,
+            }
+        );
+
+        relay.attach(Wire::default(), &logger);
+        assert_eq!(relay.port.as_ref().unwrap().sent, [cpu]);
+    }
+}
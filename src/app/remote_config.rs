@@ -0,0 +1,398 @@
+//! `lifelinetty remote-wizard`: set up the far node's display and role from the near node.
+//!
+//! The wizard walks [`REMOTE_KEYS`] over the command tunnel. For each key it reads the far end's
+//! value with `config_get`, offers it as the default answer, writes a changed answer with
+//! `config_set`, and reads the key back before reporting the step as done. The far end writes
+//! the value into its own `config.toml`, so its daemon reloads the change like a local edit and
+//! keeps it across restarts. Only the keys listed here can be read or written this way, and sets
+//! are refused while the interlock is disarmed or without the code or signature `[tunnel_auth]`
+//! asks for.
+
+use super::{
+    events::INTERLOCK_REJECTION,
+    interlock::unix_ms,
+    tunnel_auth::{CommandAuth, RequestSigner},
+    tunnel_files,
+};
+use crate::{
+    config::{loader, Config},
+    payload::TunnelMsgOwned,
+    serial::LineIo,
+    Error, Result,
+};
+use serde_json::Value;
+use std::{
+    io::{self, BufRead, Write},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// Keys the peer may read and write, in the order the wizard asks for them.
+pub const REMOTE_KEYS: &[(&str, &str)] = &[
+    ("cols", "Display columns"),
+    ("rows", "Display rows"),
+    ("scroll_speed_ms", "Scroll step (ms)"),
+    ("page_timeout_ms", "Page timeout (ms)"),
+    (
+        "negotiation.preference",
+        "Role (prefer_server, prefer_client or no_preference)",
+    ),
+];
+
+/// Daemon side of `config_get`.
+pub fn get(path: &Path, key: &str) -> TunnelMsgOwned {
+    reply(
+        key,
+        remote_key(key).and_then(|_| loader::get_key(path, key)),
+    )
+}
+
+/// Daemon side of `config_set`: the interlock and `[tunnel_auth]` checks run before anything is
+/// written, as they do for commands. The reply carries the value now in the file.
+pub fn set(
+    path: &Path,
+    request: &TunnelMsgOwned,
+    locked: bool,
+    auth: &CommandAuth,
+) -> TunnelMsgOwned {
+    let TunnelMsgOwned::ConfigSet { key, value, .. } = request else {
+        return reply("", Err(Error::Parse("not a config_set request".into())));
+    };
+    let refusal = if locked {
+        Some(INTERLOCK_REJECTION.to_string())
+    } else {
+        auth.check_message(request, unix_ms(SystemTime::now()))
+            .err()
+    };
+    if let Some(error) = refusal {
+        return TunnelMsgOwned::ConfigValue {
+            key: key.clone(),
+            value: None,
+            error: Some(error),
+        };
+    }
+    let saved = remote_key(key)
+        .and_then(|_| loader::set_key(path, key, value))
+        .and_then(|_| loader::get_key(path, key));
+    reply(key, saved)
+}
+
+fn remote_key(key: &str) -> Result<()> {
+    if REMOTE_KEYS.iter().any(|(remote, _)| *remote == key) {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgs(format!("{key} cannot be set remotely")))
+    }
+}
+
+fn reply(key: &str, value: Result<Value>) -> TunnelMsgOwned {
+    let (value, error) = match value {
+        Ok(value) => (Some(wire_value(&value)), None),
+        Err(err) => (None, Some(err.to_string())),
+    };
+    TunnelMsgOwned::ConfigValue {
+        key: key.to_string(),
+        value,
+        error,
+    }
+}
+
+/// Config values as the user types them: strings bare, everything else as JSON.
+fn wire_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn run(device: Option<String>, baud: Option<u32>) -> Result<()> {
+    let local_role = Config::load_or_default()
+        .map(|cfg| cfg.negotiation.preference.to_string())
+        .unwrap_or_default();
+    let (mut serial, mut signer) = tunnel_files::open_serial(device, baud)?;
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    writeln!(
+        out,
+        "Remote wizard: press Enter to keep the far end's current value."
+    )?;
+    let mismatches = drive(
+        &mut serial,
+        &mut input,
+        &mut out,
+        &local_role,
+        signer.as_mut(),
+    )?;
+    if mismatches > 0 {
+        return Err(Error::Parse(format!(
+            "{mismatches} setting(s) did not read back as written"
+        )));
+    }
+    Ok(())
+}
+
+/// Ask for every remote key in turn; returns how many writes read back a different value.
+/// `signer` adds the `[tunnel_auth]` code or signature to each `config_set`.
+fn drive<T: LineIo, I: BufRead, O: Write>(
+    io: &mut T,
+    input: &mut I,
+    out: &mut O,
+    local_role: &str,
+    mut signer: Option<&mut RequestSigner>,
+) -> Result<usize> {
+    let mut mismatches = 0;
+    for (key, label) in REMOTE_KEYS {
+        let current = exchange(
+            io,
+            &TunnelMsgOwned::ConfigGet {
+                key: key.to_string(),
+            },
+        )?
+        .map_err(|reason| Error::Parse(format!("far end cannot read {key}: {reason}")))?;
+        if *key == "negotiation.preference" && !local_role.is_empty() {
+            writeln!(
+                out,
+                "This end is {local_role}; give the far end the opposite role to swap."
+            )?;
+        }
+        loop {
+            write!(out, "{label} [{current}]: ")?;
+            out.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Err(Error::InvalidArgs("remote wizard: input ended".into()));
+            }
+            let answer = match line.trim() {
+                "" => current.as_str(),
+                answer => answer,
+            };
+            if answer == current {
+                writeln!(out, "  {key} unchanged")?;
+                break;
+            }
+            let mut request = TunnelMsgOwned::ConfigSet {
+                key: key.to_string(),
+                value: answer.to_string(),
+                otp: None,
+                sig: None,
+            };
+            if let Some(signer) = signer.as_deref_mut() {
+                sign(signer, &mut request, out)?;
+            }
+            let stored = match exchange(io, &request)? {
+                Ok(stored) => stored,
+                Err(reason) => {
+                    writeln!(out, "  far end refused: {reason}")?;
+                    continue;
+                }
+            };
+            let read_back = exchange(
+                io,
+                &TunnelMsgOwned::ConfigGet {
+                    key: key.to_string(),
+                },
+            )?
+            .map_err(|reason| Error::Parse(format!("far end cannot read {key}: {reason}")))?;
+            if read_back == stored {
+                writeln!(out, "  {key} {current} -> {read_back} (verified)")?;
+            } else {
+                writeln!(out, "  {key} wrote {stored} but read back {read_back}")?;
+                mismatches += 1;
+            }
+            break;
+        }
+    }
+    writeln!(
+        out,
+        "Done. Display changes apply on the far end right away; a new role applies at its next handshake."
+    )?;
+    Ok(mismatches)
+}
+
+/// Add the code or signature to `request`. When every TOTP code the far end still accepts has
+/// been used, wait for the clock to reach the next period.
+fn sign<O: Write>(
+    signer: &mut RequestSigner,
+    request: &mut TunnelMsgOwned,
+    out: &mut O,
+) -> Result<()> {
+    let mut waiting = false;
+    while !signer.sign_message(request, unix_ms(SystemTime::now())) {
+        if !waiting {
+            writeln!(out, "  waiting for the next TOTP code...")?;
+            waiting = true;
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+    Ok(())
+}
+
+/// Send a config request and wait for the `config_value` for the same key.
+fn exchange<T: LineIo>(
+    io: &mut T,
+    message: &TunnelMsgOwned,
+) -> Result<std::result::Result<String, String>> {
+    let (TunnelMsgOwned::ConfigGet { key } | TunnelMsgOwned::ConfigSet { key, .. }) = message
+    else {
+        return Err(Error::Parse("not a config request".into()));
+    };
+    tunnel_files::request(io, message, |reply| match reply {
+        TunnelMsgOwned::ConfigValue {
+            key: answered,
+            value,
+            error,
+        } if answered == *key => Some(match (value, error) {
+            (_, Some(error)) => Err(error),
+            (Some(value), None) => Ok(value),
+            (None, None) => Err("no value in reply".to_string()),
+        }),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::{decode_tunnel_frame, encode_tunnel_msg};
+    use std::{collections::VecDeque, fs, path::PathBuf};
+
+    /// A far end answering inline from its config file.
+    struct Peer {
+        path: PathBuf,
+        locked: bool,
+        auth: CommandAuth,
+        replies: VecDeque<String>,
+    }
+
+    impl LineIo for Peer {
+        fn send_command_line(&mut self, line: &str) -> Result<()> {
+            let reply = match decode_tunnel_frame(line)? {
+                TunnelMsgOwned::ConfigGet { key } => get(&self.path, &key),
+                request @ TunnelMsgOwned::ConfigSet { .. } => {
+                    set(&self.path, &request, self.locked, &self.auth)
+                }
+                other => panic!("unexpected request {other:?}"),
+            };
+            self.replies.push_back(encode_tunnel_msg(&reply)?);
+            Ok(())
+        }
+
+        fn read_message_line(&mut self, buf: &mut String) -> Result<usize> {
+            *buf = self.replies.pop_front().unwrap_or_default();
+            Ok(buf.len())
+        }
+    }
+
+    fn peer(name: &str, contents: &str) -> (tempfile::TempDir, Peer) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        fs::write(&path, contents).unwrap();
+        let peer = Peer {
+            path,
+            locked: false,
+            auth: CommandAuth::default(),
+            replies: VecDeque::new(),
+        };
+        (dir, peer)
+    }
+
+    fn config_set(key: &str, value: &str) -> TunnelMsgOwned {
+        TunnelMsgOwned::ConfigSet {
+            key: key.into(),
+            value: value.into(),
+            otp: None,
+            sig: None,
+        }
+    }
+
+    fn totp_auth() -> crate::config::TunnelAuthConfig {
+        crate::config::TunnelAuthConfig {
+            backend: crate::config::TunnelAuthBackend::Totp,
+            secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".into(),
+            period_secs: 30,
+            skew_steps: 1,
+        }
+    }
+
+    #[test]
+    fn only_listed_keys_are_served_and_sets_need_the_interlock() {
+        let (_dir, peer) = peer("config.toml", "cols = 16\n");
+        assert_eq!(
+            get(&peer.path, "cols"),
+            TunnelMsgOwned::ConfigValue {
+                key: "cols".into(),
+                value: Some("16".into()),
+                error: None,
+            }
+        );
+        let TunnelMsgOwned::ConfigValue { error, .. } = get(&peer.path, "negotiation.psk") else {
+            unreachable!()
+        };
+        assert!(error.unwrap().contains("cannot be set remotely"));
+        let no_auth = CommandAuth::default();
+        let TunnelMsgOwned::ConfigValue { error, .. } =
+            set(&peer.path, &config_set("cols", "20"), true, &no_auth)
+        else {
+            unreachable!()
+        };
+        assert_eq!(error.as_deref(), Some(INTERLOCK_REJECTION));
+        let TunnelMsgOwned::ConfigValue { error, .. } =
+            set(&peer.path, &config_set("cols", "99"), false, &no_auth)
+        else {
+            unreachable!()
+        };
+        assert!(error.is_some());
+        assert_eq!(loader::get_key(&peer.path, "cols").unwrap(), 16);
+    }
+
+    #[test]
+    fn wizard_writes_changed_answers_and_verifies_them() {
+        let (_dir, mut peer) = peer(
+            "config.toml",
+            "cols = 16\nrows = 2\n\n[negotiation]\npreference = \"prefer_server\"\n",
+        );
+        // Columns: a rejected answer, then a good one; rows through page timeout keep their
+        // values; the role swaps.
+        let mut input = io::Cursor::new("99\n20\n\n\n\nprefer_client\n");
+        let mut out = Vec::new();
+        let mismatches = drive(&mut peer, &mut input, &mut out, "prefer_client", None).unwrap();
+        assert_eq!(mismatches, 0);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("far end refused"), "{out}");
+        assert!(out.contains("cols 16 -> 20 (verified)"), "{out}");
+        assert!(out.contains("rows unchanged"), "{out}");
+        assert!(out.contains("This end is prefer_client"), "{out}");
+        assert!(
+            out.contains("negotiation.preference prefer_server -> prefer_client (verified)"),
+            "{out}"
+        );
+        let saved = loader::load_from_path(&peer.path).unwrap();
+        assert_eq!((saved.cols, saved.rows), (20, 2));
+        assert_eq!(saved.negotiation.preference.to_string(), "prefer_client");
+    }
+
+    #[test]
+    fn sets_need_a_code_when_tunnel_auth_is_on() {
+        let (_dir, mut peer) = peer("config.toml", "cols = 16\n");
+        peer.auth = CommandAuth::from_config(&totp_auth());
+        let TunnelMsgOwned::ConfigValue { error, .. } =
+            set(&peer.path, &config_set("cols", "20"), false, &peer.auth)
+        else {
+            unreachable!()
+        };
+        assert_eq!(
+            error.as_deref(),
+            Some("command rejected: TOTP code required")
+        );
+        assert_eq!(loader::get_key(&peer.path, "cols").unwrap(), 16);
+
+        let mut signer = RequestSigner::from_config(&totp_auth());
+        let mut input = io::Cursor::new("20\n\n\n\n\n");
+        let mut out = Vec::new();
+        let mismatches = drive(&mut peer, &mut input, &mut out, "", signer.as_mut()).unwrap();
+        assert_eq!(mismatches, 0);
+        assert_eq!(loader::get_key(&peer.path, "cols").unwrap(), 20);
+    }
+}
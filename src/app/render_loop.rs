@@ -6,50 +6,103 @@ use std::sync::{
 };
 use std::{
     io::Write,
-    thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
+use super::alerts::AlertBadges;
+use super::annunciator::Annunciators;
+use super::anomaly::{peer_page, AnomalyDetector};
+use super::backlight::{
+    local_minute_of_day, pattern_level, BacklightGate, BacklightPwm, BacklightSchedule, Dimmed,
+    PATTERN_TICK_MS,
+};
+use super::burst::{BurstController, BurstStep};
+use super::config_watch::ConfigWatcher;
 use super::connection::attempt_serial_connect;
+use super::control::{error_reply, ok_reply, payload_text, ControlCommand, ControlServer};
+use super::crash;
+use super::displays::{ExtraDisplays, Sources};
 use super::events::{CommandBridge, CommandEvent, CommandExecutor, ScrollOffsets};
-use super::input::Button;
-use super::lifecycle::{create_shutdown_flag, render_shutdown};
-use super::negotiation::NegotiationLog;
+use super::features::{Feature, FeatureMatrix};
+use super::frame_limit::{Admission, FrameLimiter};
+use super::handshake::{millis, HandshakeAudit};
+use super::hud::{FrameHud, HUD_ROW};
+use super::idle::{LoopIdle, LoopWaker};
+use super::input::{Button, EncoderEvent, RotaryEncoder};
+use super::interlock::{format_expiry, looks_like_arm_frame, unix_secs, Interlock};
+use super::lifecycle::{create_shutdown_flag, render_shutdown, ShutdownDeadline};
+use super::log_compaction::{local_today, LogCompactor};
+use super::metrics::{Exposition, MetricsExporter};
+use super::modem::ModemMonitor;
+use super::negotiation::{keepalive_proposal_ms, NegotiationLog, SessionCache};
+use super::one_line::OneLineFold;
+use super::page_store::PageStore;
+use super::parse_errors::ParseErrorGate;
+use super::peer_defaults::PeerDefaults;
 use super::polling::{start_polling, PollEvent, PollSnapshot, PollingHandle};
+use super::relay::Relay;
+use super::replay::{ReplayChannel, ReplayGuard};
+use super::scheduler::{ExpiryCountdown, PageScheduler, PageSource};
+use super::slo::{incident_lines, SloEventLog, SloKind, SloTracker, SloTransition};
+use super::spool::Spool;
+use super::systemd::SystemdNotifier;
+use super::throttle::{fps_ceiling, RenderThrottle};
+use super::trace::{FrameTracer, TraceId};
+use super::troubleshoot::StartupDiagnostics;
 use super::tunnel::TunnelController;
+use super::tunnel_auth::CommandAuth;
+use super::tunnel_files::{progress_page, TransferProgress};
 use super::watchdog::WatchdogMonitor;
+use super::write_budget::WriteBudget;
+use super::zones::{poll_zones, set_zones, ZoneMask};
 use super::{AppConfig, LogLevel, Logger};
 use crate::{
-    config::Config,
+    cache_dir,
+    config::{loader, Config, PollOverlayConfig},
     display::{
+        backend::DisplayBackend,
         icon_bank::{IconBank, IconPalette},
+        metric_template::MetricTemplate,
         overlays::{
-            advance_offset, line_needs_scroll, render_if_allowed, render_offline_message,
-            render_parse_error, render_reconnecting,
+            frame_needs_scroll, render_frame_with_scroll, render_if_allowed, render_incident_page,
+            render_offline_message, render_parse_error, render_row_update, StatusMarks,
         },
+        self_test::{run_self_test, SELF_TEST_STEP_MS},
     },
-    lcd::Lcd,
+    negotiation::{AnomalySummary, ControlFrame},
     payload::{
-        decode_tunnel_frame, encode_command_frame, encode_tunnel_msg, CommandMessage,
-        CompressionPolicy, Defaults as PayloadDefaults, RenderFrame, TunnelMsgOwned,
+        decode_tunnel_frame_with_seq, encode_command_frame_with_seq, encode_frame_ack,
+        encode_frame_nack, encode_tunnel_msg_with_seq, frame_crc32, BacklightPattern,
+        CommandMessage, CompressionPolicy, Defaults as PayloadDefaults, FrameAck, FrameNack,
+        RenderFrame, RowUpdate, TunnelMsgOwned,
     },
     serial::{
         backoff::BackoffController,
         classify_io_error,
-        telemetry::{log_backoff_event, BackoffPhase},
-        SerialFailureKind, SerialPort,
+        telemetry::{log_backoff_event, log_loop_stats, log_modem_event, BackoffPhase},
+        LineIo, SerialFailureKind, SerialPort,
     },
-    Error, Result, CACHE_DIR,
+    state::{InterruptStep, RenderState},
+    Error, FrameErrorCode, Result,
 };
 use crc32fast::Hasher;
 use serde::Serialize;
+use serde_json::json;
 
 const HEARTBEAT_GRACE_MS: u64 = 5_000;
 const HEARTBEAT_BLINK_MS: u64 = 1_000;
-const HEARTBEAT_MIN_TX_MS: u64 = 500;
-const HEARTBEAT_INTERVAL_DIVISOR: u64 = 3;
 const POLLING_OVERLAY_MIN_INTERVAL_MS: u64 = 1_500;
 const PROTOCOL_ERROR_LOG_MAX_BYTES: u64 = 256 * 1024;
+/// How long shutdown waits for the rate-capped outbox to empty before saying goodbye.
+const SHUTDOWN_DRAIN_MS: u64 = 2_000;
+/// Past this the process exits even if the drain or the shutdown screen is stuck.
+const SHUTDOWN_DEADLINE_MS: u64 = 5_000;
+/// How often the page of a push or pull in progress is redrawn.
+const TRANSFER_PAGE_REFRESH_MS: u64 = 500;
+/// How often the loop counters are appended to `loop_stats.log`.
+const LOOP_STATS_SNAPSHOT_MS: u64 = 60_000;
+/// Shortest read wait while a zone is due to rotate or scroll.
+const ZONE_TICK_MIN_MS: u64 = 50;
 
 struct PollingState {
     handle: PollingHandle,
@@ -58,12 +111,14 @@ struct PollingState {
     last_rendered_seq: u64,
     last_overlay_at: Instant,
     log: PollingLog,
+    layout: PollLayout,
 }
 
 impl PollingState {
-    fn new(handle: PollingHandle) -> Self {
+    fn new(handle: PollingHandle, layout: PollLayout) -> Self {
         Self {
             handle,
+            layout,
             latest: None,
             latest_seq: 0,
             last_rendered_seq: 0,
@@ -87,19 +142,44 @@ impl PollingState {
     }
 }
 
+/// Parsed `[poll_overlay]` templates; rows without one keep the built-in text.
+#[derive(Debug, Clone, Default)]
+pub(super) struct PollLayout {
+    line1: Option<MetricTemplate>,
+    line2: Option<MetricTemplate>,
+}
+
+impl PollLayout {
+    fn from_config(config: &PollOverlayConfig) -> Self {
+        // Templates were validated when the config loaded.
+        let parse = |raw: &str| {
+            if raw.is_empty() {
+                None
+            } else {
+                MetricTemplate::parse(raw).ok()
+            }
+        };
+        Self {
+            line1: parse(&config.line1),
+            line2: parse(&config.line2),
+        }
+    }
+}
+
 struct PollingLog {
     path: PathBuf,
 }
 
 impl PollingLog {
     fn new() -> Self {
-        let path = PathBuf::from(CACHE_DIR).join("polling").join("events.log");
+        let path = cache_dir().join("polling").join("events.log");
         Self { path }
     }
 
     fn snapshot(&self, seq: u64, snapshot: &PollSnapshot) -> std::io::Result<()> {
         let mut line = format!(
-            "seq={seq} cpu={:.1} mem_used_kb={} mem_total_kb={} disk_used_pct={:.1}",
+            "ts_ms={} seq={seq} cpu={:.1} mem_used_kb={} mem_total_kb={} disk_used_pct={:.1}",
+            unix_ms(),
             snapshot.cpu_percent,
             snapshot.mem_used_kb,
             snapshot.mem_total_kb,
@@ -116,7 +196,7 @@ impl PollingLog {
     }
 
     fn error(&self, err: &str) -> std::io::Result<()> {
-        let line = format!("kind=error message={err}");
+        let line = format!("ts_ms={} kind=error message={err}", unix_ms());
         self.append_line(&line)
     }
 
@@ -132,13 +212,20 @@ impl PollingLog {
     }
 }
 
+fn unix_ms() -> u128 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
 struct ProtocolErrorLog {
     path: PathBuf,
 }
 
 impl ProtocolErrorLog {
     fn new() -> Self {
-        let path = PathBuf::from(CACHE_DIR).join("protocol_errors.log");
+        let path = cache_dir().join("protocol_errors.log");
         Self { path }
     }
 
@@ -164,6 +251,7 @@ impl ProtocolErrorLog {
 
         #[derive(Serialize)]
         struct ProtocolErrorEntry {
+            code: Option<&'static str>,
             error: String,
             len: usize,
             crc32: String,
@@ -172,6 +260,7 @@ impl ProtocolErrorLog {
         }
 
         let entry = ProtocolErrorEntry {
+            code: err.frame_code().map(FrameErrorCode::as_str),
             error: err.to_string(),
             len: payload.len(),
             crc32: format!("{crc32:08x}"),
@@ -202,19 +291,137 @@ fn truncate_for_log(value: &str, max_chars: usize) -> String {
     out
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize)]
 struct LoopStats {
     frames_accepted: u64,
     frames_rejected: u64,
     checksum_failures: u64,
     duplicates: u64,
     reconnects: u64,
+    frame_cache_hits: u64,
+    frame_cache_misses: u64,
+    tunnel_frames: u64,
+    /// Frames dropped by `[render].max_frames_per_sec` because a newer one replaced them.
+    frames_coalesced: u64,
+    /// Frames dropped by a `[filters]` rule.
+    frames_filtered: u64,
+    /// Polling snapshots kept off the screen by `mute:polling`.
+    polls_muted: u64,
+    /// Bytes the LCD has put on the I2C bus, counted as a PCF8574 backpack would see them.
+    i2c_bytes: u64,
+    /// Full-frame writes over `[render].i2c_write_budget_bytes`.
+    frames_over_write_budget: u64,
+    /// Frames sent on to the next node by `[relay]`.
+    relay_forwarded: u64,
+    /// Frames `[relay].rules` kept from the next node.
+    relay_filtered: u64,
+    /// Frames lost while the relay port was down.
+    relay_dropped: u64,
+}
+
+impl LoopStats {
+    /// Add every counter to the Prometheus text.
+    fn export(&self, out: &mut Exposition) {
+        for (name, help, value) in [
+            (
+                "frames_accepted_total",
+                "Frames accepted for display",
+                self.frames_accepted,
+            ),
+            (
+                "frames_rejected_total",
+                "Frames rejected by the parser",
+                self.frames_rejected,
+            ),
+            (
+                "checksum_failures_total",
+                "Frames whose checksum did not match",
+                self.checksum_failures,
+            ),
+            (
+                "duplicate_frames_total",
+                "Frames dropped as duplicates",
+                self.duplicates,
+            ),
+            (
+                "serial_reconnects_total",
+                "Serial reconnect attempts",
+                self.reconnects,
+            ),
+            (
+                "frame_cache_hits_total",
+                "Frames served from the parse cache",
+                self.frame_cache_hits,
+            ),
+            (
+                "frame_cache_misses_total",
+                "Frames that had to be parsed",
+                self.frame_cache_misses,
+            ),
+            (
+                "tunnel_frames_total",
+                "Tunnel frames received, heartbeats excluded",
+                self.tunnel_frames,
+            ),
+            (
+                "frames_coalesced_total",
+                "Frames replaced by a newer one while over max_frames_per_sec",
+                self.frames_coalesced,
+            ),
+            (
+                "frames_filtered_total",
+                "Frames dropped by a [filters] rule",
+                self.frames_filtered,
+            ),
+            (
+                "polling_muted_total",
+                "Polling snapshots kept off the screen by mute:polling",
+                self.polls_muted,
+            ),
+            (
+                "i2c_bytes_total",
+                "Bytes written to the LCD over I2C",
+                self.i2c_bytes,
+            ),
+            (
+                "frames_over_write_budget_total",
+                "Frame writes over [render].i2c_write_budget_bytes",
+                self.frames_over_write_budget,
+            ),
+            (
+                "relay_forwarded_total",
+                "Frames forwarded on the [relay] port",
+                self.relay_forwarded,
+            ),
+            (
+                "relay_filtered_total",
+                "Frames kept from the [relay] port by relay.rules",
+                self.relay_filtered,
+            ),
+            (
+                "relay_dropped_total",
+                "Frames lost while the [relay] port was down",
+                self.relay_dropped,
+            ),
+        ] {
+            out.counter(name, help, value);
+        }
+    }
+}
+
+/// Serial heartbeat cadence: the negotiated keepalive, tightened if the local watchdog has
+/// since been reconfigured below what it was agreed against.
+fn serial_keepalive(config: &AppConfig, keepalive_ms: u64) -> Duration {
+    let local = keepalive_proposal_ms(config.baud, config.watchdog.serial_timeout_ms);
+    Duration::from_millis(keepalive_ms.min(local))
 }
 
-fn heartbeat_interval(timeout_ms: u64) -> Duration {
-    let divided = timeout_ms.saturating_div(HEARTBEAT_INTERVAL_DIVISOR);
-    let millis = divided.max(HEARTBEAT_MIN_TX_MS).min(timeout_ms);
-    Duration::from_millis(millis)
+/// Tunnel heartbeats are not negotiated; they follow the same link-aware rule locally.
+fn tunnel_keepalive(config: &AppConfig) -> Duration {
+    Duration::from_millis(keepalive_proposal_ms(
+        config.baud,
+        config.watchdog.tunnel_timeout_ms,
+    ))
 }
 
 fn log_icon_fallbacks(logger: &Logger, palette: Option<IconPalette>) {
@@ -268,475 +475,148 @@ fn compression_policy_from_config(config: &AppConfig) -> CompressionPolicy {
 
 /// Drive the main render loop: reads serial, rotates pages, scrolls text, handles reconnects.
 #[allow(clippy::too_many_arguments)] // Wiring layer; keeping args explicit avoids hidden global state.
-pub(super) fn run_render_loop(
-    lcd: &mut Lcd,
+pub(super) fn run_render_loop<D: DisplayBackend>(
+    lcd: &mut D,
     config: &mut AppConfig,
     logger: &Logger,
-    mut backoff: BackoffController,
+    backoff: BackoffController,
     mut serial_connection: Option<SerialPort>,
     initial_disconnect_reason: Option<SerialFailureKind>,
-    mut supports_heartbeat: bool,
+    features: FeatureMatrix,
     negotiation_log: &mut NegotiationLog,
+    session: &mut SessionCache,
+    handshakes: &mut HandshakeAudit,
+    keepalive_ms: u64,
+    diagnostics: &mut StartupDiagnostics,
 ) -> Result<()> {
-    let mut compression_policy = compression_policy_from_config(config);
-    let mut state = crate::state::RenderState::new_with_compression(
-        Some(PayloadDefaults {
-            scroll_speed_ms: config.scroll_speed_ms,
-            page_timeout_ms: config.page_timeout_ms,
-        }),
-        compression_policy,
-    );
-    let mut icon_bank = IconBank::new();
-    let mut incoming_line = String::new();
-    let mut last_render = Instant::now();
-    let min_render_interval = Duration::from_millis(200);
-    let mut current_frame: Option<RenderFrame> = None;
-    let mut next_page = Instant::now();
-    let mut next_scroll = Instant::now();
-    let mut scroll_offsets = ScrollOffsets::zero();
-    let mut button_input = Button::new(config.button_gpio_pin).ok();
-    let mut backlight_state = true;
-    let blink_interval = Duration::from_millis(500);
-    let mut next_blink = Instant::now();
-    let mut reconnect_displayed = serial_connection.is_none();
-    let mut last_frame_at = Instant::now();
-    let heartbeat_grace = Duration::from_millis(HEARTBEAT_GRACE_MS);
-    let mut heartbeat_visible = false;
-    let mut next_heartbeat = Instant::now() + Duration::from_millis(HEARTBEAT_BLINK_MS);
-    let mut stats = LoopStats::default();
-    let mut offline_displayed = false;
-    let mut max_backoff_warned = false;
-    let mut last_disconnect_reason = initial_disconnect_reason;
-    let mut serial_watchdog_active = false;
-    let mut tunnel_watchdog_active = false;
-    let mut tunnel = TunnelController::new(config.command_allowlist.clone())?;
-    let mut command_bridge = CommandBridge::new();
-    let mut command_executor = CommandExecutor::new(config.command_allowlist.clone());
-    let protocol_errors = ProtocolErrorLog::new();
-
-    if reconnect_displayed {
-        render_reconnecting(lcd, config.cols)?;
-    }
+    // Zone rows are masked below the gate so nothing else drawn on the main display covers
+    // them. Every backlight write, frame-driven or not, passes the idle/night policy.
+    let mut mask = ZoneMask::new(lcd);
+    let mut gate = BacklightGate::new(&mut mask);
+    let lcd = &mut gate;
+    lcd.set_pwm(open_backlight_pwm(config.backlight.pwm_channel, logger))?;
 
     let running: Arc<AtomicBool> = create_shutdown_flag()?;
-    let mut polling = if config.polling_enabled {
-        Some(PollingState::new(start_polling(
-            config.poll_interval_ms,
-            running.clone(),
-        )))
-    } else {
-        None
+    let config_watch = match loader::default_config_path() {
+        Ok(path) => ConfigWatcher::spawn(path, logger),
+        Err(err) => {
+            logger.warn(format!("config: not watching for changes: {err}"));
+            None
+        }
     };
+    let mut lp = RenderLoop::new(
+        config,
+        logger,
+        backoff,
+        features,
+        keepalive_ms,
+        initial_disconnect_reason,
+        serial_connection.is_some(),
+        running.clone(),
+        config_watch,
+    )?;
+    set_zones(lcd.inner_mut(), &mut lp.state, &lp.config.zones);
+    if let Some(store) = lp.page_store.as_mut() {
+        let restored = store.restore(&mut lp.state, logger);
+        if restored > 0 {
+            logger.info(format!("pages: restored {restored} saved page(s)"));
+        }
+    }
 
-    let mut watchdog = WatchdogMonitor::new(
-        config.watchdog.serial_timeout_ms,
-        config.watchdog.tunnel_timeout_ms,
-    );
-    let mut serial_heartbeat_interval = heartbeat_interval(config.watchdog.serial_timeout_ms);
-    let mut tunnel_heartbeat_interval = heartbeat_interval(config.watchdog.tunnel_timeout_ms);
-    let mut next_serial_heartbeat = Instant::now() + serial_heartbeat_interval;
-    let mut next_tunnel_heartbeat = Instant::now() + tunnel_heartbeat_interval;
+    if lp.reconnect_displayed {
+        diagnostics.render_link_down(lcd, lp.config.cols)?;
+    }
 
+    // READY=1 waits for the serial port; until then systemd only sees a status line.
     if serial_connection.is_some() {
-        watchdog.touch_serial();
-        watchdog.touch_tunnel();
+        lp.systemd
+            .ready(&format!("serial up on {}", lp.config.device), logger);
+    } else {
+        lp.systemd
+            .status(&format!("waiting for {}", lp.config.device));
     }
 
+    let mut incoming_line = String::new();
     while running.load(Ordering::SeqCst) {
-        if let Some(polling_state) = polling.as_mut() {
-            while let Ok(event) = polling_state.handle.receiver().try_recv() {
-                match event {
-                    PollEvent::Snapshot(snapshot) => {
-                        polling_state.record_snapshot(snapshot, logger);
-                    }
-                    PollEvent::Error(err) => {
-                        logger.warn(format!("polling error: {err}"));
-                        polling_state.record_error(&err, logger);
-                    }
-                }
-            }
+        // A background thread panicked and the hook wrote its report; go down the way a panic
+        // here would. `resume_unwind` skips the hook, so no second report is written.
+        if crash::panicked() {
+            std::panic::resume_unwind(Box::new("background thread panicked"));
         }
-
-        // Proactively send heartbeat frames when supported.
-        if supports_heartbeat {
-            if let Some(serial_ref) = serial_connection.as_mut() {
-                let now = Instant::now();
-                if now >= next_serial_heartbeat {
-                    send_command_frame(
-                        serial_ref,
-                        CommandMessage::Heartbeat { request_id: None },
-                        logger,
-                    );
-                    next_serial_heartbeat = now + serial_heartbeat_interval;
-                }
-                if now >= next_tunnel_heartbeat {
-                    send_tunnel_frame(serial_ref, TunnelMsgOwned::Heartbeat, logger);
-                    next_tunnel_heartbeat = now + tunnel_heartbeat_interval;
-                }
-            }
+        lp.systemd.ping(Instant::now());
+        if lcd.recover(Instant::now()) {
+            logger.info("lcd: display answering again; re-initialised and redrawn");
         }
-
-        // Track heartbeat visibility when frames stop arriving for a grace period.
-        let current_time = Instant::now();
+        lp.drain_polling();
         if let Some(serial_ref) = serial_connection.as_mut() {
-            flush_tunnel_messages(serial_ref, &mut tunnel, logger);
-            flush_command_messages(serial_ref, &mut command_executor, logger);
-        }
-        let heartbeat_active = current_time.duration_since(last_frame_at) >= heartbeat_grace;
-        if heartbeat_active && current_time >= next_heartbeat {
-            heartbeat_visible = !heartbeat_visible;
-            next_heartbeat = current_time + Duration::from_millis(HEARTBEAT_BLINK_MS);
-        } else if !heartbeat_active {
-            heartbeat_visible = false;
-            next_heartbeat = current_time + Duration::from_millis(HEARTBEAT_BLINK_MS);
+            lp.send_heartbeats(serial_ref);
         }
-        let heartbeat_on = heartbeat_active && heartbeat_visible;
 
-        // Manual page advance via GPIO button when configured.
-        if let Some(button) = button_input.as_mut() {
-            if button.is_pressed() {
-                if let Some(frame) = state.next_page() {
-                    current_frame = Some(frame);
-                    scroll_offsets = ScrollOffsets::zero();
-                    next_scroll = current_time + Duration::from_millis(config.scroll_speed_ms);
-                    lcd.clear()?;
-                    if let Some(frame) = current_frame.as_ref() {
-                        next_page = current_time + Duration::from_millis(frame.page_timeout_ms);
-                        let palette = render_if_allowed(
-                            lcd,
-                            frame,
-                            &mut last_render,
-                            min_render_interval,
-                            (scroll_offsets.top, scroll_offsets.bottom),
-                            heartbeat_on,
-                            &mut icon_bank,
-                        )?;
-                        log_icon_fallbacks(logger, palette);
-                    }
-                }
-            }
+        let current_time = Instant::now();
+        lp.account_writes(lcd, current_time);
+        if let Some(serial_ref) = serial_connection.as_mut() {
+            lp.flush_outgoing(serial_ref, current_time);
         }
+        lp.step_heartbeat(current_time);
+        let modem_glyph_changed = lp.sample_modem(serial_connection.as_mut(), current_time);
+        lp.step_status(lcd, modem_glyph_changed, current_time)?;
+        lp.serve_inputs(lcd, current_time)?;
 
         // Show reconnect status as soon as we know the serial link is gone.
-        if serial_connection.is_none() && !reconnect_displayed {
-            render_reconnecting(lcd, config.cols)?;
-            reconnect_displayed = true;
+        if serial_connection.is_none() && !lp.reconnect_displayed {
+            diagnostics.render_link_down(lcd, lp.config.cols)?;
+            lp.reconnect_displayed = true;
         }
-
-        // Attempt reconnect when backoff allows; reset indicators on success.
-        if serial_connection.is_none() && backoff.should_retry(current_time) {
-            let delay = backoff.current_delay_ms();
-            stats.reconnects += 1;
-            log_backoff(
-                logger,
-                BackoffPhase::Attempt,
-                stats.reconnects,
-                delay,
-                &backoff,
-                config,
-                last_disconnect_reason,
-            );
-            let reason_suffix = last_disconnect_reason
-                .map(|r| format!(" last_failure={r}"))
-                .unwrap_or_default();
-            logger.info(format!(
-                "reconnect attempt #{}, delay={}ms device={} baud={}{}",
-                stats.reconnects, delay, config.device, config.baud, reason_suffix
-            ));
-            if delay >= backoff.max_delay_ms() && !max_backoff_warned {
-                logger.warn(format!(
-                    "backoff saturated at {}ms; staying in cooldown",
-                    backoff.max_delay_ms()
-                ));
-                max_backoff_warned = true;
-            }
-            match attempt_serial_connect(
-                logger,
-                &config.device,
-                config.serial_options(),
-                &config.negotiation,
-                config.compression_enabled,
+        if serial_connection.is_none() && lp.backoff.should_retry(current_time) {
+            lp.reconnect(
+                lcd,
+                &mut serial_connection,
                 negotiation_log,
-            ) {
-                Ok(outcome) => {
-                    log_backoff(
-                        logger,
-                        BackoffPhase::Success,
-                        stats.reconnects,
-                        delay,
-                        &backoff,
-                        config,
-                        None,
-                    );
-                    serial_connection = Some(outcome.port);
-                    supports_heartbeat = outcome
-                        .remote_caps
-                        .as_ref()
-                        .map(|caps| caps.supports_heartbeat)
-                        .unwrap_or(false);
-                    backoff.mark_success(current_time);
-                    watchdog.touch_serial();
-                    watchdog.touch_tunnel();
-                    next_serial_heartbeat = Instant::now() + serial_heartbeat_interval;
-                    next_tunnel_heartbeat = Instant::now() + tunnel_heartbeat_interval;
-                    lcd.clear()?;
-                    reconnect_displayed = false;
-                    offline_displayed = false;
-                    heartbeat_visible = false;
-                    max_backoff_warned = false;
-                    last_disconnect_reason = None;
-                }
-                Err(reason) => {
-                    log_backoff(
-                        logger,
-                        BackoffPhase::Failure,
-                        stats.reconnects,
-                        delay,
-                        &backoff,
-                        config,
-                        Some(reason),
-                    );
-                    backoff.mark_failure(current_time);
-                    last_disconnect_reason = Some(reason);
-                }
-            }
+                session,
+                handshakes,
+                diagnostics,
+                current_time,
+            )?;
         }
 
+        lp.poll_local_pages(current_time);
+        lp.publish_metrics(lcd, serial_connection.is_some(), current_time);
+        lp.serve_control(lcd, serial_connection.is_some(), handshakes, current_time)?;
+
+        // Idle until the port has data, a background thread has work or the budget runs out.
+        // While the port is down the budget also ends at the next reconnect attempt.
+        let wait_budget = match serial_connection.as_ref() {
+            Some(_) => lp.read_budget,
+            None => lp.read_budget.min(lp.backoff.until_retry(Instant::now())),
+        };
+        let wait_budget = lp
+            .frame_limiter
+            .until_release(Instant::now())
+            .map_or(wait_budget, |due| wait_budget.min(due));
+        lp.idle.wait(serial_connection.as_ref(), wait_budget);
+
         // Read the next frame from serial; handle config reloads or parse failures.
         if let Some(serial_connection_ref) = serial_connection.as_mut() {
             incoming_line.clear();
-            match serial_connection_ref.read_message_line(&mut incoming_line) {
+            let (released, read_result) = next_line(
+                &mut lp.frame_limiter,
+                serial_connection_ref,
+                &mut incoming_line,
+            );
+            match read_result {
                 Ok(read) => {
-                    if read > 0 {
-                        let line = incoming_line.trim_end_matches(&['\r', '\n'][..]).trim();
-                        if !line.is_empty() {
-                            if looks_like_tunnel_frame(line) {
-                                match decode_tunnel_frame(line) {
-                                    Ok(msg) => {
-                                        if matches!(msg, TunnelMsgOwned::Heartbeat) {
-                                            watchdog.touch_serial();
-                                            watchdog.touch_tunnel();
-                                            continue;
-                                        }
-                                        watchdog.touch_serial();
-                                        watchdog.touch_tunnel();
-                                        if let Some(response) = tunnel.handle_msg(msg, logger) {
-                                            send_tunnel_frame(
-                                                serial_connection_ref,
-                                                response,
-                                                logger,
-                                            );
-                                        }
-                                        flush_tunnel_messages(
-                                            serial_connection_ref,
-                                            &mut tunnel,
-                                            logger,
-                                        );
-                                    }
-                                    Err(err) => {
-                                        logger.warn(format!("tunnel frame error: {err}"));
-                                        tunnel.log_frame_error(&format!("tunnel: {err}"), line);
-                                    }
-                                }
-                                continue;
-                            }
-                            if looks_like_command_frame(line) {
-                                match command_bridge.ingest_line(line) {
-                                    Ok(Some(event)) => {
-                                        let label =
-                                            if let Some(id) = command_bridge.last_request_id() {
-                                                format!("cmd#{id} {}", event.kind())
-                                            } else {
-                                                event.kind().to_string()
-                                            };
-                                        logger.debug(format!(
-                                            "command frame buffered ({label}), awaiting executor"
-                                        ));
-                                        if matches!(event, CommandEvent::Heartbeat { .. }) {
-                                            watchdog.touch_serial();
-                                            watchdog.touch_tunnel();
-                                            continue;
-                                        }
-                                        watchdog.touch_serial();
-                                        watchdog.touch_tunnel();
-                                        if let Some(response) = command_executor.handle_event(event)
-                                        {
-                                            send_command_frame(
-                                                serial_connection_ref,
-                                                response,
-                                                logger,
-                                            );
-                                            flush_command_messages(
-                                                serial_connection_ref,
-                                                &mut command_executor,
-                                                logger,
-                                            );
-                                        }
-                                    }
-                                    Ok(None) => {}
-                                    Err(err) => {
-                                        logger.warn(format!("command frame error: {err}"));
-                                    }
-                                }
-                                continue;
-                            }
-                            if !looks_like_payload_frame(line) {
-                                // Ignore obvious garbage/diagnostic chatter (e.g., "INIT", noise
-                                // bytes that survived UTF-8 decoding, etc.) so we don't spam the LCD
-                                // with parse errors.
-                                logger.debug(format!(
-                                    "ignoring non-payload serial frame len={} preview={}",
-                                    line.len(),
-                                    preview_frame(line, 80)
-                                ));
-                                continue;
-                            }
-                            let mut hasher = Hasher::new();
-                            hasher.update(line.as_bytes());
-                            let crc = hasher.finalize();
-                            if logger.level() >= LogLevel::Debug {
-                                logger.debug(format!("frame crc={crc:08x} len={}", line.len()));
-                            }
-                            match state.ingest(line) {
-                                Ok(Some(frame)) if frame.config_reload => {
-                                    stats.frames_accepted += 1;
-                                    watchdog.touch_serial();
-                                    logger.info("config reload requested");
-                                    match Config::load_or_default() {
-                                        Ok(new_cfg) => {
-                                            let old_device = config.device.clone();
-                                            let old_serial = config.serial_options();
-                                            let old_scroll = config.scroll_speed_ms;
-                                            let old_page = config.page_timeout_ms;
-
-                                            config.scroll_speed_ms = new_cfg.scroll_speed_ms;
-                                            config.page_timeout_ms = new_cfg.page_timeout_ms;
-                                            config.backoff_initial_ms = new_cfg.backoff_initial_ms;
-                                            config.backoff_max_ms = new_cfg.backoff_max_ms;
-                                            config.device = new_cfg.device;
-                                            config.baud = new_cfg.baud;
-                                            config.flow_control = new_cfg.flow_control;
-                                            config.parity = new_cfg.parity;
-                                            config.stop_bits = new_cfg.stop_bits;
-                                            config.dtr_on_open = new_cfg.dtr_on_open;
-                                            config.serial_timeout_ms = new_cfg.serial_timeout_ms;
-                                            config.compression_enabled =
-                                                new_cfg.protocol.compression_enabled;
-                                            config.compression_codec =
-                                                new_cfg.protocol.compression_codec;
-                                            config.watchdog = new_cfg.watchdog;
-
-                                            compression_policy =
-                                                compression_policy_from_config(config);
-                                            state.set_compression_policy(compression_policy);
-
-                                            watchdog = WatchdogMonitor::new(
-                                                config.watchdog.serial_timeout_ms,
-                                                config.watchdog.tunnel_timeout_ms,
-                                            );
-                                            serial_heartbeat_interval = heartbeat_interval(
-                                                config.watchdog.serial_timeout_ms,
-                                            );
-                                            tunnel_heartbeat_interval = heartbeat_interval(
-                                                config.watchdog.tunnel_timeout_ms,
-                                            );
-                                            next_serial_heartbeat =
-                                                Instant::now() + serial_heartbeat_interval;
-                                            next_tunnel_heartbeat =
-                                                Instant::now() + tunnel_heartbeat_interval;
-
-                                            let new_serial = config.serial_options();
-
-                                            if old_device != config.device
-                                                || old_serial != new_serial
-                                            {
-                                                logger.info(format!(
-                                                    "config reload updating serial to {} @ {} (flow={}, parity={}, stop_bits={}, dtr={}, timeout={}ms)",
-                                                    config.device,
-                                                    config.baud,
-                                                    config.flow_control,
-                                                    config.parity,
-                                                    config.stop_bits,
-                                                    config.dtr_on_open,
-                                                    config.serial_timeout_ms
-                                                ));
-                                                serial_connection = None;
-                                                reconnect_displayed = false;
-                                                offline_displayed = false;
-                                            }
-                                            if old_scroll != new_cfg.scroll_speed_ms
-                                                || old_page != new_cfg.page_timeout_ms
-                                            {
-                                                logger.debug(format!(
-                                                    "updated defaults: scroll={}ms page_timeout={}ms",
-                                                    config.scroll_speed_ms, config.page_timeout_ms
-                                                ));
-                                            }
-                                            backoff.update(
-                                                config.backoff_initial_ms,
-                                                config.backoff_max_ms,
-                                            );
-                                            state.set_defaults(PayloadDefaults {
-                                                scroll_speed_ms: config.scroll_speed_ms,
-                                                page_timeout_ms: config.page_timeout_ms,
-                                            });
-                                            logger.info("config reload applied");
-                                        }
-                                        Err(err) => {
-                                            logger.warn(format!("config reload failed: {err}"));
-                                        }
-                                    }
-                                }
-                                Ok(Some(frame)) => {
-                                    stats.frames_accepted += 1;
-                                    current_frame = Some(frame.clone());
-                                    scroll_offsets = ScrollOffsets::zero();
-                                    next_scroll = current_time
-                                        + Duration::from_millis(config.scroll_speed_ms);
-                                    lcd.clear()?;
-                                    backlight_state = frame.backlight_on;
-                                    lcd.set_backlight(backlight_state)?;
-                                    lcd.set_blink(frame.blink)?;
-                                    next_blink = current_time + blink_interval;
-                                    last_frame_at = current_time;
-                                    watchdog.touch_serial();
-                                    heartbeat_visible = false;
-                                    if let Some(frame) = current_frame.as_ref() {
-                                        next_page = current_time
-                                            + Duration::from_millis(frame.page_timeout_ms);
-                                        let palette = render_if_allowed(
-                                            lcd,
-                                            frame,
-                                            &mut last_render,
-                                            min_render_interval,
-                                            (scroll_offsets.top, scroll_offsets.bottom),
-                                            heartbeat_on,
-                                            &mut icon_bank,
-                                        )?;
-                                        log_icon_fallbacks(logger, palette);
-                                    }
-                                }
-                                Ok(None) => {
-                                    stats.duplicates += 1;
-                                    watchdog.touch_serial();
-                                    logger.debug(format!("duplicate frame ignored crc={crc:08x}"));
-                                }
-                                Err(err) => {
-                                    stats.frames_rejected += 1;
-                                    if matches!(err, Error::ChecksumMismatch) {
-                                        stats.checksum_failures += 1;
-                                    }
-                                    if matches!(err, Error::Parse(_)) {
-                                        protocol_errors.log(&err, line, crc, logger);
-                                    }
-                                    logger.warn(format!("frame error: {err}"));
-                                    render_parse_error(lcd, config.cols, &err)?;
-                                    backlight_state = true;
-                                    next_blink = current_time + blink_interval;
-                                    continue;
-                                }
-                            }
-                        }
+                    let line = incoming_line.trim_end_matches(&['\r', '\n'][..]).trim();
+                    if read > 0
+                        && !line.is_empty()
+                        && lp.route_serial_line(
+                            lcd,
+                            serial_connection_ref,
+                            line,
+                            released,
+                            current_time,
+                        )? == LineFollowUp::NextPass
+                    {
+                        continue;
                     }
                 }
                 Err(Error::Io(e)) => {
@@ -745,290 +625,3149 @@ pub(super) fn run_render_loop(
                         "serial read error [{reason}]: {e}; scheduling reconnect"
                     ));
                     serial_connection = None;
-                    backoff.mark_failure(current_time);
-                    reconnect_displayed = false;
-                    last_disconnect_reason = Some(reason);
-                    if !offline_displayed {
-                        render_offline_message(lcd, config.cols)?;
-                        offline_displayed = true;
+                    session.mark_lost(current_time);
+                    lp.backoff.mark_failure(current_time);
+                    lp.reconnect_displayed = false;
+                    lp.last_disconnect_reason = Some(reason);
+                    if !lp.offline_displayed {
+                        render_offline_message(lcd, lp.config.cols)?;
+                        lp.offline_displayed = true;
                     }
                 }
                 Err(err) => return Err(err),
             }
-        } else {
-            thread::sleep(Duration::from_millis(50));
-        }
-
-        // Evaluate watchdog states after handling inbound/outbound traffic.
-        let wd_status = watchdog.evaluate(logger);
-        if wd_status.serial_recovered {
-            serial_watchdog_active = false;
-            logger.info("watchdog: serial channel recovered");
-        }
-        if wd_status.tunnel_recovered {
-            tunnel_watchdog_active = false;
-            logger.info("watchdog: tunnel channel recovered");
-        }
-        if wd_status.serial_expired && !serial_watchdog_active {
-            serial_watchdog_active = true;
-            logger.warn("watchdog: serial channel expired; forcing reconnect");
-            if serial_connection.is_some() {
-                serial_connection = None;
-                backoff.mark_failure(current_time);
-                reconnect_displayed = false;
-                offline_displayed = false;
-                last_disconnect_reason = None;
-            }
-            if !offline_displayed {
-                render_offline_message(lcd, config.cols)?;
-                offline_displayed = true;
-            }
-        }
-        if wd_status.tunnel_expired && !tunnel_watchdog_active {
-            tunnel_watchdog_active = true;
-            logger.warn("watchdog: tunnel channel expired");
         }
 
-        // Rotate to the next queued frame after its page timeout.
-        if state.len() > 1 && current_time >= next_page {
-            if let Some(frame) = state.next_page() {
-                current_frame = Some(frame);
-                scroll_offsets = ScrollOffsets::zero();
-                if let Some(frame) = current_frame.as_ref() {
-                    next_page = current_time + Duration::from_millis(frame.page_timeout_ms);
-                    lcd.clear()?;
-                    backlight_state = frame.backlight_on;
-                    lcd.set_backlight(backlight_state)?;
-                    lcd.set_blink(frame.blink)?;
-                    next_blink = current_time + blink_interval;
-                    let palette = render_if_allowed(
-                        lcd,
-                        frame,
-                        &mut last_render,
-                        min_render_interval,
-                        (scroll_offsets.top, scroll_offsets.bottom),
-                        heartbeat_on,
-                        &mut icon_bank,
-                    )?;
-                    log_icon_fallbacks(logger, palette);
-                }
-            }
+        lp.reload_config(lcd, &mut serial_connection, session, current_time)?;
+        lp.restore_after_parse_error(lcd, serial_connection.is_some(), current_time)?;
+        lp.check_watchdog(lcd, &mut serial_connection, session, current_time)?;
+
+        // Raise or clear incident pages as error budgets are exhausted or recover.
+        if apply_slo_transitions(
+            &mut lp.slo,
+            &lp.slo_events,
+            &mut lp.incident_on_screen,
+            current_time,
+            logger,
+        ) {
+            lp.next_page = current_time;
         }
+        lp.show_transfer(lcd, current_time)?;
+        lp.step_interrupts(lcd, current_time)?;
+        lp.show_incidents(lcd, current_time)?;
+        lp.rotate_pages(lcd, serial_connection.is_some(), current_time)?;
 
-        if let Some(frame) = current_frame.as_ref() {
-            let width = lcd.cols() as usize;
-            let needs_scroll = match frame.bar_row {
-                Some(0) => frame.scroll_enabled && line_needs_scroll(&frame.line2, width),
-                Some(1) => frame.scroll_enabled && line_needs_scroll(&frame.line1, width),
-                _ => {
-                    frame.scroll_enabled
-                        && (line_needs_scroll(&frame.line1, width)
-                            || line_needs_scroll(&frame.line2, width))
-                }
-            };
-            // Scroll long lines forward when allowed by the frame.
-            if needs_scroll && current_time >= next_scroll {
-                scroll_offsets = scroll_offsets.update(
-                    advance_offset(&frame.line1, lcd.cols() as usize, scroll_offsets.top),
-                    advance_offset(&frame.line2, lcd.cols() as usize, scroll_offsets.bottom),
-                );
-                next_scroll = current_time + Duration::from_millis(frame.scroll_speed_ms);
-                let palette = render_if_allowed(
-                    lcd,
-                    frame,
-                    &mut last_render,
-                    min_render_interval,
-                    (scroll_offsets.top, scroll_offsets.bottom),
-                    heartbeat_on,
-                    &mut icon_bank,
-                )?;
-                log_icon_fallbacks(logger, palette);
-            }
-
-            if frame.blink {
-                // Drive periodic blink by toggling backlight.
-                if current_time >= next_blink {
-                    backlight_state = !backlight_state;
-                    lcd.set_backlight(backlight_state)?;
-                    next_blink = current_time + blink_interval;
-                }
-            } else if backlight_state != frame.backlight_on {
-                backlight_state = frame.backlight_on;
-                lcd.set_backlight(backlight_state)?;
+        let pattern = lp.animate(lcd, current_time)?;
+        // Step a playing pattern faster than frames usually arrive.
+        if let Some(serial_connection_ref) = serial_connection.as_mut() {
+            lp.read_budget = Duration::from_millis(match pattern {
+                Some(_) => PATTERN_TICK_MS.min(lp.config.serial_timeout_ms),
+                None => lp.config.serial_timeout_ms,
+            });
+            // Wake in time for the next zone page or scroll step as well.
+            if let Some(due) = lp.state.next_zone_step() {
+                let until = due.saturating_duration_since(current_time);
+                lp.read_budget = lp
+                    .read_budget
+                    .min(until.max(Duration::from_millis(ZONE_TICK_MIN_MS)));
             }
+            serial_connection_ref.set_read_timeout(lp.idle.port_timeout(lp.read_budget))?;
         }
 
-        let no_frames_available = state.is_empty();
-        if let Some(polling_state) = polling.as_mut() {
-            maybe_render_polling_overlay(
-                polling_state,
-                lcd,
-                config.cols,
-                serial_connection.is_some(),
-                current_frame.is_some(),
-                no_frames_available,
-            )?;
-        }
+        lp.apply_backlight_policy(lcd, current_time)?;
+        lp.finish_pass(lcd, serial_connection.is_some(), current_time)?;
     }
 
-    // Leave the display in a clean shutdown state.
-    render_shutdown(lcd)?;
-    logger.info(format!(
-        "shutdown: frames accepted={} rejected={} checksum_failures={} duplicates={} reconnects={}",
-        stats.frames_accepted,
-        stats.frames_rejected,
-        stats.checksum_failures,
-        stats.duplicates,
-        stats.reconnects
-    ));
-    logger.info("daemon exiting");
-    Ok(())
+    lp.shut_down(lcd, &mut serial_connection)
 }
 
-fn looks_like_tunnel_frame(line: &str) -> bool {
-    line.contains("\"msg\"") && line.contains("\"crc32\"")
+/// Everything the render loop carries from one pass to the next, apart from the display and
+/// the serial port. Each source the loop serves has a method here that draws on the display it
+/// is handed.
+struct RenderLoop<'a> {
+    config: &'a mut AppConfig,
+    logger: &'a Logger,
+    running: Arc<AtomicBool>,
+    idle: LoopIdle,
+    systemd: SystemdNotifier,
+    config_watch: Option<ConfigWatcher>,
+    reload_requested: bool,
+
+    // What the main display shows and when that changes next.
+    state: RenderState,
+    icon_bank: IconBank,
+    current_frame: Option<RenderFrame>,
+    pending_remote: Option<RenderFrame>,
+    scheduler: PageScheduler,
+    next_page: Instant,
+    next_scroll: Instant,
+    scroll_offsets: ScrollOffsets,
+    one_line: OneLineFold,
+    last_render: Instant,
+    throttle: RenderThrottle,
+    backlight_state: bool,
+    blink_interval: Duration,
+    next_blink: Instant,
+    pattern_phase: Option<(BacklightPattern, Instant)>,
+    last_frame_at: Instant,
+    heartbeat_visible: bool,
+    next_heartbeat: Instant,
+    /// Heartbeat marker state for the current pass.
+    heartbeat_on: bool,
+    /// Modem line glyph for the current pass; `None` when `[modem].glyph` is off.
+    modem_glyph: Option<char>,
+    incident_on_screen: bool,
+    transfer_page: TransferPage,
+    parse_errors: ParseErrorGate,
+    badges: AlertBadges,
+    countdown: ExpiryCountdown,
+    hud: FrameHud,
+    tracer: FrameTracer,
+    backlight_schedule: BacklightSchedule,
+    backlight_minute: u16,
+    next_backlight_check: Instant,
+
+    // The serial link and what keeps it alive.
+    backoff: BackoffController,
+    features: FeatureMatrix,
+    keepalive_ms: u64,
+    serial_heartbeat_interval: Duration,
+    tunnel_heartbeat_interval: Duration,
+    next_serial_heartbeat: Instant,
+    next_tunnel_heartbeat: Instant,
+    reconnect_displayed: bool,
+    offline_displayed: bool,
+    max_backoff_warned: bool,
+    last_disconnect_reason: Option<SerialFailureKind>,
+    serial_watchdog_active: bool,
+    tunnel_watchdog_active: bool,
+    watchdog: WatchdogMonitor,
+    replay: ReplayGuard,
+    burst: BurstController,
+    modem_monitor: Option<ModemMonitor>,
+    frame_ack_seq: u64,
+    frame_limiter: FrameLimiter,
+    read_budget: Duration,
+
+    // Local inputs and outputs, and the services peers and scripts talk to.
+    button_input: Option<Button>,
+    encoder: Option<RotaryEncoder>,
+    annunciators: Option<Annunciators>,
+    extra_displays: ExtraDisplays,
+    relay: Option<Relay>,
+    write_budget: WriteBudget,
+    control: Option<ControlServer>,
+    metrics: Option<MetricsExporter>,
+    polling: Option<PollingState>,
+    spool: Option<Spool>,
+    page_store: Option<PageStore>,
+    log_compactor: LogCompactor,
+    tunnel: TunnelController,
+    command_bridge: CommandBridge,
+    command_executor: CommandExecutor,
+    interlock: Interlock,
+    protocol_errors: ProtocolErrorLog,
+    peer_defaults: PeerDefaults,
+
+    // Counters and error budgets.
+    stats: LoopStats,
+    next_stats_snapshot: Instant,
+    slo: SloTracker,
+    slo_events: SloEventLog,
+    anomalies: AnomalyDetector,
 }
 
-fn looks_like_command_frame(line: &str) -> bool {
-    line.contains("\"channel\":\"command\"") && line.contains("\"crc32\"")
+/// Whether the rest of the pass runs after a serial line was handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineFollowUp {
+    /// The line may have changed the page; carry on with the pass.
+    Render,
+    /// Nothing left to draw for this line; read the next one straight away.
+    NextPass,
 }
 
-fn looks_like_payload_frame(line: &str) -> bool {
-    let trimmed = line.trim();
-    if trimmed.is_empty() {
-        return false;
-    }
-    // Never treat tunnel/command frames as display payloads.
-    if looks_like_tunnel_frame(trimmed) || looks_like_command_frame(trimmed) {
-        return false;
-    }
-    // Display payloads are either JSON objects or `key=value` pairs.
-    trimmed.starts_with('{') || trimmed.contains('=')
+/// What is left for the caller once a payload frame went through
+/// [`RenderLoop::ingest_payload`].
+enum Ingested {
+    /// Accepted, repeated or filtered.
+    Done,
+    /// The parser refused the frame; `crc` identifies the line in logs and the nack.
+    Rejected { err: Error, crc: u32 },
 }
 
-fn preview_frame(line: &str, max_chars: usize) -> String {
-    let mut out = String::new();
-    for ch in line.chars().take(max_chars) {
-        if ch.is_ascii_control() {
-            out.push(' ');
-        } else {
-            out.push(ch);
+impl<'a> RenderLoop<'a> {
+    #[allow(clippy::too_many_arguments)] // The state `run_render_loop` hands over.
+    fn new(
+        config: &'a mut AppConfig,
+        logger: &'a Logger,
+        backoff: BackoffController,
+        features: FeatureMatrix,
+        keepalive_ms: u64,
+        initial_disconnect_reason: Option<SerialFailureKind>,
+        connected: bool,
+        running: Arc<AtomicBool>,
+        config_watch: Option<ConfigWatcher>,
+    ) -> Result<Self> {
+        let now = Instant::now();
+        let backlight_minute = local_minute_of_day();
+        let log_compactor = LogCompactor::new(config.log_compaction.clone());
+        let mut state = RenderState::new_with_compression(
+            Some(PayloadDefaults {
+                scroll_speed_ms: config.scroll_speed_ms,
+                page_timeout_ms: config.page_timeout_ms,
+            }),
+            compression_policy_from_config(config),
+        );
+        state.set_frame_cache_size(config.frame_cache_size as usize);
+        state.set_strict(config.protocol_strict);
+        state.set_accessibility(config.accessibility.profile());
+        state.set_filter(config.filters.build()?);
+        state.set_minute_of_day(backlight_minute);
+        let mut icon_bank = IconBank::new();
+        icon_bank.set_bar_style(config.render.bar_style);
+        let extra_displays = ExtraDisplays::open(config, logger);
+        let mut relay = Relay::open(config, logger)?;
+        if let Some(relay) = relay.as_mut() {
+            relay.set_minute_of_day(backlight_minute);
         }
-    }
-    if line.chars().count() > max_chars {
-        out.push('…');
-    }
-    out
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::Error;
-    use serde_json::Value;
-    use std::fs;
-
-    fn unique_protocol_error_log_path() -> PathBuf {
-        let pid = std::process::id();
-        let nanos = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_nanos())
-            .unwrap_or(0);
-
-        let filename = format!("protocol_errors_{pid}_{nanos}.log");
-        let cache_tests = PathBuf::from(CACHE_DIR).join("tests");
-        if fs::create_dir_all(&cache_tests).is_ok() {
-            cache_tests.join(filename)
-        } else {
-            std::env::temp_dir().join(filename)
+        let idle = LoopIdle::new(logger);
+        let encoder = open_encoder(&config.input, idle.waker(), logger);
+        let annunciators = open_annunciators(&config.annunciators, logger);
+        let mut tunnel = TunnelController::new(
+            config.command_allowlist.clone(),
+            config.tail_allowlist.clone(),
+        )?;
+        tunnel.set_waker(idle.waker());
+        let mut command_executor = CommandExecutor::new(config.command_allowlist.clone());
+        command_executor.set_waker(idle.waker());
+        let command_auth = CommandAuth::from_config(&config.tunnel_auth);
+        tunnel.set_auth(command_auth.clone());
+        command_executor.set_auth(command_auth);
+        let mut burst = BurstController::new(config.baud, &config.burst, now);
+        burst.set_initiate(features.peer_supports(Feature::Burst));
+        if config.trace {
+            logger.info("trace: following every serial line through to the LCD");
+        }
+        let spool = Spool::from_config(&config.spool, now);
+        if let Some(spool) = spool.as_ref() {
+            logger.info(format!("spool: watching {}", spool.dir().display()));
         }
-    }
-
-    #[test]
-    fn protocol_error_log_records_len_crc32_preview_and_payload() {
-        let path = unique_protocol_error_log_path();
-        let _ = fs::remove_file(&path);
-
-        let log = ProtocolErrorLog { path: path.clone() };
-        let mut payload = "A".repeat(700);
-        payload.push('\u{0}');
-        payload.push_str("TAIL");
-
-        let mut hasher = Hasher::new();
-        hasher.update(payload.as_bytes());
-        let crc = hasher.finalize();
-
-        log.append(&Error::Parse("json: expected value".into()), &payload, crc)
-            .unwrap();
-
-        let contents = fs::read_to_string(&path).unwrap();
-        let first_line = contents.lines().next().unwrap();
-        let parsed: Value = serde_json::from_str(first_line).unwrap();
 
-        assert_eq!(parsed["len"].as_u64().unwrap() as usize, payload.len());
-        assert_eq!(parsed["crc32"].as_str().unwrap(), format!("{crc:08x}"));
-        assert!(parsed["preview"].as_str().unwrap().chars().count() <= 161); // 160 + optional ellipsis
-        assert!(parsed["error"].as_str().unwrap().contains("parse error"));
+        let control = open_control(&config.control, running.clone(), idle.waker(), logger);
+        let metrics = MetricsExporter::start(&config.metrics, running.clone(), logger);
+        let polling = if config.polling_enabled {
+            Some(PollingState::new(
+                start_polling(config.poll_interval_ms, running.clone(), idle.waker()),
+                PollLayout::from_config(&config.poll_overlay),
+            ))
+        } else {
+            None
+        };
 
-        let logged_payload = parsed["payload"].as_str().unwrap();
-        assert!(logged_payload.chars().count() <= 513); // 512 + optional ellipsis
-        assert!(
-            !logged_payload.chars().any(|c| c.is_ascii_control()),
-            "control characters must be scrubbed"
+        let mut watchdog = WatchdogMonitor::new(
+            config.watchdog.serial_timeout_ms,
+            config.watchdog.tunnel_timeout_ms,
         );
+        let serial_heartbeat_interval = serial_keepalive(config, keepalive_ms);
+        let tunnel_heartbeat_interval = tunnel_keepalive(config);
+        logger.info(format!(
+            "keepalive: serial every {}ms, tunnel every {}ms",
+            serial_heartbeat_interval.as_millis(),
+            tunnel_heartbeat_interval.as_millis()
+        ));
+        if connected {
+            watchdog.touch_serial();
+            watchdog.touch_tunnel();
+        }
 
-        // Best-effort cleanup.
-        let _ = fs::remove_file(&path);
+        Ok(Self {
+            logger,
+            running,
+            systemd: SystemdNotifier::from_env(logger),
+            config_watch,
+            reload_requested: false,
+
+            icon_bank,
+            current_frame: None,
+            pending_remote: None,
+            scheduler: PageScheduler::new(
+                config.scheduler.remote_weight,
+                config.scheduler.local_weight,
+                config.scheduler.min_display_ms,
+            ),
+            next_page: now,
+            next_scroll: now,
+            scroll_offsets: ScrollOffsets::zero(),
+            one_line: OneLineFold::new(now),
+            last_render: now,
+            throttle: RenderThrottle::new(&config.render, now),
+            backlight_state: true,
+            blink_interval: Duration::from_millis(config.render.blink_ms),
+            next_blink: now,
+            pattern_phase: None,
+            last_frame_at: now,
+            heartbeat_visible: false,
+            next_heartbeat: now + Duration::from_millis(HEARTBEAT_BLINK_MS),
+            heartbeat_on: false,
+            modem_glyph: None,
+            incident_on_screen: false,
+            transfer_page: TransferPage::default(),
+            parse_errors: ParseErrorGate::new(&config.parse_errors),
+            badges: AlertBadges::new(&config.badges),
+            countdown: ExpiryCountdown::new(config.render.expiry_countdown),
+            hud: FrameHud::new(now),
+            tracer: FrameTracer::new(config.trace),
+            backlight_schedule: BacklightSchedule::new(&config.backlight, now),
+            backlight_minute,
+            next_backlight_check: now,
+
+            backoff,
+            features,
+            keepalive_ms,
+            serial_heartbeat_interval,
+            tunnel_heartbeat_interval,
+            next_serial_heartbeat: now + serial_heartbeat_interval,
+            next_tunnel_heartbeat: now + tunnel_heartbeat_interval,
+            reconnect_displayed: !connected,
+            offline_displayed: false,
+            max_backoff_warned: false,
+            last_disconnect_reason: initial_disconnect_reason,
+            serial_watchdog_active: false,
+            tunnel_watchdog_active: false,
+            watchdog,
+            replay: ReplayGuard::new(&config.replay),
+            burst,
+            modem_monitor: ModemMonitor::new(config.modem.poll_ms),
+            frame_ack_seq: 0,
+            frame_limiter: FrameLimiter::new(config.render.max_frames_per_sec, now),
+            read_budget: Duration::from_millis(config.serial_timeout_ms),
+
+            button_input: Button::new(config.button_gpio_pin).ok(),
+            encoder,
+            annunciators,
+            extra_displays,
+            relay,
+            write_budget: WriteBudget::new(config.render.i2c_write_budget_bytes, now),
+            control,
+            metrics,
+            polling,
+            spool,
+            page_store: PageStore::from_config(&config.render, now),
+            log_compactor,
+            tunnel,
+            command_bridge: CommandBridge::new(),
+            command_executor,
+            interlock: Interlock::new(&config.interlock),
+            protocol_errors: ProtocolErrorLog::new(),
+            peer_defaults: PeerDefaults::new(&config.peer_defaults),
+
+            stats: LoopStats::default(),
+            next_stats_snapshot: now + Duration::from_millis(LOOP_STATS_SNAPSHOT_MS),
+            slo: SloTracker::new(&config.slo),
+            slo_events: SloEventLog::new(&config.slo),
+            anomalies: AnomalyDetector::new(&config.anomaly),
+
+            state,
+            idle,
+            config,
+        })
     }
 
-    #[test]
-    fn payload_probe_accepts_json_and_kv() {
-        assert!(looks_like_payload_frame(
-            r#"{"schema_version":1,"line1":"A","line2":"B"}"#
-        ));
-        assert!(looks_like_payload_frame(
-            "schema_version=1 line1=Hello line2=World"
-        ));
-        assert!(looks_like_payload_frame("  schema_version=1 line1=Hello  "));
+    /// The remote frame the display is showing, if no local, incident or error page covers it.
+    fn remote_on_screen(&self) -> Option<&RenderFrame> {
+        self.current_frame.as_ref().filter(|_| {
+            self.scheduler.current() != Some(PageSource::Local)
+                && !self.incident_on_screen
+                && !self.parse_errors.showing()
+        })
     }
 
-    #[test]
-    fn payload_probe_rejects_empty_garbage_and_control_frames() {
-        assert!(!looks_like_payload_frame(""));
-        assert!(!looks_like_payload_frame("\r\n\t  "));
-        assert!(!looks_like_payload_frame("INIT"));
-        assert!(!looks_like_payload_frame("\u{0}\u{1}\u{2}"));
-
-        // Tunnel and command frames are not display payloads.
-        assert!(!looks_like_payload_frame(
-            r#"{"msg":"heartbeat","crc32":123,"schema_version":1}"#
-        ));
-        assert!(!looks_like_payload_frame(
-            r#"{"channel":"command","schema_version":1,"message":{"type":"ack","request_id":1},"crc32":1}"#
-        ));
+    /// True while something other than the remote rotation owns the display.
+    fn local_page_active(&self) -> bool {
+        self.scheduler.current() == Some(PageSource::Local)
+            || self.incident_on_screen
+            || self.transfer_page.on_screen
     }
 
-    #[test]
-    fn preview_frame_strips_control_and_truncates() {
-        let p = preview_frame("a\u{0}b\u{1}c", 10);
-        assert_eq!(p, "a b c");
-
-        let p = preview_frame("abcdefghijk", 5);
-        assert_eq!(p, "abcde…");
+    /// Draw the current frame now, bypassing the render throttle.
+    fn draw_frame<D: DisplayBackend>(&mut self, lcd: &mut D, now: Instant) -> Result<()> {
+        if let Some(frame) = self.current_frame.as_ref() {
+            let palette = render_frame_with_scroll(
+                lcd,
+                frame,
+                &self.scroll_offsets.rows,
+                self.heartbeat_on,
+                self.badges
+                    .marks(self.modem_glyph)
+                    .folded(self.one_line.row()),
+                self.countdown.level_for(frame, &self.state, now),
+                &mut self.icon_bank,
+            )?;
+            self.last_render = now;
+            log_icon_fallbacks(self.logger, Some(palette));
+        }
+        Ok(())
     }
-}
 
-fn flush_tunnel_messages(serial: &mut SerialPort, tunnel: &mut TunnelController, logger: &Logger) {
-    while let Some(msg) = tunnel.next_outgoing() {
-        send_tunnel_frame(serial, msg, logger);
+    /// Draw the current frame unless the render throttle says it is too soon.
+    fn draw_frame_throttled<D: DisplayBackend>(&mut self, lcd: &mut D, now: Instant) -> Result<()> {
+        if let Some(frame) = self.current_frame.as_ref() {
+            let palette = render_if_allowed(
+                lcd,
+                frame,
+                &mut self.last_render,
+                self.throttle.interval(),
+                &self.scroll_offsets.rows,
+                self.heartbeat_on,
+                self.badges
+                    .marks(self.modem_glyph)
+                    .folded(self.one_line.row()),
+                self.countdown.level_for(frame, &self.state, now),
+                &mut self.icon_bank,
+            )?;
+            log_icon_fallbacks(self.logger, palette);
+        }
+        Ok(())
     }
-}
 
-fn send_tunnel_frame(serial: &mut SerialPort, msg: TunnelMsgOwned, logger: &Logger) {
-    match encode_tunnel_msg(&msg) {
-        Ok(encoded) => {
-            if let Err(err) = serial.send_command_line(&encoded) {
-                logger.warn(format!("tunnel send failed: {err}"));
+    /// Take in what the polling thread sampled since the last pass.
+    fn drain_polling(&mut self) {
+        let Some(polling_state) = self.polling.as_mut() else {
+            return;
+        };
+        while let Ok(event) = polling_state.handle.receiver().try_recv() {
+            match event {
+                PollEvent::Snapshot(snapshot) => {
+                    if let Some(anomaly) = snapshot.temperature_c.and_then(|celsius| {
+                        self.anomalies.record_temperature(celsius, Instant::now())
+                    }) {
+                        self.logger
+                            .warn(format!("anomaly: {}: {}", anomaly.kind, anomaly.summary));
+                    }
+                    polling_state.record_snapshot(snapshot, self.logger);
+                    if self.state.mutes_polling() {
+                        self.stats.polls_muted += 1;
+                        polling_state.latest = None;
+                    }
+                }
+                PollEvent::Error(err) => {
+                    self.logger.warn(format!("polling error: {err}"));
+                    polling_state.record_error(&err, self.logger);
+                }
+            }
+        }
+    }
+
+    /// Proactively send heartbeat frames when supported.
+    fn send_heartbeats(&mut self, serial: &mut SerialPort) {
+        if !self.features.active(Feature::Heartbeat) {
+            return;
+        }
+        let now = Instant::now();
+        if now >= self.next_serial_heartbeat {
+            send_command_frame(
+                serial,
+                CommandMessage::Heartbeat { request_id: None },
+                &mut self.replay,
+                self.logger,
+            );
+            self.next_serial_heartbeat = now + self.serial_heartbeat_interval;
+        }
+        if now >= self.next_tunnel_heartbeat && self.features.active(Feature::Tunnel) {
+            send_tunnel_frame(
+                serial,
+                TunnelMsgOwned::Heartbeat,
+                &mut self.replay,
+                self.logger,
+            );
+            self.next_tunnel_heartbeat = now + self.tunnel_heartbeat_interval;
+        }
+    }
+
+    /// Feed the time and bytes of the last LCD writes to the HUD, the render throttle and the
+    /// I2C write budget, and service the relay port.
+    fn account_writes<D: DisplayBackend>(&mut self, lcd: &mut D, now: Instant) {
+        let logger = self.logger;
+        self.hud.tick(now);
+        if let Some(elapsed) = lcd.take_write_time() {
+            self.hud.record_write(elapsed);
+            let remote_drawn = self.current_frame.as_ref().filter(|_| {
+                self.scheduler.current() != Some(PageSource::Local)
+                    && !self.incident_on_screen
+                    && !self.parse_errors.showing()
+            });
+            self.tracer.lcd_write(remote_drawn, elapsed, now, logger);
+            if let Some(interval) = self.throttle.record_write(elapsed) {
+                logger.info(format!(
+                    "render: throttle now {}ms (<= {:.1} fps) after {}ms LCD writes",
+                    interval.as_millis(),
+                    fps_ceiling(interval),
+                    elapsed.as_millis()
+                ));
+            }
+        }
+        if let Some(bytes) = lcd.take_write_bytes() {
+            if let Some(warning) = self.write_budget.record_frame(bytes, now) {
+                logger.warn(warning);
+            }
+        }
+        // The extra displays share the bus, so their writes count towards the rate too.
+        let bus_bytes = lcd.bus_bytes() + self.extra_displays.bus_bytes();
+        self.write_budget.observe(bus_bytes, now);
+        self.stats.i2c_bytes = bus_bytes;
+        self.stats.frames_over_write_budget = self.write_budget.over_budget();
+        if let Some(relay) = self.relay.as_mut() {
+            poll_relay(relay, &mut self.stats, now, logger);
+        }
+        if let Some(fps) = self.throttle.rate_report(now).filter(|fps| *fps > 0.0) {
+            logger.info(format!(
+                "render: {fps:.1} fps effective (ceiling {:.1} fps)",
+                fps_ceiling(self.throttle.interval())
+            ));
+        }
+    }
+
+    /// Send what the anomaly detector, tunnel and command executor queued, and let the burst
+    /// controller react to the traffic.
+    fn flush_outgoing(&mut self, serial: &mut SerialPort, now: Instant) {
+        if self.features.active(Feature::Anomaly) {
+            for summary in self.anomalies.take_pending() {
+                send_anomaly_summary(serial, summary, self.logger);
+            }
+        }
+        flush_tunnel_messages(serial, &mut self.tunnel, &mut self.replay, self.logger);
+        flush_command_messages(
+            serial,
+            &mut self.command_executor,
+            &mut self.replay,
+            self.logger,
+        );
+        // Upshift while large transfers flow; drop back once they finish or time out.
+        let mut steps = self.burst.observe_tx(serial.tx_bytes(), now);
+        steps.extend(self.burst.poll(now));
+        apply_burst_steps(serial, steps, self.logger);
+    }
+
+    /// Track heartbeat visibility when frames stop arriving for a grace period.
+    fn step_heartbeat(&mut self, now: Instant) {
+        let heartbeat_active =
+            now.duration_since(self.last_frame_at) >= Duration::from_millis(HEARTBEAT_GRACE_MS);
+        if heartbeat_active && now >= self.next_heartbeat {
+            self.heartbeat_visible = !self.heartbeat_visible;
+            self.next_heartbeat = now + Duration::from_millis(HEARTBEAT_BLINK_MS);
+        } else if !heartbeat_active {
+            self.heartbeat_visible = false;
+            self.next_heartbeat = now + Duration::from_millis(HEARTBEAT_BLINK_MS);
+        }
+        self.heartbeat_on = heartbeat_active && self.heartbeat_visible;
+    }
+
+    /// Sample CTS/DSR/CD so handshake problems show up without a scope. Returns `true` when
+    /// the glyph for the status row changed.
+    fn sample_modem(&mut self, serial: Option<&mut SerialPort>, now: Instant) -> bool {
+        let mut glyph_changed = false;
+        if let (Some(monitor), Some(serial_ref)) = (self.modem_monitor.as_mut(), serial) {
+            if monitor.due(now) {
+                match serial_ref.read_modem_lines() {
+                    Ok(lines) => {
+                        let previous_glyph = monitor.glyph();
+                        if monitor.observe(lines) {
+                            self.logger.info(format!("modem lines: {lines}"));
+                            if let Err(err) = log_modem_event(&self.config.device, lines) {
+                                self.logger
+                                    .debug(format!("modem telemetry write failed: {err}"));
+                            }
+                        }
+                        glyph_changed = previous_glyph != monitor.glyph();
+                    }
+                    Err(err) => self.logger.debug(format!("modem line read failed: {err}")),
+                }
+            }
+        }
+        self.modem_glyph = if self.config.modem.glyph {
+            self.modem_monitor.as_ref().and_then(ModemMonitor::glyph)
+        } else {
+            None
+        };
+        glyph_changed
+    }
+
+    /// Step the expiry countdown for the remote frame on screen and redraw the status marks
+    /// when any of them changed.
+    fn step_status<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        modem_glyph_changed: bool,
+        now: Instant,
+    ) -> Result<()> {
+        let remote_on_screen = self.current_frame.as_ref().filter(|_| {
+            self.scheduler.current() != Some(PageSource::Local)
+                && !self.incident_on_screen
+                && !self.parse_errors.showing()
+        });
+        let countdown_tick = self.countdown.tick(remote_on_screen, &self.state, now);
+        if countdown_tick.expired {
+            // The page outlived its duration_ms while on screen; take it down now rather than
+            // at the next page timeout, as the countdown promised.
+            self.logger.debug("render: frame on screen expired");
+            self.current_frame = None;
+            self.scheduler.end_slot();
+            self.next_page = now;
+            if self.state.is_empty() {
+                lcd.clear()?;
+                lcd.set_blink(false)?;
+            }
+        }
+
+        // Errors are counted from the rejected-frame total, whatever path rejected them.
+        self.badges.sync_rejected(self.stats.frames_rejected);
+        if ((modem_glyph_changed && self.config.modem.glyph)
+            || countdown_tick.redraw
+            || self.badges.take_dirty()
+            || self.hud.take_dirty())
+            && self.scheduler.current() != Some(PageSource::Local)
+            && !self.parse_errors.showing()
+        {
+            if let Some(frame) = self.current_frame.as_ref() {
+                // Bypass the render throttle so the glyphs track line state and time promptly.
+                let palette = render_frame_with_scroll(
+                    lcd,
+                    frame,
+                    &self.scroll_offsets.rows,
+                    self.heartbeat_on,
+                    self.badges
+                        .marks(self.modem_glyph)
+                        .folded(self.one_line.row()),
+                    countdown_tick.level,
+                    &mut self.icon_bank,
+                )?;
+                self.last_render = now;
+                log_icon_fallbacks(self.logger, Some(palette));
+            }
+        }
+        Ok(())
+    }
+
+    /// Manual navigation via the GPIO button and the rotary encoder when configured. Input on
+    /// a dark panel only lights it.
+    fn serve_inputs<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut BacklightGate<'_, D>,
+        now: Instant,
+    ) -> Result<()> {
+        let mut manual_page = None;
+        let was_dark = lcd.is_held_off();
+        if let Some(button) = self.button_input.as_mut() {
+            if button.is_pressed() {
+                self.backlight_schedule.on_input(now);
+                if !was_dark
+                    && !arm_from_button(
+                        &mut self.interlock,
+                        lcd,
+                        self.config.cols,
+                        now,
+                        self.logger,
+                    )?
+                    && !clear_badges(&mut self.badges, self.logger)
+                {
+                    manual_page = self.state.next_page().map(|frame| (frame, "button press"));
+                }
+            }
+        }
+        let mut encoder_events: Vec<EncoderEvent> = self
+            .encoder
+            .as_ref()
+            .map(|encoder| encoder.events().collect())
+            .unwrap_or_default();
+        if !encoder_events.is_empty() {
+            self.backlight_schedule.on_input(now);
+            if was_dark {
+                encoder_events.clear();
+            }
+        }
+        for event in encoder_events {
+            match event {
+                EncoderEvent::Forward => {
+                    manual_page = self
+                        .state
+                        .next_page()
+                        .map(|frame| (frame, "encoder forward"));
+                }
+                EncoderEvent::Back => {
+                    manual_page = self.state.prev_page().map(|frame| (frame, "encoder back"));
+                }
+                EncoderEvent::Press => {
+                    if !arm_from_button(
+                        &mut self.interlock,
+                        lcd,
+                        self.config.cols,
+                        now,
+                        self.logger,
+                    )? && !clear_badges(&mut self.badges, self.logger)
+                    {
+                        manual_page = self.state.next_page().map(|frame| (frame, "encoder press"));
+                    }
+                }
+                EncoderEvent::LongPress => {
+                    self.backlight_state = !self.backlight_state;
+                    lcd.set_backlight(self.backlight_state)?;
+                    self.logger.info(format!(
+                        "encoder: backlight {}",
+                        if self.backlight_state { "on" } else { "off" }
+                    ));
+                }
+            }
+        }
+        if let Some((frame, reason)) = manual_page {
+            self.tracer.scheduled(&frame, reason, now, self.logger);
+            self.next_page = now + Duration::from_millis(frame.page_timeout_ms);
+            self.current_frame = Some(frame);
+            self.scroll_offsets = ScrollOffsets::zero();
+            self.one_line.reset(now);
+            self.next_scroll = now + Duration::from_millis(self.config.scroll_speed_ms);
+            lcd.prepare_redraw()?;
+            self.draw_frame_throttled(lcd, now)?;
+            self.scheduler.begin_slot(PageSource::Remote, now);
+        }
+        Ok(())
+    }
+
+    /// Attempt a reconnect now that the backoff allows one; reset the link indicators on
+    /// success.
+    #[allow(clippy::too_many_arguments)] // The handshake state lives with the caller.
+    fn reconnect<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        serial_connection: &mut Option<SerialPort>,
+        negotiation_log: &mut NegotiationLog,
+        session: &mut SessionCache,
+        handshakes: &mut HandshakeAudit,
+        diagnostics: &mut StartupDiagnostics,
+        now: Instant,
+    ) -> Result<()> {
+        let logger = self.logger;
+        let delay = self.backoff.current_delay_ms();
+        self.stats.reconnects += 1;
+        self.slo.record(SloKind::Reconnect, now);
+        if let Some(anomaly) = self.anomalies.record_reconnect(now) {
+            logger.warn(format!("anomaly: {}: {}", anomaly.kind, anomaly.summary));
+        }
+        log_backoff(
+            logger,
+            BackoffPhase::Attempt,
+            self.stats.reconnects,
+            delay,
+            &self.backoff,
+            self.config,
+            self.last_disconnect_reason,
+        );
+        let reason_suffix = self
+            .last_disconnect_reason
+            .map(|r| format!(" last_failure={r}"))
+            .unwrap_or_default();
+        logger.info(format!(
+            "reconnect attempt #{}, delay={}ms device={} baud={}{}",
+            self.stats.reconnects, delay, self.config.device, self.config.baud, reason_suffix
+        ));
+        if delay >= self.backoff.max_delay_ms() && !self.max_backoff_warned {
+            logger.warn(format!(
+                "backoff saturated at {}ms; staying in cooldown",
+                self.backoff.max_delay_ms()
+            ));
+            self.max_backoff_warned = true;
+        }
+        match attempt_serial_connect(
+            logger,
+            &self.config.device,
+            self.config.serial_options(),
+            &self.config.negotiation,
+            self.config.compression_enabled,
+            keepalive_proposal_ms(self.config.baud, self.config.watchdog.serial_timeout_ms),
+            negotiation_log,
+            session,
+            handshakes,
+        ) {
+            Ok(outcome) => {
+                log_backoff(
+                    logger,
+                    BackoffPhase::Success,
+                    self.stats.reconnects,
+                    delay,
+                    &self.backoff,
+                    self.config,
+                    None,
+                );
+                diagnostics.record_success(logger);
+                self.systemd
+                    .ready(&format!("serial up on {}", self.config.device), logger);
+                *serial_connection = Some(outcome.port);
+                self.keepalive_ms = outcome.keepalive_ms;
+                self.serial_heartbeat_interval = serial_keepalive(self.config, self.keepalive_ms);
+                if !outcome.resumed {
+                    self.replay.reset();
+                    self.features =
+                        FeatureMatrix::negotiate(self.config, outcome.remote_caps.as_ref());
+                    self.features.announce(logger);
+                }
+                // The port was reopened at the base baud, so any burst is over.
+                self.burst.reset(now);
+                self.burst
+                    .set_initiate(self.features.peer_supports(Feature::Burst));
+                self.backoff.mark_success(now);
+                self.watchdog.touch_serial();
+                self.watchdog.touch_tunnel();
+                self.next_serial_heartbeat = Instant::now() + self.serial_heartbeat_interval;
+                self.next_tunnel_heartbeat = Instant::now() + self.tunnel_heartbeat_interval;
+                lcd.clear()?;
+                self.reconnect_displayed = false;
+                self.offline_displayed = false;
+                self.heartbeat_visible = false;
+                self.max_backoff_warned = false;
+                self.last_disconnect_reason = None;
+                self.state.arm_replay_guard();
+                if let Some(monitor) = self.modem_monitor.as_mut() {
+                    monitor.reset();
+                }
+            }
+            Err(reason) => {
+                log_backoff(
+                    logger,
+                    BackoffPhase::Failure,
+                    self.stats.reconnects,
+                    delay,
+                    &self.backoff,
+                    self.config,
+                    Some(reason),
+                );
+                self.backoff.mark_failure(now);
+                self.last_disconnect_reason = Some(reason);
+                if diagnostics.retry_failed(reason, self.config, logger) {
+                    diagnostics.render_link_down(lcd, self.config.cols)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue pages that local producers dropped into the spool directory, save the rotation
+    /// when due and append the loop counters to their log.
+    fn poll_local_pages(&mut self, now: Instant) {
+        if let Some(spool) = self.spool.as_mut() {
+            let scan = spool.poll(now, &mut self.state, self.logger);
+            self.stats.frames_accepted += scan.accepted as u64;
+            self.stats.duplicates += scan.duplicates as u64;
+            self.stats.frames_rejected += scan.rejected as u64;
+            self.badges.on_alerts(scan.alerts);
+            if scan.accepted > 0 {
+                self.backlight_schedule.on_frame(now);
+            }
+            if scan.accepted > 0 && self.current_frame.is_none() {
+                // Nothing remote on screen yet, so show the first spooled page right away.
+                self.next_page = now;
+            }
+        }
+        if let Some(store) = self.page_store.as_mut() {
+            store.poll(now, &self.state, self.logger);
+        }
+        if now >= self.next_stats_snapshot {
+            self.next_stats_snapshot = now + Duration::from_millis(LOOP_STATS_SNAPSHOT_MS);
+            if let Err(err) = log_loop_stats(&self.stats) {
+                self.logger
+                    .debug(format!("loop stats telemetry write failed: {err}"));
+            }
+        }
+    }
+
+    /// Refresh the text served to Prometheus scrapers.
+    fn publish_metrics<D: DisplayBackend>(
+        &mut self,
+        lcd: &BacklightGate<'_, D>,
+        serial_connected: bool,
+        now: Instant,
+    ) {
+        let Some(exporter) = self.metrics.as_mut().filter(|exporter| exporter.due(now)) else {
+            return;
+        };
+        let mut out = Exposition::new();
+        self.stats.export(&mut out);
+        let flag = |on: bool| if on { 1.0 } else { 0.0 };
+        out.gauge("pages", "Pages in the rotation", self.state.len() as f64);
+        out.gauge(
+            "i2c_bytes_per_second",
+            "I2C bytes written to the LCD over the last second",
+            self.write_budget.bytes_per_second() as f64,
+        );
+        out.gauge(
+            "i2c_bytes_last_frame",
+            "I2C bytes taken by the last full-frame write",
+            self.write_budget.last_frame_bytes() as f64,
+        );
+        out.gauge(
+            "serial_connected",
+            "1 while the serial port is open",
+            flag(serial_connected),
+        );
+        out.gauge(
+            "serial_backoff_delay_seconds",
+            "Delay before the next reconnect attempt",
+            self.backoff.current_delay_ms() as f64 / 1000.0,
+        );
+        out.gauge(
+            "serial_backoff_saturated",
+            "1 while reconnects are held at the maximum delay",
+            flag(self.max_backoff_warned),
+        );
+        out.gauge(
+            "tunnel_up",
+            "0 while the tunnel watchdog has expired",
+            flag(!self.tunnel_watchdog_active),
+        );
+        out.gauge(
+            "backlight_on",
+            "Backlight state pages and controls asked for",
+            flag(self.backlight_state),
+        );
+        out.gauge(
+            "backlight_dimmed",
+            "1 while the idle timeout or night schedule holds the backlight off",
+            flag(lcd.is_held_off()),
+        );
+        if let Some(snapshot) = self
+            .polling
+            .as_ref()
+            .and_then(|polling| polling.latest.as_ref())
+        {
+            out.poll_snapshot(snapshot);
+        }
+        exporter.publish(out.into_text(), now);
+    }
+
+    /// Serve commands from local scripts on the control socket.
+    fn serve_control<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut BacklightGate<'_, D>,
+        serial_connected: bool,
+        handshakes: &HandshakeAudit,
+        now: Instant,
+    ) -> Result<()> {
+        while let Some(request) = self.control.as_ref().and_then(ControlServer::try_recv) {
+            let reply = match request.command() {
+                ControlCommand::Frame { payload } => {
+                    let dropped_before = self.state.filter().dropped();
+                    match self.state.ingest(&payload_text(payload)) {
+                        Ok(Some(frame)) => {
+                            self.stats.frames_accepted += 1;
+                            self.badges.on_frame(&frame);
+                            if self.current_frame.is_none() {
+                                self.next_page = now;
+                            }
+                            ok_reply(json!({ "pages": self.state.len() }))
+                        }
+                        Ok(None) if self.state.filter().dropped() > dropped_before => {
+                            self.stats.frames_filtered += 1;
+                            ok_reply(json!({ "pages": self.state.len(), "filtered": true }))
+                        }
+                        Ok(None) => {
+                            self.stats.duplicates += 1;
+                            ok_reply(json!({ "pages": self.state.len(), "duplicate": true }))
+                        }
+                        Err(err) => {
+                            self.stats.frames_rejected += 1;
+                            error_reply(err)
+                        }
+                    }
+                }
+                ControlCommand::Clear => {
+                    let remote_shown = self.remote_on_screen().is_some();
+                    self.state.clear();
+                    self.current_frame = None;
+                    self.pending_remote = None;
+                    if remote_shown {
+                        self.scheduler.end_slot();
+                        self.next_page = now;
+                        lcd.clear()?;
+                        lcd.set_blink(false)?;
+                    }
+                    self.logger.info("control: pages cleared");
+                    ok_reply(json!({}))
+                }
+                ControlCommand::Backlight { on } => {
+                    self.backlight_schedule.on_input(now);
+                    self.backlight_state = *on;
+                    lcd.set_backlight(self.backlight_state)?;
+                    ok_reply(json!({}))
+                }
+                command @ (ControlCommand::Stats | ControlCommand::Snapshot) => {
+                    let mut body = serde_json::to_value(&self.stats).unwrap_or_default();
+                    body["pages"] = json!(self.state.len());
+                    body["serial_connected"] = json!(serial_connected);
+                    body["backlight"] = json!(self.backlight_state);
+                    body["backlight_dimmed"] = json!(lcd.is_held_off());
+                    body["hud"] = json!(self.hud.enabled());
+                    if *command == ControlCommand::Stats {
+                        ok_reply(json!({ "stats": body }))
+                    } else {
+                        let queue: Vec<_> = self
+                            .state
+                            .queued()
+                            .map(|frame| json!([frame.line1, frame.line2]))
+                            .collect();
+                        ok_reply(json!({
+                            "stats": body,
+                            "screen": lcd.screen(),
+                            "queue": queue,
+                            "link": {
+                                "device": self.config.device,
+                                "baud": self.config.baud,
+                                "serial_connected": serial_connected,
+                                "reconnect_in_ms": self.backoff.until_retry(now).as_millis() as u64,
+                                "tunnel_up": !self.tunnel_watchdog_active,
+                                "relay_connected": self.relay.as_ref().map(Relay::is_connected),
+                            },
+                            "log": crash::recent_log(),
+                        }))
+                    }
+                }
+                ControlCommand::Handshakes => {
+                    ok_reply(json!({ "handshakes": handshakes.to_json() }))
+                }
+                ControlCommand::Hud { on } => {
+                    self.hud.set_enabled(*on, now);
+                    self.logger
+                        .info(format!("control: hud {}", if *on { "on" } else { "off" }));
+                    ok_reply(json!({}))
+                }
+            };
+            request.respond(reply);
+        }
+        Ok(())
+    }
+
+    /// Route one line from the serial peer: link control, tunnel and command frames go to their
+    /// handlers, and row updates and payload frames to the display.
+    fn route_serial_line<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        serial: &mut SerialPort,
+        line: &str,
+        released: bool,
+        now: Instant,
+    ) -> Result<LineFollowUp> {
+        let logger = self.logger;
+        let trace = self.tracer.rx(line, now, logger);
+        if looks_like_arm_frame(line) {
+            self.tracer
+                .note(trace, "route", format_args!("arm message"), now, logger);
+            self.watchdog.touch_serial();
+            match self.interlock.on_signed(line, SystemTime::now()) {
+                Ok(until) => logger.info(format!(
+                    "interlock: armed until {} by signed message",
+                    format_expiry(until)
+                )),
+                Err(err) => logger.warn(format!("interlock: arm message rejected: {err}")),
+            }
+            return Ok(LineFollowUp::NextPass);
+        }
+        if looks_like_burst_frame(line) {
+            self.tracer.note(
+                trace,
+                "route",
+                format_args!("burst control frame"),
+                now,
+                logger,
+            );
+            match serde_json::from_str::<ControlFrame>(line) {
+                Ok(frame) => {
+                    self.watchdog.touch_serial();
+                    let steps = self.burst.on_frame(&frame, now);
+                    apply_burst_steps(serial, steps, logger);
+                }
+                Err(err) => {
+                    logger.warn(format!("burst frame error: {err}"));
+                    let steps = self.burst.on_error(now);
+                    apply_burst_steps(serial, steps, logger);
+                }
+            }
+            return Ok(LineFollowUp::NextPass);
+        }
+        if looks_like_goodbye_frame(line) {
+            // The peer is closing on purpose; its heartbeats stop next and the watchdog handles
+            // the reconnect as usual.
+            self.watchdog.touch_serial();
+            match serde_json::from_str::<ControlFrame>(line) {
+                Ok(ControlFrame::Goodbye { reason }) => {
+                    logger.info(format!("peer said goodbye ({reason})"));
+                }
+                _ => logger.warn("goodbye frame error"),
+            }
+            self.tracer.note(
+                trace,
+                "route",
+                format_args!("goodbye from peer"),
+                now,
+                logger,
+            );
+            return Ok(LineFollowUp::NextPass);
+        }
+        if looks_like_render_defaults_frame(line) {
+            self.watchdog.touch_serial();
+            self.tracer.note(
+                trace,
+                "route",
+                format_args!("render defaults from peer"),
+                now,
+                logger,
+            );
+            match serde_json::from_str::<ControlFrame>(line) {
+                Ok(ControlFrame::RenderDefaults(push)) => {
+                    let outcome = self
+                        .peer_defaults
+                        .accept(&push, self.config.scheduler.remote_weight);
+                    for (key, reason) in &outcome.refused {
+                        logger.warn(format!("peer defaults: ignored {key} ({reason})"));
+                    }
+                    if !outcome.applied.is_empty() {
+                        self.peer_defaults.apply(self.config);
+                        adopt_display_defaults(
+                            self.config,
+                            &mut self.state,
+                            &mut self.scheduler,
+                            &mut self.blink_interval,
+                        );
+                        logger.info(format!(
+                            "peer defaults: applied {}",
+                            outcome.applied.join(", ")
+                        ));
+                    }
+                }
+                _ => logger.warn("render_defaults frame error"),
+            }
+            return Ok(LineFollowUp::NextPass);
+        }
+        if looks_like_anomaly_frame(line) {
+            self.watchdog.touch_serial();
+            self.tracer.note(
+                trace,
+                "route",
+                format_args!("anomaly summary from peer"),
+                now,
+                logger,
+            );
+            match serde_json::from_str::<ControlFrame>(line) {
+                Ok(ControlFrame::AnomalySummary(summary)) => {
+                    logger.warn(format!(
+                        "peer anomaly: {}: {}",
+                        summary.kind, summary.summary
+                    ));
+                    if let Ok(Some(frame)) = self.state.ingest(&peer_page(&summary)) {
+                        self.stats.frames_accepted += 1;
+                        self.badges.on_frame(&frame);
+                        self.backlight_schedule.on_frame(now);
+                        if self.current_frame.is_none() {
+                            self.next_page = now;
+                        }
+                    }
+                }
+                _ => logger.warn("anomaly_summary frame error"),
+            }
+            return Ok(LineFollowUp::NextPass);
+        }
+        if looks_like_frame_ack(line) {
+            // Acks answer frames a sender wrote; the daemon sends none of its own, so a peer's
+            // ack is only traced.
+            self.tracer.note(
+                trace,
+                "route",
+                format_args!("frame ack ignored"),
+                now,
+                logger,
+            );
+            self.watchdog.touch_serial();
+            return Ok(LineFollowUp::NextPass);
+        }
+        if looks_like_tunnel_frame(line) {
+            self.tracer
+                .note(trace, "route", format_args!("tunnel frame"), now, logger);
+            self.serve_tunnel_frame(lcd, serial, line, now)?;
+            return Ok(LineFollowUp::NextPass);
+        }
+        if looks_like_command_frame(line) {
+            self.tracer
+                .note(trace, "route", format_args!("command frame"), now, logger);
+            self.serve_command_frame(serial, line);
+            return Ok(LineFollowUp::NextPass);
+        }
+        if looks_like_row_frame(line) {
+            self.apply_row_update(lcd, line, trace, now)?;
+            return Ok(LineFollowUp::NextPass);
+        }
+        if !looks_like_payload_frame(line) {
+            // Ignore obvious garbage/diagnostic chatter (e.g., "INIT", noise bytes that survived
+            // UTF-8 decoding, etc.) so we don't spam the LCD with parse errors.
+            self.tracer.note(
+                trace,
+                "parse",
+                format_args!("ignored, not a payload frame"),
+                now,
+                logger,
+            );
+            logger.debug(format!(
+                "ignoring non-payload serial frame len={} preview={}",
+                line.len(),
+                preview_frame(line, 80)
+            ));
+            // During a burst, noise usually means the faster rate is failing.
+            let steps = self.burst.on_error(now);
+            apply_burst_steps(serial, steps, logger);
+            return Ok(LineFollowUp::NextPass);
+        }
+        if !released
+            && held_by_rate_limit(
+                &mut self.frame_limiter,
+                line,
+                &mut self.stats,
+                &self.tracer,
+                trace,
+                now,
+                logger,
+            )
+        {
+            self.watchdog.touch_serial();
+            return Ok(LineFollowUp::NextPass);
+        }
+        match self.ingest_payload(lcd, serial, line, trace, now)? {
+            Ingested::Done => Ok(LineFollowUp::Render),
+            Ingested::Rejected { err, crc } => {
+                self.reject_payload(lcd, serial, line, trace, &err, crc, now)?;
+                Ok(LineFollowUp::NextPass)
+            }
+        }
+    }
+
+    /// Hand a tunnel frame to [`handle_tunnel_frame`] and carry out what it asks for next.
+    fn serve_tunnel_frame<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        serial: &mut SerialPort,
+        line: &str,
+        now: Instant,
+    ) -> Result<()> {
+        match handle_tunnel_frame(
+            line,
+            serial,
+            &mut self.tunnel,
+            &mut self.replay,
+            &mut self.watchdog,
+            &mut self.stats,
+            &self.interlock,
+            lcd,
+            &mut self.icon_bank,
+            self.logger,
+        ) {
+            TunnelFollowUp::Redraw
+                if self.scheduler.current() != Some(PageSource::Local)
+                    && !self.parse_errors.showing() =>
+            {
+                self.draw_frame(lcd, now)?;
+                if self.current_frame.is_some() {
+                    self.last_render = Instant::now();
+                }
+            }
+            // Without a watcher, a peer's `config_set` reloads here.
+            TunnelFollowUp::ConfigSaved if self.config_watch.is_none() => {
+                self.reload_requested = true;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Pass a command frame from the peer to the executor, replying over `serial`.
+    fn serve_command_frame(&mut self, serial: &mut SerialPort, line: &str) {
+        let logger = self.logger;
+        match self.command_bridge.ingest_line(line) {
+            Ok(Some(event)) => {
+                if let Err(reason) = self
+                    .replay
+                    .admit(ReplayChannel::Command, self.command_bridge.last_seq())
+                {
+                    log_replay_drop(logger, ReplayChannel::Command, &reason, &self.replay);
+                    return;
+                }
+                let label = if let Some(id) = self.command_bridge.last_request_id() {
+                    format!("cmd#{id} {}", event.kind())
+                } else {
+                    event.kind().to_string()
+                };
+                logger.debug(format!(
+                    "command frame buffered ({label}), awaiting executor"
+                ));
+                self.watchdog.touch_serial();
+                self.watchdog.touch_tunnel();
+                if matches!(event, CommandEvent::Heartbeat { .. }) {
+                    return;
+                }
+                self.command_executor
+                    .set_locked(!self.interlock.permits(SystemTime::now()));
+                if let Some(response) = self.command_executor.handle_event(event) {
+                    send_command_frame(serial, response, &mut self.replay, logger);
+                    flush_command_messages(
+                        serial,
+                        &mut self.command_executor,
+                        &mut self.replay,
+                        logger,
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                logger.warn(format!("command frame error: {err}"));
+            }
+        }
+    }
+
+    /// Apply a single-row update to the frame on screen, writing only that row when it can.
+    fn apply_row_update<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        line: &str,
+        trace: Option<TraceId>,
+        now: Instant,
+    ) -> Result<()> {
+        let logger = self.logger;
+        let update = RowUpdate::from_json(line).and_then(|update| {
+            if update.row < OneLineFold::frame_rows(lcd.rows()) {
+                Ok(update)
+            } else {
+                Err(FrameErrorCode::BadRow.error(format_args!(
+                    "row {} is off a {}-row display",
+                    update.row,
+                    lcd.rows()
+                )))
+            }
+        });
+        let update = match update {
+            Ok(update) => update,
+            Err(err) => {
+                self.stats.frames_rejected += 1;
+                let mut hasher = Hasher::new();
+                hasher.update(line.as_bytes());
+                self.protocol_errors
+                    .log(&err, line, hasher.finalize(), logger);
+                self.slo.record(SloKind::ParseFailure, now);
+                self.tracer.note(
+                    trace,
+                    "parse",
+                    format_args!("row update rejected: {err}"),
+                    now,
+                    logger,
+                );
+                logger.warn(format!("row update error: {err}"));
+                return Ok(());
+            }
+        };
+        self.watchdog.touch_serial();
+        self.last_frame_at = now;
+        self.backlight_schedule.on_frame(now);
+        let dropped_before = self.state.filter().dropped();
+        let Some(frame) = self.state.apply_row(self.current_frame.as_ref(), &update) else {
+            if self.state.filter().dropped() > dropped_before {
+                self.stats.frames_filtered += 1;
+                self.tracer.note(
+                    trace,
+                    "ingest",
+                    format_args!("row {} dropped by filter", update.row),
+                    now,
+                    logger,
+                );
+                logger.debug(format!("row {} update dropped by [filters]", update.row));
+                return Ok(());
+            }
+            self.stats.duplicates += 1;
+            self.tracer.note(
+                trace,
+                "ingest",
+                format_args!("row {} unchanged", update.row),
+                now,
+                logger,
+            );
+            logger.debug(format!("row {} unchanged; update ignored", update.row));
+            return Ok(());
+        };
+        self.tracer
+            .accepted(trace, &frame, self.state.len(), now, logger);
+        self.stats.frames_accepted += 1;
+        if let Some(relay) = self.relay.as_mut() {
+            relay.forward(line, &frame, now, logger);
+        }
+        self.parse_errors.on_frame();
+        // A row write alone would leave the rest of the error page on screen, so redraw the
+        // whole frame over it.
+        let full_redraw = self.current_frame.is_none() || self.parse_errors.showing();
+        self.current_frame = Some(frame);
+        self.scroll_offsets.reset_row(update.row);
+        let local_page_active =
+            self.scheduler.current() == Some(PageSource::Local) || self.incident_on_screen;
+        if local_page_active {
+            return Ok(());
+        }
+        let Some(frame) = self.current_frame.as_ref() else {
+            return Ok(());
+        };
+        self.tracer.scheduled(frame, "row update", now, logger);
+        // Row updates skip the render throttle: they are already a minimal write, and a
+        // counter that lags behind its host defeats the point.
+        self.last_render = now;
+        let palette = if full_redraw {
+            self.parse_errors.dismiss();
+            lcd.prepare_redraw()?;
+            lcd.set_blink(frame.blink)?;
+            render_frame_with_scroll(
+                lcd,
+                frame,
+                &self.scroll_offsets.rows,
+                self.heartbeat_on,
+                self.badges
+                    .marks(self.modem_glyph)
+                    .folded(self.one_line.row()),
+                self.countdown.level_for(frame, &self.state, now),
+                &mut self.icon_bank,
+            )?
+        } else {
+            render_row_update(
+                lcd,
+                frame,
+                update.row,
+                &self.scroll_offsets.rows,
+                self.heartbeat_on,
+                self.badges
+                    .marks(self.modem_glyph)
+                    .folded(self.one_line.row()),
+                self.countdown.level_for(frame, &self.state, now),
+                &mut self.icon_bank,
+            )?
+        };
+        log_icon_fallbacks(logger, Some(palette));
+        Ok(())
+    }
+
+    /// Parse a payload frame and show, queue or hold it, acknowledging it over `port` when
+    /// acks are on. A frame the parser refuses is handed back for [`Self::reject_payload`].
+    fn ingest_payload<D: DisplayBackend, L: LineIo>(
+        &mut self,
+        lcd: &mut D,
+        port: &mut L,
+        line: &str,
+        trace: Option<TraceId>,
+        now: Instant,
+    ) -> Result<Ingested> {
+        let logger = self.logger;
+        let crc = frame_crc32(line);
+        if logger.level() >= LogLevel::Debug {
+            logger.debug(format!("frame crc={crc:08x} len={}", line.len()));
+        }
+        let dropped_before = self.state.filter().dropped();
+        let ingested = self.state.ingest(line);
+        let filtered = self.state.filter().dropped() > dropped_before;
+        for field in self.state.take_ignored_fields() {
+            logger.warn(format!(
+                "protocol.strict=false: ignoring unknown payload field '{field}' (logged once)"
+            ));
+        }
+        if let (Some(relay), Ok(Some(frame))) = (self.relay.as_mut(), &ingested) {
+            if !frame.config_reload {
+                relay.forward(line, frame, now, logger);
+            }
+        }
+        let cache = self.state.cache_stats();
+        let replay_suppressed = cache.hits > self.stats.frame_cache_hits;
+        self.stats.frame_cache_hits = cache.hits;
+        self.stats.frame_cache_misses = cache.misses;
+        let frame = match ingested {
+            Ok(Some(frame)) => frame,
+            Ok(None) => {
+                if filtered {
+                    self.stats.frames_filtered += 1;
+                } else {
+                    self.stats.duplicates += 1;
+                }
+                self.watchdog.touch_serial();
+                // Ack repeats and filtered frames too: a retry after a lost ack lands here, and
+                // the sender should not resend either.
+                if self.config.ack_enabled {
+                    send_frame_ack(port, crc, &mut self.frame_ack_seq, logger);
+                }
+                self.tracer.note(
+                    trace,
+                    "ingest",
+                    format_args!(
+                        "{}",
+                        if filtered {
+                            "dropped by filter"
+                        } else if replay_suppressed {
+                            "replay suppressed"
+                        } else {
+                            "duplicate ignored"
+                        }
+                    ),
+                    now,
+                    logger,
+                );
+                if filtered {
+                    logger.debug(format!("frame dropped by [filters] crc={crc:08x}"));
+                } else if replay_suppressed {
+                    logger.debug(format!(
+                        "replayed frame suppressed after reconnect crc={crc:08x}"
+                    ));
+                } else {
+                    logger.debug(format!("duplicate frame ignored crc={crc:08x}"));
+                }
+                return Ok(Ingested::Done);
+            }
+            Err(err) => return Ok(Ingested::Rejected { err, crc }),
+        };
+
+        if frame.config_reload {
+            self.tracer
+                .note(trace, "ingest", format_args!("config reload"), now, logger);
+            self.stats.frames_accepted += 1;
+            self.watchdog.touch_serial();
+            logger.info("config reload requested");
+            self.reload_requested = true;
+            return Ok(Ingested::Done);
+        }
+
+        let mut held = false;
+        if frame.target.is_some() {
+            // Zones are drawn every pass; the main rotation carries on untouched.
+            self.tracer.note(
+                trace,
+                "schedule",
+                format_args!(
+                    "queued in zone '{}'",
+                    frame.target.as_deref().unwrap_or_default()
+                ),
+                now,
+                logger,
+            );
+        } else if frame.priority.interrupts() || self.state.interrupting() {
+            // The interrupt step draws priority frames; a normal frame landing meanwhile waits
+            // for the rotation to resume.
+            self.tracer
+                .accepted(trace, &frame, self.state.len(), now, logger);
+            self.tracer.note(
+                trace,
+                "schedule",
+                format_args!(
+                    "{}",
+                    if frame.priority.interrupts() {
+                        "queued as an interrupt"
+                    } else {
+                        "held until the interrupt ends"
+                    }
+                ),
+                now,
+                logger,
+            );
+        } else if self.scheduler.current() == Some(PageSource::Local)
+            && !self.scheduler.can_preempt(now)
+        {
+            // A local page owns its minimum slot; show this frame on the next remote slot
+            // instead of cutting the local page short.
+            self.tracer
+                .accepted(trace, &frame, self.state.len(), now, logger);
+            self.tracer.note(
+                trace,
+                "schedule",
+                format_args!("held until the local page slot ends"),
+                now,
+                logger,
+            );
+            held = true;
+        } else {
+            self.tracer
+                .accepted(trace, &frame, self.state.len(), now, logger);
+            self.tracer
+                .scheduled(&frame, "shown on arrival", now, logger);
+            self.show_arrival(lcd, frame, now)?;
+            if self.config.ack_enabled {
+                send_frame_ack(port, crc, &mut self.frame_ack_seq, logger);
+            }
+            return Ok(Ingested::Done);
+        }
+        self.stats.frames_accepted += 1;
+        self.badges.on_frame(&frame);
+        self.parse_errors.on_frame();
+        self.last_frame_at = now;
+        self.backlight_schedule.on_frame(now);
+        self.watchdog.touch_serial();
+        self.heartbeat_visible = false;
+        if held {
+            self.pending_remote = Some(frame);
+        }
+        if self.config.ack_enabled {
+            send_frame_ack(port, crc, &mut self.frame_ack_seq, logger);
+        }
+        Ok(Ingested::Done)
+    }
+
+    /// Put a newly accepted frame on screen straight away.
+    fn show_arrival<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        frame: RenderFrame,
+        now: Instant,
+    ) -> Result<()> {
+        self.stats.frames_accepted += 1;
+        self.badges.on_frame(&frame);
+        self.parse_errors.on_frame();
+        self.parse_errors.dismiss();
+        self.pending_remote = None;
+        self.incident_on_screen = false;
+        self.scroll_offsets = ScrollOffsets::zero();
+        self.one_line.reset(now);
+        self.next_scroll = now + Duration::from_millis(self.config.scroll_speed_ms);
+        lcd.prepare_redraw()?;
+        self.backlight_state = frame.backlight_on;
+        lcd.set_backlight(self.backlight_state)?;
+        lcd.set_blink(frame.blink)?;
+        self.next_blink = now + self.blink_interval;
+        self.last_frame_at = now;
+        self.backlight_schedule.on_frame(now);
+        self.watchdog.touch_serial();
+        self.heartbeat_visible = false;
+        // Keep the slot boundary while a local page is waiting so a chatty host cannot push it
+        // back indefinitely.
+        let local_waiting = self.scheduler.schedules_local()
+            && self.polling.as_ref().is_some_and(|p| p.latest.is_some());
+        if self.scheduler.current() != Some(PageSource::Remote) || !local_waiting {
+            self.next_page = now + Duration::from_millis(frame.page_timeout_ms);
+        }
+        if self.scheduler.current() != Some(PageSource::Remote) {
+            self.scheduler.begin_slot(PageSource::Remote, now);
+        }
+        self.current_frame = Some(frame);
+        self.draw_frame_throttled(lcd, now)
+    }
+
+    /// Count, log and nack a payload frame the parser refused, and put up the error page when
+    /// [`ParseErrorGate`] allows it.
+    #[allow(clippy::too_many_arguments)] // The frame and how it failed.
+    fn reject_payload<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        serial: &mut SerialPort,
+        line: &str,
+        trace: Option<TraceId>,
+        err: &Error,
+        crc: u32,
+        now: Instant,
+    ) -> Result<()> {
+        let logger = self.logger;
+        self.tracer
+            .note(trace, "parse", format_args!("rejected: {err}"), now, logger);
+        self.stats.frames_rejected += 1;
+        if matches!(err, Error::ChecksumMismatch) {
+            self.stats.checksum_failures += 1;
+            self.slo.record(SloKind::CrcError, now);
+            if serial.note_crc_failure(now) {
+                logger.warn("arq: sustained CRC failures; switching to stop-and-wait framing");
+            }
+        }
+        if matches!(err, Error::Parse(_)) {
+            self.protocol_errors.log(err, line, crc, logger);
+            self.slo.record(SloKind::ParseFailure, now);
+        }
+        if self.config.ack_enabled {
+            send_frame_nack(serial, crc, err, &mut self.frame_ack_seq, logger);
+        }
+        let steps = self.burst.on_error(now);
+        apply_burst_steps(serial, steps, logger);
+        logger.warn(format!("frame error: {err}"));
+        if self.parse_errors.on_error(now) {
+            render_parse_error(lcd, self.config.cols, err)?;
+            self.backlight_state = true;
+            self.next_blink = now + self.blink_interval;
+        }
+        Ok(())
+    }
+
+    /// `config_reload` frames and saves to config.toml both reload here. A file that no longer
+    /// parses is logged and the running settings stay in place.
+    fn reload_config<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut BacklightGate<'_, ZoneMask<'_, D>>,
+        serial_connection: &mut Option<SerialPort>,
+        session: &mut SessionCache,
+        now: Instant,
+    ) -> Result<()> {
+        if self
+            .config_watch
+            .as_mut()
+            .is_some_and(|watcher| watcher.poll(now))
+        {
+            self.logger.info("config file changed on disk; reloading");
+            self.reload_requested = true;
+        }
+        if !std::mem::take(&mut self.reload_requested) {
+            return Ok(());
+        }
+        match Config::load_or_default() {
+            Ok(new_cfg) => self.apply_config(lcd, new_cfg, serial_connection, session, now),
+            Err(err) => {
+                self.logger.warn(format!("config reload failed: {err}"));
+                Ok(())
+            }
+        }
+    }
+
+    /// Move the running loop onto `new_cfg`, rebuilding only what changed.
+    fn apply_config<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut BacklightGate<'_, ZoneMask<'_, D>>,
+        new_cfg: Config,
+        serial_connection: &mut Option<SerialPort>,
+        session: &mut SessionCache,
+        now: Instant,
+    ) -> Result<()> {
+        let logger = self.logger;
+        let config = &mut *self.config;
+        let old_device = config.device.clone();
+        let old_serial = config.serial_options();
+        let old_psk = config.negotiation.psk.clone();
+        let old_scroll = config.scroll_speed_ms;
+        let old_page = config.page_timeout_ms;
+
+        config.scroll_speed_ms = new_cfg.scroll_speed_ms;
+        config.page_timeout_ms = new_cfg.page_timeout_ms;
+        config.frame_cache_size = new_cfg.frame_cache_size;
+        self.state
+            .set_frame_cache_size(config.frame_cache_size as usize);
+        config.backoff_initial_ms = new_cfg.backoff_initial_ms;
+        config.backoff_max_ms = new_cfg.backoff_max_ms;
+        config.negotiation.psk = new_cfg.negotiation.psk.clone();
+        // Roles are settled by the handshake; a new preference waits for the next one.
+        config.negotiation.preference = new_cfg.negotiation.preference;
+        // FIFO and TCP inputs come from the command line; the file's device does not apply.
+        if crate::serial::fifo::fifo_path(&config.device).is_none()
+            && crate::serial::tcp::tcp_addr(&config.device).is_none()
+        {
+            config.device = new_cfg.device;
+        }
+        config.baud = new_cfg.baud;
+        config.flow_control = new_cfg.flow_control;
+        config.parity = new_cfg.parity;
+        config.stop_bits = new_cfg.stop_bits;
+        config.dtr_on_open = new_cfg.dtr_on_open;
+        config.serial_timeout_ms = new_cfg.serial_timeout_ms;
+        config.compression_enabled = new_cfg.protocol.compression_enabled;
+        config.compression_codec = new_cfg.protocol.compression_codec;
+        config.ack_enabled = new_cfg.protocol.ack_enabled;
+        config.protocol_strict = new_cfg.protocol.strict;
+        self.state.set_strict(config.protocol_strict);
+        if config.filters != new_cfg.filters {
+            match new_cfg.filters.build() {
+                Ok(filter) => self.state.set_filter(filter),
+                Err(err) => logger.warn(format!("filters: keeping the previous rules: {err}")),
+            }
+            config.filters = new_cfg.filters.clone();
+        }
+        if config.accessibility != new_cfg.accessibility {
+            config.accessibility = new_cfg.accessibility;
+            self.state.set_accessibility(config.accessibility.profile());
+        }
+        config.watchdog = new_cfg.watchdog;
+        if config.scheduler != new_cfg.scheduler {
+            self.scheduler.update(
+                new_cfg.scheduler.remote_weight,
+                new_cfg.scheduler.local_weight,
+                new_cfg.scheduler.min_display_ms,
+            );
+            config.scheduler = new_cfg.scheduler;
+        }
+        if config.anomaly != new_cfg.anomaly {
+            self.anomalies = AnomalyDetector::new(&new_cfg.anomaly);
+            config.anomaly = new_cfg.anomaly;
+        }
+        if config.slo != new_cfg.slo {
+            if config.slo.webhook_url != new_cfg.slo.webhook_url {
+                self.slo_events = SloEventLog::new(&new_cfg.slo);
+            }
+            self.slo = SloTracker::new(&new_cfg.slo);
+            config.slo = new_cfg.slo.clone();
+        }
+        if config.annunciators != new_cfg.annunciators {
+            // Release the old pins before claiming them again.
+            if let Some(mut old) = self.annunciators.take() {
+                old.release();
+            }
+            self.annunciators = open_annunciators(&new_cfg.annunciators, logger);
+            config.annunciators = new_cfg.annunciators;
+        }
+        if config.parse_errors != new_cfg.parse_errors {
+            self.parse_errors = ParseErrorGate::new(&new_cfg.parse_errors);
+            config.parse_errors = new_cfg.parse_errors;
+        }
+        if config.render != new_cfg.render {
+            self.throttle = RenderThrottle::new(&new_cfg.render, now);
+            self.blink_interval = Duration::from_millis(new_cfg.render.blink_ms);
+            self.countdown.set_enabled(new_cfg.render.expiry_countdown);
+            self.icon_bank.set_bar_style(new_cfg.render.bar_style);
+            self.frame_limiter
+                .set_rate(new_cfg.render.max_frames_per_sec, now);
+            self.write_budget
+                .set_budget(new_cfg.render.i2c_write_budget_bytes);
+            if config.render.persist_pages != new_cfg.render.persist_pages {
+                self.page_store = PageStore::from_config(&new_cfg.render, now);
+            }
+            config.render = new_cfg.render;
+        }
+        if config.relay != new_cfg.relay {
+            config.relay = new_cfg.relay;
+            self.relay = Relay::reopen(config, self.backlight_minute, logger);
+        }
+        if config.displays != new_cfg.displays {
+            self.extra_displays.shutdown();
+            config.displays = new_cfg.displays;
+            self.extra_displays = ExtraDisplays::open(config, logger);
+        }
+        if (config.cols, config.rows) != (new_cfg.cols, new_cfg.rows) {
+            match lcd.reconfigure(new_cfg.cols, new_cfg.rows) {
+                Ok(()) => {
+                    logger.info(format!(
+                        "display geometry {}x{} -> {}x{}",
+                        config.cols, config.rows, new_cfg.cols, new_cfg.rows
+                    ));
+                    config.cols = new_cfg.cols;
+                    config.rows = new_cfg.rows;
+                    // CGRAM was reloaded with the bar glyphs.
+                    self.icon_bank = IconBank::new();
+                    self.icon_bank.set_bar_style(config.render.bar_style);
+                    self.state.clamp_to(config.cols, config.rows);
+                    for frame in self
+                        .current_frame
+                        .iter_mut()
+                        .chain(self.pending_remote.iter_mut())
+                    {
+                        frame.clamp_to(config.cols, config.rows);
+                    }
+                    self.scroll_offsets = ScrollOffsets::zero();
+                    self.one_line.reset(now);
+                    // Redraw whatever owns the display now.
+                    self.scheduler.end_slot();
+                    self.next_page = now;
+                }
+                Err(err) => logger.warn(format!(
+                    "display geometry {}x{} -> {}x{} failed, keeping {}x{}: {err}",
+                    config.cols, config.rows, new_cfg.cols, new_cfg.rows, config.cols, config.rows
+                )),
+            }
+        }
+        if config.zones != new_cfg.zones {
+            set_zones(lcd.inner_mut(), &mut self.state, &new_cfg.zones);
+            config.zones = new_cfg.zones.clone();
+            // Rows a zone gave up go back to whatever owns the display.
+            self.scheduler.end_slot();
+            self.next_page = now;
+        }
+        if config.log_compaction != new_cfg.log_compaction {
+            self.log_compactor
+                .set_settings(new_cfg.log_compaction.clone());
+            config.log_compaction = new_cfg.log_compaction.clone();
+        }
+        if config.badges != new_cfg.badges {
+            self.badges.reconfigure(&new_cfg.badges);
+            config.badges = new_cfg.badges.clone();
+        }
+        if config.interlock != new_cfg.interlock {
+            self.interlock.reconfigure(&new_cfg.interlock);
+            config.interlock = new_cfg.interlock.clone();
+        }
+        if config.tunnel_auth != new_cfg.tunnel_auth {
+            // A new secret or backend starts with no spent steps.
+            let command_auth = CommandAuth::from_config(&new_cfg.tunnel_auth);
+            self.tunnel.set_auth(command_auth.clone());
+            self.command_executor.set_auth(command_auth);
+            config.tunnel_auth = new_cfg.tunnel_auth.clone();
+        }
+        if config.backlight != new_cfg.backlight {
+            self.backlight_schedule.reconfigure(&new_cfg.backlight);
+            if config.backlight.pwm_channel != new_cfg.backlight.pwm_channel {
+                // Release the old channel before claiming the new one.
+                lcd.set_pwm(None)?;
+                lcd.set_pwm(open_backlight_pwm(new_cfg.backlight.pwm_channel, logger))?;
+            }
+            config.backlight = new_cfg.backlight.clone();
+        }
+        if config.input != new_cfg.input {
+            // Release the old pins before claiming the new ones.
+            drop(self.encoder.take());
+            self.encoder = open_encoder(&new_cfg.input, self.idle.waker(), logger);
+            config.input = new_cfg.input.clone();
+        }
+        if config.poll_overlay != new_cfg.poll_overlay {
+            if let Some(polling_state) = self.polling.as_mut() {
+                polling_state.layout = PollLayout::from_config(&new_cfg.poll_overlay);
+            }
+            config.poll_overlay = new_cfg.poll_overlay.clone();
+        }
+        if config.control != new_cfg.control {
+            // Release the old socket before binding, in
+            // case the path did not change.
+            drop(self.control.take());
+            self.control = open_control(
+                &new_cfg.control,
+                self.running.clone(),
+                self.idle.waker(),
+                logger,
+            );
+            config.control = new_cfg.control.clone();
+        }
+        if config.metrics != new_cfg.metrics {
+            // Stop the old listener first so an unchanged address can be rebound.
+            self.metrics.take();
+            self.metrics = MetricsExporter::start(&new_cfg.metrics, self.running.clone(), logger);
+            config.metrics = new_cfg.metrics.clone();
+        }
+        if config.spool != new_cfg.spool {
+            self.spool = Spool::from_config(&new_cfg.spool, now);
+            config.spool = new_cfg.spool.clone();
+        }
+        if config.burst != new_cfg.burst || old_serial.baud != config.baud {
+            let steps = self.burst.end(now, "burst settings changed");
+            if let Some(serial) = serial_connection.as_mut() {
+                apply_burst_steps(serial, steps, logger);
+            }
+            self.burst = BurstController::new(config.baud, &new_cfg.burst, now);
+            self.burst
+                .set_initiate(self.features.peer_supports(Feature::Burst));
+            config.burst = new_cfg.burst;
+        }
+        if config.replay != new_cfg.replay {
+            self.replay.reconfigure(&new_cfg.replay);
+            config.replay = new_cfg.replay;
+        }
+        if config.modem != new_cfg.modem {
+            self.modem_monitor = ModemMonitor::new(new_cfg.modem.poll_ms);
+            config.modem = new_cfg.modem;
+        }
+
+        self.state
+            .set_compression_policy(compression_policy_from_config(config));
+
+        self.watchdog = WatchdogMonitor::new(
+            config.watchdog.serial_timeout_ms,
+            config.watchdog.tunnel_timeout_ms,
+        );
+        self.serial_heartbeat_interval = serial_keepalive(config, self.keepalive_ms);
+        self.tunnel_heartbeat_interval = tunnel_keepalive(config);
+        self.next_serial_heartbeat = Instant::now() + self.serial_heartbeat_interval;
+        self.next_tunnel_heartbeat = Instant::now() + self.tunnel_heartbeat_interval;
+
+        let new_serial = config.serial_options();
+
+        if old_psk != config.negotiation.psk {
+            // Link keys come from the handshake; a new PSK needs a new one.
+            logger.info("config reload changed negotiation.psk; renegotiating");
+            *serial_connection = None;
+            *session = SessionCache::new(config.negotiation.resume_grace_ms);
+            self.reconnect_displayed = false;
+            self.offline_displayed = false;
+        } else if old_device != config.device || old_serial != new_serial {
+            logger.info(format!(
+                "config reload updating serial to {} @ {} (flow={}, parity={}, stop_bits={}, dtr={}, timeout={}ms)",
+                config.device,
+                config.baud,
+                config.flow_control,
+                config.parity,
+                config.stop_bits,
+                config.dtr_on_open,
+                config.serial_timeout_ms
+            ));
+            *serial_connection = None;
+            *session = SessionCache::new(config.negotiation.resume_grace_ms);
+            self.reconnect_displayed = false;
+            self.offline_displayed = false;
+        }
+        if old_scroll != new_cfg.scroll_speed_ms || old_page != new_cfg.page_timeout_ms {
+            logger.debug(format!(
+                "updated defaults: scroll={}ms page_timeout={}ms",
+                config.scroll_speed_ms, config.page_timeout_ms
+            ));
+        }
+        self.backoff
+            .update(config.backoff_initial_ms, config.backoff_max_ms);
+        // Pushed defaults outrank the file until the daemon restarts.
+        self.peer_defaults
+            .reconfigure(&new_cfg.peer_defaults, config.scheduler.remote_weight);
+        config.peer_defaults = new_cfg.peer_defaults.clone();
+        self.peer_defaults.apply(config);
+        adopt_display_defaults(
+            config,
+            &mut self.state,
+            &mut self.scheduler,
+            &mut self.blink_interval,
+        );
+        logger.info("config reload applied");
+        Ok(())
+    }
+
+    /// Put back whatever the error page covered once its display time runs out.
+    fn restore_after_parse_error<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        serial_connected: bool,
+        now: Instant,
+    ) -> Result<()> {
+        if !self.parse_errors.restore_due(now) {
+            return Ok(());
+        }
+        if self.incident_on_screen {
+            self.incident_on_screen = false;
+            self.next_page = now;
+        } else if self.scheduler.current() == Some(PageSource::Local) {
+            if let Some(polling_state) = self.polling.as_ref() {
+                render_polling_overlay(lcd, self.config.cols, polling_state, serial_connected)?;
+            }
+        } else if let Some(frame) = self.current_frame.as_ref() {
+            lcd.prepare_redraw()?;
+            self.backlight_state = frame.backlight_on;
+            lcd.set_backlight(self.backlight_state)?;
+            lcd.set_blink(frame.blink)?;
+            self.next_blink = now + self.blink_interval;
+            self.draw_frame(lcd, now)?;
+        } else {
+            lcd.clear()?;
+            lcd.set_blink(false)?;
+        }
+        Ok(())
+    }
+
+    /// Evaluate watchdog states after handling inbound/outbound traffic.
+    fn check_watchdog<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        serial_connection: &mut Option<SerialPort>,
+        session: &mut SessionCache,
+        now: Instant,
+    ) -> Result<()> {
+        let logger = self.logger;
+        let wd_status = self.watchdog.evaluate(logger);
+        if wd_status.serial_recovered {
+            self.serial_watchdog_active = false;
+            logger.info("watchdog: serial channel recovered");
+        }
+        if wd_status.tunnel_recovered {
+            self.tunnel_watchdog_active = false;
+            logger.info("watchdog: tunnel channel recovered");
+        }
+        if wd_status.serial_expired && !self.serial_watchdog_active {
+            self.serial_watchdog_active = true;
+            logger.warn("watchdog: serial channel expired; forcing reconnect");
+            if serial_connection.is_some() {
+                *serial_connection = None;
+                session.mark_lost(now);
+                self.backoff.mark_failure(now);
+                self.reconnect_displayed = false;
+                self.offline_displayed = false;
+                self.last_disconnect_reason = None;
+            }
+            if !self.offline_displayed {
+                render_offline_message(lcd, self.config.cols)?;
+                self.offline_displayed = true;
+            }
+        }
+        if wd_status.tunnel_expired && !self.tunnel_watchdog_active {
+            self.tunnel_watchdog_active = true;
+            logger.warn("watchdog: tunnel channel expired");
+        }
+        Ok(())
+    }
+
+    /// A push or pull over the tunnel holds the LCD, with its progress on the bar row.
+    fn show_transfer<D: DisplayBackend>(&mut self, lcd: &mut D, now: Instant) -> Result<()> {
+        if self.transfer_page.render(
+            lcd,
+            self.tunnel.transfer_progress(),
+            self.badges
+                .marks(self.modem_glyph)
+                .folded(self.one_line.row()),
+            &mut self.icon_bank,
+            &mut self.scheduler,
+            now,
+        )? {
+            self.next_page = now;
+        }
+        Ok(())
+    }
+
+    /// Priority frames cut into whatever is showing and hand the rotation back once they have
+    /// all run. A transfer in progress keeps the LCD until it finishes.
+    fn step_interrupts<D: DisplayBackend>(&mut self, lcd: &mut D, now: Instant) -> Result<()> {
+        let interrupt_step = if self.transfer_page.on_screen {
+            InterruptStep::Idle
+        } else {
+            self.state.poll_interrupt(now, self.current_frame.as_ref())
+        };
+        let switch_to = match interrupt_step {
+            InterruptStep::Show(frame)
+                if self.current_frame.as_ref() != Some(&frame)
+                    || self.scheduler.current() != Some(PageSource::Remote) =>
+            {
+                Some((Some(frame), "interrupt"))
+            }
+            InterruptStep::Resume(frame) => Some((frame, "resumed after interrupt")),
+            _ => None,
+        };
+        let Some((frame, reason)) = switch_to else {
+            return Ok(());
+        };
+        self.incident_on_screen = false;
+        self.parse_errors.dismiss();
+        self.current_frame = frame;
+        self.scroll_offsets = ScrollOffsets::zero();
+        self.one_line.reset(now);
+        self.next_scroll = now + Duration::from_millis(self.config.scroll_speed_ms);
+        match self.current_frame.as_ref() {
+            Some(frame) => {
+                lcd.prepare_redraw()?;
+                self.tracer.scheduled(frame, reason, now, self.logger);
+                self.next_page = now + Duration::from_millis(frame.page_timeout_ms);
+                self.backlight_state = frame.backlight_on;
+                lcd.set_backlight(self.backlight_state)?;
+                lcd.set_blink(frame.blink)?;
+                self.next_blink = now + self.blink_interval;
+                self.scheduler.begin_slot(PageSource::Remote, now);
+                self.draw_frame(lcd, now)?;
+            }
+            None => {
+                lcd.clear()?;
+                self.scheduler.end_slot();
+                self.next_page = now;
+            }
+        }
+        Ok(())
+    }
+
+    /// Open incidents take every other page slot until their budgets recover.
+    fn show_incidents<D: DisplayBackend>(&mut self, lcd: &mut D, now: Instant) -> Result<()> {
+        let incidents = self.slo.active_incidents();
+        if now >= self.next_page
+            && !self.state.interrupting()
+            && !incidents.is_empty()
+            && !self.incident_on_screen
+            && !self.transfer_page.on_screen
+        {
+            let (line1, line2) = incident_lines(&incidents, &self.slo);
+            render_incident_page(lcd, self.config.cols, &line1, &line2)?;
+            self.backlight_state = true;
+            self.incident_on_screen = true;
+            self.parse_errors.dismiss();
+            self.scheduler.end_slot();
+            self.next_page = now + Duration::from_millis(self.config.page_timeout_ms);
+        }
+        Ok(())
+    }
+
+    /// Hand the display to the next scheduled slot after the page timeout. Minimum display
+    /// times only apply while remote frames and local pages are both competing for the LCD.
+    fn rotate_pages<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        serial_connected: bool,
+        now: Instant,
+    ) -> Result<()> {
+        let local_ready = self.scheduler.schedules_local()
+            && self.polling.as_ref().is_some_and(|p| p.latest.is_some());
+        if now < self.next_page
+            || self.transfer_page.on_screen
+            || self.state.interrupting()
+            || (local_ready && !self.scheduler.can_preempt(now))
+        {
+            return Ok(());
+        }
+        self.incident_on_screen = false;
+        let on_remote = self.scheduler.current() == Some(PageSource::Remote);
+        match self.scheduler.pick(!self.state.is_empty(), local_ready) {
+            Some(PageSource::Remote) => {
+                let next_frame = if let Some(frame) = self.pending_remote.take() {
+                    Some(frame)
+                } else if self.state.len() > 1 {
+                    self.state.next_page()
+                } else if !on_remote {
+                    self.current_frame
+                        .clone()
+                        .or_else(|| self.state.next_page())
+                } else {
+                    None
+                };
+                let Some(frame) = next_frame else {
+                    self.next_page = now + Duration::from_millis(self.config.page_timeout_ms);
+                    return Ok(());
+                };
+                self.tracer
+                    .scheduled(&frame, "page rotation", now, self.logger);
+                self.parse_errors.dismiss();
+                self.scroll_offsets = ScrollOffsets::zero();
+                self.one_line.reset(now);
+                self.next_page = now + Duration::from_millis(frame.page_timeout_ms);
+                lcd.prepare_redraw()?;
+                self.backlight_state = frame.backlight_on;
+                lcd.set_backlight(self.backlight_state)?;
+                lcd.set_blink(frame.blink)?;
+                self.next_blink = now + self.blink_interval;
+                self.current_frame = Some(frame);
+                self.draw_frame_throttled(lcd, now)?;
+                self.scheduler.begin_slot(PageSource::Remote, now);
+            }
+            Some(PageSource::Local) => {
+                if let Some(polling_state) = self.polling.as_mut() {
+                    if polling_state.latest.is_some() {
+                        self.parse_errors.dismiss();
+                        render_polling_overlay(
+                            lcd,
+                            self.config.cols,
+                            polling_state,
+                            serial_connected,
+                        )?;
+                        polling_state.last_rendered_seq = polling_state.latest_seq;
+                        polling_state.last_overlay_at = now;
+                    }
+                }
+                self.scheduler.begin_slot(PageSource::Local, now);
+                self.next_page = now
+                    + Duration::from_millis(self.config.page_timeout_ms)
+                        .max(self.scheduler.min_display());
+            }
+            None => {
+                self.next_page = now + Duration::from_millis(self.config.page_timeout_ms);
+            }
+        }
+        Ok(())
+    }
+
+    /// Scroll, fold and blink the remote frame on screen. Returns the backlight pattern that
+    /// is playing, which sets how soon the next pass must run.
+    fn animate<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        now: Instant,
+    ) -> Result<Option<BacklightPattern>> {
+        let local_page_active = self.local_page_active();
+        let frame = self
+            .current_frame
+            .as_ref()
+            .filter(|_| !local_page_active && !self.parse_errors.showing());
+        let pattern = frame.and_then(|frame| frame.backlight_pattern);
+        if let Some(frame) = frame {
+            let width = lcd.cols() as usize;
+            let needs_scroll =
+                frame_needs_scroll(frame, OneLineFold::frame_rows(lcd.rows()), width);
+            let blink = frame.blink;
+            let backlight_on = frame.backlight_on;
+            // Scroll long lines forward when allowed by the frame.
+            if needs_scroll && now >= self.next_scroll {
+                self.scroll_offsets = self.scroll_offsets.advance(frame, width);
+                self.next_scroll = now + Duration::from_millis(frame.scroll_speed_ms);
+                self.draw_frame_throttled(lcd, now)?;
+            }
+
+            // A one-row display takes turns showing the frame's first and second rows.
+            let fold_due = self
+                .current_frame
+                .as_ref()
+                .is_some_and(|frame| self.one_line.tick(frame, lcd.rows(), now));
+            if fold_due {
+                self.draw_frame(lcd, now)?;
+            }
+
+            if let Some(pattern) = pattern {
+                // A pattern overrides blink; it keeps its phase while the same one plays on.
+                let started = match self.pattern_phase {
+                    Some((playing, started)) if playing == pattern => started,
+                    _ => {
+                        self.pattern_phase = Some((pattern, now));
+                        now
+                    }
+                };
+                lcd.set_backlight_level(pattern_level(pattern, now - started))?;
+            } else if blink {
+                // Drive periodic blink by toggling backlight.
+                if now >= self.next_blink {
+                    self.backlight_state = !self.backlight_state;
+                    lcd.set_backlight(self.backlight_state)?;
+                    self.next_blink = now + self.blink_interval;
+                }
+            } else if self.backlight_state != backlight_on {
+                self.backlight_state = backlight_on;
+                lcd.set_backlight(self.backlight_state)?;
+            }
+        }
+
+        if pattern.is_none() && self.pattern_phase.take().is_some() {
+            lcd.set_backlight(self.backlight_state)?;
+        }
+        Ok(pattern)
+    }
+
+    /// Apply the idle timeout and night schedule on top of what the frames asked for.
+    fn apply_backlight_policy<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut BacklightGate<'_, D>,
+        now: Instant,
+    ) -> Result<()> {
+        if now >= self.next_backlight_check {
+            self.backlight_minute = local_minute_of_day();
+            self.state.set_minute_of_day(self.backlight_minute);
+            if let Some(relay) = self.relay.as_mut() {
+                relay.set_minute_of_day(self.backlight_minute);
+            }
+            self.next_backlight_check = now + Duration::from_secs(1);
+            self.log_compactor
+                .poll(local_today(), self.backlight_minute, self.logger);
+        }
+        let dimmed = self.backlight_schedule.dimmed(now, self.backlight_minute);
+        if dimmed.is_some() != lcd.is_held_off() {
+            lcd.hold_off(dimmed.is_some())?;
+            self.logger.info(match dimmed {
+                Some(Dimmed::Idle) => "backlight: off after idle timeout",
+                Some(Dimmed::Night) => "backlight: off for the night",
+                None => "backlight: restored",
+            });
+        }
+        Ok(())
+    }
+
+    /// Finish the pass: GPIO annunciators, the polling overlay, zones, the debug HUD and the
+    /// extra displays, in that order.
+    fn finish_pass<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut BacklightGate<'_, ZoneMask<'_, D>>,
+        serial_connected: bool,
+        now: Instant,
+    ) -> Result<()> {
+        let local_page_active = self.local_page_active();
+        // Mirror the frame on screen onto GPIO; local and status pages count as no frame.
+        if let Some(outputs) = self.annunciators.as_mut() {
+            let on_screen = self.current_frame.as_ref().filter(|_| {
+                !local_page_active && !self.reconnect_displayed && !self.offline_displayed
+            });
+            for (pin, on) in outputs.update(on_screen, now) {
+                self.logger.debug(format!(
+                    "annunciator gpio{pin} {}",
+                    if on { "on" } else { "off" }
+                ));
+            }
+        }
+
+        let no_frames_available = self.state.is_empty();
+        if let Some(polling_state) = self.polling.as_mut() {
+            maybe_render_polling_overlay(
+                polling_state,
+                lcd,
+                self.config.cols,
+                serial_connected,
+                self.current_frame.is_some() && !local_page_active,
+                no_frames_available || local_page_active,
+            )?;
+        }
+
+        let zone_rows = poll_zones(&mut self.state, self.config, now);
+        lcd.inner_mut().draw_zones(&zone_rows)?;
+
+        // The debug HUD goes on last so it sits over whatever page was drawn this pass.
+        self.hud.observe_frames(self.stats.frames_accepted, now);
+        if lcd.rows() > HUD_ROW {
+            if let Some(line) = self.hud.line(now, lcd.cols() as usize) {
+                lcd.write_row_diff(HUD_ROW, line)?;
+            }
+        }
+
+        if !self.extra_displays.is_empty() {
+            self.extra_displays.render(
+                &Sources {
+                    screen: &lcd.screen(),
+                    backlight: self.backlight_state,
+                    polling: self
+                        .polling
+                        .as_ref()
+                        .and_then(|state| Some((state.latest.as_ref()?, &state.layout))),
+                    serial_active: serial_connected,
+                    zones: &zone_rows,
+                },
+                self.logger,
+            );
+        }
+        Ok(())
+    }
+
+    /// Drain: nothing is read or ingested any more. Flush what the shaper still holds, then
+    /// tell the peer why the link is going away, all inside a hard deadline.
+    fn shut_down<D: DisplayBackend>(
+        mut self,
+        lcd: &mut D,
+        serial_connection: &mut Option<SerialPort>,
+    ) -> Result<()> {
+        let logger = self.logger;
+        self.systemd.stopping();
+        let deadline = ShutdownDeadline::arm(Duration::from_millis(SHUTDOWN_DEADLINE_MS));
+        if let Some(serial_ref) = serial_connection.as_mut() {
+            // Tell the peer to drop back before the port closes mid-burst.
+            let steps = self.burst.end(Instant::now(), "shutting down");
+            apply_burst_steps(serial_ref, steps, logger);
+            let drain_until = Instant::now() + Duration::from_millis(SHUTDOWN_DRAIN_MS);
+            match serial_ref.drain_outbox(drain_until) {
+                Ok(true) => {}
+                Ok(false) => logger.warn(format!(
+                    "shutdown: tx queue not empty after {SHUTDOWN_DRAIN_MS}ms; dropping the rest"
+                )),
+                Err(err) => logger.warn(format!("shutdown: tx drain failed: {err}")),
+            }
+            send_goodbye(serial_ref, "shutdown", logger);
+        }
+
+        if let Some(store) = self.page_store.as_mut() {
+            store.save(&self.state, logger);
+        }
+        let stats = &self.stats;
+        if let Err(err) = log_loop_stats(stats) {
+            logger.debug(format!("loop stats telemetry write failed: {err}"));
+        }
+
+        // Leave the display in a clean shutdown state.
+        if let Some(outputs) = self.annunciators.as_mut() {
+            outputs.release();
+        }
+        self.extra_displays.shutdown();
+        render_shutdown(lcd)?;
+        drop(deadline);
+        logger.info(format!(
+            "shutdown: frames accepted={} rejected={} checksum_failures={} duplicates={} reconnects={} frame_cache_hits={} frame_cache_misses={}",
+            stats.frames_accepted,
+            stats.frames_rejected,
+            stats.checksum_failures,
+            stats.duplicates,
+            stats.reconnects,
+            stats.frame_cache_hits,
+            stats.frame_cache_misses
+        ));
+        if stats.frames_filtered > 0 || stats.polls_muted > 0 {
+            let hits = self
+                .state
+                .filter()
+                .hits()
+                .map(|(rule, hits)| format!("'{rule}'={hits}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            logger.info(format!(
+                "shutdown: filters frames_filtered={} polls_muted={} {hits}",
+                stats.frames_filtered, stats.polls_muted
+            ));
+        }
+        if let Some(outbox) = serial_connection
+            .as_ref()
+            .and_then(SerialPort::outbox_stats)
+        {
+            logger.info(format!(
+                "shutdown: tx shaping sent_bytes={} delayed={} dropped={}",
+                outbox.sent_bytes, outbox.delayed_lines, outbox.dropped_lines
+            ));
+        }
+        if let Some(arq) = serial_connection.as_ref().and_then(SerialPort::arq_stats) {
+            logger.info(format!(
+                "shutdown: arq sent={} retransmits={} acked={} dropped={} received={} duplicates={}",
+                arq.sent, arq.retransmits, arq.acked, arq.dropped, arq.received, arq.duplicates
+            ));
+        }
+        logger.info("daemon exiting");
+        Ok(())
+    }
+}
+
+/// Feed the display defaults in `config`, including any the peer pushed, into the live loop.
+fn adopt_display_defaults(
+    config: &AppConfig,
+    state: &mut RenderState,
+    scheduler: &mut PageScheduler,
+    blink_interval: &mut Duration,
+) {
+    state.set_defaults(PayloadDefaults {
+        scroll_speed_ms: config.scroll_speed_ms,
+        page_timeout_ms: config.page_timeout_ms,
+    });
+    scheduler.update(
+        config.scheduler.remote_weight,
+        config.scheduler.local_weight,
+        config.scheduler.min_display_ms,
+    );
+    *blink_interval = Duration::from_millis(config.render.blink_ms);
+}
+
+fn open_annunciators(
+    config: &crate::config::AnnunciatorConfig,
+    logger: &Logger,
+) -> Option<Annunciators> {
+    match Annunciators::new(config) {
+        Ok(outputs) => outputs,
+        Err(err) => {
+            logger.warn(format!("annunciators disabled: {err}"));
+            None
+        }
+    }
+}
+
+fn open_backlight_pwm(channel: Option<u8>, logger: &Logger) -> Option<BacklightPwm> {
+    let channel = channel?;
+    match BacklightPwm::open(channel) {
+        Ok(pwm) => {
+            logger.info(format!("backlight: PWM channel {channel} enabled"));
+            Some(pwm)
+        }
+        Err(err) => {
+            logger.warn(format!("backlight PWM disabled: {err}"));
+            None
+        }
+    }
+}
+
+fn open_encoder(
+    config: &crate::config::InputConfig,
+    waker: LoopWaker,
+    logger: &Logger,
+) -> Option<RotaryEncoder> {
+    match RotaryEncoder::open(config, waker) {
+        Ok(Some(encoder)) => {
+            logger.info("encoder: rotary encoder enabled");
+            Some(encoder)
+        }
+        Ok(None) => None,
+        Err(err) => {
+            logger.warn(format!("encoder disabled: {err}"));
+            None
+        }
+    }
+}
+
+fn open_control(
+    config: &crate::config::ControlConfig,
+    running: Arc<AtomicBool>,
+    waker: LoopWaker,
+    logger: &Logger,
+) -> Option<ControlServer> {
+    if !config.enabled {
+        return None;
+    }
+    match ControlServer::bind(&config.socket, running, waker) {
+        Ok(server) => {
+            logger.info(format!("control: listening on {}", server.path().display()));
+            Some(server)
+        }
+        Err(err) => {
+            logger.warn(format!("control socket disabled: {err}"));
+            None
+        }
+    }
+}
+
+/// Link-rate control frames (`burst`, `burst_ack`, `burst_probe`, `burst_end`).
+fn looks_like_burst_frame(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('{') && trimmed.contains("\"type\":\"burst")
+}
+
+fn looks_like_goodbye_frame(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('{') && trimmed.contains("\"type\":\"goodbye\"")
+}
+
+fn looks_like_render_defaults_frame(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('{') && trimmed.contains("\"type\":\"render_defaults\"")
+}
+
+fn looks_like_anomaly_frame(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('{') && trimmed.contains("\"type\":\"anomaly_summary\"")
+}
+
+fn looks_like_frame_ack(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('{') && trimmed.contains("\"type\":\"frame_ack\"")
+}
+
+fn looks_like_tunnel_frame(line: &str) -> bool {
+    line.contains("\"msg\"") && line.contains("\"crc32\"")
+}
+
+fn looks_like_command_frame(line: &str) -> bool {
+    line.contains("\"channel\":\"command\"") && line.contains("\"crc32\"")
+}
+
+/// Row-addressed updates carry `row`/`text` and never a full `line1`.
+fn looks_like_row_frame(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('{')
+        && trimmed.contains("\"row\"")
+        && trimmed.contains("\"text\"")
+        && !trimmed.contains("\"line1\"")
+}
+
+fn looks_like_payload_frame(line: &str) -> bool {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return false;
+    }
+    // Never treat tunnel/command/burst/arm/ack frames as display payloads.
+    if looks_like_tunnel_frame(trimmed)
+        || looks_like_command_frame(trimmed)
+        || looks_like_burst_frame(trimmed)
+        || looks_like_arm_frame(trimmed)
+        || looks_like_frame_ack(trimmed)
+        || looks_like_goodbye_frame(trimmed)
+        || looks_like_render_defaults_frame(trimmed)
+        || looks_like_anomaly_frame(trimmed)
+    {
+        return false;
+    }
+    // Display payloads are either JSON objects or `key=value` pairs.
+    trimmed.starts_with('{') || trimmed.contains('=')
+}
+
+fn preview_frame(line: &str, max_chars: usize) -> String {
+    let mut out = String::new();
+    for ch in line.chars().take(max_chars) {
+        if ch.is_ascii_control() {
+            out.push(' ');
+        } else {
+            out.push(ch);
+        }
+    }
+    if line.chars().count() > max_chars {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use serde_json::Value;
+    use std::fs;
+
+    fn unique_protocol_error_log_path() -> PathBuf {
+        let pid = std::process::id();
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+
+        let filename = format!("protocol_errors_{pid}_{nanos}.log");
+        let cache_tests = PathBuf::from(crate::CACHE_DIR).join("tests");
+        if fs::create_dir_all(&cache_tests).is_ok() {
+            cache_tests.join(filename)
+        } else {
+            std::env::temp_dir().join(filename)
+        }
+    }
+
+    #[test]
+    fn protocol_error_log_records_len_crc32_preview_and_payload() {
+        let path = unique_protocol_error_log_path();
+        let _ = fs::remove_file(&path);
+
+        let log = ProtocolErrorLog { path: path.clone() };
+        let mut payload = "A".repeat(700);
+        payload.push('\u{0}');
+        payload.push_str("TAIL");
+
+        let mut hasher = Hasher::new();
+        hasher.update(payload.as_bytes());
+        let crc = hasher.finalize();
+
+        log.append(&Error::Parse("json: expected value".into()), &payload, crc)
+            .unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let first_line = contents.lines().next().unwrap();
+        let parsed: Value = serde_json::from_str(first_line).unwrap();
+
+        assert_eq!(parsed["len"].as_u64().unwrap() as usize, payload.len());
+        assert_eq!(parsed["crc32"].as_str().unwrap(), format!("{crc:08x}"));
+        assert!(parsed["preview"].as_str().unwrap().chars().count() <= 161); // 160 + optional ellipsis
+        assert!(parsed["error"].as_str().unwrap().contains("parse error"));
+
+        let logged_payload = parsed["payload"].as_str().unwrap();
+        assert!(logged_payload.chars().count() <= 513); // 512 + optional ellipsis
+        assert!(
+            !logged_payload.chars().any(|c| c.is_ascii_control()),
+            "control characters must be scrubbed"
+        );
+
+        // Best-effort cleanup.
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn row_probe_only_matches_row_frames() {
+        assert!(looks_like_row_frame(r#"{"row":1,"text":"count 7"}"#));
+        assert!(!looks_like_row_frame(
+            r#"{"schema_version":1,"line1":"row","line2":"text"}"#
+        ));
+        assert!(!looks_like_row_frame("row=1 text=hi"));
+    }
+
+    #[test]
+    fn burst_frames_are_not_payloads() {
+        let line = r#"{"type":"burst_ack","baud":115200,"accepted":true}"#;
+        assert!(looks_like_burst_frame(line));
+        assert!(!looks_like_payload_frame(line));
+        assert!(!looks_like_burst_frame(
+            r#"{"schema_version":1,"line1":"type","line2":"burst"}"#
+        ));
+    }
+
+    #[test]
+    fn payload_probe_accepts_json_and_kv() {
+        assert!(looks_like_payload_frame(
+            r#"{"schema_version":1,"line1":"A","line2":"B"}"#
+        ));
+        assert!(looks_like_payload_frame(
+            "schema_version=1 line1=Hello line2=World"
+        ));
+        assert!(looks_like_payload_frame("  schema_version=1 line1=Hello  "));
+    }
+
+    #[test]
+    fn payload_probe_rejects_empty_garbage_and_control_frames() {
+        assert!(!looks_like_payload_frame(""));
+        assert!(!looks_like_payload_frame("\r\n\t  "));
+        assert!(!looks_like_payload_frame("INIT"));
+        assert!(!looks_like_payload_frame("\u{0}\u{1}\u{2}"));
+
+        // Tunnel and command frames are not display payloads.
+        assert!(!looks_like_payload_frame(
+            r#"{"msg":"heartbeat","crc32":123,"schema_version":1}"#
+        ));
+        assert!(!looks_like_payload_frame(
+            r#"{"channel":"command","schema_version":1,"message":{"type":"ack","request_id":1},"crc32":1}"#
+        ));
+        assert!(!looks_like_payload_frame(
+            r#"{"type":"frame_ack","crc32":1,"seq":1}"#
+        ));
+        assert!(!looks_like_payload_frame(
+            r#"{"type":"goodbye","reason":"shutdown"}"#
+        ));
+    }
+
+    #[test]
+    fn loop_draws_and_acks_scripted_frames_then_rotates() {
+        use crate::display::backend::recording::{DisplayCall, RecordingDisplay};
+        use crate::serial::fake::FakeSerialPort;
+
+        let mut config = AppConfig::default();
+        config.render.persist_pages = false;
+        config.render.min_interval_ms = 0;
+        config.ack_enabled = true;
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let features = FeatureMatrix::negotiate(&config, None);
+        let mut lp = RenderLoop::new(
+            &mut config,
+            &logger,
+            BackoffController::new(100, 1_000),
+            features,
+            1_000,
+            None,
+            true,
+            Arc::new(AtomicBool::new(true)),
+            None,
+        )
+        .unwrap();
+        let mut port = FakeSerialPort::new(vec![
+            Ok(r#"{"schema_version":1,"line1":"Hello","line2":"World"}"#.to_string()),
+            Ok(r#"{"schema_version":1,"line1":"Second","line2":"Page"}"#.to_string()),
+        ]);
+        let mut display = RecordingDisplay::new(16, 2);
+        let mut mask = ZoneMask::new(&mut display);
+        let mut lcd = BacklightGate::new(&mut mask);
+
+        let start = Instant::now();
+        let mut line = String::new();
+        for _ in 0..2 {
+            line.clear();
+            let (released, read) = next_line(&mut lp.frame_limiter, &mut port, &mut line);
+            assert!(!released);
+            assert!(read.unwrap() > 0);
+            let ingested = lp
+                .ingest_payload(&mut lcd, &mut port, line.trim(), None, start)
+                .unwrap();
+            assert!(matches!(ingested, Ingested::Done));
+        }
+        assert_eq!(port.writes().len(), 2);
+        assert!(port.writes().iter().all(|w| w.contains("frame_ack")));
+
+        // The page timeout hands the display back to the first frame in the rotation.
+        let later = start + Duration::from_millis(lp.config.page_timeout_ms);
+        lp.rotate_pages(&mut lcd, true, later).unwrap();
+        drop(lcd);
+        drop(mask);
+
+        let drawn: Vec<&str> = display
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                DisplayCall::Line(0, text) => Some(text.trim_end()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(drawn, vec!["Hello", "Second", "Hello"]);
+        assert_eq!(display.lines()[1].trim_end(), "World");
+    }
+
+    #[test]
+    fn preview_frame_strips_control_and_truncates() {
+        let p = preview_frame("a\u{0}b\u{1}c", 10);
+        assert_eq!(p, "a b c");
+
+        let p = preview_frame("abcdefghijk", 5);
+        assert_eq!(p, "abcde…");
+    }
+
+    #[test]
+    fn polling_layout_templates_replace_builtin_rows() {
+        let snapshot = PollSnapshot {
+            cpu_percent: 12.0,
+            mem_used_kb: 512 * 1024,
+            mem_total_kb: 1024 * 1024,
+            disk_used_pct: 40.0,
+            disk_available_kb: Some(2048),
+            temperature_c: None,
+            load_average: Some([0.5, 0.25, 0.125]),
+        };
+        let layout = PollLayout::from_config(&PollOverlayConfig {
+            line1: "LD{load1:5.2} M{mem_mb}M".into(),
+            line2: String::new(),
+        });
+        let (line1, line2) = format_polling_lines(&snapshot, &layout, 16, false);
+        assert_eq!(line1, "RC LD 0.50 M512M");
+        assert_eq!(line2, "DSK 40% TMP  -- ");
+    }
+}
+
+/// Tell the peer this end is closing on purpose. Best effort: the link may already be gone.
+fn send_goodbye(serial: &mut SerialPort, reason: &str, logger: &Logger) {
+    let frame = ControlFrame::Goodbye {
+        reason: reason.to_string(),
+    };
+    let sent = serde_json::to_string(&frame)
+        .map_err(|err| crate::Error::Parse(format!("json: {err}")))
+        .and_then(|line| serial.send_control_line(&line));
+    match sent {
+        Ok(()) => logger.info(format!("shutdown: sent goodbye ({reason})")),
+        Err(err) => logger.warn(format!("shutdown: goodbye not sent: {err}")),
+    }
+}
+
+fn send_anomaly_summary(serial: &mut SerialPort, summary: AnomalySummary, logger: &Logger) {
+    let kind = summary.kind.clone();
+    let sent = serde_json::to_string(&ControlFrame::AnomalySummary(summary))
+        .map_err(|err| crate::Error::Parse(format!("json: {err}")))
+        .and_then(|line| serial.send_control_line(&line));
+    match sent {
+        Ok(()) => logger.info(format!("anomaly: sent {kind} summary to the peer")),
+        Err(err) => logger.warn(format!("anomaly: {kind} summary not sent: {err}")),
+    }
+}
+
+/// Carry out burst steps in order. A failed switch is only logged: the peer then sees noise,
+/// aborts its side, and the watchdog reconnect restores the base baud if needed.
+fn apply_burst_steps(serial: &mut SerialPort, steps: Vec<BurstStep>, logger: &Logger) {
+    for step in steps {
+        match step {
+            BurstStep::Send(frame) => match serde_json::to_string(&frame) {
+                Ok(line) => {
+                    if let Err(err) = serial.send_control_line(&line) {
+                        logger.warn(format!("burst send failed: {err}"));
+                    }
+                }
+                Err(err) => logger.warn(format!("burst encode failed: {err}")),
+            },
+            BurstStep::SetBaud { baud, reason } => match serial.set_baud(baud) {
+                Ok(()) => logger.info(format!("burst: {reason}; link now at {baud} baud")),
+                Err(err) => logger.warn(format!("burst: switch to {baud} baud failed: {err}")),
+            },
+        }
+    }
+}
+
+/// Feed a button press to the interlock; when it completes the arming sequence, confirm on the
+/// LCD and report the press as consumed so it does not also turn the page.
+fn arm_from_button<D: DisplayBackend>(
+    interlock: &mut Interlock,
+    lcd: &mut D,
+    cols: u8,
+    now: Instant,
+    logger: &Logger,
+) -> Result<bool> {
+    let wall = SystemTime::now();
+    match interlock.on_button(now, wall) {
+        Ok(Some(until)) => {
+            logger.info(format!(
+                "interlock: armed until {} by button",
+                format_expiry(until)
+            ));
+            let secs = until.saturating_sub(unix_secs(wall));
+            render_incident_page(lcd, cols, "CMDS ARMED", &format!("for {secs}s"))?;
+            Ok(true)
+        }
+        Ok(None) => Ok(false),
+        Err(err) => {
+            logger.warn(format!("interlock: cannot arm: {err}"));
+            Ok(false)
+        }
+    }
+}
+
+/// Acknowledge the alert badge with a button press. A press that clears a visible badge is
+/// consumed; the status row is redrawn on the next pass because the badge went dirty.
+fn clear_badges(badges: &mut AlertBadges, logger: &Logger) -> bool {
+    if !badges.clear() {
+        return false;
+    }
+    logger.info("badges: cleared by button");
+    true
+}
+
+/// Service the `[relay]` port and copy its counters into the loop stats.
+fn poll_relay(relay: &mut Relay, stats: &mut LoopStats, now: Instant, logger: &Logger) {
+    relay.poll(now, logger);
+    let counts = relay.stats();
+    stats.relay_forwarded = counts.forwarded;
+    stats.relay_filtered = counts.filtered;
+    stats.relay_dropped = counts.dropped;
+}
+
+/// The next line to handle and whether it is a frame the rate limit held back, which goes
+/// before anything new from the port.
+fn next_line(
+    frame_limiter: &mut FrameLimiter,
+    serial: &mut impl LineIo,
+    line: &mut String,
+) -> (bool, Result<usize>) {
+    match frame_limiter.release(Instant::now()) {
+        Some(held) => {
+            line.push_str(&held);
+            (true, Ok(held.len()))
+        }
+        None => (false, serial.read_message_line(line)),
+    }
+}
+
+/// Hold `line` back when it arrives over `render.max_frames_per_sec`. Returns `true` when it was
+/// held, alone or in place of an earlier held frame, and must not be ingested now.
+fn held_by_rate_limit(
+    frame_limiter: &mut FrameLimiter,
+    line: &str,
+    stats: &mut LoopStats,
+    tracer: &FrameTracer,
+    trace: Option<TraceId>,
+    now: Instant,
+    logger: &Logger,
+) -> bool {
+    let admission = frame_limiter.admit(line, now);
+    if admission == Admission::Now {
+        return false;
+    }
+    if admission == Admission::Replaced {
+        stats.frames_coalesced += 1;
+    }
+    tracer.note(
+        trace,
+        "schedule",
+        format_args!(
+            "{}",
+            if admission == Admission::Replaced {
+                "over max_frames_per_sec, replaced a held frame"
+            } else {
+                "over max_frames_per_sec, held"
+            }
+        ),
+        now,
+        logger,
+    );
+    true
+}
+
+/// Log and record the error-budget transitions at `now`. Returns `true` when the page should
+/// change straight away: a new incident wants its page, or the last one resolved while its page
+/// was up.
+fn apply_slo_transitions(
+    slo: &mut SloTracker,
+    events: &SloEventLog,
+    incident_on_screen: &mut bool,
+    now: Instant,
+    logger: &Logger,
+) -> bool {
+    let mut change_page = false;
+    if let Some(err) = events.take_webhook_error() {
+        logger.warn(format!("slo webhook delivery failed: {err}"));
+    }
+    for transition in slo.evaluate(now) {
+        if let Err(err) = events.record(&transition, slo.window()) {
+            logger.debug(format!("slo event log write failed: {err}"));
+        }
+        match transition {
+            SloTransition::Breached {
+                kind,
+                count,
+                budget,
+            } => {
+                logger.warn(format!(
+                    "slo: {} budget exhausted ({count} > {budget} per {}ms)",
+                    kind.as_str(),
+                    slo.window().as_millis()
+                ));
+                change_page = true;
+                *incident_on_screen = false;
+            }
+            SloTransition::Resolved { kind, count, .. } => {
+                logger.info(format!(
+                    "slo: {} incident resolved ({count} in window)",
+                    kind.as_str()
+                ));
+                if *incident_on_screen && slo.active_incidents().is_empty() {
+                    change_page = true;
+                }
+            }
+        }
+    }
+    change_page
+}
+
+/// The progress page of a push or pull over the tunnel, which holds the LCD while one runs.
+#[derive(Default)]
+struct TransferPage {
+    on_screen: bool,
+    /// Next redraw; `None` draws on the next pass.
+    next_render: Option<Instant>,
+}
+
+impl TransferPage {
+    /// Draw `progress` every [`TRANSFER_PAGE_REFRESH_MS`], taking the display from the
+    /// scheduler when a transfer starts. Returns `true` when one just ended and the display
+    /// should move on to the next page.
+    fn render<D: DisplayBackend>(
+        &mut self,
+        lcd: &mut D,
+        progress: Option<TransferProgress>,
+        status: StatusMarks<'_>,
+        icon_bank: &mut IconBank,
+        scheduler: &mut PageScheduler,
+        now: Instant,
+    ) -> Result<bool> {
+        match progress {
+            Some(progress) if self.next_render.is_none_or(|due| now >= due) => {
+                if !self.on_screen {
+                    self.on_screen = true;
+                    scheduler.end_slot();
+                }
+                if let Ok(frame) = RenderFrame::from_payload_json(&progress_page(&progress)) {
+                    render_frame_with_scroll(lcd, &frame, &[], false, status, None, icon_bank)?;
+                }
+                self.next_render = Some(now + Duration::from_millis(TRANSFER_PAGE_REFRESH_MS));
+                Ok(false)
+            }
+            None if self.on_screen => {
+                self.on_screen = false;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// What the render loop still has to do after [`handle_tunnel_frame`].
+enum TunnelFollowUp {
+    Nothing,
+    /// The display self-test drew over the page on screen.
+    Redraw,
+    /// A peer's `config_set` saved config.toml.
+    ConfigSaved,
+}
+
+/// Route one tunnel frame from the peer. Heartbeats only feed the watchdog, a display test runs
+/// the self-test on `lcd`, and everything else goes to the tunnel controller, whose reply and
+/// queued output go back over `serial`.
+#[allow(clippy::too_many_arguments)] // The loop state a tunnel frame touches.
+fn handle_tunnel_frame<D: DisplayBackend>(
+    line: &str,
+    serial: &mut SerialPort,
+    tunnel: &mut TunnelController,
+    replay: &mut ReplayGuard,
+    watchdog: &mut WatchdogMonitor,
+    stats: &mut LoopStats,
+    interlock: &Interlock,
+    lcd: &mut D,
+    icon_bank: &mut IconBank,
+    logger: &Logger,
+) -> TunnelFollowUp {
+    let (msg, seq) = match decode_tunnel_frame_with_seq(line) {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            logger.warn(format!("tunnel frame error: {err}"));
+            tunnel.log_frame_error(&format!("tunnel: {err}"), line);
+            return TunnelFollowUp::Nothing;
+        }
+    };
+    if let Err(reason) = replay.admit(ReplayChannel::Tunnel, seq) {
+        log_replay_drop(logger, ReplayChannel::Tunnel, &reason, replay);
+        return TunnelFollowUp::Nothing;
+    }
+    watchdog.touch_serial();
+    watchdog.touch_tunnel();
+    if matches!(msg, TunnelMsgOwned::Heartbeat) {
+        return TunnelFollowUp::Nothing;
+    }
+    stats.tunnel_frames += 1;
+    if matches!(msg, TunnelMsgOwned::DisplayTest) {
+        let result = run_display_test(lcd, icon_bank, logger);
+        send_tunnel_frame(serial, result, replay, logger);
+        return TunnelFollowUp::Redraw;
+    }
+    tunnel.set_locked(!interlock.permits(SystemTime::now()));
+    if let Some(response) = tunnel.handle_msg(msg, logger) {
+        send_tunnel_frame(serial, response, replay, logger);
+    }
+    let saved = tunnel.take_config_saved();
+    flush_tunnel_messages(serial, tunnel, replay, logger);
+    if saved {
+        TunnelFollowUp::ConfigSaved
+    } else {
+        TunnelFollowUp::Nothing
+    }
+}
+
+fn flush_tunnel_messages(
+    serial: &mut SerialPort,
+    tunnel: &mut TunnelController,
+    replay: &mut ReplayGuard,
+    logger: &Logger,
+) {
+    while let Some(msg) = tunnel.next_outgoing() {
+        send_tunnel_frame(serial, msg, replay, logger);
+    }
+}
+
+fn send_tunnel_frame(
+    serial: &mut SerialPort,
+    msg: TunnelMsgOwned,
+    replay: &mut ReplayGuard,
+    logger: &Logger,
+) {
+    match encode_tunnel_msg_with_seq(&msg, replay.next_seq(ReplayChannel::Tunnel)) {
+        Ok(encoded) => {
+            if let Err(err) = serial.send_command_line(&encoded) {
+                logger.warn(format!("tunnel send failed: {err}"));
             }
         }
         Err(err) => {
@@ -1037,18 +3776,103 @@ fn send_tunnel_frame(serial: &mut SerialPort, msg: TunnelMsgOwned, logger: &Logg
     }
 }
 
+/// Run the panel self-test a peer asked for and build the reply; a failed step is reported to
+/// the peer rather than ending the render loop.
+fn run_display_test<D: DisplayBackend>(
+    lcd: &mut D,
+    icon_bank: &mut IconBank,
+    logger: &Logger,
+) -> TunnelMsgOwned {
+    logger.info("tunnel: display self-test requested by peer");
+    let started = Instant::now();
+    let outcome = run_self_test(lcd, icon_bank, Duration::from_millis(SELF_TEST_STEP_MS));
+    let elapsed_ms = millis(started.elapsed());
+    match outcome {
+        Ok(()) => {
+            logger.info(format!(
+                "tunnel: display self-test passed in {elapsed_ms}ms"
+            ));
+            TunnelMsgOwned::DisplayTestResult {
+                ok: true,
+                elapsed_ms,
+                error: None,
+            }
+        }
+        Err(err) => {
+            logger.warn(format!("tunnel: display self-test failed: {err}"));
+            TunnelMsgOwned::DisplayTestResult {
+                ok: false,
+                elapsed_ms,
+                error: Some(err.to_string()),
+            }
+        }
+    }
+}
+
+/// Tell the sender a payload frame made it to the display; `seq` counts every ack sent.
+fn send_frame_ack(serial: &mut impl LineIo, crc32: u32, seq: &mut u64, logger: &Logger) {
+    *seq = seq.wrapping_add(1);
+    match encode_frame_ack(&FrameAck { crc32, seq: *seq }) {
+        Ok(encoded) => {
+            if let Err(err) = serial.send_command_line(&encoded) {
+                logger.warn(format!("frame ack send failed: {err}"));
+            }
+        }
+        Err(err) => {
+            logger.warn(format!("frame ack encode failed: {err}"));
+        }
+    }
+}
+
+/// Tell the sender a payload frame was rejected and why; shares the ack sequence counter.
+fn send_frame_nack(
+    serial: &mut SerialPort,
+    crc32: u32,
+    err: &Error,
+    seq: &mut u64,
+    logger: &Logger,
+) {
+    *seq = seq.wrapping_add(1);
+    let nack = FrameNack {
+        crc32,
+        seq: *seq,
+        code: err
+            .frame_code()
+            .unwrap_or(FrameErrorCode::Invalid)
+            .as_str()
+            .to_string(),
+        error: err.to_string(),
+    };
+    match encode_frame_nack(&nack) {
+        Ok(encoded) => {
+            if let Err(err) = serial.send_command_line(&encoded) {
+                logger.warn(format!("frame nack send failed: {err}"));
+            }
+        }
+        Err(err) => {
+            logger.warn(format!("frame nack encode failed: {err}"));
+        }
+    }
+}
+
 fn flush_command_messages(
     serial: &mut SerialPort,
     executor: &mut CommandExecutor,
+    replay: &mut ReplayGuard,
     logger: &Logger,
 ) {
     while let Some(msg) = executor.next_outgoing() {
-        send_command_frame(serial, msg, logger);
+        send_command_frame(serial, msg, replay, logger);
     }
 }
 
-fn send_command_frame(serial: &mut SerialPort, msg: CommandMessage, logger: &Logger) {
-    match encode_command_frame(&msg) {
+fn send_command_frame(
+    serial: &mut SerialPort,
+    msg: CommandMessage,
+    replay: &mut ReplayGuard,
+    logger: &Logger,
+) {
+    match encode_command_frame_with_seq(&msg, replay.next_seq(ReplayChannel::Command)) {
         Ok(encoded) => {
             if let Err(err) = serial.send_command_line(&encoded) {
                 logger.warn(format!("command send failed: {err}"));
@@ -1060,9 +3884,17 @@ fn send_command_frame(serial: &mut SerialPort, msg: CommandMessage, logger: &Log
     }
 }
 
-fn maybe_render_polling_overlay(
+fn log_replay_drop(logger: &Logger, channel: ReplayChannel, reason: &str, replay: &ReplayGuard) {
+    logger.warn(format!(
+        "{} frame dropped by replay window: {reason} (dropped={})",
+        channel.as_str(),
+        replay.dropped()
+    ));
+}
+
+fn maybe_render_polling_overlay<D: DisplayBackend>(
     polling: &mut PollingState,
-    lcd: &mut Lcd,
+    lcd: &mut D,
     cols: u8,
     serial_active: bool,
     has_frame: bool,
@@ -1086,21 +3918,23 @@ fn maybe_render_polling_overlay(
     {
         return Ok(());
     }
-    let snapshot = polling.latest.as_ref().unwrap();
-    render_polling_overlay(lcd, cols, snapshot, serial_active)?;
+    render_polling_overlay(lcd, cols, polling, serial_active)?;
     polling.last_rendered_seq = polling.latest_seq;
     polling.last_overlay_at = now;
     Ok(())
 }
 
-fn render_polling_overlay(
-    lcd: &mut Lcd,
+fn render_polling_overlay<D: DisplayBackend>(
+    lcd: &mut D,
     cols: u8,
-    snapshot: &PollSnapshot,
+    polling: &PollingState,
     serial_active: bool,
 ) -> Result<()> {
+    let Some(snapshot) = polling.latest.as_ref() else {
+        return Ok(());
+    };
     let width = cols as usize;
-    let (line1, line2) = format_polling_lines(snapshot, width, serial_active);
+    let (line1, line2) = format_polling_lines(snapshot, &polling.layout, width, serial_active);
     lcd.clear()?;
     lcd.set_backlight(true)?;
     lcd.set_blink(false)?;
@@ -1109,8 +3943,9 @@ fn render_polling_overlay(
     Ok(())
 }
 
-fn format_polling_lines(
+pub(super) fn format_polling_lines(
     snapshot: &PollSnapshot,
+    layout: &PollLayout,
     width: usize,
     serial_active: bool,
 ) -> (String, String) {
@@ -1131,9 +3966,22 @@ fn format_polling_lines(
         .map(|c| format!("{c:.0}C"))
         .unwrap_or_else(|| "--".into());
     let prefix = if serial_active { "" } else { "RC " };
-    let line1 = fit_line(format!("{prefix}CPU{cpu:>3}% MEM{mem_pct:>3}%"), width);
+    let fill = |template: &Option<MetricTemplate>, builtin: String| match template {
+        Some(template) => template.render(|metric| snapshot.metric(metric)),
+        None => builtin,
+    };
+    let line1 = fit_line(
+        format!(
+            "{prefix}{}",
+            fill(&layout.line1, format!("CPU{cpu:>3}% MEM{mem_pct:>3}%"))
+        ),
+        width,
+    );
     let line2 = fit_line(
-        format!("DSK{disk:>3}% TMP{temp:>4} FREE{free_mb:>4}"),
+        fill(
+            &layout.line2,
+            format!("DSK{disk:>3}% TMP{temp:>4} FREE{free_mb:>4}"),
+        ),
         width,
     );
     (line1, line2)
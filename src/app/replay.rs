@@ -0,0 +1,204 @@
+//! Sequence numbers and sliding acceptance windows for tunnel and command frames.
+//!
+//! Every outbound frame on either channel gets the next number from a per-channel counter, and
+//! every inbound `seq` must be newer than the window or fill a gap inside it exactly once, so a
+//! retransmitted or replayed `Request` cannot run its command twice. The guard lives for the
+//! whole render loop and is only reset after a full handshake, so a resumed session keeps both
+//! the counters and the windows.
+
+use crate::config::ReplayConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayChannel {
+    Tunnel,
+    Command,
+}
+
+impl ReplayChannel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReplayChannel::Tunnel => "tunnel",
+            ReplayChannel::Command => "command",
+        }
+    }
+}
+
+/// Bitmap window over the newest `size` sequence numbers; bit `n` marks `highest - n` as seen.
+#[derive(Debug, Clone, Default)]
+struct SeqWindow {
+    highest: u64,
+    seen: u64,
+}
+
+impl SeqWindow {
+    fn admit(&mut self, seq: u64, size: u8) -> Result<(), String> {
+        if seq == 0 {
+            return Err("seq 0 is never issued".into());
+        }
+        if seq > self.highest {
+            let shift = seq - self.highest;
+            self.seen = if shift >= u64::BITS as u64 {
+                0
+            } else {
+                self.seen << shift
+            };
+            self.seen |= 1;
+            self.highest = seq;
+            return Ok(());
+        }
+        let offset = self.highest - seq;
+        if offset >= u64::from(size) {
+            return Err(format!(
+                "seq {seq} is older than the window (newest {})",
+                self.highest
+            ));
+        }
+        let bit = 1u64 << offset;
+        if self.seen & bit != 0 {
+            return Err(format!("seq {seq} already accepted"));
+        }
+        self.seen |= bit;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ChannelState {
+    last_sent: u64,
+    inbound: SeqWindow,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayGuard {
+    window: u8,
+    require_seq: bool,
+    tunnel: ChannelState,
+    command: ChannelState,
+    dropped: u64,
+}
+
+impl ReplayGuard {
+    pub fn new(config: &ReplayConfig) -> Self {
+        Self {
+            window: config.window,
+            require_seq: config.require_seq,
+            tunnel: ChannelState::default(),
+            command: ChannelState::default(),
+            dropped: 0,
+        }
+    }
+
+    /// Apply new settings from a config reload without losing counters or windows.
+    pub fn reconfigure(&mut self, config: &ReplayConfig) {
+        self.window = config.window;
+        self.require_seq = config.require_seq;
+    }
+
+    fn channel(&mut self, channel: ReplayChannel) -> &mut ChannelState {
+        match channel {
+            ReplayChannel::Tunnel => &mut self.tunnel,
+            ReplayChannel::Command => &mut self.command,
+        }
+    }
+
+    /// Sequence number for the next outbound frame, or `None` while numbering is off.
+    pub fn next_seq(&mut self, channel: ReplayChannel) -> Option<u64> {
+        if self.window == 0 {
+            return None;
+        }
+        let state = self.channel(channel);
+        state.last_sent += 1;
+        Some(state.last_sent)
+    }
+
+    /// Check an inbound frame's `seq`; the error explains why the frame must be dropped.
+    pub fn admit(&mut self, channel: ReplayChannel, seq: Option<u64>) -> Result<(), String> {
+        if self.window == 0 {
+            return Ok(());
+        }
+        let window = self.window;
+        let result = match seq {
+            Some(seq) => self.channel(channel).inbound.admit(seq, window),
+            None if self.require_seq => Err("frame has no seq".into()),
+            None => Ok(()),
+        };
+        if result.is_err() {
+            self.dropped += 1;
+        }
+        result
+    }
+
+    /// Start over after a full handshake; the peer's counters restart at 1.
+    pub fn reset(&mut self) {
+        self.tunnel = ChannelState::default();
+        self.command = ChannelState::default();
+    }
+
+    /// Frames rejected since startup.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(window: u8, require_seq: bool) -> ReplayGuard {
+        ReplayGuard::new(&ReplayConfig {
+            window,
+            require_seq,
+        })
+    }
+
+    #[test]
+    fn duplicates_and_stale_frames_are_rejected() {
+        let mut guard = guard(4, false);
+        let ch = ReplayChannel::Command;
+        assert!(guard.admit(ch, Some(1)).is_ok());
+        assert!(guard.admit(ch, Some(3)).is_ok());
+        assert!(guard.admit(ch, Some(1)).is_err());
+        // Late but inside the window, and not seen yet.
+        assert!(guard.admit(ch, Some(2)).is_ok());
+        assert!(guard.admit(ch, Some(2)).is_err());
+        assert!(guard.admit(ch, Some(10)).is_ok());
+        assert!(guard.admit(ch, Some(6)).is_err(), "outside the window");
+        assert!(guard.admit(ch, Some(7)).is_ok());
+        assert!(guard.admit(ch, Some(0)).is_err());
+        assert_eq!(guard.dropped(), 4);
+    }
+
+    #[test]
+    fn channels_are_independent() {
+        let mut guard = guard(8, false);
+        assert!(guard.admit(ReplayChannel::Tunnel, Some(5)).is_ok());
+        assert!(guard.admit(ReplayChannel::Command, Some(5)).is_ok());
+        assert_eq!(guard.next_seq(ReplayChannel::Tunnel), Some(1));
+        assert_eq!(guard.next_seq(ReplayChannel::Tunnel), Some(2));
+        assert_eq!(guard.next_seq(ReplayChannel::Command), Some(1));
+    }
+
+    #[test]
+    fn unsequenced_frames_follow_require_seq() {
+        assert!(guard(8, false).admit(ReplayChannel::Tunnel, None).is_ok());
+        assert!(guard(8, true).admit(ReplayChannel::Tunnel, None).is_err());
+    }
+
+    #[test]
+    fn zero_window_disables_numbering_and_checks() {
+        let mut guard = guard(0, false);
+        assert_eq!(guard.next_seq(ReplayChannel::Command), None);
+        assert!(guard.admit(ReplayChannel::Command, Some(1)).is_ok());
+        assert!(guard.admit(ReplayChannel::Command, Some(1)).is_ok());
+    }
+
+    #[test]
+    fn reset_starts_a_new_session() {
+        let mut guard = guard(8, false);
+        assert!(guard.admit(ReplayChannel::Command, Some(40)).is_ok());
+        guard.next_seq(ReplayChannel::Command);
+        guard.reset();
+        assert!(guard.admit(ReplayChannel::Command, Some(1)).is_ok());
+        assert_eq!(guard.next_seq(ReplayChannel::Command), Some(1));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{display::icon_bank::COUNTDOWN_LEVELS, payload::RenderFrame, state::RenderState};
+use std::time::{Duration, Instant};
+
+/// Origin of a page competing for LCD time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageSource {
+    /// Frames received from the serial peer.
+    Remote,
+    /// Pages generated on this node (polling overlay today).
+    Local,
+}
+
+/// Weighted page scheduler shared by remote frames and local pages.
+///
+/// Slots are handed out with smooth weighted round-robin so neither side can starve the other,
+/// and every slot stays on screen for at least `min_display` before it can be preempted.
+#[derive(Debug, Clone)]
+pub struct PageScheduler {
+    remote_weight: u32,
+    local_weight: u32,
+    min_display: Duration,
+    current: Option<PageSource>,
+    slot_started: Instant,
+    remote_credit: i64,
+    local_credit: i64,
+}
+
+impl PageScheduler {
+    pub fn new(remote_weight: u32, local_weight: u32, min_display_ms: u64) -> Self {
+        Self {
+            remote_weight,
+            local_weight,
+            min_display: Duration::from_millis(min_display_ms),
+            current: None,
+            slot_started: Instant::now(),
+            remote_credit: 0,
+            local_credit: 0,
+        }
+    }
+
+    pub fn current(&self) -> Option<PageSource> {
+        self.current
+    }
+
+    pub fn min_display(&self) -> Duration {
+        self.min_display
+    }
+
+    /// True when local pages take part in the rotation at all (`local_weight > 0`).
+    pub fn schedules_local(&self) -> bool {
+        self.local_weight > 0
+    }
+
+    /// True once the active slot has been visible for its minimum display time.
+    pub fn can_preempt(&self, now: Instant) -> bool {
+        self.current.is_none() || now.duration_since(self.slot_started) >= self.min_display
+    }
+
+    /// Choose the source for the next slot; `None` when nothing is ready to show.
+    pub fn pick(&mut self, remote_ready: bool, local_ready: bool) -> Option<PageSource> {
+        let remote_ready = remote_ready && self.remote_weight > 0;
+        let local_ready = local_ready && self.local_weight > 0;
+        match (remote_ready, local_ready) {
+            (false, false) => None,
+            (true, false) => Some(PageSource::Remote),
+            (false, true) => Some(PageSource::Local),
+            (true, true) => {
+                let total = i64::from(self.remote_weight) + i64::from(self.local_weight);
+                self.remote_credit += i64::from(self.remote_weight);
+                self.local_credit += i64::from(self.local_weight);
+                if self.local_credit > self.remote_credit {
+                    self.local_credit -= total;
+                    Some(PageSource::Local)
+                } else {
+                    self.remote_credit -= total;
+                    Some(PageSource::Remote)
+                }
+            }
+        }
+    }
+
+    /// Record that `source` now owns the display.
+    pub fn begin_slot(&mut self, source: PageSource, now: Instant) {
+        self.current = Some(source);
+        self.slot_started = now;
+    }
+
+    /// Release the display (e.g. an incident page took over) so the next pick redraws.
+    pub fn end_slot(&mut self) {
+        self.current = None;
+    }
+
+    pub fn update(&mut self, remote_weight: u32, local_weight: u32, min_display_ms: u64) {
+        self.remote_weight = remote_weight;
+        self.local_weight = local_weight;
+        self.min_display = Duration::from_millis(min_display_ms);
+        self.remote_credit = 0;
+        self.local_credit = 0;
+    }
+}
+
+/// What the expiry countdown needs from the render loop on this pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CountdownTick {
+    /// Tick level for the frame on screen (1..=`COUNTDOWN_LEVELS`), `None` when not expiring.
+    pub level: Option<u8>,
+    /// The level moved since it was last drawn.
+    pub redraw: bool,
+    /// The frame on screen just outlived its `duration_ms`; reported once per deadline.
+    pub expired: bool,
+}
+
+/// Countdown overlay for remote frames that carry `duration_ms`.
+///
+/// The deadline comes from the page queue and is remembered per frame, so the countdown keeps
+/// running after `RenderState` prunes the expired entry.
+#[derive(Debug, Clone, Default)]
+pub struct ExpiryCountdown {
+    enabled: bool,
+    frame: Option<RenderFrame>,
+    deadline: Option<(Instant, Duration)>,
+    shown: Option<u8>,
+    expired_reported: bool,
+}
+
+impl ExpiryCountdown {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Self::default()
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Level to draw with `frame` right now; call from every render of a remote frame.
+    pub fn level_for(
+        &mut self,
+        frame: &RenderFrame,
+        state: &RenderState,
+        now: Instant,
+    ) -> Option<u8> {
+        self.track(frame, state);
+        self.shown = self.level_at(now);
+        self.shown
+    }
+
+    /// Once per loop pass with the remote frame on screen (`None` while a local, incident or
+    /// error page covers it).
+    pub fn tick(
+        &mut self,
+        frame: Option<&RenderFrame>,
+        state: &RenderState,
+        now: Instant,
+    ) -> CountdownTick {
+        let Some(frame) = frame.filter(|_| self.enabled) else {
+            return CountdownTick::default();
+        };
+        self.track(frame, state);
+        let level = self.level_at(now);
+        let redraw = level != self.shown;
+        self.shown = level;
+        let expired = match self.deadline {
+            Some((deadline, _)) if now >= deadline && !self.expired_reported => {
+                self.expired_reported = true;
+                true
+            }
+            _ => false,
+        };
+        CountdownTick {
+            level,
+            redraw,
+            expired,
+        }
+    }
+
+    fn track(&mut self, frame: &RenderFrame, state: &RenderState) {
+        let queued = state
+            .expires_at(frame)
+            .zip(frame.duration_ms.map(Duration::from_millis));
+        match queued {
+            Some(deadline) => {
+                if self.deadline != Some(deadline) {
+                    self.deadline = Some(deadline);
+                    self.expired_reported = false;
+                }
+            }
+            None if self.frame.as_ref() == Some(frame) => {}
+            None => self.deadline = None,
+        }
+        if self.frame.as_ref() != Some(frame) {
+            self.frame = Some(frame.clone());
+        }
+    }
+
+    fn level_at(&self, now: Instant) -> Option<u8> {
+        let (deadline, total) = self.deadline.filter(|_| self.enabled)?;
+        let remaining = deadline.saturating_duration_since(now);
+        if remaining.is_zero() || total.is_zero() {
+            return None;
+        }
+        let levels = u128::from(COUNTDOWN_LEVELS);
+        let level = (remaining.as_millis() * levels).div_ceil(total.as_millis().max(1));
+        Some(level.clamp(1, levels) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_share_slots_proportionally() {
+        let mut scheduler = PageScheduler::new(3, 1, 0);
+        let picks: Vec<PageSource> = (0..8).filter_map(|_| scheduler.pick(true, true)).collect();
+        let local = picks.iter().filter(|s| **s == PageSource::Local).count();
+        assert_eq!(local, 2);
+        assert_eq!(picks.len() - local, 6);
+    }
+
+    #[test]
+    fn only_ready_sources_are_picked() {
+        let mut scheduler = PageScheduler::new(1, 5, 0);
+        assert_eq!(scheduler.pick(true, false), Some(PageSource::Remote));
+        assert_eq!(scheduler.pick(false, true), Some(PageSource::Local));
+        assert_eq!(scheduler.pick(false, false), None);
+    }
+
+    #[test]
+    fn zero_weight_disables_source() {
+        let mut scheduler = PageScheduler::new(2, 0, 0);
+        assert_eq!(scheduler.pick(false, true), None);
+        assert_eq!(scheduler.pick(true, true), Some(PageSource::Remote));
+    }
+
+    #[test]
+    fn min_display_blocks_preemption() {
+        let mut scheduler = PageScheduler::new(1, 1, 2_000);
+        let start = Instant::now();
+        assert!(scheduler.can_preempt(start));
+        scheduler.begin_slot(PageSource::Local, start);
+        assert!(!scheduler.can_preempt(start + Duration::from_millis(500)));
+        assert!(scheduler.can_preempt(start + Duration::from_millis(2_000)));
+    }
+
+    #[test]
+    fn default_config_keeps_remote_only_rotation() {
+        let defaults = crate::config::SchedulerConfig::default();
+        let mut scheduler = PageScheduler::new(
+            defaults.remote_weight,
+            defaults.local_weight,
+            defaults.min_display_ms,
+        );
+        assert!(!scheduler.schedules_local());
+        let picks: Vec<_> = (0..8).map(|_| scheduler.pick(true, true)).collect();
+        assert!(picks.iter().all(|p| *p == Some(PageSource::Remote)));
+        assert_eq!(scheduler.pick(false, true), None);
+    }
+
+    fn timed_frame(state: &mut RenderState, duration_ms: u64) -> RenderFrame {
+        state
+            .ingest(&format!(
+                r#"{{"schema_version":1,"line1":"T","line2":"","duration_ms":{duration_ms}}}"#
+            ))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn countdown_shrinks_then_reports_expiry_once() {
+        let mut state = RenderState::new(None);
+        let frame = timed_frame(&mut state, 4_000);
+        let deadline = state.expires_at(&frame).unwrap();
+        let mut countdown = ExpiryCountdown::new(true);
+
+        let at = |ms: u64| deadline - Duration::from_millis(ms);
+        let first = countdown.tick(Some(&frame), &state, at(4_000));
+        assert_eq!(first.level, Some(4));
+        assert!(first.redraw);
+        assert!(!countdown.tick(Some(&frame), &state, at(3_500)).redraw);
+        assert_eq!(
+            countdown.tick(Some(&frame), &state, at(2_500)).level,
+            Some(3)
+        );
+        assert_eq!(countdown.tick(Some(&frame), &state, at(100)).level, Some(1));
+
+        let done = countdown.tick(Some(&frame), &state, deadline);
+        assert_eq!(done.level, None);
+        assert!(done.redraw && done.expired);
+        assert!(!countdown.tick(Some(&frame), &state, deadline).expired);
+    }
+
+    #[test]
+    fn countdown_is_silent_when_disabled_or_untimed() {
+        let mut state = RenderState::new(None);
+        let timed = timed_frame(&mut state, 1_000);
+        let untimed = state
+            .ingest(r#"{"schema_version":1,"line1":"U","line2":""}"#)
+            .unwrap()
+            .unwrap();
+        let now = Instant::now();
+
+        let mut disabled = ExpiryCountdown::new(false);
+        assert_eq!(
+            disabled.tick(Some(&timed), &state, now),
+            CountdownTick::default()
+        );
+        assert_eq!(disabled.level_for(&timed, &state, now), None);
+
+        let mut countdown = ExpiryCountdown::new(true);
+        assert_eq!(countdown.level_for(&untimed, &state, now), None);
+        assert_eq!(countdown.tick(None, &state, now), CountdownTick::default());
+    }
+}
@@ -0,0 +1,129 @@
+use super::{
+    serial_shell::{send_payload_frame, FrameAckPolicy, SerialShellTransport},
+    AppConfig,
+};
+use crate::{
+    cli::{RunOptions, SendOptions},
+    compression::CompressionCodec,
+    config::Config,
+    payload::{encode_compressed_payload, Payload, RenderFrame},
+    serial::SerialPort,
+    ExitCode, Result,
+};
+use std::io::Write;
+
+/// `lifelinetty send`: build a checksummed schema_v1 frame and write it to the serial port.
+/// Returns the process exit status: 1 when acks are enabled and the frame was never acked.
+pub fn run(opts: SendOptions) -> Result<i32> {
+    let cfg = Config::load_or_default().map_err(|err| err.exit_with(ExitCode::Config))?;
+    let merged = AppConfig::from_sources(
+        cfg,
+        RunOptions {
+            device: opts.device.clone(),
+            baud: opts.baud,
+            compression_enabled: opts.compression_enabled,
+            compression_codec: opts.compression_codec,
+            ..RunOptions::default()
+        },
+    );
+    let codec = merged
+        .compression_enabled
+        .then_some(merged.compression_codec)
+        .filter(|codec| *codec != CompressionCodec::None);
+    let frame = build_frame(&opts, codec).map_err(|err| err.exit_with(ExitCode::InvalidPayload))?;
+    let mut serial = SerialPort::connect(&merged.device, merged.serial_options())
+        .map_err(|err| err.exit_with(ExitCode::SerialUnavailable))?;
+    let acks = merged.ack_enabled.then(FrameAckPolicy::default);
+    send_frame(&mut serial, &frame, acks, &mut std::io::stderr())
+}
+
+/// Encode the frame exactly as it goes on the wire, rejecting anything the daemon would refuse.
+fn build_frame(opts: &SendOptions, codec: Option<CompressionCodec>) -> Result<String> {
+    let mut payload = Payload::new(opts.line1.as_str(), opts.line2.as_str());
+    payload.bar = opts.bar;
+    payload.seal()?;
+    let json =
+        serde_json::to_string(&payload).map_err(|e| crate::Error::Parse(format!("json: {e}")))?;
+    RenderFrame::from_payload_json(&json)?;
+    match codec {
+        Some(codec) => encode_compressed_payload(&json, codec),
+        None => Ok(json),
+    }
+}
+
+fn send_frame<T, E>(
+    serial: &mut T,
+    frame: &str,
+    acks: Option<FrameAckPolicy>,
+    stderr: &mut E,
+) -> Result<i32>
+where
+    T: SerialShellTransport,
+    E: Write,
+{
+    let status = send_payload_frame(serial, frame, acks, stderr)?;
+    if status == 0 {
+        let note = if acks.is_some() { "acked" } else { "sent" };
+        writeln!(stderr, "frame {note} ({} bytes)", frame.len())?;
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::{encode_frame_ack, frame_crc32, FrameAck};
+    use crate::serial::fake::FakeSerialPort;
+    use std::time::Duration;
+
+    fn opts(line1: &str, bar: Option<u8>) -> SendOptions {
+        SendOptions {
+            line1: line1.into(),
+            line2: "db ok".into(),
+            bar,
+            ..SendOptions::default()
+        }
+    }
+
+    #[test]
+    fn built_frames_carry_a_valid_checksum() {
+        let frame = build_frame(&opts("cpu 50%", Some(50)), None).unwrap();
+        assert!(frame.contains("\"checksum\""));
+        let parsed = RenderFrame::from_payload_json(&frame).unwrap();
+        assert_eq!(parsed.line1, "cpu 50%");
+        assert_eq!(parsed.bar_percent, Some(50));
+    }
+
+    #[test]
+    fn compressed_frames_decode_back_to_the_payload() {
+        let frame = build_frame(&opts("cpu", None), Some(CompressionCodec::Lz4)).unwrap();
+        assert!(frame.contains("\"compressed\""));
+        let parsed = RenderFrame::from_payload_json(&frame).unwrap();
+        assert_eq!(parsed.line2, "db ok");
+    }
+
+    #[test]
+    fn overlong_lines_are_refused_before_sending() {
+        assert!(build_frame(&opts(&"x".repeat(41), None), None).is_err());
+    }
+
+    #[test]
+    fn send_waits_for_the_frame_ack() {
+        let frame = build_frame(&opts("cpu", None), None).unwrap();
+        let ack = encode_frame_ack(&FrameAck {
+            crc32: frame_crc32(&frame),
+            seq: 1,
+        })
+        .unwrap();
+        let mut serial = FakeSerialPort::new(vec![Ok(ack)]);
+        let policy = FrameAckPolicy {
+            timeout: Duration::from_millis(20),
+            retries: 0,
+        };
+        let mut stderr = Vec::new();
+        let status = send_frame(&mut serial, &frame, Some(policy), &mut stderr).unwrap();
+        assert_eq!(status, 0);
+        assert_eq!(serial.writes(), std::slice::from_ref(&frame));
+        assert!(String::from_utf8(stderr).unwrap().contains("frame acked"));
+    }
+}
@@ -1,9 +1,41 @@
-use crate::payload::{decode_tunnel_frame, encode_tunnel_msg};
+use crate::payload::{
+    decode_frame_ack, decode_frame_nack, decode_tunnel_frame, encode_tunnel_msg, frame_crc32,
+};
 use crate::{
     app::AppConfig, cli::RunOptions, config::Config, payload::TunnelMsgOwned, serial::SerialPort,
-    Result,
+    ExitCode, Result,
 };
 use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant, SystemTime};
+
+use super::interlock::{unix_ms, unix_secs};
+pub use super::shell_pager::ShellTerminal;
+use super::shell_pager::{Pager, Reflow};
+use super::tunnel_auth::{RequestSigner, SignedRequest, TotpSigner};
+
+/// How long the shell waits for a `frame_ack` before sending a payload frame again.
+const FRAME_ACK_TIMEOUT_MS: u64 = 1_000;
+/// Resends after the first attempt before a payload frame is reported as unacked.
+const FRAME_ACK_RETRIES: u32 = 2;
+
+/// How long `display-test` waits for the far end to report; the sequence itself takes ~1s.
+const DISPLAY_TEST_TIMEOUT_MS: u64 = 10_000;
+
+/// Retry policy for payload frames typed into the shell when the daemon acks them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameAckPolicy {
+    pub timeout: Duration,
+    pub retries: u32,
+}
+
+impl Default for FrameAckPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(FRAME_ACK_TIMEOUT_MS),
+            retries: FRAME_ACK_RETRIES,
+        }
+    }
+}
 
 /// Abstraction over the serial port used by the serial shell loop.
 pub trait SerialShellTransport {
@@ -24,14 +56,28 @@ impl SerialShellTransport for SerialPort {
 /// Run the serial shell with stdin/stdout/stderr connected to the current process.
 pub fn run_serial_shell(opts: RunOptions) -> Result<i32> {
     super::wizard::maybe_run(&opts)?;
-    let cfg = Config::load_or_default()?;
+    let cfg = Config::load_or_default().map_err(|err| err.exit_with(ExitCode::Config))?;
     let merged = AppConfig::from_sources(cfg, opts);
-    let mut serial = SerialPort::connect(&merged.device, merged.serial_options())?;
+    let mut serial = SerialPort::connect(&merged.device, merged.serial_options())
+        .map_err(|err| err.exit_with(ExitCode::SerialUnavailable))?;
+    if let Some(warning) = serial.tuning_warning() {
+        eprintln!("warning: {warning}");
+    }
     let stdin = io::stdin();
     let mut stdin_lock = stdin.lock();
     let mut stdout = io::stdout();
     let mut stderr = io::stderr();
-    drive_serial_shell_loop(&mut serial, &mut stdin_lock, &mut stdout, &mut stderr)
+    let acks = merged.ack_enabled.then(FrameAckPolicy::default);
+    let signer = RequestSigner::from_config(&merged.tunnel_auth);
+    drive_serial_shell_session(
+        &mut serial,
+        &mut stdin_lock,
+        &mut stdout,
+        &mut stderr,
+        acks,
+        ShellTerminal::detect(),
+        signer,
+    )
 }
 
 /// Core loop used by `run_serial_shell`. Accepts injectable transports + IO for easier testing.
@@ -41,6 +87,46 @@ pub fn drive_serial_shell_loop<T, I, O, E>(
     stdout: &mut O,
     stderr: &mut E,
 ) -> Result<i32>
+where
+    T: SerialShellTransport,
+    I: BufRead,
+    O: Write,
+    E: Write,
+{
+    drive_serial_shell_loop_with_acks(serial, input, stdout, stderr, None)
+}
+
+/// Shell loop where lines starting with `{` are sent as display payload frames. With `acks`,
+/// each frame waits for the daemon's `frame_ack`, is resent on timeout, and sets the exit
+/// status to 1 when it is never acknowledged.
+pub fn drive_serial_shell_loop_with_acks<T, I, O, E>(
+    serial: &mut T,
+    input: &mut I,
+    stdout: &mut O,
+    stderr: &mut E,
+    acks: Option<FrameAckPolicy>,
+) -> Result<i32>
+where
+    T: SerialShellTransport,
+    I: BufRead,
+    O: Write,
+    E: Write,
+{
+    drive_serial_shell_session(serial, input, stdout, stderr, acks, None, None)
+}
+
+/// Full shell session. With a `terminal`, its size is sent to the daemon after `INIT` so
+/// commands format for it, and command output is paged to fit it. With a `signer`, every
+/// command carries a TOTP code or signature for the daemon's `[tunnel_auth]` check.
+pub fn drive_serial_shell_session<T, I, O, E>(
+    serial: &mut T,
+    input: &mut I,
+    stdout: &mut O,
+    stderr: &mut E,
+    acks: Option<FrameAckPolicy>,
+    terminal: Option<ShellTerminal>,
+    mut signer: Option<RequestSigner>,
+) -> Result<i32>
 where
     T: SerialShellTransport,
     I: BufRead,
@@ -48,6 +134,17 @@ where
     E: Write,
 {
     serial.send_command_line("INIT")?;
+    if let Some(terminal) = terminal {
+        serial.send_command_line(&encode_tunnel_msg(&TunnelMsgOwned::TermSize {
+            cols: terminal.cols(),
+            rows: terminal.rows(),
+        })?)?;
+    }
+    let mut output = CommandOutput {
+        pager: terminal.as_ref().and_then(Pager::for_terminal),
+        stdout: Reflow::default(),
+        stderr: Reflow::default(),
+    };
     let mut buffer = String::new();
     let mut last_exit = 0;
 
@@ -65,8 +162,31 @@ where
         if command.eq_ignore_ascii_case("exit") {
             break;
         }
-        send_serial_command(serial, command)?;
-        last_exit = wait_for_exit(serial, stdout, stderr)?;
+        if command.eq_ignore_ascii_case("display-test") {
+            last_exit = run_display_test(
+                serial,
+                stderr,
+                Duration::from_millis(DISPLAY_TEST_TIMEOUT_MS),
+            )?;
+            continue;
+        }
+        if command.starts_with('{') {
+            last_exit = send_payload_frame(serial, command, acks, stderr)?;
+            continue;
+        }
+        let (otp, sig) = match signer.as_mut() {
+            Some(RequestSigner::Totp(totp)) => (Some(next_totp_code(totp, stderr)?), None),
+            Some(RequestSigner::Hmac(hmac)) => {
+                let request = SignedRequest {
+                    cmd: command,
+                    scratch_path: None,
+                };
+                (None, Some(hmac.sign(&request, unix_ms(SystemTime::now()))))
+            }
+            None => (None, None),
+        };
+        send_serial_command(serial, command, otp, sig)?;
+        last_exit = wait_for_exit(serial, input, stdout, stderr, &mut output)?;
     }
 
     Ok(last_exit)
@@ -78,17 +198,218 @@ fn write_prompt<W: Write>(stderr: &mut W) -> Result<()> {
     Ok(())
 }
 
-fn send_serial_command<T: SerialShellTransport>(serial: &mut T, command: &str) -> Result<()> {
+/// A fresh code for the next command; when every code the daemon still accepts has been
+/// used, wait for the clock to reach the next period.
+fn next_totp_code<E: Write>(signer: &mut TotpSigner, stderr: &mut E) -> Result<String> {
+    let mut waiting = false;
+    loop {
+        if let Some(code) = signer.next_code(unix_secs(SystemTime::now())) {
+            return Ok(code);
+        }
+        if !waiting {
+            writeln!(stderr, "waiting for the next TOTP code...")?;
+            waiting = true;
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
+
+fn send_serial_command<T: SerialShellTransport>(
+    serial: &mut T,
+    command: &str,
+    otp: Option<String>,
+    sig: Option<String>,
+) -> Result<()> {
     let msg = TunnelMsgOwned::CmdRequest {
         cmd: command.to_string(),
+        otp,
+        sig,
+        cache_ttl_ms: None,
+        no_cache: false,
     };
     let encoded = encode_tunnel_msg(&msg)?;
     serial.send_command_line(&encoded)
 }
 
-fn wait_for_exit<T, O, E>(serial: &mut T, stdout: &mut O, stderr: &mut E) -> Result<i32>
+pub(crate) fn send_payload_frame<T, E>(
+    serial: &mut T,
+    frame: &str,
+    acks: Option<FrameAckPolicy>,
+    stderr: &mut E,
+) -> Result<i32>
+where
+    T: SerialShellTransport,
+    E: Write,
+{
+    serial.send_command_line(frame)?;
+    let Some(policy) = acks else {
+        return Ok(0);
+    };
+    let crc = frame_crc32(frame);
+    for attempt in 0..=policy.retries {
+        if attempt > 0 {
+            writeln!(
+                stderr,
+                "frame {crc:08x} unacked; retry {attempt}/{}",
+                policy.retries
+            )?;
+            serial.send_command_line(frame)?;
+        }
+        match wait_for_ack(serial, crc, policy.timeout)? {
+            AckOutcome::Acked => return Ok(0),
+            AckOutcome::Rejected { code, error } => {
+                // Resending the same bytes would be rejected the same way.
+                writeln!(stderr, "frame {crc:08x} rejected [{code}]: {error}")?;
+                return Ok(1);
+            }
+            AckOutcome::TimedOut => {}
+        }
+    }
+    writeln!(
+        stderr,
+        "frame {crc:08x} not acknowledged after {} attempts",
+        policy.retries + 1
+    )?;
+    Ok(1)
+}
+
+/// How the daemon answered a payload frame.
+enum AckOutcome {
+    Acked,
+    Rejected { code: String, error: String },
+    TimedOut,
+}
+
+/// Read lines until an ack or nack for `crc` arrives or `timeout` passes; other lines are
+/// dropped.
+fn wait_for_ack<T: SerialShellTransport>(
+    serial: &mut T,
+    crc: u32,
+    timeout: Duration,
+) -> Result<AckOutcome> {
+    let deadline = Instant::now() + timeout;
+    let mut line = String::new();
+    while Instant::now() < deadline {
+        line.clear();
+        if serial.read_message_line(&mut line)? == 0 {
+            continue;
+        }
+        if decode_frame_ack(&line).is_some_and(|ack| ack.crc32 == crc) {
+            return Ok(AckOutcome::Acked);
+        }
+        if let Some(nack) = decode_frame_nack(&line).filter(|nack| nack.crc32 == crc) {
+            return Ok(AckOutcome::Rejected {
+                code: nack.code,
+                error: nack.error,
+            });
+        }
+    }
+    Ok(AckOutcome::TimedOut)
+}
+
+/// Ask the daemon on the far end to run its display self-test and report the outcome.
+fn run_display_test<T, E>(serial: &mut T, stderr: &mut E, timeout: Duration) -> Result<i32>
 where
     T: SerialShellTransport,
+    E: Write,
+{
+    serial.send_command_line(&encode_tunnel_msg(&TunnelMsgOwned::DisplayTest)?)?;
+    let deadline = Instant::now() + timeout;
+    let mut line = String::new();
+    while Instant::now() < deadline {
+        line.clear();
+        if serial.read_message_line(&mut line)? == 0 {
+            continue;
+        }
+        let trimmed = line.trim();
+        if !is_tunnel_line(trimmed) {
+            continue;
+        }
+        if let Ok(TunnelMsgOwned::DisplayTestResult {
+            ok,
+            elapsed_ms,
+            error,
+        }) = decode_tunnel_frame(trimmed)
+        {
+            if ok {
+                writeln!(stderr, "display test passed ({elapsed_ms}ms)")?;
+                return Ok(0);
+            }
+            let reason = error.unwrap_or_else(|| "unknown error".into());
+            writeln!(stderr, "display test failed: {reason}")?;
+            return Ok(1);
+        }
+    }
+    writeln!(
+        stderr,
+        "no display test result within {}s",
+        timeout.as_secs()
+    )?;
+    Ok(1)
+}
+
+/// How one command's output reaches the terminal.
+struct CommandOutput {
+    pager: Option<Pager>,
+    stdout: Reflow,
+    stderr: Reflow,
+}
+
+impl CommandOutput {
+    fn write<I, O, E>(
+        &mut self,
+        bytes: &[u8],
+        to_stderr: bool,
+        keys: &mut I,
+        stdout: &mut O,
+        stderr: &mut E,
+    ) -> Result<()>
+    where
+        I: BufRead,
+        O: Write,
+        E: Write,
+    {
+        match (self.pager.as_mut(), to_stderr) {
+            (Some(pager), false) => pager.write(bytes, stdout, keys, stderr),
+            (Some(pager), true) => {
+                // The prompt goes to stderr as well, so stderr output pages through a buffer.
+                let mut shown = Vec::new();
+                pager.write(bytes, &mut shown, keys, stderr)?;
+                write_chunk(&shown, stderr)
+            }
+            (None, false) => write_chunk(bytes, stdout),
+            (None, true) => write_chunk(bytes, stderr),
+        }
+    }
+
+    /// Print what the reflow still holds once the command has ended.
+    fn finish<I, O, E>(&mut self, keys: &mut I, stdout: &mut O, stderr: &mut E) -> Result<()>
+    where
+        I: BufRead,
+        O: Write,
+        E: Write,
+    {
+        let out = self.stdout.finish();
+        let err = self.stderr.finish();
+        self.write(&out, false, keys, stdout, stderr)?;
+        self.write(&err, true, keys, stdout, stderr)?;
+        if let Some(pager) = self.pager.as_mut() {
+            pager.reset();
+        }
+        Ok(())
+    }
+}
+
+fn wait_for_exit<T, I, O, E>(
+    serial: &mut T,
+    keys: &mut I,
+    stdout: &mut O,
+    stderr: &mut E,
+    output: &mut CommandOutput,
+) -> Result<i32>
+where
+    T: SerialShellTransport,
+    I: BufRead,
     O: Write,
     E: Write,
 {
@@ -104,13 +425,19 @@ where
         }
         match decode_tunnel_frame(trimmed)? {
             TunnelMsgOwned::Stdout { chunk } => {
-                write_chunk(&chunk, stdout)?;
+                let ready = output.stdout.push(&chunk);
+                output.write(&ready, false, keys, stdout, stderr)?;
             }
             TunnelMsgOwned::Stderr { chunk } => {
-                write_chunk(&chunk, stderr)?;
+                let ready = output.stderr.push(&chunk);
+                output.write(&ready, true, keys, stdout, stderr)?;
+            }
+            TunnelMsgOwned::Exit { code } => {
+                output.finish(keys, stdout, stderr)?;
+                return Ok(code);
             }
-            TunnelMsgOwned::Exit { code } => return Ok(code),
             TunnelMsgOwned::Busy => {
+                output.finish(keys, stdout, stderr)?;
                 writeln!(stderr, "remote busy")?;
                 return Ok(1);
             }
@@ -177,11 +504,170 @@ mod tests {
                 "INIT".to_string(),
                 encoded(TunnelMsgOwned::CmdRequest {
                     cmd: "echo hi".into(),
+                    otp: None,
+                    sig: None,
+                    cache_ttl_ms: None,
+                    no_cache: false,
                 }),
             ]
         );
     }
 
+    const FRAME: &str = r#"{"schema_version":1,"line1":"up","line2":"ok"}"#;
+
+    fn quick_acks() -> Option<FrameAckPolicy> {
+        Some(FrameAckPolicy {
+            timeout: Duration::from_millis(20),
+            retries: 1,
+        })
+    }
+
+    fn ack_for(frame: &str, seq: u64) -> String {
+        crate::payload::encode_frame_ack(&crate::payload::FrameAck {
+            crc32: frame_crc32(frame),
+            seq,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn payload_frames_wait_for_their_ack() {
+        let mut serial = FakeSerialPort::new(vec![
+            Ok(ack_for("something else", 1)),
+            Ok(ack_for(FRAME, 2)),
+        ]);
+        let mut input = Cursor::new(format!("{FRAME}\nexit\n"));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
+        let exit_code = drive_serial_shell_loop_with_acks(
+            &mut serial,
+            &mut input,
+            &mut stdout,
+            &mut stderr,
+            quick_acks(),
+        )
+        .expect("loop failed");
+
+        assert_eq!(exit_code, 0);
+        assert_eq!(serial.writes(), &["INIT".to_string(), FRAME.to_string()]);
+        assert!(!String::from_utf8_lossy(&stderr).contains("unacked"));
+    }
+
+    #[test]
+    fn rejected_payload_frames_report_the_code_without_resending() {
+        let nack = crate::payload::encode_frame_nack(&crate::payload::FrameNack {
+            crc32: frame_crc32(FRAME),
+            seq: 1,
+            code: "E_LINE_TOO_LONG".into(),
+            error: "parse error: line1 must be <= 40 chars [E_LINE_TOO_LONG]".into(),
+        })
+        .unwrap();
+        let mut serial = FakeSerialPort::new(vec![Ok(nack)]);
+        let mut input = Cursor::new(format!("{FRAME}\nexit\n"));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
+        let exit_code = drive_serial_shell_loop_with_acks(
+            &mut serial,
+            &mut input,
+            &mut stdout,
+            &mut stderr,
+            quick_acks(),
+        )
+        .expect("loop failed");
+
+        assert_eq!(exit_code, 1);
+        assert_eq!(serial.writes(), &["INIT".to_string(), FRAME.to_string()]);
+        assert!(String::from_utf8_lossy(&stderr).contains("rejected [E_LINE_TOO_LONG]"));
+    }
+
+    #[test]
+    fn unacked_payload_frames_are_resent_then_reported() {
+        let mut serial = FakeSerialPort::new(Vec::new());
+        let mut input = Cursor::new(format!("{FRAME}\nexit\n"));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
+        let exit_code = drive_serial_shell_loop_with_acks(
+            &mut serial,
+            &mut input,
+            &mut stdout,
+            &mut stderr,
+            quick_acks(),
+        )
+        .expect("loop failed");
+
+        assert_eq!(exit_code, 1);
+        assert_eq!(
+            serial.writes(),
+            &["INIT".to_string(), FRAME.to_string(), FRAME.to_string()]
+        );
+        let err_text = String::from_utf8_lossy(&stderr);
+        assert!(err_text.contains("retry 1/1"));
+        assert!(err_text.contains("not acknowledged after 2 attempts"));
+    }
+
+    #[test]
+    fn display_test_reports_the_remote_result() {
+        let mut serial = FakeSerialPort::new(vec![
+            Ok(encoded(TunnelMsgOwned::Heartbeat)),
+            Ok(encoded(TunnelMsgOwned::DisplayTestResult {
+                ok: false,
+                elapsed_ms: 12,
+                error: Some("lcd write failed".into()),
+            })),
+        ]);
+        let mut input = Cursor::new("display-test\nexit\n");
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
+        let exit_code =
+            drive_serial_shell_loop(&mut serial, &mut input, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(exit_code, 1);
+        assert_eq!(serial.writes()[1], encoded(TunnelMsgOwned::DisplayTest));
+        assert!(String::from_utf8_lossy(&stderr).contains("display test failed: lcd write failed"));
+    }
+
+    #[test]
+    fn session_sends_the_terminal_size_and_pages_long_output() {
+        let long: String = (1..=6).map(|n| format!("row {n}\n")).collect();
+        let (head, tail) = long.as_bytes().split_at(13);
+        let mut serial = FakeSerialPort::new(vec![
+            Ok(encoded(TunnelMsgOwned::Stdout {
+                chunk: head.to_vec(),
+            })),
+            Ok(encoded(TunnelMsgOwned::Stdout {
+                chunk: tail.to_vec(),
+            })),
+            Ok(encoded(TunnelMsgOwned::Exit { code: 0 })),
+        ]);
+        // `q` at the first --More-- drops rows 4-6; the shell then reads `exit`.
+        let mut input = Cursor::new("seq 6\nqexit\n");
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
+        let exit_code = drive_serial_shell_session(
+            &mut serial,
+            &mut input,
+            &mut stdout,
+            &mut stderr,
+            None,
+            Some(ShellTerminal::new(40, 4)),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(exit_code, 0);
+        assert_eq!(
+            serial.writes()[1],
+            encoded(TunnelMsgOwned::TermSize { cols: 40, rows: 4 })
+        );
+        assert_eq!(String::from_utf8_lossy(&stdout), "row 1\nrow 2\nrow 3\n");
+        assert!(String::from_utf8_lossy(&stderr).contains("--More--"));
+    }
+
     #[test]
     fn busy_response_returns_one() {
         let mut serial = FakeSerialPort::new(vec![Ok(encoded(TunnelMsgOwned::Busy))]);
@@ -224,7 +710,13 @@ mod tests {
             serial.writes(),
             &[
                 "INIT".to_string(),
-                encoded(TunnelMsgOwned::CmdRequest { cmd: "ping".into() }),
+                encoded(TunnelMsgOwned::CmdRequest {
+                    cmd: "ping".into(),
+                    otp: None,
+                    sig: None,
+                    cache_ttl_ms: None,
+                    no_cache: false,
+                }),
             ]
         );
     }
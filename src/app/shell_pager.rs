@@ -0,0 +1,444 @@
+//! Output handling for `serialsh` on a real terminal.
+//!
+//! Tunnel chunks end wherever the far end's 512-byte reads ended, which can be halfway through
+//! a UTF-8 character or an ANSI escape sequence. [`Reflow`] holds such a tail back until the
+//! next chunk completes it. [`Pager`] counts the rows the output takes on the host terminal,
+//! wrapping at its width, and stops with `--More--` once a screenful has scrolled past: space
+//! shows the next page, Enter one more line and `q` drops the rest of the command's output.
+
+use std::io::{self, BufRead, Write};
+
+use rustix::termios::{
+    isatty, tcgetattr, tcgetwinsize, tcsetattr, LocalModes, OptionalActions, SpecialCodeIndex,
+    Termios,
+};
+
+use crate::Result;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+/// Longest tail held back for a later chunk; anything longer is not a sequence worth saving.
+const MAX_HELD_BYTES: usize = 256;
+const TAB_WIDTH: usize = 8;
+/// Below this many rows there is no room for a page plus the prompt.
+const MIN_PAGING_ROWS: u16 = 3;
+const MORE_PROMPT: &[u8] = b"--More-- (space: page, enter: line, q: quit)";
+
+/// Host terminal the shell prints to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShellTerminal {
+    cols: u16,
+    rows: u16,
+    /// Pause for keys between pages; only when the keys come from a terminal too.
+    paging: bool,
+    /// Switch stdin out of line mode while waiting for a key.
+    raw_keys: bool,
+}
+
+impl ShellTerminal {
+    /// Size of the terminal on stdout, or `None` when stdout is not a terminal. The kernel's
+    /// window size wins over `COLUMNS`/`LINES`.
+    pub fn detect() -> Option<Self> {
+        let stdout = io::stdout();
+        if !isatty(&stdout) {
+            return None;
+        }
+        let (cols, rows) = match tcgetwinsize(&stdout) {
+            Ok(size) if size.ws_col > 0 && size.ws_row > 0 => (size.ws_col, size.ws_row),
+            _ => (env_dimension("COLUMNS")?, env_dimension("LINES")?),
+        };
+        let keys_from_tty = isatty(io::stdin());
+        Some(Self {
+            cols,
+            rows,
+            paging: keys_from_tty,
+            raw_keys: keys_from_tty,
+        })
+    }
+
+    /// A terminal that pages and reads keys from the shell's input as they come.
+    #[cfg(test)]
+    pub(crate) fn new(cols: u16, rows: u16) -> Self {
+        Self {
+            cols,
+            rows,
+            paging: true,
+            raw_keys: false,
+        }
+    }
+
+    pub fn cols(&self) -> u16 {
+        self.cols
+    }
+
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+}
+
+fn env_dimension(name: &str) -> Option<u16> {
+    std::env::var(name)
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .filter(|value| *value > 0)
+}
+
+/// Re-joins UTF-8 characters and escape sequences that a chunk boundary cut in two.
+#[derive(Debug, Default)]
+pub struct Reflow {
+    held: Vec<u8>,
+}
+
+impl Reflow {
+    /// Bytes of `chunk`, plus whatever was held before, that are safe to print now.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let mut bytes = std::mem::take(&mut self.held);
+        bytes.extend_from_slice(chunk);
+        let keep = incomplete_tail(&bytes);
+        if keep > 0 && keep <= MAX_HELD_BYTES {
+            self.held = bytes.split_off(bytes.len() - keep);
+        }
+        bytes
+    }
+
+    /// Whatever is still held once the stream has ended.
+    pub fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.held)
+    }
+}
+
+/// Length of the unfinished escape sequence or UTF-8 character at the end of `bytes`.
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    let escape = bytes
+        .iter()
+        .rposition(|&b| b == ESC)
+        .filter(|&start| sequence_len(&bytes[start..]).is_none())
+        .map(|start| bytes.len() - start);
+    escape.unwrap_or_else(|| incomplete_utf8(bytes))
+}
+
+/// Length of the escape sequence at the start of `bytes`, or `None` while it is unfinished.
+fn sequence_len(bytes: &[u8]) -> Option<usize> {
+    match bytes.get(1)? {
+        // CSI: parameter and intermediate bytes, then one final byte.
+        b'[' => bytes[2..]
+            .iter()
+            .position(|b| !(0x20..=0x3f).contains(b))
+            .map(|end| end + 3),
+        // OSC and the other string sequences end with BEL or ESC \.
+        b']' | b'P' | b'X' | b'^' | b'_' => bytes[2..]
+            .iter()
+            .position(|&b| b == BEL || b == ESC)
+            .and_then(|end| match bytes[2 + end] {
+                BEL => Some(end + 3),
+                _ => bytes.get(3 + end).map(|_| end + 4),
+            }),
+        _ => Some(2),
+    }
+}
+
+fn incomplete_utf8(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xc0 == 0x80 {
+            continue;
+        }
+        let needed = utf8_len(byte);
+        return if needed > back { back } else { 0 };
+    }
+    0
+}
+
+fn utf8_len(lead: u8) -> usize {
+    match lead {
+        0xf0..=0xf7 => 4,
+        0xe0..=0xef => 3,
+        0xc0..=0xdf => 2,
+        _ => 1,
+    }
+}
+
+/// What the reader asked for at the `--More--` prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PagerKey {
+    Page,
+    Line,
+    Quit,
+}
+
+impl PagerKey {
+    fn from_byte(byte: u8) -> Self {
+        match byte {
+            b'q' | b'Q' => Self::Quit,
+            b'\r' | b'\n' => Self::Line,
+            _ => Self::Page,
+        }
+    }
+}
+
+/// Counts terminal rows across one command's output and pauses between screenfuls.
+#[derive(Debug)]
+pub struct Pager {
+    cols: usize,
+    page_rows: usize,
+    raw_keys: bool,
+    rows_shown: usize,
+    col: usize,
+    /// Set by `q`: the rest of the command's output is dropped.
+    quit: bool,
+    /// Set when the key input ran out; the output then flows without stopping.
+    keys_gone: bool,
+}
+
+impl Pager {
+    /// `None` when the terminal is too small to page or keys cannot be read from it.
+    pub fn for_terminal(terminal: &ShellTerminal) -> Option<Self> {
+        if !terminal.paging || terminal.rows < MIN_PAGING_ROWS || terminal.cols == 0 {
+            return None;
+        }
+        Some(Self {
+            cols: usize::from(terminal.cols),
+            // The last row holds the prompt.
+            page_rows: usize::from(terminal.rows) - 1,
+            raw_keys: terminal.raw_keys,
+            rows_shown: 0,
+            col: 0,
+            quit: false,
+            keys_gone: false,
+        })
+    }
+
+    /// Start counting afresh for the next command.
+    pub fn reset(&mut self) {
+        self.rows_shown = 0;
+        self.col = 0;
+        self.quit = false;
+    }
+
+    /// Print `bytes` to `out`, stopping at each full page to ask `keys` how to go on.
+    pub fn write<O, I, P>(
+        &mut self,
+        bytes: &[u8],
+        out: &mut O,
+        keys: &mut I,
+        prompt: &mut P,
+    ) -> Result<()>
+    where
+        O: Write,
+        I: BufRead,
+        P: Write,
+    {
+        let mut start = 0;
+        let mut idx = 0;
+        while idx < bytes.len() && !self.quit {
+            let byte = bytes[idx];
+            let len = match byte {
+                ESC => sequence_len(&bytes[idx..]).unwrap_or(bytes.len() - idx),
+                _ => utf8_len(byte).min(bytes.len() - idx),
+            };
+            match byte {
+                b'\n' => {
+                    self.col = 0;
+                    self.rows_shown += 1;
+                    if self.page_full() {
+                        out.write_all(&bytes[start..=idx])?;
+                        start = idx + 1;
+                        self.pause(out, keys, prompt)?;
+                    }
+                }
+                b'\r' => self.col = 0,
+                b'\t' => self.col = (self.col / TAB_WIDTH + 1) * TAB_WIDTH,
+                0x08 => self.col = self.col.saturating_sub(1),
+                ESC => {}
+                _ if byte < 0x20 || byte == 0x7f => {}
+                _ => {
+                    if self.col >= self.cols {
+                        // This character starts a wrapped row.
+                        self.col = 0;
+                        self.rows_shown += 1;
+                        if self.page_full() {
+                            out.write_all(&bytes[start..idx])?;
+                            start = idx;
+                            self.pause(out, keys, prompt)?;
+                        }
+                    }
+                    self.col += 1;
+                }
+            }
+            idx += len;
+        }
+        if !self.quit {
+            out.write_all(&bytes[start..])?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    fn page_full(&self) -> bool {
+        !self.keys_gone && self.rows_shown >= self.page_rows
+    }
+
+    fn pause<O, I, P>(&mut self, out: &mut O, keys: &mut I, prompt: &mut P) -> Result<()>
+    where
+        O: Write,
+        I: BufRead,
+        P: Write,
+    {
+        out.flush()?;
+        prompt.write_all(MORE_PROMPT)?;
+        prompt.flush()?;
+        let key = {
+            let _raw = self.raw_keys.then(RawKeys::enter).flatten();
+            read_key(keys)?
+        };
+        prompt.write_all(b"\r\x1b[K")?;
+        prompt.flush()?;
+        match key {
+            Some(PagerKey::Page) => self.rows_shown = 0,
+            Some(PagerKey::Line) => self.rows_shown = self.page_rows - 1,
+            Some(PagerKey::Quit) => self.quit = true,
+            None => self.keys_gone = true,
+        }
+        Ok(())
+    }
+}
+
+fn read_key<I: BufRead>(keys: &mut I) -> Result<Option<PagerKey>> {
+    let buf = keys.fill_buf()?;
+    let Some(&byte) = buf.first() else {
+        return Ok(None);
+    };
+    keys.consume(1);
+    Ok(Some(PagerKey::from_byte(byte)))
+}
+
+/// Puts stdin into single-key mode without echo until dropped.
+struct RawKeys {
+    saved: Termios,
+}
+
+impl RawKeys {
+    fn enter() -> Option<Self> {
+        let stdin = io::stdin();
+        let saved = tcgetattr(&stdin).ok()?;
+        let mut raw = saved.clone();
+        raw.local_modes
+            .remove(LocalModes::ICANON | LocalModes::ECHO);
+        raw.special_codes[SpecialCodeIndex::VMIN] = 1;
+        raw.special_codes[SpecialCodeIndex::VTIME] = 0;
+        tcsetattr(&stdin, OptionalActions::Now, &raw).ok()?;
+        Some(Self { saved })
+    }
+}
+
+impl Drop for RawKeys {
+    fn drop(&mut self) {
+        let _ = tcsetattr(io::stdin(), OptionalActions::Now, &self.saved);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn reflow_holds_split_escapes_and_characters() {
+        let mut reflow = Reflow::default();
+        assert_eq!(reflow.push(b"ok \x1b[3"), b"ok ");
+        assert_eq!(
+            reflow.push(b"1mred\x1b[0m caf\xc3"),
+            b"\x1b[31mred\x1b[0m caf"
+        );
+        assert_eq!(reflow.push(b"\xa9\n"), "é\n".as_bytes());
+        assert_eq!(reflow.push(b"title \x1b]0;host"), b"title ");
+        assert_eq!(reflow.push(b"\x07done"), b"\x1b]0;host\x07done");
+        assert_eq!(reflow.push(b"tail\x1b"), b"tail");
+        assert_eq!(reflow.finish(), b"\x1b");
+        assert!(reflow.finish().is_empty());
+    }
+
+    #[test]
+    fn reflow_gives_up_on_runaway_sequences() {
+        let mut reflow = Reflow::default();
+        let mut chunk = b"\x1b]".to_vec();
+        chunk.extend(std::iter::repeat_n(b'x', MAX_HELD_BYTES + 10));
+        assert_eq!(reflow.push(&chunk), chunk);
+    }
+
+    fn numbered(lines: usize) -> Vec<u8> {
+        (1..=lines)
+            .map(|n| format!("line {n}\n"))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    #[test]
+    fn pager_stops_each_screenful_and_follows_keys() {
+        let mut pager = Pager::for_terminal(&ShellTerminal::new(40, 4)).unwrap();
+        let mut out = Vec::new();
+        let mut prompt = Vec::new();
+        // Space: a full page (3 rows), Enter: one more row, q: drop the rest.
+        let mut keys = Cursor::new(" \nq");
+        pager
+            .write(&numbered(10), &mut out, &mut keys, &mut prompt)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "line 1\nline 2\nline 3\nline 4\nline 5\nline 6\nline 7\n"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&prompt).matches("--More--").count(),
+            3
+        );
+
+        // Quitting only lasts until the next command.
+        pager
+            .write(b"ignored\n", &mut Vec::new(), &mut keys, &mut prompt)
+            .unwrap();
+        pager.reset();
+        let mut out = Vec::new();
+        pager
+            .write(b"next\n", &mut out, &mut keys, &mut prompt)
+            .unwrap();
+        assert_eq!(out, b"next\n");
+    }
+
+    #[test]
+    fn pager_counts_wrapped_rows_but_not_escape_sequences() {
+        let mut pager = Pager::for_terminal(&ShellTerminal::new(10, 3)).unwrap();
+        let mut out = Vec::new();
+        let mut prompt = Vec::new();
+        let mut keys = Cursor::new("q");
+        let colored = format!("\x1b[1;32m{}\x1b[0m", "a".repeat(10));
+        let text = format!("{colored}{}", "b".repeat(15));
+        pager
+            .write(text.as_bytes(), &mut out, &mut keys, &mut prompt)
+            .unwrap();
+        // Ten coloured cells fill row one, ten b's row two; the pause lands before row three.
+        assert_eq!(out, format!("{colored}{}", "b".repeat(10)).into_bytes());
+        assert!(String::from_utf8_lossy(&prompt).contains("--More--"));
+    }
+
+    #[test]
+    fn pager_runs_on_when_keys_run_out() {
+        let mut pager = Pager::for_terminal(&ShellTerminal::new(40, 3)).unwrap();
+        let mut out = Vec::new();
+        let mut prompt = Vec::new();
+        pager
+            .write(&numbered(6), &mut out, &mut Cursor::new(""), &mut prompt)
+            .unwrap();
+        assert_eq!(out, numbered(6));
+        assert_eq!(
+            String::from_utf8_lossy(&prompt).matches("--More--").count(),
+            1
+        );
+    }
+
+    #[test]
+    fn tiny_terminals_do_not_page() {
+        assert!(Pager::for_terminal(&ShellTerminal::new(40, 2)).is_none());
+    }
+}
@@ -0,0 +1,513 @@
+use crate::{cache_dir, config::SloConfig};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    fs::{self, OpenOptions},
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const SLO_EVENT_LOG_MAX_BYTES: u64 = 256 * 1024;
+/// Events waiting for the webhook; past this, new ones are dropped rather than queued.
+const WEBHOOK_QUEUE_LEN: usize = 16;
+/// Connect, write and read each give up after this long, so a dead endpoint cannot pile up.
+const WEBHOOK_TIMEOUT_MS: u64 = 2_000;
+
+/// Error classes tracked against a budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SloKind {
+    ParseFailure,
+    Reconnect,
+    CrcError,
+}
+
+impl SloKind {
+    const ALL: [SloKind; 3] = [SloKind::ParseFailure, SloKind::Reconnect, SloKind::CrcError];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SloKind::ParseFailure => "parse",
+            SloKind::Reconnect => "reconnect",
+            SloKind::CrcError => "crc",
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            SloKind::ParseFailure => 0,
+            SloKind::Reconnect => 1,
+            SloKind::CrcError => 2,
+        }
+    }
+}
+
+/// Budget state change reported by [`SloTracker::evaluate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SloTransition {
+    Breached {
+        kind: SloKind,
+        count: usize,
+        budget: u32,
+    },
+    Resolved {
+        kind: SloKind,
+        count: usize,
+        budget: u32,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct ErrorBudget {
+    budget: u32,
+    events: VecDeque<Instant>,
+    breached: bool,
+}
+
+impl ErrorBudget {
+    fn new(budget: u32) -> Self {
+        Self {
+            budget,
+            events: VecDeque::new(),
+            breached: false,
+        }
+    }
+
+    fn prune(&mut self, now: Instant, window: Duration) {
+        while self
+            .events
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > window)
+        {
+            self.events.pop_front();
+        }
+    }
+}
+
+/// Rolling-window error budgets; a budget of 0 disables tracking for that class.
+#[derive(Debug, Clone)]
+pub struct SloTracker {
+    window: Duration,
+    budgets: [ErrorBudget; 3],
+}
+
+impl SloTracker {
+    pub fn new(config: &SloConfig) -> Self {
+        Self {
+            window: Duration::from_millis(config.window_ms),
+            budgets: [
+                ErrorBudget::new(config.parse_budget),
+                ErrorBudget::new(config.reconnect_budget),
+                ErrorBudget::new(config.crc_budget),
+            ],
+        }
+    }
+
+    pub fn record(&mut self, kind: SloKind, now: Instant) {
+        let budget = &mut self.budgets[kind.index()];
+        if budget.budget == 0 {
+            return;
+        }
+        budget.events.push_back(now);
+        // Never hold more than one entry past the point of breach.
+        while budget.events.len() > budget.budget as usize + 1 {
+            budget.events.pop_front();
+        }
+    }
+
+    /// Expire old events and report budgets that were just exhausted or recovered.
+    pub fn evaluate(&mut self, now: Instant) -> Vec<SloTransition> {
+        let mut transitions = Vec::new();
+        for kind in SloKind::ALL {
+            let budget = &mut self.budgets[kind.index()];
+            if budget.budget == 0 {
+                continue;
+            }
+            budget.prune(now, self.window);
+            let count = budget.events.len();
+            let over = count > budget.budget as usize;
+            if over && !budget.breached {
+                budget.breached = true;
+                transitions.push(SloTransition::Breached {
+                    kind,
+                    count,
+                    budget: budget.budget,
+                });
+            } else if !over && budget.breached && count <= budget.budget as usize / 2 {
+                // Resolve at half the budget so a rate hovering at the limit does not flap.
+                budget.breached = false;
+                transitions.push(SloTransition::Resolved {
+                    kind,
+                    count,
+                    budget: budget.budget,
+                });
+            }
+        }
+        transitions
+    }
+
+    /// Error classes currently over budget.
+    pub fn active_incidents(&self) -> Vec<SloKind> {
+        SloKind::ALL
+            .into_iter()
+            .filter(|kind| self.budgets[kind.index()].breached)
+            .collect()
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+}
+
+/// Where incident events go: an append-only JSON log under the cache dir, and an HTTP POST to
+/// `[slo].webhook_url` when one is set.
+pub struct SloEventLog {
+    path: PathBuf,
+    webhook: Option<SloWebhook>,
+}
+
+impl SloEventLog {
+    pub fn new(config: &SloConfig) -> Self {
+        Self {
+            path: cache_dir().join("slo_events.log"),
+            webhook: SloWebhook::start(&config.webhook_url),
+        }
+    }
+
+    /// Log `transition` and queue it for the webhook. Only the log write can fail here; webhook
+    /// failures are reported later by [`SloEventLog::take_webhook_error`].
+    pub fn record(&self, transition: &SloTransition, window: Duration) -> io::Result<()> {
+        let line = event_json(transition, window)?;
+        if let Some(webhook) = &self.webhook {
+            webhook.send(line.clone());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if let Ok(meta) = fs::metadata(&self.path) {
+            if meta.len() >= SLO_EVENT_LOG_MAX_BYTES {
+                let _ = fs::remove_file(&self.path);
+            }
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{line}")
+    }
+
+    /// The most recent webhook delivery failure since the last call, if any.
+    pub fn take_webhook_error(&self) -> Option<String> {
+        self.webhook.as_ref()?.take_error()
+    }
+}
+
+/// One `incident_open` / `incident_resolved` event as a JSON object.
+fn event_json(transition: &SloTransition, window: Duration) -> io::Result<String> {
+    #[derive(Serialize)]
+    struct SloEntry {
+        ts_ms: u128,
+        event: &'static str,
+        kind: &'static str,
+        count: usize,
+        budget: u32,
+        window_ms: u128,
+    }
+
+    let (event, kind, count, budget) = match *transition {
+        SloTransition::Breached {
+            kind,
+            count,
+            budget,
+        } => ("incident_open", kind, count, budget),
+        SloTransition::Resolved {
+            kind,
+            count,
+            budget,
+        } => ("incident_resolved", kind, count, budget),
+    };
+    let entry = SloEntry {
+        ts_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis(),
+        event,
+        kind: kind.as_str(),
+        count,
+        budget,
+        window_ms: window.as_millis(),
+    };
+    serde_json::to_string(&entry).map_err(io::Error::other)
+}
+
+/// Split an `http://host[:port][/path]` webhook URL into the address to connect to, the `Host`
+/// header and the request path. Only plain HTTP is spoken; `None` for anything else.
+pub fn parse_webhook_url(url: &str) -> Option<(String, String, String)> {
+    let rest = url.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], rest[idx..].to_string()),
+        None => (rest, "/".to_string()),
+    };
+    if authority.is_empty() || authority.contains('@') || path.contains(char::is_whitespace) {
+        return None;
+    }
+    let has_port = match authority.rfind(':') {
+        Some(idx) => !authority.ends_with(']') && authority[idx + 1..].parse::<u16>().is_ok(),
+        None => false,
+    };
+    let addr = if has_port {
+        authority.to_string()
+    } else if authority.contains(':') && !authority.starts_with('[') {
+        return None;
+    } else {
+        format!("{authority}:80")
+    };
+    Some((addr, authority.to_string(), path))
+}
+
+/// Background sender that POSTs each incident event to `[slo].webhook_url`. Delivery happens on
+/// its own thread so a slow or unreachable endpoint never stalls the render loop; events that
+/// cannot be delivered are dropped, since the event log still has them.
+struct SloWebhook {
+    events: SyncSender<String>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl SloWebhook {
+    fn start(url: &str) -> Option<Self> {
+        let (addr, host, path) = parse_webhook_url(url)?;
+        let (events, queue) = mpsc::sync_channel(WEBHOOK_QUEUE_LEN);
+        let last_error = Arc::new(Mutex::new(None));
+        let errors = last_error.clone();
+        thread::Builder::new()
+            .name("lifelinetty-slo-webhook".into())
+            .spawn(move || deliver_events(queue, &addr, &host, &path, &errors))
+            .ok()?;
+        Some(Self { events, last_error })
+    }
+
+    fn send(&self, body: String) {
+        if self.events.try_send(body).is_err() {
+            self.set_error("webhook queue full; event dropped".into());
+        }
+    }
+
+    fn set_error(&self, err: String) {
+        if let Ok(mut last) = self.last_error.lock() {
+            *last = Some(err);
+        }
+    }
+
+    fn take_error(&self) -> Option<String> {
+        self.last_error.lock().ok()?.take()
+    }
+}
+
+/// Runs until the [`SloWebhook`] is dropped, which closes the queue.
+fn deliver_events(
+    queue: Receiver<String>,
+    addr: &str,
+    host: &str,
+    path: &str,
+    errors: &Mutex<Option<String>>,
+) {
+    for body in queue {
+        if let Err(err) = post_json(addr, host, path, &body) {
+            if let Ok(mut last) = errors.lock() {
+                *last = Some(format!("POST http://{host}{path}: {err}"));
+            }
+        }
+    }
+}
+
+fn post_json(addr: &str, host: &str, path: &str, body: &str) -> io::Result<()> {
+    let timeout = Duration::from_millis(WEBHOOK_TIMEOUT_MS);
+    let target = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::other(format!("{addr} did not resolve")))?;
+    let mut stream = TcpStream::connect_timeout(&target, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+    // Only the status line matters; a 2xx from the receiver means the event was accepted.
+    let mut head = [0u8; 32];
+    let read = stream.read(&mut head)?;
+    let status_line = String::from_utf8_lossy(&head[..read]);
+    let status = status_line.split_whitespace().nth(1).unwrap_or("");
+    if status.starts_with('2') && status.len() == 3 {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "receiver answered '{}'",
+            status_line.lines().next().unwrap_or("").trim()
+        )))
+    }
+}
+
+/// Two-line incident page, e.g. `INCIDENT parse` / `budget 10/60s`.
+pub fn incident_lines(incidents: &[SloKind], tracker: &SloTracker) -> (String, String) {
+    let kinds: Vec<&str> = incidents.iter().map(|kind| kind.as_str()).collect();
+    let first = incidents
+        .first()
+        .map(|kind| tracker.budgets[kind.index()].budget)
+        .unwrap_or_default();
+    (
+        format!("INCIDENT {}", kinds.join(",")),
+        format!("budget {first}/{}s", tracker.window.as_secs()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(parse_budget: u32) -> SloConfig {
+        SloConfig {
+            window_ms: 10_000,
+            parse_budget,
+            reconnect_budget: 0,
+            crc_budget: 2,
+            webhook_url: String::new(),
+        }
+    }
+
+    #[test]
+    fn breach_then_resolve_after_window() {
+        let mut tracker = SloTracker::new(&config(3));
+        let start = Instant::now();
+        for _ in 0..3 {
+            tracker.record(SloKind::ParseFailure, start);
+        }
+        assert!(tracker.evaluate(start).is_empty());
+        tracker.record(SloKind::ParseFailure, start);
+        assert_eq!(
+            tracker.evaluate(start),
+            vec![SloTransition::Breached {
+                kind: SloKind::ParseFailure,
+                count: 4,
+                budget: 3
+            }]
+        );
+        assert_eq!(tracker.active_incidents(), vec![SloKind::ParseFailure]);
+        assert!(tracker.evaluate(start + Duration::from_secs(1)).is_empty());
+
+        let later = start + Duration::from_secs(11);
+        assert!(matches!(
+            tracker.evaluate(later).as_slice(),
+            [SloTransition::Resolved {
+                kind: SloKind::ParseFailure,
+                ..
+            }]
+        ));
+        assert!(tracker.active_incidents().is_empty());
+    }
+
+    #[test]
+    fn zero_budget_disables_tracking() {
+        let mut tracker = SloTracker::new(&config(0));
+        let now = Instant::now();
+        for _ in 0..50 {
+            tracker.record(SloKind::ParseFailure, now);
+            tracker.record(SloKind::Reconnect, now);
+        }
+        assert!(tracker.evaluate(now).is_empty());
+    }
+
+    #[test]
+    fn incident_page_lists_active_kinds() {
+        let mut tracker = SloTracker::new(&config(1));
+        let now = Instant::now();
+        for _ in 0..3 {
+            tracker.record(SloKind::ParseFailure, now);
+            tracker.record(SloKind::CrcError, now);
+        }
+        tracker.evaluate(now);
+        let (top, bottom) = incident_lines(&tracker.active_incidents(), &tracker);
+        assert_eq!(top, "INCIDENT parse,crc");
+        assert_eq!(bottom, "budget 1/10s");
+    }
+
+    #[test]
+    fn webhook_urls_must_be_plain_http() {
+        assert_eq!(
+            parse_webhook_url("http://10.0.0.5:8080/hooks/slo"),
+            Some((
+                "10.0.0.5:8080".into(),
+                "10.0.0.5:8080".into(),
+                "/hooks/slo".into()
+            ))
+        );
+        assert_eq!(
+            parse_webhook_url("http://alerts.local"),
+            Some(("alerts.local:80".into(), "alerts.local".into(), "/".into()))
+        );
+        assert!(parse_webhook_url("https://alerts.local/hook").is_none());
+        assert!(parse_webhook_url("http://user:pw@alerts.local/").is_none());
+        assert!(parse_webhook_url("http:///hook").is_none());
+        assert!(parse_webhook_url("").is_none());
+    }
+
+    #[test]
+    fn webhook_posts_incident_events() {
+        use std::{io::BufRead, net::TcpListener};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/incidents", listener.local_addr().unwrap());
+        let webhook = SloWebhook::start(&url).unwrap();
+        let transition = SloTransition::Breached {
+            kind: SloKind::CrcError,
+            count: 3,
+            budget: 2,
+        };
+        webhook.send(event_json(&transition, Duration::from_secs(10)).unwrap());
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = io::BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        assert_eq!(request_line, "POST /incidents HTTP/1.1\r\n");
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header == "\r\n" {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length: ") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["event"], "incident_open");
+        assert_eq!(body["kind"], "crc");
+        assert_eq!(body["budget"], 2);
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+            .unwrap();
+        drop(reader);
+
+        // A receiver that is gone is reported once the send fails.
+        drop(listener);
+        webhook.send(event_json(&transition, Duration::from_secs(10)).unwrap());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while webhook.take_error().is_none() {
+            assert!(Instant::now() < deadline, "no delivery error reported");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
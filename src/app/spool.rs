@@ -0,0 +1,202 @@
+//! Directory spool for local producers.
+//!
+//! Files named with a leading number and a `.json` extension (`0001.json`, `42-cpu.json`) are
+//! ingested in numeric order into `RenderState`, exactly like frames read from serial, and
+//! deleted once accepted. Producers should write to a dot-file or `*.tmp` first and rename it
+//! into place; those names are never picked up. Files that fail to parse are renamed to
+//! `*.rejected` so they are not retried on every scan.
+
+use super::Logger;
+use crate::{config::SpoolConfig, payload::Severity, state::RenderState};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Upper bound on files ingested per scan so a flooded spool cannot stall the render loop.
+const SPOOL_MAX_PER_SCAN: usize = 16;
+const REJECTED_SUFFIX: &str = "rejected";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpoolScan {
+    pub accepted: usize,
+    pub duplicates: usize,
+    pub rejected: usize,
+    /// Accepted pages that carried warn or crit severity.
+    pub alerts: usize,
+}
+
+pub struct Spool {
+    dir: PathBuf,
+    interval: Duration,
+    next_scan: Instant,
+    dir_error_logged: bool,
+}
+
+impl Spool {
+    /// `None` when the spool is disabled in config.
+    pub fn from_config(config: &SpoolConfig, now: Instant) -> Option<Self> {
+        config.enabled.then(|| Self {
+            dir: PathBuf::from(&config.dir),
+            interval: Duration::from_millis(config.poll_ms),
+            next_scan: now,
+            dir_error_logged: false,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Ingest any waiting files when a scan is due.
+    pub fn poll(&mut self, now: Instant, state: &mut RenderState, logger: &Logger) -> SpoolScan {
+        if now < self.next_scan {
+            return SpoolScan::default();
+        }
+        self.next_scan = now + self.interval;
+        let files = match pending_files(&self.dir) {
+            Ok(files) => {
+                self.dir_error_logged = false;
+                files
+            }
+            Err(err) => {
+                if !self.dir_error_logged {
+                    logger.warn(format!("spool: cannot read {}: {err}", self.dir.display()));
+                    self.dir_error_logged = true;
+                }
+                return SpoolScan::default();
+            }
+        };
+
+        let mut scan = SpoolScan::default();
+        for path in files.into_iter().take(SPOOL_MAX_PER_SCAN) {
+            let name = path.display();
+            let raw = match fs::read_to_string(&path) {
+                Ok(raw) => raw,
+                Err(err) => {
+                    logger.warn(format!("spool: read {name} failed: {err}"));
+                    continue;
+                }
+            };
+            match state.ingest(raw.trim()) {
+                Ok(result) => {
+                    if let Some(frame) = result {
+                        scan.accepted += 1;
+                        if frame.severity >= Some(Severity::Warn) {
+                            scan.alerts += 1;
+                        }
+                        logger.debug(format!("spool: queued {name}"));
+                    } else {
+                        scan.duplicates += 1;
+                        logger.debug(format!("spool: {name} repeats the current page"));
+                    }
+                    if let Err(err) = fs::remove_file(&path) {
+                        logger.warn(format!("spool: remove {name} failed: {err}"));
+                    }
+                }
+                Err(err) => {
+                    scan.rejected += 1;
+                    logger.warn(format!("spool: {name} rejected: {err}"));
+                    let parked = path.with_extension(REJECTED_SUFFIX);
+                    if let Err(err) = fs::rename(&path, &parked) {
+                        logger.warn(format!("spool: park {name} failed: {err}"));
+                    }
+                }
+            }
+        }
+        scan
+    }
+}
+
+/// Sort key for a spool file name, or `None` when the file should be left alone.
+fn spool_order(name: &str) -> Option<u64> {
+    if name.starts_with('.') {
+        return None;
+    }
+    let stem = name.strip_suffix(".json")?;
+    let digits: String = stem.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+fn pending_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut files: Vec<(u64, String, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            spool_order(&name).map(|order| (order, name, entry.path()))
+        })
+        .collect();
+    files.sort();
+    Ok(files.into_iter().map(|(_, _, path)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::logger::LogLevel;
+
+    fn spool_in(dir: &Path) -> Spool {
+        Spool::from_config(
+            &SpoolConfig {
+                enabled: true,
+                dir: dir.display().to_string(),
+                poll_ms: 100,
+            },
+            Instant::now(),
+        )
+        .unwrap()
+    }
+
+    fn page(line1: &str) -> String {
+        format!(r#"{{"schema_version":1,"line1":"{line1}","line2":""}}"#)
+    }
+
+    #[test]
+    fn only_numbered_json_files_are_picked_up() {
+        assert_eq!(spool_order("0001.json"), Some(1));
+        assert_eq!(spool_order("42-cpu.json"), Some(42));
+        assert_eq!(spool_order("cpu.json"), None);
+        assert_eq!(spool_order(".0003.json"), None);
+        assert_eq!(spool_order("0003.json.tmp"), None);
+        assert_eq!(spool_order("0004.rejected"), None);
+    }
+
+    #[test]
+    fn files_are_ingested_in_numeric_order_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("10.json"), page("TEN")).unwrap();
+        fs::write(dir.path().join("2.json"), page("TWO")).unwrap();
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let mut state = RenderState::new(None);
+        let mut spool = spool_in(dir.path());
+
+        let scan = spool.poll(Instant::now(), &mut state, &logger);
+        assert_eq!(scan.accepted, 2);
+        assert_eq!(state.next_page().unwrap().line1, "TWO");
+        assert_eq!(state.next_page().unwrap().line1, "TEN");
+        assert!(!dir.path().join("2.json").exists());
+        assert!(dir.path().join("notes.txt").exists());
+    }
+
+    #[test]
+    fn bad_payloads_are_parked_and_scans_are_paced() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("1.json"), "{not json").unwrap();
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let mut state = RenderState::new(None);
+        let mut spool = spool_in(dir.path());
+        let now = Instant::now();
+
+        assert_eq!(spool.poll(now, &mut state, &logger).rejected, 1);
+        assert!(dir.path().join("1.rejected").exists());
+
+        fs::write(dir.path().join("2.json"), page("LATE")).unwrap();
+        assert_eq!(spool.poll(now, &mut state, &logger), SpoolScan::default());
+        let later = now + Duration::from_millis(100);
+        assert_eq!(spool.poll(later, &mut state, &logger).accepted, 1);
+    }
+}
@@ -0,0 +1,334 @@
+//! `lifelinetty support-bundle`: pack recent logs, the redacted config and system details into a
+//! single `.tar.gz` under the cache directory, ready to attach to a bug report.
+//!
+//! Every `*.log`/`*.json` file under the cache directory goes in under `cache/` (protocol
+//! errors, negotiation, wizard transcripts, telemetry, the feature matrix), trimmed to its newest
+//! [`MAX_LOG_BYTES`]. Values of [`REDACTED_KEYS`] never leave the device.
+
+use crate::{cache_dir, config::loader, Result};
+use flate2::{write::GzEncoder, Compression};
+use serde_json::json;
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Bundles are written to this directory under the cache directory and never collected.
+pub const BUNDLE_DIR: &str = "support";
+/// Per-file cap; a chatty unit still produces an attachment issue trackers accept.
+pub const MAX_LOG_BYTES: u64 = 256 * 1024;
+/// Config keys (`section.key`) whose values are replaced before the config is bundled.
+pub const REDACTED_KEYS: &[&str] = &["interlock.key", "negotiation.psk"];
+/// `/proc` entries that identify the kernel, board and serial drivers.
+const PROC_FILES: &[&str] = &[
+    "/proc/version",
+    "/proc/device-tree/model",
+    "/proc/uptime",
+    "/proc/loadavg",
+    "/proc/tty/drivers",
+];
+/// `/proc/cpuinfo` fields worth keeping; the board serial number is left out.
+const CPUINFO_FIELDS: &[&str] = &["model name", "Hardware", "Revision", "Model"];
+
+/// Build a bundle from the live cache directory and print its path.
+pub fn run() -> Result<()> {
+    let config_path = loader::default_config_path().ok();
+    let path = create(
+        &cache_dir(),
+        config_path.as_deref(),
+        Path::new("/"),
+        SystemTime::now(),
+    )?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+/// Write `<cache_dir>/support/lifelinetty-support-<unix secs>.tar.gz`. `root` prefixes the
+/// `/proc` paths so tests can supply their own.
+pub fn create(
+    cache_dir: &Path,
+    config_path: Option<&Path>,
+    root: &Path,
+    now: SystemTime,
+) -> Result<PathBuf> {
+    let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let name = format!("lifelinetty-support-{secs}");
+    let out_dir = cache_dir.join(BUNDLE_DIR);
+    fs::create_dir_all(&out_dir)?;
+
+    let mut logs = Vec::new();
+    collect_logs(cache_dir, cache_dir, &out_dir, &mut logs)?;
+    logs.sort();
+
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for path in logs {
+        let rel = path.strip_prefix(cache_dir).unwrap_or(&path).to_path_buf();
+        match read_tail(&path, MAX_LOG_BYTES) {
+            Ok((data, truncated)) => {
+                files.push(json!({
+                    "path": format!("cache/{}", rel.display()),
+                    "bytes": data.len(),
+                    "truncated": truncated,
+                }));
+                entries.push((format!("cache/{}", rel.display()), data));
+            }
+            Err(err) => {
+                skipped.push(json!({ "path": rel.display().to_string(), "error": err.to_string() }))
+            }
+        }
+    }
+    match config_path.map(|path| (path, fs::read_to_string(path))) {
+        Some((_, Ok(raw))) => {
+            entries.push(("config.toml".into(), redact_config(&raw).into_bytes()))
+        }
+        Some((path, Err(err))) => {
+            skipped.push(json!({ "path": path.display().to_string(), "error": err.to_string() }))
+        }
+        None => {}
+    }
+    entries.push(("system.txt".into(), system_report(root).into_bytes()));
+
+    let manifest = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "created_unix": secs,
+        "features": {
+            "systemd": cfg!(feature = "systemd"),
+            "async_serial": cfg!(feature = "async-serial"),
+            "metrics_http": cfg!(feature = "metrics-http"),
+        },
+        "os": os_info::get().to_string(),
+        "redacted_keys": REDACTED_KEYS,
+        "files": files,
+        "skipped": skipped,
+    });
+    let manifest = serde_json::to_vec_pretty(&manifest)
+        .map_err(|err| crate::Error::Parse(format!("support bundle manifest: {err}")))?;
+    entries.insert(0, ("manifest.json".into(), manifest));
+
+    // Write under a temporary name so a half-written bundle is never mistaken for a good one.
+    let path = out_dir.join(format!("{name}.tar.gz"));
+    let partial = out_dir.join(format!("{name}.tar.gz.part"));
+    let gz = GzEncoder::new(fs::File::create(&partial)?, Compression::default());
+    let mut tar = tar::Builder::new(gz);
+    for (entry, data) in &entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(secs);
+        header.set_cksum();
+        tar.append_data(&mut header, format!("{name}/{entry}"), data.as_slice())?;
+    }
+    tar.into_inner()?.finish()?;
+    fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+fn collect_logs(cache_dir: &Path, dir: &Path, skip: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // Nothing has run since boot, so there is nothing to collect.
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && dir == cache_dir => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let kind = entry.file_type()?;
+        if kind.is_dir() && path != skip {
+            collect_logs(cache_dir, &path, skip, out)?;
+        } else if kind.is_file() && is_log_name(&entry.file_name().to_string_lossy()) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Logs, rotated logs (`events.log.1`), JSON status files and crash reports; compacted
+/// archives are left out.
+fn is_log_name(name: &str) -> bool {
+    !name.ends_with(".gz")
+        && (name.ends_with(".json")
+            || name.ends_with(".jsonl")
+            || name.contains(".log")
+            || (name.starts_with("crash-") && name.ends_with(".txt")))
+}
+
+/// The newest `limit` bytes of `path`, starting on a line boundary when trimmed.
+fn read_tail(path: &Path, limit: u64) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    if len <= limit {
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        return Ok((data, false));
+    }
+    file.seek(SeekFrom::Start(len - limit))?;
+    let mut data = Vec::new();
+    file.take(limit).read_to_end(&mut data)?;
+    if let Some(newline) = data.iter().position(|b| *b == b'\n') {
+        data.drain(..=newline);
+    }
+    Ok((data, true))
+}
+
+/// Replace the value of every [`REDACTED_KEYS`] entry, keeping the rest of the file verbatim.
+fn redact_config(raw: &str) -> String {
+    let mut section = String::new();
+    let mut out = String::with_capacity(raw.len());
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            section = name.trim().to_string();
+        } else if let Some((key, _)) = trimmed.split_once('=') {
+            let key = key.trim();
+            let path = if section.is_empty() {
+                key.to_string()
+            } else {
+                format!("{section}.{key}")
+            };
+            if REDACTED_KEYS.contains(&path.as_str()) {
+                out.push_str(&format!("{key} = \"<redacted>\"\n"));
+                continue;
+            }
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+fn system_report(root: &Path) -> String {
+    let read = |path: &str| fs::read_to_string(root.join(path.trim_start_matches('/')));
+    let mut report = format!("lifelinetty {}\n", env!("CARGO_PKG_VERSION"));
+    for path in PROC_FILES {
+        report.push_str(&format!("\n== {path} ==\n"));
+        match read(path) {
+            // The device-tree model is NUL-terminated.
+            Ok(text) => report.push_str(text.trim_end_matches(['\0', '\n'])),
+            Err(err) => report.push_str(&format!("unavailable: {err}")),
+        }
+        report.push('\n');
+    }
+    report.push_str("\n== /proc/cpuinfo (selected) ==\n");
+    match read("/proc/cpuinfo") {
+        Ok(text) => {
+            let mut seen = Vec::new();
+            for line in text.lines() {
+                let Some((field, _)) = line.split_once(':') else {
+                    continue;
+                };
+                let field = field.trim();
+                // One line per field; multi-core boards repeat them for every CPU.
+                if CPUINFO_FIELDS.contains(&field) && !seen.contains(&field) {
+                    seen.push(field);
+                    report.push_str(line.trim());
+                    report.push('\n');
+                }
+            }
+        }
+        Err(err) => report.push_str(&format!("unavailable: {err}\n")),
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::collections::BTreeMap;
+
+    fn unpack(path: &Path) -> BTreeMap<String, String> {
+        let mut archive = tar::Archive::new(GzDecoder::new(fs::File::open(path).unwrap()));
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let name = entry.path().unwrap().display().to_string();
+                let mut body = String::new();
+                entry.read_to_string(&mut body).unwrap();
+                (name, body)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bundles_logs_redacted_config_and_system_details() {
+        let cache = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(cache.path().join("logs")).unwrap();
+        fs::write(cache.path().join("logs/negotiation.log"), "hello sent\n").unwrap();
+        fs::write(cache.path().join("features.json"), "{}").unwrap();
+        fs::write(cache.path().join("armed"), "123").unwrap();
+        fs::create_dir_all(cache.path().join("crash")).unwrap();
+        fs::write(cache.path().join("crash/crash-1699999999.txt"), "panicked").unwrap();
+        let big: String = (0..40_000).map(|n| format!("line {n}\n")).collect();
+        fs::write(cache.path().join("protocol_errors.log"), &big).unwrap();
+        let config = root.path().join("config.toml");
+        fs::write(
+            &config,
+            "baud = 9600\n[interlock]\nrequired = true\nkey = \"hunter2hunter2hunter2\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.path().join("proc")).unwrap();
+        fs::write(
+            root.path().join("proc/cpuinfo"),
+            "processor\t: 0\nHardware\t: BCM2835\nSerial\t\t: 00000000abcdef\nprocessor\t: 1\nHardware\t: BCM2835\n",
+        )
+        .unwrap();
+        let now = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+
+        let first = create(cache.path(), Some(&config), root.path(), now).unwrap();
+        assert!(first.ends_with("support/lifelinetty-support-1700000000.tar.gz"));
+        let later = now + std::time::Duration::from_secs(60);
+        let bundle = create(cache.path(), Some(&config), root.path(), later).unwrap();
+
+        let files = unpack(&bundle);
+        let prefix = "lifelinetty-support-1700000060";
+        let names: Vec<_> = files.keys().map(|name| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                format!("{prefix}/cache/crash/crash-1699999999.txt"),
+                format!("{prefix}/cache/features.json"),
+                format!("{prefix}/cache/logs/negotiation.log"),
+                format!("{prefix}/cache/protocol_errors.log"),
+                format!("{prefix}/config.toml"),
+                format!("{prefix}/manifest.json"),
+                format!("{prefix}/system.txt"),
+            ]
+        );
+        let config = &files[&format!("{prefix}/config.toml")];
+        assert!(config.contains("required = true"));
+        assert!(config.contains("key = \"<redacted>\""));
+        assert!(!config.contains("hunter2"));
+
+        let errors = &files[&format!("{prefix}/cache/protocol_errors.log")];
+        assert!(errors.len() as u64 <= MAX_LOG_BYTES);
+        assert!(errors.starts_with("line ") && errors.ends_with("line 39999\n"));
+
+        let system = &files[&format!("{prefix}/system.txt")];
+        assert_eq!(system.matches("Hardware\t: BCM2835").count(), 1);
+        assert!(!system.contains("abcdef"));
+        assert!(system.contains("== /proc/version ==\nunavailable"));
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&files[&format!("{prefix}/manifest.json")]).unwrap();
+        assert_eq!(manifest["version"], env!("CARGO_PKG_VERSION"));
+        let truncated: Vec<_> = manifest["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|file| file["truncated"] == true)
+            .map(|file| file["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(truncated, vec!["cache/protocol_errors.log"]);
+    }
+}
@@ -0,0 +1,240 @@
+//! systemd readiness and watchdog notifications (`sd_notify`).
+//!
+//! With the `systemd` feature, a daemon started by a `Type=notify` unit reports `READY=1` once
+//! the LCD and serial port are up and sends `WATCHDOG=1` from the render loop when the unit sets
+//! `WatchdogSec`. Messages go straight to `$NOTIFY_SOCKET`, so no libsystemd is needed. Without
+//! the feature, [`SystemdNotifier`] is an empty type whose methods do nothing.
+
+#[cfg(feature = "systemd")]
+pub use notify::SystemdNotifier;
+
+#[cfg(not(feature = "systemd"))]
+pub use disabled::SystemdNotifier;
+
+#[cfg(not(feature = "systemd"))]
+mod disabled {
+    use crate::app::Logger;
+    use std::time::Instant;
+
+    pub struct SystemdNotifier;
+
+    impl SystemdNotifier {
+        pub fn from_env(_logger: &Logger) -> Self {
+            Self
+        }
+
+        #[inline]
+        pub fn ready(&mut self, _status: &str, _logger: &Logger) {}
+
+        #[inline]
+        pub fn status(&self, _status: &str) {}
+
+        #[inline]
+        pub fn ping(&mut self, _now: Instant) {}
+
+        #[inline]
+        pub fn stopping(&self) {}
+    }
+}
+
+#[cfg(feature = "systemd")]
+mod notify {
+    use crate::app::Logger;
+    use std::{
+        io,
+        os::unix::net::{SocketAddr, UnixDatagram},
+        time::{Duration, Instant},
+    };
+
+    /// Connection to the service manager's notification socket; inert when not run by systemd.
+    pub struct SystemdNotifier {
+        target: Option<(UnixDatagram, SocketAddr)>,
+        /// Half of `WatchdogSec`, the interval systemd recommends for pings.
+        watchdog: Option<Duration>,
+        next_ping: Instant,
+        ready: bool,
+    }
+
+    impl SystemdNotifier {
+        /// Read `NOTIFY_SOCKET`, `WATCHDOG_USEC` and `WATCHDOG_PID` from the environment.
+        pub fn from_env(logger: &Logger) -> Self {
+            let Some(socket) = std::env::var_os("NOTIFY_SOCKET") else {
+                return Self::inert();
+            };
+            let addr = match socket_addr(&socket.to_string_lossy()) {
+                Ok(addr) => addr,
+                Err(err) => {
+                    logger.warn(format!("systemd: ignoring NOTIFY_SOCKET: {err}"));
+                    return Self::inert();
+                }
+            };
+            let watchdog = watchdog_interval(
+                std::env::var("WATCHDOG_USEC").ok().as_deref(),
+                std::env::var("WATCHDOG_PID").ok().as_deref(),
+                std::process::id(),
+            );
+            match UnixDatagram::unbound() {
+                Ok(socket) => {
+                    if let Some(interval) = watchdog {
+                        logger.info(format!(
+                            "systemd: watchdog ping every {}ms",
+                            interval.as_millis()
+                        ));
+                    }
+                    Self::with_target(socket, addr, watchdog)
+                }
+                Err(err) => {
+                    logger.warn(format!("systemd: notify socket unavailable: {err}"));
+                    Self::inert()
+                }
+            }
+        }
+
+        fn inert() -> Self {
+            Self {
+                target: None,
+                watchdog: None,
+                next_ping: Instant::now(),
+                ready: false,
+            }
+        }
+
+        fn with_target(socket: UnixDatagram, addr: SocketAddr, watchdog: Option<Duration>) -> Self {
+            Self {
+                target: Some((socket, addr)),
+                watchdog,
+                next_ping: Instant::now(),
+                ready: false,
+            }
+        }
+
+        /// Send `READY=1` the first time it is called; later calls only update the status.
+        pub fn ready(&mut self, status: &str, logger: &Logger) {
+            if self.ready {
+                self.status(status);
+                return;
+            }
+            self.ready = true;
+            if self.target.is_some() {
+                logger.info(format!("systemd: ready ({status})"));
+            }
+            self.send(&format!("READY=1\nSTATUS={status}"));
+        }
+
+        pub fn status(&self, status: &str) {
+            self.send(&format!("STATUS={status}"));
+        }
+
+        /// Called every render-loop pass; sends `WATCHDOG=1` whenever the ping interval elapsed.
+        pub fn ping(&mut self, now: Instant) {
+            let Some(interval) = self.watchdog else {
+                return;
+            };
+            if now < self.next_ping {
+                return;
+            }
+            self.next_ping = now + interval;
+            self.send("WATCHDOG=1");
+        }
+
+        pub fn stopping(&self) {
+            self.send("STOPPING=1");
+        }
+
+        fn send(&self, message: &str) {
+            if let Some((socket, addr)) = &self.target {
+                // Best effort: a missing manager must never stall rendering.
+                let _ = socket.send_to_addr(message.as_bytes(), addr);
+            }
+        }
+    }
+
+    /// `NOTIFY_SOCKET` is a filesystem path, or `@name` for the Linux abstract namespace.
+    fn socket_addr(raw: &str) -> io::Result<SocketAddr> {
+        if let Some(name) = raw.strip_prefix('@') {
+            #[cfg(target_os = "linux")]
+            {
+                use std::os::linux::net::SocketAddrExt;
+                return SocketAddr::from_abstract_name(name.as_bytes());
+            }
+            #[cfg(not(target_os = "linux"))]
+            {
+                let _ = name;
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "abstract sockets need Linux",
+                ));
+            }
+        }
+        SocketAddr::from_pathname(raw)
+    }
+
+    /// Ping interval for this process: half of `WATCHDOG_USEC`, unless the watchdog belongs to
+    /// another PID.
+    fn watchdog_interval(usec: Option<&str>, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+        if let Some(pid) = pid {
+            if pid.trim().parse::<u32>().ok() != Some(own_pid) {
+                return None;
+            }
+        }
+        let usec: u64 = usec?.trim().parse().ok().filter(|usec| *usec > 0)?;
+        Some(Duration::from_micros(usec / 2))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::app::logger::LogLevel;
+
+        #[test]
+        fn watchdog_interval_is_half_and_pid_scoped() {
+            assert_eq!(
+                watchdog_interval(Some("30000000"), None, 7),
+                Some(Duration::from_secs(15))
+            );
+            assert_eq!(
+                watchdog_interval(Some("30000000"), Some("7"), 7),
+                Some(Duration::from_secs(15))
+            );
+            assert_eq!(watchdog_interval(Some("30000000"), Some("8"), 7), None);
+            assert_eq!(watchdog_interval(Some("0"), None, 7), None);
+            assert_eq!(watchdog_interval(None, None, 7), None);
+        }
+
+        #[test]
+        fn ready_is_sent_once_and_pings_are_rate_limited() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("notify.sock");
+            let manager = UnixDatagram::bind(&path).unwrap();
+            manager.set_nonblocking(true).unwrap();
+            let logger = Logger::new(LogLevel::Error, None).unwrap();
+            let mut notifier = SystemdNotifier::with_target(
+                UnixDatagram::unbound().unwrap(),
+                socket_addr(path.to_str().unwrap()).unwrap(),
+                Some(Duration::from_secs(10)),
+            );
+
+            let now = Instant::now();
+            notifier.ready("serial up", &logger);
+            notifier.ready("serial up again", &logger);
+            notifier.ping(now);
+            notifier.ping(now + Duration::from_secs(1));
+            notifier.stopping();
+
+            let mut received = Vec::new();
+            let mut buf = [0u8; 256];
+            while let Ok(len) = manager.recv(&mut buf) {
+                received.push(String::from_utf8_lossy(&buf[..len]).into_owned());
+            }
+            assert_eq!(
+                received,
+                [
+                    "READY=1\nSTATUS=serial up",
+                    "STATUS=serial up again",
+                    "WATCHDOG=1",
+                    "STOPPING=1"
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{payload::TunnelMsgOwned, Error, Result};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+pub const TAIL_MAX_LINES: u16 = 200;
+/// Bytes per `Stdout` chunk; one chunk per interval keeps a tail from starving render frames.
+const TAIL_CHUNK_BYTES: usize = 256;
+const TAIL_CHUNK_INTERVAL: Duration = Duration::from_millis(200);
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// When the file outruns the link, skip ahead rather than buffering without bound.
+const TAIL_MAX_BACKLOG: u64 = 16 * 1024;
+
+/// Resolve `requested` against the allowlist, comparing canonical paths so `..` and symlinks
+/// cannot reach files outside it.
+pub fn resolve_allowed(allowlist: &[String], requested: &str) -> Result<PathBuf> {
+    let denied = || Error::InvalidArgs(format!("tail path not allowlisted: {requested}"));
+    let target = fs::canonicalize(requested).map_err(|_| denied())?;
+    allowlist
+        .iter()
+        .filter_map(|entry| fs::canonicalize(entry).ok())
+        .find(|allowed| *allowed == target)
+        .ok_or_else(denied)
+}
+
+/// One active `TailFile` request: an initial backlog followed by appended bytes when following.
+pub struct TailSession {
+    path: PathBuf,
+    offset: u64,
+    inode: u64,
+    follow: bool,
+    pending: VecDeque<u8>,
+    next_chunk_at: Instant,
+    next_poll_at: Instant,
+    finished: bool,
+}
+
+impl TailSession {
+    pub fn open(path: PathBuf, lines: u16, follow: bool, now: Instant) -> Result<Self> {
+        let meta = fs::metadata(&path)?;
+        let len = meta.len();
+        let start = len.saturating_sub(TAIL_MAX_BACKLOG);
+        let backlog = read_range(&path, start, len)?;
+        let tail = last_lines(&backlog, lines.min(TAIL_MAX_LINES) as usize, start > 0);
+        Ok(Self {
+            path,
+            offset: len,
+            inode: meta.ino(),
+            follow,
+            pending: tail.iter().copied().collect(),
+            next_chunk_at: now,
+            next_poll_at: now + TAIL_POLL_INTERVAL,
+            finished: false,
+        })
+    }
+
+    /// Next rate-limited message: a `Stdout` chunk, or `Exit` once a non-follow tail drains.
+    pub fn poll(&mut self, now: Instant) -> Option<TunnelMsgOwned> {
+        if self.finished || now < self.next_chunk_at {
+            return None;
+        }
+        if self.pending.is_empty() && self.follow && now >= self.next_poll_at {
+            self.next_poll_at = now + TAIL_POLL_INTERVAL;
+            if let Err(err) = self.read_appended() {
+                self.finished = true;
+                return Some(TunnelMsgOwned::Stderr {
+                    chunk: format!("tail stopped: {err}\n").into_bytes(),
+                });
+            }
+        }
+        if self.pending.is_empty() {
+            if self.follow {
+                return None;
+            }
+            self.finished = true;
+            return Some(TunnelMsgOwned::Exit { code: 0 });
+        }
+        let take = self.pending.len().min(TAIL_CHUNK_BYTES);
+        let chunk: Vec<u8> = self.pending.drain(..take).collect();
+        self.next_chunk_at = now + TAIL_CHUNK_INTERVAL;
+        Some(TunnelMsgOwned::Stdout { chunk })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn read_appended(&mut self) -> Result<()> {
+        let meta = fs::metadata(&self.path)?;
+        // Rotation swaps the inode; truncation shrinks the file. Either way start over.
+        if meta.ino() != self.inode || meta.len() < self.offset {
+            self.inode = meta.ino();
+            self.offset = 0;
+        }
+        let len = meta.len();
+        if len == self.offset {
+            return Ok(());
+        }
+        let start = self.offset.max(len.saturating_sub(TAIL_MAX_BACKLOG));
+        self.pending.extend(read_range(&self.path, start, len)?);
+        self.offset = len;
+        Ok(())
+    }
+}
+
+fn read_range(path: &Path, start: u64, end: u64) -> Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf = Vec::with_capacity(end.saturating_sub(start) as usize);
+    file.take(end.saturating_sub(start)).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Last `count` lines of `buf`; `partial_head` drops a leading fragment cut by the backlog cap.
+fn last_lines(buf: &[u8], count: usize, partial_head: bool) -> &[u8] {
+    let mut buf = buf;
+    if partial_head {
+        buf = buf
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or(&[][..], |pos| &buf[pos + 1..]);
+    }
+    if count == 0 {
+        return &[];
+    }
+    let body = buf.strip_suffix(b"\n").unwrap_or(buf);
+    let start = body
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, b)| **b == b'\n')
+        .nth(count - 1)
+        .map_or(0, |(pos, _)| pos + 1);
+    &buf[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn drain(session: &mut TailSession, mut now: Instant) -> (Vec<u8>, Option<i32>) {
+        let mut out = Vec::new();
+        for _ in 0..64 {
+            match session.poll(now) {
+                Some(TunnelMsgOwned::Stdout { chunk }) => out.extend(chunk),
+                Some(TunnelMsgOwned::Exit { code }) => return (out, Some(code)),
+                _ => {}
+            }
+            now += TAIL_POLL_INTERVAL;
+        }
+        (out, None)
+    }
+
+    #[test]
+    fn last_lines_handles_trailing_newline_and_partial_head() {
+        assert_eq!(last_lines(b"a\nb\nc\n", 2, false), b"b\nc\n");
+        assert_eq!(last_lines(b"a\nb\nc", 5, false), b"a\nb\nc");
+        assert_eq!(last_lines(b"tial\nb\n", 5, true), b"b\n");
+        assert!(last_lines(b"a\n", 0, false).is_empty());
+    }
+
+    #[test]
+    fn resolve_rejects_paths_outside_allowlist() {
+        let dir = tempdir().unwrap();
+        let allowed = dir.path().join("app.log");
+        let other = dir.path().join("secret");
+        fs::write(&allowed, "ok\n").unwrap();
+        fs::write(&other, "no\n").unwrap();
+        let allowlist = vec![allowed.display().to_string()];
+
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let sneaky = dir.path().join("sub/../app.log").display().to_string();
+        assert_eq!(
+            resolve_allowed(&allowlist, &sneaky).unwrap(),
+            fs::canonicalize(&allowed).unwrap()
+        );
+        assert!(resolve_allowed(&allowlist, &other.display().to_string()).is_err());
+        assert!(resolve_allowed(&[], &allowed.display().to_string()).is_err());
+    }
+
+    #[test]
+    fn one_shot_tail_sends_last_lines_then_exit() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "one\ntwo\nthree\n").unwrap();
+        let now = Instant::now();
+        let mut session = TailSession::open(path, 2, false, now).unwrap();
+        let (out, code) = drain(&mut session, now);
+        assert_eq!(out, b"two\nthree\n");
+        assert_eq!(code, Some(0));
+        assert!(session.is_finished());
+    }
+
+    #[test]
+    fn follow_streams_appends_in_rate_limited_chunks() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "old\n").unwrap();
+        let now = Instant::now();
+        let mut session = TailSession::open(path.clone(), 0, true, now).unwrap();
+        assert!(session.poll(now).is_none());
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&vec![b'x'; TAIL_CHUNK_BYTES + 10]).unwrap();
+        let later = now + TAIL_POLL_INTERVAL;
+        match session.poll(later) {
+            Some(TunnelMsgOwned::Stdout { chunk }) => assert_eq!(chunk.len(), TAIL_CHUNK_BYTES),
+            other => panic!("expected chunk, got {other:?}"),
+        }
+        assert!(session.poll(later).is_none(), "second chunk must wait");
+        match session.poll(later + TAIL_CHUNK_INTERVAL) {
+            Some(TunnelMsgOwned::Stdout { chunk }) => assert_eq!(chunk.len(), 10),
+            other => panic!("expected chunk, got {other:?}"),
+        }
+        assert!(!session.is_finished());
+    }
+}
@@ -0,0 +1,336 @@
+//! `lifelinetty telemetry export`: one stream out of the telemetry logs in the cache dir.
+//!
+//! Serial backoff events (`serial_backoff.log`), loop counter snapshots (`loop_stats.log`) and
+//! polling history (`polling/events.log`) are read together with any rotated copies
+//! (`events.log.2`, `events.log.1`, then `events.log`), merged by time and written to stdout.
+//!
+//! Timestamps are wall-clock. When a log's clock steps backwards (NTP fixing a fast clock, an
+//! RTC-less board setting its time late), the newest records are trusted and everything written
+//! before the step is shifted back by its size, so order and spacing survive; those records
+//! keep their logged time in `raw_ts_ms`.
+
+use crate::{cache_dir, cli::TelemetryFormat, Result};
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Sources in the order their records win a timestamp tie.
+const SOURCES: &[(&str, &str, LineFormat)] = &[
+    ("backoff", "serial_backoff.log", LineFormat::Json),
+    ("loop_stats", "loop_stats.log", LineFormat::Json),
+    ("polling", "polling/events.log", LineFormat::KeyValue),
+];
+
+#[derive(Clone, Copy)]
+enum LineFormat {
+    /// One JSON object per line with a `ts_ms` field.
+    Json,
+    /// `key=value` pairs; `message=` takes the rest of the line.
+    KeyValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    ts_ms: u64,
+    raw_ts_ms: Option<u64>,
+    source: &'static str,
+    fields: Map<String, Value>,
+}
+
+pub fn run(since_ms: Option<u64>, format: TelemetryFormat) -> Result<()> {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let cutoff = since_ms.map(|since| now_ms.saturating_sub(since));
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    export(&cache_dir(), cutoff, format, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Write every record at or after `cutoff` (unix ms) from the logs under `cache`.
+fn export<W: Write>(
+    cache: &Path,
+    cutoff: Option<u64>,
+    format: TelemetryFormat,
+    out: &mut W,
+) -> io::Result<()> {
+    let mut records = Vec::new();
+    for (source, file, line_format) in SOURCES {
+        let mut from_source = Vec::new();
+        for path in rotation_chain(&cache.join(file))? {
+            for line in fs::read_to_string(&path)?.lines() {
+                if let Some((ts_ms, fields)) = parse_line(line, *line_format) {
+                    from_source.push(Record {
+                        ts_ms,
+                        raw_ts_ms: None,
+                        source,
+                        fields,
+                    });
+                }
+            }
+        }
+        undo_clock_steps(&mut from_source);
+        records.extend(from_source);
+    }
+    records.retain(|record| cutoff.is_none_or(|cutoff| record.ts_ms >= cutoff));
+    // Stable, so records sharing a millisecond keep their log order.
+    records.sort_by_key(|record| record.ts_ms);
+    match format {
+        TelemetryFormat::Jsonl => write_jsonl(&records, out),
+        TelemetryFormat::Csv => write_csv(&records, out),
+    }
+}
+
+/// `path` and its numbered rotations, oldest first; missing files are skipped.
+fn rotation_chain(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|n| n.to_str())) else {
+        return Ok(Vec::new());
+    };
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut rotated = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(suffix) = file_name
+            .to_str()
+            .and_then(|file| file.strip_prefix(name))
+            .and_then(|rest| rest.strip_prefix('.'))
+        else {
+            continue;
+        };
+        if let Ok(slot) = suffix.parse::<u32>() {
+            rotated.push((slot, entry.path()));
+        }
+    }
+    // Higher slots were rotated out earlier.
+    rotated.sort_by_key(|(slot, _)| std::cmp::Reverse(*slot));
+    let mut chain: Vec<PathBuf> = rotated.into_iter().map(|(_, path)| path).collect();
+    if path.is_file() {
+        chain.push(path.to_path_buf());
+    }
+    Ok(chain)
+}
+
+fn parse_line(line: &str, format: LineFormat) -> Option<(u64, Map<String, Value>)> {
+    let mut fields = match format {
+        LineFormat::Json => match serde_json::from_str(line.trim()) {
+            Ok(Value::Object(fields)) => fields,
+            _ => return None,
+        },
+        LineFormat::KeyValue => parse_key_values(line),
+    };
+    // Lines from before timestamps were logged cannot be placed in time.
+    let ts_ms = fields.remove("ts_ms")?.as_u64()?;
+    Some((ts_ms, fields))
+}
+
+fn parse_key_values(line: &str) -> Map<String, Value> {
+    let mut fields = Map::new();
+    let mut rest = line.trim();
+    while !rest.is_empty() {
+        let (pair, tail) = if rest.starts_with("message=") {
+            (rest, "")
+        } else {
+            rest.split_once(' ').unwrap_or((rest, ""))
+        };
+        rest = tail.trim_start();
+        let Some((key, raw)) = pair.split_once('=') else {
+            continue;
+        };
+        let value = if key == "message" {
+            Value::from(raw)
+        } else if let Ok(int) = raw.parse::<u64>() {
+            Value::from(int)
+        } else if let Ok(float) = raw.parse::<f64>() {
+            Value::from(float)
+        } else {
+            Value::from(raw)
+        };
+        fields.insert(key.to_string(), value);
+    }
+    fields
+}
+
+/// Walk one log newest to oldest and shift records written before a backwards clock step so
+/// the log's timestamps never decrease.
+fn undo_clock_steps(records: &mut [Record]) {
+    let mut offset = 0u64;
+    let mut newer: Option<u64> = None;
+    for record in records.iter_mut().rev() {
+        let mut ts = record.ts_ms.saturating_sub(offset);
+        if let Some(newer) = newer.filter(|newer| ts > *newer) {
+            offset += ts - newer;
+            ts = newer;
+        }
+        if ts != record.ts_ms {
+            record.raw_ts_ms = Some(record.ts_ms);
+            record.ts_ms = ts;
+        }
+        newer = Some(ts);
+    }
+}
+
+fn write_jsonl<W: Write>(records: &[Record], out: &mut W) -> io::Result<()> {
+    for record in records {
+        let mut object = Map::new();
+        object.insert("ts_ms".into(), record.ts_ms.into());
+        if let Some(raw) = record.raw_ts_ms {
+            object.insert("raw_ts_ms".into(), raw.into());
+        }
+        object.insert("source".into(), record.source.into());
+        for (key, value) in &record.fields {
+            object.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        writeln!(out, "{}", Value::Object(object))?;
+    }
+    Ok(())
+}
+
+fn write_csv<W: Write>(records: &[Record], out: &mut W) -> io::Result<()> {
+    let fixed = ["ts_ms", "raw_ts_ms", "source"];
+    let columns: BTreeSet<&str> = records
+        .iter()
+        .flat_map(|record| record.fields.keys().map(String::as_str))
+        .filter(|key| !fixed.contains(key))
+        .collect();
+    let header: Vec<&str> = fixed
+        .iter()
+        .copied()
+        .chain(columns.iter().copied())
+        .collect();
+    writeln!(out, "{}", header.join(","))?;
+    for record in records {
+        let mut row = vec![
+            record.ts_ms.to_string(),
+            record
+                .raw_ts_ms
+                .map(|raw| raw.to_string())
+                .unwrap_or_default(),
+            record.source.to_string(),
+        ];
+        for column in &columns {
+            row.push(match record.fields.get(*column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(text)) => csv_cell(text),
+                Some(other) => csv_cell(&other.to_string()),
+            });
+        }
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+fn csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export_to_string(cache: &Path, cutoff: Option<u64>, format: TelemetryFormat) -> String {
+        let mut out = Vec::new();
+        export(cache, cutoff, format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn merges_sources_and_rotations_in_time_order() {
+        let cache = tempfile::tempdir().unwrap();
+        let dir = cache.path();
+        fs::create_dir_all(dir.join("polling")).unwrap();
+        fs::write(
+            dir.join("serial_backoff.log.1"),
+            "{\"ts_ms\":1000,\"event\":\"serial_backoff\",\"phase\":\"attempt\"}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("serial_backoff.log"),
+            "{\"ts_ms\":3000,\"event\":\"serial_backoff\",\"phase\":\"success\"}\nnot json\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("loop_stats.log"),
+            "{\"ts_ms\":2500,\"event\":\"loop_stats\",\"frames_accepted\":4}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("polling/events.log"),
+            "seq=1 cpu=3.0 kind=snapshot\nts_ms=2000 seq=2 cpu=12.5 kind=snapshot\nts_ms=4000 kind=error message=read /proc/stat: denied\n",
+        )
+        .unwrap();
+
+        let out = export_to_string(dir, None, TelemetryFormat::Jsonl);
+        let records: Vec<Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let order: Vec<_> = records
+            .iter()
+            .map(|r| r["ts_ms"].as_u64().unwrap())
+            .collect();
+        assert_eq!(order, [1000, 2000, 2500, 3000, 4000]);
+        assert_eq!(records[1]["source"], "polling");
+        assert_eq!(records[1]["cpu"], 12.5);
+        assert_eq!(records[2]["frames_accepted"], 4);
+        assert_eq!(records[4]["message"], "read /proc/stat: denied");
+
+        let recent = export_to_string(dir, Some(2500), TelemetryFormat::Jsonl);
+        assert_eq!(recent.lines().count(), 3);
+    }
+
+    #[test]
+    fn backwards_clock_steps_shift_the_older_records() {
+        let mut records: Vec<Record> = [5_000, 6_000, 2_000, 2_500]
+            .into_iter()
+            .map(|ts_ms| Record {
+                ts_ms,
+                raw_ts_ms: None,
+                source: "backoff",
+                fields: Map::new(),
+            })
+            .collect();
+        undo_clock_steps(&mut records);
+        let ts: Vec<_> = records.iter().map(|r| r.ts_ms).collect();
+        assert_eq!(ts, [1_000, 2_000, 2_000, 2_500]);
+        assert_eq!(records[0].raw_ts_ms, Some(5_000));
+        assert_eq!(records[3].raw_ts_ms, None);
+    }
+
+    #[test]
+    fn csv_has_one_column_per_field_and_quotes_cells() {
+        let cache = tempfile::tempdir().unwrap();
+        let dir = cache.path();
+        fs::write(
+            dir.join("serial_backoff.log"),
+            "{\"ts_ms\":1,\"event\":\"serial_backoff\",\"reason\":\"eof, retrying\"}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("loop_stats.log"),
+            "{\"ts_ms\":2,\"event\":\"loop_stats\",\"duplicates\":7}\n",
+        )
+        .unwrap();
+        let out = export_to_string(dir, None, TelemetryFormat::Csv);
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines[0], "ts_ms,raw_ts_ms,source,duplicates,event,reason");
+        assert_eq!(lines[1], "1,,backoff,,serial_backoff,\"eof, retrying\"");
+        assert_eq!(lines[2], "2,,loop_stats,7,loop_stats,");
+    }
+}
@@ -0,0 +1,153 @@
+use crate::config::{RenderConfig, RenderThrottleMode};
+use std::time::{Duration, Instant};
+
+/// Keep LCD writes to at most this share of the redraw interval in adaptive mode (1/4).
+const ADAPTIVE_BUSY_DIVISOR: u32 = 4;
+/// Ignore interval changes smaller than 1/10 of the current value so the throttle does not flap.
+const ADAPTIVE_DEADBAND_DIVISOR: u32 = 10;
+const RATE_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Minimum gap between LCD redraws, optionally stretched while the bus is slow, plus a running
+/// frame count for the periodic effective-rate log line.
+#[derive(Debug, Clone)]
+pub struct RenderThrottle {
+    mode: RenderThrottleMode,
+    floor: Duration,
+    ceiling: Duration,
+    interval: Duration,
+    avg_write: Option<Duration>,
+    frames: u32,
+    window_start: Instant,
+}
+
+impl RenderThrottle {
+    pub fn new(config: &RenderConfig, now: Instant) -> Self {
+        let floor = Duration::from_millis(config.min_interval_ms);
+        Self {
+            mode: config.throttle,
+            floor,
+            ceiling: Duration::from_millis(config.max_interval_ms).max(floor),
+            interval: floor,
+            avg_write: None,
+            frames: 0,
+            window_start: now,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Record how long a frame write took. In adaptive mode, returns the new interval when it
+    /// moved far enough to be worth logging.
+    pub fn record_write(&mut self, elapsed: Duration) -> Option<Duration> {
+        self.frames = self.frames.saturating_add(1);
+        let avg = match self.avg_write {
+            Some(avg) => (avg * 3 + elapsed) / 4,
+            None => elapsed,
+        };
+        self.avg_write = Some(avg);
+        if self.mode != RenderThrottleMode::Adaptive {
+            return None;
+        }
+        let target = (avg * ADAPTIVE_BUSY_DIVISOR).clamp(self.floor, self.ceiling);
+        let delta = target.abs_diff(self.interval);
+        let pinned = target == self.floor || target == self.ceiling;
+        if delta.is_zero() || (delta < self.interval / ADAPTIVE_DEADBAND_DIVISOR && !pinned) {
+            return None;
+        }
+        self.interval = target;
+        Some(target)
+    }
+
+    /// Frames per second drawn since the last report, once per reporting window.
+    pub fn rate_report(&mut self, now: Instant) -> Option<f64> {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < RATE_REPORT_INTERVAL {
+            return None;
+        }
+        let fps = f64::from(self.frames) / elapsed.as_secs_f64();
+        self.frames = 0;
+        self.window_start = now;
+        Some(fps)
+    }
+}
+
+/// Frame-rate ceiling implied by a redraw interval.
+pub fn fps_ceiling(interval: Duration) -> f64 {
+    1.0 / interval.as_secs_f64().max(f64::EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle(mode: RenderThrottleMode) -> RenderThrottle {
+        RenderThrottle::new(
+            &RenderConfig {
+                min_interval_ms: 200,
+                max_interval_ms: 1_000,
+                blink_ms: 500,
+                throttle: mode,
+                expiry_countdown: false,
+                bar_style: Default::default(),
+                persist_pages: false,
+                max_frames_per_sec: 0,
+                i2c_write_budget_bytes: 0,
+            },
+            Instant::now(),
+        )
+    }
+
+    #[test]
+    fn fixed_mode_never_moves() {
+        let mut throttle = throttle(RenderThrottleMode::Fixed);
+        assert_eq!(throttle.record_write(Duration::from_millis(400)), None);
+        assert_eq!(throttle.interval(), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn adaptive_stretches_on_slow_writes_and_recovers() {
+        let mut throttle = throttle(RenderThrottleMode::Adaptive);
+        assert_eq!(throttle.record_write(Duration::from_millis(5)), None);
+        assert_eq!(
+            throttle.record_write(Duration::from_millis(485)),
+            Some(Duration::from_millis(500))
+        );
+        // Repeated slow writes saturate at the ceiling.
+        let mut last = None;
+        for _ in 0..20 {
+            if let Some(interval) = throttle.record_write(Duration::from_millis(600)) {
+                last = Some(interval);
+            }
+        }
+        assert_eq!(last, Some(Duration::from_millis(1_000)));
+        for _ in 0..40 {
+            throttle.record_write(Duration::from_millis(5));
+        }
+        assert_eq!(throttle.interval(), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn rate_report_waits_for_a_full_window() {
+        let start = Instant::now();
+        let mut throttle = RenderThrottle::new(&RenderConfig::default(), start);
+        for _ in 0..300 {
+            throttle.record_write(Duration::from_millis(1));
+        }
+        assert_eq!(throttle.rate_report(start + Duration::from_secs(30)), None);
+        let fps = throttle
+            .rate_report(start + Duration::from_secs(60))
+            .unwrap();
+        assert!((fps - 5.0).abs() < 1e-9);
+        assert_eq!(
+            throttle.rate_report(start + Duration::from_secs(120)),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn fps_ceiling_matches_interval() {
+        assert!((fps_ceiling(Duration::from_millis(200)) - 5.0).abs() < 1e-9);
+    }
+}
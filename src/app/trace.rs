@@ -0,0 +1,245 @@
+//! `--trace`: follow each inbound serial line through the render loop.
+//!
+//! Every non-empty line gets a correlation ID when it is read. Each stage it passes through
+//! (routing, parsing, state ingestion, page scheduling and the LCD write that finally puts it on
+//! screen) logs one line tagged with that ID and the time since the line arrived:
+//!
+//! ```text
+//! trace #17 +0.0ms rx: len=64 crc=1a2b3c4d {"line1":"CPU 42%",...
+//! trace #17 +0.3ms ingest: accepted (2 pages queued)
+//! trace #17 +4.1ms schedule: page rotation
+//! trace #17 +5.0ms lcd: written in 0.9ms
+//! ```
+//!
+//! Frames carry no ID of their own (they are compared by value for deduplication), so the tracer
+//! remembers the last few accepted frames and matches them back up when they are scheduled.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use super::Logger;
+use crate::payload::RenderFrame;
+use crc32fast::Hasher;
+
+/// Accepted frames remembered for matching against later scheduling and LCD writes.
+const RECENT_FRAMES: usize = 64;
+const PREVIEW_CHARS: usize = 48;
+
+/// Correlation ID handed out when a line is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct TraceId {
+    id: u64,
+    rx_at: Instant,
+}
+
+pub(super) struct FrameTracer {
+    enabled: bool,
+    next_id: u64,
+    recent: VecDeque<(RenderFrame, TraceId)>,
+    /// Scheduled frame whose first LCD write has not been logged yet.
+    awaiting_write: Option<TraceId>,
+}
+
+impl FrameTracer {
+    pub(super) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            next_id: 1,
+            recent: VecDeque::new(),
+            awaiting_write: None,
+        }
+    }
+
+    /// Assign the next ID to `line` and log its arrival; `None` while tracing is off.
+    pub(super) fn rx(&mut self, line: &str, now: Instant, logger: &Logger) -> Option<TraceId> {
+        if !self.enabled {
+            return None;
+        }
+        let trace = TraceId {
+            id: self.next_id,
+            rx_at: now,
+        };
+        self.next_id += 1;
+        let mut hasher = Hasher::new();
+        hasher.update(line.as_bytes());
+        let preview: String = line
+            .chars()
+            .take(PREVIEW_CHARS)
+            .map(|ch| if ch.is_control() { ' ' } else { ch })
+            .collect();
+        let ellipsis = if line.chars().count() > PREVIEW_CHARS {
+            "..."
+        } else {
+            ""
+        };
+        logger.info(trace_line(
+            trace,
+            now,
+            "rx",
+            format_args!(
+                "len={} crc={:08x} {preview}{ellipsis}",
+                line.len(),
+                hasher.finalize()
+            ),
+        ));
+        Some(trace)
+    }
+
+    /// Log a stage for a traced line; a no-op for untraced ones.
+    pub(super) fn note(
+        &self,
+        trace: Option<TraceId>,
+        stage: &str,
+        detail: fmt::Arguments<'_>,
+        now: Instant,
+        logger: &Logger,
+    ) {
+        if let Some(trace) = trace {
+            logger.info(trace_line(trace, now, stage, detail));
+        }
+    }
+
+    /// Remember the frame a traced line produced so later stages can be matched to it.
+    pub(super) fn accepted(
+        &mut self,
+        trace: Option<TraceId>,
+        frame: &RenderFrame,
+        queued: usize,
+        now: Instant,
+        logger: &Logger,
+    ) {
+        let Some(trace) = trace else {
+            return;
+        };
+        logger.info(trace_line(
+            trace,
+            now,
+            "ingest",
+            format_args!("accepted ({queued} pages queued)"),
+        ));
+        self.recent.retain(|(known, _)| known != frame);
+        if self.recent.len() >= RECENT_FRAMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((frame.clone(), trace));
+    }
+
+    /// Log that `frame` was picked for the display; returns the matching trace ID, if any.
+    pub(super) fn scheduled(
+        &mut self,
+        frame: &RenderFrame,
+        reason: &str,
+        now: Instant,
+        logger: &Logger,
+    ) -> Option<u64> {
+        let trace = self.lookup(frame)?;
+        logger.info(trace_line(trace, now, "schedule", format_args!("{reason}")));
+        self.awaiting_write = Some(trace);
+        Some(trace.id)
+    }
+
+    /// Log the first LCD write after `frame` was scheduled; later scroll and blink redraws of
+    /// the same page stay quiet. Returns the trace ID logged, if any.
+    pub(super) fn lcd_write(
+        &mut self,
+        frame: Option<&RenderFrame>,
+        elapsed: Duration,
+        now: Instant,
+        logger: &Logger,
+    ) -> Option<u64> {
+        let awaiting = self.awaiting_write?;
+        let trace = frame.and_then(|frame| self.lookup(frame))?;
+        if trace != awaiting {
+            return None;
+        }
+        self.awaiting_write = None;
+        logger.info(trace_line(
+            trace,
+            now,
+            "lcd",
+            format_args!("written in {:.1}ms", elapsed.as_secs_f64() * 1000.0),
+        ));
+        Some(trace.id)
+    }
+
+    fn lookup(&self, frame: &RenderFrame) -> Option<TraceId> {
+        if !self.enabled {
+            return None;
+        }
+        self.recent
+            .iter()
+            .rev()
+            .find(|(known, _)| known == frame)
+            .map(|(_, trace)| *trace)
+    }
+}
+
+fn trace_line(trace: TraceId, now: Instant, stage: &str, detail: fmt::Arguments<'_>) -> String {
+    let since = now.saturating_duration_since(trace.rx_at);
+    format!(
+        "trace #{} +{:.1}ms {stage}: {detail}",
+        trace.id,
+        since.as_secs_f64() * 1000.0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::logger::LogLevel;
+
+    fn frame(line1: &str) -> RenderFrame {
+        RenderFrame::from_payload_json(&format!(
+            r#"{{"schema_version":1,"line1":"{line1}","line2":""}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn ids_are_only_assigned_while_enabled() {
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let now = Instant::now();
+        assert_eq!(FrameTracer::new(false).rx("{}", now, &logger), None);
+
+        let mut tracer = FrameTracer::new(true);
+        let first = tracer.rx("{}", now, &logger).unwrap();
+        let second = tracer.rx("{}", now, &logger).unwrap();
+        assert_eq!((first.id, second.id), (1, 2));
+        let line = trace_line(
+            second,
+            now + Duration::from_micros(2500),
+            "parse",
+            format_args!("ok"),
+        );
+        assert_eq!(line, "trace #2 +2.5ms parse: ok");
+    }
+
+    #[test]
+    fn scheduled_frames_map_back_to_their_line_and_log_one_write() {
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let now = Instant::now();
+        let mut tracer = FrameTracer::new(true);
+        let a = frame("A");
+        let b = frame("B");
+        let trace_a = tracer.rx("a", now, &logger);
+        tracer.accepted(trace_a, &a, 1, now, &logger);
+        let trace_b = tracer.rx("b", now, &logger);
+        tracer.accepted(trace_b, &b, 2, now, &logger);
+
+        let write = Duration::from_millis(1);
+        assert_eq!(tracer.lcd_write(Some(&a), write, now, &logger), None);
+        assert_eq!(tracer.scheduled(&a, "page rotation", now, &logger), Some(1));
+        assert_eq!(tracer.lcd_write(Some(&b), write, now, &logger), None);
+        assert_eq!(tracer.lcd_write(Some(&a), write, now, &logger), Some(1));
+        assert_eq!(tracer.lcd_write(Some(&a), write, now, &logger), None);
+
+        assert_eq!(tracer.scheduled(&b, "page rotation", now, &logger), Some(2));
+        assert_eq!(
+            tracer.scheduled(&frame("C"), "page rotation", now, &logger),
+            None
+        );
+    }
+}
@@ -0,0 +1,512 @@
+//! Guided troubleshooting after repeated failed starts.
+//!
+//! Every daemon start records whether it could open the LCD and the serial port in
+//! `/run/serial_lcd_cache/startup.json`. Once `[troubleshoot].after_failures` starts in a row
+//! have failed, the daemon stops showing a bare `RECONNECTING` and instead names the likely
+//! cause (device missing, permission, busy, ...) on the LCD and in the log, optionally followed
+//! by the read-only doctor checks. The count resets on the first successful connect.
+
+use super::{AppConfig, Logger};
+use crate::{
+    cache_dir,
+    config::TroubleshootConfig,
+    display::{
+        backend::DisplayBackend,
+        overlays::{render_incident_page, render_reconnecting},
+    },
+    serial::{classify_error, SerialFailureKind},
+    Error, Result,
+};
+use rustix::fs::{access, Access};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const STARTUP_FILE: &str = "startup.json";
+const DEFAULT_I2C_BUS: &str = "/dev/i2c-1";
+
+/// Hardware the daemon failed to open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    Serial,
+    Lcd,
+}
+
+impl Component {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Component::Serial => "serial",
+            Component::Lcd => "lcd",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct StartFailure {
+    component: Component,
+    kind: SerialFailureKind,
+}
+
+/// Contents of the startup state file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct StartupRecord {
+    consecutive_failures: u32,
+    last: Option<StartFailure>,
+}
+
+/// What to tell the operator about one failure: two LCD lines plus a log sentence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub title: &'static str,
+    pub detail: String,
+    pub advice: String,
+}
+
+/// Build the hint for a failure to open `component`.
+pub fn hint_for(component: Component, kind: SerialFailureKind, config: &AppConfig) -> Hint {
+    let device = config.device.as_str();
+    let (title, detail, advice) = match (component, kind) {
+        (Component::Serial, SerialFailureKind::DeviceMissing) => (
+            "NO SERIAL DEV",
+            device.to_string(),
+            format!(
+                "{device} does not exist: check the cable and adapter, list /dev/serial/by-id, \
+                 or point --device at the right port"
+            ),
+        ),
+        (Component::Serial, SerialFailureKind::PermissionDenied) => (
+            "SERIAL DENIED",
+            "join dialout grp".to_string(),
+            format!(
+                "no permission to open {device}: add the service user to the dialout group \
+                 (usermod -aG dialout <user>) or fix the udev rule"
+            ),
+        ),
+        (Component::Serial, SerialFailureKind::Busy) => (
+            "SERIAL BUSY",
+            "port in use".to_string(),
+            format!(
+                "{device} is held by another process (a getty, ModemManager or a second \
+                 daemon?): stop it or use another port"
+            ),
+        ),
+        (Component::Serial, SerialFailureKind::Config) => (
+            "SERIAL CONFIG",
+            "check settings".to_string(),
+            format!(
+                "{device} rejected the port settings: check baud, parity, stop_bits and \
+                 flow_control"
+            ),
+        ),
+        (
+            Component::Serial,
+            SerialFailureKind::Timeout
+            | SerialFailureKind::Disconnected
+            | SerialFailureKind::Framing,
+        ) => (
+            "SERIAL NO LINK",
+            "check peer/baud".to_string(),
+            format!(
+                "{device} opened but the link failed ({kind}): check the peer is running and \
+                 both sides use {} baud",
+                config.baud
+            ),
+        ),
+        (Component::Serial, SerialFailureKind::Unknown) => (
+            "SERIAL FAILED",
+            "see log".to_string(),
+            format!("{device} could not be opened; run with --log-level debug for details"),
+        ),
+        (Component::Lcd, SerialFailureKind::DeviceMissing) => (
+            "NO I2C BUS",
+            "enable i2c".to_string(),
+            "no I2C bus found: enable I2C (raspi-config nonint do_i2c 0) and reboot".to_string(),
+        ),
+        (Component::Lcd, SerialFailureKind::PermissionDenied) => (
+            "I2C DENIED",
+            "join i2c group".to_string(),
+            "no permission to open the I2C bus: add the service user to the i2c group".to_string(),
+        ),
+        (Component::Lcd, SerialFailureKind::Busy) => (
+            "I2C BUSY",
+            "bus in use".to_string(),
+            "the I2C bus is held by another process: stop it or set lcd_present = false"
+                .to_string(),
+        ),
+        (Component::Lcd, _) => (
+            "LCD NO ANSWER",
+            "check wiring".to_string(),
+            format!(
+                "the LCD did not answer ({kind}): check wiring, power and pcf8574_addr \
+                 (i2cdetect -y 1)"
+            ),
+        ),
+    };
+    Hint {
+        title,
+        detail,
+        advice,
+    }
+}
+
+/// One read-only doctor check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// Inspect the serial device, I2C bus and cache directory without opening anything.
+pub fn run_doctor(config: &AppConfig) -> Vec<DoctorCheck> {
+    let mut checks = vec![check_serial_device(Path::new(&config.device))];
+    if config.lcd_present {
+        checks.push(check_i2c_bus(Path::new("/dev")));
+    }
+    checks.push(check_cache_dir(&cache_dir()));
+    checks
+}
+
+fn check_serial_device(device: &Path) -> DoctorCheck {
+    let name = "serial device";
+    let shown = device.display();
+    if !device.exists() {
+        return DoctorCheck {
+            name,
+            ok: false,
+            detail: format!("{shown} not found"),
+        };
+    }
+    if access(device, Access::READ_OK | Access::WRITE_OK).is_err() {
+        return DoctorCheck {
+            name,
+            ok: false,
+            detail: format!("{shown} exists but this user cannot read and write it"),
+        };
+    }
+    let holders = holders_of(device);
+    if holders.is_empty() {
+        return DoctorCheck {
+            name,
+            ok: true,
+            detail: format!("{shown} is present and accessible"),
+        };
+    }
+    let listed: Vec<String> = holders
+        .iter()
+        .map(|(pid, comm)| format!("{pid} ({comm})"))
+        .collect();
+    DoctorCheck {
+        name,
+        ok: false,
+        detail: format!("{shown} is open in pid {}", listed.join(", ")),
+    }
+}
+
+fn check_i2c_bus(dev_dir: &Path) -> DoctorCheck {
+    let name = "i2c bus";
+    let mut buses: Vec<String> = fs::read_dir(dev_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|file| file.starts_with("i2c-"))
+                .collect()
+        })
+        .unwrap_or_default();
+    buses.sort();
+    if buses.is_empty() {
+        return DoctorCheck {
+            name,
+            ok: false,
+            detail: format!("no i2c-* devices under {}", dev_dir.display()),
+        };
+    }
+    let default_bus = Path::new(DEFAULT_I2C_BUS);
+    if default_bus.exists() && access(default_bus, Access::READ_OK | Access::WRITE_OK).is_err() {
+        return DoctorCheck {
+            name,
+            ok: false,
+            detail: format!("{DEFAULT_I2C_BUS} exists but this user cannot read and write it"),
+        };
+    }
+    DoctorCheck {
+        name,
+        ok: true,
+        detail: format!("found {}", buses.join(", ")),
+    }
+}
+
+fn check_cache_dir(dir: &Path) -> DoctorCheck {
+    let ok = access(dir, Access::WRITE_OK).is_ok();
+    DoctorCheck {
+        name: "cache dir",
+        ok,
+        detail: if ok {
+            format!("{} is writable", dir.display())
+        } else {
+            format!("{} is missing or read-only", dir.display())
+        },
+    }
+}
+
+/// Other processes with `device` open, found by scanning `/proc/<pid>/fd`.
+fn holders_of(device: &Path) -> Vec<(u32, String)> {
+    let Ok(target) = fs::canonicalize(device) else {
+        return Vec::new();
+    };
+    let Ok(procs) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let own_pid = std::process::id();
+    let mut holders = Vec::new();
+    for entry in procs.filter_map(|entry| entry.ok()) {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let holds = fds
+            .filter_map(|fd| fd.ok())
+            .any(|fd| fs::read_link(fd.path()).is_ok_and(|link| link == target));
+        if holds {
+            let comm = fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            holders.push((pid, comm.trim().to_string()));
+        }
+    }
+    holders
+}
+
+/// Tracks consecutive failed starts and the hint shown while in diagnostic mode.
+pub struct StartupDiagnostics {
+    path: PathBuf,
+    after_failures: u32,
+    doctor: bool,
+    active: Option<(SerialFailureKind, Hint)>,
+    resolved: bool,
+}
+
+impl StartupDiagnostics {
+    pub fn new(config: &TroubleshootConfig) -> Self {
+        Self::with_path(config, cache_dir().join(STARTUP_FILE))
+    }
+
+    fn with_path(config: &TroubleshootConfig, path: PathBuf) -> Self {
+        Self {
+            path,
+            after_failures: config.after_failures,
+            doctor: config.doctor,
+            active: None,
+            resolved: false,
+        }
+    }
+
+    /// Hint to render instead of `RECONNECTING`, while in diagnostic mode.
+    pub fn hint(&self) -> Option<&Hint> {
+        self.active.as_ref().map(|(_, hint)| hint)
+    }
+
+    /// Count this start as failed. Enters diagnostic mode (logging the hint and, if enabled,
+    /// the doctor checks) once the threshold is reached; returns whether it did.
+    pub fn record_failure(
+        &mut self,
+        component: Component,
+        kind: SerialFailureKind,
+        config: &AppConfig,
+        logger: &Logger,
+    ) -> bool {
+        let mut record = self.load();
+        record.consecutive_failures = record.consecutive_failures.saturating_add(1);
+        record.last = Some(StartFailure { component, kind });
+        if let Err(err) = self.store(&record) {
+            logger.debug(format!("troubleshoot: state write failed: {err}"));
+        }
+        let failures = record.consecutive_failures;
+        if self.after_failures == 0 || failures < self.after_failures {
+            return false;
+        }
+        let hint = hint_for(component, kind, config);
+        logger.warn(format!(
+            "troubleshoot: {failures} starts in a row failed to open the {}: {}",
+            component.as_str(),
+            hint.advice
+        ));
+        if self.doctor {
+            for check in run_doctor(config) {
+                let status = if check.ok { "ok" } else { "FAIL" };
+                logger.warn(format!(
+                    "troubleshoot: doctor [{status}] {}: {}",
+                    check.name, check.detail
+                ));
+            }
+        }
+        self.active = Some((kind, hint));
+        true
+    }
+
+    /// Count this start as failed because the LCD could not be opened.
+    pub fn record_lcd_failure(&mut self, err: &Error, config: &AppConfig, logger: &Logger) {
+        self.record_failure(Component::Lcd, classify_error(err), config, logger);
+    }
+
+    /// A serial retry failed while in diagnostic mode; returns true when the cause changed and
+    /// the LCD should be redrawn.
+    pub fn retry_failed(
+        &mut self,
+        kind: SerialFailureKind,
+        config: &AppConfig,
+        logger: &Logger,
+    ) -> bool {
+        match &self.active {
+            Some((current, _)) if *current != kind => {
+                let hint = hint_for(Component::Serial, kind, config);
+                logger.warn(format!("troubleshoot: cause changed: {}", hint.advice));
+                self.active = Some((kind, hint));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The serial port opened; clear the failure count and leave diagnostic mode.
+    pub fn record_success(&mut self, logger: &Logger) {
+        if self.resolved {
+            return;
+        }
+        self.resolved = true;
+        match fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => logger.debug(format!("troubleshoot: state reset failed: {err}")),
+        }
+        if self.active.take().is_some() {
+            logger.info("troubleshoot: serial connected; leaving diagnostic mode");
+        }
+    }
+
+    /// Draw the link-down screen: the active hint, or the usual `RECONNECTING`.
+    pub fn render_link_down<D: DisplayBackend>(&self, lcd: &mut D, cols: u8) -> Result<()> {
+        match self.hint() {
+            Some(hint) => render_incident_page(lcd, cols, hint.title, &hint.detail),
+            None => render_reconnecting(lcd, cols),
+        }
+    }
+
+    fn load(&self) -> StartupRecord {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    fn store(&self, record: &StartupRecord) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string(record).map_err(std::io::Error::other)?;
+        fs::write(&self.path, format!("{json}\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::logger::LogLevel;
+
+    fn diagnostics(path: PathBuf) -> StartupDiagnostics {
+        StartupDiagnostics::with_path(
+            &TroubleshootConfig {
+                after_failures: 3,
+                doctor: false,
+            },
+            path,
+        )
+    }
+
+    #[test]
+    fn enters_after_consecutive_failed_starts_and_resets_on_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STARTUP_FILE);
+        let config = AppConfig::default();
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let kind = SerialFailureKind::Busy;
+
+        for _ in 0..2 {
+            let mut start = diagnostics(path.clone());
+            assert!(!start.record_failure(Component::Serial, kind, &config, &logger));
+            assert!(start.hint().is_none());
+        }
+        let mut start = diagnostics(path.clone());
+        assert!(start.record_failure(Component::Serial, kind, &config, &logger));
+        assert_eq!(start.hint().unwrap().title, "SERIAL BUSY");
+
+        start.record_success(&logger);
+        assert!(start.hint().is_none());
+        assert!(!path.exists());
+        let mut next = diagnostics(path);
+        assert!(!next.record_failure(Component::Serial, kind, &config, &logger));
+    }
+
+    #[test]
+    fn retry_with_a_new_cause_updates_the_hint() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let logger = Logger::new(LogLevel::Error, None).unwrap();
+        let mut start = StartupDiagnostics::with_path(
+            &TroubleshootConfig {
+                after_failures: 1,
+                doctor: false,
+            },
+            dir.path().join(STARTUP_FILE),
+        );
+        let missing = SerialFailureKind::DeviceMissing;
+        assert!(start.record_failure(Component::Serial, missing, &config, &logger));
+        assert!(!start.retry_failed(missing, &config, &logger));
+        assert!(start.retry_failed(SerialFailureKind::PermissionDenied, &config, &logger));
+        assert_eq!(start.hint().unwrap().title, "SERIAL DENIED");
+    }
+
+    #[test]
+    fn hints_distinguish_missing_denied_and_busy() {
+        let config = AppConfig::default();
+        let titles: Vec<&str> = [
+            SerialFailureKind::DeviceMissing,
+            SerialFailureKind::PermissionDenied,
+            SerialFailureKind::Busy,
+        ]
+        .into_iter()
+        .map(|kind| hint_for(Component::Serial, kind, &config).title)
+        .collect();
+        assert_eq!(titles, ["NO SERIAL DEV", "SERIAL DENIED", "SERIAL BUSY"]);
+        assert!(
+            hint_for(Component::Serial, SerialFailureKind::DeviceMissing, &config)
+                .advice
+                .contains(&config.device)
+        );
+        assert_eq!(
+            hint_for(Component::Lcd, SerialFailureKind::Unknown, &config).title,
+            "LCD NO ANSWER"
+        );
+    }
+
+    #[test]
+    fn doctor_flags_a_missing_device() {
+        let dir = tempfile::tempdir().unwrap();
+        let check = check_serial_device(&dir.path().join("ttyNOPE"));
+        assert!(!check.ok);
+        assert!(check.detail.ends_with("not found"));
+        assert!(!check_i2c_bus(dir.path()).ok);
+        assert!(check_cache_dir(dir.path()).ok);
+    }
+}
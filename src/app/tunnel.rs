@@ -1,24 +1,45 @@
+use super::command_cache::CacheRequest;
+use super::idle::LoopWaker;
+use super::interlock::unix_ms;
+use super::remote_config;
+use super::tail::{resolve_allowed, TailSession};
+use super::tunnel_auth::CommandAuth;
+use super::tunnel_files::{TransferProgress, TunnelFiles};
 use super::Logger;
-use crate::app::events::{CommandEvent, CommandExecutor};
+use crate::app::events::{CommandEvent, CommandExecutor, INTERLOCK_REJECTION};
 use crate::{
+    cache_dir,
+    config::loader,
     payload::{CommandMessage, CommandStream, TunnelMsgOwned},
-    Result, CACHE_DIR,
+    Result,
 };
+use std::collections::VecDeque;
 use std::fs::{create_dir_all, OpenOptions};
 use std::io::{ErrorKind, Write};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 pub struct TunnelController {
     executor: CommandExecutor,
     request_counter: AtomicU32,
     tunnel_dir: PathBuf,
+    tail_allowlist: Vec<String>,
+    tail: Option<TailSession>,
+    files: TunnelFiles,
+    queued: VecDeque<TunnelMsgOwned>,
+    locked: bool,
+    /// `[tunnel_auth]` check for tails and `config_set`; the executor and file transfers hold
+    /// clones.
+    auth: CommandAuth,
+    /// Where `config_set` writes; `None` when the config path cannot be resolved.
+    config_path: Option<PathBuf>,
+    config_saved: bool,
 }
 
 impl TunnelController {
-    pub fn new(allowlist: Vec<String>) -> Result<Self> {
-        let tunnel_dir = PathBuf::from(CACHE_DIR).join("tunnel");
+    pub fn new(allowlist: Vec<String>, tail_allowlist: Vec<String>) -> Result<Self> {
+        let tunnel_dir = cache_dir().join("tunnel");
         match create_dir_all(&tunnel_dir) {
             Ok(_) => {}
             Err(err)
@@ -35,19 +56,61 @@ impl TunnelController {
             executor: CommandExecutor::new(allowlist),
             request_counter: AtomicU32::new(1),
             tunnel_dir,
+            files: TunnelFiles::new(cache_dir(), tail_allowlist.clone()),
+            tail_allowlist,
+            tail: None,
+            queued: VecDeque::new(),
+            locked: false,
+            auth: CommandAuth::default(),
+            config_path: loader::default_config_path().ok(),
+            config_saved: false,
         })
     }
 
+    /// Wake the render loop when a tunnel command has output to send.
+    pub fn set_waker(&mut self, waker: LoopWaker) {
+        self.executor.set_waker(waker);
+    }
+
+    /// Reject commands and tail requests while the interlock is disarmed.
+    pub fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+        self.executor.set_locked(locked);
+    }
+
+    /// Require `[tunnel_auth]` codes on command, tail, file transfer and `config_set` requests.
+    pub fn set_auth(&mut self, auth: CommandAuth) {
+        self.files.set_auth(auth.clone());
+        self.executor.set_auth(auth.clone());
+        self.auth = auth;
+    }
+
     pub fn handle_msg(&mut self, msg: TunnelMsgOwned, logger: &Logger) -> Option<TunnelMsgOwned> {
+        if TunnelFiles::handles(&msg) {
+            self.files.handle(msg, self.locked, Instant::now(), logger);
+            return None;
+        }
         match msg {
-            TunnelMsgOwned::CmdRequest { cmd } => {
+            TunnelMsgOwned::CmdRequest {
+                cmd,
+                otp,
+                sig,
+                cache_ttl_ms,
+                no_cache,
+            } => {
                 let request_id = self.request_counter.fetch_add(1, Ordering::SeqCst);
                 let event = CommandEvent::Request {
                     request_id,
                     cmd,
                     scratch_path: None,
+                    otp,
+                    sig,
+                };
+                let cache = CacheRequest {
+                    ttl_ms: cache_ttl_ms,
+                    bypass: no_cache,
                 };
-                if let Some(command_msg) = self.executor.handle_event(event) {
+                if let Some(command_msg) = self.executor.handle_event_with_cache(event, cache) {
                     if let CommandMessage::Error { message, .. } = &command_msg {
                         logger.warn(format!("command error: {message}"));
                     }
@@ -55,17 +118,110 @@ impl TunnelController {
                 }
                 None
             }
+            TunnelMsgOwned::TailFile {
+                ref path,
+                lines,
+                follow,
+                ..
+            } => {
+                // A new request replaces any running tail rather than interleaving two streams.
+                self.tail = None;
+                if self.locked {
+                    logger.warn(format!(
+                        "tunnel: tail of {path} rejected: interlock not armed"
+                    ));
+                    self.queued.push_back(TunnelMsgOwned::Exit { code: 1 });
+                    return Some(TunnelMsgOwned::Stderr {
+                        chunk: format!("{INTERLOCK_REJECTION}\n").into_bytes(),
+                    });
+                }
+                if let Err(reason) = self.auth.check_message(&msg, unix_ms(SystemTime::now())) {
+                    logger.warn(format!("tunnel: tail of {path} rejected: {reason}"));
+                    self.queued.push_back(TunnelMsgOwned::Exit { code: 1 });
+                    return Some(TunnelMsgOwned::Stderr {
+                        chunk: format!("{reason}\n").into_bytes(),
+                    });
+                }
+                let opened = resolve_allowed(&self.tail_allowlist, path).and_then(|resolved| {
+                    TailSession::open(resolved, lines, follow, Instant::now())
+                });
+                match opened {
+                    Ok(session) => {
+                        logger.info(format!("tunnel: tailing {path} (follow={follow})"));
+                        self.tail = Some(session);
+                        None
+                    }
+                    Err(err) => {
+                        logger.warn(format!("tunnel: tail rejected: {err}"));
+                        self.queued.push_back(TunnelMsgOwned::Exit { code: 1 });
+                        Some(TunnelMsgOwned::Stderr {
+                            chunk: format!("{err}\n").into_bytes(),
+                        })
+                    }
+                }
+            }
+            TunnelMsgOwned::TailStop => self.tail.take().map(|_| TunnelMsgOwned::Exit { code: 0 }),
+            TunnelMsgOwned::TermSize { cols, rows } => {
+                logger.debug(format!("tunnel: client terminal is {cols}x{rows}"));
+                self.executor.set_terminal_size(cols, rows);
+                None
+            }
+            TunnelMsgOwned::ConfigGet { key } => Some(match &self.config_path {
+                Some(path) => remote_config::get(path, &key),
+                None => no_config_path(key),
+            }),
+            TunnelMsgOwned::ConfigSet {
+                ref key, ref value, ..
+            } => {
+                let Some(path) = &self.config_path else {
+                    return Some(no_config_path(key.clone()));
+                };
+                let reply = remote_config::set(path, &msg, self.locked, &self.auth);
+                match &reply {
+                    TunnelMsgOwned::ConfigValue {
+                        error: Some(err), ..
+                    } => {
+                        logger.warn(format!("tunnel: config_set {key} rejected: {err}"));
+                    }
+                    _ => {
+                        logger.info(format!("tunnel: peer set {key} = {value}"));
+                        self.config_saved = true;
+                    }
+                }
+                Some(reply)
+            }
             _ => None,
         }
     }
 
+    /// Whether a `config_set` wrote the config file since the last call.
+    pub fn take_config_saved(&mut self) -> bool {
+        std::mem::take(&mut self.config_saved)
+    }
+
     pub fn next_outgoing(&mut self) -> Option<TunnelMsgOwned> {
         while let Some(msg) = self.executor.next_outgoing() {
             if let Some(frame) = command_message_to_tunnel(msg) {
                 return Some(frame);
             }
         }
-        None
+        if let Some(msg) = self.queued.pop_front() {
+            return Some(msg);
+        }
+        if let Some(msg) = self.files.next_outgoing(Instant::now()) {
+            return Some(msg);
+        }
+        let tail = self.tail.as_mut()?;
+        let msg = tail.poll(Instant::now());
+        if tail.is_finished() {
+            self.tail = None;
+        }
+        msg
+    }
+
+    /// The push or pull in progress, for the LCD progress page.
+    pub fn transfer_progress(&self) -> Option<TransferProgress> {
+        self.files.progress()
     }
 
     pub fn log_frame_error(&self, detail: &str, raw: &str) {
@@ -81,6 +237,14 @@ impl TunnelController {
     }
 }
 
+fn no_config_path(key: String) -> TunnelMsgOwned {
+    TunnelMsgOwned::ConfigValue {
+        key,
+        value: None,
+        error: Some("config path unavailable".into()),
+    }
+}
+
 fn command_message_to_tunnel(msg: CommandMessage) -> Option<TunnelMsgOwned> {
     match msg {
         CommandMessage::Chunk { stream, data, .. } => match stream {
@@ -132,20 +296,33 @@ mod tests {
     #[cfg(unix)]
     #[test]
     fn busy_response_blocks_concurrent_commands() {
-        let mut controller = TunnelController::new(Vec::new()).unwrap();
+        let mut controller = TunnelController::new(Vec::new(), Vec::new()).unwrap();
         let logger = Logger::new(LogLevel::Info, None).unwrap();
 
         assert!(controller
             .handle_msg(
                 TunnelMsgOwned::CmdRequest {
                     cmd: "sleep 1".into(),
+                    otp: None,
+                    sig: None,
+                    cache_ttl_ms: None,
+                    no_cache: false,
                 },
                 &logger,
             )
             .is_none());
 
         let busy = controller
-            .handle_msg(TunnelMsgOwned::CmdRequest { cmd: "true".into() }, &logger)
+            .handle_msg(
+                TunnelMsgOwned::CmdRequest {
+                    cmd: "true".into(),
+                    otp: None,
+                    sig: None,
+                    cache_ttl_ms: None,
+                    no_cache: false,
+                },
+                &logger,
+            )
             .expect("expected Busy response");
         assert!(matches!(busy, TunnelMsgOwned::Busy));
 
@@ -153,7 +330,16 @@ mod tests {
         assert!(matches!(exit, TunnelMsgOwned::Exit { code: 0 }));
 
         assert!(controller
-            .handle_msg(TunnelMsgOwned::CmdRequest { cmd: "true".into() }, &logger,)
+            .handle_msg(
+                TunnelMsgOwned::CmdRequest {
+                    cmd: "true".into(),
+                    otp: None,
+                    sig: None,
+                    cache_ttl_ms: None,
+                    no_cache: false,
+                },
+                &logger,
+            )
             .is_none());
 
         let final_exit = wait_for_exit(&mut controller, Duration::from_secs(5));
@@ -163,13 +349,17 @@ mod tests {
     #[cfg(unix)]
     #[test]
     fn streams_stdout_chunks_before_exit() {
-        let mut controller = TunnelController::new(vec!["echo".into()]).unwrap();
+        let mut controller = TunnelController::new(vec!["echo".into()], Vec::new()).unwrap();
         let logger = Logger::new(LogLevel::Info, None).unwrap();
 
         assert!(controller
             .handle_msg(
                 TunnelMsgOwned::CmdRequest {
                     cmd: "echo hello".into(),
+                    otp: None,
+                    sig: None,
+                    cache_ttl_ms: None,
+                    no_cache: false,
                 },
                 &logger,
             )
@@ -199,4 +389,157 @@ mod tests {
         assert_eq!(exit_code, Some(0));
         assert!(String::from_utf8_lossy(&stdout).contains("hello"));
     }
+
+    #[test]
+    fn tail_outside_allowlist_is_rejected_with_exit() {
+        let mut controller = TunnelController::new(Vec::new(), Vec::new()).unwrap();
+        let logger = Logger::new(crate::app::logger::LogLevel::Info, None).unwrap();
+        let reply = controller.handle_msg(
+            TunnelMsgOwned::TailFile {
+                path: "/etc/passwd".into(),
+                lines: 10,
+                follow: false,
+                otp: None,
+                sig: None,
+            },
+            &logger,
+        );
+        assert!(matches!(reply, Some(TunnelMsgOwned::Stderr { .. })));
+        assert_eq!(
+            controller.next_outgoing(),
+            Some(TunnelMsgOwned::Exit { code: 1 })
+        );
+        assert!(controller.next_outgoing().is_none());
+    }
+
+    #[test]
+    fn locked_controller_rejects_commands_and_tails() {
+        let mut controller = TunnelController::new(Vec::new(), Vec::new()).unwrap();
+        let logger = Logger::new(crate::app::logger::LogLevel::Error, None).unwrap();
+        controller.set_locked(true);
+        let reply = controller.handle_msg(
+            TunnelMsgOwned::CmdRequest {
+                cmd: "true".into(),
+                otp: None,
+                sig: None,
+                cache_ttl_ms: None,
+                no_cache: false,
+            },
+            &logger,
+        );
+        assert_eq!(
+            reply,
+            Some(TunnelMsgOwned::Stderr {
+                chunk: INTERLOCK_REJECTION.as_bytes().to_vec(),
+            })
+        );
+        let reply = controller.handle_msg(
+            TunnelMsgOwned::TailFile {
+                path: "/etc/passwd".into(),
+                lines: 10,
+                follow: false,
+                otp: None,
+                sig: None,
+            },
+            &logger,
+        );
+        assert!(matches!(reply, Some(TunnelMsgOwned::Stderr { .. })));
+        assert_eq!(
+            controller.next_outgoing(),
+            Some(TunnelMsgOwned::Stderr {
+                chunk: INTERLOCK_REJECTION.as_bytes().to_vec(),
+            })
+        );
+    }
+
+    #[test]
+    fn unauthenticated_tails_and_transfers_are_rejected() {
+        let mut controller = TunnelController::new(Vec::new(), Vec::new()).unwrap();
+        let logger = Logger::new(crate::app::logger::LogLevel::Error, None).unwrap();
+        controller.set_auth(CommandAuth::from_config(&crate::config::TunnelAuthConfig {
+            backend: crate::config::TunnelAuthBackend::Totp,
+            secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".into(),
+            period_secs: 30,
+            skew_steps: 1,
+        }));
+
+        let reply = controller.handle_msg(
+            TunnelMsgOwned::TailFile {
+                path: "/var/log/syslog".into(),
+                lines: 10,
+                follow: false,
+                otp: None,
+                sig: None,
+            },
+            &logger,
+        );
+        assert_eq!(
+            reply,
+            Some(TunnelMsgOwned::Stderr {
+                chunk: b"command rejected: TOTP code required\n".to_vec(),
+            })
+        );
+        assert_eq!(
+            controller.next_outgoing(),
+            Some(TunnelMsgOwned::Exit { code: 1 })
+        );
+
+        for request in [
+            TunnelMsgOwned::FilePush {
+                path: "fw.bin".into(),
+                size: 4,
+                crc32: 0,
+                otp: None,
+                sig: None,
+            },
+            TunnelMsgOwned::FilePull {
+                path: "fw.bin".into(),
+                otp: Some("000000".into()),
+                sig: None,
+            },
+        ] {
+            assert_eq!(controller.handle_msg(request, &logger), None);
+            let Some(TunnelMsgOwned::FileEnd { ok, error }) = controller.next_outgoing() else {
+                panic!("expected file_end");
+            };
+            assert!(!ok);
+            assert!(error.unwrap().starts_with("command rejected: "));
+            assert!(controller.transfer_progress().is_none());
+        }
+    }
+
+    #[test]
+    fn unauthenticated_config_set_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "cols = 16\n").unwrap();
+        let mut controller = TunnelController::new(Vec::new(), Vec::new()).unwrap();
+        controller.config_path = Some(path.clone());
+        let logger = Logger::new(crate::app::logger::LogLevel::Error, None).unwrap();
+        controller.set_auth(CommandAuth::from_config(&crate::config::TunnelAuthConfig {
+            backend: crate::config::TunnelAuthBackend::Hmac,
+            secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".into(),
+            period_secs: 30,
+            skew_steps: 1,
+        }));
+        let reply = controller.handle_msg(
+            TunnelMsgOwned::ConfigSet {
+                key: "cols".into(),
+                value: "20".into(),
+                otp: None,
+                sig: None,
+            },
+            &logger,
+        );
+        assert_eq!(
+            reply,
+            Some(TunnelMsgOwned::ConfigValue {
+                key: "cols".into(),
+                value: None,
+                error: Some("command rejected: signature required".into()),
+            })
+        );
+        assert!(!controller.take_config_saved());
+        assert_eq!(loader::get_key(&path, "cols").unwrap(), 16);
+    }
 }
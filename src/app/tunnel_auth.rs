@@ -0,0 +1,538 @@
+//! Authorization backends for tunnel commands, checked after the interlock and before the
+//! allowlist.
+//!
+//! The `totp` backend expects every command `Request` to carry an RFC 6238 code: HMAC-SHA1
+//! over the 30-second step counter, dynamically truncated to six digits. Codes from
+//! `skew_steps` periods either side are accepted, and each step is good for one command only
+//! (RFC 6238 §5.2), so a code sniffed off the wire cannot be replayed. Both command channels
+//! share one [`CommandAuth`] so a code spent on one is spent on the other.
+//!
+//! The `hmac` backend signs the request itself: `sig` is `<unix ms>.<hex HMAC-SHA256>` over
+//! the timestamp, the command line and the scratch path, so a device on the UART can neither
+//! forge a request nor reuse a captured signature for another command. The timestamp must be
+//! within `period_secs` of the daemon's clock and newer than the last one accepted.
+//!
+//! Tail, file and `config_set` requests carry the same `otp` or `sig` fields. They have no command line, so
+//! their signature covers the line from [`request_line`] instead, which names the path and the
+//! rest of the request.
+
+use super::digest::{constant_time_eq, hmac_sha1, hmac_sha256, to_hex};
+use crate::config::{TunnelAuthBackend, TunnelAuthConfig};
+use crate::payload::TunnelMsgOwned;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Digits in every code, as authenticator apps show them.
+pub const TOTP_DIGITS: u32 = 6;
+
+/// Verifier half, held by the command executors.
+#[derive(Debug, Clone, Default)]
+pub struct CommandAuth {
+    totp: Option<Totp>,
+    hmac: Option<Hmac>,
+}
+
+#[derive(Debug, Clone)]
+struct Totp {
+    secret: Vec<u8>,
+    period_secs: u64,
+    skew_steps: u8,
+    /// Newest step a code was accepted for, plus one; 0 before the first.
+    spent: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Clone)]
+struct Hmac {
+    secret: Vec<u8>,
+    window_ms: u64,
+    /// Timestamp of the newest accepted signature; 0 before the first.
+    spent: Arc<AtomicU64>,
+}
+
+impl CommandAuth {
+    /// Build the verifier for `[tunnel_auth]`; validation has already checked the secret.
+    pub fn from_config(config: &TunnelAuthConfig) -> Self {
+        let mut auth = Self::default();
+        match config.backend {
+            TunnelAuthBackend::None => {}
+            TunnelAuthBackend::Totp => {
+                auth.totp = decode_base32(&config.secret).map(|secret| Totp {
+                    secret,
+                    period_secs: config.period_secs,
+                    skew_steps: config.skew_steps,
+                    spent: Arc::new(AtomicU64::new(0)),
+                })
+            }
+            TunnelAuthBackend::Hmac => {
+                auth.hmac = decode_base32(&config.secret).map(|secret| Hmac {
+                    secret,
+                    window_ms: config.period_secs * 1_000,
+                    spent: Arc::new(AtomicU64::new(0)),
+                })
+            }
+        }
+        auth
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.totp.is_some() || self.hmac.is_some()
+    }
+
+    /// Check a request against whichever backend is configured; the error is sent back as-is.
+    pub fn check(
+        &self,
+        request: &SignedRequest<'_>,
+        otp: Option<&str>,
+        sig: Option<&str>,
+        unix_ms: u64,
+    ) -> Result<(), String> {
+        self.authorize(otp, unix_ms / 1_000)?;
+        self.verify_signature(request, sig, unix_ms)
+    }
+
+    /// Check a tail, file or `config_set` request the way command requests are checked; other
+    /// messages pass.
+    pub fn check_message(&self, msg: &TunnelMsgOwned, unix_ms: u64) -> Result<(), String> {
+        let Some(line) = request_line(msg) else {
+            return Ok(());
+        };
+        let (otp, sig) = credentials(msg);
+        let request = SignedRequest {
+            cmd: &line,
+            scratch_path: None,
+        };
+        self.check(&request, otp, sig, unix_ms)
+    }
+
+    /// Check the `hmac` signature sent with a request at `unix_ms`.
+    pub fn verify_signature(
+        &self,
+        request: &SignedRequest<'_>,
+        sig: Option<&str>,
+        unix_ms: u64,
+    ) -> Result<(), String> {
+        let Some(hmac) = &self.hmac else {
+            return Ok(());
+        };
+        let Some(sig) = sig.map(str::trim).filter(|sig| !sig.is_empty()) else {
+            return Err("command rejected: signature required".into());
+        };
+        let (ts_ms, mac) = sig
+            .split_once('.')
+            .and_then(|(ts, mac)| Some((ts.parse::<u64>().ok()?, mac)))
+            .ok_or_else(|| "command rejected: malformed signature".to_string())?;
+        let expected = request.mac(&hmac.secret, ts_ms);
+        if !constant_time_eq(expected.as_bytes(), mac.to_ascii_lowercase().as_bytes()) {
+            return Err("command rejected: invalid signature".into());
+        }
+        if ts_ms.abs_diff(unix_ms) > hmac.window_ms {
+            return Err("command rejected: signature expired (check both clocks)".into());
+        }
+        hmac.spent
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |spent| {
+                (ts_ms > spent).then_some(ts_ms)
+            })
+            .map(|_| ())
+            .map_err(|_| "command rejected: signature already used".to_string())
+    }
+
+    /// Check the code sent with a request at `unix_secs`; the error is sent back as-is.
+    pub fn authorize(&self, otp: Option<&str>, unix_secs: u64) -> Result<(), String> {
+        let Some(totp) = &self.totp else {
+            return Ok(());
+        };
+        let Some(otp) = otp.map(str::trim).filter(|otp| !otp.is_empty()) else {
+            return Err("command rejected: TOTP code required".into());
+        };
+        let now = unix_secs / totp.period_secs;
+        let skew = totp.skew_steps as u64;
+        let step = (now.saturating_sub(skew)..=now + skew)
+            .find(|&step| {
+                constant_time_eq(totp_code(&totp.secret, step).as_bytes(), otp.as_bytes())
+            })
+            .ok_or_else(|| "command rejected: invalid TOTP code".to_string())?;
+        // Only move forward: a step at or before the last accepted one has been spent.
+        totp.spent
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |spent| {
+                (step >= spent).then_some(step + 1)
+            })
+            .map(|_| ())
+            .map_err(|_| "command rejected: TOTP code already used".to_string())
+    }
+}
+
+/// The parts of a command request a signature covers.
+#[derive(Debug, Clone, Copy)]
+pub struct SignedRequest<'a> {
+    pub cmd: &'a str,
+    pub scratch_path: Option<&'a str>,
+}
+
+impl SignedRequest<'_> {
+    /// Hex HMAC-SHA256 over the timestamp and request, one field per line.
+    fn mac(&self, secret: &[u8], ts_ms: u64) -> String {
+        let message = format!(
+            "lifelinetty-cmd-v1\n{ts_ms}\n{}\n{}",
+            self.cmd,
+            self.scratch_path.unwrap_or_default()
+        );
+        to_hex(&hmac_sha256(secret, message.as_bytes()))
+    }
+}
+
+/// What the signature of a tail, file or `config_set` request covers in place of a command
+/// line, so a signature for one path or value cannot be reused for another. `None` for every
+/// other message.
+pub fn request_line(msg: &TunnelMsgOwned) -> Option<String> {
+    match msg {
+        TunnelMsgOwned::TailFile {
+            path,
+            lines,
+            follow,
+            ..
+        } => Some(format!("tail_file {path} {lines} {follow}")),
+        TunnelMsgOwned::FilePush {
+            path, size, crc32, ..
+        } => Some(format!("file_push {path} {size} {crc32:08x}")),
+        TunnelMsgOwned::FilePull { path, .. } => Some(format!("file_pull {path}")),
+        TunnelMsgOwned::ConfigSet { key, value, .. } => Some(format!("config_set {key} {value}")),
+        _ => None,
+    }
+}
+
+fn credentials(msg: &TunnelMsgOwned) -> (Option<&str>, Option<&str>) {
+    match msg {
+        TunnelMsgOwned::TailFile { otp, sig, .. }
+        | TunnelMsgOwned::FilePush { otp, sig, .. }
+        | TunnelMsgOwned::FilePull { otp, sig, .. }
+        | TunnelMsgOwned::ConfigSet { otp, sig, .. } => (otp.as_deref(), sig.as_deref()),
+        _ => (None, None),
+    }
+}
+
+/// Client half of the `hmac` backend: signs each request with a fresh timestamp.
+#[derive(Debug, Clone)]
+pub struct HmacSigner {
+    secret: Vec<u8>,
+    last_ts_ms: u64,
+}
+
+impl HmacSigner {
+    /// `None` unless `[tunnel_auth]` selects the `hmac` backend.
+    pub fn from_config(config: &TunnelAuthConfig) -> Option<Self> {
+        if config.backend != TunnelAuthBackend::Hmac {
+            return None;
+        }
+        Some(Self {
+            secret: decode_base32(&config.secret)?,
+            last_ts_ms: 0,
+        })
+    }
+
+    /// The `sig` value for `request` at `unix_ms`. Timestamps never repeat, so two commands
+    /// sent within one millisecond still both pass the daemon's replay check.
+    pub fn sign(&mut self, request: &SignedRequest<'_>, unix_ms: u64) -> String {
+        let ts_ms = unix_ms.max(self.last_ts_ms + 1);
+        self.last_ts_ms = ts_ms;
+        format!("{ts_ms}.{}", request.mac(&self.secret, ts_ms))
+    }
+}
+
+/// Client half for `--serialsh`, which adds a fresh code to each command it sends.
+#[derive(Debug, Clone)]
+pub struct TotpSigner {
+    secret: Vec<u8>,
+    period_secs: u64,
+    skew_steps: u8,
+    /// Step the previous command used, which the daemon will not take again.
+    last_step: Option<u64>,
+}
+
+impl TotpSigner {
+    /// `None` unless `[tunnel_auth]` selects the `totp` backend.
+    pub fn from_config(config: &TunnelAuthConfig) -> Option<Self> {
+        if config.backend != TunnelAuthBackend::Totp {
+            return None;
+        }
+        Some(Self {
+            secret: decode_base32(&config.secret)?,
+            period_secs: config.period_secs,
+            skew_steps: config.skew_steps,
+            last_step: None,
+        })
+    }
+
+    /// Code for the next command at `unix_secs`. Commands sent within one period borrow codes
+    /// from the steps ahead that the daemon's skew still accepts; `None` means those are used
+    /// up too and the caller has to wait for the clock to move on.
+    pub fn next_code(&mut self, unix_secs: u64) -> Option<String> {
+        let now = unix_secs / self.period_secs;
+        let step = self.last_step.map_or(now, |last| now.max(last + 1));
+        if step > now + self.skew_steps as u64 {
+            return None;
+        }
+        self.last_step = Some(step);
+        Some(totp_code(&self.secret, step))
+    }
+}
+
+/// What `--serialsh` adds to each command for the configured backend.
+#[derive(Debug, Clone)]
+pub enum RequestSigner {
+    Totp(TotpSigner),
+    Hmac(HmacSigner),
+}
+
+impl RequestSigner {
+    /// `None` when `[tunnel_auth]` needs nothing from the client.
+    pub fn from_config(config: &TunnelAuthConfig) -> Option<Self> {
+        TotpSigner::from_config(config)
+            .map(Self::Totp)
+            .or_else(|| HmacSigner::from_config(config).map(Self::Hmac))
+    }
+
+    /// Fill in the `otp` or `sig` of a tail, file or `config_set` request sent at `unix_ms`. `false` when
+    /// every TOTP code the daemon still accepts has been used and the caller has to wait.
+    pub fn sign_message(&mut self, msg: &mut TunnelMsgOwned, unix_ms: u64) -> bool {
+        let Some(line) = request_line(msg) else {
+            return true;
+        };
+        let (code, signature) = match self {
+            Self::Totp(totp) => match totp.next_code(unix_ms / 1_000) {
+                Some(code) => (Some(code), None),
+                None => return false,
+            },
+            Self::Hmac(hmac) => {
+                let request = SignedRequest {
+                    cmd: &line,
+                    scratch_path: None,
+                };
+                (None, Some(hmac.sign(&request, unix_ms)))
+            }
+        };
+        if let TunnelMsgOwned::TailFile { otp, sig, .. }
+        | TunnelMsgOwned::FilePush { otp, sig, .. }
+        | TunnelMsgOwned::FilePull { otp, sig, .. }
+        | TunnelMsgOwned::ConfigSet { otp, sig, .. } = msg
+        {
+            *otp = code;
+            *sig = signature;
+        }
+        true
+    }
+}
+
+/// RFC 4226 HOTP value for `counter`, zero-padded to [`TOTP_DIGITS`].
+fn totp_code(secret: &[u8], counter: u64) -> String {
+    let mac = hmac_sha1(secret, &counter.to_be_bytes());
+    let offset = (mac[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        mac[offset] & 0x7f,
+        mac[offset + 1],
+        mac[offset + 2],
+        mac[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    )
+}
+
+/// Decode RFC 4648 base32, ignoring case, spaces, dashes and `=` padding.
+pub fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer = 0u64;
+    let mut bits = 0u32;
+    for ch in input.chars() {
+        let value = match ch.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            ' ' | '-' | '=' => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "12345678901234567890", the RFC 6238 appendix B SHA-1 secret.
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    fn totp(skew_steps: u8) -> TunnelAuthConfig {
+        TunnelAuthConfig {
+            backend: TunnelAuthBackend::Totp,
+            secret: RFC_SECRET.into(),
+            period_secs: 30,
+            skew_steps,
+        }
+    }
+
+    #[test]
+    fn codes_match_the_rfc_6238_vectors() {
+        let secret = decode_base32(RFC_SECRET).unwrap();
+        assert_eq!(secret, b"12345678901234567890");
+        // Appendix B lists eight digits; six-digit codes are the low six of the same value.
+        for (time, code) in [
+            (59, "287082"),
+            (1_111_111_109, "081804"),
+            (1_234_567_890, "005924"),
+            (20_000_000_000, "353130"),
+        ] {
+            assert_eq!(totp_code(&secret, time / 30), code, "T={time}");
+        }
+        assert_eq!(decode_base32("mzxw6-ytb oi=").unwrap(), b"foobar");
+        assert_eq!(decode_base32("MZXW1"), None);
+    }
+
+    #[test]
+    fn totp_accepts_skewed_codes_once() {
+        let auth = CommandAuth::from_config(&totp(1));
+        let now = 1_111_111_109;
+        let secret = decode_base32(RFC_SECRET).unwrap();
+        let code = |step: u64| totp_code(&secret, step);
+        let step = now / 30;
+
+        assert_eq!(
+            auth.authorize(None, now).unwrap_err(),
+            "command rejected: TOTP code required"
+        );
+        assert_eq!(
+            auth.authorize(Some("000000"), now).unwrap_err(),
+            "command rejected: invalid TOTP code"
+        );
+        assert!(auth
+            .authorize(Some(&code(step + 2)), now)
+            .unwrap_err()
+            .contains("invalid"));
+
+        assert_eq!(auth.authorize(Some(&code(step - 1)), now), Ok(()));
+        // Clones share the spent step, as the tunnel and command executors do.
+        let other = auth.clone();
+        assert!(other
+            .authorize(Some(&code(step - 1)), now)
+            .unwrap_err()
+            .contains("already used"));
+        assert_eq!(other.authorize(Some(&code(step + 1)), now), Ok(()));
+        assert!(auth
+            .authorize(Some(&code(step)), now)
+            .unwrap_err()
+            .contains("already used"));
+        assert_eq!(auth.authorize(Some(&code(step + 2)), now + 30), Ok(()));
+    }
+
+    #[test]
+    fn signer_borrows_steps_ahead_then_waits() {
+        let config = totp(1);
+        let auth = CommandAuth::from_config(&config);
+        let mut signer = TotpSigner::from_config(&config).unwrap();
+        let now = 1_111_111_109;
+
+        let first = signer.next_code(now).unwrap();
+        let second = signer.next_code(now).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(signer.next_code(now), None);
+        assert_eq!(auth.authorize(Some(&first), now), Ok(()));
+        assert_eq!(auth.authorize(Some(&second), now), Ok(()));
+
+        let later = signer.next_code(now + 30).unwrap();
+        assert_eq!(auth.authorize(Some(&later), now + 30), Ok(()));
+
+        assert!(TotpSigner::from_config(&TunnelAuthConfig::default()).is_none());
+        assert!(!CommandAuth::from_config(&TunnelAuthConfig::default()).is_enabled());
+        assert_eq!(CommandAuth::default().authorize(None, now), Ok(()));
+    }
+
+    #[test]
+    fn hmac_signatures_bind_the_request_and_are_spent_once() {
+        let config = TunnelAuthConfig {
+            backend: TunnelAuthBackend::Hmac,
+            ..totp(1)
+        };
+        let auth = CommandAuth::from_config(&config);
+        let mut signer = HmacSigner::from_config(&config).unwrap();
+        let now = 1_700_000_000_000;
+        let uptime = SignedRequest {
+            cmd: "uptime",
+            scratch_path: None,
+        };
+        let first = signer.sign(&uptime, now);
+        let second = signer.sign(&uptime, now);
+        assert_ne!(first, second);
+
+        assert!(auth.is_enabled());
+        assert_eq!(
+            auth.verify_signature(&uptime, None, now).unwrap_err(),
+            "command rejected: signature required"
+        );
+        let reboot = SignedRequest {
+            cmd: "reboot",
+            ..uptime
+        };
+        assert!(auth
+            .verify_signature(&reboot, Some(&first), now)
+            .unwrap_err()
+            .contains("invalid"));
+        assert!(auth
+            .verify_signature(&uptime, Some(&first), now + 31_000)
+            .unwrap_err()
+            .contains("expired"));
+        assert_eq!(auth.verify_signature(&uptime, Some(&second), now), Ok(()));
+        // The older signature was never used, but a newer one has been.
+        assert!(auth
+            .verify_signature(&uptime, Some(&first), now)
+            .unwrap_err()
+            .contains("already used"));
+        // The TOTP check is off for this backend.
+        assert_eq!(auth.authorize(None, now / 1_000), Ok(()));
+        assert!(matches!(
+            RequestSigner::from_config(&config),
+            Some(RequestSigner::Hmac(_))
+        ));
+    }
+
+    #[test]
+    fn file_requests_are_signed_over_their_path() {
+        let config = TunnelAuthConfig {
+            backend: TunnelAuthBackend::Hmac,
+            ..totp(1)
+        };
+        let auth = CommandAuth::from_config(&config);
+        let mut signer = RequestSigner::from_config(&config).unwrap();
+        let now = 1_700_000_000_000;
+        let mut pull = TunnelMsgOwned::FilePull {
+            path: "/var/log/syslog".into(),
+            otp: None,
+            sig: None,
+        };
+        assert_eq!(
+            auth.check_message(&pull, now).unwrap_err(),
+            "command rejected: signature required"
+        );
+        assert!(signer.sign_message(&mut pull, now));
+        let TunnelMsgOwned::FilePull { sig, .. } = pull.clone() else {
+            unreachable!()
+        };
+        let moved = TunnelMsgOwned::FilePull {
+            path: "/etc/shadow".into(),
+            otp: None,
+            sig,
+        };
+        assert!(auth
+            .check_message(&moved, now)
+            .unwrap_err()
+            .contains("invalid"));
+        assert_eq!(auth.check_message(&pull, now), Ok(()));
+        assert_eq!(auth.check_message(&TunnelMsgOwned::TailStop, now), Ok(()));
+    }
+}
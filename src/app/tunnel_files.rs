@@ -0,0 +1,1156 @@
+//! `lifelinetty push` / `pull`: copy files through the command tunnel of a running daemon.
+//!
+//! The end holding the data is the sender. It describes the file (`file_push`, or `file_info` in
+//! answer to `file_pull`), the receiver answers `file_resume` with the offset it already has
+//! staged, and the sender streams hex `file_chunk` frames, each with the CRC-32 of its bytes. Up
+//! to [`WINDOW_CHUNKS`] chunks may be unacknowledged; `file_ack` is cumulative, and a sender that
+//! sees no progress for [`ACK_TIMEOUT_MS`] goes back to the last acked offset. Partial files are
+//! staged under `CACHE_DIR/transfers` like `lifelinetty transfer` stages them, so repeating an
+//! interrupted push or pull continues where it stopped. The receiver checks the whole-file CRC
+//! and closes with `file_end`. While the daemon is part of a transfer it shows the progress on
+//! the LCD bar row.
+
+use super::{
+    events::INTERLOCK_REJECTION,
+    file_transfer::{from_hex, to_hex, FileOffer, FileTransferManager, IncomingFile},
+    interlock::unix_ms,
+    tail::resolve_allowed,
+    tunnel_auth::{CommandAuth, RequestSigner},
+    AppConfig, LogLevel, Logger,
+};
+use crate::{
+    cache_dir,
+    cli::{RunOptions, TunnelCopyOptions},
+    config::Config,
+    payload::{decode_tunnel_frame, encode_tunnel_msg, Payload, TunnelMsgOwned},
+    serial::{LineIo, SerialPort},
+    Error, ExitCode, Result,
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// File bytes per chunk; hex-encoded inside a tunnel frame they stay under `MAX_FRAME_BYTES`.
+pub const TUNNEL_CHUNK_BYTES: usize = 160;
+/// Chunks the sender may have in flight before it waits for an ack.
+pub const WINDOW_CHUNKS: u64 = 4;
+/// How long a sender waits for the ack offset to move before resending from it.
+pub const ACK_TIMEOUT_MS: u64 = 1_500;
+/// Resends without progress before either end gives up.
+const MAX_RETRIES: u32 = 8;
+/// How long a receiver keeps a silent transfer open; the staged part stays for a resume.
+const IDLE_TIMEOUT_MS: u64 = 60_000;
+/// Where pushed files with a relative remote path land, inside `CACHE_DIR`.
+const PUSH_DIR: &str = "files";
+/// Longest label on the LCD progress page.
+const MAX_LABEL_CHARS: usize = 40;
+
+/// Reads a local file out in windowed chunks.
+pub struct ChunkSender {
+    file: File,
+    size: u64,
+    crc32: u32,
+    next: u64,
+    acked: u64,
+    started: bool,
+    last_progress: Instant,
+    retries: u32,
+}
+
+impl ChunkSender {
+    pub fn open(path: &Path, now: Instant) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut hasher = crc32fast::Hasher::new();
+        let mut buf = [0u8; 8192];
+        let mut size = 0u64;
+        loop {
+            let read = file.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+            size += read as u64;
+        }
+        Ok(Self {
+            file,
+            size,
+            crc32: hasher.finalize(),
+            next: 0,
+            acked: 0,
+            started: false,
+            last_progress: now,
+            retries: 0,
+        })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    /// Bytes the receiver has confirmed.
+    pub fn acked(&self) -> u64 {
+        self.acked
+    }
+
+    /// Begin (or restart) streaming at the receiver's `offset`.
+    pub fn start(&mut self, offset: u64, now: Instant) {
+        self.acked = offset.min(self.size);
+        self.next = self.acked;
+        self.started = true;
+        self.last_progress = now;
+    }
+
+    pub fn on_ack(&mut self, offset: u64, now: Instant) {
+        if offset > self.acked && offset <= self.next {
+            self.acked = offset;
+            self.last_progress = now;
+            self.retries = 0;
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.started && self.acked == self.size
+    }
+
+    /// The next chunk the window allows, if any. Rewinds to the acked offset when the receiver
+    /// has gone quiet, and fails once that has happened [`MAX_RETRIES`] times in a row.
+    pub fn poll(&mut self, now: Instant) -> Result<Option<TunnelMsgOwned>> {
+        if !self.started {
+            return Ok(None);
+        }
+        if self.acked < self.next
+            && now.duration_since(self.last_progress) >= Duration::from_millis(ACK_TIMEOUT_MS)
+        {
+            self.retries += 1;
+            if self.retries > MAX_RETRIES {
+                return Err(Error::Parse(format!(
+                    "peer stopped acknowledging at byte {}",
+                    self.acked
+                )));
+            }
+            self.next = self.acked;
+            self.last_progress = now;
+        }
+        let window = WINDOW_CHUNKS * TUNNEL_CHUNK_BYTES as u64;
+        if self.next >= self.size || self.next - self.acked >= window {
+            return Ok(None);
+        }
+        if self.next == self.acked {
+            // The ack timer runs from the first chunk of a window.
+            self.last_progress = now;
+        }
+        let len = (self.size - self.next).min(TUNNEL_CHUNK_BYTES as u64) as usize;
+        let mut data = vec![0u8; len];
+        self.file.seek(SeekFrom::Start(self.next))?;
+        self.file.read_exact(&mut data)?;
+        let chunk = TunnelMsgOwned::FileChunk {
+            offset: self.next,
+            data: to_hex(&data),
+            crc32: crc32fast::hash(&data),
+        };
+        self.next += len as u64;
+        Ok(Some(chunk))
+    }
+}
+
+/// Writes chunks into the staging area and moves the verified file to its destination.
+pub struct ChunkReceiver {
+    incoming: IncomingFile,
+    size: u64,
+    dest_dir: PathBuf,
+}
+
+impl ChunkReceiver {
+    /// Stage `dest` for a file of `size` bytes and whole-file `crc32`, keeping a partial file left
+    /// by an earlier attempt at the same file.
+    pub fn begin(
+        manager: &FileTransferManager,
+        dest: &Path,
+        size: u64,
+        crc32: u32,
+    ) -> Result<Self> {
+        let name = dest
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| Error::InvalidArgs(format!("{} has no usable name", dest.display())))?;
+        let offer = FileOffer {
+            name: name.to_string(),
+            size: Some(size),
+            crc32: Some(crc32),
+        };
+        Ok(Self {
+            incoming: manager.begin_receive(&offer, true)?,
+            size,
+            dest_dir: dest.parent().map(Path::to_path_buf).unwrap_or_default(),
+        })
+    }
+
+    pub fn received(&self) -> u64 {
+        self.incoming.received()
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.incoming.received() == self.size
+    }
+
+    /// Append the chunk when it is the next one and intact. The reply is the cumulative ack
+    /// either way, so a gap or a damaged chunk shows the sender where to resume.
+    pub fn on_chunk(&mut self, offset: u64, data: &str, crc32: u32) -> Result<TunnelMsgOwned> {
+        if offset == self.incoming.received() {
+            if let Some(bytes) = from_hex(data).filter(|bytes| crc32fast::hash(bytes) == crc32) {
+                self.incoming.write_all(&bytes)?;
+            }
+        }
+        Ok(TunnelMsgOwned::FileAck {
+            offset: self.incoming.received(),
+        })
+    }
+
+    /// Verify the whole file and move it into place.
+    pub fn finish(self) -> Result<PathBuf> {
+        self.incoming.finish(&self.dest_dir)
+    }
+}
+
+/// How far the transfer the daemon is part of has got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferProgress {
+    pub label: String,
+    pub done: u64,
+    pub total: u64,
+}
+
+impl TransferProgress {
+    pub fn percent(&self) -> u8 {
+        if self.total == 0 {
+            return 100;
+        }
+        (self.done.min(self.total) * 100 / self.total) as u8
+    }
+}
+
+/// Payload JSON for the LCD page of a transfer: the label over a bar of the bytes done.
+pub fn progress_page(progress: &TransferProgress) -> String {
+    let mut payload = Payload::new(
+        progress
+            .label
+            .chars()
+            .take(MAX_LABEL_CHARS)
+            .collect::<String>(),
+        String::new(),
+    );
+    payload.bar = Some(progress.percent());
+    payload.bar_label = Some(format!("{}%", progress.percent()));
+    serde_json::to_string(&payload).unwrap_or_default()
+}
+
+enum Session {
+    Receiving {
+        receiver: ChunkReceiver,
+        label: String,
+        last_heard: Instant,
+    },
+    Sending {
+        sender: ChunkSender,
+        label: String,
+    },
+}
+
+/// Daemon side of push and pull: one transfer at a time, driven by tunnel messages.
+pub struct TunnelFiles {
+    cache_dir: PathBuf,
+    manager: FileTransferManager,
+    pull_allowlist: Vec<String>,
+    auth: CommandAuth,
+    session: Option<Session>,
+    outgoing: VecDeque<TunnelMsgOwned>,
+}
+
+impl TunnelFiles {
+    /// Pushed files land inside `cache_dir/files`; pulls may read `pull_allowlist` (the tail
+    /// allowlist) and files pushed earlier.
+    pub fn new(cache_dir: impl AsRef<Path>, pull_allowlist: Vec<String>) -> Self {
+        Self {
+            cache_dir: cache_dir.as_ref().to_path_buf(),
+            manager: FileTransferManager::new(&cache_dir),
+            pull_allowlist,
+            auth: CommandAuth::default(),
+            session: None,
+            outgoing: VecDeque::new(),
+        }
+    }
+
+    /// Require `[tunnel_auth]` codes on `file_push` and `file_pull`.
+    pub fn set_auth(&mut self, auth: CommandAuth) {
+        self.auth = auth;
+    }
+
+    /// Whether `msg` belongs to a push or pull.
+    pub fn handles(msg: &TunnelMsgOwned) -> bool {
+        matches!(
+            msg,
+            TunnelMsgOwned::FilePush { .. }
+                | TunnelMsgOwned::FilePull { .. }
+                | TunnelMsgOwned::FileResume { .. }
+                | TunnelMsgOwned::FileChunk { .. }
+                | TunnelMsgOwned::FileAck { .. }
+                | TunnelMsgOwned::FileEnd { .. }
+        )
+    }
+
+    /// Act on a message from the peer; replies wait in [`TunnelFiles::next_outgoing`].
+    pub fn handle(&mut self, msg: TunnelMsgOwned, locked: bool, now: Instant, logger: &Logger) {
+        match msg {
+            TunnelMsgOwned::FilePush {
+                ref path,
+                size,
+                crc32,
+                ..
+            } => {
+                if !self.admit(&msg, "push", locked, logger) {
+                    return;
+                }
+                let begun = push_target(&self.cache_dir, path)
+                    .and_then(|dest| ChunkReceiver::begin(&self.manager, &dest, size, crc32));
+                match begun {
+                    Ok(receiver) => {
+                        let offset = receiver.received();
+                        logger.log_with(
+                            LogLevel::Info,
+                            "tunnel: receiving push",
+                            &[
+                                ("path", path.as_str().into()),
+                                ("size", size.into()),
+                                ("offset", offset.into()),
+                            ],
+                        );
+                        self.outgoing
+                            .push_back(TunnelMsgOwned::FileResume { offset });
+                        self.session = Some(Session::Receiving {
+                            receiver,
+                            label: format!("PUSH {}", file_label(path)),
+                            last_heard: now,
+                        });
+                        self.finish_if_complete(logger);
+                    }
+                    Err(err) => {
+                        logger.warn(format!("tunnel: push of {path} rejected: {err}"));
+                        self.fail(err.to_string());
+                    }
+                }
+            }
+            TunnelMsgOwned::FilePull { ref path, .. } => {
+                if !self.admit(&msg, "pull", locked, logger) {
+                    return;
+                }
+                let opened = pull_source(&self.cache_dir, &self.pull_allowlist, path)
+                    .and_then(|source| ChunkSender::open(&source, now));
+                match opened {
+                    Ok(sender) => {
+                        logger.log_with(
+                            LogLevel::Info,
+                            "tunnel: sending pull",
+                            &[
+                                ("path", path.as_str().into()),
+                                ("size", sender.size().into()),
+                            ],
+                        );
+                        self.outgoing.push_back(TunnelMsgOwned::FileInfo {
+                            size: sender.size(),
+                            crc32: sender.crc32(),
+                        });
+                        self.session = Some(Session::Sending {
+                            sender,
+                            label: format!("PULL {}", file_label(path)),
+                        });
+                    }
+                    Err(err) => {
+                        logger.warn(format!("tunnel: pull of {path} rejected: {err}"));
+                        self.fail(err.to_string());
+                    }
+                }
+            }
+            TunnelMsgOwned::FileResume { offset } => {
+                if let Some(Session::Sending { sender, .. }) = self.session.as_mut() {
+                    sender.start(offset, now);
+                }
+            }
+            TunnelMsgOwned::FileChunk {
+                offset,
+                data,
+                crc32,
+            } => {
+                let Some(Session::Receiving {
+                    receiver,
+                    last_heard,
+                    ..
+                }) = self.session.as_mut()
+                else {
+                    self.fail("no push in progress".into());
+                    return;
+                };
+                *last_heard = now;
+                match receiver.on_chunk(offset, &data, crc32) {
+                    Ok(ack) => {
+                        self.outgoing.push_back(ack);
+                        self.finish_if_complete(logger);
+                    }
+                    Err(err) => {
+                        logger.warn(format!("tunnel: push failed: {err}"));
+                        self.session = None;
+                        self.fail(err.to_string());
+                    }
+                }
+            }
+            TunnelMsgOwned::FileAck { offset } => {
+                if let Some(Session::Sending { sender, .. }) = self.session.as_mut() {
+                    sender.on_ack(offset, now);
+                }
+            }
+            TunnelMsgOwned::FileEnd { ok, error } if self.session.take().is_some() => {
+                match (ok, error) {
+                    (true, _) => logger.info("tunnel: pull complete"),
+                    (false, error) => logger.warn(format!(
+                        "tunnel: transfer abandoned by peer: {}",
+                        error.unwrap_or_else(|| "no reason given".into())
+                    )),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn next_outgoing(&mut self, now: Instant) -> Option<TunnelMsgOwned> {
+        if let Some(msg) = self.outgoing.pop_front() {
+            return Some(msg);
+        }
+        match self.session.as_mut()? {
+            Session::Sending { sender, .. } => match sender.poll(now) {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    self.session = None;
+                    Some(end(Err(err)))
+                }
+            },
+            Session::Receiving { last_heard, .. } => {
+                if now.duration_since(*last_heard) < Duration::from_millis(IDLE_TIMEOUT_MS) {
+                    return None;
+                }
+                self.session = None;
+                Some(end(Err(Error::Parse("push went idle".into()))))
+            }
+        }
+    }
+
+    pub fn progress(&self) -> Option<TransferProgress> {
+        match self.session.as_ref()? {
+            Session::Receiving {
+                receiver, label, ..
+            } => Some(TransferProgress {
+                label: label.clone(),
+                done: receiver.received(),
+                total: receiver.size(),
+            }),
+            Session::Sending { sender, label } => Some(TransferProgress {
+                label: label.clone(),
+                done: sender.acked(),
+                total: sender.size(),
+            }),
+        }
+    }
+
+    /// Drop any running transfer, then check the interlock and `[tunnel_auth]` for a new one.
+    /// A refusal is queued as a failed `file_end`.
+    fn admit(&mut self, msg: &TunnelMsgOwned, kind: &str, locked: bool, logger: &Logger) -> bool {
+        self.session = None;
+        let refusal = if locked {
+            Some(INTERLOCK_REJECTION.to_string())
+        } else {
+            self.auth
+                .check_message(msg, unix_ms(SystemTime::now()))
+                .err()
+        };
+        let Some(reason) = refusal else {
+            return true;
+        };
+        if let TunnelMsgOwned::FilePush { path, .. } | TunnelMsgOwned::FilePull { path, .. } = msg {
+            logger.warn(format!("tunnel: {kind} of {path} rejected: {reason}"));
+        }
+        self.fail(reason);
+        false
+    }
+
+    fn finish_if_complete(&mut self, logger: &Logger) {
+        let complete = matches!(
+            &self.session,
+            Some(Session::Receiving { receiver, .. }) if receiver.is_complete()
+        );
+        if !complete {
+            return;
+        }
+        let Some(Session::Receiving { receiver, .. }) = self.session.take() else {
+            return;
+        };
+        let finished = receiver.finish();
+        match &finished {
+            Ok(path) => logger.info(format!("tunnel: received {}", path.display())),
+            Err(err) => logger.warn(format!("tunnel: push failed verification: {err}")),
+        }
+        self.outgoing.push_back(end(finished.map(|_| ())));
+    }
+
+    fn fail(&mut self, error: String) {
+        self.outgoing.push_back(TunnelMsgOwned::FileEnd {
+            ok: false,
+            error: Some(error),
+        });
+    }
+}
+
+fn end(outcome: Result<()>) -> TunnelMsgOwned {
+    match outcome {
+        Ok(()) => TunnelMsgOwned::FileEnd {
+            ok: true,
+            error: None,
+        },
+        Err(err) => TunnelMsgOwned::FileEnd {
+            ok: false,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+fn file_label(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
+
+/// Where a pushed file may land: relative paths go under `cache_dir/files`, absolute ones must
+/// already be inside it. `..` is refused either way, and so is a parent that resolves outside
+/// `cache_dir/files` through a symlink or a target that is one. Missing directories are created.
+fn push_target(cache_dir: &Path, remote: &str) -> Result<PathBuf> {
+    let denied = || {
+        Error::InvalidArgs(format!(
+            "remote path must stay inside {}: {remote}",
+            cache_dir.join(PUSH_DIR).display()
+        ))
+    };
+    let target = files_path(cache_dir, remote).ok_or_else(denied)?;
+    let (Some(parent), Some(name)) = (target.parent(), target.file_name()) else {
+        return Err(denied());
+    };
+    let root = files_root(cache_dir)?;
+    // Resolve the deepest directory that exists before creating anything below it.
+    let existing = parent
+        .ancestors()
+        .find(|dir| dir.exists())
+        .ok_or_else(denied)?;
+    if !fs::canonicalize(existing)?.starts_with(&root) {
+        return Err(denied());
+    }
+    fs::create_dir_all(parent)?;
+    let parent = fs::canonicalize(parent)?;
+    if !parent.starts_with(&root) {
+        return Err(denied());
+    }
+    let dest = parent.join(name);
+    if fs::symlink_metadata(&dest).is_ok_and(|meta| meta.file_type().is_symlink()) {
+        return Err(denied());
+    }
+    Ok(dest)
+}
+
+/// A pull may read a tail-allowlisted file or one inside `cache_dir/files`, compared after
+/// resolving symlinks.
+fn pull_source(cache_dir: &Path, allowlist: &[String], remote: &str) -> Result<PathBuf> {
+    if let Ok(path) = resolve_allowed(allowlist, remote) {
+        return Ok(path);
+    }
+    let denied = || Error::InvalidArgs(format!("remote path not allowed: {remote}"));
+    let candidate = files_path(cache_dir, remote).ok_or_else(denied)?;
+    let resolved = fs::canonicalize(&candidate).map_err(|_| denied())?;
+    let root = files_root(cache_dir).map_err(|_| denied())?;
+    if !resolved.starts_with(root) || !resolved.is_file() {
+        return Err(denied());
+    }
+    Ok(resolved)
+}
+
+/// `remote` as a path under `cache_dir/files`, before any symlink is resolved; `None` when it
+/// uses `..` or names a place outside that directory.
+fn files_path(cache_dir: &Path, remote: &str) -> Option<PathBuf> {
+    let requested = Path::new(remote);
+    if requested
+        .components()
+        .any(|part| matches!(part, Component::ParentDir))
+    {
+        return None;
+    }
+    let files = cache_dir.join(PUSH_DIR);
+    let target = if requested.is_absolute() {
+        requested.to_path_buf()
+    } else {
+        files.join(requested)
+    };
+    (target.starts_with(&files) && target != files).then_some(target)
+}
+
+/// `cache_dir/files` with symlinks resolved, created on first use.
+fn files_root(cache_dir: &Path) -> Result<PathBuf> {
+    let root = cache_dir.join(PUSH_DIR);
+    fs::create_dir_all(&root)?;
+    Ok(fs::canonicalize(root)?)
+}
+
+/// Copy `local` to `remote` on the daemon at the far end. `signer` adds the `[tunnel_auth]`
+/// code or signature; `progress` sees the acked bytes and the size.
+pub fn push<T: LineIo>(
+    io: &mut T,
+    local: &Path,
+    remote: &str,
+    signer: Option<&mut RequestSigner>,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    let mut sender = ChunkSender::open(local, Instant::now())?;
+    let size = sender.size();
+    let mut announce = TunnelMsgOwned::FilePush {
+        path: remote.to_string(),
+        size,
+        crc32: sender.crc32(),
+        otp: None,
+        sig: None,
+    };
+    sign(signer, &mut announce)?;
+    let offset = request(io, &announce, |reply| match reply {
+        TunnelMsgOwned::FileResume { offset } => Some(offset),
+        _ => None,
+    })?;
+    sender.start(offset, Instant::now());
+    progress(sender.acked(), size);
+    let mut done_at: Option<Instant> = None;
+    loop {
+        while let Some(chunk) = sender.poll(Instant::now())? {
+            send(io, &chunk)?;
+        }
+        match read(io)? {
+            Some(TunnelMsgOwned::FileAck { offset }) => {
+                sender.on_ack(offset, Instant::now());
+                progress(sender.acked(), size);
+            }
+            Some(TunnelMsgOwned::FileEnd { ok: true, .. }) => return Ok(()),
+            Some(TunnelMsgOwned::FileEnd { ok: false, error }) => return Err(refused(error)),
+            _ => {}
+        }
+        if sender.is_done() {
+            let since = *done_at.get_or_insert_with(Instant::now);
+            if since.elapsed() >= Duration::from_millis(ACK_TIMEOUT_MS) {
+                return Err(Error::Parse(
+                    "peer received every byte but did not confirm the file".into(),
+                ));
+            }
+        }
+    }
+}
+
+/// Copy `remote` from the daemon at the far end to `local`, resuming a part staged by
+/// `manager`.
+pub fn pull<T: LineIo>(
+    io: &mut T,
+    manager: &FileTransferManager,
+    remote: &str,
+    local: &Path,
+    signer: Option<&mut RequestSigner>,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<PathBuf> {
+    let mut ask = TunnelMsgOwned::FilePull {
+        path: remote.to_string(),
+        otp: None,
+        sig: None,
+    };
+    sign(signer, &mut ask)?;
+    let (size, crc32) = request(io, &ask, |reply| match reply {
+        TunnelMsgOwned::FileInfo { size, crc32 } => Some((size, crc32)),
+        _ => None,
+    })?;
+    let mut receiver = ChunkReceiver::begin(manager, local, size, crc32)?;
+    let mut reply = TunnelMsgOwned::FileResume {
+        offset: receiver.received(),
+    };
+    send(io, &reply)?;
+    progress(receiver.received(), size);
+    let mut last_heard = Instant::now();
+    let mut retries = 0;
+    while !receiver.is_complete() {
+        match read(io)? {
+            Some(TunnelMsgOwned::FileChunk {
+                offset,
+                data,
+                crc32,
+            }) => {
+                reply = receiver.on_chunk(offset, &data, crc32)?;
+                send(io, &reply)?;
+                progress(receiver.received(), size);
+                last_heard = Instant::now();
+                retries = 0;
+            }
+            Some(TunnelMsgOwned::FileEnd { ok: false, error }) => return Err(refused(error)),
+            _ => {
+                // Repeat the resume or last ack in case it was lost; the part stays staged.
+                if last_heard.elapsed() >= Duration::from_millis(ACK_TIMEOUT_MS) {
+                    retries += 1;
+                    if retries > MAX_RETRIES {
+                        return Err(Error::Parse(format!(
+                            "peer stopped sending at byte {}",
+                            receiver.received()
+                        )));
+                    }
+                    send(io, &reply)?;
+                    last_heard = Instant::now();
+                }
+            }
+        }
+    }
+    let finished = receiver.finish();
+    send(
+        io,
+        &end(finished
+            .as_ref()
+            .map(|_| ())
+            .map_err(|err| Error::Parse(err.to_string()))),
+    )?;
+    finished
+}
+
+/// Send `message` until `pick` accepts a reply, resending after each silent `ACK_TIMEOUT_MS`.
+/// A failed `file_end` from the peer ends the transfer.
+pub(super) fn request<T: LineIo, R>(
+    io: &mut T,
+    message: &TunnelMsgOwned,
+    pick: impl Fn(TunnelMsgOwned) -> Option<R>,
+) -> Result<R> {
+    for _ in 0..=MAX_RETRIES {
+        send(io, message)?;
+        let deadline = Instant::now() + Duration::from_millis(ACK_TIMEOUT_MS);
+        while Instant::now() < deadline {
+            match read(io)? {
+                Some(TunnelMsgOwned::FileEnd { ok: false, error }) => return Err(refused(error)),
+                Some(reply) => {
+                    if let Some(picked) = pick(reply) {
+                        return Ok(picked);
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+    Err(Error::Parse(format!(
+        "peer did not answer after {MAX_RETRIES} retries"
+    )))
+}
+
+/// Add the `[tunnel_auth]` code or signature to a request, when the config asks for one.
+pub(super) fn sign(signer: Option<&mut RequestSigner>, message: &mut TunnelMsgOwned) -> Result<()> {
+    let Some(signer) = signer else {
+        return Ok(());
+    };
+    if signer.sign_message(message, unix_ms(SystemTime::now())) {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgs(
+            "no unused TOTP code left this period; try again shortly".into(),
+        ))
+    }
+}
+
+fn send<T: LineIo>(io: &mut T, message: &TunnelMsgOwned) -> Result<()> {
+    io.send_command_line(&encode_tunnel_msg(message)?)
+}
+
+/// The next tunnel message, or `None` on a timeout or a line that is not one.
+fn read<T: LineIo>(io: &mut T) -> Result<Option<TunnelMsgOwned>> {
+    let mut line = String::new();
+    match io.read_message_line(&mut line) {
+        Ok(0) | Err(Error::Parse(_)) => Ok(None),
+        Ok(_) => Ok(decode_tunnel_frame(line.trim()).ok()),
+        Err(err) => Err(err),
+    }
+}
+
+fn refused(error: Option<String>) -> Error {
+    Error::Parse(format!(
+        "peer refused the transfer: {}",
+        error.unwrap_or_else(|| "no reason given".into())
+    ))
+}
+
+/// `lifelinetty push <local> <remote>`.
+pub fn run_push(opts: TunnelCopyOptions) -> Result<()> {
+    let (mut serial, mut signer) = open_serial(opts.device.clone(), opts.baud)?;
+    let bar = progress_bar(&opts.local);
+    push(
+        &mut serial,
+        Path::new(&opts.local),
+        &opts.remote,
+        signer.as_mut(),
+        &mut |done, total| {
+            bar.set_length(total);
+            bar.set_position(done);
+        },
+    )?;
+    bar.finish();
+    eprintln!("pushed {} to {}", opts.local, opts.remote);
+    Ok(())
+}
+
+/// `lifelinetty pull <remote> <local>`.
+pub fn run_pull(opts: TunnelCopyOptions) -> Result<()> {
+    let (mut serial, mut signer) = open_serial(opts.device.clone(), opts.baud)?;
+    let manager = FileTransferManager::new(cache_dir());
+    let bar = progress_bar(&opts.remote);
+    let path = pull(
+        &mut serial,
+        &manager,
+        &opts.remote,
+        Path::new(&opts.local),
+        signer.as_mut(),
+        &mut |done, total| {
+            bar.set_length(total);
+            bar.set_position(done);
+        },
+    )?;
+    bar.finish();
+    eprintln!("pulled {} to {}", opts.remote, path.display());
+    Ok(())
+}
+
+/// The configured serial port, with `--device`/`--baud` taking precedence, and the signer
+/// `[tunnel_auth]` asks requests to carry.
+pub(super) fn open_serial(
+    device: Option<String>,
+    baud: Option<u32>,
+) -> Result<(SerialPort, Option<RequestSigner>)> {
+    let cfg = Config::load_or_default().map_err(|err| err.exit_with(ExitCode::Config))?;
+    let merged = AppConfig::from_sources(
+        cfg,
+        RunOptions {
+            device,
+            baud,
+            ..RunOptions::default()
+        },
+    );
+    let serial = SerialPort::connect(&merged.device, merged.serial_options())
+        .map_err(|err| err.exit_with(ExitCode::SerialUnavailable))?;
+    Ok((serial, RequestSigner::from_config(&merged.tunnel_auth)))
+}
+
+fn progress_bar(name: &str) -> ProgressBar {
+    let bar = ProgressBar::new(0);
+    if let Ok(style) =
+        ProgressStyle::with_template("{msg} {bytes}/{total_bytes} [{wide_bar}] {eta}")
+    {
+        bar.set_style(style);
+    }
+    bar.set_message(name.to_string());
+    bar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::LogLevel;
+    use crate::payload::RenderFrame;
+    use crate::state::MAX_FRAME_BYTES;
+    use std::{
+        sync::mpsc::{channel, Receiver, Sender},
+        thread,
+    };
+    use tempfile::tempdir;
+
+    /// The test side of the link; the daemon side is driven inline through [`TunnelFiles`].
+    struct Daemon {
+        files: TunnelFiles,
+        logger: Logger,
+        rx: Receiver<String>,
+        tx: Sender<String>,
+        /// Drop every chunk at this offset once, to force a rewind.
+        drop_once: Option<u64>,
+    }
+
+    impl Daemon {
+        fn run(mut self, until: Instant) {
+            while Instant::now() < until {
+                if let Ok(line) = self.rx.recv_timeout(Duration::from_millis(5)) {
+                    let msg = decode_tunnel_frame(&line).unwrap();
+                    let done = matches!(msg, TunnelMsgOwned::FileEnd { .. });
+                    if let TunnelMsgOwned::FileChunk { offset, .. } = &msg {
+                        if self.drop_once == Some(*offset) {
+                            self.drop_once = None;
+                            continue;
+                        }
+                    }
+                    self.files.handle(msg, false, Instant::now(), &self.logger);
+                    if done {
+                        return;
+                    }
+                }
+                while let Some(reply) = self.files.next_outgoing(Instant::now()) {
+                    let _ = self.tx.send(encode_tunnel_msg(&reply).unwrap());
+                    if matches!(reply, TunnelMsgOwned::FileEnd { .. }) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    struct Client {
+        tx: Sender<String>,
+        rx: Receiver<String>,
+    }
+
+    impl LineIo for Client {
+        fn send_command_line(&mut self, line: &str) -> Result<()> {
+            let _ = self.tx.send(line.to_string());
+            Ok(())
+        }
+
+        fn read_message_line(&mut self, buf: &mut String) -> Result<usize> {
+            match self.rx.recv_timeout(Duration::from_millis(5)) {
+                Ok(line) => {
+                    *buf = line;
+                    Ok(buf.len())
+                }
+                Err(_) => Ok(0),
+            }
+        }
+    }
+
+    fn link(cache_dir: &Path, allowlist: Vec<String>, drop_once: Option<u64>) -> (Client, Daemon) {
+        let (to_daemon, daemon_rx) = channel();
+        let (daemon_tx, client_rx) = channel();
+        (
+            Client {
+                tx: to_daemon,
+                rx: client_rx,
+            },
+            Daemon {
+                files: TunnelFiles::new(cache_dir, allowlist),
+                logger: Logger::new(LogLevel::Error, None).unwrap(),
+                rx: daemon_rx,
+                tx: daemon_tx,
+                drop_once,
+            },
+        )
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|idx| (idx * 7 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn a_full_chunk_fits_in_one_serial_line() {
+        let msg = TunnelMsgOwned::FileChunk {
+            offset: u64::MAX,
+            data: to_hex(&[0xff; TUNNEL_CHUNK_BYTES]),
+            crc32: u32::MAX,
+        };
+        let line = crate::payload::encode_tunnel_msg_with_seq(&msg, Some(u64::MAX)).unwrap();
+        assert!(line.len() < MAX_FRAME_BYTES, "{} bytes", line.len());
+    }
+
+    #[test]
+    fn push_survives_a_lost_chunk_and_resumes_a_staged_part() {
+        let dir = tempdir().unwrap();
+        let local = dir.path().join("fw.bin");
+        let data = sample(TUNNEL_CHUNK_BYTES * 9 + 17);
+        fs::write(&local, &data).unwrap();
+        let cache = dir.path().join("cache");
+
+        // Leave two chunks staged from an earlier, interrupted push.
+        let manager = FileTransferManager::new(&cache);
+        let sender = ChunkSender::open(&local, Instant::now()).unwrap();
+        let dest = cache.join(PUSH_DIR).join("fw.bin");
+        let mut staged =
+            ChunkReceiver::begin(&manager, &dest, sender.size(), sender.crc32()).unwrap();
+        let first = 2 * TUNNEL_CHUNK_BYTES;
+        let part = &data[..first];
+        staged
+            .on_chunk(0, &to_hex(part), crc32fast::hash(part))
+            .unwrap();
+        drop(staged);
+
+        let resumed_at = first as u64;
+        let (mut client, daemon) = link(&cache, Vec::new(), Some(resumed_at + 320));
+        let until = Instant::now() + Duration::from_secs(10);
+        let handle = thread::spawn(move || daemon.run(until));
+        let mut seen = Vec::new();
+        push(&mut client, &local, "fw.bin", None, &mut |done, _| {
+            seen.push(done)
+        })
+        .unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(seen.first(), Some(&resumed_at));
+        assert_eq!(fs::read(&dest).unwrap(), data);
+    }
+
+    #[test]
+    fn pull_reads_allowlisted_files_and_refuses_others() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("syslog");
+        let data = sample(1_000);
+        fs::write(&source, &data).unwrap();
+        let cache = dir.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        let allowlist = vec![source.display().to_string()];
+
+        let (mut client, daemon) = link(&cache, allowlist.clone(), None);
+        let until = Instant::now() + Duration::from_secs(10);
+        let handle = thread::spawn(move || daemon.run(until));
+        let manager = FileTransferManager::new(dir.path().join("local_cache"));
+        let local = dir.path().join("out").join("syslog.copy");
+        let path = pull(
+            &mut client,
+            &manager,
+            &source.display().to_string(),
+            &local,
+            None,
+            &mut |_, _| {},
+        )
+        .unwrap();
+        handle.join().unwrap();
+        assert_eq!(path, local);
+        assert_eq!(fs::read(&local).unwrap(), data);
+
+        let (mut client, daemon) = link(&cache, allowlist, None);
+        let until = Instant::now() + Duration::from_secs(10);
+        let handle = thread::spawn(move || daemon.run(until));
+        let err = pull(
+            &mut client,
+            &manager,
+            "/etc/passwd",
+            &local,
+            None,
+            &mut |_, _| {},
+        )
+        .unwrap_err();
+        handle.join().unwrap();
+        assert!(err.to_string().contains("not allowed"), "{err}");
+    }
+
+    #[test]
+    fn transfers_need_a_signature_when_tunnel_auth_is_on() {
+        let dir = tempdir().unwrap();
+        let local = dir.path().join("fw.bin");
+        let data = sample(500);
+        fs::write(&local, &data).unwrap();
+        let cache = dir.path().join("cache");
+        let config = crate::config::TunnelAuthConfig {
+            backend: crate::config::TunnelAuthBackend::Hmac,
+            secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".into(),
+            period_secs: 30,
+            skew_steps: 1,
+        };
+
+        let (mut client, mut daemon) = link(&cache, Vec::new(), None);
+        daemon.files.set_auth(CommandAuth::from_config(&config));
+        let until = Instant::now() + Duration::from_secs(10);
+        let handle = thread::spawn(move || daemon.run(until));
+        let err = push(&mut client, &local, "fw.bin", None, &mut |_, _| {}).unwrap_err();
+        handle.join().unwrap();
+        assert!(err.to_string().contains("signature required"), "{err}");
+        assert!(!cache.join(PUSH_DIR).join("fw.bin").exists());
+
+        let (mut client, mut daemon) = link(&cache, Vec::new(), None);
+        daemon.files.set_auth(CommandAuth::from_config(&config));
+        let until = Instant::now() + Duration::from_secs(10);
+        let handle = thread::spawn(move || daemon.run(until));
+        let mut signer = RequestSigner::from_config(&config);
+        push(
+            &mut client,
+            &local,
+            "fw.bin",
+            signer.as_mut(),
+            &mut |_, _| {},
+        )
+        .unwrap();
+        handle.join().unwrap();
+        assert_eq!(fs::read(cache.join(PUSH_DIR).join("fw.bin")).unwrap(), data);
+    }
+
+    #[test]
+    fn push_targets_stay_inside_the_files_dir() {
+        let dir = tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        let files = cache.join(PUSH_DIR);
+        fs::create_dir_all(&files).unwrap();
+        let files = fs::canonicalize(&files).unwrap();
+        assert_eq!(
+            push_target(&cache, "logs/a.txt").unwrap(),
+            files.join("logs/a.txt")
+        );
+        assert!(files.join("logs").is_dir());
+        let inside = cache.join(PUSH_DIR).join("x.bin");
+        assert!(push_target(&cache, &inside.display().to_string()).is_ok());
+        // The daemon's own state next to the files dir is off limits.
+        let spool = cache.join("spool.jsonl");
+        assert!(push_target(&cache, &spool.display().to_string()).is_err());
+        assert!(push_target(&cache, "/etc/passwd").is_err());
+        assert!(push_target(&cache, "../escape").is_err());
+        assert!(push_target(&cache, &files.display().to_string()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_lead_pushes_or_pulls_out_of_the_files_dir() {
+        let dir = tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(cache.join(PUSH_DIR)).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("secret"), b"x").unwrap();
+        fs::write(cache.join("state.json"), b"{}").unwrap();
+        std::os::unix::fs::symlink(&outside, cache.join(PUSH_DIR).join("out")).unwrap();
+        std::os::unix::fs::symlink(outside.join("secret"), cache.join(PUSH_DIR).join("secret"))
+            .unwrap();
+
+        assert!(push_target(&cache, "out/new.bin").is_err());
+        assert!(push_target(&cache, "out/deeper/new.bin").is_err());
+        assert!(!outside.join("deeper").exists());
+        assert!(push_target(&cache, "secret").is_err());
+        assert!(pull_source(&cache, &[], "out/secret").is_err());
+        assert!(pull_source(&cache, &[], "secret").is_err());
+        let state = cache.join("state.json").display().to_string();
+        assert!(pull_source(&cache, &[], &state).is_err());
+
+        fs::write(cache.join(PUSH_DIR).join("ok.txt"), b"ok").unwrap();
+        assert!(pull_source(&cache, &[], "ok.txt").is_ok());
+    }
+
+    #[test]
+    fn progress_page_fills_the_bar_row() {
+        let page = progress_page(&TransferProgress {
+            label: "PUSH fw.bin".into(),
+            done: 512,
+            total: 2_048,
+        });
+        let frame = RenderFrame::from_payload_json(&page).unwrap();
+        assert_eq!(frame.line1, "PUSH fw.bin");
+        assert_eq!(frame.bar_percent, Some(25));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{app::Logger, CACHE_DIR};
+use crate::{app::Logger, cache_dir};
 use std::fs::{create_dir_all, OpenOptions};
 use std::io::Write;
 use std::path::PathBuf;
@@ -48,9 +48,7 @@ struct WatchdogLog {
 
 impl WatchdogLog {
     fn new() -> Self {
-        let path = PathBuf::from(CACHE_DIR)
-            .join(WATCHDOG_DIR)
-            .join("events.log");
+        let path = cache_dir().join(WATCHDOG_DIR).join("events.log");
         Self { path }
     }
 
@@ -141,7 +139,7 @@ impl WatchdogMonitor {
     }
 
     fn trigger_hook(&self, logger: &Logger) {
-        let hook_path = PathBuf::from(CACHE_DIR).join(WATCHDOG_DIR).join(HOOK_NAME);
+        let hook_path = cache_dir().join(WATCHDOG_DIR).join(HOOK_NAME);
         if !hook_path.exists() {
             logger.debug("watchdog: offline hook missing; skipping");
             return;
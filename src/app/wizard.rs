@@ -1,11 +1,15 @@
 use crate::{
+    cache_dir,
     cli::RunOptions,
-    config::{loader, Config, DEFAULT_DEVICE, MAX_COLS, MAX_ROWS, MIN_BAUD, MIN_COLS, MIN_ROWS},
+    config::{
+        loader, Config, COMMAND_ALLOWLIST_OFF, DEFAULT_DEVICE, MAX_COLS, MAX_ROWS, MIN_BAUD,
+        MIN_COLS, MIN_ROWS,
+    },
     lcd::Lcd,
     negotiation::RolePreference,
     payload::{decode_tunnel_frame, encode_tunnel_msg, TunnelMsgOwned},
     serial::{SerialOptions, SerialPort},
-    Result, CACHE_DIR,
+    Result,
 };
 use humantime::format_rfc3339;
 use serde_json;
@@ -194,6 +198,10 @@ impl FirstRunWizard {
         );
         println!("Usage intent: {}", answers.intent.as_str());
         println!("Role preference: {}", answers.preference.as_str());
+        println!(
+            "Command allowlist: {}",
+            describe_command_allowlist(answers.command_allowlist.as_deref())
+        );
         println!("Probe serial: {}", answers.run_probe);
         println!("Link rehearsal: {}", answers.run_link_rehearsal);
         println!("Show helper snippets: {}", answers.show_helpers);
@@ -201,9 +209,10 @@ impl FirstRunWizard {
         let save_confirmed =
             prompt_yes_no(&mut prompter, "Write these settings to disk (y/n)", true)?;
         if !save_confirmed {
-            return Err(crate::Error::InvalidArgs(
-                "wizard aborted; config not saved".to_string(),
-            ));
+            return Err(
+                crate::Error::InvalidArgs("wizard aborted; config not saved".to_string())
+                    .exit_with(crate::ExitCode::WizardAborted),
+            );
         }
 
         self.save_config(&answers)?;
@@ -245,6 +254,9 @@ impl FirstRunWizard {
         cfg.rows = answers.rows;
         cfg.lcd_present = answers.lcd_present;
         cfg.negotiation.preference = answers.preference;
+        if let Some(allowlist) = &answers.command_allowlist {
+            cfg.command_allowlist = allowlist.clone();
+        }
         cfg.save_to_path(&self.config_path)
     }
 
@@ -307,6 +319,7 @@ impl FirstRunWizard {
                 parity: self.defaults.parity,
                 stop_bits: self.defaults.stop_bits,
                 dtr: self.defaults.dtr_on_open,
+                ..SerialOptions::default()
             };
             let (chosen, attempts) = run_link_speed_rehearsal(
                 &device,
@@ -356,6 +369,10 @@ impl FirstRunWizard {
         display.banner("Role preference", "server/client/auto");
         let preference = prompt_role(prompter, intent.to_role_preference())?;
 
+        display.banner("Command tunnel", "optional");
+        let command_allowlist =
+            prompt_command_allowlist(prompter, &self.defaults.command_allowlist)?;
+
         let show_helpers = prompt_yes_no(
             prompter,
             "Show helper snippets (ssh/scp/tmux) (y/n)",
@@ -368,6 +385,7 @@ impl FirstRunWizard {
             cols,
             rows,
             preference,
+            command_allowlist,
             lcd_present,
             intent,
             run_probe,
@@ -423,6 +441,8 @@ struct WizardAnswers {
     cols: u8,
     rows: u8,
     preference: RolePreference,
+    /// `None` when the allowlist step was skipped and the existing list stays.
+    command_allowlist: Option<Vec<String>>,
     lcd_present: bool,
     intent: UsageIntent,
     run_probe: bool,
@@ -447,9 +467,7 @@ struct LinkRehearsalLog {
 
 impl LinkRehearsalLog {
     fn new() -> Self {
-        let path = Path::new(CACHE_DIR)
-            .join("wizard")
-            .join("link_rehearsal.log");
+        let path = cache_dir().join("wizard").join("link_rehearsal.log");
         Self { path }
     }
 
@@ -590,7 +608,8 @@ fn rehearsal_handshake<IO: crate::serial::LineIo>(
     negotiation: &crate::config::NegotiationConfig,
     compression_enabled: bool,
 ) -> Result<()> {
-    let negotiator = crate::app::negotiation::Negotiator::new(negotiation, compression_enabled);
+    let negotiator =
+        crate::app::negotiation::Negotiator::new(negotiation, compression_enabled, None)?;
     let hello_frame = negotiator.hello_frame();
     let hello_payload = serde_json::to_string(&hello_frame)
         .map_err(|e| crate::Error::Parse(format!("json: {e}")))?;
@@ -623,6 +642,8 @@ fn rehearsal_handshake<IO: crate::serial::LineIo>(
                     peer_caps: crate::negotiation::ControlCaps {
                         bits: negotiator.local_caps().bits(),
                     },
+                    // The rehearsal only times the link; it never encrypts.
+                    link_proof: None,
                 };
                 let ack_payload = serde_json::to_string(&ack)
                     .map_err(|e| crate::Error::Parse(format!("json: {e}")))?;
@@ -630,6 +651,17 @@ fn rehearsal_handshake<IO: crate::serial::LineIo>(
                 continue;
             }
             Ok(crate::negotiation::ControlFrame::HelloAck { .. }) => return Ok(()),
+            Ok(
+                crate::negotiation::ControlFrame::Resume { .. }
+                | crate::negotiation::ControlFrame::ResumeAck { .. }
+                | crate::negotiation::ControlFrame::Burst { .. }
+                | crate::negotiation::ControlFrame::BurstAck { .. }
+                | crate::negotiation::ControlFrame::BurstProbe { .. }
+                | crate::negotiation::ControlFrame::BurstEnd
+                | crate::negotiation::ControlFrame::Goodbye { .. }
+                | crate::negotiation::ControlFrame::RenderDefaults(_)
+                | crate::negotiation::ControlFrame::AnomalySummary(_),
+            ) => continue,
             Ok(crate::negotiation::ControlFrame::LegacyFallback) => {
                 return Err(crate::Error::Parse("peer requested legacy fallback".into()))
             }
@@ -751,7 +783,7 @@ struct WizardSummary {
 
 impl WizardSummary {
     fn new() -> Self {
-        let path = Path::new(CACHE_DIR).join("wizard").join("summary.log");
+        let path = cache_dir().join("wizard").join("summary.log");
         Self { path }
     }
 
@@ -791,6 +823,11 @@ impl WizardSummary {
             entry.answers.run_link_rehearsal
         )?;
         writeln!(file, "show_helpers: {}", entry.answers.show_helpers)?;
+        writeln!(
+            file,
+            "command_allowlist: {}",
+            describe_command_allowlist(entry.answers.command_allowlist.as_deref())
+        )?;
         for probe in &entry.probes {
             let status = if probe.success { "ok" } else { "error" };
             writeln!(
@@ -810,7 +847,7 @@ struct WizardTranscript {
 
 impl WizardTranscript {
     fn new() -> Self {
-        let path = Path::new(CACHE_DIR).join("wizard.log");
+        let path = cache_dir().join("wizard.log");
         Self { path }
     }
 
@@ -853,6 +890,11 @@ impl WizardTranscript {
             entry.answers.run_link_rehearsal
         )?;
         writeln!(file, "show_helpers: {}", entry.answers.show_helpers)?;
+        writeln!(
+            file,
+            "command_allowlist: {}",
+            describe_command_allowlist(entry.answers.command_allowlist.as_deref())
+        )?;
 
         writeln!(file, "candidates:")?;
         if entry.candidates.is_empty() {
@@ -955,6 +997,7 @@ impl WizardDisplay {
                 defaults.rows,
                 defaults.pcf8574_addr.clone(),
                 defaults.display_driver,
+                defaults.lcd_gpio,
             )
             .map_err(|err| {
                 eprintln!("lifelinetty wizard: LCD unavailable ({err})");
@@ -1298,6 +1341,145 @@ fn prompt_dimension(
     }
 }
 
+/// Programs the allowlist step offers; all of them only report state when run as suggested.
+const SUGGESTED_COMMANDS: &[(&str, &str)] = &[
+    ("uptime", "load and uptime"),
+    ("df", "disk usage, e.g. `df -h`"),
+    (
+        "systemctl",
+        "service state, e.g. `systemctl status`; note it can also stop services",
+    ),
+];
+
+/// Optional command tunnel step; `None` keeps the current `command_allowlist`.
+fn prompt_command_allowlist(
+    prompter: &mut WizardPrompter,
+    current: &[String],
+) -> Result<Option<Vec<String>>> {
+    if !prompt_yes_no(prompter, "Set up the command tunnel allowlist (y/n)", false)? {
+        return Ok(None);
+    }
+    println!("\n=== Command tunnel ===");
+    println!(
+        "The command tunnel lets whatever is on the other end of the serial line run programs"
+    );
+    println!(
+        "on this machine. Allow only what you need, and consider [interlock] or [tunnel_auth]."
+    );
+    println!("An empty allowlist allows every program.");
+    println!(
+        "Current allowlist: {}",
+        describe_command_allowlist(Some(current))
+    );
+    loop {
+        let response = prompter.prompt("Command tunnel (1=allowlist, 2=disable)", "allowlist")?;
+        match response.trim().to_ascii_lowercase().as_str() {
+            "1" | "allowlist" | "allow" => break,
+            "2" | "disable" | "off" | "none" => {
+                return Ok(Some(vec![COMMAND_ALLOWLIST_OFF.to_string()]))
+            }
+            "?" | "help" => eprintln!("Choose 1=allowlist, 2=disable."),
+            other => eprintln!("Unknown choice '{other}', choose allowlist or disable."),
+        }
+    }
+
+    println!("Suggested commands (entries match the program; its arguments are not checked):");
+    for (idx, (program, note)) in SUGGESTED_COMMANDS.iter().enumerate() {
+        println!("  [{}] {program}: {note}", idx + 1);
+    }
+    let mut allowlist = loop {
+        let response = prompter.prompt(
+            "Suggested commands to allow (numbers separated by commas, 'all' or 'none')",
+            "all",
+        )?;
+        match parse_suggested_picks(&response) {
+            Ok(picks) => break picks,
+            Err(err) => eprintln!("{err}"),
+        }
+    };
+    loop {
+        let response = prompter.prompt("Extra program to allow (blank to finish)", "")?;
+        let entry = response.trim();
+        if entry.is_empty() {
+            break;
+        }
+        match validate_allowlist_entry(entry) {
+            Ok(()) => append_unique(&mut allowlist, entry.to_string()),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+    if allowlist.is_empty() {
+        println!("Nothing selected; disabling the command tunnel instead of allowing everything.");
+        allowlist.push(COMMAND_ALLOWLIST_OFF.to_string());
+    }
+    Ok(Some(allowlist))
+}
+
+fn parse_suggested_picks(response: &str) -> std::result::Result<Vec<String>, String> {
+    let all = || {
+        SUGGESTED_COMMANDS
+            .iter()
+            .map(|(p, _)| p.to_string())
+            .collect()
+    };
+    match response.trim().to_ascii_lowercase().as_str() {
+        "" | "all" => return Ok(all()),
+        "none" => return Ok(Vec::new()),
+        _ => {}
+    }
+    let mut picks = Vec::new();
+    for part in response.split(',') {
+        let part = part.trim();
+        match part.parse::<usize>() {
+            Ok(idx) if (1..=SUGGESTED_COMMANDS.len()).contains(&idx) => {
+                append_unique(&mut picks, SUGGESTED_COMMANDS[idx - 1].0.to_string())
+            }
+            _ => {
+                return Err(format!(
+                    "'{part}' is not a suggestion number between 1 and {}.",
+                    SUGGESTED_COMMANDS.len()
+                ))
+            }
+        }
+    }
+    Ok(picks)
+}
+
+/// Commands run without a shell and the executor compares the program alone, so an entry is
+/// one bare program name or one absolute path.
+fn validate_allowlist_entry(entry: &str) -> std::result::Result<(), String> {
+    if entry == COMMAND_ALLOWLIST_OFF {
+        return Err(format!(
+            "'{COMMAND_ALLOWLIST_OFF}' is reserved for disabling the tunnel."
+        ));
+    }
+    if entry.chars().any(char::is_whitespace) {
+        return Err(format!(
+            "'{entry}' has arguments; enter the program only, its arguments are not checked."
+        ));
+    }
+    if entry.contains(|c: char| ";|&$<>`'\"\\*?(){}!~".contains(c)) {
+        return Err(format!(
+            "'{entry}' contains shell characters; commands run without a shell."
+        ));
+    }
+    if entry.contains('/') && !entry.starts_with('/') {
+        return Err(format!(
+            "'{entry}' is a relative path; enter a program name or an absolute path."
+        ));
+    }
+    Ok(())
+}
+
+fn describe_command_allowlist(allowlist: Option<&[String]>) -> String {
+    match allowlist {
+        None => "unchanged".to_string(),
+        Some([]) => "empty (every program is allowed)".to_string(),
+        Some([only]) if only == COMMAND_ALLOWLIST_OFF => "disabled".to_string(),
+        Some(entries) => entries.join(", "),
+    }
+}
+
 fn prompt_role(prompter: &mut WizardPrompter, default: RolePreference) -> Result<RolePreference> {
     let default_label = match default {
         RolePreference::PreferServer => "server",
@@ -1348,6 +1530,7 @@ mod tests {
             "2",
             "client",
             "n",
+            "n",
             "y",
         ];
         wizard
@@ -1359,6 +1542,41 @@ mod tests {
         assert_eq!(cfg.cols, 16);
         assert_eq!(cfg.rows, 2);
         assert_eq!(cfg.negotiation.preference, RolePreference::PreferClient);
+        assert!(cfg.command_allowlist.is_empty());
+    }
+
+    #[test]
+    fn command_allowlist_step_builds_or_disables_the_list() {
+        let mut prompter = WizardPrompter::new(scripted_input(&[
+            "y",
+            "allowlist",
+            "1,3,9",
+            "1,3",
+            "vcgencmd",
+            "rm -rf /",
+            "bin/backup",
+            "/usr/local/bin/backup",
+            "uptime",
+            "",
+        ]));
+        let allowlist = prompt_command_allowlist(&mut prompter, &[]).unwrap();
+        assert_eq!(
+            allowlist.unwrap(),
+            ["uptime", "systemctl", "vcgencmd", "/usr/local/bin/backup"]
+        );
+
+        let mut prompter = WizardPrompter::new(scripted_input(&["y", "2"]));
+        let allowlist = prompt_command_allowlist(&mut prompter, &["ls".into()]).unwrap();
+        assert_eq!(allowlist.unwrap(), [COMMAND_ALLOWLIST_OFF]);
+
+        let mut prompter = WizardPrompter::new(scripted_input(&["y", "1", "none", ""]));
+        let allowlist = prompt_command_allowlist(&mut prompter, &[]).unwrap();
+        assert_eq!(allowlist.unwrap(), [COMMAND_ALLOWLIST_OFF]);
+
+        let mut prompter = WizardPrompter::new(PromptInput::AutoDefaults {
+            reason: "test".into(),
+        });
+        assert_eq!(prompt_command_allowlist(&mut prompter, &[]).unwrap(), None);
     }
 
     #[test]
@@ -1431,7 +1649,7 @@ mod tests {
     #[test]
     fn link_rehearsal_log_stays_under_cache_dir() {
         let log = LinkRehearsalLog::new();
-        assert!(log.path.starts_with(CACHE_DIR));
+        assert!(log.path.starts_with(crate::CACHE_DIR));
         assert!(log.path.ends_with(Path::new("wizard/link_rehearsal.log")));
     }
 }
@@ -0,0 +1,137 @@
+//! I2C write accounting for the render path.
+//!
+//! The LCD counts the bytes an HD44780 on a PCF8574 backpack would take for everything drawn
+//! (see `Lcd::bus_bytes`). [`WriteBudget`] turns that into a bytes-per-second rate and the size
+//! of each full-frame write for the metrics endpoint and `loop_stats.log`, and, with
+//! `[render].i2c_write_budget_bytes`, warns when frames go over the budget. Warnings are
+//! folded into one line per [`WARN_INTERVAL`] so a page that is always over does not flood the
+//! log.
+
+use std::time::{Duration, Instant};
+
+/// Shortest gap between two over-budget warnings.
+pub const WARN_INTERVAL: Duration = Duration::from_secs(60);
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct WriteBudget {
+    /// Per-frame budget in bytes; 0 turns the warning off.
+    budget: u32,
+    last_frame: u64,
+    over_budget: u64,
+    /// Over-budget frames since the last warning, and the largest of them.
+    unreported: u64,
+    unreported_peak: u64,
+    next_warning: Instant,
+    window_start: Instant,
+    window_base: u64,
+    per_second: u64,
+}
+
+impl WriteBudget {
+    pub fn new(budget: u32, now: Instant) -> Self {
+        Self {
+            budget,
+            last_frame: 0,
+            over_budget: 0,
+            unreported: 0,
+            unreported_peak: 0,
+            next_warning: now,
+            window_start: now,
+            window_base: 0,
+            per_second: 0,
+        }
+    }
+
+    pub fn set_budget(&mut self, budget: u32) {
+        self.budget = budget;
+    }
+
+    /// Note a full-frame write of `bytes`. Returns a warning to log when frames have gone over
+    /// the budget and the last warning is at least [`WARN_INTERVAL`] old.
+    pub fn record_frame(&mut self, bytes: u64, now: Instant) -> Option<String> {
+        self.last_frame = bytes;
+        if self.budget == 0 || bytes <= u64::from(self.budget) {
+            return None;
+        }
+        self.over_budget += 1;
+        self.unreported += 1;
+        self.unreported_peak = self.unreported_peak.max(bytes);
+        if now < self.next_warning {
+            return None;
+        }
+        let warning = format!(
+            "render: {} frame(s) over the {}-byte I2C write budget (largest {} bytes)",
+            self.unreported, self.budget, self.unreported_peak
+        );
+        self.unreported = 0;
+        self.unreported_peak = 0;
+        self.next_warning = now + WARN_INTERVAL;
+        Some(warning)
+    }
+
+    /// Sample the display's running byte count; the rate is refreshed once a second.
+    pub fn observe(&mut self, total: u64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < RATE_WINDOW {
+            return;
+        }
+        let sent = total.saturating_sub(self.window_base);
+        self.per_second = (sent as f64 / elapsed.as_secs_f64()).round() as u64;
+        self.window_start = now;
+        self.window_base = total;
+    }
+
+    pub fn bytes_per_second(&self) -> u64 {
+        self.per_second
+    }
+
+    pub fn last_frame_bytes(&self) -> u64 {
+        self.last_frame
+    }
+
+    /// Frames that went over the budget since startup.
+    pub fn over_budget(&self) -> u64 {
+        self.over_budget
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_once_per_interval_with_the_frames_it_folded() {
+        let start = Instant::now();
+        let mut budget = WriteBudget::new(200, start);
+        assert_eq!(budget.record_frame(150, start), None);
+        let first = budget.record_frame(320, start).unwrap();
+        assert!(first.contains("1 frame(s)") && first.contains("largest 320"));
+        assert_eq!(
+            budget.record_frame(400, start + Duration::from_secs(1)),
+            None
+        );
+        assert_eq!(
+            budget.record_frame(260, start + Duration::from_secs(2)),
+            None
+        );
+        let later = budget.record_frame(210, start + WARN_INTERVAL).unwrap();
+        assert!(later.contains("3 frame(s)") && later.contains("largest 400"));
+        assert_eq!((budget.over_budget(), budget.last_frame_bytes()), (4, 210));
+
+        budget.set_budget(0);
+        assert_eq!(budget.record_frame(10_000, start + WARN_INTERVAL * 2), None);
+    }
+
+    #[test]
+    fn rate_is_bytes_over_the_last_window() {
+        let start = Instant::now();
+        let mut budget = WriteBudget::new(0, start);
+        budget.observe(500, start + Duration::from_millis(400));
+        assert_eq!(budget.bytes_per_second(), 0);
+        budget.observe(1_000, start + Duration::from_secs(2));
+        assert_eq!(budget.bytes_per_second(), 500);
+        budget.observe(1_300, start + Duration::from_secs(3));
+        assert_eq!(budget.bytes_per_second(), 300);
+    }
+}
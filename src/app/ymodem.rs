@@ -0,0 +1,534 @@
+//! YMODEM framing for peers that only speak classic serial file transfer.
+//!
+//! Blocks carry 1024 (`STX`) or 128 (`SOH`) bytes between the block number and its complement
+//! and a CRC-16/XMODEM trailer. Block 0 names the file and its size, and an empty block 0 ends the
+//! batch. The receiver also takes a plain XMODEM-CRC stream that starts at block 1: the name is
+//! then unknown and trailing `SUB` padding is stripped. Neither protocol can resume, so an
+//! interrupted transfer starts again from the first block.
+
+use crate::{Error, Result};
+use std::{
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+const SOH: u8 = 0x01;
+const STX: u8 = 0x02;
+const EOT: u8 = 0x04;
+const ACK: u8 = 0x06;
+const NAK: u8 = 0x15;
+const CAN: u8 = 0x18;
+/// Padding after the last data byte.
+const SUB: u8 = 0x1a;
+/// Sent by the receiver to ask for CRC-16 blocks rather than the original checksum.
+const CRC_REQUEST: u8 = b'C';
+const BLOCK_1K: usize = 1024;
+const BLOCK_128: usize = 128;
+/// Resends of one block, or consecutive bad blocks, before the transfer is abandoned.
+const MAX_RETRIES: u32 = 10;
+/// Name given to a file that arrived over XMODEM, which carries none.
+pub const XMODEM_FILE_NAME: &str = "xmodem.bin";
+
+/// What block 0 announces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YmodemHeader {
+    pub name: String,
+    /// Absent for XMODEM and for YMODEM senders that leave the field out.
+    pub size: Option<u64>,
+}
+
+/// CRC-16/XMODEM (polynomial 0x1021, initial value 0), as carried by every block.
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// Send one file as a single-file batch. `progress` sees the bytes acknowledged so far.
+pub fn send<T: Read + Write, R: Read>(
+    io: &mut T,
+    header: &YmodemHeader,
+    data: &mut R,
+    timeout: Duration,
+    progress: &mut dyn FnMut(u64),
+) -> Result<()> {
+    wait_for_go_ahead(io, timeout)?;
+    send_block(io, &header_block(header), timeout)?;
+    wait_for_go_ahead(io, timeout)?;
+
+    let mut seq: u8 = 1;
+    let mut sent = 0u64;
+    let mut buf = vec![0u8; BLOCK_1K];
+    loop {
+        let read = read_full(data, &mut buf)?;
+        if read == 0 {
+            break;
+        }
+        let size = if read <= BLOCK_128 {
+            BLOCK_128
+        } else {
+            BLOCK_1K
+        };
+        send_block(io, &encode_block(seq, &buf[..read], size, SUB), timeout)?;
+        seq = seq.wrapping_add(1);
+        sent += read as u64;
+        progress(sent);
+        if read < BLOCK_1K {
+            break;
+        }
+    }
+
+    // Classic receivers NAK the first EOT to make sure it was not line noise.
+    send_block(io, &[EOT], timeout)?;
+    wait_for_go_ahead(io, timeout)?;
+    send_block(io, &encode_block(0, &[], BLOCK_128, 0), timeout)
+}
+
+/// Receive one file. `open` is handed the header once it is known and returns the sink the
+/// data is written to; both come back once the sender has finished.
+pub fn receive<T, S, F>(io: &mut T, timeout: Duration, open: F) -> Result<(YmodemHeader, S)>
+where
+    T: Read + Write,
+    S: Write,
+    F: FnOnce(&YmodemHeader) -> Result<S>,
+{
+    let (header, first_block) = receive_header(io, timeout)?;
+    let ymodem = first_block.is_none();
+    let mut sink = open(&header)?;
+    io.write_all(&[ACK])?;
+    if ymodem {
+        io.write_all(&[CRC_REQUEST])?;
+    }
+    io.flush()?;
+
+    // The newest block is held back until the next one arrives, since only the last block
+    // carries padding.
+    let mut held = first_block;
+    let mut expected: u8 = if ymodem { 1 } else { 2 };
+    let mut written = 0u64;
+    let mut errors = 0;
+    let mut eot_seen = false;
+    loop {
+        let reply = match read_frame(io, timeout)? {
+            Frame::Block { seq, data } if seq == expected => {
+                if let Some(block) = held.replace(data) {
+                    written += write_block(&mut sink, &block, header.size, written, false)?;
+                }
+                expected = expected.wrapping_add(1);
+                errors = 0;
+                ACK
+            }
+            // Our ACK was lost and the sender repeated the block.
+            Frame::Block { seq, .. } if seq == expected.wrapping_sub(1) => ACK,
+            Frame::Block { seq, .. } => {
+                cancel(io);
+                return Err(Error::Parse(format!(
+                    "ymodem: expected block {expected}, got {seq}"
+                )));
+            }
+            Frame::Eot if eot_seen => break,
+            Frame::Eot => {
+                eot_seen = true;
+                NAK
+            }
+            Frame::Cancel => return Err(cancelled()),
+            Frame::Bad | Frame::Timeout => {
+                errors += 1;
+                if errors > MAX_RETRIES {
+                    cancel(io);
+                    return Err(Error::Parse("ymodem: too many bad blocks".into()));
+                }
+                purge(io);
+                NAK
+            }
+        };
+        io.write_all(&[reply])?;
+        io.flush()?;
+    }
+    if let Some(block) = held.take() {
+        write_block(&mut sink, &block, header.size, written, true)?;
+    }
+    sink.flush()?;
+    io.write_all(&[ACK])?;
+    io.flush()?;
+
+    if ymodem {
+        finish_batch(io, timeout)?;
+    }
+    Ok((header, sink))
+}
+
+enum Frame {
+    Block { seq: u8, data: Vec<u8> },
+    Eot,
+    Cancel,
+    Bad,
+    Timeout,
+}
+
+/// Ask for block 0 until it, or an XMODEM block 1, arrives. The second half of the result is
+/// the XMODEM block, whose data still has to be written.
+fn receive_header<T: Read + Write>(
+    io: &mut T,
+    timeout: Duration,
+) -> Result<(YmodemHeader, Option<Vec<u8>>)> {
+    for _ in 0..MAX_RETRIES {
+        io.write_all(&[CRC_REQUEST])?;
+        io.flush()?;
+        match read_frame(io, timeout)? {
+            Frame::Block { seq: 0, data } => {
+                let header = parse_header(&data)?;
+                if header.name.is_empty() {
+                    io.write_all(&[ACK])?;
+                    return Err(Error::Parse("ymodem: sender had no file to send".into()));
+                }
+                return Ok((header, None));
+            }
+            Frame::Block { seq: 1, data } => {
+                let header = YmodemHeader {
+                    name: XMODEM_FILE_NAME.to_string(),
+                    size: None,
+                };
+                return Ok((header, Some(data)));
+            }
+            Frame::Cancel => return Err(cancelled()),
+            Frame::Block { .. } | Frame::Eot | Frame::Bad => purge(io),
+            Frame::Timeout => {}
+        }
+    }
+    Err(Error::Parse("ymodem: no sender answered".into()))
+}
+
+/// Acknowledge the empty block 0 that closes the batch. The file is already complete, so a
+/// sender that never sends it is not an error.
+fn finish_batch<T: Read + Write>(io: &mut T, timeout: Duration) -> Result<()> {
+    for _ in 0..MAX_RETRIES {
+        io.write_all(&[CRC_REQUEST])?;
+        io.flush()?;
+        match read_frame(io, timeout)? {
+            Frame::Block { seq: 0, .. } | Frame::Eot => {
+                io.write_all(&[ACK])?;
+                io.flush()?;
+                return Ok(());
+            }
+            Frame::Cancel | Frame::Timeout => return Ok(()),
+            Frame::Block { .. } | Frame::Bad => purge(io),
+        }
+    }
+    Ok(())
+}
+
+fn header_block(header: &YmodemHeader) -> Vec<u8> {
+    let mut payload = header.name.as_bytes().to_vec();
+    payload.push(0);
+    if let Some(size) = header.size {
+        payload.extend_from_slice(size.to_string().as_bytes());
+    }
+    let size = if payload.len() <= BLOCK_128 {
+        BLOCK_128
+    } else {
+        BLOCK_1K
+    };
+    payload.truncate(size);
+    encode_block(0, &payload, size, 0)
+}
+
+fn parse_header(data: &[u8]) -> Result<YmodemHeader> {
+    let mut fields = data.split(|byte| *byte == 0);
+    let name = String::from_utf8_lossy(fields.next().unwrap_or_default()).into_owned();
+    let size = fields
+        .next()
+        .and_then(|field| std::str::from_utf8(field).ok())
+        .and_then(|field| field.split(' ').next())
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            field
+                .parse()
+                .map_err(|_| Error::Parse(format!("ymodem: bad file size '{field}'")))
+        })
+        .transpose()?;
+    Ok(YmodemHeader { name, size })
+}
+
+fn encode_block(seq: u8, data: &[u8], size: usize, pad: u8) -> Vec<u8> {
+    let mut block = Vec::with_capacity(size + 5);
+    block.push(if size == BLOCK_1K { STX } else { SOH });
+    block.push(seq);
+    block.push(!seq);
+    block.extend_from_slice(data);
+    block.resize(3 + size, pad);
+    let crc = crc16(&block[3..]);
+    block.extend_from_slice(&crc.to_be_bytes());
+    block
+}
+
+/// Write `block` to the sink, cut to the announced size or, without one, stripped of padding
+/// when it is the last.
+fn write_block<S: Write>(
+    sink: &mut S,
+    block: &[u8],
+    size: Option<u64>,
+    written: u64,
+    last: bool,
+) -> Result<u64> {
+    let data = match size {
+        Some(size) => {
+            let remaining = size.saturating_sub(written);
+            &block[..block.len().min(remaining as usize)]
+        }
+        None if last => {
+            let end = block
+                .iter()
+                .rposition(|byte| *byte != SUB)
+                .map_or(0, |pos| pos + 1);
+            &block[..end]
+        }
+        None => block,
+    };
+    sink.write_all(data)?;
+    Ok(data.len() as u64)
+}
+
+fn read_frame<T: Read>(io: &mut T, timeout: Duration) -> Result<Frame> {
+    let deadline = Instant::now() + timeout;
+    let Some(first) = read_byte(io, deadline)? else {
+        return Ok(Frame::Timeout);
+    };
+    let size = match first {
+        SOH => BLOCK_128,
+        STX => BLOCK_1K,
+        EOT => return Ok(Frame::Eot),
+        CAN => return Ok(Frame::Cancel),
+        _ => return Ok(Frame::Bad),
+    };
+    let mut rest = vec![0u8; size + 4];
+    for slot in rest.iter_mut() {
+        match read_byte(io, deadline)? {
+            Some(byte) => *slot = byte,
+            None => return Ok(Frame::Bad),
+        }
+    }
+    let (seq, inverse) = (rest[0], rest[1]);
+    let data = &rest[2..2 + size];
+    let crc = u16::from_be_bytes([rest[2 + size], rest[3 + size]]);
+    if seq != !inverse || crc16(data) != crc {
+        return Ok(Frame::Bad);
+    }
+    Ok(Frame::Block {
+        seq,
+        data: data.to_vec(),
+    })
+}
+
+/// Resend `block` until the receiver ACKs it.
+fn send_block<T: Read + Write>(io: &mut T, block: &[u8], timeout: Duration) -> Result<()> {
+    for _ in 0..MAX_RETRIES {
+        io.write_all(block)?;
+        io.flush()?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            match read_byte(io, deadline)? {
+                Some(ACK) => return Ok(()),
+                Some(CAN) => return Err(cancelled()),
+                Some(NAK) | None => break,
+                // A repeated 'C' or line noise.
+                Some(_) => {}
+            }
+        }
+    }
+    Err(Error::Parse(format!(
+        "ymodem: block not acknowledged after {MAX_RETRIES} tries"
+    )))
+}
+
+/// Wait for the receiver's 'C'. A NAK is taken as a go-ahead too, since that is what a receiver
+/// repeats when the 'C' was lost.
+fn wait_for_go_ahead<T: Read>(io: &mut T, timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout * MAX_RETRIES;
+    loop {
+        match read_byte(io, deadline)? {
+            Some(CRC_REQUEST) | Some(NAK) => return Ok(()),
+            Some(CAN) => return Err(cancelled()),
+            Some(_) => {}
+            None => return Err(Error::Parse("ymodem: receiver never asked for data".into())),
+        }
+    }
+}
+
+fn read_byte<T: Read>(io: &mut T, deadline: Instant) -> Result<Option<u8>> {
+    let mut byte = [0u8; 1];
+    loop {
+        match io.read(&mut byte) {
+            Ok(1) => return Ok(Some(byte[0])),
+            Ok(_) => {}
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::TimedOut
+                        | io::ErrorKind::WouldBlock
+                        | io::ErrorKind::Interrupted
+                ) => {}
+            Err(err) => return Err(Error::Io(err)),
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+    }
+}
+
+fn read_full<R: Read>(data: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match data.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(Error::Io(err)),
+        }
+    }
+    Ok(filled)
+}
+
+/// Drop whatever is left of a damaged block so its bytes are not read as the next header.
+fn purge<T: Read>(io: &mut T) {
+    let deadline = Instant::now();
+    while let Ok(Some(_)) = read_byte(io, deadline) {}
+}
+
+fn cancel<T: Write>(io: &mut T) {
+    let _ = io.write_all(&[CAN, CAN]);
+    let _ = io.flush();
+}
+
+fn cancelled() -> Error {
+    Error::Parse("ymodem: transfer cancelled by the peer".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::mpsc::{channel, Receiver, Sender},
+        thread,
+    };
+
+    /// One end of an in-memory serial line.
+    struct Wire {
+        tx: Sender<u8>,
+        rx: Receiver<u8>,
+    }
+
+    impl Read for Wire {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.rx.recv_timeout(Duration::from_millis(5)) {
+                Ok(byte) => {
+                    buf[0] = byte;
+                    Ok(1)
+                }
+                Err(_) => Err(io::ErrorKind::TimedOut.into()),
+            }
+        }
+    }
+
+    impl Write for Wire {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            for byte in buf {
+                let _ = self.tx.send(*byte);
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn wire_pair() -> (Wire, Wire) {
+        let (a_tx, b_rx) = channel();
+        let (b_tx, a_rx) = channel();
+        (Wire { tx: a_tx, rx: a_rx }, Wire { tx: b_tx, rx: b_rx })
+    }
+
+    #[test]
+    fn crc16_matches_the_xmodem_check_value() {
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+    }
+
+    #[test]
+    fn batch_round_trips_and_cuts_padding_to_the_announced_size() {
+        let data: Vec<u8> = (0..2_500u32).map(|n| (n % 251) as u8).collect();
+        let (mut sender_end, mut receiver_end) = wire_pair();
+        let header = YmodemHeader {
+            name: "fw.bin".into(),
+            size: Some(data.len() as u64),
+        };
+        let sent = data.clone();
+        let sender = thread::spawn(move || {
+            let mut acked = 0;
+            send(
+                &mut sender_end,
+                &header,
+                &mut sent.as_slice(),
+                Duration::from_millis(200),
+                &mut |bytes| acked = bytes,
+            )
+            .map(|_| acked)
+        });
+
+        let (header, received) = receive(&mut receiver_end, Duration::from_millis(200), |_| {
+            Ok(Vec::new())
+        })
+        .unwrap();
+        assert_eq!(sender.join().unwrap().unwrap(), data.len() as u64);
+        assert_eq!(header.name, "fw.bin");
+        assert_eq!(header.size, Some(2_500));
+        assert_eq!(received, data);
+    }
+
+    #[test]
+    fn xmodem_stream_strips_trailing_padding() {
+        let (mut sender_end, mut receiver_end) = wire_pair();
+        let sender = thread::spawn(move || {
+            // A bare XMODEM-CRC sender: block 1 straight after the 'C', then EOT until ACKed.
+            wait_for_go_ahead(&mut sender_end, Duration::from_millis(200))?;
+            let block = encode_block(1, b"hello", BLOCK_128, SUB);
+            send_block(&mut sender_end, &block, Duration::from_millis(200))?;
+            send_block(&mut sender_end, &[EOT], Duration::from_millis(200))
+        });
+
+        let (header, received) = receive(&mut receiver_end, Duration::from_millis(200), |_| {
+            Ok(Vec::new())
+        })
+        .unwrap();
+        sender.join().unwrap().unwrap();
+        assert_eq!(header.name, XMODEM_FILE_NAME);
+        assert_eq!(received, b"hello");
+    }
+
+    #[test]
+    fn damaged_blocks_are_refused() {
+        let mut block = encode_block(3, b"abc", BLOCK_128, SUB);
+        assert!(matches!(
+            read_frame(&mut block.as_slice(), Duration::ZERO).unwrap(),
+            Frame::Block { seq: 3, .. }
+        ));
+        block[10] ^= 0xff;
+        assert!(matches!(
+            read_frame(&mut block.as_slice(), Duration::ZERO).unwrap(),
+            Frame::Bad
+        ));
+        assert_eq!(
+            parse_header(b"notes.txt\x00123 0\x00").unwrap(),
+            YmodemHeader {
+                name: "notes.txt".into(),
+                size: Some(123)
+            }
+        );
+    }
+}
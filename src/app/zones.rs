@@ -0,0 +1,255 @@
+//! Zones: named blocks of rows that payloads address with `target`.
+//!
+//! Each `[[zones]]` entry owns some rows on the main display or on a `[[displays]]` entry with
+//! `role = "zones"`. Frames sent to a zone rotate and scroll there on their own timers (see
+//! [`RenderState::poll_zone`]) while the main rotation keeps the other rows. On the main display
+//! a [`ZoneMask`] sits under the backlight gate, so page, HUD and status writes pass through it
+//! and leave the zone rows alone.
+
+use std::time::Instant;
+
+use super::AppConfig;
+use crate::{
+    config::{Pcf8574Addr, ZoneConfig},
+    display::{backend::DisplayBackend, overlays::zone_view},
+    state::RenderState,
+    Result,
+};
+
+/// What one zone shows on this pass of the render loop.
+pub(super) struct ZoneRows {
+    /// Address of the extra display it sits on; `None` for the main display.
+    pub display: Option<u8>,
+    pub row: u8,
+    /// One padded line per zone row.
+    pub lines: Vec<String>,
+}
+
+/// Step every configured zone to `now` and lay out its rows for the display it sits on.
+pub(super) fn poll_zones(
+    state: &mut RenderState,
+    config: &AppConfig,
+    now: Instant,
+) -> Vec<ZoneRows> {
+    config
+        .zones
+        .iter()
+        .map(|zone| ZoneRows {
+            display: zone.display,
+            row: zone.row,
+            lines: zone_lines(state, zone, zone_width(config, zone), now),
+        })
+        .collect()
+}
+
+fn zone_width(config: &AppConfig, zone: &ZoneConfig) -> u8 {
+    match zone.display {
+        None => config.cols,
+        Some(addr) => config
+            .displays
+            .iter()
+            .find(|display| display.addr == Pcf8574Addr::Addr(addr))
+            .map_or(config.cols, |display| display.cols),
+    }
+}
+
+fn zone_lines(state: &mut RenderState, zone: &ZoneConfig, cols: u8, now: Instant) -> Vec<String> {
+    let width = cols as usize;
+    match state.poll_zone(&zone.name, now, zone.rows, width) {
+        Some((frame, offsets)) => zone_view(frame, offsets, zone.rows, width),
+        None => vec![" ".repeat(width); zone.rows as usize],
+    }
+}
+
+/// Hand a new `[[zones]]` list to the mask on the main display and to the render state.
+pub(super) fn set_zones<D: DisplayBackend>(
+    mask: &mut ZoneMask<'_, D>,
+    state: &mut RenderState,
+    zones: &[ZoneConfig],
+) {
+    mask.set_zones(zones);
+    state.set_zones(zones.iter().map(|zone| zone.name.as_str()));
+}
+
+/// Display wrapper that keeps the rows zones own out of reach of everything else drawn on it.
+/// Writes to those rows are dropped, and a clear or redraw puts the zone text back.
+pub struct ZoneMask<'a, D: DisplayBackend> {
+    inner: &'a mut D,
+    /// Text on each zone row; `None` for rows the main rotation draws.
+    zones: Vec<Option<String>>,
+}
+
+impl<'a, D: DisplayBackend> ZoneMask<'a, D> {
+    pub fn new(inner: &'a mut D) -> Self {
+        let rows = inner.rows() as usize;
+        Self {
+            inner,
+            zones: vec![None; rows],
+        }
+    }
+
+    /// Hand the rows of the main-display `zones` to them, blank until drawn; every other row
+    /// goes back to the main rotation.
+    pub fn set_zones(&mut self, zones: &[ZoneConfig]) {
+        self.zones = vec![None; self.inner.rows() as usize];
+        for zone in zones.iter().filter(|zone| zone.display.is_none()) {
+            for row in zone.row..zone.row + zone.rows {
+                if let Some(slot) = self.zones.get_mut(row as usize) {
+                    *slot = Some(String::new());
+                }
+            }
+        }
+    }
+
+    /// Draw the zones laid out by [`poll_zones`] that sit on this display.
+    pub(super) fn draw_zones(&mut self, zones: &[ZoneRows]) -> Result<()> {
+        for zone in zones.iter().filter(|zone| zone.display.is_none()) {
+            for (row, line) in (zone.row..).zip(&zone.lines) {
+                self.draw_zone(row, line)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Put `line` on zone row `row`; unchanged text costs nothing.
+    pub fn draw_zone(&mut self, row: u8, line: &str) -> Result<()> {
+        let Some(Some(shown)) = self.zones.get_mut(row as usize) else {
+            return Ok(());
+        };
+        if shown != line {
+            *shown = line.to_string();
+            self.inner.write_row_diff(row, line)?;
+        }
+        Ok(())
+    }
+
+    fn is_zone_row(&self, row: u8) -> bool {
+        matches!(self.zones.get(row as usize), Some(Some(_)))
+    }
+
+    /// Write the zone text back, after something below may have wiped it.
+    fn restore(&mut self) -> Result<()> {
+        for (row, line) in self.zones.iter().enumerate() {
+            if let Some(line) = line {
+                self.inner.write_row_diff(row as u8, line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<D: DisplayBackend> DisplayBackend for ZoneMask<'_, D> {
+    fn cols(&self) -> u8 {
+        self.inner.cols()
+    }
+
+    fn rows(&self) -> u8 {
+        self.inner.rows()
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.inner.clear()?;
+        self.restore()
+    }
+
+    fn write_line(&mut self, row: u8, content: &str) -> Result<()> {
+        if self.is_zone_row(row) {
+            return Ok(());
+        }
+        self.inner.write_line(row, content)
+    }
+
+    fn set_backlight(&mut self, on: bool) -> Result<()> {
+        self.inner.set_backlight(on)
+    }
+
+    fn set_backlight_level(&mut self, percent: u8) -> Result<()> {
+        self.inner.set_backlight_level(percent)
+    }
+
+    fn set_blink(&mut self, on: bool) -> Result<()> {
+        self.inner.set_blink(on)
+    }
+
+    fn custom_char(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<()> {
+        self.inner.custom_char(slot, bitmap)
+    }
+
+    fn write_lines(&mut self, lines: &[&str]) -> Result<()> {
+        if self.zones.iter().all(Option::is_none) {
+            return self.inner.write_lines(lines);
+        }
+        let lines: Vec<&str> = lines
+            .iter()
+            .enumerate()
+            .map(|(row, line)| match self.zones.get(row) {
+                Some(Some(zone)) => zone.as_str(),
+                _ => line,
+            })
+            .collect();
+        self.inner.write_lines(&lines)
+    }
+
+    fn prepare_redraw(&mut self) -> Result<()> {
+        self.inner.prepare_redraw()?;
+        self.restore()
+    }
+
+    fn write_row_diff(&mut self, row: u8, content: &str) -> Result<usize> {
+        if self.is_zone_row(row) {
+            return Ok(0);
+        }
+        self.inner.write_row_diff(row, content)
+    }
+
+    fn take_write_time(&mut self) -> Option<std::time::Duration> {
+        self.inner.take_write_time()
+    }
+
+    fn take_write_bytes(&mut self) -> Option<u64> {
+        self.inner.take_write_bytes()
+    }
+
+    fn bus_bytes(&self) -> u64 {
+        self.inner.bus_bytes()
+    }
+
+    fn screen(&self) -> Vec<String> {
+        self.inner.screen()
+    }
+
+    fn reconfigure(&mut self, cols: u8, rows: u8) -> Result<()> {
+        self.inner.reconfigure(cols, rows)?;
+        self.zones.resize(rows as usize, None);
+        Ok(())
+    }
+
+    fn recover(&mut self, now: Instant) -> bool {
+        self.inner.recover(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::backend::recording::RecordingDisplay;
+
+    #[test]
+    fn page_writes_leave_the_zone_rows_alone() {
+        let mut glass = RecordingDisplay::new(16, 4);
+        let mut mask = ZoneMask::new(&mut glass);
+        mask.set_zones(&[ZoneConfig {
+            name: "alerts".into(),
+            display: None,
+            row: 3,
+            rows: 1,
+        }]);
+        mask.draw_zone(3, "disk 91%        ").unwrap();
+        mask.write_lines(&["CPU 42%", "up 3d", "eth0", "page row 4"])
+            .unwrap();
+        mask.write_line(3, "HUD").unwrap();
+        mask.clear().unwrap();
+        mask.write_line(0, "ERR PARSE").unwrap();
+        assert_eq!(glass.lines(), ["ERR PARSE", "", "", "disk 91%        "]);
+    }
+}
@@ -1,9 +1,544 @@
 use crate::{
+    cache_dir,
     compression::CompressionCodec,
-    config::Pcf8574Addr,
+    config::{
+        DisplayDriver, Pcf8574Addr, DEFAULT_COLS, MAX_COLS, MAX_INTERLOCK_ARM_MS, MIN_COLS,
+        MIN_INTERLOCK_ARM_MS,
+    },
+    display::capture::CaptureFormat,
     serial::{DtrBehavior, FlowControlMode, ParityMode, StopBitsMode},
-    Error, Result,
+    Error, Result, CACHE_DIR,
 };
+use clap_complete::Shell;
+
+/// What follows a flag on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagValue {
+    /// On/off switch without a value.
+    Switch,
+    /// Free-form value; the string is the placeholder shown in help.
+    Value(&'static str),
+    Path,
+    /// One of a fixed set of words (the parser may accept extra aliases).
+    Choice(&'static [&'static str]),
+}
+
+/// One flag, or positional argument, of the CLI table.
+#[derive(Debug, Clone, Copy)]
+pub struct FlagSpec {
+    pub name: &'static str,
+    pub value: FlagValue,
+    pub help: &'static str,
+}
+
+impl FlagSpec {
+    /// `--flag <value>` as shown in help.
+    pub fn usage(&self) -> String {
+        match self.value {
+            FlagValue::Switch => self.name.to_string(),
+            FlagValue::Value(placeholder) => format!("{} <{placeholder}>", self.name),
+            FlagValue::Path => format!("{} <path>", self.name),
+            FlagValue::Choice(choices) => format!("{} <{}>", self.name, choices.join("|")),
+        }
+    }
+}
+
+/// One subcommand of the CLI table.
+#[derive(Debug, Clone, Copy)]
+pub struct SubcommandSpec {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub flags: &'static [FlagSpec],
+    /// Positional arguments in order; `name` doubles as the placeholder.
+    pub args: &'static [FlagSpec],
+    pub subcommands: &'static [SubcommandSpec],
+}
+
+const fn switch(name: &'static str, help: &'static str) -> FlagSpec {
+    FlagSpec {
+        name,
+        value: FlagValue::Switch,
+        help,
+    }
+}
+
+const fn value(name: &'static str, placeholder: &'static str, help: &'static str) -> FlagSpec {
+    FlagSpec {
+        name,
+        value: FlagValue::Value(placeholder),
+        help,
+    }
+}
+
+const fn path(name: &'static str, help: &'static str) -> FlagSpec {
+    FlagSpec {
+        name,
+        value: FlagValue::Path,
+        help,
+    }
+}
+
+const fn choice(
+    name: &'static str,
+    choices: &'static [&'static str],
+    help: &'static str,
+) -> FlagSpec {
+    FlagSpec {
+        name,
+        value: FlagValue::Choice(choices),
+        help,
+    }
+}
+
+/// Flags accepted anywhere on the command line and applied to every subcommand; `main` takes
+/// them out with [`take_instance`] before parsing the rest.
+pub const GLOBAL_FLAGS: &[FlagSpec] = &[value(
+    "--instance",
+    "name",
+    "Run as a named instance with its own config file, cache dir, control socket and logs",
+)];
+
+/// Flags of `run`, which are also accepted without the `run` keyword. The parser, `--help`
+/// and `completions` all read this table.
+pub const RUN_FLAGS: &[FlagSpec] = &[
+    path("--device", "Serial device path (default: /dev/ttyUSB0)"),
+    value(
+        "--input",
+        "serial|fifo:<path>",
+        "Read payload lines from --device (default) or a FIFO under /run/serial_lcd_cache",
+    ),
+    value(
+        "--listen",
+        "tcp:<addr>",
+        "Take frames from one TCP client at a time instead of --device (e.g. tcp:127.0.0.1:7777)",
+    ),
+    value("--baud", "number", "Baud rate (default: 9600)"),
+    choice(
+        "--flow-control",
+        &["none", "software", "hardware"],
+        "Flow control override (default: none)",
+    ),
+    choice(
+        "--parity",
+        &["none", "odd", "even"],
+        "Parity override (default: none)",
+    ),
+    choice("--stop-bits", &["1", "2"], "Stop bits override (default: 1)"),
+    choice(
+        "--dtr-on-open",
+        &["auto", "on", "off"],
+        "Control DTR state when opening the port (default: auto)",
+    ),
+    value(
+        "--serial-timeout-ms",
+        "number",
+        "Read timeout in milliseconds (default: 500)",
+    ),
+    switch(
+        "--low-latency",
+        "Shorten the USB adapter latency timer (default: off)",
+    ),
+    switch(
+        "--no-low-latency",
+        "Leave the latency timer alone even if config enables it",
+    ),
+    switch(
+        "--autobaud",
+        "Sample candidate rates at startup and switch to the detected baud",
+    ),
+    switch(
+        "--no-autobaud",
+        "Keep the configured baud even if config enables autobaud",
+    ),
+    switch(
+        "--autobaud-probe",
+        "Listen only, print the suggested baud for --device, and exit",
+    ),
+    value("--cols", "number", "LCD columns (default: 16)"),
+    value("--rows", "number", "LCD rows (default: 2)"),
+    path(
+        "--payload-file",
+        "Load a local JSON payload and render it once (testing helper)",
+    ),
+    path(
+        "--payload-watch",
+        "Re-render a local JSON payload every time the file changes",
+    ),
+    value(
+        "--backoff-initial-ms",
+        "number",
+        "Initial reconnect backoff (default: 500)",
+    ),
+    value(
+        "--backoff-max-ms",
+        "number",
+        "Maximum reconnect backoff (default: 10000)",
+    ),
+    value(
+        "--pcf8574-addr",
+        "auto|0xNN",
+        "PCF8574 I2C address or 'auto' to probe (default: auto)",
+    ),
+    choice(
+        "--display-driver",
+        &["auto", "in-tree", "hd44780-driver", "ssd1306", "sh1106", "gpio"],
+        "LCD driver backend (default: config)",
+    ),
+    choice(
+        "--log-level",
+        &["error", "warn", "info", "debug", "trace"],
+        "Log verbosity (default: info)",
+    ),
+    path(
+        "--log-file",
+        "Append logs inside /run/serial_lcd_cache (also honors LIFELINETTY_LOG_PATH)",
+    ),
+    path(
+        "--config-file",
+        "Load config from the provided TOML instead of ~/.serial_lcd/config.toml (env overrides still apply)",
+    ),
+    switch("--polling", "Enable hardware polling (default: config)"),
+    switch(
+        "--no-polling",
+        "Disable hardware polling even if config enables it",
+    ),
+    value(
+        "--poll-interval-ms",
+        "number",
+        "Polling interval in milliseconds (default: 5000)",
+    ),
+    switch(
+        "--compressed",
+        "Enable schema compression (applies to schema_v1 payloads)",
+    ),
+    switch(
+        "--no-compressed",
+        "Disable compression even if config enables it",
+    ),
+    choice(
+        "--codec",
+        &["none", "lz4", "zstd"],
+        "Codec to use when compression is enabled (default: lz4)",
+    ),
+    switch(
+        "--demo",
+        "Run built-in demo pages on the LCD (no serial input)",
+    ),
+    path(
+        "--capture",
+        "Draw each demo page once into this directory as a snapshot, then exit (implies --demo)",
+    ),
+    choice(
+        "--capture-format",
+        &["png", "text"],
+        "Snapshot format for --capture (default: png)",
+    ),
+    switch(
+        "--dry-run",
+        "Draw the LCD as a box in the terminal instead of driving I2C hardware",
+    ),
+    switch(
+        "--echo-frames",
+        "Print each rendered frame and backlight/blink state to stdout",
+    ),
+    switch(
+        "--trace",
+        "Log every serial line with a trace ID through parse, scheduling and LCD writes",
+    ),
+    switch(
+        "--serialsh",
+        "Enable the optional serial shell that runs commands over the tunnel and streams remote stdout/stderr + exit codes",
+    ),
+    switch(
+        "--wizard",
+        "Run the guided first-run setup wizard even if a config already exists",
+    ),
+];
+
+const RENDER_FLAGS: &[FlagSpec] = &[
+    path("--payload", "Payload file to preview"),
+    value("--width", "cols", "Display width (default: 16)"),
+];
+
+const PROVISION_FLAGS: &[FlagSpec] = &[path("--from", "Provisioning document to apply")];
+
+const MIGRATE_FLAGS: &[FlagSpec] = &[switch(
+    "--dry-run",
+    "Print the upgrade steps without writing anything",
+)];
+
+const VALIDATE_FLAGS: &[FlagSpec] = &[switch(
+    "--lint",
+    "Also print warnings for settings that load but look wrong",
+)];
+
+const ARM_FLAGS: &[FlagSpec] = &[
+    value(
+        "--ms",
+        "ms",
+        "How long to stay armed (default: interlock.arm_ms)",
+    ),
+    switch(
+        "--sign",
+        "Print a signed arm message for the peer instead of arming this device",
+    ),
+];
+
+const SEND_FLAGS: &[FlagSpec] = &[
+    path(
+        "--device",
+        "Serial device path (default: config or /dev/ttyUSB0)",
+    ),
+    value("--baud", "number", "Baud rate (default: config or 9600)"),
+    value("--line1", "text", "Top row text"),
+    value("--line2", "text", "Second row text"),
+    value("--bar", "percent", "Progress bar value 0-100"),
+    switch("--compressed", "Wrap the frame in a compressed envelope"),
+    switch(
+        "--no-compressed",
+        "Send uncompressed even if config enables compression",
+    ),
+    choice(
+        "--codec",
+        &["none", "lz4", "zstd"],
+        "Codec to use when compression is enabled (default: lz4)",
+    ),
+];
+
+const TRANSFER_SEND_FLAGS: &[FlagSpec] = &[
+    path(
+        "--device",
+        "Serial device path (default: config or /dev/ttyUSB0)",
+    ),
+    value("--baud", "number", "Baud rate (default: config or 9600)"),
+    switch(
+        "--ymodem",
+        "Use YMODEM for peers without lifelinetty (no resume)",
+    ),
+];
+
+const TRANSFER_RECEIVE_FLAGS: &[FlagSpec] = &[
+    path(
+        "--device",
+        "Serial device path (default: config or /dev/ttyUSB0)",
+    ),
+    value("--baud", "number", "Baud rate (default: config or 9600)"),
+    switch("--ymodem", "Expect YMODEM or XMODEM instead of JSON chunks"),
+    path("--dir", "Where to put the received file (default: .)"),
+];
+
+const TELEMETRY_EXPORT_FLAGS: &[FlagSpec] = &[
+    value(
+        "--since",
+        "age",
+        "Only records newer than this, e.g. 90m, 24h or 7d (default: everything)",
+    ),
+    choice(
+        "--format",
+        &["jsonl", "csv"],
+        "Output format (default: jsonl)",
+    ),
+];
+
+const MONITOR_FLAGS: &[FlagSpec] = &[value(
+    "--interval",
+    "ms",
+    "How often to refresh the dashboard (default: 1000)",
+)];
+
+/// Refresh period of `lifelinetty monitor`.
+pub const DEFAULT_MONITOR_INTERVAL_MS: u64 = 1_000;
+const MIN_MONITOR_INTERVAL_MS: u64 = 100;
+const MAX_MONITOR_INTERVAL_MS: u64 = 60_000;
+
+const TUNNEL_COPY_FLAGS: &[FlagSpec] = &[
+    path(
+        "--device",
+        "Serial device path (default: config or /dev/ttyUSB0)",
+    ),
+    value("--baud", "number", "Baud rate (default: config or 9600)"),
+];
+
+/// Shells `completions` can emit scripts for.
+pub const COMPLETION_SHELLS: &[&str] = &["bash", "zsh", "fish", "elvish", "powershell"];
+
+/// Every subcommand with its flags, for help and shell completions.
+pub const SUBCOMMANDS: &[SubcommandSpec] = &[
+    SubcommandSpec {
+        name: "run",
+        about: "Run the daemon (default when no subcommand is given)",
+        flags: RUN_FLAGS,
+        args: &[],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "config",
+        about: "Inspect or upgrade the config file",
+        flags: &[],
+        args: &[],
+        subcommands: &[
+            SubcommandSpec {
+                name: "schema",
+                about: "Print the config key registry as JSON",
+                flags: &[],
+                args: &[],
+                subcommands: &[],
+            },
+            SubcommandSpec {
+                name: "migrate",
+                about: "Upgrade an older config file to the current format",
+                flags: MIGRATE_FLAGS,
+                args: &[],
+                subcommands: &[],
+            },
+            SubcommandSpec {
+                name: "validate",
+                about: "Check the config file without starting the daemon",
+                flags: VALIDATE_FLAGS,
+                args: &[],
+                subcommands: &[],
+            },
+        ],
+    },
+    SubcommandSpec {
+        name: "provision",
+        about: "Apply a provisioning document without prompting",
+        flags: PROVISION_FLAGS,
+        args: &[],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "render",
+        about: "Print the LCD cells a payload produces",
+        flags: RENDER_FLAGS,
+        args: &[],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "send",
+        about: "Write one checksummed payload frame to the serial port",
+        flags: SEND_FLAGS,
+        args: &[],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "transfer",
+        about: "Copy a file over the serial link",
+        flags: &[],
+        args: &[],
+        subcommands: &[
+            SubcommandSpec {
+                name: "send",
+                about: "Offer a file to the peer, resuming an interrupted copy",
+                flags: TRANSFER_SEND_FLAGS,
+                args: &[FlagSpec {
+                    name: "file",
+                    value: FlagValue::Path,
+                    help: "File to send",
+                }],
+                subcommands: &[],
+            },
+            SubcommandSpec {
+                name: "receive",
+                about: "Wait for one file from the peer",
+                flags: TRANSFER_RECEIVE_FLAGS,
+                args: &[],
+                subcommands: &[],
+            },
+        ],
+    },
+    SubcommandSpec {
+        name: "push",
+        about: "Copy a file to the daemon at the far end over the command tunnel",
+        flags: TUNNEL_COPY_FLAGS,
+        args: &[
+            FlagSpec {
+                name: "local",
+                value: FlagValue::Path,
+                help: "File to send",
+            },
+            FlagSpec {
+                name: "remote",
+                value: FlagValue::Value("path"),
+                help: "Destination on the far end, inside its cache dir",
+            },
+        ],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "pull",
+        about: "Copy a file from the daemon at the far end over the command tunnel",
+        flags: TUNNEL_COPY_FLAGS,
+        args: &[
+            FlagSpec {
+                name: "remote",
+                value: FlagValue::Value("path"),
+                help: "Tail-allowlisted or cached file on the far end",
+            },
+            FlagSpec {
+                name: "local",
+                value: FlagValue::Path,
+                help: "Where to write the copy",
+            },
+        ],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "remote-wizard",
+        about: "Set the far end's display geometry, scrolling and role over the command tunnel",
+        flags: TUNNEL_COPY_FLAGS,
+        args: &[],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "arm",
+        about: "Allow tunnel commands while the interlock is required",
+        flags: ARM_FLAGS,
+        args: &[],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "disarm",
+        about: "End an arming early",
+        flags: &[],
+        args: &[],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "monitor",
+        about: "Show a live dashboard of the running daemon over the control socket",
+        flags: MONITOR_FLAGS,
+        args: &[],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "support-bundle",
+        about: "Pack logs, the redacted config and system details for a bug report",
+        flags: &[],
+        args: &[],
+        subcommands: &[],
+    },
+    SubcommandSpec {
+        name: "telemetry",
+        about: "Work with the telemetry logs in the cache dir",
+        flags: &[],
+        args: &[],
+        subcommands: &[SubcommandSpec {
+            name: "export",
+            about: "Print backoff events, loop stats and polling history as one stream",
+            flags: TELEMETRY_EXPORT_FLAGS,
+            args: &[],
+            subcommands: &[],
+        }],
+    },
+    SubcommandSpec {
+        name: "completions",
+        about: "Print a shell completion script",
+        flags: &[],
+        args: &[choice("shell", COMPLETION_SHELLS, "Target shell")],
+        subcommands: &[],
+    },
+];
 
 /// Entry mode for the `run` command.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -13,6 +548,8 @@ pub enum RunMode {
     Daemon,
     /// P7: CLI integration groundwork for the serial shell preview gate.
     SerialShell,
+    /// Listen-only probe that suggests the peer's baud rate, then exits.
+    AutobaudProbe,
 }
 
 /// Options for the `run` command; values are `None` when not provided on CLI.
@@ -20,33 +557,161 @@ pub enum RunMode {
 pub struct RunOptions {
     pub mode: RunMode,
     pub device: Option<String>,
+    /// `--input fifo:<path>`: read payloads from this FIFO instead of the serial device.
+    pub fifo: Option<String>,
+    /// `--listen tcp:<addr>`: accept frames from a TCP client on this address instead.
+    pub listen: Option<String>,
     pub baud: Option<u32>,
     pub flow_control: Option<FlowControlMode>,
     pub parity: Option<ParityMode>,
     pub stop_bits: Option<StopBitsMode>,
     pub dtr_on_open: Option<DtrBehavior>,
     pub serial_timeout_ms: Option<u64>,
+    pub low_latency: Option<bool>,
+    pub autobaud: Option<bool>,
     pub cols: Option<u8>,
     pub rows: Option<u8>,
     pub payload_file: Option<String>,
+    /// Re-render this payload file whenever it changes.
+    pub payload_watch: Option<String>,
     pub backoff_initial_ms: Option<u64>,
     pub backoff_max_ms: Option<u64>,
     pub pcf8574_addr: Option<Pcf8574Addr>,
+    pub display_driver: Option<DisplayDriver>,
     pub log_level: Option<String>,
     pub log_file: Option<String>,
     pub config_file: Option<String>,
     pub compression_enabled: Option<bool>,
     pub compression_codec: Option<CompressionCodec>,
     pub demo: bool,
+    /// `--capture`: write the demo pages here instead of cycling them on the LCD.
+    pub capture: Option<String>,
+    pub capture_format: Option<CaptureFormat>,
+    pub dry_run: bool,
+    pub echo_frames: bool,
+    pub trace: bool,
     pub polling_enabled: Option<bool>,
     pub poll_interval_ms: Option<u64>,
     pub wizard: bool,
 }
 
+/// Options for the `send` command; serial and compression settings fall back to the config.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SendOptions {
+    pub device: Option<String>,
+    pub baud: Option<u32>,
+    pub line1: String,
+    pub line2: String,
+    pub bar: Option<u8>,
+    pub compression_enabled: Option<bool>,
+    pub compression_codec: Option<CompressionCodec>,
+}
+
+/// Which way `transfer` copies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferDirection {
+    Send { file: String },
+    Receive,
+}
+
+/// Wire protocol for `transfer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransferProtocol {
+    /// JSON `file_chunk` lines with resume; needs lifelinetty on both ends.
+    #[default]
+    Chunks,
+    /// YMODEM-1K, for peers that only speak classic serial protocols.
+    Ymodem,
+}
+
+/// Options for the `transfer` command; serial settings fall back to the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferOptions {
+    pub direction: TransferDirection,
+    pub device: Option<String>,
+    pub baud: Option<u32>,
+    pub protocol: TransferProtocol,
+    /// Where `receive` puts the finished file; the working directory when `None`.
+    pub dir: Option<String>,
+}
+
+/// Output format of `telemetry export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TelemetryFormat {
+    /// One JSON object per line.
+    #[default]
+    Jsonl,
+    /// A header row with every field seen, then one row per record.
+    Csv,
+}
+
+/// Options for `push` and `pull`; serial settings fall back to the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TunnelCopyOptions {
+    pub local: String,
+    pub remote: String,
+    pub device: Option<String>,
+    pub baud: Option<u32>,
+}
+
 /// Parsed command-line intent.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
     Run(Box<RunOptions>),
+    /// `config schema`: print the config key registry as JSON.
+    ConfigSchema,
+    /// `config migrate [--dry-run]`: upgrade the config file to the current `config_version`.
+    ConfigMigrate {
+        dry_run: bool,
+    },
+    /// `config validate [--lint]`: check the config file, optionally listing lint warnings.
+    ConfigValidate {
+        lint: bool,
+    },
+    /// `provision --from <file>`: apply a provisioning document without prompting.
+    Provision {
+        from: String,
+    },
+    /// `render --payload <file> [--width <cols>]`: print the LCD cells a payload produces.
+    Render {
+        payload: String,
+        width: u8,
+    },
+    /// `send --line1 <text> [--line2 <text>] [--bar <percent>]`: write one payload frame.
+    Send(Box<SendOptions>),
+    /// `transfer send <file>` / `transfer receive`: copy a file over the serial link.
+    Transfer(Box<TransferOptions>),
+    /// `push <local> <remote>`: copy a file to the far end's daemon over the command tunnel.
+    Push(Box<TunnelCopyOptions>),
+    /// `pull <remote> <local>`: copy a file from the far end's daemon over the command tunnel.
+    Pull(Box<TunnelCopyOptions>),
+    /// `remote-wizard`: set the far end's geometry, scrolling and role, verifying each write.
+    RemoteWizard {
+        device: Option<String>,
+        baud: Option<u32>,
+    },
+    /// `arm [--ms <n>] [--sign]`: arm the command interlock, or print a signed arm message.
+    Arm {
+        ms: Option<u64>,
+        sign: bool,
+    },
+    /// `disarm`: end an arming early.
+    Disarm,
+    /// `monitor [--interval <ms>]`: live dashboard of the daemon, read from the control socket.
+    Monitor {
+        interval_ms: u64,
+    },
+    /// `support-bundle`: pack logs, the redacted config and system details for a bug report.
+    SupportBundle,
+    /// `telemetry export [--since <age>] [--format jsonl|csv]`: merge the telemetry logs.
+    TelemetryExport {
+        since_ms: Option<u64>,
+        format: TelemetryFormat,
+    },
+    /// `completions <shell>`: print a completion script generated from the CLI table.
+    Completions {
+        shell: Shell,
+    },
     ShowHelp,
     ShowVersion,
 }
@@ -60,6 +725,79 @@ impl Command {
         let mut iter = args.iter();
         match iter.next().map(|s| s.as_str()) {
             Some("run") => Ok(Command::Run(Box::new(parse_run_options(&mut iter)?))),
+            Some("config") => match iter.next().map(|s| s.as_str()) {
+                Some("schema") => Ok(Command::ConfigSchema),
+                Some("migrate") => match (iter.next().map(|s| s.as_str()), iter.next()) {
+                    (None, _) => Ok(Command::ConfigMigrate { dry_run: false }),
+                    (Some("--dry-run"), None) => Ok(Command::ConfigMigrate { dry_run: true }),
+                    _ => Err(Error::InvalidArgs(
+                        "usage: lifelinetty config migrate [--dry-run]".to_string(),
+                    )),
+                },
+                Some("validate") => match (iter.next().map(|s| s.as_str()), iter.next()) {
+                    (None, _) => Ok(Command::ConfigValidate { lint: false }),
+                    (Some("--lint"), None) => Ok(Command::ConfigValidate { lint: true }),
+                    _ => Err(Error::InvalidArgs(
+                        "usage: lifelinetty config validate [--lint]".to_string(),
+                    )),
+                },
+                Some(other) => Err(Error::InvalidArgs(format!(
+                    "unknown config subcommand '{other}', expected 'schema', 'migrate' or 'validate'"
+                ))),
+                None => Err(Error::InvalidArgs(
+                    "config requires a subcommand: schema, migrate, validate".to_string(),
+                )),
+            },
+            Some("provision") => match (iter.next().map(|s| s.as_str()), iter.next()) {
+                (Some("--from"), Some(path)) if iter.next().is_none() => {
+                    Ok(Command::Provision { from: path.clone() })
+                }
+                _ => Err(Error::InvalidArgs(
+                    "usage: lifelinetty provision --from <file>".to_string(),
+                )),
+            },
+            Some("render") => parse_render(&mut iter),
+            Some("send") => parse_send(&mut iter),
+            Some("transfer") => parse_transfer(&mut iter),
+            Some("push") => parse_tunnel_copy(&mut iter, true),
+            Some("pull") => parse_tunnel_copy(&mut iter, false),
+            Some("remote-wizard") => parse_remote_wizard(&mut iter),
+            Some("arm") => parse_arm(&mut iter),
+            Some("disarm") => match iter.next() {
+                None => Ok(Command::Disarm),
+                Some(_) => Err(Error::InvalidArgs("usage: lifelinetty disarm".into())),
+            },
+            Some("monitor") => parse_monitor(&mut iter),
+            Some("support-bundle") => match iter.next() {
+                None => Ok(Command::SupportBundle),
+                Some(_) => Err(Error::InvalidArgs(
+                    "usage: lifelinetty support-bundle".into(),
+                )),
+            },
+            Some("telemetry") => match iter.next().map(|s| s.as_str()) {
+                Some("export") => parse_telemetry_export(&mut iter),
+                Some(other) => Err(Error::InvalidArgs(format!(
+                    "unknown telemetry subcommand '{other}', expected 'export'"
+                ))),
+                None => Err(Error::InvalidArgs(
+                    "telemetry requires a subcommand: export".to_string(),
+                )),
+            },
+            Some("completions") => match (iter.next(), iter.next()) {
+                (Some(shell), None) => shell
+                    .parse()
+                    .map(|shell| Command::Completions { shell })
+                    .map_err(|_| {
+                        Error::InvalidArgs(format!(
+                            "unknown shell '{shell}', expected one of: {}",
+                            COMPLETION_SHELLS.join(", ")
+                        ))
+                    }),
+                _ => Err(Error::InvalidArgs(format!(
+                    "usage: lifelinetty completions <{}>",
+                    COMPLETION_SHELLS.join("|")
+                ))),
+            },
             Some("--help") | Some("-h") => Ok(Command::ShowHelp),
             Some("--version") | Some("-V") => Ok(Command::ShowVersion),
             Some(flag) if flag.starts_with('-') => {
@@ -78,22 +816,13 @@ impl Command {
         }
     }
     pub fn help() -> String {
-        let mut help = String::from(
-            "lifelinetty - Serial-to-LCD daemon\n\nUSAGE:\n  lifelinetty run [--device <path>] [--baud <number>] [--cols <number>] [--rows <number>] [--payload-file <path>]\n  lifelinetty --help\n  lifelinetty --version\n\nOPTIONS:\n  --device <path>   Serial device path (default: /dev/ttyUSB0)\n  --baud <number>   Baud rate (default: 9600)\n  --flow-control <none|software|hardware>  Flow control override (default: none)\n  --parity <none|odd|even>       Parity override (default: none)\n  --stop-bits <1|2>              Stop bits override (default: 1)\n  --dtr-on-open <auto|on|off>    Control DTR state when opening the port (default: auto)\n  --serial-timeout-ms <number>   Read timeout in milliseconds (default: 500)\n  --cols <number>   LCD columns (default: 16)\n  --rows <number>   LCD rows (default: 2)\n  --payload-file <path>  Load a local JSON payload and render it once (testing helper)\n  --backoff-initial-ms <number>  Initial reconnect backoff (default: 500)\n  --backoff-max-ms <number>      Maximum reconnect backoff (default: 10000)\n  --pcf8574-addr <auto|0xNN>     PCF8574 I2C address or 'auto' to probe (default: auto)\n  --log-level <error|warn|info|debug|trace>  Log verbosity (default: info)\n  --log-file <path>              Append logs inside /run/serial_lcd_cache (also honors LIFELINETTY_LOG_PATH)\n",
-        );
-
-        help.push_str(
-            "  --config-file <path>           Load config from the provided TOML instead of ~/.serial_lcd/config.toml (env overrides still apply)\n  --polling                      Enable hardware polling (default: config)\n  --no-polling                   Disable hardware polling even if config enables it\n  --poll-interval-ms <number>    Polling interval in milliseconds (default: 5000)\n  --compressed                   Enable schema compression (applies to schema_v1 payloads)\n  --no-compressed                Disable compression even if config enables it\n  --codec <lz4|zstd>             Codec to use when compression is enabled (default: lz4)\n  --demo                         Run built-in demo pages on the LCD (no serial input)\n",
-        );
-
-        help.push_str(
-            "  --serialsh                   Enable the optional serial shell that runs commands over the tunnel and streams remote stdout/stderr + exit codes\n",
-        );
-
-        help.push_str(
-            "  --wizard                    Run the guided first-run setup wizard even if a config already exists\n",
+        let mut help = format!(
+            "lifelinetty - Serial-to-LCD daemon\n\nUSAGE:\n  lifelinetty run [--device <path>] [--baud <number>] [--cols <number>] [--rows <number>] [--payload-file <path> | --payload-watch <path>]\n  lifelinetty config schema\n  lifelinetty config migrate [--dry-run]\n  lifelinetty provision --from <file>\n  lifelinetty render --payload <file> [--width <cols>]\n  lifelinetty send [--device <path>] --line1 <text> [--line2 <text>] [--bar <percent>] [--compressed]\n  lifelinetty transfer send <file> [--device <path>] [--ymodem]\n  lifelinetty transfer receive [--device <path>] [--dir <path>] [--ymodem]\n  lifelinetty push <local> <remote> [--device <path>]\n  lifelinetty pull <remote> <local> [--device <path>]\n  lifelinetty arm [--ms <n>] [--sign]\n  lifelinetty disarm\n  lifelinetty monitor [--interval <ms>]\n  lifelinetty support-bundle\n  lifelinetty telemetry export [--since <age>] [--format jsonl|csv]\n  lifelinetty completions <{}>\n  lifelinetty --help\n  lifelinetty --version\n\nAny command also takes --instance <name>.\n\nOPTIONS:\n",
+            COMPLETION_SHELLS.join("|")
         );
-
+        for flag in GLOBAL_FLAGS.iter().chain(RUN_FLAGS) {
+            help.push_str(&format!("  {:<31} {}\n", flag.usage(), flag.help));
+        }
         help.push_str("  -h, --help        Show this help\n  -V, --version     Show version\n");
         help
     }
@@ -103,71 +832,421 @@ impl Command {
     }
 }
 
+fn parse_render(iter: &mut std::slice::Iter<String>) -> Result<Command> {
+    let mut payload = None;
+    let mut width = DEFAULT_COLS;
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_inline(arg);
+        let Some(spec) = find_flag(RENDER_FLAGS, flag) else {
+            return Err(Error::InvalidArgs(format!(
+                "unknown render flag '{flag}', expected --payload or --width"
+            )));
+        };
+        let raw = flag_value(spec, inline, iter)?;
+        match spec.name {
+            "--payload" => payload = Some(raw),
+            "--width" => {
+                width = raw
+                    .parse()
+                    .ok()
+                    .filter(|w| (MIN_COLS..=MAX_COLS).contains(w))
+                    .ok_or_else(|| {
+                        Error::InvalidArgs(format!(
+                            "width must be between {MIN_COLS} and {MAX_COLS}"
+                        ))
+                    })?;
+            }
+            other => {
+                return Err(Error::InvalidArgs(format!(
+                    "render flag '{other}' is not implemented"
+                )))
+            }
+        }
+    }
+    let payload = payload.ok_or_else(|| {
+        Error::InvalidArgs("usage: lifelinetty render --payload <file> [--width <cols>]".into())
+    })?;
+    Ok(Command::Render { payload, width })
+}
+
+fn parse_send(iter: &mut std::slice::Iter<String>) -> Result<Command> {
+    let mut opts = SendOptions::default();
+    let mut line1 = None;
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_inline(arg);
+        let Some(spec) = find_flag(SEND_FLAGS, flag) else {
+            return Err(Error::InvalidArgs(format!(
+                "unknown send flag '{flag}', try --help"
+            )));
+        };
+        let raw = flag_value(spec, inline, iter)?;
+        match spec.name {
+            "--device" => opts.device = Some(raw),
+            "--baud" => {
+                opts.baud = Some(raw.parse().map_err(|_| {
+                    Error::InvalidArgs("baud must be a positive integer".to_string())
+                })?);
+            }
+            "--line1" => line1 = Some(raw),
+            "--line2" => opts.line2 = raw,
+            "--bar" => {
+                opts.bar = Some(raw.parse().ok().filter(|bar| *bar <= 100).ok_or_else(|| {
+                    Error::InvalidArgs("--bar must be between 0 and 100".to_string())
+                })?);
+            }
+            "--compressed" => opts.compression_enabled = Some(true),
+            "--no-compressed" => opts.compression_enabled = Some(false),
+            "--codec" => {
+                opts.compression_codec =
+                    Some(CompressionCodec::from_name(&raw).ok_or_else(|| {
+                        Error::InvalidArgs("codec must be one of: none, lz4, zstd".to_string())
+                    })?);
+            }
+            other => {
+                return Err(Error::InvalidArgs(format!(
+                    "send flag '{other}' is not implemented"
+                )))
+            }
+        }
+    }
+    opts.line1 = line1.ok_or_else(|| {
+        Error::InvalidArgs(
+            "usage: lifelinetty send --line1 <text> [--line2 <text>] [--bar <percent>]".into(),
+        )
+    })?;
+    Ok(Command::Send(Box::new(opts)))
+}
+
+fn parse_transfer(iter: &mut std::slice::Iter<String>) -> Result<Command> {
+    const USAGE: &str =
+        "usage: lifelinetty transfer send <file> | lifelinetty transfer receive [--dir <path>]";
+    let (direction, table) = match iter.next().map(|s| s.as_str()) {
+        Some("send") => (None, TRANSFER_SEND_FLAGS),
+        Some("receive") => (Some(TransferDirection::Receive), TRANSFER_RECEIVE_FLAGS),
+        _ => return Err(Error::InvalidArgs(USAGE.into())),
+    };
+    let mut opts = TransferOptions {
+        direction: TransferDirection::Receive,
+        device: None,
+        baud: None,
+        protocol: TransferProtocol::Chunks,
+        dir: None,
+    };
+    let mut file = None;
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_inline(arg);
+        if !flag.starts_with('-') && direction.is_none() && file.is_none() {
+            file = Some(arg.clone());
+            continue;
+        }
+        let Some(spec) = find_flag(table, flag) else {
+            return Err(Error::InvalidArgs(format!(
+                "unknown transfer flag '{flag}', try --help"
+            )));
+        };
+        let raw = flag_value(spec, inline, iter)?;
+        match spec.name {
+            "--device" => opts.device = Some(raw),
+            "--baud" => {
+                opts.baud = Some(raw.parse().map_err(|_| {
+                    Error::InvalidArgs("baud must be a positive integer".to_string())
+                })?);
+            }
+            "--ymodem" => opts.protocol = TransferProtocol::Ymodem,
+            "--dir" => opts.dir = Some(raw),
+            other => {
+                return Err(Error::InvalidArgs(format!(
+                    "transfer flag '{other}' is not implemented"
+                )))
+            }
+        }
+    }
+    opts.direction = match (direction, file) {
+        (Some(direction), _) => direction,
+        (None, Some(file)) => TransferDirection::Send { file },
+        (None, None) => return Err(Error::InvalidArgs(USAGE.into())),
+    };
+    Ok(Command::Transfer(Box::new(opts)))
+}
+
+fn parse_remote_wizard(iter: &mut std::slice::Iter<String>) -> Result<Command> {
+    let mut device = None;
+    let mut baud = None;
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_inline(arg);
+        let Some(spec) = find_flag(TUNNEL_COPY_FLAGS, flag) else {
+            return Err(Error::InvalidArgs(
+                "usage: lifelinetty remote-wizard [--device <path>] [--baud <number>]".into(),
+            ));
+        };
+        let raw = flag_value(spec, inline, iter)?;
+        match spec.name {
+            "--device" => device = Some(raw),
+            "--baud" => {
+                baud = Some(raw.parse().map_err(|_| {
+                    Error::InvalidArgs("baud must be a positive integer".to_string())
+                })?);
+            }
+            other => {
+                return Err(Error::InvalidArgs(format!(
+                    "flag '{other}' is not implemented"
+                )))
+            }
+        }
+    }
+    Ok(Command::RemoteWizard { device, baud })
+}
+
+fn parse_tunnel_copy(iter: &mut std::slice::Iter<String>, push: bool) -> Result<Command> {
+    let usage = if push {
+        "usage: lifelinetty push <local> <remote>"
+    } else {
+        "usage: lifelinetty pull <remote> <local>"
+    };
+    let mut paths = Vec::new();
+    let mut device = None;
+    let mut baud = None;
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_inline(arg);
+        if !flag.starts_with('-') {
+            paths.push(arg.clone());
+            continue;
+        }
+        let Some(spec) = find_flag(TUNNEL_COPY_FLAGS, flag) else {
+            return Err(Error::InvalidArgs(format!(
+                "unknown flag '{flag}', try --help"
+            )));
+        };
+        let raw = flag_value(spec, inline, iter)?;
+        match spec.name {
+            "--device" => device = Some(raw),
+            "--baud" => {
+                baud = Some(raw.parse().map_err(|_| {
+                    Error::InvalidArgs("baud must be a positive integer".to_string())
+                })?);
+            }
+            other => {
+                return Err(Error::InvalidArgs(format!(
+                    "flag '{other}' is not implemented"
+                )))
+            }
+        }
+    }
+    let [first, second] =
+        <[String; 2]>::try_from(paths).map_err(|_| Error::InvalidArgs(usage.into()))?;
+    let (local, remote) = if push {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    let opts = Box::new(TunnelCopyOptions {
+        local,
+        remote,
+        device,
+        baud,
+    });
+    Ok(if push {
+        Command::Push(opts)
+    } else {
+        Command::Pull(opts)
+    })
+}
+
+fn parse_arm(iter: &mut std::slice::Iter<String>) -> Result<Command> {
+    let mut ms = None;
+    let mut sign = false;
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_inline(arg);
+        let Some(spec) = find_flag(ARM_FLAGS, flag) else {
+            return Err(Error::InvalidArgs(format!(
+                "unknown arm flag '{flag}', expected --ms or --sign"
+            )));
+        };
+        let raw = flag_value(spec, inline, iter)?;
+        match spec.name {
+            "--sign" => sign = true,
+            "--ms" => {
+                ms = Some(
+                    raw.parse()
+                        .ok()
+                        .filter(|ms| (MIN_INTERLOCK_ARM_MS..=MAX_INTERLOCK_ARM_MS).contains(ms))
+                        .ok_or_else(|| {
+                            Error::InvalidArgs(format!(
+                                "--ms must be between {MIN_INTERLOCK_ARM_MS} and {MAX_INTERLOCK_ARM_MS}"
+                            ))
+                        })?,
+                );
+            }
+            other => {
+                return Err(Error::InvalidArgs(format!(
+                    "arm flag '{other}' is not implemented"
+                )))
+            }
+        }
+    }
+    Ok(Command::Arm { ms, sign })
+}
+
+fn parse_monitor(iter: &mut std::slice::Iter<String>) -> Result<Command> {
+    let mut interval_ms = DEFAULT_MONITOR_INTERVAL_MS;
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_inline(arg);
+        let Some(spec) = find_flag(MONITOR_FLAGS, flag) else {
+            return Err(Error::InvalidArgs(format!(
+                "unknown monitor flag '{flag}', expected --interval"
+            )));
+        };
+        let raw = flag_value(spec, inline, iter)?;
+        interval_ms = raw
+            .parse()
+            .ok()
+            .filter(|ms| (MIN_MONITOR_INTERVAL_MS..=MAX_MONITOR_INTERVAL_MS).contains(ms))
+            .ok_or_else(|| {
+                Error::InvalidArgs(format!(
+                    "--interval must be between {MIN_MONITOR_INTERVAL_MS} and {MAX_MONITOR_INTERVAL_MS}"
+                ))
+            })?;
+    }
+    Ok(Command::Monitor { interval_ms })
+}
+
+fn parse_telemetry_export(iter: &mut std::slice::Iter<String>) -> Result<Command> {
+    let mut since_ms = None;
+    let mut format = TelemetryFormat::default();
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_inline(arg);
+        let Some(spec) = find_flag(TELEMETRY_EXPORT_FLAGS, flag) else {
+            return Err(Error::InvalidArgs(format!(
+                "unknown telemetry export flag '{flag}', expected --since or --format"
+            )));
+        };
+        let raw = flag_value(spec, inline, iter)?;
+        match spec.name {
+            "--since" => {
+                since_ms = Some(parse_age_ms(&raw).ok_or_else(|| {
+                    Error::InvalidArgs(format!(
+                        "--since must be a number followed by s, m, h or d (got '{raw}')"
+                    ))
+                })?);
+            }
+            "--format" => {
+                format = match raw.as_str() {
+                    "jsonl" => TelemetryFormat::Jsonl,
+                    "csv" => TelemetryFormat::Csv,
+                    other => {
+                        return Err(Error::InvalidArgs(format!(
+                            "unknown format '{other}', expected jsonl or csv"
+                        )))
+                    }
+                };
+            }
+            other => {
+                return Err(Error::InvalidArgs(format!(
+                    "telemetry export flag '{other}' is not implemented"
+                )))
+            }
+        }
+    }
+    Ok(Command::TelemetryExport { since_ms, format })
+}
+
+/// `90s`, `15m`, `24h` or `7d` in milliseconds.
+fn parse_age_ms(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = raw.split_at(split);
+    let unit_ms: u64 = match unit {
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => return None,
+    };
+    count.parse::<u64>().ok()?.checked_mul(unit_ms)
+}
+
 fn parse_run_options(iter: &mut std::slice::Iter<String>) -> Result<RunOptions> {
     let mut opts = RunOptions::default();
 
-    while let Some(flag) = iter.next() {
-        match flag.as_str() {
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_inline(arg);
+        let spec = find_flag(RUN_FLAGS, flag)
+            .ok_or_else(|| Error::InvalidArgs(format!("unknown flag '{flag}', try --help")))?;
+        let raw = flag_value(spec, inline, iter)?;
+        match spec.name {
             "--device" => {
-                opts.device = Some(take_value(flag, iter)?);
+                opts.device = Some(raw);
             }
             "--baud" => {
-                let raw = take_value(flag, iter)?;
                 opts.baud = Some(raw.parse().map_err(|_| {
                     Error::InvalidArgs("baud must be a positive integer".to_string())
                 })?);
             }
             "--flow-control" => {
-                let raw = take_value(flag, iter)?;
                 opts.flow_control = Some(raw.parse().map_err(|e: String| Error::InvalidArgs(e))?);
             }
             "--parity" => {
-                let raw = take_value(flag, iter)?;
                 opts.parity = Some(raw.parse().map_err(|e: String| Error::InvalidArgs(e))?);
             }
             "--stop-bits" => {
-                let raw = take_value(flag, iter)?;
                 opts.stop_bits = Some(raw.parse().map_err(|e: String| Error::InvalidArgs(e))?);
             }
             "--dtr-on-open" => {
-                let raw = take_value(flag, iter)?;
                 opts.dtr_on_open = Some(raw.parse().map_err(|e: String| Error::InvalidArgs(e))?);
             }
             "--serial-timeout-ms" => {
-                let raw = take_value(flag, iter)?;
                 opts.serial_timeout_ms = Some(raw.parse().map_err(|_| {
                     Error::InvalidArgs("serial-timeout-ms must be a positive integer".to_string())
                 })?);
             }
+            "--low-latency" => {
+                opts.low_latency = Some(true);
+            }
+            "--no-low-latency" => {
+                opts.low_latency = Some(false);
+            }
+            "--autobaud" => {
+                opts.autobaud = Some(true);
+            }
+            "--no-autobaud" => {
+                opts.autobaud = Some(false);
+            }
+            "--autobaud-probe" => {
+                opts.mode = RunMode::AutobaudProbe;
+            }
             "--cols" => {
-                let raw = take_value(flag, iter)?;
                 opts.cols = Some(raw.parse().map_err(|_| {
                     Error::InvalidArgs("cols must be a positive integer".to_string())
                 })?);
             }
             "--rows" => {
-                let raw = take_value(flag, iter)?;
                 opts.rows = Some(raw.parse().map_err(|_| {
                     Error::InvalidArgs("rows must be a positive integer".to_string())
                 })?);
             }
             "--payload-file" => {
-                opts.payload_file = Some(take_value(flag, iter)?);
+                opts.payload_file = Some(raw);
+            }
+            "--payload-watch" => {
+                opts.payload_watch = Some(raw);
+            }
+            "--input" => {
+                opts.fifo = parse_input(&raw)?;
+            }
+            "--listen" => {
+                opts.listen = Some(parse_listen(&raw)?);
             }
             "--backoff-initial-ms" => {
-                let raw = take_value(flag, iter)?;
                 opts.backoff_initial_ms = Some(raw.parse().map_err(|_| {
                     Error::InvalidArgs("backoff-initial-ms must be a positive integer".to_string())
                 })?);
             }
             "--backoff-max-ms" => {
-                let raw = take_value(flag, iter)?;
                 opts.backoff_max_ms = Some(raw.parse().map_err(|_| {
                     Error::InvalidArgs("backoff-max-ms must be a positive integer".to_string())
                 })?);
             }
             "--pcf8574-addr" => {
-                let raw = take_value(flag, iter)?;
                 opts.pcf8574_addr = Some(raw.parse().map_err(|_| {
                     Error::InvalidArgs(
                         "pcf8574-addr must be 'auto' or a hex/decimal address (e.g., 0x27)"
@@ -175,14 +1254,17 @@ fn parse_run_options(iter: &mut std::slice::Iter<String>) -> Result<RunOptions>
                     )
                 })?);
             }
+            "--display-driver" => {
+                opts.display_driver = Some(raw.parse().map_err(|e: String| Error::InvalidArgs(e))?);
+            }
             "--log-level" => {
-                opts.log_level = Some(take_value(flag, iter)?);
+                opts.log_level = Some(raw);
             }
             "--log-file" => {
-                opts.log_file = Some(take_value(flag, iter)?);
+                opts.log_file = Some(raw);
             }
             "--config-file" => {
-                opts.config_file = Some(take_value(flag, iter)?);
+                opts.config_file = Some(raw);
             }
             "--polling" => {
                 opts.polling_enabled = Some(true);
@@ -191,7 +1273,6 @@ fn parse_run_options(iter: &mut std::slice::Iter<String>) -> Result<RunOptions>
                 opts.polling_enabled = Some(false);
             }
             "--poll-interval-ms" => {
-                let raw = take_value(flag, iter)?;
                 opts.poll_interval_ms = Some(raw.parse().map_err(|_| {
                     Error::InvalidArgs("poll-interval-ms must be a positive integer".to_string())
                 })?);
@@ -203,7 +1284,6 @@ fn parse_run_options(iter: &mut std::slice::Iter<String>) -> Result<RunOptions>
                 opts.compression_enabled = Some(false);
             }
             "--codec" => {
-                let raw = take_value(flag, iter)?;
                 opts.compression_codec =
                     Some(CompressionCodec::from_name(&raw).ok_or_else(|| {
                         Error::InvalidArgs("codec must be one of: none, lz4, zstd".to_string())
@@ -212,6 +1292,22 @@ fn parse_run_options(iter: &mut std::slice::Iter<String>) -> Result<RunOptions>
             "--demo" => {
                 opts.demo = true;
             }
+            "--capture" => {
+                opts.demo = true;
+                opts.capture = Some(raw);
+            }
+            "--capture-format" => {
+                opts.capture_format = Some(raw.parse().map_err(|e: String| Error::InvalidArgs(e))?);
+            }
+            "--dry-run" => {
+                opts.dry_run = true;
+            }
+            "--echo-frames" => {
+                opts.echo_frames = true;
+            }
+            "--trace" => {
+                opts.trace = true;
+            }
             "--serialsh" => {
                 // Milestone G: run the CLI serial shell through the command tunnel.
                 opts.mode = RunMode::SerialShell;
@@ -220,27 +1316,152 @@ fn parse_run_options(iter: &mut std::slice::Iter<String>) -> Result<RunOptions>
                 opts.wizard = true;
             }
             other => {
+                // `every_table_flag_is_parsed` keeps RUN_FLAGS and these arms in step.
                 return Err(Error::InvalidArgs(format!(
-                    "unknown flag '{other}', try --help"
+                    "flag '{other}' is not implemented"
                 )));
             }
         }
     }
 
-    validate_serialsh_options(&opts)?;
+    validate_run_options(&opts)?;
     Ok(opts)
 }
 
+fn find_flag<'a>(table: &'a [FlagSpec], flag: &str) -> Option<&'a FlagSpec> {
+    table.iter().find(|spec| spec.name == flag)
+}
+
+/// Split `--flag=value`, the form zsh and fish completions insert, into its parts.
+/// Remove `--instance <name>` (or `--instance=<name>`) from `args` and return the name.
+pub fn take_instance(args: &mut Vec<String>) -> Result<Option<String>> {
+    let Some(idx) = args
+        .iter()
+        .position(|arg| split_inline(arg).0 == "--instance")
+    else {
+        return Ok(None);
+    };
+    let arg = args.remove(idx);
+    let name = match split_inline(&arg).1 {
+        Some(inline) => inline.to_string(),
+        None if idx < args.len() => args.remove(idx),
+        None => {
+            return Err(Error::InvalidArgs(
+                "--instance requires a value".to_string(),
+            ))
+        }
+    };
+    crate::validate_instance_name(&name)?;
+    if args.iter().any(|arg| split_inline(arg).0 == "--instance") {
+        return Err(Error::InvalidArgs(
+            "--instance may only be given once".to_string(),
+        ));
+    }
+    Ok(Some(name))
+}
+
+fn split_inline(arg: &str) -> (&str, Option<&str>) {
+    match arg.split_once('=') {
+        Some((flag, value)) if flag.starts_with("--") => (flag, Some(value)),
+        _ => (arg, None),
+    }
+}
+
+/// The flag's value from `--flag=value` or the next argument; empty for switches.
+fn flag_value(
+    spec: &FlagSpec,
+    inline: Option<&str>,
+    iter: &mut std::slice::Iter<String>,
+) -> Result<String> {
+    match (spec.value, inline) {
+        (FlagValue::Switch, None) => Ok(String::new()),
+        (FlagValue::Switch, Some(_)) => Err(Error::InvalidArgs(format!(
+            "{} does not take a value",
+            spec.name
+        ))),
+        (_, Some(value)) => Ok(value.to_string()),
+        (_, None) => take_value(spec.name, iter),
+    }
+}
+
 fn take_value(flag: &str, iter: &mut std::slice::Iter<String>) -> Result<String> {
     iter.next()
         .cloned()
         .ok_or_else(|| Error::InvalidArgs(format!("expected a value after {flag}")))
 }
 
-fn validate_serialsh_options(opts: &RunOptions) -> Result<()> {
-    if matches!(opts.mode, RunMode::SerialShell) && (opts.payload_file.is_some() || opts.demo) {
+/// `serial` keeps the device; `fifo:<path>` names a FIFO inside the cache directory (relative
+/// paths land under it).
+fn parse_input(raw: &str) -> Result<Option<String>> {
+    if raw == "serial" {
+        return Ok(None);
+    }
+    let Some(path) = raw.strip_prefix(crate::serial::fifo::FIFO_PREFIX) else {
+        return Err(Error::InvalidArgs(
+            "input must be 'serial' or 'fifo:<path>'".to_string(),
+        ));
+    };
+    if path.is_empty() {
+        return Err(Error::InvalidArgs("fifo path cannot be empty".to_string()));
+    }
+    let path = cache_dir().join(path);
+    let escapes = path
+        .components()
+        .any(|component| matches!(component, std::path::Component::ParentDir));
+    if escapes || !path.starts_with(CACHE_DIR) || path == std::path::Path::new(CACHE_DIR) {
+        return Err(Error::InvalidArgs(format!(
+            "fifo path must live inside {CACHE_DIR}"
+        )));
+    }
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+/// `tcp:<ip>:<port>`; the address is returned without the prefix.
+fn parse_listen(raw: &str) -> Result<String> {
+    raw.strip_prefix(crate::serial::tcp::TCP_PREFIX)
+        .filter(|addr| addr.parse::<std::net::SocketAddr>().is_ok())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            Error::InvalidArgs(format!(
+                "listen must be 'tcp:<ip>:<port>' (e.g. tcp:127.0.0.1:7777), got '{raw}'"
+            ))
+        })
+}
+
+fn validate_run_options(opts: &RunOptions) -> Result<()> {
+    let local_payload = opts.payload_file.is_some() || opts.payload_watch.is_some();
+    if matches!(opts.mode, RunMode::SerialShell) && (local_payload || opts.demo) {
         return Err(Error::InvalidArgs(
-            "--serialsh cannot be combined with --demo or --payload-file".to_string(),
+            "--serialsh cannot be combined with --demo, --payload-file, or --payload-watch"
+                .to_string(),
+        ));
+    }
+    if opts.payload_watch.is_some() && (opts.payload_file.is_some() || opts.demo) {
+        return Err(Error::InvalidArgs(
+            "--payload-watch cannot be combined with --demo or --payload-file".to_string(),
+        ));
+    }
+    if opts.fifo.is_some()
+        && (local_payload
+            || opts.demo
+            || opts.device.is_some()
+            || !matches!(opts.mode, RunMode::Daemon))
+    {
+        return Err(Error::InvalidArgs(
+            "--input fifo: cannot be combined with --device, --serialsh, --autobaud-probe, --demo, --payload-file, or --payload-watch"
+                .to_string(),
+        ));
+    }
+    if opts.listen.is_some()
+        && (opts.fifo.is_some()
+            || local_payload
+            || opts.demo
+            || opts.device.is_some()
+            || !matches!(opts.mode, RunMode::Daemon))
+    {
+        return Err(Error::InvalidArgs(
+            "--listen cannot be combined with --device, --input fifo:, --serialsh, --autobaud-probe, --demo, --payload-file, or --payload-watch"
+                .to_string(),
         ));
     }
     Ok(())
@@ -295,18 +1516,24 @@ mod tests {
         let expected = RunOptions {
             mode: RunMode::Daemon,
             device: Some("/dev/ttyUSB0".into()),
+            fifo: None,
+            listen: None,
             baud: Some(9600),
             flow_control: Some(FlowControlMode::Hardware),
             parity: Some(ParityMode::Even),
             stop_bits: Some(StopBitsMode::Two),
             dtr_on_open: Some(DtrBehavior::Assert),
             serial_timeout_ms: Some(1500),
+            low_latency: None,
+            autobaud: None,
             cols: Some(16),
             rows: Some(2),
             payload_file: Some("/tmp/payload.json".into()),
+            payload_watch: None,
             backoff_initial_ms: Some(750),
             backoff_max_ms: Some(9000),
             pcf8574_addr: Some(Pcf8574Addr::Addr(0x23)),
+            display_driver: None,
             log_level: Some("debug".into()),
             log_file: Some("/tmp/lifelinetty.log".into()),
             config_file: None,
@@ -315,6 +1542,11 @@ mod tests {
             polling_enabled: None,
             poll_interval_ms: None,
             demo: true,
+            capture: None,
+            capture_format: None,
+            dry_run: false,
+            echo_frames: false,
+            trace: false,
             wizard: false,
         };
         let cmd = Command::parse(&args).unwrap();
@@ -332,18 +1564,24 @@ mod tests {
         let expected = RunOptions {
             mode: RunMode::Daemon,
             device: Some("/dev/ttyS1".into()),
+            fifo: None,
+            listen: None,
             baud: None,
             flow_control: None,
             parity: None,
             stop_bits: None,
             dtr_on_open: None,
             serial_timeout_ms: None,
+            low_latency: None,
+            autobaud: None,
             cols: None,
             rows: None,
             payload_file: Some("/tmp/payload.json".into()),
+            payload_watch: None,
             backoff_initial_ms: None,
             backoff_max_ms: None,
             pcf8574_addr: None,
+            display_driver: None,
             log_level: None,
             log_file: None,
             config_file: None,
@@ -352,6 +1590,11 @@ mod tests {
             polling_enabled: None,
             poll_interval_ms: None,
             demo: false,
+            capture: None,
+            capture_format: None,
+            dry_run: false,
+            echo_frames: false,
+            trace: false,
             wizard: false,
         };
         let cmd = Command::parse(&args).unwrap();
@@ -370,6 +1613,18 @@ mod tests {
         assert_eq!(cmd, Command::Run(Box::new(expected)));
     }
 
+    #[test]
+    fn parse_low_latency_flags() {
+        for (flag, value) in [("--low-latency", true), ("--no-low-latency", false)] {
+            let expected = RunOptions {
+                low_latency: Some(value),
+                ..Default::default()
+            };
+            let cmd = Command::parse(&[flag.into()]).unwrap();
+            assert_eq!(cmd, Command::Run(Box::new(expected)));
+        }
+    }
+
     #[test]
     fn parse_no_compression_flag() {
         let args = vec!["--no-compressed".into()];
@@ -408,6 +1663,385 @@ mod tests {
         assert_eq!(cmd, Command::Run(Box::new(expected)));
     }
 
+    #[test]
+    fn parse_autobaud_flags() {
+        let args = vec!["--autobaud".into()];
+        let expected = RunOptions {
+            autobaud: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Run(Box::new(expected))
+        );
+
+        let args = vec!["--autobaud-probe".into(), "--device".into(), "fake".into()];
+        match Command::parse(&args).unwrap() {
+            Command::Run(opts) => assert!(matches!(opts.mode, RunMode::AutobaudProbe)),
+            other => panic!("expected Run variant, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_echo_frames_flag() {
+        let args = vec!["--echo-frames".into(), "--demo".into()];
+        let expected = RunOptions {
+            echo_frames: true,
+            demo: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Run(Box::new(expected))
+        );
+    }
+
+    #[test]
+    fn parse_dry_run_flag() {
+        let args = vec![
+            "run".into(),
+            "--dry-run".into(),
+            "--rows".into(),
+            "4".into(),
+        ];
+        let expected = RunOptions {
+            dry_run: true,
+            rows: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Run(Box::new(expected))
+        );
+    }
+
+    #[test]
+    fn parse_trace_flag() {
+        let args = vec!["run".into(), "--trace".into()];
+        let expected = RunOptions {
+            trace: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Run(Box::new(expected))
+        );
+    }
+
+    #[test]
+    fn parse_config_schema() {
+        let args = vec!["config".into(), "schema".into()];
+        assert_eq!(Command::parse(&args).unwrap(), Command::ConfigSchema);
+        let args = vec!["config".into(), "dump".into()];
+        assert!(Command::parse(&args).is_err());
+    }
+
+    #[test]
+    fn parse_config_migrate() {
+        let args = vec!["config".into(), "migrate".into()];
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::ConfigMigrate { dry_run: false }
+        );
+        let args = vec!["config".into(), "migrate".into(), "--dry-run".into()];
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::ConfigMigrate { dry_run: true }
+        );
+        let args = vec!["config".into(), "migrate".into(), "--force".into()];
+        assert!(Command::parse(&args).is_err());
+    }
+
+    #[test]
+    fn parse_config_validate() {
+        let args = vec!["config".into(), "validate".into()];
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::ConfigValidate { lint: false }
+        );
+        let args = vec!["config".into(), "validate".into(), "--lint".into()];
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::ConfigValidate { lint: true }
+        );
+        let args = vec![
+            "config".into(),
+            "validate".into(),
+            "--lint".into(),
+            "x".into(),
+        ];
+        assert!(Command::parse(&args).is_err());
+    }
+
+    #[test]
+    fn parse_provision_requires_from() {
+        let args = vec![
+            "provision".into(),
+            "--from".into(),
+            "/boot/lifelinetty.json".into(),
+        ];
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Provision {
+                from: "/boot/lifelinetty.json".into()
+            }
+        );
+        assert!(Command::parse(&["provision".to_string()]).is_err());
+    }
+
+    #[test]
+    fn parse_render_with_width() {
+        let args = vec![
+            "render".into(),
+            "--payload".into(),
+            "frame.json".into(),
+            "--width".into(),
+            "20".into(),
+        ];
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Render {
+                payload: "frame.json".into(),
+                width: 20
+            }
+        );
+        let args = vec![
+            "render".into(),
+            "--payload".into(),
+            "f.json".into(),
+            "--width".into(),
+            "99".into(),
+        ];
+        assert!(Command::parse(&args).is_err());
+        assert!(Command::parse(&["render".to_string()]).is_err());
+    }
+
+    #[test]
+    fn parse_send_builds_options() {
+        let args: Vec<String> = [
+            "send",
+            "--device",
+            "/dev/ttyUSB1",
+            "--line1",
+            "cpu 50%",
+            "--line2=db ok",
+            "--bar",
+            "50",
+            "--compressed",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Send(Box::new(SendOptions {
+                device: Some("/dev/ttyUSB1".into()),
+                line1: "cpu 50%".into(),
+                line2: "db ok".into(),
+                bar: Some(50),
+                compression_enabled: Some(true),
+                ..SendOptions::default()
+            }))
+        );
+        assert!(Command::parse(&["send".to_string()]).is_err());
+        let args: Vec<String> = ["send", "--line1", "x", "--bar", "101"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(Command::parse(&args).is_err());
+    }
+
+    #[test]
+    fn parse_transfer_send_and_receive() {
+        let args: Vec<String> = ["transfer", "send", "fw.bin", "--ymodem", "--baud=115200"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Transfer(Box::new(TransferOptions {
+                direction: TransferDirection::Send {
+                    file: "fw.bin".into()
+                },
+                device: None,
+                baud: Some(115_200),
+                protocol: TransferProtocol::Ymodem,
+                dir: None,
+            }))
+        );
+        let args: Vec<String> = ["transfer", "receive", "--dir", "/tmp/in"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Transfer(Box::new(TransferOptions {
+                direction: TransferDirection::Receive,
+                device: None,
+                baud: None,
+                protocol: TransferProtocol::Chunks,
+                dir: Some("/tmp/in".into()),
+            }))
+        );
+        let args = vec!["transfer".into(), "send".into()];
+        assert!(Command::parse(&args).is_err());
+        let args = vec![
+            "transfer".into(),
+            "send".into(),
+            "a".into(),
+            "--dir=x".into(),
+        ];
+        assert!(Command::parse(&args).is_err());
+    }
+
+    #[test]
+    fn parse_push_and_pull() {
+        let args: Vec<String> = ["push", "fw.bin", "updates/fw.bin", "--baud=115200"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Push(Box::new(TunnelCopyOptions {
+                local: "fw.bin".into(),
+                remote: "updates/fw.bin".into(),
+                device: None,
+                baud: Some(115_200),
+            }))
+        );
+        let args: Vec<String> = [
+            "pull",
+            "/var/log/syslog",
+            "syslog",
+            "--device",
+            "/dev/ttyS0",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Pull(Box::new(TunnelCopyOptions {
+                local: "syslog".into(),
+                remote: "/var/log/syslog".into(),
+                device: Some("/dev/ttyS0".into()),
+                baud: None,
+            }))
+        );
+        assert!(Command::parse(&["push".into(), "only-one".into()]).is_err());
+        assert!(
+            Command::parse(&["pull".into(), "a".into(), "b".into(), "--dir=x".into()]).is_err()
+        );
+    }
+
+    #[test]
+    fn parse_remote_wizard() {
+        assert_eq!(
+            Command::parse(&["remote-wizard".into()]).unwrap(),
+            Command::RemoteWizard {
+                device: None,
+                baud: None,
+            }
+        );
+        let args: Vec<String> = ["remote-wizard", "--device", "/dev/ttyS0", "--baud=115200"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::RemoteWizard {
+                device: Some("/dev/ttyS0".into()),
+                baud: Some(115_200),
+            }
+        );
+        assert!(Command::parse(&["remote-wizard".into(), "extra".into()]).is_err());
+    }
+
+    #[test]
+    fn parse_arm_and_disarm() {
+        let args = vec!["arm".into(), "--ms=60000".into(), "--sign".into()];
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Arm {
+                ms: Some(60_000),
+                sign: true
+            }
+        );
+        assert_eq!(
+            Command::parse(&["arm".to_string()]).unwrap(),
+            Command::Arm {
+                ms: None,
+                sign: false
+            }
+        );
+        let args = vec!["arm".into(), "--ms".into(), "5".into()];
+        assert!(Command::parse(&args).is_err());
+        assert_eq!(
+            Command::parse(&["disarm".to_string()]).unwrap(),
+            Command::Disarm
+        );
+    }
+
+    #[test]
+    fn parse_monitor() {
+        assert_eq!(
+            Command::parse(&["monitor".to_string()]).unwrap(),
+            Command::Monitor {
+                interval_ms: DEFAULT_MONITOR_INTERVAL_MS
+            }
+        );
+        let args = vec!["monitor".into(), "--interval=250".into()];
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Monitor { interval_ms: 250 }
+        );
+        for bad in ["10", "fast"] {
+            let args = vec!["monitor".into(), "--interval".into(), bad.into()];
+            assert!(Command::parse(&args).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn parse_support_bundle() {
+        assert_eq!(
+            Command::parse(&["support-bundle".to_string()]).unwrap(),
+            Command::SupportBundle
+        );
+        let args = vec!["support-bundle".into(), "--all".into()];
+        assert!(Command::parse(&args).is_err());
+    }
+
+    #[test]
+    fn parse_telemetry_export() {
+        let args: Vec<String> = ["telemetry", "export", "--since", "24h", "--format=csv"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::TelemetryExport {
+                since_ms: Some(86_400_000),
+                format: TelemetryFormat::Csv
+            }
+        );
+        assert_eq!(
+            Command::parse(&["telemetry".into(), "export".into()]).unwrap(),
+            Command::TelemetryExport {
+                since_ms: None,
+                format: TelemetryFormat::Jsonl
+            }
+        );
+        for bad in ["24", "1w", "h"] {
+            let args = vec![
+                "telemetry".into(),
+                "export".into(),
+                "--since".into(),
+                bad.into(),
+            ];
+            assert!(Command::parse(&args).is_err(), "{bad}");
+        }
+        assert!(Command::parse(&["telemetry".into()]).is_err());
+    }
+
     #[test]
     fn parse_help() {
         let args = vec!["--help".into()];
@@ -468,4 +2102,205 @@ mod tests {
         let err = Command::parse(&args).unwrap_err();
         assert!(format!("{err}").contains("serialsh"));
     }
+
+    #[test]
+    fn parse_fifo_input_into_the_cache_dir() {
+        let args = vec!["--input".into(), "fifo:frames.fifo".into()];
+        let expected = RunOptions {
+            fifo: Some(format!("{CACHE_DIR}/frames.fifo")),
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Run(Box::new(expected))
+        );
+
+        for bad in [
+            "fifo:/tmp/frames.fifo",
+            "fifo:../frames.fifo",
+            "fifo:",
+            "pipe:x",
+        ] {
+            let args = vec!["--input".into(), bad.into()];
+            assert!(Command::parse(&args).is_err(), "{bad} accepted");
+        }
+        let args = vec![
+            "--input".into(),
+            "fifo:frames.fifo".into(),
+            "--device".into(),
+            "/dev/ttyS0".into(),
+        ];
+        let err = Command::parse(&args).unwrap_err();
+        assert!(format!("{err}").contains("--input fifo:"));
+    }
+
+    #[test]
+    fn parse_tcp_listen_address() {
+        let args = vec!["--listen".into(), "tcp:0.0.0.0:7777".into()];
+        let expected = RunOptions {
+            listen: Some("0.0.0.0:7777".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Run(Box::new(expected))
+        );
+
+        for bad in [
+            "0.0.0.0:7777",
+            "tcp:localhost:7777",
+            "tcp:0.0.0.0",
+            "udp:[::]:7777",
+        ] {
+            let args = vec!["--listen".into(), bad.into()];
+            assert!(Command::parse(&args).is_err(), "{bad} accepted");
+        }
+        let args = vec![
+            "--listen".into(),
+            "tcp:[::1]:7777".into(),
+            "--serialsh".into(),
+        ];
+        let err = Command::parse(&args).unwrap_err();
+        assert!(format!("{err}").contains("--listen cannot"));
+    }
+
+    #[test]
+    fn parse_payload_watch_and_reject_conflicts() {
+        let args = vec!["--payload-watch".into(), "/run/lcd.json".into()];
+        let expected = RunOptions {
+            payload_watch: Some("/run/lcd.json".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Run(Box::new(expected))
+        );
+
+        let args = vec![
+            "--payload-watch".into(),
+            "a.json".into(),
+            "--payload-file".into(),
+            "b.json".into(),
+        ];
+        let err = Command::parse(&args).unwrap_err();
+        assert!(format!("{err}").contains("--payload-watch"));
+    }
+
+    fn sample_values(value: FlagValue) -> Vec<&'static str> {
+        match value {
+            FlagValue::Switch => vec![],
+            FlagValue::Value("auto|0xNN") => vec!["0x27"],
+            FlagValue::Value("serial|fifo:<path>") => vec!["serial", "fifo:frames.fifo"],
+            FlagValue::Value("tcp:<addr>") => vec!["tcp:127.0.0.1:7777"],
+            FlagValue::Value(_) => vec!["16"],
+            FlagValue::Path => vec!["/tmp/lifelinetty-test"],
+            FlagValue::Choice(choices) => choices.to_vec(),
+        }
+    }
+
+    #[test]
+    fn instance_is_taken_from_anywhere_on_the_line() {
+        let mut args: Vec<String> = ["run", "--device", "/dev/ttyS0", "--instance", "left"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(take_instance(&mut args).unwrap().as_deref(), Some("left"));
+        assert_eq!(args, ["run", "--device", "/dev/ttyS0"]);
+
+        let mut args: Vec<String> = ["--instance=right", "config", "validate"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(take_instance(&mut args).unwrap().as_deref(), Some("right"));
+        assert!(matches!(
+            Command::parse(&args).unwrap(),
+            Command::ConfigValidate { lint: false }
+        ));
+
+        for bad in [
+            &["--instance"][..],
+            &["--instance", "../etc"],
+            &["--instance", "a", "--instance", "b"],
+        ] {
+            let mut args: Vec<String> = bad.iter().map(|s| s.to_string()).collect();
+            assert!(take_instance(&mut args).is_err(), "{bad:?}");
+        }
+        assert_eq!(take_instance(&mut vec!["run".into()]).unwrap(), None);
+    }
+
+    #[test]
+    fn every_table_flag_is_parsed() {
+        for spec in RUN_FLAGS {
+            let values = sample_values(spec.value);
+            if values.is_empty() {
+                assert!(
+                    Command::parse(&[spec.name.to_string()]).is_ok(),
+                    "{} rejected",
+                    spec.name
+                );
+            }
+            for value in values {
+                let args = vec![spec.name.to_string(), value.to_string()];
+                assert!(
+                    Command::parse(&args).is_ok(),
+                    "{} {value} rejected",
+                    spec.name
+                );
+            }
+        }
+        for spec in PROVISION_FLAGS {
+            let args = vec!["provision".into(), spec.name.into(), "doc.json".into()];
+            assert!(Command::parse(&args).is_ok(), "{} rejected", spec.name);
+        }
+    }
+
+    #[test]
+    fn parse_inline_flag_values() {
+        let args = vec!["--device=/dev/ttyS0".into(), "--parity=odd".into()];
+        let expected = RunOptions {
+            device: Some("/dev/ttyS0".into()),
+            parity: Some(ParityMode::Odd),
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Run(Box::new(expected))
+        );
+        assert!(Command::parse(&["--demo=yes".to_string()]).is_err());
+    }
+
+    #[test]
+    fn parse_display_driver_flag() {
+        let args = vec!["--display-driver".into(), "in-tree".into()];
+        let expected = RunOptions {
+            display_driver: Some(DisplayDriver::InTree),
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::parse(&args).unwrap(),
+            Command::Run(Box::new(expected))
+        );
+        let args = vec!["--display-driver".into(), "st7920".into()];
+        assert!(Command::parse(&args).is_err());
+    }
+
+    #[test]
+    fn parse_completions_for_each_shell() {
+        for name in COMPLETION_SHELLS {
+            let args = vec!["completions".into(), name.to_string()];
+            assert!(
+                matches!(Command::parse(&args), Ok(Command::Completions { .. })),
+                "{name} rejected"
+            );
+        }
+        let args = vec!["completions".into(), "tcsh".into()];
+        assert!(Command::parse(&args).is_err());
+        assert!(Command::parse(&["completions".to_string()]).is_err());
+    }
+
+    #[test]
+    fn help_lists_every_table_flag() {
+        let help = Command::help();
+        for spec in RUN_FLAGS {
+            assert!(help.contains(&spec.usage()), "help missing {}", spec.name);
+        }
+    }
 }
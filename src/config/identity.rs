@@ -0,0 +1,124 @@
+//! Per-install identity: a random UUID generated on first run, and the `node_id` derived from
+//! it when `[negotiation].node_id` is left at 0.
+
+use std::path::Path;
+
+use super::{loader, Config};
+use crate::{Error, Result};
+
+/// Every config written before install IDs existed carries this node ID.
+pub const LEGACY_DEFAULT_NODE_ID: u32 = 42;
+
+/// A fresh random (version 4) UUID in the usual `8-4-4-4-12` form.
+pub fn generate_install_id() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    fill_random(&mut bytes)?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
+
+/// Fill `bytes` from the OS random source. There is no weaker fallback: callers that need
+/// unguessable values (link nonces, session tokens) must fail instead.
+pub fn fill_random(bytes: &mut [u8]) -> Result<()> {
+    getrandom::getrandom(bytes).map_err(|err| {
+        Error::Io(std::io::Error::other(format!(
+            "OS random source unavailable: {err}"
+        )))
+    })
+}
+
+/// Whether `raw` is a UUID in `8-4-4-4-12` hex form.
+pub fn is_install_id(raw: &str) -> bool {
+    raw.len() == 36
+        && raw.char_indices().all(|(idx, ch)| match idx {
+            8 | 13 | 18 | 23 => ch == '-',
+            _ => ch.is_ascii_hexdigit(),
+        })
+}
+
+/// Node ID derived from the first 32 bits of an install ID; never 0, which means "auto".
+pub fn derive_node_id(install_id: &str) -> u32 {
+    install_id
+        .get(..8)
+        .and_then(|prefix| u32::from_str_radix(prefix, 16).ok())
+        .unwrap_or(0)
+        .max(1)
+}
+
+/// Give `cfg` an install ID if it has none, and write it back to `path` when that file exists
+/// so the identity survives restarts. A config still on the legacy shared node ID moves to the
+/// derived one at the same time. Returns whether a new ID was generated.
+pub fn ensure_install_id(cfg: &mut Config, path: &Path) -> Result<bool> {
+    if !cfg.negotiation.install_id.is_empty() {
+        return Ok(false);
+    }
+    cfg.negotiation.install_id = generate_install_id()?;
+    if cfg.negotiation.node_id == LEGACY_DEFAULT_NODE_ID {
+        cfg.negotiation.node_id = 0;
+    }
+    if path.exists() {
+        // Re-read the file so environment overrides applied to `cfg` are not persisted.
+        let mut on_disk = loader::parse(&std::fs::read_to_string(path)?)?;
+        on_disk.negotiation.install_id = cfg.negotiation.install_id.clone();
+        if on_disk.negotiation.node_id == LEGACY_DEFAULT_NODE_ID {
+            on_disk.negotiation.node_id = 0;
+        }
+        on_disk.save_to_path(path)?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_ids_are_v4_uuids_and_distinct() {
+        let first = generate_install_id().unwrap();
+        let second = generate_install_id().unwrap();
+        assert!(is_install_id(&first), "{first}");
+        assert_eq!(&first[14..15], "4");
+        assert!(matches!(&first[19..20], "8" | "9" | "a" | "b"));
+        assert_ne!(first, second);
+        assert!(!is_install_id("not-a-uuid"));
+    }
+
+    #[test]
+    fn derived_node_id_uses_the_leading_bits_and_skips_zero() {
+        assert_eq!(
+            derive_node_id("1a2b3c4d-0000-4000-8000-000000000000"),
+            0x1a2b_3c4d
+        );
+        assert_eq!(derive_node_id("00000000-0000-4000-8000-000000000000"), 1);
+        assert_eq!(derive_node_id(""), 1);
+    }
+
+    #[test]
+    fn ensure_persists_once_and_migrates_the_legacy_node_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut legacy = Config::default();
+        legacy.negotiation.node_id = LEGACY_DEFAULT_NODE_ID;
+        legacy.save_to_path(&path).unwrap();
+
+        let mut cfg = Config::load_from_path(&path).unwrap();
+        cfg.device = "/dev/from-env".into();
+        assert!(ensure_install_id(&mut cfg, &path).unwrap());
+        assert_eq!(cfg.negotiation.node_id, 0);
+
+        let mut reloaded = Config::load_from_path(&path).unwrap();
+        assert_eq!(reloaded.negotiation.install_id, cfg.negotiation.install_id);
+        assert_eq!(reloaded.negotiation.node_id, 0);
+        assert_eq!(reloaded.device, Config::default().device);
+        assert!(!ensure_install_id(&mut reloaded, &path).unwrap());
+    }
+}
@@ -0,0 +1,122 @@
+//! Advisory checks for settings that load fine but probably do not do what was meant.
+//!
+//! `validate` rejects a config outright; the lint pass only reports. Its warnings are logged at
+//! startup and printed by `config validate --lint`, and never stop the daemon.
+
+use super::{Config, TunnelAuthBackend, COMMAND_ALLOWLIST_OFF};
+use std::fmt;
+
+/// At or below this baud the link is slow enough that every extra byte per frame shows.
+const SLOW_BAUD: u32 = 9_600;
+
+/// One finding of [`lint`], tied to the key it is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    pub key: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+/// Every warning for `cfg`, in config file order.
+pub fn lint(cfg: &Config) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let mut warn = |key, message| warnings.push(LintWarning { key, message });
+    if cfg.protocol.compression_enabled && cfg.baud <= SLOW_BAUD {
+        warn(
+            "protocol.compression",
+            format!(
+                "enabled at {} baud; LCD frames are too small to shrink and the envelope makes each one longer on a slow link",
+                cfg.baud
+            ),
+        );
+    }
+    if cfg.scroll_speed_ms < cfg.render.min_interval_ms {
+        warn(
+            "scroll_speed_ms",
+            format!(
+                "{} ms is shorter than render.min_interval_ms ({} ms); marquees skip steps between redraws",
+                cfg.scroll_speed_ms, cfg.render.min_interval_ms
+            ),
+        );
+    }
+    if cfg.polling_enabled && cfg.poll_interval_ms < cfg.render.min_interval_ms {
+        warn(
+            "poll_interval_ms",
+            format!(
+                "{} ms is shorter than render.min_interval_ms ({} ms); samples are taken that never reach the display",
+                cfg.poll_interval_ms, cfg.render.min_interval_ms
+            ),
+        );
+    }
+    if cfg.backoff_max_ms < cfg.backoff_initial_ms {
+        warn(
+            "backoff_max_ms",
+            format!(
+                "{} ms is below backoff_initial_ms ({} ms); reconnects retry every {} ms and never back off",
+                cfg.backoff_max_ms, cfg.backoff_initial_ms, cfg.backoff_initial_ms
+            ),
+        );
+    }
+    if !cfg.command_allowlist.is_empty()
+        && cfg.command_allowlist != [COMMAND_ALLOWLIST_OFF]
+        && !cfg.interlock.required
+        && cfg.tunnel_auth.backend == TunnelAuthBackend::None
+    {
+        warn(
+            "command_allowlist",
+            "commands are allowed but neither interlock.required nor tunnel_auth is set; anyone on the serial line can run them".into(),
+        );
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_clean() {
+        assert_eq!(lint(&Config::default()), Vec::new());
+    }
+
+    #[test]
+    fn flags_each_suspicious_setting() {
+        let mut cfg = Config {
+            baud: 9_600,
+            scroll_speed_ms: 250,
+            polling_enabled: true,
+            poll_interval_ms: 1_000,
+            backoff_initial_ms: 5_000,
+            backoff_max_ms: 1_000,
+            command_allowlist: vec!["uptime".into()],
+            ..Config::default()
+        };
+        cfg.protocol.compression_enabled = true;
+        cfg.render.min_interval_ms = 2_000;
+        let keys: Vec<_> = lint(&cfg).into_iter().map(|w| w.key).collect();
+        assert_eq!(
+            keys,
+            [
+                "protocol.compression",
+                "scroll_speed_ms",
+                "poll_interval_ms",
+                "backoff_max_ms",
+                "command_allowlist",
+            ]
+        );
+
+        cfg.interlock.required = true;
+        cfg.baud = 115_200;
+        let warnings = lint(&cfg);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(
+            warnings[2].to_string(),
+            "backoff_max_ms: 1000 ms is below backoff_initial_ms (5000 ms); reconnects retry every 5000 ms and never back off"
+        );
+    }
+}
@@ -6,36 +6,10 @@ use std::{
 
 use crate::{compression::CompressionCodec, Error, Result};
 
-use super::{Config, CONFIG_DIR_NAME, CONFIG_FILE_NAME};
-
-const REQUIRED_KEYS: &[&str] = &[
-    "device",
-    "baud",
-    "flow_control",
-    "parity",
-    "stop_bits",
-    "dtr_on_open",
-    "serial_timeout_ms",
-    "cols",
-    "rows",
-    "lcd_present",
-    "scroll_speed_ms",
-    "page_timeout_ms",
-    "polling_enabled",
-    "poll_interval_ms",
-    "button_gpio_pin",
-    "pcf8574_addr",
-    "display_driver",
-    "backoff_initial_ms",
-    "backoff_max_ms",
-    "watchdog.serial_timeout_ms",
-    "watchdog.tunnel_timeout_ms",
-    "negotiation.node_id",
-    "negotiation.preference",
-    "negotiation.timeout_ms",
-    "protocol.schema_version",
-    "command_allowlist",
-];
+use super::{
+    migrate::{self, CONFIG_VERSION},
+    schema, Config, DisplayConfig, ZoneConfig, CONFIG_DIR_NAME, CONFIG_FILE_NAME,
+};
 
 pub fn load_or_default() -> Result<Config> {
     let path = config_path()?;
@@ -81,6 +55,13 @@ pub fn load_from_path(path: &Path) -> Result<Config> {
         return Ok(cfg);
     }
 
+    let raw = if migrate::file_version(&raw)? < CONFIG_VERSION {
+        migrate::upgrade_file(path, false)?;
+        fs::read_to_string(path)?
+    } else {
+        raw
+    };
+
     let (mut cfg, seen_keys) = parse_with_seen(&raw)?;
     let missing_required = missing_required_keys(&seen_keys);
     if missing_required {
@@ -94,6 +75,62 @@ pub fn load_from_path(path: &Path) -> Result<Config> {
     Ok(cfg)
 }
 
+/// Parse and validate `path` the way startup would, without migrating or backfilling the file.
+pub fn check_path(path: &Path) -> Result<Config> {
+    let raw = fs::read_to_string(path)?;
+    let raw = if !raw.trim().is_empty() && migrate::file_version(&raw)? < CONFIG_VERSION {
+        migrate::migrate(&raw)?.0
+    } else {
+        raw
+    };
+    let (mut cfg, _) = parse_with_seen(&raw)?;
+    apply_env_overrides(&mut cfg)?;
+    super::validate(&cfg)?;
+    Ok(cfg)
+}
+
+/// Current value of one dotted key as written in `path`, defaults filling anything missing.
+/// Environment overrides are left out, since they never reach the file.
+pub fn get_key(path: &Path, key: &str) -> Result<serde_json::Value> {
+    let spec = schema::find(key)
+        .ok_or_else(|| Error::InvalidArgs(format!("unknown config key '{key}'")))?;
+    let (cfg, _) = parse_with_seen(&read_current(path)?)?;
+    Ok(spec.value(&cfg))
+}
+
+/// Set one dotted key in `path` and save the file, refusing values the loader would reject.
+/// `value` is written the way it would appear after `=`, without quotes.
+pub fn set_key(path: &Path, key: &str, value: &str) -> Result<Config> {
+    let spec = schema::find(key)
+        .ok_or_else(|| Error::InvalidArgs(format!("unknown config key '{key}'")))?;
+    if value.contains(['\n', '\r']) {
+        return Err(Error::InvalidArgs(format!("{key} must be a single line")));
+    }
+    // A later line wins, so appending the key after a blank line overrides the old value.
+    let mut raw = read_current(path)?;
+    match spec.section {
+        Some(section) => raw.push_str(&format!("\n\n[{section}]\n{} = {value}\n", spec.key)),
+        None => raw.push_str(&format!("\n\n{} = {value}\n", spec.key)),
+    }
+    let (cfg, _) = parse_with_seen(&raw)?;
+    super::validate(&cfg)?;
+    save_to_path(&cfg, path)?;
+    Ok(cfg)
+}
+
+/// The file in the current format, or nothing when it is missing or empty.
+fn read_current(path: &Path) -> Result<String> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(err) => return Err(err.into()),
+    };
+    if !raw.trim().is_empty() && migrate::file_version(&raw)? < CONFIG_VERSION {
+        return Ok(migrate::migrate(&raw)?.0);
+    }
+    Ok(raw)
+}
+
 pub fn save(config: &Config) -> Result<()> {
     let path = config_path()?;
     save_to_path(config, &path)
@@ -105,9 +142,17 @@ pub fn save_to_path(config: &Config, path: &Path) -> Result<()> {
     }
 
     let allowlist = format_string_array(&config.command_allowlist);
+    let tail_allowlist = format_string_array(&config.tail_allowlist);
+    let annunciator_rules = format_string_array(&config.annunciators.rules);
+    let peer_defaults_allow = format_string_array(&config.peer_defaults.allow);
+    let peer_defaults_deny = format_string_array(&config.peer_defaults.deny);
+    let charmap_map = format_string_array(&config.charmap.map);
+    let filter_rules = format_string_array(&config.filters.rules);
+    let relay_rules = format_string_array(&config.relay.rules);
 
     let contents = format!(
         "# lifelinetty config\n\
+config_version = {}\n\
 device = \"{}\"\n\
 baud = {}\n\
 flow_control = \"{}\"\n\
@@ -115,11 +160,17 @@ parity = \"{}\"\n\
 stop_bits = \"{}\"\n\
 dtr_on_open = \"{}\"\n\
 serial_timeout_ms = {}\n\
+serial_read_min_bytes = {}\n\
+serial_read_gap_ds = {}\n\
+serial_low_latency = {}\n\
+serial_tx_rate_percent = {}\n\
+autobaud = {}\n\
 cols = {}\n\
 rows = {}\n\
 lcd_present = {}\n\
 scroll_speed_ms = {}\n\
 page_timeout_ms = {}\n\
+frame_cache_size = {}\n\
     polling_enabled = {}\n\
     poll_interval_ms = {}\n\
     button_gpio_pin = {}\n\
@@ -130,13 +181,143 @@ backoff_max_ms = {}\n\
 [watchdog]\n\
 serial_timeout_ms = {}\n\
 tunnel_timeout_ms = {}\n\
+[modem]\n\
+poll_ms = {}\n\
+glyph = {}\n\
+[scheduler]\n\
+remote_weight = {}\n\
+local_weight = {}\n\
+min_display_ms = {}\n\
+[slo]\n\
+window_ms = {}\n\
+parse_budget = {}\n\
+reconnect_budget = {}\n\
+crc_budget = {}\n\
+webhook_url = \"{}\"\n\
+[anomaly]\n\
+push = {}\n\
+window_ms = {}\n\
+reconnects = {}\n\
+temp_rise_c = {}\n\
+[arq]\n\
+mode = \"{}\"\n\
+ack_timeout_ms = {}\n\
+max_retries = {}\n\
+auto_crc_failures = {}\n\
+[annunciators]\n\
+rules = {}\n\
+bar_hysteresis = {}\n\
+hold_ms = {}\n\
+active_low = {}\n\
+[parse_errors]\n\
+display = \"{}\"\n\
+after = {}\n\
+display_ms = {}\n\
+[replay]\n\
+window = {}\n\
+require_seq = {}\n\
+[render]\n\
+min_interval_ms = {}\n\
+max_interval_ms = {}\n\
+blink_ms = {}\n\
+throttle = \"{}\"\n\
+expiry_countdown = {}\n\
+bar_style = \"{}\"\n\
+persist_pages = {}\n\
+max_frames_per_sec = {}\n\
+i2c_write_budget_bytes = {}\n\
+[accessibility]\n\
+enabled = {}\n\
+min_scroll_speed_ms = {}\n\
+min_page_timeout_ms = {}\n\
+[charmap]\n\
+enabled = {}\n\
+map = {}\n\
+[filters]\n\
+rules = {}\n\
+[spool]\n\
+enabled = {}\n\
+dir = \"{}\"\n\
+poll_ms = {}\n\
+[burst]\n\
+enabled = {}\n\
+baud = {}\n\
+threshold_bytes = {}\n\
+idle_ms = {}\n\
+max_errors = {}\n\
+[troubleshoot]\n\
+after_failures = {}\n\
+doctor = {}\n\
+[poll_overlay]\n\
+line1 = \"{}\"\n\
+line2 = \"{}\"\n\
+[interlock]\n\
+required = {}\n\
+arm_ms = {}\n\
+button_presses = {}\n\
+key = \"{}\"\n\
+[input]\n\
+encoder_a_pin = {}\n\
+encoder_b_pin = {}\n\
+encoder_button_pin = {}\n\
+long_press_ms = {}\n\
+[lcd.gpio]\n\
+rs_pin = {}\n\
+e_pin = {}\n\
+d4_pin = {}\n\
+d5_pin = {}\n\
+d6_pin = {}\n\
+d7_pin = {}\n\
+backlight_pin = {}\n\
+[backlight]\n\
+idle_timeout_ms = {}\n\
+night_start = \"{}\"\n\
+night_end = \"{}\"\n\
+pwm_channel = {}\n\
+[control]\n\
+enabled = {}\n\
+socket = \"{}\"\n\
+[metrics]\n\
+enabled = {}\n\
+listen = \"{}\"\n\
+[badges]\n\
+enabled = {}\n\
+width = {}\n\
+[peer_defaults]\n\
+allow = {}\n\
+deny = {}\n\
+[logging]\n\
+format = \"{}\"\n\
+max_bytes = {}\n\
+keep = {}\n\
+[log_compaction]\n\
+enabled = {}\n\
+at = \"{}\"\n\
+keep_days = {}\n\
+max_archive_bytes = {}\n\
+[tunnel_auth]\n\
+backend = \"{}\"\n\
+secret = \"{}\"\n\
+period_secs = {}\n\
+skew_steps = {}\n\
+[relay]\n\
+enabled = {}\n\
+device = \"{}\"\n\
+baud = {}\n\
+rules = {}\n\
 [protocol]\n\
 schema_version = {}\n\
 compression = {{ enabled = {}, codec = \"{}\" }}\n\
+ack_enabled = {}\n\
+strict = {}\n\
 [negotiation]\n\
 node_id = {}\n\
+install_id = \"{}\"\n\
 preference = \"{}\"\n\
-timeout_ms = {}\n",
+timeout_ms = {}\n\
+resume_grace_ms = {}\n\
+psk = \"{}\"\n",
+        CONFIG_VERSION,
         config.device,
         config.baud,
         config.flow_control,
@@ -144,11 +325,17 @@ timeout_ms = {}\n",
         config.stop_bits,
         config.dtr_on_open,
         config.serial_timeout_ms,
+        config.serial_read_min_bytes,
+        config.serial_read_gap_ds,
+        config.serial_low_latency,
+        config.serial_tx_rate_percent,
+        config.autobaud,
         config.cols,
         config.rows,
         config.lcd_present,
         config.scroll_speed_ms,
         config.page_timeout_ms,
+        config.frame_cache_size,
         config.polling_enabled,
         config.poll_interval_ms,
         config
@@ -161,23 +348,161 @@ timeout_ms = {}\n",
         config.backoff_max_ms,
         config.watchdog.serial_timeout_ms,
         config.watchdog.tunnel_timeout_ms,
+        config.modem.poll_ms,
+        config.modem.glyph,
+        config.scheduler.remote_weight,
+        config.scheduler.local_weight,
+        config.scheduler.min_display_ms,
+        config.slo.window_ms,
+        config.slo.parse_budget,
+        config.slo.reconnect_budget,
+        config.slo.crc_budget,
+        config.slo.webhook_url,
+        config.anomaly.push,
+        config.anomaly.window_ms,
+        config.anomaly.reconnects,
+        config.anomaly.temp_rise_c,
+        config.arq.mode,
+        config.arq.ack_timeout_ms,
+        config.arq.max_retries,
+        config.arq.auto_crc_failures,
+        annunciator_rules,
+        config.annunciators.bar_hysteresis,
+        config.annunciators.hold_ms,
+        config.annunciators.active_low,
+        config.parse_errors.display,
+        config.parse_errors.after,
+        config.parse_errors.display_ms,
+        config.replay.window,
+        config.replay.require_seq,
+        config.render.min_interval_ms,
+        config.render.max_interval_ms,
+        config.render.blink_ms,
+        config.render.throttle,
+        config.render.expiry_countdown,
+        config.render.bar_style,
+        config.render.persist_pages,
+        config.render.max_frames_per_sec,
+        config.render.i2c_write_budget_bytes,
+        config.accessibility.enabled,
+        config.accessibility.min_scroll_speed_ms,
+        config.accessibility.min_page_timeout_ms,
+        config.charmap.enabled,
+        charmap_map,
+        filter_rules,
+        config.spool.enabled,
+        config.spool.dir,
+        config.spool.poll_ms,
+        config.burst.enabled,
+        config.burst.baud,
+        config.burst.threshold_bytes,
+        config.burst.idle_ms,
+        config.burst.max_errors,
+        config.troubleshoot.after_failures,
+        config.troubleshoot.doctor,
+        config.poll_overlay.line1,
+        config.poll_overlay.line2,
+        config.interlock.required,
+        config.interlock.arm_ms,
+        config.interlock.button_presses,
+        config.interlock.key,
+        format_optional_pin(config.input.encoder_a_pin),
+        format_optional_pin(config.input.encoder_b_pin),
+        format_optional_pin(config.input.encoder_button_pin),
+        config.input.long_press_ms,
+        format_optional_pin(config.lcd_gpio.rs_pin),
+        format_optional_pin(config.lcd_gpio.e_pin),
+        format_optional_pin(config.lcd_gpio.d4_pin),
+        format_optional_pin(config.lcd_gpio.d5_pin),
+        format_optional_pin(config.lcd_gpio.d6_pin),
+        format_optional_pin(config.lcd_gpio.d7_pin),
+        format_optional_pin(config.lcd_gpio.backlight_pin),
+        config.backlight.idle_timeout_ms,
+        config.backlight.night_start,
+        config.backlight.night_end,
+        format_optional_pin(config.backlight.pwm_channel),
+        config.control.enabled,
+        config.control.socket,
+        config.metrics.enabled,
+        config.metrics.listen,
+        config.badges.enabled,
+        config.badges.width,
+        peer_defaults_allow,
+        peer_defaults_deny,
+        config.logging.format,
+        config.logging.max_bytes,
+        config.logging.keep,
+        config.log_compaction.enabled,
+        config.log_compaction.at,
+        config.log_compaction.keep_days,
+        config.log_compaction.max_archive_bytes,
+        config.tunnel_auth.backend,
+        config.tunnel_auth.secret,
+        config.tunnel_auth.period_secs,
+        config.tunnel_auth.skew_steps,
+        config.relay.enabled,
+        config.relay.device,
+        config.relay.baud,
+        relay_rules,
         config.protocol.schema_version,
         config.protocol.compression_enabled,
         config.protocol.compression_codec.as_str(),
+        config.protocol.ack_enabled,
+        config.protocol.strict,
         config.negotiation.node_id,
+        config.negotiation.install_id,
         config.negotiation.preference,
         config.negotiation.timeout_ms,
+        config.negotiation.resume_grace_ms,
+        config.negotiation.psk,
     );
-    let contents = format!("{contents}\ncommand_allowlist = {allowlist}\n");
+    let mut contents =
+        format!("{contents}\ncommand_allowlist = {allowlist}\ntail_allowlist = {tail_allowlist}\n");
+    for display in &config.displays {
+        contents.push_str(&format!(
+            "\n[[displays]]\naddr = {}\ncols = {}\nrows = {}\nrole = \"{}\"\n",
+            super::format_pcf_addr(&display.addr),
+            display.cols,
+            display.rows,
+            display.role
+        ));
+    }
+    for zone in &config.zones {
+        contents.push_str(&format!(
+            "\n[[zones]]\nname = \"{}\"\ndisplay = {}\nrow = {}\nrows = {}\n",
+            zone.name,
+            super::format_zone_display(zone.display),
+            zone.row,
+            zone.rows
+        ));
+    }
     fs::write(path, contents)?;
     Ok(())
 }
 
+/// The display the current `[[displays]]` section describes.
+fn last_display(cfg: &mut Config) -> &mut DisplayConfig {
+    if cfg.displays.is_empty() {
+        cfg.displays.push(DisplayConfig::default());
+    }
+    let last = cfg.displays.len() - 1;
+    &mut cfg.displays[last]
+}
+
+/// The zone the current `[[zones]]` section describes.
+fn last_zone(cfg: &mut Config) -> &mut ZoneConfig {
+    if cfg.zones.is_empty() {
+        cfg.zones.push(ZoneConfig::default());
+    }
+    let last = cfg.zones.len() - 1;
+    &mut cfg.zones[last]
+}
+
 pub fn parse(raw: &str) -> Result<Config> {
     parse_with_seen(raw).map(|(cfg, _)| cfg)
 }
 
-fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
+pub(crate) fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
     let mut cfg = Config::default();
     let mut current_section: Option<&str> = None;
     let mut seen_keys: HashSet<String> = HashSet::new();
@@ -192,7 +517,14 @@ fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
         }
 
         if trimmed.starts_with('[') && trimmed.ends_with(']') {
-            current_section = Some(trimmed.trim_matches(|c| c == '[' || c == ']'));
+            let section = trimmed.trim_matches(|c| c == '[' || c == ']');
+            // Each `[[displays]]` or `[[zones]]` header starts another entry.
+            match section {
+                "displays" => cfg.displays.push(DisplayConfig::default()),
+                "zones" => cfg.zones.push(ZoneConfig::default()),
+                _ => {}
+            }
+            current_section = Some(section);
             continue;
         }
 
@@ -209,6 +541,12 @@ fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
         };
         seen_keys.insert(full_key.clone());
         match full_key.as_str() {
+            // Checked by `migrate::file_version` before a file is parsed.
+            "config_version" => {
+                value.parse::<u32>().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid config_version on line {}", idx + 1))
+                })?;
+            }
             "device" => cfg.device = value.to_string(),
             "baud" => {
                 cfg.baud = value.parse().map_err(|_| {
@@ -240,6 +578,34 @@ fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
                     Error::InvalidArgs(format!("invalid serial_timeout_ms on line {}", idx + 1))
                 })?;
             }
+            "serial_read_min_bytes" => {
+                cfg.serial_read_min_bytes = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid serial_read_min_bytes on line {}", idx + 1))
+                })?;
+            }
+            "serial_read_gap_ds" => {
+                cfg.serial_read_gap_ds = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid serial_read_gap_ds on line {}", idx + 1))
+                })?;
+            }
+            "serial_low_latency" => {
+                cfg.serial_low_latency = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid serial_low_latency on line {}", idx + 1))
+                })?;
+            }
+            "serial_tx_rate_percent" => {
+                cfg.serial_tx_rate_percent = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid serial_tx_rate_percent on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "autobaud" => {
+                cfg.autobaud = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid autobaud on line {}", idx + 1))
+                })?;
+            }
             "cols" => {
                 cfg.cols = value.parse().map_err(|_| {
                     Error::InvalidArgs(format!("invalid cols value on line {}", idx + 1))
@@ -260,6 +626,11 @@ fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
                     Error::InvalidArgs(format!("invalid scroll_speed_ms on line {}", idx + 1))
                 })?;
             }
+            "frame_cache_size" => {
+                cfg.frame_cache_size = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid frame_cache_size on line {}", idx + 1))
+                })?;
+            }
             "page_timeout_ms" => {
                 cfg.page_timeout_ms = value.parse().map_err(|_| {
                     Error::InvalidArgs(format!("invalid page_timeout_ms on line {}", idx + 1))
@@ -275,6 +646,44 @@ fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
                     Error::InvalidArgs(format!("invalid poll_interval_ms on line {}", idx + 1))
                 })?;
             }
+            "displays.addr" => {
+                last_display(&mut cfg).addr = super::parse_pcf_addr(value).map_err(|e| {
+                    Error::InvalidArgs(format!("invalid displays.addr on line {}: {e}", idx + 1))
+                })?;
+            }
+            "displays.cols" => {
+                last_display(&mut cfg).cols = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid displays.cols on line {}", idx + 1))
+                })?;
+            }
+            "displays.rows" => {
+                last_display(&mut cfg).rows = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid displays.rows on line {}", idx + 1))
+                })?;
+            }
+            "displays.role" => {
+                last_display(&mut cfg).role = value.parse().map_err(|e: String| {
+                    Error::InvalidArgs(format!("invalid displays.role on line {}: {e}", idx + 1))
+                })?;
+            }
+            "zones.name" => {
+                last_zone(&mut cfg).name = value.to_string();
+            }
+            "zones.display" => {
+                last_zone(&mut cfg).display = super::parse_zone_display(value).map_err(|e| {
+                    Error::InvalidArgs(format!("invalid zones.display on line {}: {e}", idx + 1))
+                })?;
+            }
+            "zones.row" => {
+                last_zone(&mut cfg).row = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid zones.row on line {}", idx + 1))
+                })?;
+            }
+            "zones.rows" => {
+                last_zone(&mut cfg).rows = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid zones.rows on line {}", idx + 1))
+                })?;
+            }
             "pcf8574_addr" => {
                 cfg.pcf8574_addr = super::parse_pcf_addr(value).map_err(|e| {
                     Error::InvalidArgs(format!("invalid pcf8574_addr on line {}: {e}", idx + 1))
@@ -311,11 +720,504 @@ fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
                     ))
                 })?;
             }
+            "modem.poll_ms" => {
+                cfg.modem.poll_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid modem.poll_ms on line {}", idx + 1))
+                })?;
+            }
+            "modem.glyph" => {
+                cfg.modem.glyph = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid modem.glyph on line {}", idx + 1))
+                })?;
+            }
+            "scheduler.remote_weight" => {
+                cfg.scheduler.remote_weight = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid scheduler.remote_weight on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "scheduler.local_weight" => {
+                cfg.scheduler.local_weight = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid scheduler.local_weight on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "scheduler.min_display_ms" => {
+                cfg.scheduler.min_display_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid scheduler.min_display_ms on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "slo.window_ms" => {
+                cfg.slo.window_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid slo.window_ms on line {}", idx + 1))
+                })?;
+            }
+            "slo.parse_budget" => {
+                cfg.slo.parse_budget = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid slo.parse_budget on line {}", idx + 1))
+                })?;
+            }
+            "slo.reconnect_budget" => {
+                cfg.slo.reconnect_budget = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid slo.reconnect_budget on line {}", idx + 1))
+                })?;
+            }
+            "slo.crc_budget" => {
+                cfg.slo.crc_budget = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid slo.crc_budget on line {}", idx + 1))
+                })?;
+            }
+            "slo.webhook_url" => cfg.slo.webhook_url = value.to_string(),
+            "anomaly.push" => {
+                cfg.anomaly.push = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid anomaly.push on line {}", idx + 1))
+                })?;
+            }
+            "anomaly.window_ms" => {
+                cfg.anomaly.window_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid anomaly.window_ms on line {}", idx + 1))
+                })?;
+            }
+            "anomaly.reconnects" => {
+                cfg.anomaly.reconnects = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid anomaly.reconnects on line {}", idx + 1))
+                })?;
+            }
+            "anomaly.temp_rise_c" => {
+                cfg.anomaly.temp_rise_c = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid anomaly.temp_rise_c on line {}", idx + 1))
+                })?;
+            }
+            "arq.mode" => {
+                cfg.arq.mode = value.parse().map_err(|e: String| {
+                    Error::InvalidArgs(format!("invalid arq.mode on line {}: {e}", idx + 1))
+                })?;
+            }
+            "arq.ack_timeout_ms" => {
+                cfg.arq.ack_timeout_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid arq.ack_timeout_ms on line {}", idx + 1))
+                })?;
+            }
+            "arq.max_retries" => {
+                cfg.arq.max_retries = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid arq.max_retries on line {}", idx + 1))
+                })?;
+            }
+            "arq.auto_crc_failures" => {
+                cfg.arq.auto_crc_failures = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid arq.auto_crc_failures on line {}", idx + 1))
+                })?;
+            }
+            "annunciators.rules" => {
+                cfg.annunciators.rules = parse_string_array(value).map_err(|e| {
+                    Error::InvalidArgs(format!(
+                        "invalid annunciators.rules on line {}: {e}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "annunciators.bar_hysteresis" => {
+                cfg.annunciators.bar_hysteresis = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid annunciators.bar_hysteresis on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "annunciators.hold_ms" => {
+                cfg.annunciators.hold_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid annunciators.hold_ms on line {}", idx + 1))
+                })?;
+            }
+            "annunciators.active_low" => {
+                cfg.annunciators.active_low = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid annunciators.active_low on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "parse_errors.display" => {
+                cfg.parse_errors.display = value.parse().map_err(|e: String| {
+                    Error::InvalidArgs(format!(
+                        "invalid parse_errors.display on line {}: {e}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "parse_errors.after" => {
+                cfg.parse_errors.after = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid parse_errors.after on line {}", idx + 1))
+                })?;
+            }
+            "parse_errors.display_ms" => {
+                cfg.parse_errors.display_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid parse_errors.display_ms on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "replay.window" => {
+                cfg.replay.window = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid replay.window on line {}", idx + 1))
+                })?;
+            }
+            "replay.require_seq" => {
+                cfg.replay.require_seq = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid replay.require_seq on line {}", idx + 1))
+                })?;
+            }
+            "render.min_interval_ms" => {
+                cfg.render.min_interval_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid render.min_interval_ms on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "render.max_interval_ms" => {
+                cfg.render.max_interval_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid render.max_interval_ms on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "render.blink_ms" => {
+                cfg.render.blink_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid render.blink_ms on line {}", idx + 1))
+                })?;
+            }
+            "render.throttle" => {
+                cfg.render.throttle = value.parse().map_err(|e: String| {
+                    Error::InvalidArgs(format!("invalid render.throttle on line {}: {e}", idx + 1))
+                })?;
+            }
+            "render.expiry_countdown" => {
+                cfg.render.expiry_countdown = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid render.expiry_countdown on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "render.persist_pages" => {
+                cfg.render.persist_pages = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid render.persist_pages on line {}", idx + 1))
+                })?;
+            }
+            "render.max_frames_per_sec" => {
+                cfg.render.max_frames_per_sec = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid render.max_frames_per_sec on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "render.i2c_write_budget_bytes" => {
+                cfg.render.i2c_write_budget_bytes = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid render.i2c_write_budget_bytes on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "render.bar_style" => {
+                cfg.render.bar_style = value.parse().map_err(|e: String| {
+                    Error::InvalidArgs(format!("invalid render.bar_style on line {}: {e}", idx + 1))
+                })?;
+            }
+            "accessibility.enabled" => {
+                cfg.accessibility.enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid accessibility.enabled on line {}", idx + 1))
+                })?;
+            }
+            "accessibility.min_scroll_speed_ms" => {
+                cfg.accessibility.min_scroll_speed_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid accessibility.min_scroll_speed_ms on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "accessibility.min_page_timeout_ms" => {
+                cfg.accessibility.min_page_timeout_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid accessibility.min_page_timeout_ms on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "charmap.enabled" => {
+                cfg.charmap.enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid charmap.enabled on line {}", idx + 1))
+                })?;
+            }
+            "charmap.map" => {
+                cfg.charmap.map = parse_string_array(value).map_err(|e| {
+                    Error::InvalidArgs(format!("invalid charmap.map on line {}: {e}", idx + 1))
+                })?;
+            }
+            "filters.rules" => {
+                cfg.filters.rules = parse_string_array(value).map_err(|e| {
+                    Error::InvalidArgs(format!("invalid filters.rules on line {}: {e}", idx + 1))
+                })?;
+            }
+            "spool.enabled" => {
+                cfg.spool.enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid spool.enabled on line {}", idx + 1))
+                })?;
+            }
+            "spool.dir" => cfg.spool.dir = value.to_string(),
+            "spool.poll_ms" => {
+                cfg.spool.poll_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid spool.poll_ms on line {}", idx + 1))
+                })?;
+            }
+            "burst.enabled" => {
+                cfg.burst.enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid burst.enabled on line {}", idx + 1))
+                })?;
+            }
+            "burst.baud" => {
+                cfg.burst.baud = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid burst.baud on line {}", idx + 1))
+                })?;
+            }
+            "burst.threshold_bytes" => {
+                cfg.burst.threshold_bytes = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid burst.threshold_bytes on line {}", idx + 1))
+                })?;
+            }
+            "burst.idle_ms" => {
+                cfg.burst.idle_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid burst.idle_ms on line {}", idx + 1))
+                })?;
+            }
+            "burst.max_errors" => {
+                cfg.burst.max_errors = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid burst.max_errors on line {}", idx + 1))
+                })?;
+            }
+            "troubleshoot.after_failures" => {
+                cfg.troubleshoot.after_failures = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid troubleshoot.after_failures on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "troubleshoot.doctor" => {
+                cfg.troubleshoot.doctor = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid troubleshoot.doctor on line {}", idx + 1))
+                })?;
+            }
+            "poll_overlay.line1" => cfg.poll_overlay.line1 = value.to_string(),
+            "poll_overlay.line2" => cfg.poll_overlay.line2 = value.to_string(),
+            "interlock.required" => {
+                cfg.interlock.required = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid interlock.required on line {}", idx + 1))
+                })?;
+            }
+            "interlock.arm_ms" => {
+                cfg.interlock.arm_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid interlock.arm_ms on line {}", idx + 1))
+                })?;
+            }
+            "interlock.button_presses" => {
+                cfg.interlock.button_presses = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid interlock.button_presses on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "interlock.key" => cfg.interlock.key = value.to_string(),
+            "input.encoder_a_pin" => {
+                cfg.input.encoder_a_pin = parse_optional_pin(value, "input.encoder_a_pin", idx)?;
+            }
+            "input.encoder_b_pin" => {
+                cfg.input.encoder_b_pin = parse_optional_pin(value, "input.encoder_b_pin", idx)?;
+            }
+            "input.encoder_button_pin" => {
+                cfg.input.encoder_button_pin =
+                    parse_optional_pin(value, "input.encoder_button_pin", idx)?;
+            }
+            "input.long_press_ms" => {
+                cfg.input.long_press_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid input.long_press_ms on line {}", idx + 1))
+                })?;
+            }
+            "lcd.gpio.rs_pin" => {
+                cfg.lcd_gpio.rs_pin = parse_optional_pin(value, "lcd.gpio.rs_pin", idx)?;
+            }
+            "lcd.gpio.e_pin" => {
+                cfg.lcd_gpio.e_pin = parse_optional_pin(value, "lcd.gpio.e_pin", idx)?;
+            }
+            "lcd.gpio.d4_pin" => {
+                cfg.lcd_gpio.d4_pin = parse_optional_pin(value, "lcd.gpio.d4_pin", idx)?;
+            }
+            "lcd.gpio.d5_pin" => {
+                cfg.lcd_gpio.d5_pin = parse_optional_pin(value, "lcd.gpio.d5_pin", idx)?;
+            }
+            "lcd.gpio.d6_pin" => {
+                cfg.lcd_gpio.d6_pin = parse_optional_pin(value, "lcd.gpio.d6_pin", idx)?;
+            }
+            "lcd.gpio.d7_pin" => {
+                cfg.lcd_gpio.d7_pin = parse_optional_pin(value, "lcd.gpio.d7_pin", idx)?;
+            }
+            "lcd.gpio.backlight_pin" => {
+                cfg.lcd_gpio.backlight_pin =
+                    parse_optional_pin(value, "lcd.gpio.backlight_pin", idx)?;
+            }
+            "backlight.idle_timeout_ms" => {
+                cfg.backlight.idle_timeout_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid backlight.idle_timeout_ms on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "backlight.night_start" => cfg.backlight.night_start = value.to_string(),
+            "backlight.night_end" => cfg.backlight.night_end = value.to_string(),
+            "backlight.pwm_channel" => {
+                cfg.backlight.pwm_channel =
+                    parse_optional_pin(value, "backlight.pwm_channel", idx)?;
+            }
+            "control.enabled" => {
+                cfg.control.enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid control.enabled on line {}", idx + 1))
+                })?;
+            }
+            "control.socket" => cfg.control.socket = value.to_string(),
+            "metrics.enabled" => {
+                cfg.metrics.enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid metrics.enabled on line {}", idx + 1))
+                })?;
+            }
+            "metrics.listen" => cfg.metrics.listen = value.to_string(),
+            "badges.enabled" => {
+                cfg.badges.enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid badges.enabled on line {}", idx + 1))
+                })?;
+            }
+            "badges.width" => {
+                cfg.badges.width = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid badges.width on line {}", idx + 1))
+                })?;
+            }
+            "logging.format" => {
+                cfg.logging.format = value.parse().map_err(|e: String| {
+                    Error::InvalidArgs(format!("invalid logging.format on line {}: {e}", idx + 1))
+                })?;
+            }
+            "logging.max_bytes" => {
+                cfg.logging.max_bytes = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid logging.max_bytes on line {}", idx + 1))
+                })?;
+            }
+            "logging.keep" => {
+                cfg.logging.keep = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid logging.keep on line {}", idx + 1))
+                })?;
+            }
+            "log_compaction.enabled" => {
+                cfg.log_compaction.enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid log_compaction.enabled on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "log_compaction.at" => cfg.log_compaction.at = value.to_string(),
+            "log_compaction.keep_days" => {
+                cfg.log_compaction.keep_days = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid log_compaction.keep_days on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "log_compaction.max_archive_bytes" => {
+                cfg.log_compaction.max_archive_bytes = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid log_compaction.max_archive_bytes on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "tunnel_auth.backend" => {
+                cfg.tunnel_auth.backend = value.parse().map_err(|e: String| {
+                    Error::InvalidArgs(format!(
+                        "invalid tunnel_auth.backend on line {}: {e}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "tunnel_auth.secret" => cfg.tunnel_auth.secret = value.to_string(),
+            "tunnel_auth.period_secs" => {
+                cfg.tunnel_auth.period_secs = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid tunnel_auth.period_secs on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "tunnel_auth.skew_steps" => {
+                cfg.tunnel_auth.skew_steps = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid tunnel_auth.skew_steps on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "relay.enabled" => {
+                cfg.relay.enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid relay.enabled on line {}", idx + 1))
+                })?;
+            }
+            "relay.device" => cfg.relay.device = value.to_string(),
+            "relay.baud" => {
+                cfg.relay.baud = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid relay.baud on line {}", idx + 1))
+                })?;
+            }
+            "relay.rules" => {
+                cfg.relay.rules = parse_string_array(value).map_err(|e| {
+                    Error::InvalidArgs(format!("invalid relay.rules on line {}: {e}", idx + 1))
+                })?;
+            }
+            "peer_defaults.allow" => {
+                cfg.peer_defaults.allow = parse_string_array(value).map_err(|e| {
+                    Error::InvalidArgs(format!(
+                        "invalid peer_defaults.allow on line {}: {e}",
+                        idx + 1
+                    ))
+                })?;
+            }
+            "peer_defaults.deny" => {
+                cfg.peer_defaults.deny = parse_string_array(value).map_err(|e| {
+                    Error::InvalidArgs(format!(
+                        "invalid peer_defaults.deny on line {}: {e}",
+                        idx + 1
+                    ))
+                })?;
+            }
             "negotiation.node_id" => {
                 cfg.negotiation.node_id = value.parse().map_err(|_| {
                     Error::InvalidArgs(format!("invalid negotiation.node_id on line {}", idx + 1))
                 })?;
             }
+            "negotiation.install_id" => cfg.negotiation.install_id = value.to_string(),
+            "negotiation.psk" => cfg.negotiation.psk = value.to_string(),
             "negotiation.preference" => {
                 cfg.negotiation.preference = value.parse().map_err(|e: String| {
                     Error::InvalidArgs(format!(
@@ -332,6 +1234,14 @@ fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
                     ))
                 })?;
             }
+            "negotiation.resume_grace_ms" => {
+                cfg.negotiation.resume_grace_ms = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!(
+                        "invalid negotiation.resume_grace_ms on line {}",
+                        idx + 1
+                    ))
+                })?;
+            }
             "button_gpio_pin" => {
                 if value == "null" {
                     cfg.button_gpio_pin = None;
@@ -349,6 +1259,11 @@ fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
                     ))
                 })?;
             }
+            "tail_allowlist" => {
+                cfg.tail_allowlist = parse_string_array(value).map_err(|e| {
+                    Error::InvalidArgs(format!("invalid tail_allowlist on line {}: {e}", idx + 1))
+                })?;
+            }
             "protocol.schema_version" => {
                 cfg.protocol.schema_version = value.parse().map_err(|_| {
                     Error::InvalidArgs(format!(
@@ -384,6 +1299,16 @@ fn parse_with_seen(raw: &str) -> Result<(Config, HashSet<String>)> {
                 cfg.protocol.compression_enabled = enabled;
                 cfg.protocol.compression_codec = codec;
             }
+            "protocol.ack_enabled" => {
+                cfg.protocol.ack_enabled = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid protocol.ack_enabled on line {}", idx + 1))
+                })?;
+            }
+            "protocol.strict" => {
+                cfg.protocol.strict = value.parse().map_err(|_| {
+                    Error::InvalidArgs(format!("invalid protocol.strict on line {}", idx + 1))
+                })?;
+            }
             other => {
                 return Err(Error::InvalidArgs(format!(
                     "unknown config key '{}' on line {}",
@@ -402,7 +1327,21 @@ fn config_path() -> Result<PathBuf> {
     let home = std::env::var_os("HOME")
         .map(PathBuf::from)
         .ok_or_else(|| Error::InvalidArgs("HOME not set; cannot locate config directory".into()))?;
-    Ok(home.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+    Ok(config_path_in(
+        &home.join(CONFIG_DIR_NAME),
+        crate::instance(),
+    ))
+}
+
+/// Named instances keep their config in `instances/<name>/` beside the default one.
+fn config_path_in(config_dir: &Path, instance: Option<&str>) -> PathBuf {
+    match instance {
+        Some(name) => config_dir
+            .join("instances")
+            .join(name)
+            .join(CONFIG_FILE_NAME),
+        None => config_dir.join(CONFIG_FILE_NAME),
+    }
 }
 
 fn missing_required_keys(seen_keys: &HashSet<String>) -> bool {
@@ -414,13 +1353,10 @@ fn missing_required_keys(seen_keys: &HashSet<String>) -> bool {
         return true;
     }
 
-    for key in REQUIRED_KEYS {
-        if !seen_keys.contains(*key) {
-            return true;
-        }
-    }
-
-    false
+    schema::KEYS
+        .iter()
+        .filter(|spec| spec.required)
+        .any(|spec| !seen_keys.contains(&spec.path()))
 }
 
 fn apply_env_overrides(cfg: &mut Config) -> Result<()> {
@@ -486,6 +1422,21 @@ fn parse_string_array(value: &str) -> std::result::Result<Vec<String>, String> {
     Ok(entries)
 }
 
+/// GPIO pins are written as a number or `null` when unset.
+fn parse_optional_pin(value: &str, key: &str, idx: usize) -> Result<Option<u8>> {
+    if value == "null" {
+        return Ok(None);
+    }
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| Error::InvalidArgs(format!("invalid {key} on line {}", idx + 1)))
+}
+
+fn format_optional_pin(pin: Option<u8>) -> String {
+    pin.map(|p| p.to_string()).unwrap_or_else(|| "null".into())
+}
+
 fn format_string_array(values: &[String]) -> String {
     if values.is_empty() {
         return "[]".into();
@@ -655,6 +1606,17 @@ mod tests {
         let _ = fs::remove_file(path);
     }
 
+    #[test]
+    fn command_allowlist_off_marker_stands_alone() {
+        let path = temp_path("allowlist_off");
+        fs::write(&path, "command_allowlist = [\"none\"]").unwrap();
+        assert_eq!(load_from_path(&path).unwrap().command_allowlist, ["none"]);
+        fs::write(&path, "command_allowlist = [\"none\", \"ls\"]").unwrap();
+        let err = load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("cannot be combined"));
+        let _ = fs::remove_file(path);
+    }
+
     #[test]
     fn rejects_unknown_key() {
         let path = temp_path("unknown");
@@ -675,26 +1637,218 @@ mod tests {
             stop_bits: StopBitsMode::Two,
             dtr_on_open: DtrBehavior::Deassert,
             serial_timeout_ms: 1200,
+            serial_read_min_bytes: 8,
+            serial_read_gap_ds: 2,
+            serial_low_latency: true,
+            serial_tx_rate_percent: 70,
+            autobaud: false,
             cols: 20,
             rows: 4,
             scroll_speed_ms: 250,
             page_timeout_ms: 4000,
+            frame_cache_size: 64,
             polling_enabled: true,
             poll_interval_ms: 2000,
             button_gpio_pin: Some(22),
             pcf8574_addr: Pcf8574Addr::Auto,
             display_driver: DisplayDriver::Hd44780Driver,
             lcd_present: crate::config::DEFAULT_LCD_PRESENT,
+            displays: vec![
+                crate::config::DisplayConfig {
+                    addr: Pcf8574Addr::Addr(0x26),
+                    cols: 16,
+                    rows: 2,
+                    role: crate::config::DisplayRole::Polling,
+                },
+                crate::config::DisplayConfig {
+                    addr: Pcf8574Addr::Addr(0x3f),
+                    cols: 20,
+                    rows: 4,
+                    role: crate::config::DisplayRole::Zones,
+                },
+            ],
+            zones: vec![
+                crate::config::ZoneConfig {
+                    name: "alerts".into(),
+                    display: None,
+                    row: 3,
+                    rows: 1,
+                },
+                crate::config::ZoneConfig {
+                    name: "weather".into(),
+                    display: Some(0x3f),
+                    row: 0,
+                    rows: 2,
+                },
+            ],
             backoff_initial_ms: DEFAULT_BACKOFF_INITIAL_MS,
             backoff_max_ms: DEFAULT_BACKOFF_MAX_MS,
             negotiation: crate::config::NegotiationConfig::default(),
             command_allowlist: Vec::new(),
+            tail_allowlist: vec!["/var/log/syslog".into()],
             protocol: crate::config::ProtocolConfig {
                 schema_version: 1,
                 compression_enabled: true,
                 compression_codec: CompressionCodec::Lz4,
+                ack_enabled: true,
+                strict: false,
             },
             watchdog: crate::config::WatchdogConfig::default(),
+            modem: crate::config::ModemConfig {
+                poll_ms: 250,
+                glyph: true,
+            },
+            scheduler: crate::config::SchedulerConfig {
+                remote_weight: 2,
+                local_weight: 1,
+                min_display_ms: 1_500,
+            },
+            slo: crate::config::SloConfig {
+                window_ms: 30_000,
+                parse_budget: 5,
+                reconnect_budget: 3,
+                crc_budget: 0,
+                webhook_url: "http://10.0.0.9:8080/incidents".into(),
+            },
+            anomaly: crate::config::AnomalyConfig {
+                push: true,
+                window_ms: 120_000,
+                reconnects: 3,
+                temp_rise_c: 0,
+            },
+            arq: crate::serial::arq::ArqSettings {
+                mode: crate::serial::arq::ArqMode::Off,
+                ack_timeout_ms: 750,
+                max_retries: 3,
+                auto_crc_failures: 8,
+            },
+            annunciators: crate::config::AnnunciatorConfig {
+                rules: vec!["17:severity>=warn".into(), "27:bar>=80".into()],
+                bar_hysteresis: 10,
+                hold_ms: 1_000,
+                active_low: true,
+            },
+            parse_errors: crate::config::ParseErrorConfig {
+                display: crate::config::ParseErrorDisplay::After,
+                after: 5,
+                display_ms: 0,
+            },
+            replay: crate::config::ReplayConfig {
+                window: 16,
+                require_seq: true,
+            },
+            render: crate::config::RenderConfig {
+                min_interval_ms: 100,
+                max_interval_ms: 800,
+                blink_ms: 750,
+                throttle: crate::config::RenderThrottleMode::Adaptive,
+                expiry_countdown: true,
+                bar_style: crate::payload::BarStyle::Segmented,
+                persist_pages: false,
+                max_frames_per_sec: 20,
+                i2c_write_budget_bytes: 480,
+            },
+            accessibility: crate::config::AccessibilityConfig {
+                enabled: true,
+                min_scroll_speed_ms: 900,
+                min_page_timeout_ms: 12_000,
+            },
+            charmap: crate::config::CharmapConfig {
+                enabled: false,
+                map: vec!["€=E".into(), "°=0xdf".into()],
+            },
+            filters: crate::config::FilterConfig {
+                rules: vec![
+                    "drop:line1~^DEBUG".into(),
+                    "mute:polling@09:00-17:00".into(),
+                ],
+            },
+            spool: crate::config::SpoolConfig {
+                enabled: true,
+                dir: format!("{}/pages", crate::CACHE_DIR),
+                poll_ms: 250,
+            },
+            burst: crate::config::BurstConfig {
+                enabled: true,
+                baud: 230_400,
+                threshold_bytes: 4_096,
+                idle_ms: 1_500,
+                max_errors: 5,
+            },
+            troubleshoot: crate::config::TroubleshootConfig {
+                after_failures: 5,
+                doctor: false,
+            },
+            poll_overlay: crate::config::PollOverlayConfig {
+                line1: "CPU{cpu:3}% LD{load1:5.2}".into(),
+                line2: String::new(),
+            },
+            interlock: crate::config::InterlockConfig {
+                required: true,
+                arm_ms: 60_000,
+                button_presses: 4,
+                key: "0123456789abcdef-shared".into(),
+            },
+            input: crate::config::InputConfig {
+                encoder_a_pin: Some(5),
+                encoder_b_pin: Some(6),
+                encoder_button_pin: Some(13),
+                long_press_ms: 1_200,
+            },
+            lcd_gpio: crate::config::LcdGpioConfig {
+                rs_pin: Some(25),
+                e_pin: Some(24),
+                d4_pin: Some(23),
+                d5_pin: Some(17),
+                d6_pin: Some(27),
+                d7_pin: Some(16),
+                backlight_pin: None,
+            },
+            backlight: crate::config::BacklightConfig {
+                idle_timeout_ms: 120_000,
+                night_start: "22:30".into(),
+                night_end: "06:45".into(),
+                pwm_channel: Some(1),
+            },
+            control: crate::config::ControlConfig {
+                enabled: true,
+                socket: "/run/serial_lcd_cache/ctl/lcd.sock".into(),
+            },
+            metrics: crate::config::MetricsConfig {
+                enabled: true,
+                listen: "0.0.0.0:9100".into(),
+            },
+            badges: crate::config::BadgesConfig {
+                enabled: true,
+                width: 4,
+            },
+            peer_defaults: crate::config::PeerDefaultsConfig {
+                allow: vec!["*".into()],
+                deny: vec!["local_weight".into()],
+            },
+            logging: crate::config::LoggingConfig {
+                format: crate::config::LogFormat::Json,
+                max_bytes: 262_144,
+                keep: 5,
+            },
+            log_compaction: crate::config::LogCompactionConfig {
+                enabled: false,
+                at: "04:15".into(),
+                keep_days: 14,
+                max_archive_bytes: 0,
+            },
+            tunnel_auth: crate::config::TunnelAuthConfig {
+                backend: crate::config::TunnelAuthBackend::Totp,
+                secret: "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP".into(),
+                period_secs: 60,
+                skew_steps: 2,
+            },
+            relay: crate::config::RelayConfig {
+                enabled: true,
+                device: "/dev/ttyAMA0".into(),
+                baud: 115_200,
+                rules: vec!["drop:line1~^DEBUG".into()],
+            },
         };
         save_to_path(&cfg, &path).unwrap();
         let loaded = load_from_path(&path).unwrap();
@@ -722,6 +1876,41 @@ mod tests {
         let _ = fs::remove_dir_all(home);
     }
 
+    #[test]
+    fn set_key_rewrites_one_key_and_refuses_invalid_values() {
+        let path = temp_path("set_key");
+        fs::write(
+            &path,
+            "cols = 16\nrows = 2\n\n[negotiation]\npreference = \"prefer_server\"\n",
+        )
+        .unwrap();
+        set_key(&path, "cols", "20").unwrap();
+        set_key(&path, "negotiation.preference", "prefer_client").unwrap();
+        assert_eq!(get_key(&path, "cols").unwrap(), serde_json::json!(20));
+        assert_eq!(get_key(&path, "rows").unwrap(), serde_json::json!(2));
+        assert_eq!(
+            get_key(&path, "negotiation.preference").unwrap(),
+            serde_json::json!("prefer_client")
+        );
+
+        assert!(set_key(&path, "cols", "99").is_err());
+        assert!(set_key(&path, "negotiation.preference", "leader").is_err());
+        assert!(set_key(&path, "no_such_key", "1").is_err());
+        assert!(set_key(&path, "cols", "20\nrows = 4").is_err());
+        assert_eq!(load_from_path(&path).unwrap().cols, 20);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn instances_get_their_own_config_file() {
+        let dir = Path::new("/home/pi/.serial_lcd");
+        assert_eq!(config_path_in(dir, None), dir.join("config.toml"));
+        assert_eq!(
+            config_path_in(dir, Some("left")),
+            dir.join("instances/left/config.toml")
+        );
+    }
+
     #[test]
     fn rejects_cols_outside_range() {
         let path = temp_path("cols_out_of_range");
@@ -758,6 +1947,72 @@ mod tests {
         let _ = fs::remove_file(path);
     }
 
+    #[test]
+    fn rejects_all_zero_scheduler_weights() {
+        let path = temp_path("scheduler_zero");
+        fs::write(
+            &path,
+            "[scheduler]\nremote_weight = 0\nlocal_weight = 0\nmin_display_ms = 1000",
+        )
+        .unwrap();
+        let err = load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("cannot both be 0"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn rejects_unknown_peer_defaults_key() {
+        let path = temp_path("peer_defaults_unknown");
+        fs::write(
+            &path,
+            "[peer_defaults]\nallow = [\"scroll_speed_ms\", \"baud\"]",
+        )
+        .unwrap();
+        let err = load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("peer_defaults.allow has unknown key 'baud'"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn rejects_a_non_http_slo_webhook() {
+        let path = temp_path("slo_webhook");
+        fs::write(&path, "[slo]\nwebhook_url = \"https://alerts.example/hook\"").unwrap();
+        let err = load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("slo.webhook_url"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn rejects_metrics_listen_without_a_port() {
+        let path = temp_path("metrics_listen");
+        fs::write(&path, "[metrics]\nlisten = \"0.0.0.0\"").unwrap();
+        let err = load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("metrics.listen"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn rejects_half_a_night_schedule() {
+        let path = temp_path("backlight_night");
+        fs::write(
+            &path,
+            "[backlight]\nnight_start = \"22:00\"\nnight_end = \"\"",
+        )
+        .unwrap();
+        let err = load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("must both be HH:MM"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn rejects_unknown_backlight_pwm_channel() {
+        let path = temp_path("backlight_pwm");
+        fs::write(&path, "[backlight]\npwm_channel = 2").unwrap();
+        let err = load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("backlight.pwm_channel"));
+        let _ = fs::remove_file(path);
+    }
+
     #[test]
     fn rejects_baud_below_minimum() {
         let path = temp_path("baud_low");
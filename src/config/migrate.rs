@@ -0,0 +1,352 @@
+//! Versioned upgrades for `config.toml`.
+//!
+//! Every saved file starts with `config_version`; files written before it existed count as
+//! version 0. When a key is renamed, moved to another section or retired, bump
+//! [`CONFIG_VERSION`] and append a [`Migration`] instead of teaching the parser the old name.
+//! On load, older files are rewritten in the current format after the original is copied to
+//! `config.toml.v<N>.bak`; `lifelinetty config migrate --dry-run` prints the same plan without
+//! touching anything.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use super::loader;
+use crate::{Error, Result};
+
+/// Format version written by this build.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// One edit a migration makes to the file's keys. Paths are dotted (`section.key` or `key`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyChange {
+    /// Move a value to a new path, replacing anything already there.
+    Rename {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// Remove a key this build no longer reads.
+    Drop(&'static str),
+}
+
+/// Upgrade from version `from` to `from + 1`.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub from: u32,
+    pub summary: &'static str,
+    pub changes: &'static [KeyChange],
+}
+
+/// Every upgrade step, oldest first; `MIGRATIONS[n].from == n`.
+pub static MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    summary: "record config_version",
+    changes: &[],
+}];
+
+/// What a migration did, or would do in a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    /// One line per step and per key it touched.
+    pub steps: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<PathBuf>,
+}
+
+impl MigrationReport {
+    pub fn is_current(&self) -> bool {
+        self.from == self.to
+    }
+
+    /// Human-readable summary for `config migrate`.
+    pub fn describe(&self, path: &Path, dry_run: bool) -> String {
+        if self.is_current() {
+            return format!(
+                "{} is current (config_version {})\n",
+                path.display(),
+                self.to
+            );
+        }
+        let mut out = format!(
+            "{}: config_version {} -> {}{}\n",
+            path.display(),
+            self.from,
+            self.to,
+            if dry_run {
+                " (dry run, nothing written)"
+            } else {
+                ""
+            }
+        );
+        for step in &self.steps {
+            out.push_str(&format!("  {step}\n"));
+        }
+        if let Some(backup) = &self.backup {
+            out.push_str(&format!("backup: {}\n", backup.display()));
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    key: String,
+    value: String,
+    /// First key after a `[[section]]` header, so repeated tables stay apart.
+    starts_table: bool,
+}
+
+/// Version recorded in `raw`; 0 when the key is missing.
+pub fn file_version(raw: &str) -> Result<u32> {
+    let version =
+        match read_entries(raw)?
+            .into_iter()
+            .find(|entry| entry.key == "config_version")
+        {
+            Some(entry) => entry.value.trim_matches('"').parse().map_err(|_| {
+                Error::InvalidArgs(format!("invalid config_version '{}'", entry.value))
+            })?,
+            None => 0,
+        };
+    if version > CONFIG_VERSION {
+        return Err(Error::InvalidArgs(format!(
+            "config_version {version} is newer than this build supports ({CONFIG_VERSION})"
+        )));
+    }
+    Ok(version)
+}
+
+/// Apply every step newer than the file's version and return the upgraded text.
+pub fn migrate(raw: &str) -> Result<(String, MigrationReport)> {
+    migrate_with(raw, MIGRATIONS, CONFIG_VERSION)
+}
+
+fn migrate_with(raw: &str, steps: &[Migration], target: u32) -> Result<(String, MigrationReport)> {
+    let from = file_version(raw)?;
+    let mut entries = read_entries(raw)?;
+    let mut report = MigrationReport {
+        from,
+        to: target,
+        ..MigrationReport::default()
+    };
+    for step in steps.iter().filter(|step| step.from >= from) {
+        report.steps.push(format!(
+            "v{} -> v{}: {}",
+            step.from,
+            step.from + 1,
+            step.summary
+        ));
+        for change in step.changes {
+            if let Some(note) = apply_change(&mut entries, *change) {
+                report.steps.push(format!("  {note}"));
+            }
+        }
+    }
+    entries.retain(|entry| entry.key != "config_version");
+    entries.insert(
+        0,
+        Entry {
+            key: "config_version".into(),
+            value: target.to_string(),
+            starts_table: false,
+        },
+    );
+    Ok((write_entries(&entries), report))
+}
+
+/// Bring the file at `path` up to [`CONFIG_VERSION`]. Unless `dry_run`, the original is copied
+/// to `<file>.v<N>.bak` and the file is rewritten in the current format.
+pub fn upgrade_file(path: &Path, dry_run: bool) -> Result<MigrationReport> {
+    let raw = fs::read_to_string(path)?;
+    let (migrated, mut report) = migrate(&raw)?;
+    if report.is_current() {
+        report.steps.clear();
+        return Ok(report);
+    }
+    // Parse before writing anything so a bad file is left exactly as it was.
+    let cfg = loader::parse(&migrated)?;
+    if dry_run {
+        return Ok(report);
+    }
+    let backup = backup_path(path, report.from);
+    fs::copy(path, &backup)?;
+    cfg.save_to_path(path)?;
+    report.backup = Some(backup);
+    Ok(report)
+}
+
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{version}.bak"));
+    path.with_file_name(name)
+}
+
+fn apply_change(entries: &mut Vec<Entry>, change: KeyChange) -> Option<String> {
+    match change {
+        KeyChange::Rename { from, to } => {
+            if !entries.iter().any(|entry| entry.key == from) {
+                return None;
+            }
+            entries.retain(|entry| entry.key != to);
+            for entry in entries.iter_mut().filter(|entry| entry.key == from) {
+                entry.key = to.to_string();
+            }
+            Some(format!("renamed {from} -> {to}"))
+        }
+        KeyChange::Drop(key) => {
+            let before = entries.len();
+            entries.retain(|entry| entry.key != key);
+            (entries.len() != before).then(|| format!("dropped {key}"))
+        }
+    }
+}
+
+/// Flatten the file into dotted keys, following the loader's rules: a `[section]` header
+/// applies until the next blank line, and comments are skipped.
+fn read_entries(raw: &str) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut section: Option<&str> = None;
+    let mut new_table = false;
+    for (idx, line) in raw.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            section = None;
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            section = Some(trimmed.trim_matches(|c| c == '[' || c == ']'));
+            new_table = trimmed.starts_with("[[");
+            continue;
+        }
+        let (key, value) = trimmed.split_once('=').ok_or_else(|| {
+            Error::InvalidArgs(format!("invalid config line {}: '{}'", idx + 1, line))
+        })?;
+        let key = key.trim();
+        entries.push(Entry {
+            key: match section {
+                Some(section) => format!("{section}.{key}"),
+                None => key.to_string(),
+            },
+            value: value.trim().to_string(),
+            starts_table: std::mem::take(&mut new_table),
+        });
+    }
+    Ok(entries)
+}
+
+fn write_entries(entries: &[Entry]) -> String {
+    let mut out = String::new();
+    let mut current: Option<&str> = None;
+    for entry in entries {
+        let (section, key) = match entry.key.split_once('.') {
+            Some((section, key)) => (Some(section), key),
+            None => (None, entry.key.as_str()),
+        };
+        if entry.starts_table {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("[[{}]]\n", section.unwrap_or_default()));
+            current = section;
+        } else if section != current {
+            if current.is_some() {
+                out.push('\n');
+            }
+            if let Some(section) = section {
+                out.push_str(&format!("[{section}]\n"));
+            }
+            current = section;
+        }
+        out.push_str(&format!("{key} = {}\n", entry.value));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    const STEPS: &[Migration] = &[
+        Migration {
+            from: 0,
+            summary: "record config_version",
+            changes: &[],
+        },
+        Migration {
+            from: 1,
+            summary: "move the poll interval into [polling]",
+            changes: &[
+                KeyChange::Rename {
+                    from: "poll_interval_ms",
+                    to: "polling.interval_ms",
+                },
+                KeyChange::Drop("retired.flag"),
+                KeyChange::Drop("never_present"),
+            ],
+        },
+    ];
+
+    #[test]
+    fn steps_run_in_order_from_the_file_version() {
+        let raw = "# old file\ndevice = \"/dev/ttyS0\"\npoll_interval_ms = 750\n[retired]\nflag = true\n\nbaud = 9600\n";
+        let (migrated, report) = migrate_with(raw, STEPS, 2).unwrap();
+        assert_eq!(report.from, 0);
+        assert_eq!(
+            report.steps,
+            vec![
+                "v0 -> v1: record config_version",
+                "v1 -> v2: move the poll interval into [polling]",
+                "  renamed poll_interval_ms -> polling.interval_ms",
+                "  dropped retired.flag",
+            ]
+        );
+        assert_eq!(
+            migrated,
+            "config_version = 2\ndevice = \"/dev/ttyS0\"\n[polling]\ninterval_ms = 750\n\nbaud = 9600\n"
+        );
+
+        let (_, report) = migrate_with("config_version = 1\n", STEPS, 2).unwrap();
+        assert_eq!(report.steps.len(), 1);
+    }
+
+    #[test]
+    fn newer_files_are_refused() {
+        let raw = format!("config_version = {}\n", CONFIG_VERSION + 1);
+        assert!(file_version(&raw).is_err());
+        assert_eq!(file_version("device = \"/dev/ttyS0\"\n").unwrap(), 0);
+    }
+
+    #[test]
+    fn upgrade_backs_up_the_original_and_dry_run_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        Config::default().save_to_path(&path).unwrap();
+        let current = fs::read_to_string(&path).unwrap();
+        let legacy: String = current
+            .lines()
+            .filter(|line| !line.starts_with("config_version"))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        fs::write(&path, &legacy).unwrap();
+
+        let report = upgrade_file(&path, true).unwrap();
+        assert_eq!((report.from, report.to), (0, CONFIG_VERSION));
+        assert!(report.backup.is_none());
+        assert_eq!(fs::read_to_string(&path).unwrap(), legacy);
+
+        let report = upgrade_file(&path, false).unwrap();
+        let backup = report.backup.unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), legacy);
+        assert_eq!(fs::read_to_string(&path).unwrap(), current);
+        assert!(upgrade_file(&path, false).unwrap().is_current());
+    }
+}
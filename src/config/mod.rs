@@ -1,13 +1,21 @@
 use crate::{
+    cache_dir,
     compression::CompressionCodec,
+    display::{charmap::CharMap, metric_template::MetricTemplate},
+    lcd_driver::ssd1306,
     negotiation::RolePreference,
-    serial::{DtrBehavior, FlowControlMode, ParityMode, StopBitsMode},
-    Error, Result,
+    payload::{is_zone_name, AccessibilityProfile, BarStyle, FrameFilter, MAX_TARGET_LENGTH},
+    serial::{arq::ArqSettings, DtrBehavior, FlowControlMode, ParityMode, StopBitsMode},
+    Error, Result, CACHE_DIR,
 };
 use std::path::Path;
 
+pub mod identity;
+pub mod lint;
 pub mod loader;
+pub mod migrate;
 pub mod profiles;
+pub mod schema;
 
 pub const DEFAULT_DEVICE: &str = "/dev/ttyUSB0";
 pub const DEFAULT_BAUD: u32 = 9_600;
@@ -27,25 +35,176 @@ pub const DEFAULT_POLLING_ENABLED: bool = false;
 pub const DEFAULT_POLL_INTERVAL_MS: u64 = 5000;
 pub const MIN_POLL_INTERVAL_MS: u64 = 1000;
 pub const MAX_POLL_INTERVAL_MS: u64 = 60000;
+/// A `command_allowlist` of just this entry turns the command tunnel off; an empty list allows
+/// every program.
+pub const COMMAND_ALLOWLIST_OFF: &str = "none";
 pub const DEFAULT_PCF8574_ADDR: Pcf8574Addr = Pcf8574Addr::Auto;
 pub const DEFAULT_DISPLAY_DRIVER: DisplayDriver = DisplayDriver::Auto;
+/// `[[displays]]` may appear this many times; a PCF8574 has eight addresses, one is the main
+/// display's.
+pub const MAX_EXTRA_DISPLAYS: usize = 7;
+/// Usual address of a second backpack once the first sits at 0x27.
+pub const DEFAULT_EXTRA_DISPLAY_ADDR: Pcf8574Addr = Pcf8574Addr::Addr(0x26);
+/// `[[zones]]` may appear this many times.
+pub const MAX_ZONES: usize = 8;
+pub const DEFAULT_ZONE_NAME: &str = "status";
 pub const DEFAULT_BACKOFF_INITIAL_MS: u64 = 500;
 pub const DEFAULT_BACKOFF_MAX_MS: u64 = 10_000;
 pub const DEFAULT_SERIAL_TIMEOUT_MS: u64 = 500;
 pub const MIN_SERIAL_TIMEOUT_MS: u64 = 50;
 pub const MAX_SERIAL_TIMEOUT_MS: u64 = 60_000;
+pub const DEFAULT_AUTOBAUD: bool = false;
+pub const DEFAULT_SERIAL_READ_MIN_BYTES: u8 = crate::serial::tuning::DEFAULT_READ_MIN_BYTES;
+pub const DEFAULT_SERIAL_READ_GAP_DS: u8 = crate::serial::tuning::DEFAULT_READ_GAP_DS;
+pub const DEFAULT_SERIAL_LOW_LATENCY: bool = false;
+pub const DEFAULT_SERIAL_TX_RATE_PERCENT: u8 = 0;
+pub const MAX_SERIAL_TX_RATE_PERCENT: u8 = 100;
 pub const DEFAULT_WATCHDOG_SERIAL_TIMEOUT_MS: u64 = 12_000;
 pub const DEFAULT_WATCHDOG_TUNNEL_TIMEOUT_MS: u64 = 5_000;
 pub const MIN_WATCHDOG_TIMEOUT_MS: u64 = 1_000;
 pub const MAX_WATCHDOG_TIMEOUT_MS: u64 = 120_000;
-pub const DEFAULT_NEGOTIATION_NODE_ID: u32 = 42;
+pub const DEFAULT_NEGOTIATION_NODE_ID: u32 = 0;
 pub const DEFAULT_NEGOTIATION_TIMEOUT_MS: u64 = 1_000;
 pub const MIN_NEGOTIATION_TIMEOUT_MS: u64 = 250;
 pub const MAX_NEGOTIATION_TIMEOUT_MS: u64 = 5_000;
+pub const DEFAULT_NEGOTIATION_RESUME_GRACE_MS: u64 = 10_000;
+pub const MAX_NEGOTIATION_RESUME_GRACE_MS: u64 = 300_000;
+/// Shortest `negotiation.psk` accepted; the link keys are only as strong as the passphrase.
+pub const MIN_NEGOTIATION_PSK_CHARS: usize = 16;
 pub const NEGOTIATION_SECTION_NAME: &str = "negotiation";
 pub const DEFAULT_PROTOCOL_SCHEMA_VERSION: u8 = 1;
 pub const DEFAULT_PROTOCOL_COMPRESSION_ENABLED: bool = false;
 pub const DEFAULT_PROTOCOL_COMPRESSION_CODEC: CompressionCodec = CompressionCodec::Lz4;
+pub const DEFAULT_PROTOCOL_ACK_ENABLED: bool = false;
+pub const DEFAULT_PROTOCOL_STRICT: bool = true;
+pub const DEFAULT_MODEM_POLL_MS: u64 = 0;
+pub const MIN_MODEM_POLL_MS: u64 = 100;
+pub const MAX_MODEM_POLL_MS: u64 = 60_000;
+pub const DEFAULT_MODEM_GLYPH: bool = false;
+pub const DEFAULT_SCHEDULER_REMOTE_WEIGHT: u32 = 1;
+pub const DEFAULT_SCHEDULER_LOCAL_WEIGHT: u32 = 0;
+pub const MAX_SCHEDULER_WEIGHT: u32 = 100;
+pub const DEFAULT_SCHEDULER_MIN_DISPLAY_MS: u64 = 2_000;
+pub const MAX_SCHEDULER_MIN_DISPLAY_MS: u64 = 60_000;
+pub const DEFAULT_FRAME_CACHE_SIZE: u16 = 32;
+pub const MAX_FRAME_CACHE_SIZE: u16 = 1_024;
+pub const DEFAULT_SLO_WINDOW_MS: u64 = 60_000;
+pub const MIN_SLO_WINDOW_MS: u64 = 1_000;
+pub const MAX_SLO_WINDOW_MS: u64 = 3_600_000;
+pub const DEFAULT_SLO_BUDGET: u32 = 0;
+pub const MAX_SLO_BUDGET: u32 = 10_000;
+pub const DEFAULT_ANOMALY_PUSH: bool = false;
+pub const DEFAULT_ANOMALY_WINDOW_MS: u64 = 300_000;
+pub const MIN_ANOMALY_WINDOW_MS: u64 = 10_000;
+pub const MAX_ANOMALY_WINDOW_MS: u64 = 3_600_000;
+pub const DEFAULT_ANOMALY_RECONNECTS: u32 = 5;
+pub const MAX_ANOMALY_RECONNECTS: u32 = 1_000;
+pub const DEFAULT_ANOMALY_TEMP_RISE_C: u32 = 10;
+pub const MAX_ANOMALY_TEMP_RISE_C: u32 = 100;
+pub const MIN_ARQ_ACK_TIMEOUT_MS: u64 = 50;
+pub const MAX_ARQ_ACK_TIMEOUT_MS: u64 = 10_000;
+pub const MAX_ARQ_RETRIES: u8 = 20;
+pub const MIN_ARQ_AUTO_CRC_FAILURES: u32 = 1;
+pub const MAX_ARQ_AUTO_CRC_FAILURES: u32 = 1_000;
+pub const DEFAULT_ANNUNCIATOR_BAR_HYSTERESIS: u8 = 5;
+pub const MAX_ANNUNCIATOR_BAR_HYSTERESIS: u8 = 50;
+pub const DEFAULT_ANNUNCIATOR_HOLD_MS: u64 = 500;
+pub const MAX_ANNUNCIATOR_HOLD_MS: u64 = 60_000;
+pub const DEFAULT_ANNUNCIATOR_ACTIVE_LOW: bool = false;
+pub const DEFAULT_PARSE_ERROR_DISPLAY: ParseErrorDisplay = ParseErrorDisplay::Always;
+pub const DEFAULT_PARSE_ERROR_AFTER: u32 = 3;
+pub const MIN_PARSE_ERROR_AFTER: u32 = 1;
+pub const MAX_PARSE_ERROR_AFTER: u32 = 1_000;
+pub const DEFAULT_PARSE_ERROR_DISPLAY_MS: u64 = 3_000;
+pub const MAX_PARSE_ERROR_DISPLAY_MS: u64 = 60_000;
+pub const DEFAULT_REPLAY_WINDOW: u8 = 64;
+pub const MAX_REPLAY_WINDOW: u8 = 64;
+pub const DEFAULT_REPLAY_REQUIRE_SEQ: bool = false;
+pub const DEFAULT_RENDER_MIN_INTERVAL_MS: u64 = 200;
+pub const DEFAULT_RENDER_MAX_INTERVAL_MS: u64 = 1_000;
+pub const MIN_RENDER_INTERVAL_MS: u64 = 20;
+pub const MAX_RENDER_INTERVAL_MS: u64 = 5_000;
+pub const DEFAULT_RENDER_BLINK_MS: u64 = 500;
+pub const MIN_RENDER_BLINK_MS: u64 = 100;
+pub const MAX_RENDER_BLINK_MS: u64 = 10_000;
+pub const DEFAULT_RENDER_THROTTLE: RenderThrottleMode = RenderThrottleMode::Fixed;
+pub const DEFAULT_RENDER_EXPIRY_COUNTDOWN: bool = false;
+pub const DEFAULT_RENDER_BAR_STYLE: BarStyle = BarStyle::Solid;
+pub const DEFAULT_RENDER_PERSIST_PAGES: bool = true;
+pub const DEFAULT_RENDER_MAX_FRAMES_PER_SEC: u32 = 0;
+pub const MAX_RENDER_MAX_FRAMES_PER_SEC: u32 = 1_000;
+pub const DEFAULT_RENDER_I2C_WRITE_BUDGET_BYTES: u32 = 0;
+pub const MAX_RENDER_I2C_WRITE_BUDGET_BYTES: u32 = 65_535;
+pub const DEFAULT_CHARMAP_ENABLED: bool = true;
+pub const DEFAULT_ACCESSIBILITY_ENABLED: bool = false;
+pub const DEFAULT_ACCESSIBILITY_MIN_SCROLL_MS: u64 = 600;
+pub const MAX_ACCESSIBILITY_MIN_SCROLL_MS: u64 = 5_000;
+pub const DEFAULT_ACCESSIBILITY_MIN_PAGE_TIMEOUT_MS: u64 = 8_000;
+pub const MAX_ACCESSIBILITY_MIN_PAGE_TIMEOUT_MS: u64 = 120_000;
+pub const DEFAULT_SPOOL_ENABLED: bool = false;
+pub const DEFAULT_SPOOL_POLL_MS: u64 = 500;
+pub const MIN_SPOOL_POLL_MS: u64 = 100;
+pub const MAX_SPOOL_POLL_MS: u64 = 60_000;
+pub const DEFAULT_BURST_ENABLED: bool = false;
+pub const DEFAULT_BURST_BAUD: u32 = 115_200;
+pub const DEFAULT_BURST_THRESHOLD_BYTES: u64 = 2_048;
+pub const MIN_BURST_THRESHOLD_BYTES: u64 = 256;
+pub const MAX_BURST_THRESHOLD_BYTES: u64 = 1_048_576;
+pub const DEFAULT_BURST_IDLE_MS: u64 = 2_000;
+pub const MIN_BURST_IDLE_MS: u64 = 100;
+pub const MAX_BURST_IDLE_MS: u64 = 60_000;
+pub const DEFAULT_BURST_MAX_ERRORS: u32 = 3;
+pub const MIN_BURST_MAX_ERRORS: u32 = 1;
+pub const MAX_BURST_MAX_ERRORS: u32 = 100;
+pub const DEFAULT_TROUBLESHOOT_AFTER_FAILURES: u32 = 3;
+pub const MAX_TROUBLESHOOT_AFTER_FAILURES: u32 = 100;
+pub const DEFAULT_TROUBLESHOOT_DOCTOR: bool = true;
+pub const DEFAULT_INTERLOCK_REQUIRED: bool = false;
+pub const DEFAULT_INTERLOCK_ARM_MS: u64 = 300_000;
+pub const MIN_INTERLOCK_ARM_MS: u64 = 10_000;
+pub const MAX_INTERLOCK_ARM_MS: u64 = 86_400_000;
+pub const DEFAULT_INTERLOCK_BUTTON_PRESSES: u8 = 3;
+pub const MAX_INTERLOCK_BUTTON_PRESSES: u8 = 10;
+pub const MIN_INTERLOCK_KEY_LEN: usize = 16;
+pub const DEFAULT_INPUT_LONG_PRESS_MS: u64 = 800;
+pub const MIN_INPUT_LONG_PRESS_MS: u64 = 200;
+pub const MAX_INPUT_LONG_PRESS_MS: u64 = 10_000;
+pub const DEFAULT_BACKLIGHT_IDLE_TIMEOUT_MS: u64 = 0;
+pub const MIN_BACKLIGHT_IDLE_TIMEOUT_MS: u64 = 5_000;
+pub const MAX_BACKLIGHT_IDLE_TIMEOUT_MS: u64 = 86_400_000;
+pub const MAX_BACKLIGHT_PWM_CHANNEL: u8 = 1;
+pub const DEFAULT_CONTROL_ENABLED: bool = false;
+pub const DEFAULT_METRICS_ENABLED: bool = false;
+pub const DEFAULT_METRICS_LISTEN: &str = "127.0.0.1:9464";
+pub const DEFAULT_RELAY_ENABLED: bool = false;
+pub const DEFAULT_BADGES_ENABLED: bool = false;
+pub const DEFAULT_BADGES_WIDTH: u8 = 3;
+pub const MIN_BADGES_WIDTH: u8 = 2;
+pub const MAX_BADGES_WIDTH: u8 = 6;
+pub const DEFAULT_LOGGING_MAX_BYTES: u64 = 1_048_576;
+/// Smallest rotation size; tinier files would rotate on every few lines.
+pub const MIN_LOGGING_MAX_BYTES: u64 = 16_384;
+pub const DEFAULT_LOGGING_KEEP: u8 = 3;
+pub const MAX_LOGGING_KEEP: u8 = 20;
+pub const DEFAULT_LOG_COMPACTION_ENABLED: bool = true;
+pub const DEFAULT_LOG_COMPACTION_AT: &str = "03:30";
+pub const DEFAULT_LOG_COMPACTION_KEEP_DAYS: u32 = 7;
+pub const MAX_LOG_COMPACTION_KEEP_DAYS: u32 = 365;
+pub const DEFAULT_LOG_COMPACTION_MAX_ARCHIVE_BYTES: u64 = 4 * 1_048_576;
+pub const DEFAULT_TUNNEL_AUTH_PERIOD_SECS: u64 = 30;
+pub const MIN_TUNNEL_AUTH_PERIOD_SECS: u64 = 15;
+pub const MAX_TUNNEL_AUTH_PERIOD_SECS: u64 = 300;
+pub const DEFAULT_TUNNEL_AUTH_SKEW_STEPS: u8 = 1;
+pub const MAX_TUNNEL_AUTH_SKEW_STEPS: u8 = 5;
+/// RFC 4226 asks for shared secrets of at least 128 bits.
+pub const MIN_TUNNEL_AUTH_SECRET_BYTES: usize = 16;
+/// Keys a peer's `render_defaults` frame may set, as named in `[peer_defaults]` lists.
+pub const PEER_DEFAULTS_KEYS: &[&str] = &[
+    "scroll_speed_ms",
+    "page_timeout_ms",
+    "blink_ms",
+    "local_weight",
+];
 const CONFIG_DIR_NAME: &str = ".serial_lcd";
 const CONFIG_FILE_NAME: &str = "config.toml";
 
@@ -54,6 +213,10 @@ pub struct ProtocolConfig {
     pub schema_version: u8,
     pub compression_enabled: bool,
     pub compression_codec: CompressionCodec,
+    /// Reply with a `frame_ack` line after each payload frame is rendered.
+    pub ack_enabled: bool,
+    /// Reject payload frames with unknown fields; `false` drops the fields and logs each once.
+    pub strict: bool,
 }
 
 impl Default for ProtocolConfig {
@@ -62,6 +225,8 @@ impl Default for ProtocolConfig {
             schema_version: DEFAULT_PROTOCOL_SCHEMA_VERSION,
             compression_enabled: DEFAULT_PROTOCOL_COMPRESSION_ENABLED,
             compression_codec: DEFAULT_PROTOCOL_COMPRESSION_CODEC,
+            ack_enabled: DEFAULT_PROTOCOL_ACK_ENABLED,
+            strict: DEFAULT_PROTOCOL_STRICT,
         }
     }
 }
@@ -69,17 +234,38 @@ impl Default for ProtocolConfig {
 /// Settings that control how this node participates in auto-negotiation.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NegotiationConfig {
+    /// Role tie-breaker; 0 derives it from `install_id`.
     pub node_id: u32,
+    /// Random UUID generated on first run; empty until then.
+    pub install_id: String,
     pub preference: RolePreference,
     pub timeout_ms: u64,
+    /// How long after a disconnect the previous session may be resumed; 0 always renegotiates.
+    pub resume_grace_ms: u64,
+    /// Pre-shared key for link encryption; empty leaves the link in plaintext.
+    pub psk: String,
 }
 
 impl Default for NegotiationConfig {
     fn default() -> Self {
         Self {
             node_id: DEFAULT_NEGOTIATION_NODE_ID,
+            install_id: String::new(),
             preference: RolePreference::default(),
             timeout_ms: DEFAULT_NEGOTIATION_TIMEOUT_MS,
+            resume_grace_ms: DEFAULT_NEGOTIATION_RESUME_GRACE_MS,
+            psk: String::new(),
+        }
+    }
+}
+
+impl NegotiationConfig {
+    /// The node ID sent in `hello`: `node_id` when set, otherwise derived from `install_id`.
+    pub fn effective_node_id(&self) -> u32 {
+        if self.node_id != 0 {
+            self.node_id
+        } else {
+            identity::derive_node_id(&self.install_id)
         }
     }
 }
@@ -104,6 +290,17 @@ pub enum DisplayDriver {
     Auto,
     InTree,
     Hd44780Driver,
+    /// 128x64 I2C OLED drawn as a character grid (see `lcd_driver::ssd1306`).
+    Ssd1306,
+    Sh1106,
+    /// HD44780 wired straight to GPIO in 4-bit mode, pins from `[lcd.gpio]`.
+    Gpio,
+}
+
+impl DisplayDriver {
+    pub fn is_oled(self) -> bool {
+        matches!(self, DisplayDriver::Ssd1306 | DisplayDriver::Sh1106)
+    }
 }
 
 impl std::str::FromStr for DisplayDriver {
@@ -114,8 +311,11 @@ impl std::str::FromStr for DisplayDriver {
             "auto" => Ok(DisplayDriver::Auto),
             "in-tree" | "intree" => Ok(DisplayDriver::InTree),
             "hd44780-driver" | "hd44780" => Ok(DisplayDriver::Hd44780Driver),
+            "ssd1306" => Ok(DisplayDriver::Ssd1306),
+            "sh1106" => Ok(DisplayDriver::Sh1106),
+            "gpio" => Ok(DisplayDriver::Gpio),
             other => Err(format!(
-                "expected 'auto', 'in-tree', or 'hd44780-driver', got '{other}'"
+                "expected 'auto', 'in-tree', 'hd44780-driver', 'ssd1306', 'sh1106', or 'gpio', got '{other}'"
             )),
         }
     }
@@ -127,185 +327,1703 @@ impl std::fmt::Display for DisplayDriver {
             DisplayDriver::Auto => "auto",
             DisplayDriver::InTree => "in-tree",
             DisplayDriver::Hd44780Driver => "hd44780-driver",
+            DisplayDriver::Ssd1306 => "ssd1306",
+            DisplayDriver::Sh1106 => "sh1106",
+            DisplayDriver::Gpio => "gpio",
+        })
+    }
+}
+
+/// What an extra display from `[[displays]]` shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayRole {
+    /// A copy of the main display, cut or padded to this display's size.
+    #[default]
+    Mirror,
+    /// The local polling stats, all the time; needs `polling_enabled`.
+    Polling,
+    /// Only the `[[zones]]` placed on it; other rows stay blank.
+    Zones,
+}
+
+impl std::str::FromStr for DisplayRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mirror" => Ok(DisplayRole::Mirror),
+            "polling" => Ok(DisplayRole::Polling),
+            "zones" => Ok(DisplayRole::Zones),
+            other => Err(format!(
+                "expected 'mirror', 'polling' or 'zones', got '{other}'"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for DisplayRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DisplayRole::Mirror => "mirror",
+            DisplayRole::Polling => "polling",
+            DisplayRole::Zones => "zones",
+        })
+    }
+}
+
+/// One extra LCD on the main display's I2C bus, from a `[[displays]]` section. It uses the
+/// main `display_driver`; the page rotation stays on the main display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayConfig {
+    pub addr: Pcf8574Addr,
+    pub cols: u8,
+    pub rows: u8,
+    pub role: DisplayRole,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            addr: DEFAULT_EXTRA_DISPLAY_ADDR,
+            cols: DEFAULT_COLS,
+            rows: DEFAULT_ROWS,
+            role: DisplayRole::default(),
+        }
+    }
+}
+
+/// A block of rows, from a `[[zones]]` section, that shows the frames sent with a matching
+/// `target`. Each zone rotates and scrolls its own pages; the main rotation keeps the rest of
+/// the main display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneConfig {
+    pub name: String,
+    /// Address of the `[[displays]]` entry it sits on; `None` for the main display.
+    pub display: Option<u8>,
+    /// First row, 0-based.
+    pub row: u8,
+    pub rows: u8,
+}
+
+impl Default for ZoneConfig {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_ZONE_NAME.to_string(),
+            display: None,
+            row: 0,
+            rows: 1,
+        }
+    }
+}
+
+/// When a rejected frame is allowed to take over the LCD with the `ERR PARSE` page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseErrorDisplay {
+    #[default]
+    Always,
+    /// Only once `parse_errors.after` errors arrive without a good frame in between.
+    After,
+    /// Log only; the LCD keeps showing the current page.
+    Never,
+}
+
+impl std::str::FromStr for ParseErrorDisplay {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "always" => Ok(ParseErrorDisplay::Always),
+            "after" => Ok(ParseErrorDisplay::After),
+            "never" => Ok(ParseErrorDisplay::Never),
+            other => Err(format!(
+                "expected 'always', 'after', or 'never', got '{other}'"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseErrorDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ParseErrorDisplay::Always => "always",
+            ParseErrorDisplay::After => "after",
+            ParseErrorDisplay::Never => "never",
+        })
+    }
+}
+
+/// How the minimum gap between LCD redraws is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderThrottleMode {
+    /// Always `render.min_interval_ms`.
+    #[default]
+    Fixed,
+    /// Start at `render.min_interval_ms` and stretch toward `render.max_interval_ms` while
+    /// LCD writes are slow.
+    Adaptive,
+}
+
+impl std::str::FromStr for RenderThrottleMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fixed" => Ok(RenderThrottleMode::Fixed),
+            "adaptive" => Ok(RenderThrottleMode::Adaptive),
+            other => Err(format!("expected 'fixed' or 'adaptive', got '{other}'")),
+        }
+    }
+}
+
+impl std::fmt::Display for RenderThrottleMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RenderThrottleMode::Fixed => "fixed",
+            RenderThrottleMode::Adaptive => "adaptive",
+        })
+    }
+}
+
+/// Shape of each log line on stderr and in the log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// `[ts] [Level] message`.
+    #[default]
+    Text,
+    /// One JSON object per line: `ts`, `level`, `module`, `msg` and any fields.
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("expected 'text' or 'json', got '{other}'")),
+        }
+    }
+}
+
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LogFormat::Text => "text",
+            LogFormat::Json => "json",
+        })
+    }
+}
+
+/// How tunnel command requests prove they may run, on top of the allowlist and interlock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TunnelAuthBackend {
+    /// Any allowlisted command runs.
+    #[default]
+    None,
+    /// Each request carries a 6-digit RFC 6238 code derived from `tunnel_auth.secret`.
+    Totp,
+    /// Each request carries an HMAC-SHA256 signature keyed with `tunnel_auth.secret`.
+    Hmac,
+}
+
+impl std::str::FromStr for TunnelAuthBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(TunnelAuthBackend::None),
+            "totp" => Ok(TunnelAuthBackend::Totp),
+            "hmac" => Ok(TunnelAuthBackend::Hmac),
+            other => Err(format!("expected 'none', 'totp' or 'hmac', got '{other}'")),
+        }
+    }
+}
+
+impl std::fmt::Display for TunnelAuthBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TunnelAuthBackend::None => "none",
+            TunnelAuthBackend::Totp => "totp",
+            TunnelAuthBackend::Hmac => "hmac",
+        })
+    }
+}
+
+/// User-supplied settings loaded from the config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchdogConfig {
+    pub serial_timeout_ms: u64,
+    pub tunnel_timeout_ms: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            serial_timeout_ms: DEFAULT_WATCHDOG_SERIAL_TIMEOUT_MS,
+            tunnel_timeout_ms: DEFAULT_WATCHDOG_TUNNEL_TIMEOUT_MS,
+        }
+    }
+}
+
+/// Sampling of RS-232 modem status lines (CTS/DSR/CD/RI). `poll_ms = 0` disables it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModemConfig {
+    pub poll_ms: u64,
+    pub glyph: bool,
+}
+
+impl Default for ModemConfig {
+    fn default() -> Self {
+        Self {
+            poll_ms: DEFAULT_MODEM_POLL_MS,
+            glyph: DEFAULT_MODEM_GLYPH,
+        }
+    }
+}
+
+/// Fairness between remote frames and local pages (polling overlay) sharing the LCD.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchedulerConfig {
+    pub remote_weight: u32,
+    pub local_weight: u32,
+    pub min_display_ms: u64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            remote_weight: DEFAULT_SCHEDULER_REMOTE_WEIGHT,
+            local_weight: DEFAULT_SCHEDULER_LOCAL_WEIGHT,
+            min_display_ms: DEFAULT_SCHEDULER_MIN_DISPLAY_MS,
+        }
+    }
+}
+
+/// Rolling error budgets; exhausting one raises an incident page. A budget of 0 disables it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SloConfig {
+    pub window_ms: u64,
+    pub parse_budget: u32,
+    pub reconnect_budget: u32,
+    pub crc_budget: u32,
+    /// `http://` URL each incident event is POSTed to as JSON; empty disables delivery.
+    pub webhook_url: String,
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: DEFAULT_SLO_WINDOW_MS,
+            parse_budget: DEFAULT_SLO_BUDGET,
+            reconnect_budget: DEFAULT_SLO_BUDGET,
+            crc_budget: DEFAULT_SLO_BUDGET,
+            webhook_url: String::new(),
+        }
+    }
+}
+
+/// Anomalies worth telling the peer about, each counted over a rolling window. A threshold of 0
+/// disables that detector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnomalyConfig {
+    /// Send `anomaly_summary` frames to a peer that advertises support for them.
+    pub push: bool,
+    pub window_ms: u64,
+    /// Reconnect attempts per window that make a reconnect storm.
+    pub reconnects: u32,
+    /// Degrees Celsius the polled temperature may climb within a window.
+    pub temp_rise_c: u32,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            push: DEFAULT_ANOMALY_PUSH,
+            window_ms: DEFAULT_ANOMALY_WINDOW_MS,
+            reconnects: DEFAULT_ANOMALY_RECONNECTS,
+            temp_rise_c: DEFAULT_ANOMALY_TEMP_RISE_C,
+        }
+    }
+}
+
+/// GPIO outputs that mirror frame attributes; see `app::annunciator` for the rule syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnunciatorConfig {
+    pub rules: Vec<String>,
+    /// Percent a bar must fall below its threshold before a lit `bar>=` rule releases.
+    pub bar_hysteresis: u8,
+    /// Minimum time an output holds a state before it may change again.
+    pub hold_ms: u64,
+    pub active_low: bool,
+}
+
+impl Default for AnnunciatorConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            bar_hysteresis: DEFAULT_ANNUNCIATOR_BAR_HYSTERESIS,
+            hold_ms: DEFAULT_ANNUNCIATOR_HOLD_MS,
+            active_low: DEFAULT_ANNUNCIATOR_ACTIVE_LOW,
+        }
+    }
+}
+
+/// Display policy for frame errors; every error is logged regardless.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseErrorConfig {
+    pub display: ParseErrorDisplay,
+    /// Consecutive errors needed before `display = "after"` shows the error page.
+    pub after: u32,
+    /// How long the error page stays up before the previous page returns; 0 holds it until the
+    /// next good frame.
+    pub display_ms: u64,
+}
+
+impl Default for ParseErrorConfig {
+    fn default() -> Self {
+        Self {
+            display: DEFAULT_PARSE_ERROR_DISPLAY,
+            after: DEFAULT_PARSE_ERROR_AFTER,
+            display_ms: DEFAULT_PARSE_ERROR_DISPLAY_MS,
+        }
+    }
+}
+
+/// Sequence-number checks applied to inbound tunnel and command frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayConfig {
+    /// How many sequence numbers behind the newest one are still accepted once; 0 turns the
+    /// check (and outbound numbering) off.
+    pub window: u8,
+    /// Reject frames that carry no `seq` instead of passing them through for older peers.
+    pub require_seq: bool,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_REPLAY_WINDOW,
+            require_seq: DEFAULT_REPLAY_REQUIRE_SEQ,
+        }
+    }
+}
+
+/// Redraw pacing for the LCD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderConfig {
+    /// Minimum gap between redraws; 200 ms caps the display at 5 fps.
+    pub min_interval_ms: u64,
+    /// Upper bound the adaptive throttle may stretch to.
+    pub max_interval_ms: u64,
+    /// Backlight toggle period for blinking frames.
+    pub blink_ms: u64,
+    pub throttle: RenderThrottleMode,
+    /// Draw shrinking tick marks in the last column while a `duration_ms` frame runs out.
+    pub expiry_countdown: bool,
+    /// Glyph set loaded into CGRAM for bars; payloads may override it per frame.
+    pub bar_style: BarStyle,
+    /// Save the page queue to the cache dir on shutdown and restore it on startup.
+    pub persist_pages: bool,
+    /// Payload frames accepted per second before bursts are coalesced; 0 disables the limit.
+    pub max_frames_per_sec: u32,
+    /// Estimated I2C bytes one full-frame write may take before a warning is logged; 0 turns
+    /// the warning off.
+    pub i2c_write_budget_bytes: u32,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            min_interval_ms: DEFAULT_RENDER_MIN_INTERVAL_MS,
+            max_interval_ms: DEFAULT_RENDER_MAX_INTERVAL_MS,
+            blink_ms: DEFAULT_RENDER_BLINK_MS,
+            throttle: DEFAULT_RENDER_THROTTLE,
+            expiry_countdown: DEFAULT_RENDER_EXPIRY_COUNTDOWN,
+            bar_style: DEFAULT_RENDER_BAR_STYLE,
+            persist_pages: DEFAULT_RENDER_PERSIST_PAGES,
+            max_frames_per_sec: DEFAULT_RENDER_MAX_FRAMES_PER_SEC,
+            i2c_write_budget_bytes: DEFAULT_RENDER_I2C_WRITE_BUDGET_BYTES,
+        }
+    }
+}
+
+/// Unicode to character-ROM translation for the HD44780; see `display::charmap`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharmapConfig {
+    pub enabled: bool,
+    /// Extra `CHAR=TARGET` entries on top of the built-in table.
+    pub map: Vec<String>,
+}
+
+impl Default for CharmapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_CHARMAP_ENABLED,
+            map: Vec::new(),
+        }
+    }
+}
+
+impl CharmapConfig {
+    /// The translation to install on the LCD, or `None` when it is off.
+    pub fn build(&self) -> Result<Option<CharMap>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        CharMap::with_extra(&self.map).map(Some)
+    }
+}
+
+/// Rules that drop accepted frames or mute a source; see `payload::FrameFilter`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterConfig {
+    pub rules: Vec<String>,
+}
+
+impl FilterConfig {
+    pub fn build(&self) -> Result<FrameFilter> {
+        FrameFilter::new(&self.rules)
+    }
+}
+
+/// Readability floors applied to every incoming frame; see `payload::AccessibilityProfile`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessibilityConfig {
+    pub enabled: bool,
+    /// Rows never scroll faster than one step per this many milliseconds.
+    pub min_scroll_speed_ms: u64,
+    /// Pages stay on screen at least this long.
+    pub min_page_timeout_ms: u64,
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_ACCESSIBILITY_ENABLED,
+            min_scroll_speed_ms: DEFAULT_ACCESSIBILITY_MIN_SCROLL_MS,
+            min_page_timeout_ms: DEFAULT_ACCESSIBILITY_MIN_PAGE_TIMEOUT_MS,
+        }
+    }
+}
+
+impl AccessibilityConfig {
+    /// The constraint to apply to frames, or `None` when the profile is off.
+    pub fn profile(&self) -> Option<AccessibilityProfile> {
+        self.enabled.then_some(AccessibilityProfile {
+            min_scroll_speed_ms: self.min_scroll_speed_ms,
+            min_page_timeout_ms: self.min_page_timeout_ms,
         })
     }
-}
-
-/// User-supplied settings loaded from the config file.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct WatchdogConfig {
-    pub serial_timeout_ms: u64,
-    pub tunnel_timeout_ms: u64,
-}
-
-impl Default for WatchdogConfig {
-    fn default() -> Self {
-        Self {
-            serial_timeout_ms: DEFAULT_WATCHDOG_SERIAL_TIMEOUT_MS,
-            tunnel_timeout_ms: DEFAULT_WATCHDOG_TUNNEL_TIMEOUT_MS,
-        }
+}
+
+/// Local payload spool: numbered files dropped into `dir` are queued as pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpoolConfig {
+    pub enabled: bool,
+    /// Must live under the cache directory so producers only ever write to RAM.
+    pub dir: String,
+    pub poll_ms: u64,
+}
+
+impl Default for SpoolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_SPOOL_ENABLED,
+            dir: default_spool_dir(),
+            poll_ms: DEFAULT_SPOOL_POLL_MS,
+        }
+    }
+}
+
+pub fn default_spool_dir() -> String {
+    cache_dir().join("spool").display().to_string()
+}
+
+/// Temporary upshift to a faster baud while large transfers are queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurstConfig {
+    pub enabled: bool,
+    /// Rate used during a burst; must be above the base `baud`.
+    pub baud: u32,
+    /// Bytes sent within one second that start a burst.
+    pub threshold_bytes: u64,
+    /// Quiet time after which the initiating side drops back to the base baud.
+    pub idle_ms: u64,
+    /// Line errors during a burst that abort it and fall back.
+    pub max_errors: u32,
+}
+
+impl Default for BurstConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_BURST_ENABLED,
+            baud: DEFAULT_BURST_BAUD,
+            threshold_bytes: DEFAULT_BURST_THRESHOLD_BYTES,
+            idle_ms: DEFAULT_BURST_IDLE_MS,
+            max_errors: DEFAULT_BURST_MAX_ERRORS,
+        }
+    }
+}
+
+/// Diagnostic mode entered after the daemon fails to start cleanly several times in a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TroubleshootConfig {
+    /// Consecutive failed starts before hints are shown; 0 disables the mode.
+    pub after_failures: u32,
+    /// Run the read-only doctor checks when the mode is entered.
+    pub doctor: bool,
+}
+
+impl Default for TroubleshootConfig {
+    fn default() -> Self {
+        Self {
+            after_failures: DEFAULT_TROUBLESHOOT_AFTER_FAILURES,
+            doctor: DEFAULT_TROUBLESHOOT_DOCTOR,
+        }
+    }
+}
+
+/// Physical-presence gate for the command tunnel: while `required`, remote commands are
+/// rejected until the device is armed locally or by a signed arm message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterlockConfig {
+    pub required: bool,
+    /// How long one arming lasts; signed messages may ask for less but never more.
+    pub arm_ms: u64,
+    /// Button presses within a few seconds that arm the device; 0 disables button arming.
+    pub button_presses: u8,
+    /// Shared secret for signed arm messages; empty disables remote arming.
+    pub key: String,
+}
+
+impl Default for InterlockConfig {
+    fn default() -> Self {
+        Self {
+            required: DEFAULT_INTERLOCK_REQUIRED,
+            arm_ms: DEFAULT_INTERLOCK_ARM_MS,
+            button_presses: DEFAULT_INTERLOCK_BUTTON_PRESSES,
+            key: String::new(),
+        }
+    }
+}
+
+/// Rotary encoder for page navigation (BCM pin numbers): turning steps through queued pages,
+/// the push switch acts like the page button and a long press toggles the backlight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputConfig {
+    pub encoder_a_pin: Option<u8>,
+    pub encoder_b_pin: Option<u8>,
+    pub encoder_button_pin: Option<u8>,
+    pub long_press_ms: u64,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            encoder_a_pin: None,
+            encoder_b_pin: None,
+            encoder_button_pin: None,
+            long_press_ms: DEFAULT_INPUT_LONG_PRESS_MS,
+        }
+    }
+}
+
+/// BCM pins of an HD44780 wired without a backpack, for `display_driver = "gpio"`. R/W must be
+/// tied to ground; the driver never reads the busy flag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LcdGpioConfig {
+    pub rs_pin: Option<u8>,
+    pub e_pin: Option<u8>,
+    pub d4_pin: Option<u8>,
+    pub d5_pin: Option<u8>,
+    pub d6_pin: Option<u8>,
+    pub d7_pin: Option<u8>,
+    /// Switches the backlight through a transistor; `None` leaves it wired on.
+    pub backlight_pin: Option<u8>,
+}
+
+impl LcdGpioConfig {
+    /// Every configured pin with its key, for validation and error messages.
+    pub fn pins(&self) -> [(&'static str, Option<u8>); 7] {
+        [
+            ("lcd.gpio.rs_pin", self.rs_pin),
+            ("lcd.gpio.e_pin", self.e_pin),
+            ("lcd.gpio.d4_pin", self.d4_pin),
+            ("lcd.gpio.d5_pin", self.d5_pin),
+            ("lcd.gpio.d6_pin", self.d6_pin),
+            ("lcd.gpio.d7_pin", self.d7_pin),
+            ("lcd.gpio.backlight_pin", self.backlight_pin),
+        ]
+    }
+}
+
+/// Central backlight policy: off after `idle_timeout_ms` without frames or input, and off during
+/// the local-time night window unless a button press wakes it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BacklightConfig {
+    /// 0 keeps the backlight on however long the link is quiet.
+    pub idle_timeout_ms: u64,
+    /// `HH:MM` local time; empty (with `night_end`) disables the schedule.
+    pub night_start: String,
+    pub night_end: String,
+    /// Hardware PWM channel dimming the backlight LED, for smooth patterns.
+    pub pwm_channel: Option<u8>,
+}
+
+/// Minutes since midnight for an `HH:MM` string.
+pub fn parse_time_of_day(raw: &str) -> Option<u16> {
+    let (hours, minutes) = raw.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Local control socket for scripts on the same host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlConfig {
+    pub enabled: bool,
+    /// Must live under the cache directory, like every other runtime file.
+    pub socket: String,
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_CONTROL_ENABLED,
+            socket: default_control_socket(),
+        }
+    }
+}
+
+pub fn default_control_socket() -> String {
+    cache_dir().join("control.sock").display().to_string()
+}
+
+/// Prometheus `/metrics` endpoint; only served by builds with the `metrics-http` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// `address:port` to listen on; the default only answers local scrapers.
+    pub listen: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_METRICS_ENABLED,
+            listen: DEFAULT_METRICS_LISTEN.to_string(),
+        }
+    }
+}
+
+/// Second serial port that accepted frames are forwarded on, for a node in the middle of a chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayConfig {
+    pub enabled: bool,
+    /// Downstream port; same forms as `device`.
+    pub device: String,
+    pub baud: u32,
+    /// `[filters]`-style rules; a frame they drop is shown here but not forwarded.
+    pub rules: Vec<String>,
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_RELAY_ENABLED,
+            device: String::new(),
+            baud: DEFAULT_BAUD,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl RelayConfig {
+    pub fn build(&self) -> Result<FrameFilter> {
+        FrameFilter::new(&self.rules)
+    }
+}
+
+/// Counters of alerts and errors nobody has looked at yet, shown on the status row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BadgesConfig {
+    pub enabled: bool,
+    /// Cells the badge takes, just left of the heartbeat cell.
+    pub width: u8,
+}
+
+impl Default for BadgesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_BADGES_ENABLED,
+            width: DEFAULT_BADGES_WIDTH,
+        }
+    }
+}
+
+/// Which display defaults the peer may push. A key applies when `allow` names it (or holds
+/// `"*"`) and `deny` does not; both empty means pushes are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerDefaultsConfig {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl PeerDefaultsConfig {
+    pub fn allows(&self, key: &str) -> bool {
+        let named = |list: &[String]| list.iter().any(|entry| entry == key || entry == "*");
+        named(&self.allow) && !named(&self.deny)
+    }
+}
+
+/// Log line format and size-based rotation of the `--log-file` sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// Rotate the log file before it grows past this many bytes; 0 never rotates.
+    pub max_bytes: u64,
+    /// Rotated files kept beside the live one (`lifelinetty.log.1` is the newest).
+    pub keep: u8,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            max_bytes: DEFAULT_LOGGING_MAX_BYTES,
+            keep: DEFAULT_LOGGING_KEEP,
+        }
+    }
+}
+
+/// Nightly gzip of the cache dir logs into dated archives; see `app::log_compaction`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogCompactionConfig {
+    pub enabled: bool,
+    /// `HH:MM` local time the daily pass starts.
+    pub at: String,
+    /// Archives are deleted once they are this many days old.
+    pub keep_days: u32,
+    /// Oldest archives go first once all of them together pass this size; 0 never does.
+    pub max_archive_bytes: u64,
+}
+
+impl Default for LogCompactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_LOG_COMPACTION_ENABLED,
+            at: DEFAULT_LOG_COMPACTION_AT.to_string(),
+            keep_days: DEFAULT_LOG_COMPACTION_KEEP_DAYS,
+            max_archive_bytes: DEFAULT_LOG_COMPACTION_MAX_ARCHIVE_BYTES,
+        }
+    }
+}
+
+/// Authorization of tunnel commands. Both ends share the config: the daemon verifies codes and
+/// `--serialsh` adds them to each request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TunnelAuthConfig {
+    pub backend: TunnelAuthBackend,
+    /// Base32 shared secret, as authenticator apps and `oathtool --totp -b` take it.
+    pub secret: String,
+    /// Seconds each code is valid for; with `hmac`, how far a signature's timestamp may be
+    /// from the daemon's clock.
+    pub period_secs: u64,
+    /// Codes this many periods either side of ours are accepted, for clock drift.
+    pub skew_steps: u8,
+}
+
+impl Default for TunnelAuthConfig {
+    fn default() -> Self {
+        Self {
+            backend: TunnelAuthBackend::default(),
+            secret: String::new(),
+            period_secs: DEFAULT_TUNNEL_AUTH_PERIOD_SECS,
+            skew_steps: DEFAULT_TUNNEL_AUTH_SKEW_STEPS,
+        }
+    }
+}
+
+/// Custom polling overlay layout; an empty line keeps the built-in CPU/MEM/DSK/TMP text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PollOverlayConfig {
+    /// Template for the first row, e.g. `LD{load1:5.2} T{temp:3}C`.
+    pub line1: String,
+    /// Template for the second row.
+    pub line2: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub device: String,
+    pub baud: u32,
+    pub flow_control: FlowControlMode,
+    pub parity: ParityMode,
+    pub stop_bits: StopBitsMode,
+    pub dtr_on_open: DtrBehavior,
+    pub serial_timeout_ms: u64,
+    pub serial_read_min_bytes: u8,
+    pub serial_read_gap_ds: u8,
+    pub serial_low_latency: bool,
+    pub serial_tx_rate_percent: u8,
+    pub autobaud: bool,
+    pub cols: u8,
+    pub rows: u8,
+    pub scroll_speed_ms: u64,
+    pub page_timeout_ms: u64,
+    pub frame_cache_size: u16,
+    pub polling_enabled: bool,
+    pub poll_interval_ms: u64,
+    pub button_gpio_pin: Option<u8>,
+    pub pcf8574_addr: Pcf8574Addr,
+    pub display_driver: DisplayDriver,
+    pub lcd_present: bool,
+    pub displays: Vec<DisplayConfig>,
+    pub zones: Vec<ZoneConfig>,
+    pub backoff_initial_ms: u64,
+    pub backoff_max_ms: u64,
+    pub negotiation: NegotiationConfig,
+    pub command_allowlist: Vec<String>,
+    pub tail_allowlist: Vec<String>,
+    pub protocol: ProtocolConfig,
+    pub watchdog: WatchdogConfig,
+    pub modem: ModemConfig,
+    pub scheduler: SchedulerConfig,
+    pub slo: SloConfig,
+    pub anomaly: AnomalyConfig,
+    pub arq: ArqSettings,
+    pub annunciators: AnnunciatorConfig,
+    pub parse_errors: ParseErrorConfig,
+    pub replay: ReplayConfig,
+    pub render: RenderConfig,
+    pub accessibility: AccessibilityConfig,
+    pub charmap: CharmapConfig,
+    pub filters: FilterConfig,
+    pub spool: SpoolConfig,
+    pub burst: BurstConfig,
+    pub troubleshoot: TroubleshootConfig,
+    pub poll_overlay: PollOverlayConfig,
+    pub interlock: InterlockConfig,
+    pub input: InputConfig,
+    pub lcd_gpio: LcdGpioConfig,
+    pub backlight: BacklightConfig,
+    pub control: ControlConfig,
+    pub metrics: MetricsConfig,
+    pub badges: BadgesConfig,
+    pub peer_defaults: PeerDefaultsConfig,
+    pub logging: LoggingConfig,
+    pub log_compaction: LogCompactionConfig,
+    pub tunnel_auth: TunnelAuthConfig,
+    pub relay: RelayConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            device: DEFAULT_DEVICE.to_string(),
+            baud: DEFAULT_BAUD,
+            flow_control: FlowControlMode::default(),
+            parity: ParityMode::default(),
+            stop_bits: StopBitsMode::default(),
+            dtr_on_open: DtrBehavior::default(),
+            serial_timeout_ms: DEFAULT_SERIAL_TIMEOUT_MS,
+            serial_read_min_bytes: DEFAULT_SERIAL_READ_MIN_BYTES,
+            serial_read_gap_ds: DEFAULT_SERIAL_READ_GAP_DS,
+            serial_low_latency: DEFAULT_SERIAL_LOW_LATENCY,
+            serial_tx_rate_percent: DEFAULT_SERIAL_TX_RATE_PERCENT,
+            autobaud: DEFAULT_AUTOBAUD,
+            cols: DEFAULT_COLS,
+            rows: DEFAULT_ROWS,
+            scroll_speed_ms: DEFAULT_SCROLL_MS,
+            page_timeout_ms: DEFAULT_PAGE_TIMEOUT_MS,
+            frame_cache_size: DEFAULT_FRAME_CACHE_SIZE,
+            polling_enabled: DEFAULT_POLLING_ENABLED,
+            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+            button_gpio_pin: None,
+            pcf8574_addr: DEFAULT_PCF8574_ADDR,
+            display_driver: DEFAULT_DISPLAY_DRIVER,
+            lcd_present: DEFAULT_LCD_PRESENT,
+            displays: Vec::new(),
+            zones: Vec::new(),
+            backoff_initial_ms: DEFAULT_BACKOFF_INITIAL_MS,
+            backoff_max_ms: DEFAULT_BACKOFF_MAX_MS,
+            negotiation: NegotiationConfig::default(),
+            command_allowlist: Vec::new(),
+            tail_allowlist: Vec::new(),
+            protocol: ProtocolConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            modem: ModemConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            slo: SloConfig::default(),
+            anomaly: AnomalyConfig::default(),
+            arq: ArqSettings::default(),
+            annunciators: AnnunciatorConfig::default(),
+            parse_errors: ParseErrorConfig::default(),
+            replay: ReplayConfig::default(),
+            render: RenderConfig::default(),
+            accessibility: AccessibilityConfig::default(),
+            charmap: CharmapConfig::default(),
+            filters: FilterConfig::default(),
+            spool: SpoolConfig::default(),
+            burst: BurstConfig::default(),
+            troubleshoot: TroubleshootConfig::default(),
+            poll_overlay: PollOverlayConfig::default(),
+            interlock: InterlockConfig::default(),
+            input: InputConfig::default(),
+            lcd_gpio: LcdGpioConfig::default(),
+            backlight: BacklightConfig::default(),
+            control: ControlConfig::default(),
+            metrics: MetricsConfig::default(),
+            badges: BadgesConfig::default(),
+            peer_defaults: PeerDefaultsConfig::default(),
+            logging: LoggingConfig::default(),
+            log_compaction: LogCompactionConfig::default(),
+            tunnel_auth: TunnelAuthConfig::default(),
+            relay: RelayConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load_or_default() -> Result<Self> {
+        loader::load_or_default()
+    }
+
+    pub fn load_from_path(path: &Path) -> Result<Self> {
+        loader::load_from_path(path)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        loader::save(self)
+    }
+
+    pub fn save_to_path(&self, path: &Path) -> Result<()> {
+        loader::save_to_path(self, path)
+    }
+
+    #[allow(dead_code)]
+    fn parse(raw: &str) -> Result<Self> {
+        loader::parse(raw)
+    }
+}
+
+/// `"main"` or the address of a `[[displays]]` entry.
+fn parse_zone_display(raw: &str) -> std::result::Result<Option<u8>, String> {
+    if raw.eq_ignore_ascii_case("main") {
+        return Ok(None);
+    }
+    match parse_pcf_addr(raw) {
+        Ok(Pcf8574Addr::Addr(addr)) => Ok(Some(addr)),
+        _ => Err("expected 'main' or a [[displays]] address (e.g., 0x26)".to_string()),
+    }
+}
+
+fn parse_pcf_addr(raw: &str) -> std::result::Result<Pcf8574Addr, String> {
+    if raw.eq_ignore_ascii_case("auto") {
+        return Ok(Pcf8574Addr::Auto);
+    }
+    let cleaned = raw.trim_start_matches("0x");
+    let value = u8::from_str_radix(cleaned, 16)
+        .or_else(|_| raw.parse::<u8>())
+        .map_err(|_| "expected 'auto' or a hex/decimal address (e.g., 0x27)".to_string())?;
+    Ok(Pcf8574Addr::Addr(value))
+}
+
+pub(crate) fn validate(cfg: &Config) -> Result<()> {
+    validate_baud(cfg.baud)?;
+    if cfg.cols < MIN_COLS || cfg.cols > MAX_COLS {
+        return Err(Error::InvalidArgs(format!(
+            "cols must be between {MIN_COLS} and {MAX_COLS}"
+        )));
+    }
+    if cfg.rows < MIN_ROWS || cfg.rows > MAX_ROWS {
+        return Err(Error::InvalidArgs(format!(
+            "rows must be between {MIN_ROWS} and {MAX_ROWS}"
+        )));
+    }
+    if cfg.display_driver.is_oled() && cfg.cols > ssd1306::MAX_COLS {
+        return Err(Error::InvalidArgs(format!(
+            "cols must be at most {} with display_driver = \"{}\"",
+            ssd1306::MAX_COLS,
+            cfg.display_driver
+        )));
+    }
+    if cfg.scroll_speed_ms < MIN_SCROLL_MS {
+        return Err(Error::InvalidArgs(format!(
+            "scroll_speed_ms must be at least {MIN_SCROLL_MS}"
+        )));
+    }
+    if cfg.page_timeout_ms < MIN_PAGE_TIMEOUT_MS {
+        return Err(Error::InvalidArgs(format!(
+            "page_timeout_ms must be at least {MIN_PAGE_TIMEOUT_MS}"
+        )));
+    }
+    if cfg.poll_interval_ms < MIN_POLL_INTERVAL_MS || cfg.poll_interval_ms > MAX_POLL_INTERVAL_MS {
+        return Err(Error::InvalidArgs(format!(
+            "poll_interval_ms must be between {MIN_POLL_INTERVAL_MS} and {MAX_POLL_INTERVAL_MS}"
+        )));
+    }
+    for entry in &cfg.command_allowlist {
+        if entry.trim().is_empty() {
+            return Err(Error::InvalidArgs(
+                "command_allowlist entries must be non-empty".to_string(),
+            ));
+        }
+    }
+    if cfg.command_allowlist.len() > 1
+        && cfg
+            .command_allowlist
+            .iter()
+            .any(|entry| entry == COMMAND_ALLOWLIST_OFF)
+    {
+        return Err(Error::InvalidArgs(format!(
+            "command_allowlist entry '{COMMAND_ALLOWLIST_OFF}' disables the command tunnel and cannot be combined with other entries"
+        )));
+    }
+    for entry in &cfg.tail_allowlist {
+        if !entry.starts_with('/') {
+            return Err(Error::InvalidArgs(format!(
+                "tail_allowlist entries must be absolute paths, got '{entry}'"
+            )));
+        }
+    }
+    if cfg.protocol.schema_version != DEFAULT_PROTOCOL_SCHEMA_VERSION {
+        return Err(Error::InvalidArgs(format!(
+            "protocol.schema_version must be {DEFAULT_PROTOCOL_SCHEMA_VERSION}"
+        )));
+    }
+    if cfg.serial_timeout_ms < MIN_SERIAL_TIMEOUT_MS
+        || cfg.serial_timeout_ms > MAX_SERIAL_TIMEOUT_MS
+    {
+        return Err(Error::InvalidArgs(format!(
+            "serial_timeout_ms must be between {MIN_SERIAL_TIMEOUT_MS} and {MAX_SERIAL_TIMEOUT_MS}"
+        )));
+    }
+    if cfg.negotiation.timeout_ms < MIN_NEGOTIATION_TIMEOUT_MS
+        || cfg.negotiation.timeout_ms > MAX_NEGOTIATION_TIMEOUT_MS
+    {
+        return Err(Error::InvalidArgs(format!(
+            "negotiation.timeout_ms must be between {MIN_NEGOTIATION_TIMEOUT_MS} and {MAX_NEGOTIATION_TIMEOUT_MS}"
+        )));
+    }
+    if cfg.negotiation.resume_grace_ms > MAX_NEGOTIATION_RESUME_GRACE_MS {
+        return Err(Error::InvalidArgs(format!(
+            "negotiation.resume_grace_ms must be between 0 and {MAX_NEGOTIATION_RESUME_GRACE_MS}"
+        )));
+    }
+    if cfg.watchdog.serial_timeout_ms < MIN_WATCHDOG_TIMEOUT_MS
+        || cfg.watchdog.serial_timeout_ms > MAX_WATCHDOG_TIMEOUT_MS
+    {
+        return Err(Error::InvalidArgs(format!(
+            "watchdog.serial_timeout_ms must be between {MIN_WATCHDOG_TIMEOUT_MS} and {MAX_WATCHDOG_TIMEOUT_MS}"
+        )));
+    }
+    if cfg.watchdog.tunnel_timeout_ms < MIN_WATCHDOG_TIMEOUT_MS
+        || cfg.watchdog.tunnel_timeout_ms > MAX_WATCHDOG_TIMEOUT_MS
+    {
+        return Err(Error::InvalidArgs(format!(
+            "watchdog.tunnel_timeout_ms must be between {MIN_WATCHDOG_TIMEOUT_MS} and {MAX_WATCHDOG_TIMEOUT_MS}"
+        )));
+    }
+    if cfg.modem.poll_ms != 0
+        && (cfg.modem.poll_ms < MIN_MODEM_POLL_MS || cfg.modem.poll_ms > MAX_MODEM_POLL_MS)
+    {
+        return Err(Error::InvalidArgs(format!(
+            "modem.poll_ms must be 0 (disabled) or between {MIN_MODEM_POLL_MS} and {MAX_MODEM_POLL_MS}"
+        )));
+    }
+    if cfg.scheduler.remote_weight > MAX_SCHEDULER_WEIGHT
+        || cfg.scheduler.local_weight > MAX_SCHEDULER_WEIGHT
+    {
+        return Err(Error::InvalidArgs(format!(
+            "scheduler weights must be between 0 and {MAX_SCHEDULER_WEIGHT}"
+        )));
+    }
+    if cfg.scheduler.remote_weight == 0 && cfg.scheduler.local_weight == 0 {
+        return Err(Error::InvalidArgs(
+            "scheduler.remote_weight and scheduler.local_weight cannot both be 0".to_string(),
+        ));
+    }
+    if cfg.scheduler.min_display_ms > MAX_SCHEDULER_MIN_DISPLAY_MS {
+        return Err(Error::InvalidArgs(format!(
+            "scheduler.min_display_ms must be at most {MAX_SCHEDULER_MIN_DISPLAY_MS}"
+        )));
+    }
+    if cfg.frame_cache_size > MAX_FRAME_CACHE_SIZE {
+        return Err(Error::InvalidArgs(format!(
+            "frame_cache_size must be between 0 and {MAX_FRAME_CACHE_SIZE}"
+        )));
+    }
+    if cfg.slo.window_ms < MIN_SLO_WINDOW_MS || cfg.slo.window_ms > MAX_SLO_WINDOW_MS {
+        return Err(Error::InvalidArgs(format!(
+            "slo.window_ms must be between {MIN_SLO_WINDOW_MS} and {MAX_SLO_WINDOW_MS}"
+        )));
+    }
+    if [
+        cfg.slo.parse_budget,
+        cfg.slo.reconnect_budget,
+        cfg.slo.crc_budget,
+    ]
+    .iter()
+    .any(|budget| *budget > MAX_SLO_BUDGET)
+    {
+        return Err(Error::InvalidArgs(format!(
+            "slo budgets must be between 0 and {MAX_SLO_BUDGET}"
+        )));
+    }
+    if !cfg.slo.webhook_url.is_empty()
+        && crate::app::slo::parse_webhook_url(&cfg.slo.webhook_url).is_none()
+    {
+        return Err(Error::InvalidArgs(
+            "slo.webhook_url must be an http:// URL such as http://127.0.0.1:8080/incidents".into(),
+        ));
+    }
+    if !(MIN_ANOMALY_WINDOW_MS..=MAX_ANOMALY_WINDOW_MS).contains(&cfg.anomaly.window_ms) {
+        return Err(Error::InvalidArgs(format!(
+            "anomaly.window_ms must be between {MIN_ANOMALY_WINDOW_MS} and {MAX_ANOMALY_WINDOW_MS}"
+        )));
+    }
+    if cfg.anomaly.reconnects > MAX_ANOMALY_RECONNECTS {
+        return Err(Error::InvalidArgs(format!(
+            "anomaly.reconnects must be between 0 and {MAX_ANOMALY_RECONNECTS}"
+        )));
+    }
+    if cfg.anomaly.temp_rise_c > MAX_ANOMALY_TEMP_RISE_C {
+        return Err(Error::InvalidArgs(format!(
+            "anomaly.temp_rise_c must be between 0 and {MAX_ANOMALY_TEMP_RISE_C}"
+        )));
+    }
+    if cfg.serial_tx_rate_percent > MAX_SERIAL_TX_RATE_PERCENT {
+        return Err(Error::InvalidArgs(format!(
+            "serial_tx_rate_percent must be between 0 and {MAX_SERIAL_TX_RATE_PERCENT}"
+        )));
+    }
+    if cfg.arq.ack_timeout_ms < MIN_ARQ_ACK_TIMEOUT_MS
+        || cfg.arq.ack_timeout_ms > MAX_ARQ_ACK_TIMEOUT_MS
+    {
+        return Err(Error::InvalidArgs(format!(
+            "arq.ack_timeout_ms must be between {MIN_ARQ_ACK_TIMEOUT_MS} and {MAX_ARQ_ACK_TIMEOUT_MS}"
+        )));
+    }
+    if cfg.arq.max_retries > MAX_ARQ_RETRIES {
+        return Err(Error::InvalidArgs(format!(
+            "arq.max_retries must be between 0 and {MAX_ARQ_RETRIES}"
+        )));
+    }
+    if cfg.arq.auto_crc_failures < MIN_ARQ_AUTO_CRC_FAILURES
+        || cfg.arq.auto_crc_failures > MAX_ARQ_AUTO_CRC_FAILURES
+    {
+        return Err(Error::InvalidArgs(format!(
+            "arq.auto_crc_failures must be between {MIN_ARQ_AUTO_CRC_FAILURES} and {MAX_ARQ_AUTO_CRC_FAILURES}"
+        )));
+    }
+    for rule in &cfg.annunciators.rules {
+        rule.parse::<crate::app::annunciator::AnnunciatorRule>()
+            .map_err(|e| Error::InvalidArgs(format!("annunciators.rules: {e}")))?;
+    }
+    if cfg.annunciators.bar_hysteresis > MAX_ANNUNCIATOR_BAR_HYSTERESIS {
+        return Err(Error::InvalidArgs(format!(
+            "annunciators.bar_hysteresis must be between 0 and {MAX_ANNUNCIATOR_BAR_HYSTERESIS}"
+        )));
+    }
+    if cfg.annunciators.hold_ms > MAX_ANNUNCIATOR_HOLD_MS {
+        return Err(Error::InvalidArgs(format!(
+            "annunciators.hold_ms must be between 0 and {MAX_ANNUNCIATOR_HOLD_MS}"
+        )));
+    }
+    if cfg.parse_errors.after < MIN_PARSE_ERROR_AFTER
+        || cfg.parse_errors.after > MAX_PARSE_ERROR_AFTER
+    {
+        return Err(Error::InvalidArgs(format!(
+            "parse_errors.after must be between {MIN_PARSE_ERROR_AFTER} and {MAX_PARSE_ERROR_AFTER}"
+        )));
+    }
+    if cfg.parse_errors.display_ms > MAX_PARSE_ERROR_DISPLAY_MS {
+        return Err(Error::InvalidArgs(format!(
+            "parse_errors.display_ms must be between 0 and {MAX_PARSE_ERROR_DISPLAY_MS}"
+        )));
+    }
+    if cfg.replay.window > MAX_REPLAY_WINDOW {
+        return Err(Error::InvalidArgs(format!(
+            "replay.window must be between 0 and {MAX_REPLAY_WINDOW}"
+        )));
+    }
+    if cfg.replay.require_seq && cfg.replay.window == 0 {
+        return Err(Error::InvalidArgs(
+            "replay.require_seq needs a non-zero replay.window".into(),
+        ));
+    }
+    if cfg.render.min_interval_ms < MIN_RENDER_INTERVAL_MS
+        || cfg.render.min_interval_ms > MAX_RENDER_INTERVAL_MS
+    {
+        return Err(Error::InvalidArgs(format!(
+            "render.min_interval_ms must be between {MIN_RENDER_INTERVAL_MS} and {MAX_RENDER_INTERVAL_MS}"
+        )));
+    }
+    if cfg.render.max_interval_ms < cfg.render.min_interval_ms
+        || cfg.render.max_interval_ms > MAX_RENDER_INTERVAL_MS
+    {
+        return Err(Error::InvalidArgs(format!(
+            "render.max_interval_ms must be between render.min_interval_ms and {MAX_RENDER_INTERVAL_MS}"
+        )));
+    }
+    if cfg.render.blink_ms < MIN_RENDER_BLINK_MS || cfg.render.blink_ms > MAX_RENDER_BLINK_MS {
+        return Err(Error::InvalidArgs(format!(
+            "render.blink_ms must be between {MIN_RENDER_BLINK_MS} and {MAX_RENDER_BLINK_MS}"
+        )));
+    }
+    if cfg.render.max_frames_per_sec > MAX_RENDER_MAX_FRAMES_PER_SEC {
+        return Err(Error::InvalidArgs(format!(
+            "render.max_frames_per_sec must be at most {MAX_RENDER_MAX_FRAMES_PER_SEC}"
+        )));
+    }
+    if cfg.render.i2c_write_budget_bytes > MAX_RENDER_I2C_WRITE_BUDGET_BYTES {
+        return Err(Error::InvalidArgs(format!(
+            "render.i2c_write_budget_bytes must be at most {MAX_RENDER_I2C_WRITE_BUDGET_BYTES}"
+        )));
+    }
+    cfg.charmap.build()?;
+    cfg.filters.build()?;
+    if !(MIN_SCROLL_MS..=MAX_ACCESSIBILITY_MIN_SCROLL_MS)
+        .contains(&cfg.accessibility.min_scroll_speed_ms)
+    {
+        return Err(Error::InvalidArgs(format!(
+            "accessibility.min_scroll_speed_ms must be between {MIN_SCROLL_MS} and {MAX_ACCESSIBILITY_MIN_SCROLL_MS}"
+        )));
+    }
+    if !(MIN_PAGE_TIMEOUT_MS..=MAX_ACCESSIBILITY_MIN_PAGE_TIMEOUT_MS)
+        .contains(&cfg.accessibility.min_page_timeout_ms)
+    {
+        return Err(Error::InvalidArgs(format!(
+            "accessibility.min_page_timeout_ms must be between {MIN_PAGE_TIMEOUT_MS} and {MAX_ACCESSIBILITY_MIN_PAGE_TIMEOUT_MS}"
+        )));
+    }
+    if cfg.spool.poll_ms < MIN_SPOOL_POLL_MS || cfg.spool.poll_ms > MAX_SPOOL_POLL_MS {
+        return Err(Error::InvalidArgs(format!(
+            "spool.poll_ms must be between {MIN_SPOOL_POLL_MS} and {MAX_SPOOL_POLL_MS}"
+        )));
+    }
+    let spool_dir = Path::new(&cfg.spool.dir);
+    if !spool_dir.starts_with(CACHE_DIR) || spool_dir == Path::new(CACHE_DIR) {
+        return Err(Error::InvalidArgs(format!(
+            "spool.dir must be a directory under {CACHE_DIR}"
+        )));
+    }
+    validate_baud(cfg.burst.baud)?;
+    if cfg.burst.enabled && cfg.burst.baud <= cfg.baud {
+        return Err(Error::InvalidArgs(
+            "burst.baud must be higher than baud".into(),
+        ));
+    }
+    if cfg.burst.threshold_bytes < MIN_BURST_THRESHOLD_BYTES
+        || cfg.burst.threshold_bytes > MAX_BURST_THRESHOLD_BYTES
+    {
+        return Err(Error::InvalidArgs(format!(
+            "burst.threshold_bytes must be between {MIN_BURST_THRESHOLD_BYTES} and {MAX_BURST_THRESHOLD_BYTES}"
+        )));
+    }
+    if cfg.burst.idle_ms < MIN_BURST_IDLE_MS || cfg.burst.idle_ms > MAX_BURST_IDLE_MS {
+        return Err(Error::InvalidArgs(format!(
+            "burst.idle_ms must be between {MIN_BURST_IDLE_MS} and {MAX_BURST_IDLE_MS}"
+        )));
+    }
+    if cfg.burst.max_errors < MIN_BURST_MAX_ERRORS || cfg.burst.max_errors > MAX_BURST_MAX_ERRORS {
+        return Err(Error::InvalidArgs(format!(
+            "burst.max_errors must be between {MIN_BURST_MAX_ERRORS} and {MAX_BURST_MAX_ERRORS}"
+        )));
+    }
+    if cfg.troubleshoot.after_failures > MAX_TROUBLESHOOT_AFTER_FAILURES {
+        return Err(Error::InvalidArgs(format!(
+            "troubleshoot.after_failures must be between 0 and {MAX_TROUBLESHOOT_AFTER_FAILURES}"
+        )));
     }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Config {
-    pub device: String,
-    pub baud: u32,
-    pub flow_control: FlowControlMode,
-    pub parity: ParityMode,
-    pub stop_bits: StopBitsMode,
-    pub dtr_on_open: DtrBehavior,
-    pub serial_timeout_ms: u64,
-    pub cols: u8,
-    pub rows: u8,
-    pub scroll_speed_ms: u64,
-    pub page_timeout_ms: u64,
-    pub polling_enabled: bool,
-    pub poll_interval_ms: u64,
-    pub button_gpio_pin: Option<u8>,
-    pub pcf8574_addr: Pcf8574Addr,
-    pub display_driver: DisplayDriver,
-    pub lcd_present: bool,
-    pub backoff_initial_ms: u64,
-    pub backoff_max_ms: u64,
-    pub negotiation: NegotiationConfig,
-    pub command_allowlist: Vec<String>,
-    pub protocol: ProtocolConfig,
-    pub watchdog: WatchdogConfig,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            device: DEFAULT_DEVICE.to_string(),
-            baud: DEFAULT_BAUD,
-            flow_control: FlowControlMode::default(),
-            parity: ParityMode::default(),
-            stop_bits: StopBitsMode::default(),
-            dtr_on_open: DtrBehavior::default(),
-            serial_timeout_ms: DEFAULT_SERIAL_TIMEOUT_MS,
-            cols: DEFAULT_COLS,
-            rows: DEFAULT_ROWS,
-            scroll_speed_ms: DEFAULT_SCROLL_MS,
-            page_timeout_ms: DEFAULT_PAGE_TIMEOUT_MS,
-            polling_enabled: DEFAULT_POLLING_ENABLED,
-            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
-            button_gpio_pin: None,
-            pcf8574_addr: DEFAULT_PCF8574_ADDR,
-            display_driver: DEFAULT_DISPLAY_DRIVER,
-            lcd_present: DEFAULT_LCD_PRESENT,
-            backoff_initial_ms: DEFAULT_BACKOFF_INITIAL_MS,
-            backoff_max_ms: DEFAULT_BACKOFF_MAX_MS,
-            negotiation: NegotiationConfig::default(),
-            command_allowlist: Vec::new(),
-            protocol: ProtocolConfig::default(),
-            watchdog: WatchdogConfig::default(),
-        }
+    if cfg.interlock.arm_ms < MIN_INTERLOCK_ARM_MS || cfg.interlock.arm_ms > MAX_INTERLOCK_ARM_MS {
+        return Err(Error::InvalidArgs(format!(
+            "interlock.arm_ms must be between {MIN_INTERLOCK_ARM_MS} and {MAX_INTERLOCK_ARM_MS}"
+        )));
     }
-}
-
-impl Config {
-    pub fn load_or_default() -> Result<Self> {
-        loader::load_or_default()
+    if cfg.interlock.button_presses > MAX_INTERLOCK_BUTTON_PRESSES {
+        return Err(Error::InvalidArgs(format!(
+            "interlock.button_presses must be between 0 and {MAX_INTERLOCK_BUTTON_PRESSES}"
+        )));
     }
-
-    pub fn load_from_path(path: &Path) -> Result<Self> {
-        loader::load_from_path(path)
+    if !cfg.interlock.key.is_empty() && cfg.interlock.key.len() < MIN_INTERLOCK_KEY_LEN {
+        return Err(Error::InvalidArgs(format!(
+            "interlock.key must be at least {MIN_INTERLOCK_KEY_LEN} characters (leave it empty to disable signed arming)"
+        )));
     }
-
-    pub fn save(&self) -> Result<()> {
-        loader::save(self)
+    validate_input(cfg)?;
+    validate_lcd_gpio(cfg)?;
+    validate_displays(cfg)?;
+    validate_zones(cfg)?;
+    validate_relay(cfg)?;
+    validate_backlight(&cfg.backlight)?;
+    let control_socket = Path::new(&cfg.control.socket);
+    if !control_socket.starts_with(CACHE_DIR) || control_socket == Path::new(CACHE_DIR) {
+        return Err(Error::InvalidArgs(format!(
+            "control.socket must be a path under {CACHE_DIR}"
+        )));
     }
-
-    pub fn save_to_path(&self, path: &Path) -> Result<()> {
-        loader::save_to_path(self, path)
+    if cfg.metrics.listen.parse::<std::net::SocketAddr>().is_err() {
+        return Err(Error::InvalidArgs(
+            "metrics.listen must be an address:port such as 0.0.0.0:9464".into(),
+        ));
     }
-
-    #[allow(dead_code)]
-    fn parse(raw: &str) -> Result<Self> {
-        loader::parse(raw)
+    if !(MIN_BADGES_WIDTH..=MAX_BADGES_WIDTH).contains(&cfg.badges.width) {
+        return Err(Error::InvalidArgs(format!(
+            "badges.width must be between {MIN_BADGES_WIDTH} and {MAX_BADGES_WIDTH}"
+        )));
     }
-}
-
-fn parse_pcf_addr(raw: &str) -> std::result::Result<Pcf8574Addr, String> {
-    if raw.eq_ignore_ascii_case("auto") {
-        return Ok(Pcf8574Addr::Auto);
+    for (name, list) in [
+        ("peer_defaults.allow", &cfg.peer_defaults.allow),
+        ("peer_defaults.deny", &cfg.peer_defaults.deny),
+    ] {
+        if let Some(unknown) = list
+            .iter()
+            .find(|entry| *entry != "*" && !PEER_DEFAULTS_KEYS.contains(&entry.as_str()))
+        {
+            return Err(Error::InvalidArgs(format!(
+                "{name} has unknown key '{unknown}' (expected \"*\" or one of {})",
+                PEER_DEFAULTS_KEYS.join(", ")
+            )));
+        }
     }
-    let cleaned = raw.trim_start_matches("0x");
-    let value = u8::from_str_radix(cleaned, 16)
-        .or_else(|_| raw.parse::<u8>())
-        .map_err(|_| "expected 'auto' or a hex/decimal address (e.g., 0x27)".to_string())?;
-    Ok(Pcf8574Addr::Addr(value))
-}
-
-pub(crate) fn validate(cfg: &Config) -> Result<()> {
-    validate_baud(cfg.baud)?;
-    if cfg.cols < MIN_COLS || cfg.cols > MAX_COLS {
+    if cfg.logging.max_bytes != 0 && cfg.logging.max_bytes < MIN_LOGGING_MAX_BYTES {
         return Err(Error::InvalidArgs(format!(
-            "cols must be between {MIN_COLS} and {MAX_COLS}"
+            "logging.max_bytes must be 0 (never rotate) or at least {MIN_LOGGING_MAX_BYTES}"
         )));
     }
-    if cfg.rows < MIN_ROWS || cfg.rows > MAX_ROWS {
+    if !(1..=MAX_LOGGING_KEEP).contains(&cfg.logging.keep) {
         return Err(Error::InvalidArgs(format!(
-            "rows must be between {MIN_ROWS} and {MAX_ROWS}"
+            "logging.keep must be between 1 and {MAX_LOGGING_KEEP}"
         )));
     }
-    if cfg.scroll_speed_ms < MIN_SCROLL_MS {
+    if parse_time_of_day(&cfg.log_compaction.at).is_none() {
+        return Err(Error::InvalidArgs("log_compaction.at must be HH:MM".into()));
+    }
+    if !(1..=MAX_LOG_COMPACTION_KEEP_DAYS).contains(&cfg.log_compaction.keep_days) {
         return Err(Error::InvalidArgs(format!(
-            "scroll_speed_ms must be at least {MIN_SCROLL_MS}"
+            "log_compaction.keep_days must be between 1 and {MAX_LOG_COMPACTION_KEEP_DAYS}"
         )));
     }
-    if cfg.page_timeout_ms < MIN_PAGE_TIMEOUT_MS {
+    validate_tunnel_auth(&cfg.tunnel_auth)?;
+    if !cfg.negotiation.install_id.is_empty()
+        && !identity::is_install_id(&cfg.negotiation.install_id)
+    {
+        return Err(Error::InvalidArgs(
+            "negotiation.install_id must be a UUID (leave it empty to generate one)".into(),
+        ));
+    }
+    if !cfg.negotiation.psk.is_empty()
+        && cfg.negotiation.psk.chars().count() < MIN_NEGOTIATION_PSK_CHARS
+    {
         return Err(Error::InvalidArgs(format!(
-            "page_timeout_ms must be at least {MIN_PAGE_TIMEOUT_MS}"
+            "negotiation.psk must be at least {MIN_NEGOTIATION_PSK_CHARS} characters (leave it empty to disable link encryption)"
         )));
     }
-    if cfg.poll_interval_ms < MIN_POLL_INTERVAL_MS || cfg.poll_interval_ms > MAX_POLL_INTERVAL_MS {
+    for (name, raw) in [
+        ("poll_overlay.line1", &cfg.poll_overlay.line1),
+        ("poll_overlay.line2", &cfg.poll_overlay.line2),
+    ] {
+        if raw.is_empty() {
+            continue;
+        }
+        let template = MetricTemplate::parse(raw)
+            .map_err(|err| Error::InvalidArgs(format!("invalid {name}: {err}")))?;
+        let width = template.max_width();
+        if width > cfg.cols as usize {
+            return Err(Error::InvalidArgs(format!(
+                "{name} needs up to {width} columns but the display has {}",
+                cfg.cols
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn validate_displays(cfg: &Config) -> Result<()> {
+    if cfg.displays.is_empty() {
+        return Ok(());
+    }
+    if cfg.displays.len() > MAX_EXTRA_DISPLAYS {
         return Err(Error::InvalidArgs(format!(
-            "poll_interval_ms must be between {MIN_POLL_INTERVAL_MS} and {MAX_POLL_INTERVAL_MS}"
+            "at most {MAX_EXTRA_DISPLAYS} [[displays]] sections are supported"
         )));
     }
-    for entry in &cfg.command_allowlist {
-        if entry.trim().is_empty() {
+    if cfg.display_driver == DisplayDriver::Gpio {
+        return Err(Error::InvalidArgs(
+            "[[displays]] needs an I2C display_driver, not \"gpio\"".into(),
+        ));
+    }
+    let mut taken: Vec<u8> = match cfg.pcf8574_addr {
+        Pcf8574Addr::Addr(addr) => vec![addr],
+        Pcf8574Addr::Auto => Vec::new(),
+    };
+    for display in &cfg.displays {
+        let Pcf8574Addr::Addr(addr) = display.addr else {
             return Err(Error::InvalidArgs(
-                "command_allowlist entries must be non-empty".to_string(),
+                "displays.addr must be an address such as 0x26, not auto".into(),
             ));
+        };
+        if taken.contains(&addr) {
+            return Err(Error::InvalidArgs(format!(
+                "displays.addr {addr:#04x} is already used by another display"
+            )));
+        }
+        taken.push(addr);
+        if !(MIN_COLS..=MAX_COLS).contains(&display.cols) {
+            return Err(Error::InvalidArgs(format!(
+                "displays.cols must be between {MIN_COLS} and {MAX_COLS}"
+            )));
+        }
+        if !(MIN_ROWS..=MAX_ROWS).contains(&display.rows) {
+            return Err(Error::InvalidArgs(format!(
+                "displays.rows must be between {MIN_ROWS} and {MAX_ROWS}"
+            )));
+        }
+        if cfg.display_driver.is_oled() && display.cols > ssd1306::MAX_COLS {
+            return Err(Error::InvalidArgs(format!(
+                "displays.cols must be at most {} with display_driver = \"{}\"",
+                ssd1306::MAX_COLS,
+                cfg.display_driver
+            )));
+        }
+        if display.role == DisplayRole::Polling && !cfg.polling_enabled {
+            return Err(Error::InvalidArgs(format!(
+                "the display at {addr:#04x} has role = \"polling\", which needs polling_enabled = true"
+            )));
         }
     }
-    if cfg.protocol.schema_version != DEFAULT_PROTOCOL_SCHEMA_VERSION {
+    Ok(())
+}
+
+fn validate_zones(cfg: &Config) -> Result<()> {
+    if cfg.zones.len() > MAX_ZONES {
         return Err(Error::InvalidArgs(format!(
-            "protocol.schema_version must be {DEFAULT_PROTOCOL_SCHEMA_VERSION}"
+            "at most {MAX_ZONES} [[zones]] sections are supported"
         )));
     }
-    if cfg.serial_timeout_ms < MIN_SERIAL_TIMEOUT_MS
-        || cfg.serial_timeout_ms > MAX_SERIAL_TIMEOUT_MS
-    {
+    for (idx, zone) in cfg.zones.iter().enumerate() {
+        let name = &zone.name;
+        if !is_zone_name(name) {
+            return Err(Error::InvalidArgs(format!(
+                "zones.name '{name}' must be 1-{MAX_TARGET_LENGTH} lowercase letters, digits, '-' or '_'"
+            )));
+        }
+        if cfg.zones[..idx].iter().any(|other| &other.name == name) {
+            return Err(Error::InvalidArgs(format!(
+                "zones.name '{name}' is used twice"
+            )));
+        }
+        let rows = match zone.display {
+            None => cfg.rows,
+            Some(addr) => {
+                let display = cfg
+                    .displays
+                    .iter()
+                    .find(|display| display.addr == Pcf8574Addr::Addr(addr))
+                    .ok_or_else(|| {
+                        Error::InvalidArgs(format!(
+                            "zone '{name}' is on display {addr:#04x}, which has no [[displays]] section"
+                        ))
+                    })?;
+                if display.role != DisplayRole::Zones {
+                    return Err(Error::InvalidArgs(format!(
+                        "zone '{name}' needs the display at {addr:#04x} to have role = \"zones\""
+                    )));
+                }
+                display.rows
+            }
+        };
+        let end = zone.row as u16 + zone.rows as u16;
+        if zone.rows == 0 || end > rows as u16 {
+            return Err(Error::InvalidArgs(format!(
+                "zone '{name}' needs rows {}-{} but its display has {rows}",
+                zone.row,
+                end.saturating_sub(1)
+            )));
+        }
+        if let Some(other) = cfg.zones[..idx].iter().find(|other| {
+            other.display == zone.display
+                && zone.row < other.row + other.rows
+                && other.row < zone.row + zone.rows
+        }) {
+            return Err(Error::InvalidArgs(format!(
+                "zones '{}' and '{name}' overlap",
+                other.name
+            )));
+        }
+    }
+    let main_rows: u16 = cfg
+        .zones
+        .iter()
+        .filter(|zone| zone.display.is_none())
+        .map(|zone| zone.rows as u16)
+        .sum();
+    if main_rows > 0 && main_rows >= cfg.rows as u16 {
+        return Err(Error::InvalidArgs(
+            "zones on the main display must leave at least one row for the page rotation".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_relay(cfg: &Config) -> Result<()> {
+    let relay = &cfg.relay;
+    if relay.baud < MIN_BAUD {
         return Err(Error::InvalidArgs(format!(
-            "serial_timeout_ms must be between {MIN_SERIAL_TIMEOUT_MS} and {MAX_SERIAL_TIMEOUT_MS}"
+            "relay.baud must be at least {MIN_BAUD}"
         )));
     }
-    if cfg.negotiation.timeout_ms < MIN_NEGOTIATION_TIMEOUT_MS
-        || cfg.negotiation.timeout_ms > MAX_NEGOTIATION_TIMEOUT_MS
-    {
+    relay.build()?;
+    if !relay.enabled {
+        return Ok(());
+    }
+    if relay.device.is_empty() {
+        return Err(Error::InvalidArgs(
+            "relay.device must be set when relay.enabled = true".into(),
+        ));
+    }
+    if relay.device == cfg.device {
+        return Err(Error::InvalidArgs(
+            "relay.device must be a different port from device".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_input(cfg: &Config) -> Result<()> {
+    let input = &cfg.input;
+    if input.encoder_a_pin.is_some() != input.encoder_b_pin.is_some() {
+        return Err(Error::InvalidArgs(
+            "input.encoder_a_pin and input.encoder_b_pin must be set together".into(),
+        ));
+    }
+    if input.encoder_button_pin.is_some() && input.encoder_a_pin.is_none() {
+        return Err(Error::InvalidArgs(
+            "input.encoder_button_pin needs input.encoder_a_pin and input.encoder_b_pin".into(),
+        ));
+    }
+    let pins = [
+        ("button_gpio_pin", cfg.button_gpio_pin),
+        ("input.encoder_a_pin", input.encoder_a_pin),
+        ("input.encoder_b_pin", input.encoder_b_pin),
+        ("input.encoder_button_pin", input.encoder_button_pin),
+    ];
+    for (idx, (name, pin)) in pins.iter().enumerate() {
+        let Some(pin) = pin else { continue };
+        if let Some((other, _)) = pins[idx + 1..]
+            .iter()
+            .find(|(_, other)| *other == Some(*pin))
+        {
+            return Err(Error::InvalidArgs(format!(
+                "{name} and {other} both use GPIO {pin}"
+            )));
+        }
+    }
+    if !(MIN_INPUT_LONG_PRESS_MS..=MAX_INPUT_LONG_PRESS_MS).contains(&input.long_press_ms) {
         return Err(Error::InvalidArgs(format!(
-            "negotiation.timeout_ms must be between {MIN_NEGOTIATION_TIMEOUT_MS} and {MAX_NEGOTIATION_TIMEOUT_MS}"
+            "input.long_press_ms must be between {MIN_INPUT_LONG_PRESS_MS} and {MAX_INPUT_LONG_PRESS_MS}"
         )));
     }
-    if cfg.watchdog.serial_timeout_ms < MIN_WATCHDOG_TIMEOUT_MS
-        || cfg.watchdog.serial_timeout_ms > MAX_WATCHDOG_TIMEOUT_MS
+    Ok(())
+}
+
+fn validate_lcd_gpio(cfg: &Config) -> Result<()> {
+    let pins = cfg.lcd_gpio.pins();
+    if cfg.display_driver == DisplayDriver::Gpio {
+        if let Some((name, _)) = pins[..6].iter().find(|(_, pin)| pin.is_none()) {
+            return Err(Error::InvalidArgs(format!(
+                "{name} must be set with display_driver = \"gpio\""
+            )));
+        }
+    }
+    let input = [
+        ("button_gpio_pin", cfg.button_gpio_pin),
+        ("input.encoder_a_pin", cfg.input.encoder_a_pin),
+        ("input.encoder_b_pin", cfg.input.encoder_b_pin),
+        ("input.encoder_button_pin", cfg.input.encoder_button_pin),
+    ];
+    for (idx, (name, pin)) in pins.iter().enumerate() {
+        let Some(pin) = pin else { continue };
+        if let Some((other, _)) = pins[idx + 1..]
+            .iter()
+            .chain(&input)
+            .find(|(_, other)| *other == Some(*pin))
+        {
+            return Err(Error::InvalidArgs(format!(
+                "{name} and {other} both use GPIO {pin}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn validate_tunnel_auth(auth: &TunnelAuthConfig) -> Result<()> {
+    if !(MIN_TUNNEL_AUTH_PERIOD_SECS..=MAX_TUNNEL_AUTH_PERIOD_SECS).contains(&auth.period_secs) {
+        return Err(Error::InvalidArgs(format!(
+            "tunnel_auth.period_secs must be between {MIN_TUNNEL_AUTH_PERIOD_SECS} and {MAX_TUNNEL_AUTH_PERIOD_SECS}"
+        )));
+    }
+    if auth.skew_steps > MAX_TUNNEL_AUTH_SKEW_STEPS {
+        return Err(Error::InvalidArgs(format!(
+            "tunnel_auth.skew_steps must be at most {MAX_TUNNEL_AUTH_SKEW_STEPS}"
+        )));
+    }
+    if auth.backend != TunnelAuthBackend::None {
+        match crate::app::tunnel_auth::decode_base32(&auth.secret) {
+            Some(secret) if secret.len() >= MIN_TUNNEL_AUTH_SECRET_BYTES => {}
+            Some(_) => {
+                return Err(Error::InvalidArgs(format!(
+                    "tunnel_auth.secret must hold at least {MIN_TUNNEL_AUTH_SECRET_BYTES} bytes (26 base32 characters)"
+                )));
+            }
+            None => {
+                return Err(Error::InvalidArgs(
+                    "tunnel_auth.secret must be base32 (A-Z, 2-7)".into(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_backlight(backlight: &BacklightConfig) -> Result<()> {
+    if backlight.idle_timeout_ms != 0
+        && !(MIN_BACKLIGHT_IDLE_TIMEOUT_MS..=MAX_BACKLIGHT_IDLE_TIMEOUT_MS)
+            .contains(&backlight.idle_timeout_ms)
     {
         return Err(Error::InvalidArgs(format!(
-            "watchdog.serial_timeout_ms must be between {MIN_WATCHDOG_TIMEOUT_MS} and {MAX_WATCHDOG_TIMEOUT_MS}"
+            "backlight.idle_timeout_ms must be 0 (never) or between {MIN_BACKLIGHT_IDLE_TIMEOUT_MS} and {MAX_BACKLIGHT_IDLE_TIMEOUT_MS}"
         )));
     }
-    if cfg.watchdog.tunnel_timeout_ms < MIN_WATCHDOG_TIMEOUT_MS
-        || cfg.watchdog.tunnel_timeout_ms > MAX_WATCHDOG_TIMEOUT_MS
+    if backlight
+        .pwm_channel
+        .is_some_and(|channel| channel > MAX_BACKLIGHT_PWM_CHANNEL)
     {
         return Err(Error::InvalidArgs(format!(
-            "watchdog.tunnel_timeout_ms must be between {MIN_WATCHDOG_TIMEOUT_MS} and {MAX_WATCHDOG_TIMEOUT_MS}"
+            "backlight.pwm_channel must be between 0 and {MAX_BACKLIGHT_PWM_CHANNEL}"
         )));
     }
-    Ok(())
+    if backlight.night_start.is_empty() && backlight.night_end.is_empty() {
+        return Ok(());
+    }
+    let start = parse_time_of_day(&backlight.night_start);
+    let end = parse_time_of_day(&backlight.night_end);
+    match (start, end) {
+        (Some(start), Some(end)) if start != end => Ok(()),
+        (Some(_), Some(_)) => Err(Error::InvalidArgs(
+            "backlight.night_start and backlight.night_end must differ".into(),
+        )),
+        _ => Err(Error::InvalidArgs(
+            "backlight.night_start and backlight.night_end must both be HH:MM (or both empty)"
+                .into(),
+        )),
+    }
 }
 
 pub fn validate_baud(baud: u32) -> Result<()> {
@@ -324,6 +2042,13 @@ fn format_pcf_addr(addr: &Pcf8574Addr) -> String {
     }
 }
 
+fn format_zone_display(display: Option<u8>) -> String {
+    match display {
+        None => "\"main\"".into(),
+        Some(addr) => format!("{addr:#04x}"),
+    }
+}
+
 fn format_display_driver(driver: &DisplayDriver) -> String {
     format!("\"{driver}\"")
 }
@@ -418,6 +2143,170 @@ mod tests {
         let _ = fs::remove_file(path);
     }
 
+    #[test]
+    fn rejects_poll_overlay_wider_than_display() {
+        let _guard = lock_env();
+        let path = temp_path("overlay");
+        fs::write(
+            &path,
+            "cols = 16\n[poll_overlay]\nline1 = \"CPU{cpu:3}% LOAD{load15:6.2}\"\n",
+        )
+        .unwrap();
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("needs up to 18 columns"));
+        fs::write(&path, "[poll_overlay]\nline2 = \"{gpu}\"\n").unwrap();
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("unknown metric 'gpu'"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn oled_drivers_cap_the_column_count() {
+        let _guard = lock_env();
+        let path = temp_path("oled");
+        fs::write(&path, "display_driver = \"sh1106\"\ncols = 20\nrows = 4\n").unwrap();
+        let cfg = Config::load_from_path(&path).unwrap();
+        assert_eq!(cfg.display_driver, DisplayDriver::Sh1106);
+        fs::write(&path, "display_driver = \"ssd1306\"\ncols = 24\n").unwrap();
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("cols must be at most 21"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn gpio_driver_needs_every_data_pin_and_no_shared_pins() {
+        let _guard = lock_env();
+        let path = temp_path("lcd_gpio");
+        let pins = "[lcd.gpio]\nrs_pin = 25\ne_pin = 24\nd4_pin = 23\nd5_pin = 17\nd6_pin = 18\n";
+        fs::write(
+            &path,
+            format!("display_driver = \"gpio\"\n\n{pins}d7_pin = 22\n"),
+        )
+        .unwrap();
+        let cfg = Config::load_from_path(&path).unwrap();
+        assert_eq!(cfg.display_driver, DisplayDriver::Gpio);
+        assert_eq!(cfg.lcd_gpio.d7_pin, Some(22));
+        fs::write(&path, format!("display_driver = \"gpio\"\n\n{pins}")).unwrap();
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("lcd.gpio.d7_pin must be set"));
+        fs::write(
+            &path,
+            format!("button_gpio_pin = 17\ndisplay_driver = \"gpio\"\n\n{pins}d7_pin = 22\n"),
+        )
+        .unwrap();
+        let err = Config::load_from_path(&path).unwrap_err();
+        assert!(format!("{err}").contains("lcd.gpio.d5_pin and button_gpio_pin both use GPIO 17"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn extra_displays_need_their_own_address() {
+        let _guard = lock_env();
+        let path = temp_path("displays");
+        let raw = "pcf8574_addr = 0x27\npolling_enabled = true\n\n[[displays]]\naddr = 0x26\nrole = \"polling\"\n\n[[displays]]\naddr = 0x3f\ncols = 20\nrows = 4\n";
+        fs::write(&path, raw).unwrap();
+        let cfg = Config::load_from_path(&path).unwrap();
+        assert_eq!(cfg.displays.len(), 2);
+        assert_eq!(cfg.displays[0].role, DisplayRole::Polling);
+        assert_eq!((cfg.displays[0].cols, cfg.displays[0].rows), (16, 2));
+        assert_eq!(cfg.displays[1].addr, Pcf8574Addr::Addr(0x3f));
+        assert_eq!(cfg.displays[1].role, DisplayRole::Mirror);
+        for (raw, message) in [
+            (
+                "pcf8574_addr = 0x27\n\n[[displays]]\naddr = 0x27\n",
+                "0x27 is already used",
+            ),
+            ("[[displays]]\naddr = auto\n", "not auto"),
+            (
+                "[[displays]]\naddr = 0x26\nrole = \"polling\"\n",
+                "needs polling_enabled",
+            ),
+        ] {
+            fs::write(&path, raw).unwrap();
+            let err = Config::load_from_path(&path).unwrap_err();
+            assert!(format!("{err}").contains(message), "{raw}: {err}");
+        }
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn zones_must_fit_their_display_without_overlapping() {
+        let _guard = lock_env();
+        let path = temp_path("zones");
+        let raw = "rows = 4
+
+[[displays]]
+addr = 0x26
+role = \"zones\"
+
+[[zones]]
+name = \"alerts\"
+row = 3
+
+[[zones]]
+name = \"weather\"
+display = 0x26
+rows = 2
+";
+        fs::write(&path, raw).unwrap();
+        let cfg = Config::load_from_path(&path).unwrap();
+        assert_eq!(cfg.zones.len(), 2);
+        assert_eq!((cfg.zones[0].display, cfg.zones[0].row), (None, 3));
+        assert_eq!((cfg.zones[1].display, cfg.zones[1].rows), (Some(0x26), 2));
+        for (raw, message) in [
+            (
+                "rows = 2
+
+[[zones]]
+name = \"a\"
+row = 1
+rows = 2
+",
+                "its display has 2",
+            ),
+            (
+                "rows = 2
+
+[[zones]]
+name = \"a\"
+rows = 2
+",
+                "leave at least one row",
+            ),
+            (
+                "rows = 4
+
+[[zones]]
+name = \"a\"
+rows = 2
+
+[[zones]]
+name = \"b\"
+row = 1
+",
+                "overlap",
+            ),
+            (
+                "[[zones]]
+name = \"a\"
+display = 0x26
+",
+                "no [[displays]] section",
+            ),
+            (
+                "[[zones]]
+name = \"Alerts\"
+",
+                "lowercase",
+            ),
+        ] {
+            fs::write(&path, raw).unwrap();
+            let err = Config::load_from_path(&path).unwrap_err();
+            assert!(format!("{err}").contains(message), "{raw}: {err}");
+        }
+        let _ = fs::remove_file(path);
+    }
+
     #[test]
     fn saves_and_loads_round_trip() {
         let _guard = lock_env();
@@ -430,22 +2319,62 @@ mod tests {
             stop_bits: StopBitsMode::Two,
             dtr_on_open: DtrBehavior::Assert,
             serial_timeout_ms: 750,
+            serial_read_min_bytes: DEFAULT_SERIAL_READ_MIN_BYTES,
+            serial_read_gap_ds: DEFAULT_SERIAL_READ_GAP_DS,
+            serial_low_latency: DEFAULT_SERIAL_LOW_LATENCY,
+            serial_tx_rate_percent: DEFAULT_SERIAL_TX_RATE_PERCENT,
+            autobaud: true,
             cols: 20,
             rows: 4,
             scroll_speed_ms: 250,
             page_timeout_ms: 4000,
+            frame_cache_size: DEFAULT_FRAME_CACHE_SIZE,
             polling_enabled: true,
             poll_interval_ms: 2000,
             button_gpio_pin: Some(22),
             pcf8574_addr: Pcf8574Addr::Auto,
             display_driver: DisplayDriver::InTree,
+            displays: Vec::new(),
+            zones: Vec::new(),
             backoff_initial_ms: DEFAULT_BACKOFF_INITIAL_MS,
             backoff_max_ms: DEFAULT_BACKOFF_MAX_MS,
             negotiation: NegotiationConfig::default(),
             command_allowlist: Vec::new(),
+            tail_allowlist: Vec::new(),
             protocol: ProtocolConfig::default(),
             lcd_present: DEFAULT_LCD_PRESENT,
             watchdog: WatchdogConfig::default(),
+            modem: ModemConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            slo: SloConfig::default(),
+            anomaly: AnomalyConfig::default(),
+            arq: ArqSettings {
+                mode: crate::serial::arq::ArqMode::On,
+                ..ArqSettings::default()
+            },
+            annunciators: AnnunciatorConfig::default(),
+            parse_errors: ParseErrorConfig::default(),
+            replay: ReplayConfig::default(),
+            render: RenderConfig::default(),
+            accessibility: AccessibilityConfig::default(),
+            charmap: CharmapConfig::default(),
+            filters: FilterConfig::default(),
+            spool: SpoolConfig::default(),
+            burst: BurstConfig::default(),
+            troubleshoot: TroubleshootConfig::default(),
+            poll_overlay: PollOverlayConfig::default(),
+            interlock: InterlockConfig::default(),
+            input: InputConfig::default(),
+            lcd_gpio: LcdGpioConfig::default(),
+            backlight: BacklightConfig::default(),
+            control: ControlConfig::default(),
+            metrics: MetricsConfig::default(),
+            badges: BadgesConfig::default(),
+            peer_defaults: PeerDefaultsConfig::default(),
+            logging: LoggingConfig::default(),
+            log_compaction: LogCompactionConfig::default(),
+            tunnel_auth: TunnelAuthConfig::default(),
+            relay: RelayConfig::default(),
         };
         cfg.save_to_path(&path).unwrap();
         let loaded = Config::load_from_path(&path).unwrap();
@@ -0,0 +1,1581 @@
+//! Declarative registry of every supported config key.
+//!
+//! The loader derives its required-key list from this table and `lifelinetty config schema`
+//! prints it, so external tooling can validate config files without running the daemon.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::*;
+
+/// Value type accepted for a config key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyType {
+    String,
+    Integer,
+    Bool,
+    Enum,
+    StringArray,
+    InlineTable,
+}
+
+/// Metadata for one config key.
+#[derive(Clone, Copy)]
+pub struct KeySpec {
+    /// Section name (`[section]`), or `None` for top-level keys.
+    pub section: Option<&'static str>,
+    pub key: &'static str,
+    pub kind: KeyType,
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+    pub allowed: &'static [&'static str],
+    /// Missing required keys trigger a backfill save of the defaults.
+    pub required: bool,
+    pub description: &'static str,
+    default: fn(&Config) -> Value,
+}
+
+impl KeySpec {
+    /// Dotted key as matched by the loader (`section.key` or `key`).
+    pub fn path(&self) -> String {
+        match self.section {
+            Some(section) => format!("{section}.{}", self.key),
+            None => self.key.to_string(),
+        }
+    }
+
+    pub fn default_value(&self) -> Value {
+        self.value(&Config::default())
+    }
+
+    /// This key's value in `config`.
+    pub fn value(&self, config: &Config) -> Value {
+        (self.default)(config)
+    }
+}
+
+#[derive(Serialize)]
+struct KeySpecEntry<'a> {
+    path: String,
+    section: Option<&'a str>,
+    key: &'a str,
+    #[serde(rename = "type")]
+    kind: KeyType,
+    default: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<u64>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    allowed: &'a [&'a str],
+    required: bool,
+    description: &'a str,
+}
+
+const fn key(
+    section: Option<&'static str>,
+    key: &'static str,
+    kind: KeyType,
+    default: fn(&Config) -> Value,
+    description: &'static str,
+) -> KeySpec {
+    KeySpec {
+        section,
+        key,
+        kind,
+        min: None,
+        max: None,
+        allowed: &[],
+        required: true,
+        description,
+        default,
+    }
+}
+
+const fn ranged(mut spec: KeySpec, min: u64, max: u64) -> KeySpec {
+    spec.min = Some(min);
+    spec.max = Some(max);
+    spec
+}
+
+const fn at_least(mut spec: KeySpec, min: u64) -> KeySpec {
+    spec.min = Some(min);
+    spec
+}
+
+const fn one_of(mut spec: KeySpec, allowed: &'static [&'static str]) -> KeySpec {
+    spec.allowed = allowed;
+    spec
+}
+
+const fn optional(mut spec: KeySpec) -> KeySpec {
+    spec.required = false;
+    spec
+}
+
+const PEER_DEFAULTS_ALLOWED: &[&str] = &[
+    "*",
+    "scroll_speed_ms",
+    "page_timeout_ms",
+    "blink_ms",
+    "local_weight",
+];
+
+/// Every key the loader accepts, in the order `save_to_path` writes them.
+pub static KEYS: &[KeySpec] = &[
+    ranged(
+        key(
+            None,
+            "config_version",
+            KeyType::Integer,
+            |_| json!(migrate::CONFIG_VERSION),
+            "Config file format version; older files are upgraded on load",
+        ),
+        0,
+        migrate::CONFIG_VERSION as u64,
+    ),
+    key(
+        None,
+        "device",
+        KeyType::String,
+        |c| json!(c.device),
+        "Serial device path",
+    ),
+    at_least(
+        key(
+            None,
+            "baud",
+            KeyType::Integer,
+            |c| json!(c.baud),
+            "Serial baud rate",
+        ),
+        MIN_BAUD as u64,
+    ),
+    one_of(
+        key(
+            None,
+            "flow_control",
+            KeyType::Enum,
+            |c| json!(c.flow_control.to_string()),
+            "UART flow control",
+        ),
+        &["none", "software", "hardware"],
+    ),
+    one_of(
+        key(
+            None,
+            "parity",
+            KeyType::Enum,
+            |c| json!(c.parity.to_string()),
+            "UART parity",
+        ),
+        &["none", "odd", "even"],
+    ),
+    one_of(
+        key(
+            None,
+            "stop_bits",
+            KeyType::Enum,
+            |c| json!(c.stop_bits.to_string()),
+            "UART stop bits",
+        ),
+        &["1", "2"],
+    ),
+    one_of(
+        key(
+            None,
+            "dtr_on_open",
+            KeyType::Enum,
+            |c| json!(c.dtr_on_open.to_string()),
+            "DTR state applied when the port opens",
+        ),
+        &["preserve", "on", "off"],
+    ),
+    ranged(
+        key(
+            None,
+            "serial_timeout_ms",
+            KeyType::Integer,
+            |c| json!(c.serial_timeout_ms),
+            "Serial read timeout in milliseconds",
+        ),
+        MIN_SERIAL_TIMEOUT_MS,
+        MAX_SERIAL_TIMEOUT_MS,
+    ),
+    ranged(
+        key(
+            None,
+            "serial_read_min_bytes",
+            KeyType::Integer,
+            |c| json!(c.serial_read_min_bytes),
+            "VMIN: bytes a tty read waits for (larger favours throughput)",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            None,
+            "serial_read_gap_ds",
+            KeyType::Integer,
+            |c| json!(c.serial_read_gap_ds),
+            "VTIME: inter-byte gap in tenths of a second that ends a read",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    key(
+        None,
+        "serial_low_latency",
+        KeyType::Bool,
+        |c| json!(c.serial_low_latency),
+        "Set the ftdi_sio latency timer to 1 ms for interactive sessions",
+    ),
+    ranged(
+        key(
+            None,
+            "serial_tx_rate_percent",
+            KeyType::Integer,
+            |c| json!(c.serial_tx_rate_percent),
+            "Outbound cap as a percent of the line's byte rate, shared fairly per channel; 0 disables",
+        ),
+        0,
+        MAX_SERIAL_TX_RATE_PERCENT as u64,
+    ),
+    key(
+        None,
+        "autobaud",
+        KeyType::Bool,
+        |c| json!(c.autobaud),
+        "Probe candidate rates at startup and switch to the detected baud",
+    ),
+    ranged(
+        key(
+            None,
+            "cols",
+            KeyType::Integer,
+            |c| json!(c.cols),
+            "LCD columns",
+        ),
+        MIN_COLS as u64,
+        MAX_COLS as u64,
+    ),
+    ranged(
+        key(
+            None,
+            "rows",
+            KeyType::Integer,
+            |c| json!(c.rows),
+            "LCD rows",
+        ),
+        MIN_ROWS as u64,
+        MAX_ROWS as u64,
+    ),
+    key(
+        None,
+        "lcd_present",
+        KeyType::Bool,
+        |c| json!(c.lcd_present),
+        "Drive real LCD hardware (false renders to a stub)",
+    ),
+    at_least(
+        key(
+            None,
+            "scroll_speed_ms",
+            KeyType::Integer,
+            |c| json!(c.scroll_speed_ms),
+            "Default scroll step interval",
+        ),
+        MIN_SCROLL_MS,
+    ),
+    at_least(
+        key(
+            None,
+            "page_timeout_ms",
+            KeyType::Integer,
+            |c| json!(c.page_timeout_ms),
+            "Default page rotation interval",
+        ),
+        MIN_PAGE_TIMEOUT_MS,
+    ),
+    ranged(
+        key(
+            None,
+            "frame_cache_size",
+            KeyType::Integer,
+            |c| json!(c.frame_cache_size),
+            "Recent frame checksums kept to drop replays after reconnects; 0 disables",
+        ),
+        0,
+        MAX_FRAME_CACHE_SIZE as u64,
+    ),
+    key(
+        None,
+        "polling_enabled",
+        KeyType::Bool,
+        |c| json!(c.polling_enabled),
+        "Enable the hardware polling overlay",
+    ),
+    ranged(
+        key(
+            None,
+            "poll_interval_ms",
+            KeyType::Integer,
+            |c| json!(c.poll_interval_ms),
+            "Hardware polling interval",
+        ),
+        MIN_POLL_INTERVAL_MS,
+        MAX_POLL_INTERVAL_MS,
+    ),
+    ranged(
+        key(
+            None,
+            "button_gpio_pin",
+            KeyType::Integer,
+            |c| json!(c.button_gpio_pin),
+            "GPIO pin for the page button, or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    key(
+        None,
+        "pcf8574_addr",
+        KeyType::String,
+        |c| json!(format_pcf_addr(&c.pcf8574_addr).trim_matches('"')),
+        "PCF8574 I2C address (\"auto\" or hex such as 0x27)",
+    ),
+    one_of(
+        key(
+            None,
+            "display_driver",
+            KeyType::Enum,
+            |c| json!(c.display_driver.to_string()),
+            "LCD driver backend",
+        ),
+        &["auto", "in-tree", "hd44780-driver", "ssd1306", "sh1106", "gpio"],
+    ),
+    key(
+        None,
+        "backoff_initial_ms",
+        KeyType::Integer,
+        |c| json!(c.backoff_initial_ms),
+        "Initial reconnect backoff",
+    ),
+    key(
+        None,
+        "backoff_max_ms",
+        KeyType::Integer,
+        |c| json!(c.backoff_max_ms),
+        "Maximum reconnect backoff",
+    ),
+    ranged(
+        key(
+            Some("watchdog"),
+            "serial_timeout_ms",
+            KeyType::Integer,
+            |c| json!(c.watchdog.serial_timeout_ms),
+            "Serial watchdog timeout",
+        ),
+        MIN_WATCHDOG_TIMEOUT_MS,
+        MAX_WATCHDOG_TIMEOUT_MS,
+    ),
+    ranged(
+        key(
+            Some("watchdog"),
+            "tunnel_timeout_ms",
+            KeyType::Integer,
+            |c| json!(c.watchdog.tunnel_timeout_ms),
+            "Tunnel watchdog timeout",
+        ),
+        MIN_WATCHDOG_TIMEOUT_MS,
+        MAX_WATCHDOG_TIMEOUT_MS,
+    ),
+    ranged(
+        key(
+            Some("modem"),
+            "poll_ms",
+            KeyType::Integer,
+            |c| json!(c.modem.poll_ms),
+            "Modem status line sampling interval; 0 disables sampling",
+        ),
+        MIN_MODEM_POLL_MS,
+        MAX_MODEM_POLL_MS,
+    ),
+    key(
+        Some("modem"),
+        "glyph",
+        KeyType::Bool,
+        |c| json!(c.modem.glyph),
+        "Mark dropped CTS/DSR/CD on the LCD",
+    ),
+    ranged(
+        key(
+            Some("scheduler"),
+            "remote_weight",
+            KeyType::Integer,
+            |c| json!(c.scheduler.remote_weight),
+            "Page slots given to remote frames",
+        ),
+        0,
+        MAX_SCHEDULER_WEIGHT as u64,
+    ),
+    ranged(
+        key(
+            Some("scheduler"),
+            "local_weight",
+            KeyType::Integer,
+            |c| json!(c.scheduler.local_weight),
+            "Page slots given to local pages",
+        ),
+        0,
+        MAX_SCHEDULER_WEIGHT as u64,
+    ),
+    ranged(
+        key(
+            Some("scheduler"),
+            "min_display_ms",
+            KeyType::Integer,
+            |c| json!(c.scheduler.min_display_ms),
+            "Minimum time a page slot stays up while both sources compete",
+        ),
+        0,
+        MAX_SCHEDULER_MIN_DISPLAY_MS,
+    ),
+    ranged(
+        key(
+            Some("slo"),
+            "window_ms",
+            KeyType::Integer,
+            |c| json!(c.slo.window_ms),
+            "Rolling window for error budgets",
+        ),
+        MIN_SLO_WINDOW_MS,
+        MAX_SLO_WINDOW_MS,
+    ),
+    ranged(
+        key(
+            Some("slo"),
+            "parse_budget",
+            KeyType::Integer,
+            |c| json!(c.slo.parse_budget),
+            "Payload parse failures allowed per window; 0 disables",
+        ),
+        0,
+        MAX_SLO_BUDGET as u64,
+    ),
+    ranged(
+        key(
+            Some("slo"),
+            "reconnect_budget",
+            KeyType::Integer,
+            |c| json!(c.slo.reconnect_budget),
+            "Reconnect attempts allowed per window; 0 disables",
+        ),
+        0,
+        MAX_SLO_BUDGET as u64,
+    ),
+    ranged(
+        key(
+            Some("slo"),
+            "crc_budget",
+            KeyType::Integer,
+            |c| json!(c.slo.crc_budget),
+            "Checksum mismatches allowed per window; 0 disables",
+        ),
+        0,
+        MAX_SLO_BUDGET as u64,
+    ),
+    key(
+        Some("slo"),
+        "webhook_url",
+        KeyType::String,
+        |c| json!(c.slo.webhook_url),
+        "http:// URL incident events are POSTed to; empty disables",
+    ),
+    key(
+        Some("anomaly"),
+        "push",
+        KeyType::Bool,
+        |c| json!(c.anomaly.push),
+        "Send anomaly summaries to the peer's LCD and log",
+    ),
+    ranged(
+        key(
+            Some("anomaly"),
+            "window_ms",
+            KeyType::Integer,
+            |c| json!(c.anomaly.window_ms),
+            "Rolling window the anomaly detectors count over",
+        ),
+        MIN_ANOMALY_WINDOW_MS,
+        MAX_ANOMALY_WINDOW_MS,
+    ),
+    ranged(
+        key(
+            Some("anomaly"),
+            "reconnects",
+            KeyType::Integer,
+            |c| json!(c.anomaly.reconnects),
+            "Reconnect attempts per window that count as a storm; 0 disables",
+        ),
+        0,
+        MAX_ANOMALY_RECONNECTS as u64,
+    ),
+    ranged(
+        key(
+            Some("anomaly"),
+            "temp_rise_c",
+            KeyType::Integer,
+            |c| json!(c.anomaly.temp_rise_c),
+            "Temperature rise in Celsius per window that counts as a spike; 0 disables",
+        ),
+        0,
+        MAX_ANOMALY_TEMP_RISE_C as u64,
+    ),
+    one_of(
+        key(
+            Some("arq"),
+            "mode",
+            KeyType::String,
+            |c| json!(c.arq.mode.to_string()),
+            "Stop-and-wait ARQ: off, auto (after sustained CRC failures), or on",
+        ),
+        &["off", "auto", "on"],
+    ),
+    ranged(
+        key(
+            Some("arq"),
+            "ack_timeout_ms",
+            KeyType::Integer,
+            |c| json!(c.arq.ack_timeout_ms),
+            "How long to wait for an ack before retransmitting",
+        ),
+        MIN_ARQ_ACK_TIMEOUT_MS,
+        MAX_ARQ_ACK_TIMEOUT_MS,
+    ),
+    ranged(
+        key(
+            Some("arq"),
+            "max_retries",
+            KeyType::Integer,
+            |c| json!(c.arq.max_retries),
+            "Retransmits before a line is dropped",
+        ),
+        0,
+        MAX_ARQ_RETRIES as u64,
+    ),
+    ranged(
+        key(
+            Some("arq"),
+            "auto_crc_failures",
+            KeyType::Integer,
+            |c| json!(c.arq.auto_crc_failures),
+            "CRC failures within a minute that switch auto mode on",
+        ),
+        MIN_ARQ_AUTO_CRC_FAILURES as u64,
+        MAX_ARQ_AUTO_CRC_FAILURES as u64,
+    ),
+    key(
+        Some("annunciators"),
+        "rules",
+        KeyType::StringArray,
+        |c| json!(c.annunciators.rules),
+        "GPIO rules as PIN:CONDITION (severity>=LEVEL, severity=LEVEL, icon=NAME, bar>=PERCENT)",
+    ),
+    ranged(
+        key(
+            Some("annunciators"),
+            "bar_hysteresis",
+            KeyType::Integer,
+            |c| json!(c.annunciators.bar_hysteresis),
+            "Percent a bar must drop below its threshold before a bar rule releases",
+        ),
+        0,
+        MAX_ANNUNCIATOR_BAR_HYSTERESIS as u64,
+    ),
+    ranged(
+        key(
+            Some("annunciators"),
+            "hold_ms",
+            KeyType::Integer,
+            |c| json!(c.annunciators.hold_ms),
+            "Minimum time an annunciator output holds a state",
+        ),
+        0,
+        MAX_ANNUNCIATOR_HOLD_MS,
+    ),
+    key(
+        Some("annunciators"),
+        "active_low",
+        KeyType::Bool,
+        |c| json!(c.annunciators.active_low),
+        "Drive annunciator pins low when active",
+    ),
+    one_of(
+        key(
+            Some("parse_errors"),
+            "display",
+            KeyType::Enum,
+            |c| json!(c.parse_errors.display.to_string()),
+            "When frame errors take over the LCD: always, after N consecutive errors, or never",
+        ),
+        &["always", "after", "never"],
+    ),
+    ranged(
+        key(
+            Some("parse_errors"),
+            "after",
+            KeyType::Integer,
+            |c| json!(c.parse_errors.after),
+            "Consecutive frame errors before display = \"after\" shows the error page",
+        ),
+        MIN_PARSE_ERROR_AFTER as u64,
+        MAX_PARSE_ERROR_AFTER as u64,
+    ),
+    ranged(
+        key(
+            Some("parse_errors"),
+            "display_ms",
+            KeyType::Integer,
+            |c| json!(c.parse_errors.display_ms),
+            "How long the error page stays before the previous page returns; 0 holds it",
+        ),
+        0,
+        MAX_PARSE_ERROR_DISPLAY_MS,
+    ),
+    ranged(
+        key(
+            Some("replay"),
+            "window",
+            KeyType::Integer,
+            |c| json!(c.replay.window),
+            "Sequence numbers behind the newest still accepted on tunnel/command frames; 0 disables",
+        ),
+        0,
+        MAX_REPLAY_WINDOW as u64,
+    ),
+    key(
+        Some("replay"),
+        "require_seq",
+        KeyType::Bool,
+        |c| json!(c.replay.require_seq),
+        "Reject tunnel/command frames that carry no sequence number",
+    ),
+    ranged(
+        key(
+            Some("render"),
+            "min_interval_ms",
+            KeyType::Integer,
+            |c| json!(c.render.min_interval_ms),
+            "Minimum gap between LCD redraws (the frame-rate ceiling)",
+        ),
+        MIN_RENDER_INTERVAL_MS,
+        MAX_RENDER_INTERVAL_MS,
+    ),
+    ranged(
+        key(
+            Some("render"),
+            "max_interval_ms",
+            KeyType::Integer,
+            |c| json!(c.render.max_interval_ms),
+            "Longest redraw gap the adaptive throttle may stretch to; at least min_interval_ms",
+        ),
+        MIN_RENDER_INTERVAL_MS,
+        MAX_RENDER_INTERVAL_MS,
+    ),
+    ranged(
+        key(
+            Some("render"),
+            "blink_ms",
+            KeyType::Integer,
+            |c| json!(c.render.blink_ms),
+            "Backlight toggle period for blinking frames",
+        ),
+        MIN_RENDER_BLINK_MS,
+        MAX_RENDER_BLINK_MS,
+    ),
+    one_of(
+        key(
+            Some("render"),
+            "throttle",
+            KeyType::Enum,
+            |c| json!(c.render.throttle.to_string()),
+            "fixed keeps min_interval_ms; adaptive slows redraws while LCD writes are slow",
+        ),
+        &["fixed", "adaptive"],
+    ),
+    key(
+        Some("render"),
+        "expiry_countdown",
+        KeyType::Bool,
+        |c| json!(c.render.expiry_countdown),
+        "Show shrinking tick marks in the last column while a duration_ms frame expires",
+    ),
+    one_of(
+        key(
+            Some("render"),
+            "bar_style",
+            KeyType::Enum,
+            |c| json!(c.render.bar_style.to_string()),
+            "Bar glyphs: solid, segmented (gaps between cells), vertical (one 8-step cell) or block (whole cells)",
+        ),
+        &["solid", "segmented", "vertical", "block"],
+    ),
+    key(
+        Some("render"),
+        "persist_pages",
+        KeyType::Bool,
+        |c| json!(c.render.persist_pages),
+        "Save the page queue on shutdown and show it again after a restart",
+    ),
+    ranged(
+        key(
+            Some("render"),
+            "max_frames_per_sec",
+            KeyType::Integer,
+            |c| json!(c.render.max_frames_per_sec),
+            "Payload frames per second before bursts are coalesced (0 = no limit)",
+        ),
+        0,
+        MAX_RENDER_MAX_FRAMES_PER_SEC as u64,
+    ),
+    ranged(
+        key(
+            Some("render"),
+            "i2c_write_budget_bytes",
+            KeyType::Integer,
+            |c| json!(c.render.i2c_write_budget_bytes),
+            "Warn when one frame takes more estimated I2C bytes than this (0 = off)",
+        ),
+        0,
+        MAX_RENDER_I2C_WRITE_BUDGET_BYTES as u64,
+    ),
+    key(
+        Some("accessibility"),
+        "enabled",
+        KeyType::Bool,
+        |c| json!(c.accessibility.enabled),
+        "Slow scrolling and paging, drop blinking and draw whole-cell bars on every frame",
+    ),
+    ranged(
+        key(
+            Some("accessibility"),
+            "min_scroll_speed_ms",
+            KeyType::Integer,
+            |c| json!(c.accessibility.min_scroll_speed_ms),
+            "Fastest scroll step frames may ask for while the profile is on",
+        ),
+        MIN_SCROLL_MS,
+        MAX_ACCESSIBILITY_MIN_SCROLL_MS,
+    ),
+    ranged(
+        key(
+            Some("accessibility"),
+            "min_page_timeout_ms",
+            KeyType::Integer,
+            |c| json!(c.accessibility.min_page_timeout_ms),
+            "Shortest page timeout frames may ask for while the profile is on",
+        ),
+        MIN_PAGE_TIMEOUT_MS,
+        MAX_ACCESSIBILITY_MIN_PAGE_TIMEOUT_MS,
+    ),
+    key(
+        Some("charmap"),
+        "enabled",
+        KeyType::Bool,
+        |c| json!(c.charmap.enabled),
+        "Translate non-ASCII text to the HD44780 character ROM before it is drawn",
+    ),
+    key(
+        Some("charmap"),
+        "map",
+        KeyType::StringArray,
+        |c| json!(c.charmap.map),
+        "Extra CHAR=TARGET entries; TARGET is one ASCII char or a 0xNN ROM code",
+    ),
+    key(
+        Some("filters"),
+        "rules",
+        KeyType::StringArray,
+        |c| json!(c.filters.rules),
+        "drop:LINE~REGEX or mute:remote|polling rules, each with an optional @HH:MM-HH:MM window",
+    ),
+    key(
+        Some("spool"),
+        "enabled",
+        KeyType::Bool,
+        |c| json!(c.spool.enabled),
+        "Queue numbered payload files dropped into spool.dir as pages",
+    ),
+    key(
+        Some("spool"),
+        "dir",
+        KeyType::String,
+        |c| json!(c.spool.dir),
+        "Spool directory; must live under /run/serial_lcd_cache",
+    ),
+    ranged(
+        key(
+            Some("spool"),
+            "poll_ms",
+            KeyType::Integer,
+            |c| json!(c.spool.poll_ms),
+            "How often the spool directory is scanned",
+        ),
+        MIN_SPOOL_POLL_MS,
+        MAX_SPOOL_POLL_MS,
+    ),
+    key(
+        Some("burst"),
+        "enabled",
+        KeyType::Bool,
+        |c| json!(c.burst.enabled),
+        "Upshift to burst.baud while large transfers are queued, then drop back",
+    ),
+    at_least(
+        key(
+            Some("burst"),
+            "baud",
+            KeyType::Integer,
+            |c| json!(c.burst.baud),
+            "Baud used during a burst; must be higher than baud when enabled",
+        ),
+        MIN_BAUD as u64,
+    ),
+    ranged(
+        key(
+            Some("burst"),
+            "threshold_bytes",
+            KeyType::Integer,
+            |c| json!(c.burst.threshold_bytes),
+            "Bytes sent within one second that start a burst",
+        ),
+        MIN_BURST_THRESHOLD_BYTES,
+        MAX_BURST_THRESHOLD_BYTES,
+    ),
+    ranged(
+        key(
+            Some("burst"),
+            "idle_ms",
+            KeyType::Integer,
+            |c| json!(c.burst.idle_ms),
+            "Quiet time before the initiating side drops back to the base baud",
+        ),
+        MIN_BURST_IDLE_MS,
+        MAX_BURST_IDLE_MS,
+    ),
+    ranged(
+        key(
+            Some("burst"),
+            "max_errors",
+            KeyType::Integer,
+            |c| json!(c.burst.max_errors),
+            "Line errors during a burst that abort it and fall back",
+        ),
+        MIN_BURST_MAX_ERRORS as u64,
+        MAX_BURST_MAX_ERRORS as u64,
+    ),
+    ranged(
+        key(
+            Some("troubleshoot"),
+            "after_failures",
+            KeyType::Integer,
+            |c| json!(c.troubleshoot.after_failures),
+            "Consecutive failed starts before troubleshooting hints are shown (0 disables)",
+        ),
+        0,
+        MAX_TROUBLESHOOT_AFTER_FAILURES as u64,
+    ),
+    key(
+        Some("troubleshoot"),
+        "doctor",
+        KeyType::Bool,
+        |c| json!(c.troubleshoot.doctor),
+        "Run the read-only doctor checks when troubleshooting starts",
+    ),
+    key(
+        Some("poll_overlay"),
+        "line1",
+        KeyType::String,
+        |c| json!(c.poll_overlay.line1),
+        "Polling overlay template for row 1, e.g. \"LD{load1:5.2} T{temp:3}C\" (empty = built-in)",
+    ),
+    key(
+        Some("poll_overlay"),
+        "line2",
+        KeyType::String,
+        |c| json!(c.poll_overlay.line2),
+        "Polling overlay template for row 2 (empty = built-in)",
+    ),
+    key(
+        Some("interlock"),
+        "required",
+        KeyType::Bool,
+        |c| json!(c.interlock.required),
+        "Reject tunnel and command-channel commands until the device is armed",
+    ),
+    ranged(
+        key(
+            Some("interlock"),
+            "arm_ms",
+            KeyType::Integer,
+            |c| json!(c.interlock.arm_ms),
+            "How long one arming lasts (signed messages may ask for less)",
+        ),
+        MIN_INTERLOCK_ARM_MS,
+        MAX_INTERLOCK_ARM_MS,
+    ),
+    ranged(
+        key(
+            Some("interlock"),
+            "button_presses",
+            KeyType::Integer,
+            |c| json!(c.interlock.button_presses),
+            "Button presses within a few seconds that arm the device (0 disables)",
+        ),
+        0,
+        MAX_INTERLOCK_BUTTON_PRESSES as u64,
+    ),
+    key(
+        Some("interlock"),
+        "key",
+        KeyType::String,
+        |c| json!(c.interlock.key),
+        "Shared secret for HMAC-signed arm messages (empty disables remote arming)",
+    ),
+    ranged(
+        key(
+            Some("input"),
+            "encoder_a_pin",
+            KeyType::Integer,
+            |c| json!(c.input.encoder_a_pin),
+            "GPIO pin for rotary encoder channel A, or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("input"),
+            "encoder_b_pin",
+            KeyType::Integer,
+            |c| json!(c.input.encoder_b_pin),
+            "GPIO pin for rotary encoder channel B, or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("input"),
+            "encoder_button_pin",
+            KeyType::Integer,
+            |c| json!(c.input.encoder_button_pin),
+            "GPIO pin for the encoder's push switch, or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("input"),
+            "long_press_ms",
+            KeyType::Integer,
+            |c| json!(c.input.long_press_ms),
+            "Hold time on the encoder switch that toggles the backlight",
+        ),
+        MIN_INPUT_LONG_PRESS_MS,
+        MAX_INPUT_LONG_PRESS_MS,
+    ),
+    ranged(
+        key(
+            Some("lcd.gpio"),
+            "rs_pin",
+            KeyType::Integer,
+            |c| json!(c.lcd_gpio.rs_pin),
+            "GPIO pin for the HD44780 RS line with display_driver = \"gpio\", or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("lcd.gpio"),
+            "e_pin",
+            KeyType::Integer,
+            |c| json!(c.lcd_gpio.e_pin),
+            "GPIO pin for the HD44780 E (enable) line, or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("lcd.gpio"),
+            "d4_pin",
+            KeyType::Integer,
+            |c| json!(c.lcd_gpio.d4_pin),
+            "GPIO pin for HD44780 data line D4, or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("lcd.gpio"),
+            "d5_pin",
+            KeyType::Integer,
+            |c| json!(c.lcd_gpio.d5_pin),
+            "GPIO pin for HD44780 data line D5, or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("lcd.gpio"),
+            "d6_pin",
+            KeyType::Integer,
+            |c| json!(c.lcd_gpio.d6_pin),
+            "GPIO pin for HD44780 data line D6, or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("lcd.gpio"),
+            "d7_pin",
+            KeyType::Integer,
+            |c| json!(c.lcd_gpio.d7_pin),
+            "GPIO pin for HD44780 data line D7, or null",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("lcd.gpio"),
+            "backlight_pin",
+            KeyType::Integer,
+            |c| json!(c.lcd_gpio.backlight_pin),
+            "GPIO pin switching the backlight, or null to leave it on",
+        ),
+        0,
+        u8::MAX as u64,
+    ),
+    ranged(
+        key(
+            Some("backlight"),
+            "idle_timeout_ms",
+            KeyType::Integer,
+            |c| json!(c.backlight.idle_timeout_ms),
+            "Turn the backlight off after this long without frames or input; 0 never does",
+        ),
+        MIN_BACKLIGHT_IDLE_TIMEOUT_MS,
+        MAX_BACKLIGHT_IDLE_TIMEOUT_MS,
+    ),
+    key(
+        Some("backlight"),
+        "night_start",
+        KeyType::String,
+        |c| json!(c.backlight.night_start),
+        "Local HH:MM when the backlight goes off for the night; empty disables the schedule",
+    ),
+    key(
+        Some("backlight"),
+        "night_end",
+        KeyType::String,
+        |c| json!(c.backlight.night_end),
+        "Local HH:MM when the night window ends",
+    ),
+    ranged(
+        key(
+            Some("backlight"),
+            "pwm_channel",
+            KeyType::Integer,
+            |c| json!(c.backlight.pwm_channel),
+            "Hardware PWM channel (0 = GPIO18, 1 = GPIO19) dimming the backlight, or null",
+        ),
+        0,
+        MAX_BACKLIGHT_PWM_CHANNEL as u64,
+    ),
+    key(
+        Some("control"),
+        "enabled",
+        KeyType::Bool,
+        |c| json!(c.control.enabled),
+        "Accept JSON commands from local scripts on control.socket",
+    ),
+    key(
+        Some("control"),
+        "socket",
+        KeyType::String,
+        |c| json!(c.control.socket),
+        "Control socket path; must live under /run/serial_lcd_cache",
+    ),
+    key(
+        Some("metrics"),
+        "enabled",
+        KeyType::Bool,
+        |c| json!(c.metrics.enabled),
+        "Serve Prometheus metrics over HTTP (needs a metrics-http build)",
+    ),
+    key(
+        Some("metrics"),
+        "listen",
+        KeyType::String,
+        |c| json!(c.metrics.listen),
+        "address:port for the /metrics endpoint",
+    ),
+    key(
+        Some("badges"),
+        "enabled",
+        KeyType::Bool,
+        |c| json!(c.badges.enabled),
+        "Count unseen alerts and errors on the status row until a button press",
+    ),
+    ranged(
+        key(
+            Some("badges"),
+            "width",
+            KeyType::Integer,
+            |c| json!(c.badges.width),
+            "Cells the badge takes on the status row",
+        ),
+        MIN_BADGES_WIDTH as u64,
+        MAX_BADGES_WIDTH as u64,
+    ),
+    one_of(
+        key(
+            Some("peer_defaults"),
+            "allow",
+            KeyType::StringArray,
+            |c| json!(c.peer_defaults.allow),
+            "Display defaults the peer may push with a render_defaults frame; \"*\" allows all",
+        ),
+        PEER_DEFAULTS_ALLOWED,
+    ),
+    one_of(
+        key(
+            Some("peer_defaults"),
+            "deny",
+            KeyType::StringArray,
+            |c| json!(c.peer_defaults.deny),
+            "Display defaults the peer may never push, even when allow matches",
+        ),
+        PEER_DEFAULTS_ALLOWED,
+    ),
+    one_of(
+        key(
+            Some("logging"),
+            "format",
+            KeyType::Enum,
+            |c| json!(c.logging.format.to_string()),
+            "text keeps plain lines; json writes one object per line with ts, level, module, msg",
+        ),
+        &["text", "json"],
+    ),
+    key(
+        Some("logging"),
+        "max_bytes",
+        KeyType::Integer,
+        |c| json!(c.logging.max_bytes),
+        "Rotate the log file before it passes this size; 0 never rotates (else >= 16384)",
+    ),
+    ranged(
+        key(
+            Some("logging"),
+            "keep",
+            KeyType::Integer,
+            |c| json!(c.logging.keep),
+            "Rotated log files kept as <file>.1 .. <file>.N",
+        ),
+        1,
+        MAX_LOGGING_KEEP as u64,
+    ),
+    key(
+        Some("log_compaction"),
+        "enabled",
+        KeyType::Bool,
+        |c| json!(c.log_compaction.enabled),
+        "Gzip the cache dir logs into dated archives once a day",
+    ),
+    key(
+        Some("log_compaction"),
+        "at",
+        KeyType::String,
+        |c| json!(c.log_compaction.at),
+        "HH:MM local time the daily compaction runs",
+    ),
+    ranged(
+        key(
+            Some("log_compaction"),
+            "keep_days",
+            KeyType::Integer,
+            |c| json!(c.log_compaction.keep_days),
+            "Days an archive is kept",
+        ),
+        1,
+        MAX_LOG_COMPACTION_KEEP_DAYS as u64,
+    ),
+    key(
+        Some("log_compaction"),
+        "max_archive_bytes",
+        KeyType::Integer,
+        |c| json!(c.log_compaction.max_archive_bytes),
+        "Delete the oldest archives once all of them pass this size; 0 keeps them all",
+    ),
+    one_of(
+        key(
+            Some("tunnel_auth"),
+            "backend",
+            KeyType::Enum,
+            |c| json!(c.tunnel_auth.backend.to_string()),
+            "none runs any allowlisted command; totp needs a 6-digit code with each request; hmac needs a signature",
+        ),
+        &["none", "totp", "hmac"],
+    ),
+    key(
+        Some("tunnel_auth"),
+        "secret",
+        KeyType::String,
+        |c| json!(c.tunnel_auth.secret),
+        "Base32 TOTP or HMAC secret shared by the daemon and serialsh (at least 26 characters)",
+    ),
+    ranged(
+        key(
+            Some("tunnel_auth"),
+            "period_secs",
+            KeyType::Integer,
+            |c| json!(c.tunnel_auth.period_secs),
+            "Seconds each TOTP code is valid for; for hmac, the allowed signature clock skew",
+        ),
+        MIN_TUNNEL_AUTH_PERIOD_SECS,
+        MAX_TUNNEL_AUTH_PERIOD_SECS,
+    ),
+    ranged(
+        key(
+            Some("tunnel_auth"),
+            "skew_steps",
+            KeyType::Integer,
+            |c| json!(c.tunnel_auth.skew_steps),
+            "Periods of clock drift tolerated either side",
+        ),
+        0,
+        MAX_TUNNEL_AUTH_SKEW_STEPS as u64,
+    ),
+    key(
+        Some("relay"),
+        "enabled",
+        KeyType::Bool,
+        |c| json!(c.relay.enabled),
+        "Forward accepted frames to another node on relay.device",
+    ),
+    key(
+        Some("relay"),
+        "device",
+        KeyType::String,
+        |c| json!(c.relay.device),
+        "Downstream serial port; must differ from device",
+    ),
+    at_least(
+        key(
+            Some("relay"),
+            "baud",
+            KeyType::Integer,
+            |c| json!(c.relay.baud),
+            "Baud rate of the downstream port",
+        ),
+        MIN_BAUD as u64,
+    ),
+    key(
+        Some("relay"),
+        "rules",
+        KeyType::StringArray,
+        |c| json!(c.relay.rules),
+        "[filters]-style rules; frames they drop are not forwarded",
+    ),
+    ranged(
+        key(
+            Some("protocol"),
+            "schema_version",
+            KeyType::Integer,
+            |c| json!(c.protocol.schema_version),
+            "Payload schema version",
+        ),
+        DEFAULT_PROTOCOL_SCHEMA_VERSION as u64,
+        DEFAULT_PROTOCOL_SCHEMA_VERSION as u64,
+    ),
+    optional(key(
+        Some("protocol"),
+        "compression",
+        KeyType::InlineTable,
+        |c| {
+            json!({
+                "enabled": c.protocol.compression_enabled,
+                "codec": c.protocol.compression_codec.as_str(),
+            })
+        },
+        "Compression settings: { enabled = <bool>, codec = \"none|lz4|zstd\" }",
+    )),
+    optional(key(
+        Some("protocol"),
+        "compression_enabled",
+        KeyType::Bool,
+        |c| json!(c.protocol.compression_enabled),
+        "Legacy spelling of protocol.compression.enabled",
+    )),
+    optional(one_of(
+        key(
+            Some("protocol"),
+            "compression_codec",
+            KeyType::Enum,
+            |c| json!(c.protocol.compression_codec.as_str()),
+            "Legacy spelling of protocol.compression.codec",
+        ),
+        &["none", "lz4", "zstd"],
+    )),
+    key(
+        Some("protocol"),
+        "ack_enabled",
+        KeyType::Bool,
+        |c| json!(c.protocol.ack_enabled),
+        "Reply with a frame_ack (CRC + sequence) after each payload frame is rendered",
+    ),
+    key(
+        Some("protocol"),
+        "strict",
+        KeyType::Bool,
+        |c| json!(c.protocol.strict),
+        "Reject payload frames with unknown fields (false = ignore them and log each once)",
+    ),
+    key(
+        Some(NEGOTIATION_SECTION_NAME),
+        "node_id",
+        KeyType::Integer,
+        |c| json!(c.negotiation.node_id),
+        "Node identifier used to break role ties (0 = derive from install_id)",
+    ),
+    key(
+        Some(NEGOTIATION_SECTION_NAME),
+        "install_id",
+        KeyType::String,
+        |c| json!(c.negotiation.install_id),
+        "Random UUID generated on first run; clear it to get a new identity",
+    ),
+    one_of(
+        key(
+            Some(NEGOTIATION_SECTION_NAME),
+            "preference",
+            KeyType::Enum,
+            |c| json!(c.negotiation.preference.to_string()),
+            "Preferred negotiation role",
+        ),
+        &["prefer_server", "prefer_client", "no_preference"],
+    ),
+    ranged(
+        key(
+            Some(NEGOTIATION_SECTION_NAME),
+            "timeout_ms",
+            KeyType::Integer,
+            |c| json!(c.negotiation.timeout_ms),
+            "Handshake timeout",
+        ),
+        MIN_NEGOTIATION_TIMEOUT_MS,
+        MAX_NEGOTIATION_TIMEOUT_MS,
+    ),
+    ranged(
+        key(
+            Some(NEGOTIATION_SECTION_NAME),
+            "resume_grace_ms",
+            KeyType::Integer,
+            |c| json!(c.negotiation.resume_grace_ms),
+            "How long after a disconnect a reconnect may resume the previous session; 0 disables",
+        ),
+        0,
+        MAX_NEGOTIATION_RESUME_GRACE_MS,
+    ),
+    key(
+        Some(NEGOTIATION_SECTION_NAME),
+        "psk",
+        KeyType::String,
+        |c| json!(c.negotiation.psk),
+        "Pre-shared key that encrypts the link when both peers set it (empty = plaintext)",
+    ),
+    key(
+        None,
+        "command_allowlist",
+        KeyType::StringArray,
+        |c| json!(c.command_allowlist),
+        "Commands the tunnel may run (empty allows all, [\"none\"] disables it)",
+    ),
+    key(
+        None,
+        "tail_allowlist",
+        KeyType::StringArray,
+        |c| json!(c.tail_allowlist),
+        "Absolute file paths the tunnel may tail (empty denies all)",
+    ),
+    optional(key(
+        Some("displays"),
+        "addr",
+        KeyType::String,
+        |_| json!(format_pcf_addr(&DEFAULT_EXTRA_DISPLAY_ADDR)),
+        "I2C address of an extra display on the main display's bus (one [[displays]] section each)",
+    )),
+    optional(ranged(
+        key(
+            Some("displays"),
+            "cols",
+            KeyType::Integer,
+            |_| json!(DEFAULT_COLS),
+            "Columns of the extra display",
+        ),
+        MIN_COLS as u64,
+        MAX_COLS as u64,
+    )),
+    optional(ranged(
+        key(
+            Some("displays"),
+            "rows",
+            KeyType::Integer,
+            |_| json!(DEFAULT_ROWS),
+            "Rows of the extra display",
+        ),
+        MIN_ROWS as u64,
+        MAX_ROWS as u64,
+    )),
+    optional(one_of(
+        key(
+            Some("displays"),
+            "role",
+            KeyType::Enum,
+            |_| json!(DisplayRole::default().to_string()),
+            "What the extra display shows: a copy of the main display, the polling stats, or its zones",
+        ),
+        &["mirror", "polling", "zones"],
+    )),
+    optional(key(
+        Some("zones"),
+        "name",
+        KeyType::String,
+        |_| json!(DEFAULT_ZONE_NAME),
+        "Zone name that payloads address with \"target\" (one [[zones]] section each)",
+    )),
+    optional(key(
+        Some("zones"),
+        "display",
+        KeyType::String,
+        |_| json!("main"),
+        "Display the zone sits on: \"main\" or the address of a [[displays]] entry",
+    )),
+    optional(ranged(
+        key(
+            Some("zones"),
+            "row",
+            KeyType::Integer,
+            |_| json!(0),
+            "First row of the zone, counting from 0",
+        ),
+        0,
+        MAX_ROWS as u64 - 1,
+    )),
+    optional(ranged(
+        key(
+            Some("zones"),
+            "rows",
+            KeyType::Integer,
+            |_| json!(1),
+            "Rows the zone covers",
+        ),
+        1,
+        MAX_ROWS as u64,
+    )),
+];
+
+/// Look up a key by its dotted path.
+pub fn find(path: &str) -> Option<&'static KeySpec> {
+    KEYS.iter().find(|spec| spec.path() == path)
+}
+
+/// Render the registry as a JSON array for `lifelinetty config schema`.
+pub fn to_json() -> String {
+    let entries: Vec<KeySpecEntry<'_>> = KEYS
+        .iter()
+        .map(|spec| KeySpecEntry {
+            path: spec.path(),
+            section: spec.section,
+            key: spec.key,
+            kind: spec.kind,
+            default: spec.default_value(),
+            min: spec.min,
+            max: spec.max,
+            allowed: spec.allowed,
+            required: spec.required,
+            description: spec.description,
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_config_only_uses_registered_keys() {
+        let path = std::env::temp_dir().join(format!(
+            "lifelinetty_schema_{}_{}.toml",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        Config::default().save_to_path(&path).unwrap();
+        let raw = std::fs::read_to_string(&path).unwrap();
+        let (_, seen) = loader::parse_with_seen(&raw).unwrap();
+        for key in &seen {
+            assert!(
+                find(key).is_some(),
+                "saved key '{key}' missing from registry"
+            );
+        }
+        for spec in KEYS.iter().filter(|spec| spec.required) {
+            assert!(seen.contains(&spec.path()), "{} not saved", spec.path());
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn every_registered_key_is_parseable() {
+        for spec in KEYS {
+            let value = match spec.default_value() {
+                Value::String(s) => format!("\"{s}\""),
+                Value::Null => "null".to_string(),
+                Value::Object(_) => "{ enabled = false, codec = \"lz4\" }".to_string(),
+                Value::Array(_) => "[]".to_string(),
+                other => other.to_string(),
+            };
+            let raw = match spec.section {
+                Some(section) => format!("[{section}]\n{} = {value}\n", spec.key),
+                None => format!("{} = {value}\n", spec.key),
+            };
+            loader::parse(&raw).unwrap_or_else(|e| panic!("{}: {e}", spec.path()));
+        }
+    }
+
+    #[test]
+    fn json_lists_defaults_and_ranges() {
+        let parsed: Value = serde_json::from_str(&to_json()).unwrap();
+        let entries = parsed.as_array().unwrap();
+        assert_eq!(entries.len(), KEYS.len());
+        let baud = entries.iter().find(|e| e["path"] == "baud").unwrap();
+        assert_eq!(baud["default"], json!(DEFAULT_BAUD));
+        assert_eq!(baud["min"], json!(MIN_BAUD));
+        assert_eq!(baud["type"], "integer");
+    }
+}
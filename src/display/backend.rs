@@ -0,0 +1,194 @@
+use std::time::{Duration, Instant};
+
+use crate::{display::icon_bank::GlyphWriter, Error, Result};
+
+/// What the render loop needs from a display. `Lcd` covers the HD44780, OLED, terminal and stub
+/// cases; other backends (a remote mirror, a recording fake in tests) only have to provide the
+/// required methods.
+pub trait DisplayBackend {
+    fn cols(&self) -> u8;
+    fn rows(&self) -> u8;
+    fn clear(&mut self) -> Result<()>;
+    fn write_line(&mut self, row: u8, content: &str) -> Result<()>;
+    fn set_backlight(&mut self, on: bool) -> Result<()>;
+    fn set_blink(&mut self, on: bool) -> Result<()>;
+    /// Load a 5x8 glyph into CGRAM `slot` (0-7).
+    fn custom_char(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<()>;
+
+    /// Write every row back-to-back; lines past the last row are dropped.
+    fn write_lines(&mut self, lines: &[&str]) -> Result<()> {
+        for (row, line) in (0..self.rows()).zip(lines) {
+            self.write_line(row, line)?;
+        }
+        Ok(())
+    }
+
+    /// Get ready to draw a new frame over every row. Backends without a shadow of the glass
+    /// clear it, since their `write_lines` only covers the cells it is given.
+    fn prepare_redraw(&mut self) -> Result<()> {
+        self.clear()
+    }
+
+    /// Bring one row to `content`. Backends without a shadow rewrite the whole row; the return
+    /// value is the number of cells sent.
+    fn write_row_diff(&mut self, row: u8, content: &str) -> Result<usize> {
+        let cols = self.cols() as usize;
+        let mut line: String = content.chars().take(cols).collect();
+        let pad = cols.saturating_sub(line.chars().count());
+        line.extend(std::iter::repeat_n(' ', pad));
+        self.write_line(row, &line)?;
+        Ok(cols)
+    }
+
+    /// Backlight brightness in percent. Backends that can only switch the light treat 50 and
+    /// above as on.
+    fn set_backlight_level(&mut self, percent: u8) -> Result<()> {
+        self.set_backlight(percent >= 50)
+    }
+
+    /// Duration of the last full-frame write, for render timing; `None` when not measured.
+    fn take_write_time(&mut self) -> Option<Duration> {
+        None
+    }
+
+    /// Estimated I2C bytes the last full-frame write sent; `None` when not measured.
+    fn take_write_bytes(&mut self) -> Option<u64> {
+        None
+    }
+
+    /// Estimated I2C bytes sent so far; 0 for backends that do not count.
+    fn bus_bytes(&self) -> u64 {
+        0
+    }
+
+    /// Rows as they look on the glass, in printable chars; empty for backends without a
+    /// shadow of the display.
+    fn screen(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Switch to a new geometry without restarting.
+    fn reconfigure(&mut self, cols: u8, rows: u8) -> Result<()> {
+        if (cols, rows) == (self.cols(), self.rows()) {
+            return Ok(());
+        }
+        Err(Error::InvalidArgs(
+            "this display backend cannot change geometry".into(),
+        ))
+    }
+
+    /// Re-open hardware that stopped answering, once its retry is due; `true` when it came
+    /// back. Backends without hardware to lose never need to.
+    fn recover(&mut self, _now: Instant) -> bool {
+        false
+    }
+}
+
+impl<D: DisplayBackend> GlyphWriter for D {
+    fn write_glyph(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<()> {
+        self.custom_char(slot, bitmap)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod recording {
+    use super::*;
+
+    /// One call made against a [`RecordingDisplay`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum DisplayCall {
+        Clear,
+        Line(u8, String),
+        Backlight(bool),
+        Blink(bool),
+        Glyph(u8),
+    }
+
+    /// Fake backend that records every call, so render paths can be checked without an `Lcd`.
+    #[derive(Debug)]
+    pub struct RecordingDisplay {
+        cols: u8,
+        rows: u8,
+        pub calls: Vec<DisplayCall>,
+    }
+
+    impl RecordingDisplay {
+        pub fn new(cols: u8, rows: u8) -> Self {
+            Self {
+                cols,
+                rows,
+                calls: Vec::new(),
+            }
+        }
+
+        /// Text last written to each row.
+        pub fn lines(&self) -> Vec<String> {
+            let mut rows = vec![String::new(); self.rows as usize];
+            for call in &self.calls {
+                match call {
+                    DisplayCall::Clear => rows.iter_mut().for_each(String::clear),
+                    DisplayCall::Line(row, text) => rows[*row as usize] = text.clone(),
+                    _ => {}
+                }
+            }
+            rows
+        }
+    }
+
+    impl DisplayBackend for RecordingDisplay {
+        fn cols(&self) -> u8 {
+            self.cols
+        }
+
+        fn rows(&self) -> u8 {
+            self.rows
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            self.calls.push(DisplayCall::Clear);
+            Ok(())
+        }
+
+        fn write_line(&mut self, row: u8, content: &str) -> Result<()> {
+            if row >= self.rows {
+                return Err(Error::InvalidArgs(format!("row {row} out of bounds")));
+            }
+            self.calls.push(DisplayCall::Line(row, content.to_string()));
+            Ok(())
+        }
+
+        fn set_backlight(&mut self, on: bool) -> Result<()> {
+            self.calls.push(DisplayCall::Backlight(on));
+            Ok(())
+        }
+
+        fn set_blink(&mut self, on: bool) -> Result<()> {
+            self.calls.push(DisplayCall::Blink(on));
+            Ok(())
+        }
+
+        fn custom_char(&mut self, slot: u8, _bitmap: &[u8; 8]) -> Result<()> {
+            self.calls.push(DisplayCall::Glyph(slot));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::recording::{DisplayCall, RecordingDisplay};
+    use super::*;
+
+    #[test]
+    fn provided_methods_go_through_write_line() {
+        let mut display = RecordingDisplay::new(4, 2);
+        display.write_lines(&["ab", "cd", "dropped"]).unwrap();
+        assert_eq!(display.write_row_diff(1, "x").unwrap(), 4);
+        assert_eq!(display.lines(), vec!["ab".to_string(), "x   ".to_string()]);
+        assert!(display.take_write_time().is_none());
+        assert!(display.reconfigure(4, 2).is_ok());
+        assert!(display.reconfigure(8, 2).is_err());
+        display.write_glyph(3, &[0; 8]).unwrap();
+        assert_eq!(display.calls.last(), Some(&DisplayCall::Glyph(3)));
+    }
+}
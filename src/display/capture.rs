@@ -0,0 +1,282 @@
+//! Snapshots of the glass for `--capture <dir>`.
+//!
+//! A snapshot is taken from the `Lcd` shadow after the normal composition pipeline has drawn a
+//! page, so bars, icons and custom glyphs come out as they would on the hardware. PNGs draw every
+//! cell from a bundled 5x8 raster: the OLED driver's 5x7 font, the bar glyphs CGRAM starts
+//! with, whatever bitmaps were loaded since, and a few ROM symbols. Text snapshots are the box
+//! `--dry-run` prints. Nothing depends on the clock, so the same pages give the same files.
+
+use std::{
+    fmt, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crc32fast::Hasher;
+use flate2::{write::ZlibEncoder, Compression};
+
+use super::lcd::{Lcd, BAR_GLYPHS};
+use crate::{lcd_driver::ssd1306::font_glyph, Result};
+
+/// Screen pixels per glass pixel.
+const SCALE: usize = 3;
+/// A cell is 5x8 pixels with one pixel of gap to the right and below.
+const CELL_WIDTH: usize = 5;
+const CELL_HEIGHT: usize = 8;
+const PITCH_X: usize = CELL_WIDTH + 1;
+const PITCH_Y: usize = CELL_HEIGHT + 1;
+/// Glass pixels of bezel around the grid.
+const BORDER: usize = 4;
+
+/// Background, unlit pixel and lit pixel, with the backlight on and off.
+const LIT_PALETTE: [[u8; 3]; 3] = [[0x9a, 0xbe, 0x28], [0x8c, 0xb0, 0x22], [0x1e, 0x2a, 0x10]];
+const DARK_PALETTE: [[u8; 3]; 3] = [[0x46, 0x52, 0x24], [0x42, 0x4d, 0x22], [0x1a, 0x20, 0x0e]];
+
+/// HD44780 ROM symbols the bundled font lacks, as 5x8 rows with bit 4 on the left.
+const ROM_GLYPHS: [(u32, [u8; 8]); 5] = [
+    (0x7e, [0x00, 0x04, 0x02, 0x1f, 0x02, 0x04, 0x00, 0x00]), // →
+    (0x7f, [0x00, 0x04, 0x08, 0x1f, 0x08, 0x04, 0x00, 0x00]), // ←
+    (0xa5, [0x00, 0x00, 0x00, 0x0c, 0x0c, 0x00, 0x00, 0x00]), // ·
+    (0xdf, [0x0e, 0x0a, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00]), // °
+    (0xff, [0x1f; 8]),                                        // █
+];
+
+/// What `--capture` writes for each page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaptureFormat {
+    #[default]
+    Png,
+    Text,
+}
+
+impl CaptureFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            CaptureFormat::Png => "png",
+            CaptureFormat::Text => "txt",
+        }
+    }
+}
+
+impl FromStr for CaptureFormat {
+    type Err = String;
+
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        match raw.to_ascii_lowercase().as_str() {
+            "png" => Ok(CaptureFormat::Png),
+            "text" | "txt" => Ok(CaptureFormat::Text),
+            other => Err(format!("expected 'png' or 'text', got '{other}'")),
+        }
+    }
+}
+
+impl fmt::Display for CaptureFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CaptureFormat::Png => "png",
+            CaptureFormat::Text => "text",
+        })
+    }
+}
+
+/// Writes numbered snapshots into one directory.
+pub struct Capture {
+    dir: PathBuf,
+    format: CaptureFormat,
+    taken: usize,
+}
+
+impl Capture {
+    /// Create `dir` if needed; files already in it are overwritten as pages are saved.
+    pub fn new(dir: &Path, format: CaptureFormat) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            format,
+            taken: 0,
+        })
+    }
+
+    /// Save what `lcd` shows as `NN-<label>.png` (or `.txt`) and return the path.
+    pub fn save(&mut self, lcd: &Lcd, label: &str) -> Result<PathBuf> {
+        self.taken += 1;
+        let path = self.dir.join(format!(
+            "{:02}-{}.{}",
+            self.taken,
+            slug(label),
+            self.format.extension()
+        ));
+        match self.format {
+            CaptureFormat::Png => fs::write(&path, png_snapshot(lcd)?)?,
+            CaptureFormat::Text => fs::write(&path, text_snapshot(lcd))?,
+        }
+        Ok(path)
+    }
+}
+
+/// `label` cut down to a short file-name-safe word list.
+fn slug(label: &str) -> String {
+    let mut out = String::new();
+    for ch in label.chars() {
+        if ch.is_ascii_alphanumeric() {
+            out.push(ch.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+        if out.len() >= 24 {
+            break;
+        }
+    }
+    let out = out.trim_end_matches('-');
+    if out.is_empty() {
+        "page".into()
+    } else {
+        out.to_string()
+    }
+}
+
+/// The rows in the same box `--dry-run` draws, with the backlight state underneath.
+pub fn text_snapshot(lcd: &Lcd) -> String {
+    let border = "─".repeat(lcd.cols() as usize);
+    let mut out = format!("┌{border}┐\n");
+    for row in lcd.screen() {
+        out.push_str(&format!("│{row}│\n"));
+    }
+    out.push_str(&format!("└{border}┘\n"));
+    out.push_str(&format!(
+        " backlight={}\n",
+        if lcd.backlight_on() { "on" } else { "off" }
+    ));
+    out
+}
+
+/// The glass drawn pixel for pixel as an RGB PNG.
+pub fn png_snapshot(lcd: &Lcd) -> Result<Vec<u8>> {
+    let (rows, loaded) = lcd.glass();
+    let cols = rows.first().map_or(0, Vec::len);
+    let width = (2 * BORDER + cols * PITCH_X - 1) * SCALE;
+    let height = (2 * BORDER + rows.len() * PITCH_Y - 1) * SCALE;
+    let [background, unlit, lit] = if lcd.backlight_on() {
+        LIT_PALETTE
+    } else {
+        DARK_PALETTE
+    };
+    let mut rgb: Vec<u8> = background.repeat(width * height);
+    for (row, cells) in rows.iter().enumerate() {
+        for (col, ch) in cells.iter().enumerate() {
+            let bitmap = cell_bitmap(*ch, loaded);
+            for (y, bits) in bitmap.iter().enumerate() {
+                for x in 0..CELL_WIDTH {
+                    let on = bits & (0x10 >> x) != 0;
+                    let left = (BORDER + col * PITCH_X + x) * SCALE;
+                    let top = (BORDER + row * PITCH_Y + y) * SCALE;
+                    for py in top..top + SCALE {
+                        for px in left..left + SCALE {
+                            let at = (py * width + px) * 3;
+                            rgb[at..at + 3].copy_from_slice(if on { &lit } else { &unlit });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    encode_png(width as u32, height as u32, &rgb)
+}
+
+/// The 5x8 rows for one shadow cell, bit 4 on the left.
+fn cell_bitmap(ch: char, loaded: &[Option<[u8; 8]>; 8]) -> [u8; 8] {
+    let code = ch as u32;
+    if code < 8 {
+        return loaded[code as usize].unwrap_or_else(|| preloaded(code as usize));
+    }
+    if let Some((_, bitmap)) = ROM_GLYPHS.iter().find(|(rom, _)| *rom == code) {
+        return *bitmap;
+    }
+    // The font is column-major with bit 0 at the top; turn it into rows.
+    let columns = font_glyph(ch);
+    let mut bitmap = [0u8; 8];
+    for (x, column) in columns.iter().enumerate() {
+        for (y, bits) in bitmap.iter_mut().enumerate() {
+            if column & (1 << y) != 0 {
+                *bits |= 0x10 >> x;
+            }
+        }
+    }
+    bitmap
+}
+
+fn preloaded(slot: usize) -> [u8; 8] {
+    let mut bitmap = [0u8; 8];
+    for (bits, row) in bitmap.iter_mut().zip(BAR_GLYPHS[slot]) {
+        for (x, pixel) in row.chars().enumerate() {
+            if pixel == '1' {
+                *bits |= 0x10 >> x;
+            }
+        }
+    }
+    bitmap
+}
+
+/// 8-bit RGB, no interlace, one zlib stream.
+fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Result<Vec<u8>> {
+    let mut scanlines = Vec::with_capacity(rgb.len() + height as usize);
+    for line in rgb.chunks(width as usize * 3) {
+        scanlines.push(0); // filter: none
+        scanlines.extend_from_slice(line);
+    }
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+    zlib.write_all(&scanlines)?;
+    let data = zlib.finish()?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    push_chunk(&mut png, b"IHDR", &header);
+    push_chunk(&mut png, b"IDAT", &data);
+    push_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+fn push_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let mut crc = Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc.finalize().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_draw_the_glass_and_name_files_after_the_page() {
+        let mut lcd = Lcd::new_stub(16, 2);
+        lcd.write_line(0, "CPU 42%").unwrap();
+        lcd.write_line(1, "\u{5}\u{5}\u{3}").unwrap();
+        assert_eq!(
+            text_snapshot(&lcd),
+            "┌────────────────┐\n│CPU 42%         │\n│██▋             │\n└────────────────┘\n backlight=on\n"
+        );
+        assert_eq!(cell_bitmap('\u{5}', &[None; 8]), [0x1f; 8]);
+        assert_eq!(cell_bitmap('T', &[None; 8])[..2], [0x1f, 0x04]);
+
+        let png = png_snapshot(&lcd).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+        assert_eq!(width as usize, (2 * BORDER + 16 * PITCH_X - 1) * SCALE);
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut capture = Capture::new(dir.path(), CaptureFormat::Text).unwrap();
+        let path = capture.save(&lcd, "Up 12:34 CPU 42%").unwrap();
+        assert_eq!(path, dir.path().join("01-up-12-34-cpu-42.txt"));
+        assert_eq!(fs::read_to_string(path).unwrap(), text_snapshot(&lcd));
+    }
+}
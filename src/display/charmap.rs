@@ -0,0 +1,239 @@
+//! Unicode to HD44780 character translation.
+//!
+//! The controller draws one byte per cell from its character ROM, and the common A00 ROM only
+//! agrees with ASCII from 0x20 to 0x7d; the driver sends the low byte of whatever char it is
+//! given, so "°C" or "é" come out as unrelated glyphs. [`CharMap`] rewrites each char to one
+//! cell before it reaches the glass: chars the ROM has become their ROM code (carried as the
+//! char with that code point, e.g. `'\u{df}'` for °), accented letters it lacks fall back to
+//! the bare letter, and anything else non-ASCII becomes `?`. ASCII and the CGRAM slots 0-7
+//! pass through, so icons and bars are unaffected.
+
+use std::{borrow::Cow, collections::HashMap, str::FromStr};
+
+/// Stand-in for chars with no mapping.
+const UNKNOWN: char = '?';
+
+const fn rom(code: u8) -> char {
+    code as char
+}
+
+/// Built-in mappings for the A00 ROM. Where several chars share a ROM code, the first is the
+/// one [`glass_char`] shows for it.
+const BUILTIN: &[(char, char)] = &[
+    // Symbols the ROM has.
+    ('°', rom(0xdf)),
+    ('→', rom(0x7e)),
+    ('←', rom(0x7f)),
+    ('µ', rom(0xe4)),
+    ('μ', rom(0xe4)),
+    ('Ω', rom(0xf4)),
+    ('α', rom(0xe0)),
+    ('β', rom(0xe2)),
+    ('ß', rom(0xe2)),
+    ('ε', rom(0xe3)),
+    ('σ', rom(0xe5)),
+    ('ρ', rom(0xe6)),
+    ('√', rom(0xe8)),
+    ('¢', rom(0xec)),
+    ('θ', rom(0xf2)),
+    ('∞', rom(0xf3)),
+    ('Σ', rom(0xf6)),
+    ('π', rom(0xf7)),
+    ('÷', rom(0xfd)),
+    ('█', rom(0xff)),
+    ('·', rom(0xa5)),
+    ('ä', rom(0xe1)),
+    ('ñ', rom(0xee)),
+    ('ö', rom(0xef)),
+    ('ü', rom(0xf5)),
+    // Accented letters the ROM lacks.
+    ('à', 'a'),
+    ('á', 'a'),
+    ('â', 'a'),
+    ('ã', 'a'),
+    ('å', 'a'),
+    ('ç', 'c'),
+    ('è', 'e'),
+    ('é', 'e'),
+    ('ê', 'e'),
+    ('ë', 'e'),
+    ('ì', 'i'),
+    ('í', 'i'),
+    ('î', 'i'),
+    ('ï', 'i'),
+    ('ò', 'o'),
+    ('ó', 'o'),
+    ('ô', 'o'),
+    ('õ', 'o'),
+    ('ø', 'o'),
+    ('ù', 'u'),
+    ('ú', 'u'),
+    ('û', 'u'),
+    ('ý', 'y'),
+    ('ÿ', 'y'),
+    ('À', 'A'),
+    ('Á', 'A'),
+    ('Â', 'A'),
+    ('Ã', 'A'),
+    ('Ä', 'A'),
+    ('Å', 'A'),
+    ('Ç', 'C'),
+    ('È', 'E'),
+    ('É', 'E'),
+    ('Ê', 'E'),
+    ('Ë', 'E'),
+    ('Ì', 'I'),
+    ('Í', 'I'),
+    ('Î', 'I'),
+    ('Ï', 'I'),
+    ('Ñ', 'N'),
+    ('Ò', 'O'),
+    ('Ó', 'O'),
+    ('Ô', 'O'),
+    ('Õ', 'O'),
+    ('Ö', 'O'),
+    ('Ø', 'O'),
+    ('Ù', 'U'),
+    ('Ú', 'U'),
+    ('Û', 'U'),
+    ('Ü', 'U'),
+    ('Ý', 'Y'),
+    // Punctuation with a close ASCII twin.
+    ('↑', '^'),
+    ('↓', 'v'),
+    ('‘', '\''),
+    ('’', '\''),
+    ('“', '"'),
+    ('”', '"'),
+    ('–', '-'),
+    ('—', '-'),
+    ('…', '.'),
+    ('×', 'x'),
+    ('\u{a0}', ' '),
+];
+
+/// One `[charmap].map` entry: `"€=E"` maps to a char, `"°=0xdf"` to a ROM code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharMapping {
+    pub from: char,
+    pub to: char,
+}
+
+impl FromStr for CharMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let (Some(from), Some('=')) = (chars.next(), chars.next()) else {
+            return Err(format!("expected CHAR=TARGET, got '{s}'"));
+        };
+        let target = chars.as_str();
+        let to = if let Some(hex) = target
+            .strip_prefix("0x")
+            .or_else(|| target.strip_prefix("0X"))
+        {
+            let code = u8::from_str_radix(hex, 16)
+                .map_err(|_| format!("'{target}' in '{s}' is not a ROM code (0x08-0xff)"))?;
+            if code < 0x08 {
+                return Err(format!(
+                    "'{target}' in '{s}' is a CGRAM slot; ROM codes start at 0x08"
+                ));
+            }
+            rom(code)
+        } else {
+            let mut target_chars = target.chars();
+            match (target_chars.next(), target_chars.next()) {
+                (Some(to), None) if to.is_ascii() && !to.is_ascii_control() => to,
+                _ => {
+                    return Err(format!(
+                        "target in '{s}' must be one printable ASCII char or a 0xNN ROM code"
+                    ))
+                }
+            }
+        };
+        Ok(Self { from, to })
+    }
+}
+
+/// Per-char translation applied to every row before it is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharMap {
+    table: HashMap<char, char>,
+}
+
+impl Default for CharMap {
+    fn default() -> Self {
+        Self {
+            table: BUILTIN.iter().copied().collect(),
+        }
+    }
+}
+
+impl CharMap {
+    /// The built-in table with `extra` entries added on top, replacing built-in ones for the
+    /// same char.
+    pub fn with_extra(extra: &[String]) -> crate::Result<Self> {
+        let mut map = Self::default();
+        for entry in extra {
+            let mapping: CharMapping = entry
+                .parse()
+                .map_err(|e| crate::Error::InvalidArgs(format!("charmap.map: {e}")))?;
+            map.table.insert(mapping.from, mapping.to);
+        }
+        Ok(map)
+    }
+
+    /// `text` with every char replaced by the one cell the glass should show for it.
+    pub fn translate<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if text.chars().all(|ch| self.translate_char(ch) == ch) {
+            return Cow::Borrowed(text);
+        }
+        Cow::Owned(text.chars().map(|ch| self.translate_char(ch)).collect())
+    }
+
+    fn translate_char(&self, ch: char) -> char {
+        match self.table.get(&ch) {
+            Some(to) => *to,
+            None if ch.is_ascii() => ch,
+            None => UNKNOWN,
+        }
+    }
+}
+
+/// What the glass shows for a cell the driver would write `ch` to, for terminal previews:
+/// ROM codes from the built-in table become their Unicode char again.
+pub fn glass_char(ch: char) -> Option<char> {
+    match ch as u32 {
+        0x7f..=0xff => BUILTIN
+            .iter()
+            .find(|(_, code)| *code == ch)
+            .map(|(from, _)| *from),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_to_rom_codes_and_plain_letters() {
+        let map = CharMap::default();
+        assert_eq!(map.translate("21°C → café"), "21\u{df}C \u{7e} cafe");
+        assert_eq!(map.translate("Grüße ✓"), "Gr\u{f5}\u{e2}e ?");
+        // ASCII and CGRAM slots are untouched, without allocating.
+        assert!(matches!(map.translate("CPU \u{1}42%"), Cow::Borrowed(_)));
+        assert_eq!(glass_char('\u{df}'), Some('°'));
+        assert_eq!(glass_char('\u{e2}'), Some('β'));
+        assert_eq!(glass_char('A'), None);
+    }
+
+    #[test]
+    fn extra_entries_override_the_builtin_table() {
+        let map = CharMap::with_extra(&["€=E".into(), "é=0xe9".into()]).unwrap();
+        assert_eq!(map.translate("5€ é"), "5E \u{e9}");
+        for bad in ["€", "€=", "€=EU", "€=0x03", "€=0x1ff", "€=\u{7}"] {
+            assert!(bad.parse::<CharMapping>().is_err(), "{bad}");
+        }
+    }
+}
@@ -1,25 +1,25 @@
 use std::collections::{HashMap, HashSet};
 
-use crate::{display::lcd::Lcd, payload::Icon, Result};
+use crate::{
+    payload::{BarStyle, Icon},
+    Result,
+};
 
 pub trait GlyphWriter {
     fn write_glyph(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<()>;
 }
 
-impl GlyphWriter for Lcd {
-    fn write_glyph(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<()> {
-        self.write_custom_char(slot, bitmap)
-    }
-}
-
 const MAX_SLOTS: usize = 8;
-const BAR_LEVEL_COUNT: usize = 6;
+/// Levels of the finest bar style, counting the empty level 0.
+const BAR_LEVEL_COUNT: usize = 9;
+/// Tick levels of the expiry countdown glyph; level 4 is a fresh frame, level 1 about to expire.
+pub const COUNTDOWN_LEVELS: u8 = 4;
 // Note: We do not provide ASCII fallbacks here — missing glyphs are considered
 // missing and will be surfaced to callers. Render code should decide how to
 // present absence of CGRAM glyphs (e.g. leave blank) rather than substituting
 // ASCII characters silently.
 
-const BAR_BITMAPS: [[u8; 8]; BAR_LEVEL_COUNT] = [
+const BAR_BITMAPS: [[u8; 8]; 6] = [
     [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
     [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10],
     [0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18],
@@ -28,11 +28,108 @@ const BAR_BITMAPS: [[u8; 8]; BAR_LEVEL_COUNT] = [
     [0x1f, 0x1f, 0x1f, 0x1f, 0x1f, 0x1f, 0x1f, 0x1f],
 ];
 
+/// Column masks for the segmented style; the rightmost column stays dark as the gap.
+const SEGMENTED_BAR_ROWS: [u8; 5] = [0x00, 0x10, 0x18, 0x1c, 0x1e];
+
+/// Short ticks stacked from the bottom of the cell; one tick disappears per level.
+const COUNTDOWN_BITMAPS: [[u8; 8]; COUNTDOWN_LEVELS as usize] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0e],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0e, 0x00, 0x0e],
+    [0x00, 0x00, 0x00, 0x0e, 0x00, 0x0e, 0x00, 0x0e],
+    [0x00, 0x0e, 0x00, 0x0e, 0x00, 0x0e, 0x00, 0x0e],
+];
+
+/// Cells of the two-row big-digit font. Each glyph is built from these five shapes, so a clock
+/// or a reading takes at most five CGRAM slots and leaves room for the heartbeat and icons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BigSegment {
+    Full,
+    Top,
+    Bottom,
+    /// Top and bottom bars; the bottom one lines up with the middle stroke of the digit.
+    Both,
+    Dot,
+}
+
+impl BigSegment {
+    fn from_code(code: char) -> Option<Self> {
+        match code {
+            'F' => Some(Self::Full),
+            'T' => Some(Self::Top),
+            'B' => Some(Self::Bottom),
+            'M' => Some(Self::Both),
+            'D' => Some(Self::Dot),
+            _ => None,
+        }
+    }
+
+    fn bitmap(self) -> [u8; 8] {
+        match self {
+            Self::Full => [0x1f; 8],
+            Self::Top => [0x1f, 0x1f, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00],
+            Self::Bottom => [0x00, 0x00, 0x00, 0x00, 0x00, 0x1f, 0x1f, 0x1f],
+            Self::Both => [0x1f, 0x1f, 0x1f, 0x00, 0x00, 0x1f, 0x1f, 0x1f],
+            Self::Dot => [0x00, 0x00, 0x0e, 0x0e, 0x0e, 0x00, 0x00, 0x00],
+        }
+    }
+}
+
+/// Big-font glyphs as top and bottom rows of segment codes: `F`ull, `T`op, `B`ottom, both
+/// (`M`), `D`ot, or a blank cell.
+const BIG_FONT: &[(char, &str, &str)] = &[
+    ('0', "FTF", "FBF"),
+    ('1', "TF ", "BFB"),
+    ('2', "MMF", "FBB"),
+    ('3', "MMF", "BBF"),
+    ('4', "FBF", "  F"),
+    ('5', "FMM", "BBF"),
+    ('6', "FMM", "FBF"),
+    ('7', "TTF", "  F"),
+    ('8', "FMF", "FBF"),
+    ('9', "FMF", "BBF"),
+    ('-', "BB", "  "),
+    (':', "D", "D"),
+    ('.', " ", "D"),
+    ('°', "D", " "),
+    ('C', "FTT", "FBB"),
+    ('F', "FMM", "F  "),
+    (' ', " ", " "),
+];
+
+/// Top and bottom rows of `ch` in the big font, one segment (or blank) per column; `None` for
+/// characters the font lacks.
+pub fn big_glyph(ch: char) -> Option<(BigRow, BigRow)> {
+    let ch = ch.to_ascii_uppercase();
+    BIG_FONT
+        .iter()
+        .find(|(glyph, _, _)| *glyph == ch)
+        .map(|(_, top, bottom)| (BigRow(top), BigRow(bottom)))
+}
+
+/// One row of a big-font glyph.
+#[derive(Clone, Copy, Debug)]
+pub struct BigRow(&'static str);
+
+impl BigRow {
+    pub fn width(self) -> usize {
+        self.0.len()
+    }
+
+    pub fn cells(self) -> impl Iterator<Item = Option<BigSegment>> {
+        self.0.chars().map(BigSegment::from_code)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum GlyphKind {
-    Bar(u8),
+    Bar(BarStyle, u8),
     Heartbeat,
+    Countdown(u8),
     Icon(Icon),
+    Big(BigSegment),
+    /// A payload's own bitmap, keyed by its rows so identical glyphs share a slot and a glyph
+    /// that stays on screen across frames is not rewritten.
+    Custom([u8; 8]),
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -42,33 +139,48 @@ struct SlotEntry {
 }
 
 pub struct IconPalette {
+    bar_style: BarStyle,
     bar_chars: [Option<char>; BAR_LEVEL_COUNT],
     heartbeat_char: Option<char>,
+    countdown_char: Option<char>,
     icon_chars: HashMap<Icon, char>,
+    big_chars: HashMap<BigSegment, char>,
+    custom_chars: HashMap<[u8; 8], char>,
     pub missing_icons: Vec<Icon>,
 }
 
 impl IconPalette {
     fn new() -> Self {
         Self {
+            bar_style: BarStyle::default(),
             bar_chars: [None; BAR_LEVEL_COUNT],
             heartbeat_char: None,
+            countdown_char: None,
             icon_chars: HashMap::new(),
+            big_chars: HashMap::new(),
+            custom_chars: HashMap::new(),
             missing_icons: Vec::new(),
         }
     }
 
     fn register(&mut self, kind: GlyphKind, ch: char) {
         match kind {
-            GlyphKind::Bar(level) => {
+            GlyphKind::Bar(_, level) => {
                 if let Some(dest) = self.bar_chars.get_mut(level as usize) {
                     *dest = Some(ch);
                 }
             }
             GlyphKind::Heartbeat => self.heartbeat_char = Some(ch),
+            GlyphKind::Countdown(_) => self.countdown_char = Some(ch),
             GlyphKind::Icon(icon) => {
                 self.icon_chars.insert(icon, ch);
             }
+            GlyphKind::Big(segment) => {
+                self.big_chars.insert(segment, ch);
+            }
+            GlyphKind::Custom(rows) => {
+                self.custom_chars.insert(rows, ch);
+            }
         }
     }
 
@@ -82,6 +194,11 @@ impl IconPalette {
         self.missing_icons.push(icon);
     }
 
+    /// Style the bar glyphs were loaded in; `bar_char` levels run up to its `levels()`.
+    pub fn bar_style(&self) -> BarStyle {
+        self.bar_style
+    }
+
     pub fn bar_char(&self, level: usize) -> Option<char> {
         self.bar_chars.get(level).and_then(|ch| *ch)
     }
@@ -90,14 +207,30 @@ impl IconPalette {
         self.heartbeat_char
     }
 
+    /// Glyph for the countdown level that was requested, if a slot was free for it.
+    pub fn countdown_char(&self) -> Option<char> {
+        self.countdown_char
+    }
+
     pub fn icon_char(&self, icon: Icon) -> Option<char> {
         self.icon_chars.get(&icon).copied()
     }
+
+    pub fn big_char(&self, segment: BigSegment) -> Option<char> {
+        self.big_chars.get(&segment).copied()
+    }
+
+    /// Slot holding a payload-defined bitmap, if one was free for it.
+    pub fn custom_char(&self, rows: &[u8; 8]) -> Option<char> {
+        self.custom_chars.get(rows).copied()
+    }
 }
 
 pub struct IconBank {
     slots: [Option<SlotEntry>; MAX_SLOTS],
     next_stamp: u64,
+    /// `[render].bar_style`, used unless a frame asks for another.
+    bar_style: BarStyle,
 }
 
 impl Default for IconBank {
@@ -111,9 +244,14 @@ impl IconBank {
         Self {
             slots: [None; MAX_SLOTS],
             next_stamp: 0,
+            bar_style: BarStyle::default(),
         }
     }
 
+    pub fn set_bar_style(&mut self, style: BarStyle) {
+        self.bar_style = style;
+    }
+
     pub fn build_palette<W: GlyphWriter>(
         &mut self,
         writer: &mut W,
@@ -122,9 +260,33 @@ impl IconBank {
         let mut palette = IconPalette::new();
         let mut required: Vec<GlyphKind> = Vec::new();
 
-        if request.bar_required {
-            for level in 0..BAR_LEVEL_COUNT {
-                required.push(GlyphKind::Bar(level as u8));
+        // Big digits are the page itself, so their segments claim slots before any overlay.
+        if let Some(text) = request.big_digits {
+            for segment in text
+                .chars()
+                .filter_map(big_glyph)
+                .flat_map(|(top, bottom)| top.cells().chain(bottom.cells()))
+                .flatten()
+            {
+                if !required.contains(&GlyphKind::Big(segment)) {
+                    required.push(GlyphKind::Big(segment));
+                }
+            }
+        }
+
+        if let Some(bar) = request.bar {
+            let style = bar.style.unwrap_or(self.bar_style);
+            palette.bar_style = style;
+            if style == BarStyle::Vertical {
+                // A single cell only ever needs the one level it shows; level 0 is a blank.
+                let level = vertical_bar_level(bar.percent);
+                if level > 0 {
+                    required.push(GlyphKind::Bar(style, level));
+                }
+            } else {
+                for level in 0..=style.levels() {
+                    required.push(GlyphKind::Bar(style, level));
+                }
             }
         }
 
@@ -132,6 +294,10 @@ impl IconBank {
             required.push(GlyphKind::Heartbeat);
         }
 
+        if let Some(level) = request.countdown {
+            required.push(GlyphKind::Countdown(level));
+        }
+
         for icon in request.icons {
             if icon.bitmap().is_some() {
                 required.push(GlyphKind::Icon(*icon));
@@ -140,6 +306,12 @@ impl IconBank {
             }
         }
 
+        for rows in request.custom {
+            if !required.contains(&GlyphKind::Custom(*rows)) {
+                required.push(GlyphKind::Custom(*rows));
+            }
+        }
+
         let required_set: HashSet<GlyphKind> = required.iter().copied().collect();
         for kind in required {
             match self.ensure_glyph(kind, &required_set, writer)? {
@@ -238,21 +410,64 @@ impl IconBank {
 
 fn bitmap_for(kind: GlyphKind) -> Option<[u8; 8]> {
     match kind {
-        GlyphKind::Bar(level) => BAR_BITMAPS.get(level as usize).copied(),
+        GlyphKind::Bar(style, level) => bar_bitmap(style, level),
         GlyphKind::Heartbeat => Icon::Heart.bitmap(),
+        GlyphKind::Countdown(level) => level
+            .checked_sub(1)
+            .and_then(|idx| COUNTDOWN_BITMAPS.get(idx as usize))
+            .copied(),
         GlyphKind::Icon(icon) => icon.bitmap(),
+        GlyphKind::Big(segment) => Some(segment.bitmap()),
+        GlyphKind::Custom(rows) => Some(rows),
+    }
+}
+
+fn bar_bitmap(style: BarStyle, level: u8) -> Option<[u8; 8]> {
+    match style {
+        BarStyle::Solid => BAR_BITMAPS.get(level as usize).copied(),
+        BarStyle::Segmented => SEGMENTED_BAR_ROWS.get(level as usize).map(|row| [*row; 8]),
+        BarStyle::Vertical => (1..=8).contains(&level).then(|| {
+            let mut bitmap = [0u8; 8];
+            for row in bitmap.iter_mut().skip(8 - level as usize) {
+                *row = 0x1f;
+            }
+            bitmap
+        }),
+        BarStyle::Block => match level {
+            0 => Some([0; 8]),
+            1 => Some([0x1f; 8]),
+            _ => None,
+        },
     }
 }
 
+/// Fill level (0-8) of a vertical bar cell showing `percent`.
+pub fn vertical_bar_level(percent: u8) -> u8 {
+    ((u16::from(percent.min(100)) * 8 + 50) / 100) as u8
+}
+
 fn slot_to_char(idx: usize) -> char {
     char::from_u32((idx & 0xFF) as u32).unwrap_or(' ')
 }
 
+/// Bar a frame shows: its fill and the payload's style override, if any.
+#[derive(Clone, Copy)]
+pub struct BarRequest {
+    pub percent: u8,
+    pub style: Option<BarStyle>,
+}
+
 #[derive(Clone, Copy)]
 pub struct PaletteRequest<'a> {
-    pub bar_required: bool,
+    pub bar: Option<BarRequest>,
     pub heartbeat: bool,
+    /// Countdown level (1..=`COUNTDOWN_LEVELS`) to load, if the frame is expiring.
+    pub countdown: Option<u8>,
     pub icons: &'a [Icon],
+    /// Text drawn in the big-digit font; loads only the segments its glyphs use.
+    pub big_digits: Option<&'a str>,
+    /// Payload-defined bitmaps the frame shows, loaded after everything else.
+    pub custom: &'a [[u8; 8]],
 }
 
 impl Default for IconPalette {
@@ -283,9 +498,12 @@ mod tests {
         let mut writer = TestWriter::default();
         let icon_list = [Icon::Battery];
         let request = PaletteRequest {
-            bar_required: false,
+            bar: None,
             heartbeat: false,
+            countdown: None,
             icons: &icon_list,
+            big_digits: None,
+            custom: &[],
         };
 
         let palette = bank.build_palette(&mut writer, request).unwrap();
@@ -319,9 +537,15 @@ mod tests {
             .build_palette(
                 &mut writer,
                 PaletteRequest {
-                    bar_required: true,
+                    bar: Some(BarRequest {
+                        percent: 50,
+                        style: None,
+                    }),
                     heartbeat: true,
+                    countdown: None,
                     icons: &icons,
+                    big_digits: None,
+                    custom: &[],
                 },
             )
             .unwrap();
@@ -334,4 +558,89 @@ mod tests {
             .iter()
             .all(|icon| icons[1..].contains(icon)));
     }
+
+    #[test]
+    fn custom_bitmaps_share_slots_and_evict_the_oldest() {
+        let mut bank = IconBank::new();
+        let mut writer = TestWriter::default();
+        let request = |custom| PaletteRequest {
+            bar: None,
+            heartbeat: false,
+            countdown: None,
+            icons: &[],
+            big_digits: None,
+            custom,
+        };
+        let glyphs: Vec<[u8; 8]> = (0..9u8).map(|idx| [idx; 8]).collect();
+
+        let twice = [glyphs[0], glyphs[0]];
+        let palette = bank.build_palette(&mut writer, request(&twice)).unwrap();
+        assert_eq!(palette.custom_char(&glyphs[0]), Some('\0'));
+        assert_eq!(writer.writes.len(), 1, "identical bitmaps share a slot");
+
+        bank.build_palette(&mut writer, request(&glyphs[..8]))
+            .unwrap();
+        assert_eq!(writer.writes.len(), 8);
+        // Slot 0 holds the least recently drawn glyph once the rest were touched again.
+        bank.build_palette(&mut writer, request(&glyphs[1..8]))
+            .unwrap();
+        let palette = bank
+            .build_palette(&mut writer, request(&glyphs[8..]))
+            .unwrap();
+        assert_eq!(palette.custom_char(&glyphs[8]), Some('\0'));
+        assert_eq!(writer.writes.last(), Some(&(0, glyphs[8])));
+    }
+
+    #[test]
+    fn vertical_bars_load_only_the_level_shown() {
+        let mut bank = IconBank::new();
+        bank.set_bar_style(BarStyle::Vertical);
+        let mut writer = TestWriter::default();
+        let request = |percent| PaletteRequest {
+            bar: Some(BarRequest {
+                percent,
+                style: None,
+            }),
+            heartbeat: false,
+            countdown: None,
+            icons: &[],
+            big_digits: None,
+            custom: &[],
+        };
+
+        let palette = bank.build_palette(&mut writer, request(60)).unwrap();
+        assert_eq!(vertical_bar_level(60), 5);
+        assert!(palette.bar_char(5).is_some());
+        assert_eq!(
+            writer.writes,
+            vec![(0, [0, 0, 0, 0x1f, 0x1f, 0x1f, 0x1f, 0x1f])]
+        );
+
+        let palette = bank.build_palette(&mut writer, request(2)).unwrap();
+        assert_eq!(palette.bar_char(0), None, "an empty gauge needs no glyph");
+        assert_eq!(writer.writes.len(), 1);
+    }
+
+    #[test]
+    fn countdown_levels_share_one_slot_at_a_time() {
+        let mut bank = IconBank::new();
+        let mut writer = TestWriter::default();
+        let request = |level| PaletteRequest {
+            bar: None,
+            heartbeat: false,
+            countdown: Some(level),
+            icons: &[],
+            big_digits: None,
+            custom: &[],
+        };
+
+        let palette = bank.build_palette(&mut writer, request(4)).unwrap();
+        assert!(palette.countdown_char().is_some());
+        assert_eq!(writer.writes.last().unwrap().1, COUNTDOWN_BITMAPS[3]);
+        bank.build_palette(&mut writer, request(1)).unwrap();
+        assert_eq!(writer.writes.last().unwrap().1, COUNTDOWN_BITMAPS[0]);
+
+        let palette = bank.build_palette(&mut writer, request(0)).unwrap();
+        assert_eq!(palette.countdown_char(), None, "level 0 has no glyph");
+    }
 }
@@ -1,15 +1,29 @@
 use crate::{
-    config::{DisplayDriver, Pcf8574Addr},
+    config::{DisplayDriver, LcdGpioConfig, Pcf8574Addr},
+    display::{
+        backend::DisplayBackend,
+        charmap::{glass_char, CharMap},
+        terminal::{preview_rows, TerminalLcd},
+    },
     Error, Result,
 };
+use std::{
+    borrow::Cow,
+    ops::Range,
+    time::{Duration, Instant},
+};
 
 #[cfg(target_os = "linux")]
 use crate::lcd_driver::{
     self,
     external::ExternalHd44780,
+    gpio::GpioBus,
     pcf8574::{I2cdevBus, RppalBus},
+    ssd1306::{self, Controller, Ssd1306},
 };
 #[cfg(target_os = "linux")]
+use crate::serial::backoff::BackoffController;
+#[cfg(target_os = "linux")]
 use linux_embedded_hal::I2cdev;
 #[cfg(target_os = "linux")]
 use rppal::i2c::I2c as RppalI2c;
@@ -24,8 +38,8 @@ pub const BATTERY_CHAR: char = '\u{7}';
 pub const CGRAM_FREE_CHAR: char = BATTERY_CHAR;
 pub const WIFI_CHAR: char = 'w';
 
-#[cfg(target_os = "linux")]
-const BAR_GLYPHS: [[&str; 8]; 8] = [
+/// What CGRAM holds after init: the bar levels, the heartbeat and the battery.
+pub(crate) const BAR_GLYPHS: [[&str; 8]; 8] = [
     [
         "00000", "00000", "00000", "00000", "00000", "00000", "00000", "00000",
     ],
@@ -58,6 +72,12 @@ const PCF8574_ADDR_CANDIDATES: [u8; 8] = [0x27, 0x26, 0x25, 0x24, 0x23, 0x22, 0x
 #[cfg(target_os = "linux")]
 const I2CDEV_PATHS: [&str; 2] = ["/dev/i2c-1", "/dev/i2c-0"];
 
+/// Re-init backoff after the display stops answering, matching the serial reconnect defaults.
+#[cfg(target_os = "linux")]
+const REINIT_BACKOFF_INITIAL_MS: u64 = 500;
+#[cfg(target_os = "linux")]
+const REINIT_BACKOFF_MAX_MS: u64 = 10_000;
+
 #[cfg(target_os = "linux")]
 fn discover_i2cdev_paths(dev_dir: &std::path::Path) -> Vec<PathBuf> {
     // Keep the old, Pi-friendly defaults first, then fall back to scanning /dev for
@@ -147,6 +167,17 @@ impl StubState {
     }
 }
 
+/// How the hardware was opened, so a geometry change or a re-init can open it again.
+#[cfg(target_os = "linux")]
+struct Hardware {
+    /// Address in use; a geometry change keeps it.
+    addr: u8,
+    /// Address as configured; a re-init after a replug probes again when it is `auto`.
+    requested: Pcf8574Addr,
+    driver: DisplayDriver,
+    gpio: LcdGpioConfig,
+}
+
 #[cfg(target_os = "linux")]
 pub enum LcdBus {
     Rppal(RppalI2c),
@@ -160,8 +191,65 @@ pub struct Lcd {
     rows: u8,
     stub: StubState,
     observe_stub: bool,
+    /// What the glass currently shows, one padded row per line; used to diff partial updates.
+    shadow: Vec<Vec<char>>,
+    echo_frames: bool,
+    backlight_on: bool,
+    blink_on: bool,
+    /// How long the most recent full-frame write took; feeds the adaptive render throttle.
+    last_write: Option<Duration>,
+    /// Estimated I2C bytes sent so far, and by the most recent full-frame write.
+    bus_bytes: u64,
+    last_write_bytes: Option<u64>,
+    /// Box-drawn mock on stdout (`--dry-run`); only ever set on a stub.
+    terminal: Option<TerminalLcd>,
+    /// Glyphs written to CGRAM since the bar glyphs were loaded, reloaded after a re-init.
+    glyphs: [Option<[u8; 8]>; 8],
+    /// Unicode to character-ROM translation for every row written; `None` sends chars as is.
+    charmap: Option<CharMap>,
     #[cfg(target_os = "linux")]
     driver: Option<DriverBackend>,
+    #[cfg(target_os = "linux")]
+    hardware: Option<Hardware>,
+    /// Set while the hardware is not answering: the driver is dropped, writes only reach the
+    /// shadow, and [`Lcd::recover`] retries the init on this backoff.
+    #[cfg(target_os = "linux")]
+    lost: Option<BackoffController>,
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// PCF8574 writes per HD44780 command or data byte: two nibbles, each latched with E high and
+/// then E low.
+pub const I2C_BYTES_PER_LCD_BYTE: u64 = 4;
+/// Controller bytes to load one CGRAM glyph: the CGRAM address, eight rows and the cursor move
+/// back to DDRAM.
+const GLYPH_LCD_BYTES: usize = 10;
+
+/// Unchanged cells between two changed runs that are rewritten rather than skipped: jumping
+/// over them costs a cursor move, one command byte, which is no cheaper than one cell.
+const REWRITE_GAP: usize = 1;
+
+/// Column ranges where `target` differs from `current`, with short unchanged gaps folded in.
+fn changed_runs(current: &[char], target: &[char]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for idx in (0..target.len()).filter(|&idx| current.get(idx) != Some(&target[idx])) {
+        match runs.last_mut() {
+            Some(run) if idx - run.end <= REWRITE_GAP => run.end = idx + 1,
+            _ => runs.push(idx..idx + 1),
+        }
+    }
+    runs
+}
+
+fn blank_shadow(cols: u8, rows: u8) -> Vec<Vec<char>> {
+    vec![vec![' '; cols as usize]; rows as usize]
 }
 
 fn observe_lcd_stub_enabled() -> bool {
@@ -181,30 +269,70 @@ impl Lcd {
             rows,
             stub: StubState::new(),
             observe_stub: observe_lcd_stub_enabled(),
+            shadow: blank_shadow(cols, rows),
+            echo_frames: false,
+            backlight_on: true,
+            blink_on: false,
+            last_write: None,
+            bus_bytes: 0,
+            last_write_bytes: None,
+            terminal: None,
+            glyphs: [None; 8],
+            charmap: None,
             #[cfg(target_os = "linux")]
             driver: None,
+            #[cfg(target_os = "linux")]
+            hardware: None,
+            #[cfg(target_os = "linux")]
+            lost: None,
         }
     }
 
+    /// Stub that draws every change as a box in the terminal instead of touching I2C.
+    pub fn new_terminal(cols: u8, rows: u8) -> Self {
+        let mut lcd = Self::new_stub(cols, rows);
+        lcd.terminal = Some(TerminalLcd::new());
+        lcd
+    }
+
     pub fn new(
         cols: u8,
         rows: u8,
         pcf_addr: Pcf8574Addr,
         display_driver: DisplayDriver,
+        gpio: LcdGpioConfig,
     ) -> Result<Self> {
         #[cfg(target_os = "linux")]
         {
             let stub = StubState::new();
-            match DriverBackend::new(cols, rows, pcf_addr, display_driver) {
-                Ok((mut driver, addr)) => {
-                    eprintln!("pcf8574 addr: 0x{addr:02x}");
-                    driver.load_bar_glyphs()?;
+            match DriverBackend::reinit(cols, rows, pcf_addr.clone(), display_driver, gpio) {
+                Ok((driver, addr)) => {
+                    if display_driver != DisplayDriver::Gpio {
+                        eprintln!("pcf8574 addr: 0x{addr:02x}");
+                    }
                     Ok(Self {
                         cols,
                         rows,
                         stub,
                         observe_stub: observe_lcd_stub_enabled(),
+                        shadow: blank_shadow(cols, rows),
+                        echo_frames: false,
+                        backlight_on: true,
+                        blink_on: false,
+                        last_write: None,
+                        bus_bytes: 0,
+                        last_write_bytes: None,
+                        terminal: None,
+                        glyphs: [None; 8],
+                        charmap: None,
                         driver: Some(driver),
+                        hardware: Some(Hardware {
+                            addr,
+                            requested: pcf_addr,
+                            driver: display_driver,
+                            gpio,
+                        }),
+                        lost: None,
                     })
                 }
                 Err(err) => {
@@ -217,16 +345,74 @@ impl Lcd {
 
         #[cfg(not(target_os = "linux"))]
         {
-            let _ = (pcf_addr, display_driver);
+            let _ = (pcf_addr, display_driver, gpio);
             Ok(Self {
                 cols,
                 rows,
                 stub: StubState::new(),
                 observe_stub: observe_lcd_stub_enabled(),
+                shadow: blank_shadow(cols, rows),
+                echo_frames: false,
+                backlight_on: true,
+                blink_on: false,
+                last_write: None,
+                bus_bytes: 0,
+                last_write_bytes: None,
+                terminal: None,
+                glyphs: [None; 8],
+                charmap: None,
             })
         }
     }
 
+    /// Print every drawn frame to stdout (`--echo-frames`) so headless units can be checked
+    /// from the journal.
+    pub fn set_echo_frames(&mut self, on: bool) {
+        self.echo_frames = on;
+    }
+
+    /// Translate text through `charmap` before it reaches the glass.
+    pub fn set_charmap(&mut self, charmap: Option<CharMap>) {
+        self.charmap = charmap;
+    }
+
+    fn translate<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match &self.charmap {
+            Some(charmap) => charmap.translate(text),
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    fn echo_frame(&self) {
+        if !self.echo_frames {
+            return;
+        }
+        let rows: Vec<String> = (0..self.rows)
+            .map(|row| {
+                let mut line = self.shadow_row(row);
+                if self.charmap.is_some() {
+                    line = line
+                        .chars()
+                        .map(|ch| glass_char(ch).unwrap_or(ch))
+                        .collect();
+                }
+                format!("[{line}]")
+            })
+            .collect();
+        println!(
+            "frame {} backlight={} blink={}",
+            rows.join(" "),
+            on_off(self.backlight_on),
+            on_off(self.blink_on)
+        );
+    }
+
+    fn present_terminal(&mut self) {
+        if let Some(terminal) = self.terminal.as_mut() {
+            terminal.present(&self.shadow, self.backlight_on, self.blink_on);
+        }
+    }
+
     fn observe_stub_snapshot(&self) {
         if !self.observe_stub {
             return;
@@ -243,38 +429,52 @@ impl Lcd {
     }
 
     pub fn clear(&mut self) -> Result<()> {
+        self.shadow = blank_shadow(self.cols, self.rows);
+        // Clear, then home.
+        self.count_lcd_bytes(2);
         #[cfg(target_os = "linux")]
         {
             if let Some(driver) = &mut self.driver {
-                return driver.clear();
+                let out = driver.clear();
+                return self.checked(out);
             }
         }
         let out = self.stub.clear();
         self.observe_stub_snapshot();
+        self.present_terminal();
         out
     }
 
     pub fn set_backlight(&mut self, on: bool) -> Result<()> {
+        self.backlight_on = on;
+        // The backlight is an expander pin, so this is one raw write.
+        self.bus_bytes += 1;
         #[cfg(target_os = "linux")]
         {
             if let Some(driver) = &mut self.driver {
-                return driver.set_backlight(on);
+                let out = driver.set_backlight(on);
+                return self.checked(out);
             }
         }
         let out = self.stub.set_backlight(on);
         self.observe_stub_snapshot();
+        self.present_terminal();
         out
     }
 
     pub fn set_blink(&mut self, on: bool) -> Result<()> {
+        self.blink_on = on;
+        self.count_lcd_bytes(1);
         #[cfg(target_os = "linux")]
         {
             if let Some(driver) = &mut self.driver {
-                return driver.set_blink(on);
+                let out = driver.set_blink(on);
+                return self.checked(out);
             }
         }
         let out = self.stub.set_blink(on);
         self.observe_stub_snapshot();
+        self.present_terminal();
         out
     }
 
@@ -286,37 +486,316 @@ impl Lcd {
             )));
         }
 
+        let content = self.translate(content);
         let trimmed = content.chars().take(self.cols as usize).collect::<String>();
+        let mut written = 0;
+        for (cell, ch) in self.shadow[row as usize].iter_mut().zip(trimmed.chars()) {
+            *cell = ch;
+            written += 1;
+        }
+        // The cursor move, the text, and the driver's wrap to the next row on a full one.
+        let wraps = usize::from(written == self.cols as usize);
+        self.count_lcd_bytes(1 + written + wraps);
 
         #[cfg(target_os = "linux")]
         {
             if let Some(driver) = &mut self.driver {
-                return driver.write_line(row, &trimmed);
+                let out = driver.write_line(row, &trimmed);
+                return self.checked(out);
             }
         }
         let out = self.stub.write_line(row, &trimmed);
         self.observe_stub_snapshot();
+        self.present_terminal();
         out
     }
 
-    /// Convenience to write both lines back-to-back to reduce flicker.
-    pub fn write_lines(&mut self, line1: &str, line2: &str) -> Result<()> {
-        self.write_line(0, line1)?;
-        self.write_line(1, line2)
+    /// Bring one row to `content` (padded to the width) by writing only the cells that differ
+    /// from the shadow. Returns the number of cells sent to the glass.
+    pub fn write_row_diff(&mut self, row: u8, content: &str) -> Result<usize> {
+        let sent = self.diff_row(row, content)?;
+        if sent > 0 {
+            self.echo_frame();
+        }
+        Ok(sent)
+    }
+
+    fn diff_row(&mut self, row: u8, content: &str) -> Result<usize> {
+        if row >= self.rows {
+            return Err(Error::InvalidArgs(format!(
+                "row {row} out of bounds for display with {} rows",
+                self.rows
+            )));
+        }
+        let cols = self.cols as usize;
+        let mut target: Vec<char> = self.translate(content).chars().take(cols).collect();
+        target.resize(cols, ' ');
+        let runs = changed_runs(&self.shadow[row as usize], &target);
+        if runs.is_empty() {
+            return Ok(0);
+        }
+        let sent = runs.iter().map(|run| run.len()).sum();
+        // One cursor move per run, plus the driver's wrap after a run that ends the row.
+        let wraps = runs.iter().filter(|run| run.end == cols).count();
+        self.count_lcd_bytes(runs.len() + sent + wraps);
+
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(driver) = &mut self.driver {
+                let mut out = Ok(());
+                for run in &runs {
+                    let span: String = target[run.clone()].iter().collect();
+                    out = driver.write_at(run.start as u8, row, &span);
+                    if out.is_err() {
+                        break;
+                    }
+                }
+                self.shadow[row as usize] = target;
+                self.checked(out)?;
+                return Ok(sent);
+            }
+        }
+        self.shadow[row as usize] = target;
+        let line = self.shadow_row(row);
+        self.stub.write_line(row, line.trim_end())?;
+        self.observe_stub_snapshot();
+        self.present_terminal();
+        Ok(sent)
+    }
+
+    /// Current shadow contents of `row`, padded to the display width.
+    pub fn shadow_row(&self, row: u8) -> String {
+        self.shadow
+            .get(row as usize)
+            .map(|cells| cells.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Bring each row to its line, padded to the width, writing only the cells that changed
+    /// since the last frame; lines past the last row are dropped. Nothing has to be cleared
+    /// first, so a page change does not blank the glass.
+    pub fn write_lines(&mut self, lines: &[&str]) -> Result<()> {
+        let started = Instant::now();
+        let bytes_before = self.bus_bytes;
+        for (row, line) in (0..self.rows).zip(lines) {
+            self.diff_row(row, line)?;
+        }
+        self.last_write = Some(started.elapsed());
+        self.last_write_bytes = Some(self.bus_bytes - bytes_before);
+        self.echo_frame();
+        Ok(())
+    }
+
+    /// Duration of the last `write_lines` call, cleared once read.
+    pub fn take_write_time(&mut self) -> Option<Duration> {
+        self.last_write.take()
+    }
+
+    /// Estimated I2C bytes the last `write_lines` call sent, cleared once read.
+    pub fn take_write_bytes(&mut self) -> Option<u64> {
+        self.last_write_bytes.take()
+    }
+
+    /// Estimated I2C bytes sent since the display was opened. Every backend is counted as an
+    /// HD44780 on a PCF8574 backpack would need, so the stub and `--dry-run` report the same
+    /// numbers as the glass.
+    pub fn bus_bytes(&self) -> u64 {
+        self.bus_bytes
+    }
+
+    /// Shadow rows with CGRAM slots and ROM codes turned back into printable chars.
+    pub fn screen(&self) -> Vec<String> {
+        preview_rows(&self.shadow, &self.glyphs)
+    }
+
+    /// Shadow rows as sent to the glass, with the CGRAM bitmaps loaded since the bar glyphs;
+    /// what a snapshot needs to draw the display pixel for pixel.
+    pub fn glass(&self) -> (&[Vec<char>], &[Option<[u8; 8]>; 8]) {
+        (&self.shadow, &self.glyphs)
+    }
+
+    pub fn backlight_on(&self) -> bool {
+        self.backlight_on
+    }
+
+    fn count_lcd_bytes(&mut self, lcd_bytes: usize) {
+        self.bus_bytes += lcd_bytes as u64 * I2C_BYTES_PER_LCD_BYTE;
     }
 
     pub(crate) fn write_custom_char(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<()> {
+        if let Some(glyph) = self.glyphs.get_mut(slot as usize) {
+            *glyph = Some(*bitmap);
+        }
+        self.count_lcd_bytes(GLYPH_LCD_BYTES);
         #[cfg(target_os = "linux")]
         {
             if let Some(driver) = &mut self.driver {
-                return driver.custom_char(slot, bitmap);
+                let out = driver.custom_char(slot, bitmap);
+                return self.checked(out);
             }
         }
         let out = self.stub.custom_char(slot, bitmap);
         self.observe_stub_snapshot();
+        if let Some(terminal) = self.terminal.as_mut() {
+            terminal.load_glyph(slot, bitmap);
+        }
+        self.present_terminal();
         out
     }
 
+    /// Switch to a `cols` x `rows` geometry without restarting. Hardware is initialised again
+    /// with the new dimensions and the preloaded CGRAM glyphs. The old driver is released
+    /// first; on failure the old geometry is opened again and the error returned. The display
+    /// comes back blank with the backlight as it was.
+    pub fn reconfigure(&mut self, cols: u8, rows: u8) -> Result<()> {
+        if (cols, rows) == (self.cols, self.rows) {
+            return Ok(());
+        }
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(hardware) = &self.hardware {
+                let (addr, kind, gpio) = (hardware.addr, hardware.driver, hardware.gpio);
+                let backlight = self.backlight_on;
+                let opened = replace_driver(&mut self.driver, || {
+                    let (mut driver, _) =
+                        DriverBackend::reinit(cols, rows, Pcf8574Addr::Addr(addr), kind, gpio)?;
+                    driver.set_backlight(backlight)?;
+                    Ok(driver)
+                });
+                if let Err(err) = opened {
+                    // The old driver is already gone; bring the old geometry back up.
+                    match self.reopen() {
+                        Ok(_) => self.lost = None,
+                        Err(reopen_err) => {
+                            eprintln!(
+                                "warning: lcd re-open at {}x{} failed ({reopen_err}); re-initialising with backoff",
+                                self.cols, self.rows
+                            );
+                            self.mark_lost();
+                        }
+                    }
+                    return Err(err);
+                }
+                self.lost = None;
+            }
+        }
+        self.cols = cols;
+        self.rows = rows;
+        self.glyphs = [None; 8];
+        self.blink_on = false;
+        self.stub.blink_on = false;
+        if let Some(terminal) = self.terminal.as_mut() {
+            terminal.reset_glyphs();
+        }
+        self.clear()
+    }
+
+    /// Re-open the display after a failed write, once the backoff allows another attempt. The
+    /// address is probed again when configured as `auto`, CGRAM gets the bar glyphs and every
+    /// glyph written since, and the shadow is drawn back. Returns `true` when the display came
+    /// back.
+    pub fn recover(&mut self, now: Instant) -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            if !self
+                .lost
+                .as_ref()
+                .is_some_and(|backoff| backoff.should_retry(now))
+            {
+                return false;
+            }
+            match self.reopen() {
+                Ok(addr) => {
+                    eprintln!("lcd: display answering again at 0x{addr:02x}; redrawn");
+                    self.lost = None;
+                    true
+                }
+                Err(err) => {
+                    if let Some(backoff) = self.lost.as_mut() {
+                        backoff.mark_failure(now);
+                        eprintln!(
+                            "warning: lcd re-init failed ({err}); next try in {}ms",
+                            backoff.until_retry(now).as_millis()
+                        );
+                    }
+                    false
+                }
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = now;
+            false
+        }
+    }
+
+    /// Whether the hardware stopped answering and has not come back yet.
+    pub fn is_lost(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            self.lost.is_some()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            false
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn reopen(&mut self) -> Result<u8> {
+        let Some(hardware) = &self.hardware else {
+            return Err(Error::InvalidArgs("no display hardware to re-open".into()));
+        };
+        let (requested, kind, gpio) = (hardware.requested.clone(), hardware.driver, hardware.gpio);
+        let (cols, rows, glyphs) = (self.cols, self.rows, self.glyphs);
+        let (backlight, blink) = (self.backlight_on, self.blink_on);
+        let lines: Vec<String> = (0..rows).map(|row| self.shadow_row(row)).collect();
+        let mut addr = 0;
+        replace_driver(&mut self.driver, || {
+            let (mut driver, found) = DriverBackend::reinit(cols, rows, requested, kind, gpio)?;
+            for (slot, glyph) in glyphs.iter().enumerate() {
+                if let Some(bitmap) = glyph {
+                    driver.custom_char(slot as u8, bitmap)?;
+                }
+            }
+            driver.set_backlight(backlight)?;
+            driver.set_blink(blink)?;
+            for (row, line) in lines.iter().enumerate() {
+                driver.write_line(row as u8, line)?;
+            }
+            addr = found;
+            Ok(driver)
+        })?;
+        if let Some(hardware) = self.hardware.as_mut() {
+            hardware.addr = addr;
+        }
+        Ok(addr)
+    }
+
+    /// Pass a hardware write result through. A failure drops the driver and starts the re-init
+    /// backoff instead of failing the caller, so the render loop keeps running on the shadow.
+    #[cfg(target_os = "linux")]
+    fn checked(&mut self, out: Result<()>) -> Result<()> {
+        let Err(err) = out else {
+            return Ok(());
+        };
+        if self.hardware.is_none() {
+            return Err(err);
+        }
+        eprintln!("warning: lcd write failed ({err}); re-initialising with backoff");
+        self.mark_lost();
+        Ok(())
+    }
+
+    /// Drop the driver and start the re-init backoff that [`Lcd::recover`] works through.
+    #[cfg(target_os = "linux")]
+    fn mark_lost(&mut self) {
+        self.driver = None;
+        let mut backoff = BackoffController::new(REINIT_BACKOFF_INITIAL_MS, REINIT_BACKOFF_MAX_MS);
+        backoff.mark_failure(Instant::now());
+        self.lost = Some(backoff);
+    }
+
     pub fn cols(&self) -> u8 {
         self.cols
     }
@@ -355,7 +834,24 @@ impl Lcd {
             rows,
             stub: StubState::new(),
             observe_stub: observe_lcd_stub_enabled(),
+            shadow: blank_shadow(cols, rows),
+            echo_frames: false,
+            backlight_on: true,
+            blink_on: false,
+            last_write: None,
+            bus_bytes: 0,
+            last_write_bytes: None,
+            terminal: None,
+            glyphs: [None; 8],
+            charmap: None,
             driver: Some(driver),
+            hardware: Some(Hardware {
+                addr,
+                requested: Pcf8574Addr::Addr(addr),
+                driver: display_driver,
+                gpio: LcdGpioConfig::default(),
+            }),
+            lost: None,
         })
     }
 
@@ -376,6 +872,74 @@ impl Lcd {
     }
 }
 
+impl DisplayBackend for Lcd {
+    fn cols(&self) -> u8 {
+        Lcd::cols(self)
+    }
+
+    fn rows(&self) -> u8 {
+        Lcd::rows(self)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        Lcd::clear(self)
+    }
+
+    fn write_line(&mut self, row: u8, content: &str) -> Result<()> {
+        Lcd::write_line(self, row, content)
+    }
+
+    fn set_backlight(&mut self, on: bool) -> Result<()> {
+        Lcd::set_backlight(self, on)
+    }
+
+    fn set_blink(&mut self, on: bool) -> Result<()> {
+        Lcd::set_blink(self, on)
+    }
+
+    fn custom_char(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<()> {
+        self.write_custom_char(slot, bitmap)
+    }
+
+    fn write_lines(&mut self, lines: &[&str]) -> Result<()> {
+        Lcd::write_lines(self, lines)
+    }
+
+    fn write_row_diff(&mut self, row: u8, content: &str) -> Result<usize> {
+        Lcd::write_row_diff(self, row, content)
+    }
+
+    /// `write_lines` diffs every row against the shadow, so the glass is left as it is rather
+    /// than blanked between frames.
+    fn prepare_redraw(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn take_write_time(&mut self) -> Option<Duration> {
+        Lcd::take_write_time(self)
+    }
+
+    fn take_write_bytes(&mut self) -> Option<u64> {
+        Lcd::take_write_bytes(self)
+    }
+
+    fn bus_bytes(&self) -> u64 {
+        Lcd::bus_bytes(self)
+    }
+
+    fn screen(&self) -> Vec<String> {
+        Lcd::screen(self)
+    }
+
+    fn reconfigure(&mut self, cols: u8, rows: u8) -> Result<()> {
+        Lcd::reconfigure(self, cols, rows)
+    }
+
+    fn recover(&mut self, now: Instant) -> bool {
+        Lcd::recover(self, now)
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn load_bar_glyphs_internal<B: lcd_driver::I2cBus>(
     driver: &mut lcd_driver::Hd44780<B>,
@@ -388,10 +952,22 @@ fn load_bar_glyphs_external(driver: &mut ExternalHd44780) -> Result<()> {
     driver.load_custom_bitmaps(&BAR_GLYPHS)
 }
 
+/// Put the driver `open` returns into `slot`, dropping the current one first: a GPIO bus holds
+/// its pins until it is dropped, so a second bus opened next to it would be refused them. `slot`
+/// is left empty when `open` fails.
+#[cfg(target_os = "linux")]
+fn replace_driver<D>(slot: &mut Option<D>, open: impl FnOnce() -> Result<D>) -> Result<()> {
+    *slot = None;
+    *slot = Some(open()?);
+    Ok(())
+}
+
 #[cfg(target_os = "linux")]
 enum DriverBackend {
     Internal(InternalDriver),
     External(ExternalHd44780),
+    Oled(OledDriver),
+    Gpio(Box<lcd_driver::Hd44780<GpioBus<rppal::gpio::OutputPin>>>),
 }
 
 #[cfg(target_os = "linux")]
@@ -464,6 +1040,13 @@ impl InternalDriver {
         }
     }
 
+    fn write_at(&mut self, col: u8, row: u8, text: &str) -> Result<()> {
+        match self {
+            InternalDriver::Rppal(driver) => driver.write_at(col, row, text),
+            InternalDriver::I2cdev(driver) => driver.write_at(col, row, text),
+        }
+    }
+
     fn load_bar_glyphs(&mut self) -> Result<()> {
         match self {
             InternalDriver::Rppal(driver) => load_bar_glyphs_internal(driver),
@@ -479,6 +1062,79 @@ impl InternalDriver {
     }
 }
 
+#[cfg(target_os = "linux")]
+enum OledDriver {
+    Rppal(Ssd1306<RppalBus>),
+    I2cdev(Ssd1306<I2cdevBus>),
+}
+
+#[cfg(target_os = "linux")]
+impl OledDriver {
+    fn clear(&mut self) -> Result<()> {
+        match self {
+            OledDriver::Rppal(driver) => driver.clear(),
+            OledDriver::I2cdev(driver) => driver.clear(),
+        }
+    }
+
+    /// No backlight to switch, so the panel itself goes dark.
+    fn set_backlight(&mut self, on: bool) -> Result<()> {
+        match (self, on) {
+            (OledDriver::Rppal(driver), true) => driver.display_on(),
+            (OledDriver::Rppal(driver), false) => driver.display_off(),
+            (OledDriver::I2cdev(driver), true) => driver.display_on(),
+            (OledDriver::I2cdev(driver), false) => driver.display_off(),
+        }
+    }
+
+    fn write_line(&mut self, row: u8, text: &str) -> Result<()> {
+        match self {
+            OledDriver::Rppal(driver) => driver.write_line(row, text),
+            OledDriver::I2cdev(driver) => driver.write_line(row, text),
+        }
+    }
+
+    fn write_at(&mut self, col: u8, row: u8, text: &str) -> Result<()> {
+        match self {
+            OledDriver::Rppal(driver) => driver.write_at(col, row, text),
+            OledDriver::I2cdev(driver) => driver.write_at(col, row, text),
+        }
+    }
+
+    fn load_bar_glyphs(&mut self) -> Result<()> {
+        match self {
+            OledDriver::Rppal(driver) => driver.load_custom_bitmaps(&BAR_GLYPHS),
+            OledDriver::I2cdev(driver) => driver.load_custom_bitmaps(&BAR_GLYPHS),
+        }
+    }
+
+    fn custom_char(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<()> {
+        match self {
+            OledDriver::Rppal(driver) => driver.custom_char(slot, bitmap),
+            OledDriver::I2cdev(driver) => driver.custom_char(slot, bitmap),
+        }
+    }
+}
+
+/// Controller behind an OLED `display_driver`.
+#[cfg(target_os = "linux")]
+fn oled_controller(preference: DisplayDriver) -> Controller {
+    match preference {
+        DisplayDriver::Sh1106 => Controller::Sh1106,
+        _ => Controller::Ssd1306,
+    }
+}
+
+/// Addresses probed for `pcf8574_addr = "auto"`, and the one used when none answers.
+#[cfg(target_os = "linux")]
+fn address_candidates(preference: DisplayDriver) -> (&'static [u8], u8) {
+    if preference.is_oled() {
+        (&ssd1306::ADDR_CANDIDATES, ssd1306::DEFAULT_I2C_ADDR)
+    } else {
+        (&PCF8574_ADDR_CANDIDATES, 0x27)
+    }
+}
+
 #[cfg(target_os = "linux")]
 impl DriverBackend {
     fn new(
@@ -516,10 +1172,17 @@ impl DriverBackend {
                 let external = ExternalHd44780::new_from_rppal(raw, addr, cols, rows)?;
                 Ok(DriverBackend::External(external))
             }
+            DisplayDriver::Ssd1306 | DisplayDriver::Sh1106 => {
+                let oled = Ssd1306::new(bus, addr, oled_controller(preference), cols, rows)?;
+                Ok(DriverBackend::Oled(OledDriver::Rppal(oled)))
+            }
             DisplayDriver::Auto | DisplayDriver::InTree => {
                 let internal = InternalDriver::from_rppal(bus, addr, cols, rows)?;
                 Ok(DriverBackend::Internal(internal))
             }
+            DisplayDriver::Gpio => Err(Error::InvalidArgs(
+                "display_driver = \"gpio\" does not use an I2C bus".into(),
+            )),
         }
     }
 
@@ -536,10 +1199,17 @@ impl DriverBackend {
                 let external = ExternalHd44780::new_from_i2cdev(raw, addr, cols, rows)?;
                 Ok(DriverBackend::External(external))
             }
+            DisplayDriver::Ssd1306 | DisplayDriver::Sh1106 => {
+                let oled = Ssd1306::new(bus, addr, oled_controller(preference), cols, rows)?;
+                Ok(DriverBackend::Oled(OledDriver::I2cdev(oled)))
+            }
             DisplayDriver::Auto | DisplayDriver::InTree => {
                 let internal = InternalDriver::from_i2cdev(bus, addr, cols, rows)?;
                 Ok(DriverBackend::Internal(internal))
             }
+            DisplayDriver::Gpio => Err(Error::InvalidArgs(
+                "display_driver = \"gpio\" does not use an I2C bus".into(),
+            )),
         }
     }
 
@@ -550,8 +1220,9 @@ impl DriverBackend {
         preference: DisplayDriver,
     ) -> Result<(Self, u8)> {
         let mut bus = RppalBus::new_default()?;
+        let (candidates, fallback) = address_candidates(preference);
         let addr = match pcf_addr {
-            Pcf8574Addr::Auto => bus.detect_address(&PCF8574_ADDR_CANDIDATES, 0x27),
+            Pcf8574Addr::Auto => bus.detect_address(candidates, fallback),
             Pcf8574Addr::Addr(addr) => addr,
         };
         let backend = Self::from_rppal_bus(bus, addr, cols, rows, preference)?;
@@ -565,14 +1236,41 @@ impl DriverBackend {
         preference: DisplayDriver,
     ) -> Result<(Self, u8)> {
         let mut bus = Self::open_i2cdev_bus()?;
+        let (candidates, fallback) = address_candidates(preference);
         let addr = match pcf_addr {
-            Pcf8574Addr::Auto => bus.detect_address(&PCF8574_ADDR_CANDIDATES, 0x27),
+            Pcf8574Addr::Auto => bus.detect_address(candidates, fallback),
             Pcf8574Addr::Addr(addr) => addr,
         };
         let backend = Self::from_i2cdev_bus(bus, addr, cols, rows, preference)?;
         Ok((backend, addr))
     }
 
+    /// Open the display from scratch, probing the address again when `pcf_addr` is `auto`, and
+    /// load the bar glyphs into CGRAM. Startup, geometry changes and recovery after a replug or
+    /// bus error all come through here.
+    fn reinit(
+        cols: u8,
+        rows: u8,
+        pcf_addr: Pcf8574Addr,
+        preference: DisplayDriver,
+        gpio: LcdGpioConfig,
+    ) -> Result<(Self, u8)> {
+        let (mut driver, addr) = if preference == DisplayDriver::Gpio {
+            (Self::new_with_gpio(cols, rows, &gpio)?, 0)
+        } else {
+            Self::new(cols, rows, pcf_addr, preference)?
+        };
+        driver.load_bar_glyphs()?;
+        Ok((driver, addr))
+    }
+
+    /// No bus to probe: the pins come from `[lcd.gpio]` and the address is unused.
+    fn new_with_gpio(cols: u8, rows: u8, pins: &LcdGpioConfig) -> Result<Self> {
+        let bus = GpioBus::open(pins)?;
+        let driver = lcd_driver::Hd44780::new(bus, 0, cols, rows)?;
+        Ok(DriverBackend::Gpio(Box::new(driver)))
+    }
+
     fn open_i2cdev_bus() -> Result<I2cdevBus> {
         let mut failures: Vec<String> = Vec::new();
         let candidates = discover_i2cdev_paths(std::path::Path::new("/dev"));
@@ -599,6 +1297,8 @@ impl DriverBackend {
         match self {
             DriverBackend::Internal(driver) => driver.clear(),
             DriverBackend::External(driver) => driver.clear(),
+            DriverBackend::Oled(driver) => driver.clear(),
+            DriverBackend::Gpio(driver) => driver.clear(),
         }
     }
 
@@ -607,6 +1307,9 @@ impl DriverBackend {
             (DriverBackend::Internal(driver), _) => driver.set_backlight(on),
             (DriverBackend::External(driver), true) => driver.backlight_on(),
             (DriverBackend::External(driver), false) => driver.backlight_off(),
+            (DriverBackend::Oled(driver), _) => driver.set_backlight(on),
+            (DriverBackend::Gpio(driver), true) => driver.backlight_on(),
+            (DriverBackend::Gpio(driver), false) => driver.backlight_off(),
         }
     }
 
@@ -615,6 +1318,10 @@ impl DriverBackend {
             (DriverBackend::Internal(driver), _) => driver.set_blink(on),
             (DriverBackend::External(driver), true) => driver.blink_cursor_on(),
             (DriverBackend::External(driver), false) => driver.blink_cursor_off(),
+            // No cursor to blink on an OLED.
+            (DriverBackend::Oled(_), _) => Ok(()),
+            (DriverBackend::Gpio(driver), true) => driver.blink_cursor_on(),
+            (DriverBackend::Gpio(driver), false) => driver.blink_cursor_off(),
         }
     }
 
@@ -622,6 +1329,17 @@ impl DriverBackend {
         match self {
             DriverBackend::Internal(driver) => driver.write_line(row, text),
             DriverBackend::External(driver) => driver.write_line(row, text),
+            DriverBackend::Oled(driver) => driver.write_line(row, text),
+            DriverBackend::Gpio(driver) => driver.write_line(row, text),
+        }
+    }
+
+    fn write_at(&mut self, col: u8, row: u8, text: &str) -> Result<()> {
+        match self {
+            DriverBackend::Internal(driver) => driver.write_at(col, row, text),
+            DriverBackend::External(driver) => driver.write_at(col, row, text),
+            DriverBackend::Oled(driver) => driver.write_at(col, row, text),
+            DriverBackend::Gpio(driver) => driver.write_at(col, row, text),
         }
     }
 
@@ -629,6 +1347,8 @@ impl DriverBackend {
         match self {
             DriverBackend::Internal(driver) => driver.load_bar_glyphs(),
             DriverBackend::External(driver) => load_bar_glyphs_external(driver),
+            DriverBackend::Oled(driver) => driver.load_bar_glyphs(),
+            DriverBackend::Gpio(driver) => driver.load_custom_bitmaps(&BAR_GLYPHS),
         }
     }
 
@@ -636,6 +1356,8 @@ impl DriverBackend {
         match self {
             DriverBackend::Internal(driver) => driver.custom_char(slot, bitmap),
             DriverBackend::External(driver) => driver.custom_char(slot, bitmap),
+            DriverBackend::Oled(driver) => driver.custom_char(slot, bitmap),
+            DriverBackend::Gpio(driver) => driver.custom_char(slot, bitmap),
         }
     }
 }
@@ -691,6 +1413,91 @@ mod tests {
     // They will be properly enabled as part of P4 (LCD driver regression tests)
     // For now, skipping them on development machines without GPIO support
 
+    #[test]
+    fn tracks_backlight_and_blink_for_echo() {
+        let mut lcd = Lcd::new_stub(8, 2);
+        lcd.set_echo_frames(true);
+        lcd.set_backlight(false).unwrap();
+        lcd.set_blink(true).unwrap();
+        lcd.write_lines(&["up", "down"]).unwrap();
+        assert!(!lcd.backlight_on);
+        assert!(lcd.blink_on);
+        assert_eq!(lcd.shadow_row(1), "down    ");
+    }
+
+    #[test]
+    fn row_diff_writes_only_changed_span() {
+        let mut lcd = Lcd::new_stub(8, 2);
+        lcd.write_line(0, "count 09").unwrap();
+        assert_eq!(lcd.write_row_diff(0, "count 10").unwrap(), 2);
+        assert_eq!(lcd.write_row_diff(0, "count 10").unwrap(), 0);
+        assert_eq!(lcd.write_row_diff(0, "cnt").unwrap(), 7);
+        assert_eq!(lcd.shadow_row(0), "cnt     ");
+        assert_eq!(lcd.last_lines().0, "cnt");
+        lcd.clear().unwrap();
+        assert_eq!(lcd.shadow_row(0), " ".repeat(8));
+        assert!(lcd.write_row_diff(2, "x").is_err());
+    }
+
+    #[test]
+    fn frames_overwrite_the_previous_one_cell_by_cell() {
+        let current: Vec<char> = "CPU 42% up 3d".chars().collect();
+        let target: Vec<char> = "CPU 47% up 4d".chars().collect();
+        assert_eq!(changed_runs(&current, &target), vec![5..6, 11..12]);
+        // A single unchanged cell between changes is rewritten instead of skipped.
+        let target: Vec<char> = "CPU 17% up 3d".chars().collect();
+        assert_eq!(changed_runs(&current, &target), vec![4..6]);
+
+        let mut lcd = Lcd::new_stub(8, 2);
+        lcd.write_lines(&["load 1.5", "mem 40%"]).unwrap();
+        lcd.write_lines(&["up", "mem 41%"]).unwrap();
+        assert_eq!(lcd.shadow_row(0), "up      ");
+        assert_eq!(lcd.shadow_row(1), "mem 41% ");
+        assert_eq!(lcd.clear_count(), 0);
+        assert_eq!(lcd.write_row_diff(1, "mem 41%").unwrap(), 0);
+    }
+
+    #[test]
+    fn counts_i2c_bytes_per_frame() {
+        let mut lcd = Lcd::new_stub(16, 2);
+        lcd.write_lines(&["CPU 42%", "up"]).unwrap();
+        // One address byte per run plus a byte per cell, four bus bytes per LCD byte.
+        assert_eq!(lcd.take_write_bytes(), Some((1 + 7 + 1 + 2) * 4));
+        assert_eq!(lcd.take_write_bytes(), None);
+        lcd.write_lines(&["CPU 47%", "up"]).unwrap();
+        assert_eq!(lcd.take_write_bytes(), Some((1 + 1) * 4));
+        assert_eq!(lcd.bus_bytes(), 52);
+    }
+
+    #[test]
+    fn reconfigure_resizes_and_blanks_the_display() {
+        let mut lcd = Lcd::new_stub(16, 2);
+        lcd.write_lines(&["old", "rows"]).unwrap();
+        lcd.set_backlight(false).unwrap();
+        lcd.reconfigure(20, 4).unwrap();
+        assert_eq!((lcd.cols(), lcd.rows()), (20, 4));
+        assert_eq!(lcd.shadow_row(3), " ".repeat(20));
+        assert!(!lcd.last_backlight());
+        lcd.write_line(3, "fourth row").unwrap();
+        lcd.reconfigure(16, 2).unwrap();
+        assert!(lcd.write_line(3, "gone").is_err());
+        assert_eq!(lcd.shadow_row(0), " ".repeat(16));
+    }
+
+    #[test]
+    fn remembers_glyphs_for_a_reinit_until_the_geometry_changes() {
+        let mut lcd = Lcd::new_stub(16, 2);
+        let heart = [0, 10, 31, 31, 14, 4, 0, 0];
+        lcd.write_custom_char(6, &heart).unwrap();
+        assert_eq!(lcd.glyphs[6], Some(heart));
+        assert!(lcd.glyphs[0].is_none());
+        // A stub never loses its hardware, so there is nothing to recover.
+        assert!(!lcd.recover(Instant::now()));
+        assert!(!lcd.is_lost());
+        lcd.reconfigure(20, 4).unwrap();
+        assert!(lcd.glyphs.iter().all(Option::is_none));
+    }
+
     #[test]
     #[ignore]
     fn rejects_out_of_bounds_row() {
@@ -699,6 +1506,7 @@ mod tests {
             2,
             crate::config::DEFAULT_PCF8574_ADDR,
             crate::config::DEFAULT_DISPLAY_DRIVER,
+            LcdGpioConfig::default(),
         )
         .unwrap();
         let err = lcd.write_line(2, "oops").unwrap_err();
@@ -713,8 +1521,83 @@ mod tests {
             2,
             crate::config::DEFAULT_PCF8574_ADDR,
             crate::config::DEFAULT_DISPLAY_DRIVER,
+            LcdGpioConfig::default(),
         )
         .unwrap();
         lcd.write_line(1, "ok").unwrap();
     }
+
+    /// Pins handed out and not yet dropped, shared between a [`FakePins`] and its lines.
+    #[cfg(target_os = "linux")]
+    type Claimed = std::rc::Rc<std::cell::RefCell<HashSet<u8>>>;
+
+    /// Refuses a pin that is still claimed, like the GPIO chip does.
+    #[cfg(target_os = "linux")]
+    #[derive(Default)]
+    struct FakePins(Claimed);
+
+    #[cfg(target_os = "linux")]
+    struct FakeLine {
+        pin: u8,
+        claimed: Claimed,
+    }
+
+    #[cfg(target_os = "linux")]
+    impl lcd_driver::gpio::OutputLine for FakeLine {
+        fn write(&mut self, _high: bool) {}
+    }
+
+    #[cfg(target_os = "linux")]
+    impl Drop for FakeLine {
+        fn drop(&mut self) {
+            self.claimed.borrow_mut().remove(&self.pin);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    impl lcd_driver::gpio::PinSource for FakePins {
+        type Line = FakeLine;
+
+        fn output(&mut self, pin: u8, _high: bool) -> Result<FakeLine> {
+            if !self.0.borrow_mut().insert(pin) {
+                return Err(Error::InvalidArgs(format!("pin {pin} is already in use")));
+            }
+            Ok(FakeLine {
+                pin,
+                claimed: self.0.clone(),
+            })
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn replacing_a_gpio_driver_releases_its_pins_first() {
+        let pins = LcdGpioConfig {
+            rs_pin: Some(25),
+            e_pin: Some(24),
+            d4_pin: Some(23),
+            d5_pin: Some(17),
+            d6_pin: Some(18),
+            d7_pin: Some(22),
+            backlight_pin: Some(4),
+        };
+        let mut source = FakePins::default();
+        let open = |source: &mut FakePins, cols, rows| {
+            lcd_driver::Hd44780::new(GpioBus::claim(source, &pins)?, 0, cols, rows)
+        };
+        let mut slot = Some(open(&mut source, 16, 2).unwrap());
+        // Opening next to the live bus is refused, which is what a re-init used to run into.
+        assert!(open(&mut source, 20, 4).is_err());
+        replace_driver(&mut slot, || open(&mut source, 20, 4)).unwrap();
+        assert!(slot.is_some());
+        assert_eq!(source.0.borrow().len(), 7);
+
+        // A failed open leaves the slot empty with every pin free for the next attempt.
+        let refused = replace_driver(&mut slot, || -> Result<_> {
+            Err(Error::InvalidArgs("no display".into()))
+        });
+        assert!(refused.is_err());
+        assert!(slot.is_none());
+        assert!(source.0.borrow().is_empty());
+    }
 }
@@ -0,0 +1,285 @@
+//! Layout templates for the polling overlay.
+//!
+//! A template is literal text with metric fields: `{name}`, `{name:W}` or `{name:W.P}` right-aligns
+//! the value to at least `W` columns with `P` decimals, and `{{` / `}}` are literal braces. Units
+//! are part of the metric name (`mem` is a percentage, `mem_mb` megabytes). Metrics the host
+//! cannot report render as `--`.
+
+use std::{fmt::Write, str::FromStr};
+
+const MAX_FIELD_WIDTH: usize = 40;
+const MAX_PRECISION: usize = 3;
+const MISSING: &str = "--";
+
+/// A value the poller can report, in a fixed unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Cpu,
+    Load1,
+    Load5,
+    Load15,
+    Mem,
+    MemMb,
+    MemGb,
+    Disk,
+    FreeMb,
+    FreeGb,
+    Temp,
+    TempF,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 12] = [
+        Metric::Cpu,
+        Metric::Load1,
+        Metric::Load5,
+        Metric::Load15,
+        Metric::Mem,
+        Metric::MemMb,
+        Metric::MemGb,
+        Metric::Disk,
+        Metric::FreeMb,
+        Metric::FreeGb,
+        Metric::Temp,
+        Metric::TempF,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Metric::Cpu => "cpu",
+            Metric::Load1 => "load1",
+            Metric::Load5 => "load5",
+            Metric::Load15 => "load15",
+            Metric::Mem => "mem",
+            Metric::MemMb => "mem_mb",
+            Metric::MemGb => "mem_gb",
+            Metric::Disk => "disk",
+            Metric::FreeMb => "free_mb",
+            Metric::FreeGb => "free_gb",
+            Metric::Temp => "temp",
+            Metric::TempF => "temp_f",
+        }
+    }
+
+    fn default_precision(self) -> usize {
+        match self {
+            Metric::Load1 | Metric::Load5 | Metric::Load15 => 2,
+            Metric::MemGb | Metric::FreeGb => 1,
+            _ => 0,
+        }
+    }
+
+    /// Integer digits of the widest value the metric normally takes.
+    fn int_digits(self) -> usize {
+        match self {
+            Metric::Load1 | Metric::Load5 | Metric::Load15 => 2,
+            Metric::MemMb => 5,
+            Metric::FreeMb => 7,
+            Metric::FreeGb => 4,
+            _ => 3,
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Metric::ALL
+            .into_iter()
+            .find(|metric| metric.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Metric::ALL.iter().map(|m| m.as_str()).collect();
+                format!(
+                    "unknown metric '{s}' (expected one of {})",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Field {
+    metric: Metric,
+    width: usize,
+    precision: usize,
+}
+
+impl Field {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let (name, format) = match spec.split_once(':') {
+            Some((name, format)) => (name, Some(format)),
+            None => (spec, None),
+        };
+        let metric: Metric = name.trim().parse()?;
+        let mut field = Field {
+            metric,
+            width: 0,
+            precision: metric.default_precision(),
+        };
+        if let Some(format) = format {
+            let (width, precision) = match format.split_once('.') {
+                Some((width, precision)) => (width, Some(precision)),
+                None => (format, None),
+            };
+            if !width.is_empty() {
+                field.width = width
+                    .parse()
+                    .ok()
+                    .filter(|w| *w <= MAX_FIELD_WIDTH)
+                    .ok_or_else(|| {
+                        format!("invalid width '{width}' in {{{spec}}} (0-{MAX_FIELD_WIDTH})")
+                    })?;
+            }
+            if let Some(precision) = precision {
+                field.precision = precision
+                    .parse()
+                    .ok()
+                    .filter(|p| *p <= MAX_PRECISION)
+                    .ok_or_else(|| {
+                        format!("invalid precision '{precision}' in {{{spec}}} (0-{MAX_PRECISION})")
+                    })?;
+            }
+        }
+        Ok(field)
+    }
+
+    fn max_width(&self) -> usize {
+        let value = self.metric.int_digits()
+            + if self.precision > 0 {
+                self.precision + 1
+            } else {
+                0
+            };
+        self.width.max(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Field(Field),
+}
+
+/// A parsed overlay line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricTemplate {
+    segments: Vec<Segment>,
+}
+
+impl MetricTemplate {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = raw.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut spec = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        spec.push(c);
+                    }
+                    if !closed {
+                        return Err(format!("unclosed field '{{{spec}'"));
+                    }
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Field(Field::parse(&spec)?));
+                }
+                '}' => return Err("unmatched '}' (write '}}' for a literal brace)".into()),
+                _ => text.push(ch),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Widest line this template can produce, assuming each metric stays in its usual range.
+    pub fn max_width(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.chars().count(),
+                Segment::Field(field) => field.max_width(),
+            })
+            .sum()
+    }
+
+    /// Fill in the fields; `value` returns `None` for metrics the host cannot report.
+    pub fn render(&self, value: impl Fn(Metric) -> Option<f64>) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Field(field) => {
+                    let width = field.width;
+                    let _ = match value(field.metric) {
+                        Some(v) => write!(out, "{v:>width$.prec$}", prec = field.precision),
+                        None => write!(out, "{MISSING:>width$}"),
+                    };
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::Cpu => Some(7.4),
+            Metric::Load1 => Some(0.456),
+            Metric::Temp => None,
+            _ => Some(50.0),
+        }
+    }
+
+    #[test]
+    fn renders_width_precision_and_missing_values() {
+        let template = MetricTemplate::parse("CPU{cpu:3}% LD{load1} T{temp:3}").unwrap();
+        assert_eq!(template.render(sample), "CPU  7% LD0.46 T --");
+        let template = MetricTemplate::parse("L{load1:5.1}").unwrap();
+        assert_eq!(template.render(sample), "L  0.5");
+    }
+
+    #[test]
+    fn braces_escape_and_errors_are_reported() {
+        let template = MetricTemplate::parse("{{cpu}} {cpu}").unwrap();
+        assert_eq!(template.render(sample), "{cpu} 7");
+        assert!(MetricTemplate::parse("{gpu}")
+            .unwrap_err()
+            .contains("unknown metric"));
+        assert!(MetricTemplate::parse("{cpu").is_err());
+        assert!(MetricTemplate::parse("cpu}").is_err());
+        assert!(MetricTemplate::parse("{cpu:3.9}").is_err());
+    }
+
+    #[test]
+    fn max_width_counts_fields_at_their_widest() {
+        assert_eq!(MetricTemplate::parse("CPU{cpu:3}%").unwrap().max_width(), 7);
+        assert_eq!(MetricTemplate::parse("L{load1}").unwrap().max_width(), 6);
+        assert_eq!(
+            MetricTemplate::parse("{free_mb:2}M").unwrap().max_width(),
+            8
+        );
+    }
+}
@@ -1,3 +1,10 @@
+pub mod backend;
+pub mod capture;
+pub mod charmap;
 pub mod icon_bank;
 pub mod lcd;
+pub mod metric_template;
 pub mod overlays;
+pub mod self_test;
+pub mod sink;
+pub mod terminal;
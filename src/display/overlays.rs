@@ -3,27 +3,65 @@ use std::time::Instant;
 
 use crate::{
     display::{
-        icon_bank::{IconBank, IconPalette, PaletteRequest},
-        lcd::Lcd,
+        backend::DisplayBackend,
+        icon_bank::{
+            big_glyph, vertical_bar_level, BarRequest, IconBank, IconPalette, PaletteRequest,
+        },
     },
-    payload::{Icon, RenderFrame},
+    payload::{placeholder_index, BarStyle, DisplayMode, Icon, RenderFrame},
     Error, Result,
 };
 
 const SCROLL_GAP: &str = "    |    ";
 
-/// Render a single frame with no scrolling offsets.
-pub fn render_frame_once(lcd: &mut Lcd, frame: &RenderFrame) -> Result<()> {
+/// Marks the render loop adds to the status row (the row the heartbeat sits on) and the row
+/// below it, independent of the page being shown.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatusMarks<'a> {
+    /// Modem line-state glyph for the last cell of the row the heartbeat leaves alone.
+    pub glyph: Option<char>,
+    /// Unseen alert counters (e.g. `!3`), right-aligned just left of the heartbeat cell.
+    pub badge: Option<&'a str>,
+    /// Frame row a one-row display shows; the render loop alternates it so `line2` is not lost.
+    pub fold_row: u8,
+}
+
+impl StatusMarks<'_> {
+    pub fn folded(self, fold_row: u8) -> Self {
+        Self { fold_row, ..self }
+    }
+}
+
+/// Render a single frame with no scrolling offsets, drawing bars in `bar_style` unless the
+/// frame overrides it.
+pub fn render_frame_once<D: DisplayBackend>(
+    lcd: &mut D,
+    frame: &RenderFrame,
+    bar_style: BarStyle,
+) -> Result<()> {
     let mut icon_bank = IconBank::new();
-    render_frame_with_scroll(lcd, frame, (0, 0), false, &mut icon_bank).map(|_| ())
+    icon_bank.set_bar_style(bar_style);
+    render_frame_with_scroll(
+        lcd,
+        frame,
+        &[],
+        false,
+        StatusMarks::default(),
+        None,
+        &mut icon_bank,
+    )
+    .map(|_| ())
 }
 
-/// Render a frame, applying scroll offsets and optional heartbeat overlay.
-pub fn render_frame_with_scroll(
-    lcd: &mut Lcd,
+/// Render a frame, applying per-row scroll offsets plus optional heartbeat, status marks and
+/// expiry countdown overlays.
+pub fn render_frame_with_scroll<D: DisplayBackend>(
+    lcd: &mut D,
     frame: &RenderFrame,
-    offsets: (usize, usize),
+    offsets: &[usize],
     heartbeat_on: bool,
+    status: StatusMarks<'_>,
+    countdown: Option<u8>,
     icon_bank: &mut IconBank,
 ) -> Result<IconPalette> {
     lcd.set_blink(frame.blink)?;
@@ -32,67 +70,229 @@ pub fn render_frame_with_scroll(
         lcd.clear()?;
     }
 
+    let (lines, palette) = compose_lines(
+        lcd,
+        frame,
+        offsets,
+        heartbeat_on,
+        status,
+        countdown,
+        icon_bank,
+    )?;
+    let out: Vec<&str> = lines
+        .iter()
+        .enumerate()
+        .map(|(row, line)| {
+            if line.trim().is_empty() && frame.bar_row != Some(row as u8) {
+                ""
+            } else {
+                line.as_str()
+            }
+        })
+        .collect();
+
+    lcd.write_lines(&out)?;
+    Ok(palette)
+}
+
+/// Recompose `frame` and push only the changed cells of `row` to the glass; used for
+/// row-addressed updates so the other lines are left untouched.
+#[allow(clippy::too_many_arguments)] // Mirrors render_frame_with_scroll plus the target row.
+pub fn render_row_update<D: DisplayBackend>(
+    lcd: &mut D,
+    frame: &RenderFrame,
+    row: u8,
+    offsets: &[usize],
+    heartbeat_on: bool,
+    status: StatusMarks<'_>,
+    countdown: Option<u8>,
+    icon_bank: &mut IconBank,
+) -> Result<IconPalette> {
+    let (lines, palette) = compose_lines(
+        lcd,
+        frame,
+        offsets,
+        heartbeat_on,
+        status,
+        countdown,
+        icon_bank,
+    )?;
+    // A one-row display only has the row its fold is showing.
+    let row = match lcd.rows() {
+        1 if row != status.fold_row => return Ok(palette),
+        1 => 0,
+        _ => row,
+    };
+    let line = lines.get(row as usize).map_or("", String::as_str);
+    lcd.write_row_diff(row, line)?;
+    Ok(palette)
+}
+
+/// The first `rows` text rows of a zone page, scrolled by `offsets` and padded to `width`.
+/// Zones show text only; bars, icons and the status overlays belong to the main rotation.
+pub fn zone_view(frame: &RenderFrame, offsets: &[usize], rows: u8, width: usize) -> Vec<String> {
+    (0..rows)
+        .map(|row| {
+            let offset = offsets.get(row as usize).copied().unwrap_or(0);
+            let mut line = view_line(frame.line(row), width, offset, frame.scroll_enabled);
+            let pad = width.saturating_sub(line.chars().count());
+            line.extend(std::iter::repeat_n(' ', pad));
+            line
+        })
+        .collect()
+}
+
+/// Build one line per display row for `frame`: scrolled text or bar, then heartbeat,
+/// countdown, badge, status glyph and icon overlays. The overlays stay on the top two rows.
+fn compose_lines<D: DisplayBackend>(
+    lcd: &mut D,
+    frame: &RenderFrame,
+    offsets: &[usize],
+    heartbeat_on: bool,
+    status: StatusMarks<'_>,
+    countdown: Option<u8>,
+    icon_bank: &mut IconBank,
+) -> Result<(Vec<String>, IconPalette)> {
     let width = lcd.cols() as usize;
+    let big_digits =
+        (frame.mode == DisplayMode::BigDigits && lcd.rows() >= 2).then_some(frame.line1.as_str());
+    let custom = custom_bitmaps(frame, lcd.rows().max(2));
     let palette = icon_bank.build_palette(
         lcd,
         PaletteRequest {
-            bar_required: frame.bar_percent.is_some(),
+            bar: frame.bar_percent.map(|percent| BarRequest {
+                percent,
+                style: frame.bar_style,
+            }),
             heartbeat: heartbeat_on,
+            countdown,
             icons: &frame.icons,
+            big_digits,
+            custom: &custom,
         },
     )?;
     let bar_row = frame.bar_row;
-    let mut line1 = if bar_row == Some(0) && frame.bar_percent.is_some() {
-        render_bar(frame.bar_percent.unwrap(), width, &palette)
-    } else {
-        view_line(&frame.line1, width, offsets.0, frame.scroll_enabled)
-    };
-    let mut line2 = if bar_row == Some(1) && frame.bar_percent.is_some() {
-        render_bar(frame.bar_percent.unwrap(), width, &palette)
-    } else {
-        view_line(&frame.line2, width, offsets.1, frame.scroll_enabled)
-    };
+    // A one-row display shows whichever frame row the fold has reached.
+    let one_line = lcd.rows() == 1;
+    let mut lines: Vec<String> = (0..lcd.rows())
+        .map(|row| if one_line { status.fold_row } else { row })
+        .map(|row| match frame.bar_percent {
+            Some(percent) if bar_row == Some(row) => {
+                render_bar(percent, frame.line(row), width, &palette)
+            }
+            _ => view_line(
+                frame.line(row),
+                width,
+                offsets.get(row as usize).copied().unwrap_or(0),
+                frame.scroll_enabled,
+            ),
+        })
+        .collect();
+    if let Some(text) = big_digits {
+        let [top, bottom] = big_digit_rows(text, width, &palette);
+        lines[0] = top;
+        lines[1] = bottom;
+    }
+    if !frame.custom_glyphs.is_empty() {
+        for line in &mut lines {
+            resolve_placeholders(line, frame, &palette);
+        }
+    }
+    // The heartbeat and countdown move off the top row when the bar sits there.
+    let (heartbeat_row, glyph_row) = if bar_row == Some(0) { (1, 0) } else { (0, 1) };
+
+    if let Some(line) = lines
+        .get_mut(heartbeat_row)
+        .filter(|_| heartbeat_on && width > 0)
+    {
+        overlay_heartbeat(line, width, &palette);
+    }
+
+    if let Some(tick) = palette.countdown_char().filter(|_| width > 0) {
+        // The countdown shares the heartbeat cell and wins it: an expiring page matters more
+        // than an idle link.
+        if let Some(line) = lines.get_mut(heartbeat_row) {
+            overlay_last_char(line, width, tick);
+        }
+    }
+
+    if let Some(badge) = status.badge {
+        if let Some(line) = lines.get_mut(heartbeat_row) {
+            overlay_badge(line, width, badge);
+        }
+    }
+
+    if let Some(glyph) = status.glyph {
+        // The status glyph takes the last column of the row the heartbeat leaves alone.
+        if let Some(line) = lines.get_mut(glyph_row) {
+            overlay_last_char(line, width, glyph);
+        }
+    }
 
-    if heartbeat_on && width > 0 {
-        if bar_row == Some(0) {
-            overlay_heartbeat(&mut line2, width, &palette);
+    let icon_row = if bar_row == Some(1) { 0 } else { 1 };
+    if let Some(line) = lines.get_mut(icon_row) {
+        if frame.icons.is_empty() {
+            let custom_icon = frame
+                .custom_icons
+                .first()
+                .and_then(|idx| frame.custom_glyphs.get(*idx))
+                .and_then(|glyph| palette.custom_char(&glyph.rows));
+            if let Some(ch) = custom_icon.filter(|_| width > 0) {
+                overlay_last_char(line, width, ch);
+            }
         } else {
-            overlay_heartbeat(&mut line1, width, &palette);
+            overlay_icons(line, width, &frame.icons, &palette);
         }
     }
 
-    overlay_icons(
-        &mut line1,
-        &mut line2,
-        width,
-        &frame.icons,
-        bar_row,
-        &palette,
-    );
+    Ok((lines, palette))
+}
 
-    let out1 = if line1.trim().is_empty() && bar_row != Some(0) {
-        ""
-    } else {
-        &line1
-    };
-    let out2 = if line2.trim().is_empty() && bar_row != Some(1) {
-        ""
-    } else {
-        &line2
-    };
+/// Bitmaps of the payload glyphs `frame` shows in its first `rows` rows, then those its
+/// `icons` name.
+fn custom_bitmaps(frame: &RenderFrame, rows: u8) -> Vec<[u8; 8]> {
+    if frame.custom_glyphs.is_empty() {
+        return Vec::new();
+    }
+    (0..rows)
+        .flat_map(|row| frame.line(row).chars())
+        .filter_map(placeholder_index)
+        .chain(frame.custom_icons.iter().copied())
+        .filter_map(|idx| frame.custom_glyphs.get(idx))
+        .map(|glyph| glyph.rows)
+        .collect()
+}
 
-    lcd.write_lines(out1, out2)?;
-    Ok(palette)
+/// Swap the glyph placeholders in `line` for the CGRAM slots their bitmaps were loaded into;
+/// a glyph left without a slot shows as a blank.
+fn resolve_placeholders(line: &mut String, frame: &RenderFrame, palette: &IconPalette) {
+    if !line.chars().any(|ch| placeholder_index(ch).is_some()) {
+        return;
+    }
+    *line = line
+        .chars()
+        .map(|ch| match placeholder_index(ch) {
+            Some(idx) => frame
+                .custom_glyphs
+                .get(idx)
+                .and_then(|glyph| palette.custom_char(&glyph.rows))
+                .unwrap_or(' '),
+            None => ch,
+        })
+        .collect();
 }
 
 /// Avoids flicker by respecting a minimum interval between render calls.
-pub fn render_if_allowed(
-    lcd: &mut Lcd,
+#[allow(clippy::too_many_arguments)] // Mirrors render_frame_with_scroll plus throttling state.
+pub fn render_if_allowed<D: DisplayBackend>(
+    lcd: &mut D,
     frame: &RenderFrame,
     last_render: &mut Instant,
     min_interval: Duration,
-    scroll_offsets: (usize, usize),
+    scroll_offsets: &[usize],
     heartbeat_on: bool,
+    status: StatusMarks<'_>,
+    countdown: Option<u8>,
     icon_bank: &mut IconBank,
 ) -> Result<Option<IconPalette>> {
     let now = Instant::now();
@@ -100,7 +300,15 @@ pub fn render_if_allowed(
         return Ok(None);
     }
     *last_render = now;
-    let palette = render_frame_with_scroll(lcd, frame, scroll_offsets, heartbeat_on, icon_bank)?;
+    let palette = render_frame_with_scroll(
+        lcd,
+        frame,
+        scroll_offsets,
+        heartbeat_on,
+        status,
+        countdown,
+        icon_bank,
+    )?;
     Ok(Some(palette))
 }
 
@@ -108,6 +316,15 @@ pub fn line_needs_scroll(text: &str, width: usize) -> bool {
     text.chars().count() > width
 }
 
+/// Whether any of the first `rows` text rows of `frame` is wider than the display; the bar
+/// row never scrolls.
+pub fn frame_needs_scroll(frame: &RenderFrame, rows: u8, width: usize) -> bool {
+    frame.scroll_enabled
+        && (0..rows)
+            .filter(|row| frame.bar_row != Some(*row))
+            .any(|row| line_needs_scroll(frame.line(row), width))
+}
+
 pub fn advance_offset(text: &str, width: usize, current: usize) -> usize {
     let len = text.chars().count();
     if len <= width {
@@ -118,7 +335,7 @@ pub fn advance_offset(text: &str, width: usize, current: usize) -> usize {
     (current + 1) % cycle
 }
 
-pub fn render_parse_error(lcd: &mut Lcd, cols: u8, err: &Error) -> Result<()> {
+pub fn render_parse_error<D: DisplayBackend>(lcd: &mut D, cols: u8, err: &Error) -> Result<()> {
     let width = cols as usize;
     let msg = truncate_with_ellipsis(&format!("{err}"), width);
     lcd.set_backlight(true)?;
@@ -128,7 +345,23 @@ pub fn render_parse_error(lcd: &mut Lcd, cols: u8, err: &Error) -> Result<()> {
     Ok(())
 }
 
-pub fn render_reconnecting(lcd: &mut Lcd, cols: u8) -> Result<()> {
+/// Error-budget incident page; stays lit (no blink) so it reads as a status, not a fault flash.
+pub fn render_incident_page<D: DisplayBackend>(
+    lcd: &mut D,
+    cols: u8,
+    line1: &str,
+    line2: &str,
+) -> Result<()> {
+    let width = cols as usize;
+    lcd.clear()?;
+    lcd.set_backlight(true)?;
+    lcd.set_blink(false)?;
+    lcd.write_line(0, &truncate_to_width(line1, width))?;
+    lcd.write_line(1, &truncate_to_width(line2, width))?;
+    Ok(())
+}
+
+pub fn render_reconnecting<D: DisplayBackend>(lcd: &mut D, cols: u8) -> Result<()> {
     let width = cols as usize;
     let title: String = "RECONNECTING".chars().take(width).collect();
     let detail = truncate_to_width("retrying...", width);
@@ -140,7 +373,7 @@ pub fn render_reconnecting(lcd: &mut Lcd, cols: u8) -> Result<()> {
     Ok(())
 }
 
-pub fn render_offline_message(lcd: &mut Lcd, cols: u8) -> Result<()> {
+pub fn render_offline_message<D: DisplayBackend>(lcd: &mut D, cols: u8) -> Result<()> {
     let width = cols as usize;
     let title: String = truncate_to_width("SERIAL OFFLINE", width);
     let detail = truncate_to_width("will retry...", width);
@@ -152,12 +385,23 @@ pub fn render_offline_message(lcd: &mut Lcd, cols: u8) -> Result<()> {
     Ok(())
 }
 
-fn render_bar(percent: u8, width: usize, palette: &IconPalette) -> String {
+/// Bar row in the palette's style. Horizontal styles fill the whole row; a vertical bar takes
+/// the first cell and leaves the rest to the row's own text.
+fn render_bar(percent: u8, text: &str, width: usize, palette: &IconPalette) -> String {
     if width == 0 {
         return String::new();
     }
 
-    let max_level = 5usize;
+    if palette.bar_style() == BarStyle::Vertical {
+        let level = vertical_bar_level(percent) as usize;
+        let gauge = palette.bar_char(level).unwrap_or(' ');
+        if width < 3 {
+            return gauge.to_string();
+        }
+        return format!("{gauge} {}", truncate_with_ellipsis(text, width - 2));
+    }
+
+    let max_level = palette.bar_style().levels() as usize;
     let total_units = width * max_level;
     let filled_units = (percent as usize * total_units) / 100;
     let mut s = String::with_capacity(width);
@@ -198,6 +442,34 @@ fn view_line(text: &str, width: usize, offset: usize, scroll_enabled: bool) -> S
     truncate_with_ellipsis(text, width)
 }
 
+/// Lay `text` out in the big-digit font as two rows, centred in `width` and clipped on the
+/// right. Wide glyphs sit one column apart; narrow ones (`:`, `.`, space) hug their neighbours.
+/// Characters the font lacks are skipped, and segments that found no CGRAM slot stay blank.
+fn big_digit_rows(text: &str, width: usize, palette: &IconPalette) -> [String; 2] {
+    let mut rows = [String::new(), String::new()];
+    let mut prev_wide = false;
+    for (top, bottom) in text.chars().filter_map(big_glyph) {
+        let wide = top.width() > 1;
+        for (row, cells) in rows.iter_mut().zip([top, bottom]) {
+            if wide && prev_wide {
+                row.push(' ');
+            }
+            row.extend(
+                cells
+                    .cells()
+                    .map(|cell| cell.and_then(|seg| palette.big_char(seg)).unwrap_or(' ')),
+            );
+        }
+        prev_wide = wide;
+    }
+    let pad = width.saturating_sub(rows[0].chars().count()) / 2;
+    rows.map(|row| {
+        let mut line = " ".repeat(pad);
+        line.extend(row.chars().take(width - pad));
+        line
+    })
+}
+
 fn truncate_with_ellipsis(text: &str, width: usize) -> String {
     if text.chars().count() <= width {
         return text.to_string();
@@ -228,18 +500,37 @@ fn overlay_heartbeat(text: &mut String, width: usize, palette: &IconPalette) {
     *text = chars.into_iter().collect();
 }
 
-fn overlay_icons(
-    line1: &mut String,
-    line2: &mut String,
-    width: usize,
-    icons: &[Icon],
-    bar_row: Option<u8>,
-    palette: &IconPalette,
-) {
+fn overlay_last_char(text: &mut String, width: usize, ch: char) {
+    if width == 0 {
+        return;
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    chars.resize(width, ' ');
+    if let Some(last) = chars.last_mut() {
+        *last = ch;
+    }
+    *text = chars.into_iter().collect();
+}
+
+/// Right-align `badge` against the heartbeat cell; skipped when it would fill the row.
+fn overlay_badge(text: &mut String, width: usize, badge: &str) {
+    let len = badge.chars().count();
+    if len == 0 || len + 1 >= width {
+        return;
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    chars.resize(width, ' ');
+    let start = width - 1 - len;
+    for (cell, ch) in chars[start..].iter_mut().zip(badge.chars()) {
+        *cell = ch;
+    }
+    *text = chars.into_iter().collect();
+}
+
+fn overlay_icons(target: &mut String, width: usize, icons: &[Icon], palette: &IconPalette) {
     if icons.is_empty() || width == 0 {
         return;
     }
-    let target = if bar_row == Some(1) { line1 } else { line2 };
     let icon = icons[0];
     let Some(icon_char) = palette.icon_char(icon) else {
         return;
@@ -259,6 +550,10 @@ fn overlay_icons(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::display::{
+        backend::recording::{DisplayCall, RecordingDisplay},
+        lcd::Lcd,
+    };
 
     #[test]
     fn view_with_scroll_wraps_through_gap() {
@@ -286,6 +581,230 @@ mod tests {
         );
     }
 
+    fn bar_row(default_style: BarStyle, raw: &str) -> (String, IconPalette) {
+        let mut lcd = Lcd::new_stub(10, 2);
+        let mut icon_bank = IconBank::new();
+        icon_bank.set_bar_style(default_style);
+        let frame = RenderFrame::from_payload_json(raw).unwrap();
+        let palette = render_frame_with_scroll(
+            &mut lcd,
+            &frame,
+            &[0, 0],
+            false,
+            StatusMarks::default(),
+            None,
+            &mut icon_bank,
+        )
+        .unwrap();
+        (lcd.last_lines().1, palette)
+    }
+
+    #[test]
+    fn bar_styles_use_their_own_glyph_sets() {
+        // Ten cells of four steps: 50% is five full cells.
+        let (row, palette) = bar_row(
+            BarStyle::Segmented,
+            r#"{"schema_version":1,"line1":"","line2":"CPU","bar":50}"#,
+        );
+        assert_eq!(palette.bar_style(), BarStyle::Segmented);
+        let full = palette.bar_char(4).unwrap().to_string();
+        let empty = palette.bar_char(0).unwrap().to_string();
+        assert_eq!(row, format!("{}{}", full.repeat(5), empty.repeat(5)));
+
+        // The payload override wins; a vertical gauge leaves room for the row's text.
+        let (row, palette) = bar_row(
+            BarStyle::Segmented,
+            r#"{"schema_version":1,"line1":"","line2":"CPU","bar":50,"bar_style":"vertical"}"#,
+        );
+        assert_eq!(palette.bar_style(), BarStyle::Vertical);
+        assert_eq!(row, format!("{} CPU", palette.bar_char(4).unwrap()));
+    }
+
+    #[test]
+    fn bigdigits_draw_two_rows_from_five_segments() {
+        let mut display = RecordingDisplay::new(16, 2);
+        let frame = RenderFrame::from_payload_json(
+            r#"{"schema_version":1,"line1":"12:34","line2":"","mode":"bigdigits"}"#,
+        )
+        .unwrap();
+        render_frame_once(&mut display, &frame, BarStyle::Solid).unwrap();
+        let glyphs = display
+            .calls
+            .iter()
+            .filter(|call| matches!(call, DisplayCall::Glyph(_)))
+            .count();
+        assert_eq!(glyphs, 5);
+        // Slots fill in order of first use: top bar, full, bottom bar, both bars, dot.
+        let segments: Vec<String> = display
+            .lines()
+            .iter()
+            .map(|line| {
+                line.chars()
+                    .map(|ch| match ch as u32 {
+                        0..=4 => "TFBMD".as_bytes()[ch as usize] as char,
+                        _ => ch,
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(segments, ["TF  MMFDMMF FBF", "BFB FBBDBBF   F"]);
+    }
+
+    #[test]
+    fn payload_glyphs_fill_placeholders_and_the_icon_cell() {
+        let mut display = RecordingDisplay::new(8, 2);
+        let mut icon_bank = IconBank::new();
+        let frame = RenderFrame::from_payload_json(
+            r#"{"schema_version":1,"line1":"{glyph:up}5 {glyph:dn}2","line2":"net",
+                "icons":["dn"],"custom_glyphs":{"up":[4,14,31,4,4,4,4,0],"dn":[4,4,4,4,31,14,4,0]}}"#,
+        )
+        .unwrap();
+        let palette = render_frame_with_scroll(
+            &mut display,
+            &frame,
+            &[],
+            false,
+            StatusMarks::default(),
+            None,
+            &mut icon_bank,
+        )
+        .unwrap();
+        let up = palette.custom_char(&[4, 14, 31, 4, 4, 4, 4, 0]).unwrap();
+        let down = palette.custom_char(&[4, 4, 4, 4, 31, 14, 4, 0]).unwrap();
+        assert_eq!(display.lines()[0], format!("{up}5 {down}2"));
+        assert_eq!(display.lines()[1], format!("net    {down}"));
+
+        // The same bitmaps on the next frame keep their slots without being rewritten.
+        let writes = display.calls.len();
+        render_frame_with_scroll(
+            &mut display,
+            &frame,
+            &[],
+            false,
+            StatusMarks::default(),
+            None,
+            &mut icon_bank,
+        )
+        .unwrap();
+        assert!(!display.calls[writes..]
+            .iter()
+            .any(|call| matches!(call, DisplayCall::Glyph(_))));
+    }
+
+    #[test]
+    fn frames_render_through_any_display_backend() {
+        let mut display = RecordingDisplay::new(8, 2);
+        let frame = RenderFrame::from_payload_json(
+            r#"{"schema_version":1,"line1":"CPU","line2":"","bar":100}"#,
+        )
+        .unwrap();
+        render_frame_once(&mut display, &frame, BarStyle::Solid).unwrap();
+        assert!(display
+            .calls
+            .iter()
+            .any(|call| matches!(call, DisplayCall::Glyph(_))));
+        assert!(display.lines()[0].starts_with("CPU"));
+
+        render_offline_message(&mut display, 8).unwrap();
+        assert_eq!(display.lines(), vec!["SERIAL O", "will ret"]);
+        assert_eq!(
+            display.calls.last(),
+            Some(&DisplayCall::Line(1, "will ret".into()))
+        );
+    }
+
+    #[test]
+    fn one_row_displays_show_the_folded_row() {
+        let mut display = RecordingDisplay::new(8, 1);
+        let mut icon_bank = IconBank::new();
+        let frame =
+            RenderFrame::from_payload_json(r#"{"schema_version":1,"line1":"CPU","line2":"MEM"}"#)
+                .unwrap();
+        let marks = StatusMarks::default();
+        render_frame_with_scroll(
+            &mut display,
+            &frame,
+            &[0],
+            false,
+            marks,
+            None,
+            &mut icon_bank,
+        )
+        .unwrap();
+        assert_eq!(display.lines(), vec!["CPU"]);
+
+        render_frame_with_scroll(
+            &mut display,
+            &frame,
+            &[0],
+            false,
+            marks.folded(1),
+            None,
+            &mut icon_bank,
+        )
+        .unwrap();
+        assert_eq!(display.lines(), vec!["MEM"]);
+    }
+
+    #[test]
+    fn badges_sit_just_left_of_the_heartbeat_cell() {
+        let mut lcd = Lcd::new_stub(16, 2);
+        let mut icon_bank = IconBank::new();
+        let frame =
+            RenderFrame::from_payload_json(r#"{"schema_version":1,"line1":"UPLINK","line2":"ok"}"#)
+                .unwrap();
+        let marks = StatusMarks {
+            glyph: None,
+            badge: Some("!2"),
+            ..StatusMarks::default()
+        };
+        render_frame_with_scroll(
+            &mut lcd,
+            &frame,
+            &[0, 0],
+            false,
+            marks,
+            None,
+            &mut icon_bank,
+        )
+        .unwrap();
+        let top: Vec<char> = lcd.last_lines().0.chars().collect();
+        assert!(top.iter().collect::<String>().starts_with("UPLINK"));
+        assert_eq!(top[13..15].iter().collect::<String>(), "!2");
+
+        // A badge that would crowd out the whole row is dropped.
+        let mut line = "AB".to_string();
+        overlay_badge(&mut line, 3, "!99");
+        assert_eq!(line, "AB");
+    }
+
+    #[test]
+    fn four_row_frames_place_the_bar_and_scroll_per_row() {
+        let mut lcd = Lcd::new_stub(10, 4);
+        let mut icon_bank = IconBank::new();
+        let frame = RenderFrame::from_payload_json(
+            r#"{"schema_version":1,"line1":"top","line2":"","line3":"","line4":"a long bottom line","bar":100,"bar_line3":true}"#,
+        )
+        .unwrap();
+        assert!(frame_needs_scroll(&frame, 4, 10));
+        assert!(!frame_needs_scroll(&frame, 3, 10));
+
+        render_frame_with_scroll(
+            &mut lcd,
+            &frame,
+            &[0, 0, 0, 2],
+            false,
+            StatusMarks::default(),
+            None,
+            &mut icon_bank,
+        )
+        .unwrap();
+        assert_eq!(lcd.shadow_row(0), "top       ");
+        assert_eq!(lcd.shadow_row(1), " ".repeat(10));
+        assert!(lcd.shadow_row(2).trim().chars().count() == 10);
+        assert_eq!(lcd.shadow_row(3), "long botto");
+    }
+
     #[test]
     fn view_line_truncates_with_ellipsis_when_scroll_disabled() {
         let text = "THIS STRING IS LONG";
@@ -293,15 +812,20 @@ mod tests {
         assert_eq!(view, "THI...");
     }
 
+    #[test]
+    fn overlay_last_char_pads_short_lines() {
+        let mut line = "AB".to_string();
+        overlay_last_char(&mut line, 5, 'c');
+        assert_eq!(line, "AB  c");
+    }
+
     #[test]
     fn overlay_icons_does_not_substitute_when_missing() {
-        let mut line1 = "LINE1".to_string();
-        let mut line2 = "LN2".to_string();
+        let mut line = "LN2".to_string();
         let palette = IconPalette::default();
 
-        overlay_icons(&mut line1, &mut line2, 6, &[Icon::Heart], None, &palette);
+        overlay_icons(&mut line, 6, &[Icon::Heart], &palette);
 
-        assert_eq!(line1, "LINE1");
-        assert_eq!(line2, "LN2");
+        assert_eq!(line, "LN2");
     }
 }
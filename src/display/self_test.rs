@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use crate::{
+    display::{
+        backend::DisplayBackend,
+        icon_bank::{BarRequest, IconBank, PaletteRequest},
+    },
+    payload::BarStyle,
+    Result,
+};
+
+/// How long each self-test step stays on the glass.
+pub const SELF_TEST_STEP_MS: u64 = 350;
+
+/// Characters cycled through the pattern step; every row starts at a different offset so a
+/// crossed address line shows up as two identical rows.
+const PATTERN: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Short remote-requested check of the panel: every cell lit with a full block, a character
+/// pattern on each row, then the backlight off and on again. Leaves the display cleared; the
+/// caller redraws whatever page was up.
+pub fn run_self_test<D: DisplayBackend>(
+    lcd: &mut D,
+    icon_bank: &mut IconBank,
+    step: Duration,
+) -> Result<()> {
+    let cols = lcd.cols() as usize;
+    let rows = lcd.rows();
+    let palette = icon_bank.build_palette(
+        lcd,
+        PaletteRequest {
+            bar: Some(BarRequest {
+                percent: 100,
+                style: Some(BarStyle::Vertical),
+            }),
+            heartbeat: false,
+            countdown: None,
+            icons: &[],
+            big_digits: None,
+            custom: &[],
+        },
+    )?;
+    let block = palette
+        .bar_char(BarStyle::Vertical.levels() as usize)
+        .unwrap_or('#');
+
+    lcd.set_blink(false)?;
+    lcd.set_backlight(true)?;
+    lcd.clear()?;
+    let solid: String = std::iter::repeat_n(block, cols).collect();
+    for row in 0..rows {
+        lcd.write_line(row, &solid)?;
+    }
+    std::thread::sleep(step);
+
+    for row in 0..rows {
+        lcd.write_line(row, &pattern_row(row, cols))?;
+    }
+    std::thread::sleep(step);
+
+    lcd.set_backlight(false)?;
+    std::thread::sleep(step);
+    lcd.set_backlight(true)?;
+    lcd.clear()?;
+    Ok(())
+}
+
+fn pattern_row(row: u8, cols: usize) -> String {
+    PATTERN
+        .chars()
+        .cycle()
+        .skip(row as usize * 4)
+        .take(cols)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::backend::recording::{DisplayCall, RecordingDisplay};
+
+    #[test]
+    fn self_test_lights_every_cell_then_flashes_the_backlight() {
+        let mut display = RecordingDisplay::new(8, 2);
+        let mut icon_bank = IconBank::new();
+        run_self_test(&mut display, &mut icon_bank, Duration::ZERO).unwrap();
+
+        let lines: Vec<&DisplayCall> = display
+            .calls
+            .iter()
+            .filter(|call| matches!(call, DisplayCall::Line(..)))
+            .collect();
+        assert_eq!(lines.len(), 4);
+        let DisplayCall::Line(0, solid) = lines[0] else {
+            panic!("first write should fill row 0");
+        };
+        assert_eq!(solid.chars().count(), 8);
+        assert!(solid.chars().all(|ch| ch == solid.chars().next().unwrap()));
+        assert_eq!(lines[2], &DisplayCall::Line(0, "01234567".into()));
+        assert_eq!(lines[3], &DisplayCall::Line(1, "456789AB".into()));
+
+        let backlight: Vec<&DisplayCall> = display
+            .calls
+            .iter()
+            .filter(|call| matches!(call, DisplayCall::Backlight(_)))
+            .collect();
+        assert_eq!(
+            backlight,
+            [
+                &DisplayCall::Backlight(true),
+                &DisplayCall::Backlight(false),
+                &DisplayCall::Backlight(true)
+            ]
+        );
+        assert_eq!(display.calls.last(), Some(&DisplayCall::Clear));
+    }
+}
@@ -0,0 +1,310 @@
+//! Embedding API: drive an LCD from caller code without the daemon.
+//!
+//! [`FrameSink`] takes [`RenderFrame`]s (or payload JSON) and owns everything the render loop
+//! would otherwise keep for them: the page rotation queue, per-row marquee offsets, frame
+//! expiry and the custom-glyph bank. The caller owns the clock and calls [`FrameSink::tick`]
+//! when the returned deadline comes due; nothing here spawns threads, touches serial ports or
+//! reads the config file.
+//!
+//! This module is the stable surface for embedding. Its public items follow semver: they are
+//! only removed or changed in signature on a major version bump. The daemon's own modules
+//! (`app`, `state`, `serial`, ...) are public for the binaries and tests and carry no such
+//! promise.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::{
+    config::MAX_ROWS,
+    display::{
+        backend::DisplayBackend,
+        icon_bank::IconBank,
+        overlays::{advance_offset, frame_needs_scroll, render_frame_with_scroll, StatusMarks},
+    },
+    payload::{is_page_set, normalize_payload_json, BarStyle, Defaults, RenderFrame},
+    Result,
+};
+
+struct Page {
+    frame: RenderFrame,
+    expires_at: Option<Instant>,
+}
+
+/// Renders frames onto a [`DisplayBackend`], rotating and scrolling them as the daemon does.
+///
+/// The page on screen is the front of the queue. [`FrameSink::show`] replaces the queue,
+/// [`FrameSink::push`] adds a page to the rotation, and each page stays up for its
+/// `page_timeout_ms` before the next one takes over. Rows wider than the display scroll every
+/// `scroll_speed_ms` when the frame allows it, and frames with `duration_ms` drop out once it
+/// has passed.
+pub struct FrameSink<D: DisplayBackend> {
+    display: D,
+    icon_bank: IconBank,
+    defaults: Defaults,
+    pages: VecDeque<Page>,
+    offsets: [usize; MAX_ROWS as usize],
+    next_page: Option<Instant>,
+    next_scroll: Option<Instant>,
+}
+
+impl<D: DisplayBackend> FrameSink<D> {
+    /// Wrap `display`. Nothing is drawn until the first frame arrives.
+    pub fn new(display: D) -> Self {
+        Self {
+            display,
+            icon_bank: IconBank::new(),
+            defaults: Defaults {
+                scroll_speed_ms: crate::payload::DEFAULT_SCROLL_MS,
+                page_timeout_ms: crate::payload::DEFAULT_PAGE_TIMEOUT_MS,
+            },
+            pages: VecDeque::new(),
+            offsets: [0; MAX_ROWS as usize],
+            next_page: None,
+            next_scroll: None,
+        }
+    }
+
+    /// Timings used for JSON payloads that leave `scroll_speed_ms` or `page_timeout_ms` out.
+    pub fn set_defaults(&mut self, defaults: Defaults) {
+        self.defaults = defaults;
+    }
+
+    /// Glyph style for bars whose frame does not pick one.
+    pub fn set_bar_style(&mut self, style: BarStyle) {
+        self.icon_bank.set_bar_style(style);
+    }
+
+    /// Replace every queued page with `frame` and draw it now.
+    pub fn show(&mut self, frame: RenderFrame, now: Instant) -> Result<()> {
+        self.pages.clear();
+        self.push(frame, now)
+    }
+
+    /// Add `frame` to the rotation and draw it now; the rotation carries on from it. A frame
+    /// equal to the one on screen is ignored.
+    pub fn push(&mut self, frame: RenderFrame, now: Instant) -> Result<()> {
+        if self.current() == Some(&frame) {
+            return Ok(());
+        }
+        let page = self.page(frame, now);
+        self.pages.push_front(page);
+        self.enter_page(now)
+    }
+
+    /// Parse a payload line as the daemon would and show it: a page set (`pages: [...]`)
+    /// replaces the queue, any other frame is pushed.
+    pub fn push_json(&mut self, raw: &str, now: Instant) -> Result<()> {
+        let normalized = normalize_payload_json(raw)?;
+        if is_page_set(&normalized) {
+            let frames = RenderFrame::pages_from_normalized_payload(&normalized, self.defaults)?;
+            self.pages = frames
+                .into_iter()
+                .map(|frame| self.page(frame, now))
+                .collect();
+            return self.enter_page(now);
+        }
+        let frame = RenderFrame::from_normalized_payload_with_defaults(&normalized, self.defaults)?;
+        self.push(frame, now)
+    }
+
+    /// Drop every page and blank the display.
+    pub fn clear(&mut self) -> Result<()> {
+        self.pages.clear();
+        self.next_page = None;
+        self.next_scroll = None;
+        self.display.clear()
+    }
+
+    /// Run whatever is due at `now`: expire frames, rotate pages and step marquees. Returns
+    /// when to call again, or `None` while nothing is queued or changing.
+    pub fn tick(&mut self, now: Instant) -> Result<Option<Instant>> {
+        let expired = self.prune_expired(now);
+        if self.pages.is_empty() {
+            if expired {
+                self.next_page = None;
+                self.next_scroll = None;
+                self.display.clear()?;
+            }
+            return Ok(None);
+        }
+        if expired {
+            self.enter_page(now)?;
+        } else if self.next_page.is_some_and(|at| now >= at) {
+            self.pages.rotate_left(1);
+            self.enter_page(now)?;
+        } else if self.next_scroll.is_some_and(|at| now >= at) {
+            let width = self.display.cols() as usize;
+            let frame = &self.pages[0].frame;
+            for (row, offset) in self.offsets.iter_mut().enumerate() {
+                *offset = advance_offset(frame.line(row as u8), width, *offset);
+            }
+            self.next_scroll = Some(now + Duration::from_millis(frame.scroll_speed_ms));
+            self.draw()?;
+        }
+        Ok(self.next_deadline())
+    }
+
+    /// The frame on screen.
+    pub fn current(&self) -> Option<&RenderFrame> {
+        self.pages.front().map(|page| &page.frame)
+    }
+
+    /// Number of pages in the rotation, including the one on screen.
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    pub fn display(&self) -> &D {
+        &self.display
+    }
+
+    /// Direct access to the display. Anything drawn through it is overwritten by the next
+    /// redraw.
+    pub fn display_mut(&mut self) -> &mut D {
+        &mut self.display
+    }
+
+    /// Give the display back.
+    pub fn into_inner(self) -> D {
+        self.display
+    }
+
+    fn page(&self, frame: RenderFrame, now: Instant) -> Page {
+        Page {
+            expires_at: frame.duration_ms.map(|ms| now + Duration::from_millis(ms)),
+            frame,
+        }
+    }
+
+    /// Draw the front page from a fresh start: full clear, line state, offsets and timers.
+    fn enter_page(&mut self, now: Instant) -> Result<()> {
+        let Some(frame) = self.current() else {
+            return Ok(());
+        };
+        let rotates = self.pages.len() > 1;
+        let page_timeout = Duration::from_millis(frame.page_timeout_ms);
+        let scroll_speed = Duration::from_millis(frame.scroll_speed_ms);
+        let scrolls = frame_needs_scroll(frame, self.display.rows(), self.display.cols() as usize);
+        let backlight = frame.backlight_on;
+        self.offsets = [0; MAX_ROWS as usize];
+        self.next_page = rotates.then_some(now + page_timeout);
+        self.next_scroll = scrolls.then_some(now + scroll_speed);
+        self.display.clear()?;
+        self.display.set_backlight(backlight)?;
+        self.draw()
+    }
+
+    fn draw(&mut self) -> Result<()> {
+        let frame = &self.pages[0].frame;
+        render_frame_with_scroll(
+            &mut self.display,
+            frame,
+            &self.offsets,
+            false,
+            StatusMarks::default(),
+            None,
+            &mut self.icon_bank,
+        )
+        .map(|_| ())
+    }
+
+    /// Drop expired pages; true when the page on screen was one of them.
+    fn prune_expired(&mut self, now: Instant) -> bool {
+        let shown_expired = self
+            .pages
+            .front()
+            .and_then(|page| page.expires_at)
+            .is_some_and(|at| at <= now);
+        self.pages
+            .retain(|page| page.expires_at.is_none_or(|at| at > now));
+        shown_expired
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        let expiry = self.pages.iter().filter_map(|page| page.expires_at).min();
+        [self.next_page, self.next_scroll, expiry]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::backend::recording::RecordingDisplay;
+
+    fn frame(raw: &str) -> RenderFrame {
+        RenderFrame::from_payload_json(raw).unwrap()
+    }
+
+    #[test]
+    fn rotates_pages_and_expires_frames() {
+        let start = Instant::now();
+        let mut sink = FrameSink::new(RecordingDisplay::new(16, 2));
+        assert_eq!(sink.tick(start).unwrap(), None);
+
+        sink.push(
+            frame(r#"{"schema_version":1,"line1":"one","line2":"","page_timeout_ms":1000}"#),
+            start,
+        )
+        .unwrap();
+        assert_eq!(sink.display().lines()[0].trim_end(), "one");
+        // A single page has nothing to rotate to.
+        assert_eq!(sink.tick(start).unwrap(), None);
+
+        sink.push(
+            frame(r#"{"schema_version":1,"line1":"two","line2":"","page_timeout_ms":1000,"duration_ms":1500}"#),
+            start,
+        )
+        .unwrap();
+        assert_eq!(sink.display().lines()[0].trim_end(), "two");
+        let due = sink.tick(start).unwrap().unwrap();
+        assert_eq!(due, start + Duration::from_millis(1000));
+
+        sink.tick(due).unwrap();
+        assert_eq!(sink.display().lines()[0].trim_end(), "one");
+        assert_eq!(sink.len(), 2);
+
+        // "two" expires while "one" is up; "one" stays and stops rotating.
+        let later = start + Duration::from_millis(1500);
+        assert_eq!(
+            sink.tick(later).unwrap(),
+            Some(later + Duration::from_millis(500))
+        );
+        assert_eq!(sink.len(), 1);
+        assert_eq!(sink.current().unwrap().line1, "one");
+    }
+
+    #[test]
+    fn scrolls_long_lines_and_takes_page_sets() {
+        let start = Instant::now();
+        let mut sink = FrameSink::new(RecordingDisplay::new(8, 2));
+        sink.push_json(
+            r#"{"schema_version":1,"line1":"a long marquee line","line2":"","scroll_speed_ms":200}"#,
+            start,
+        )
+        .unwrap();
+        assert_eq!(sink.display().lines()[0], "a long m");
+        let due = sink.tick(start).unwrap().unwrap();
+        assert_eq!(due, start + Duration::from_millis(200));
+        sink.tick(due).unwrap();
+        assert_eq!(sink.display().lines()[0], " long ma");
+
+        sink.push_json(
+            r#"{"schema_version":1,"pages":[{"line1":"p1","line2":""},{"line1":"p2","line2":""}]}"#,
+            start + Duration::from_millis(300),
+        )
+        .unwrap();
+        assert_eq!(sink.len(), 2);
+        assert_eq!(sink.current().unwrap().line1, "p1");
+
+        sink.clear().unwrap();
+        assert!(sink.is_empty());
+        assert_eq!(sink.into_inner().lines(), ["", ""]);
+    }
+}
@@ -0,0 +1,180 @@
+//! Terminal mock of the LCD for `--dry-run`.
+//!
+//! The glass is drawn as a box around the shadow rows with the backlight and blink state
+//! underneath. On a terminal each change redraws the box in place and the backlight shows as
+//! the cell colours; piped output appends one plain box per change instead. CGRAM slots have no
+//! terminal equivalent, so each loaded bitmap is approximated by the closest block character.
+
+use std::io::{self, IsTerminal, Write};
+
+use super::charmap::glass_char;
+
+/// Terminal stand-ins for the bar, heartbeat and battery glyphs the hardware preloads.
+const PRELOADED_GLYPHS: [char; 8] = [' ', '▎', '▍', '▋', '▊', '█', '♥', '▯'];
+const LEFT_BLOCKS: [char; 9] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+const LOWER_BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const CELL_WIDTH: u32 = 5;
+const CELL_HEIGHT: u32 = 8;
+
+const BACKLIGHT_ON: &str = "\x1b[97;44m";
+const BACKLIGHT_OFF: &str = "\x1b[90;40m";
+const RESET: &str = "\x1b[0m";
+
+pub(crate) struct TerminalLcd {
+    glyphs: [char; 8],
+    color: bool,
+    /// Last box drawn and its height, so unchanged states are skipped and redraws overwrite it.
+    last: Option<(String, usize)>,
+}
+
+impl TerminalLcd {
+    pub(crate) fn new() -> Self {
+        Self::with_color(io::stdout().is_terminal())
+    }
+
+    fn with_color(color: bool) -> Self {
+        Self {
+            glyphs: PRELOADED_GLYPHS,
+            color,
+            last: None,
+        }
+    }
+
+    pub(crate) fn load_glyph(&mut self, slot: u8, bitmap: &[u8; 8]) {
+        if let Some(glyph) = self.glyphs.get_mut(slot as usize) {
+            *glyph = approximate_glyph(bitmap);
+        }
+    }
+
+    /// Back to the preloaded glyphs, as after the hardware is initialised again.
+    pub(crate) fn reset_glyphs(&mut self) {
+        self.glyphs = PRELOADED_GLYPHS;
+    }
+
+    /// Draw the current state unless it matches what is already on screen.
+    pub(crate) fn present(&mut self, rows: &[Vec<char>], backlight: bool, blink: bool) {
+        let text = self.compose(rows, backlight, blink);
+        if self.last.as_ref().is_some_and(|(last, _)| *last == text) {
+            return;
+        }
+        let mut out = io::stdout().lock();
+        if let (true, Some((_, height))) = (self.color, self.last.as_ref()) {
+            let _ = write!(out, "\x1b[{height}A\x1b[J");
+        }
+        let _ = out.write_all(text.as_bytes());
+        let _ = out.flush();
+        let height = rows.len() + 3;
+        self.last = Some((text, height));
+    }
+
+    fn compose(&self, rows: &[Vec<char>], backlight: bool, blink: bool) -> String {
+        let cols = rows.first().map_or(0, Vec::len);
+        let border = "─".repeat(cols);
+        let (tint, reset) = match (self.color, backlight) {
+            (false, _) => ("", ""),
+            (true, true) => (BACKLIGHT_ON, RESET),
+            (true, false) => (BACKLIGHT_OFF, RESET),
+        };
+        let mut out = format!("┌{border}┐\n");
+        for row in rows {
+            let cells: String = row.iter().map(|ch| self.cell(*ch)).collect();
+            out.push_str(&format!("│{tint}{cells}{reset}│\n"));
+        }
+        out.push_str(&format!("└{border}┘\n"));
+        out.push_str(&format!(
+            " backlight={} blink={}\n",
+            if backlight { "on" } else { "off" },
+            if blink { "on" } else { "off" }
+        ));
+        out
+    }
+
+    fn cell(&self, ch: char) -> char {
+        printable(ch, &self.glyphs)
+    }
+}
+
+/// `rows` as the terminal preview would draw them, with each CGRAM slot loaded since the bar
+/// glyphs shown as its closest block character.
+pub(crate) fn preview_rows(rows: &[Vec<char>], loaded: &[Option<[u8; 8]>; 8]) -> Vec<String> {
+    let mut glyphs = PRELOADED_GLYPHS;
+    for (glyph, bitmap) in glyphs.iter_mut().zip(loaded) {
+        if let Some(bitmap) = bitmap {
+            *glyph = approximate_glyph(bitmap);
+        }
+    }
+    rows.iter()
+        .map(|row| row.iter().map(|ch| printable(*ch, &glyphs)).collect())
+        .collect()
+}
+
+fn printable(ch: char, glyphs: &[char; 8]) -> char {
+    // Same rule as the driver: the low byte selects a CGRAM slot or a ROM character.
+    match ch as u32 {
+        code @ 0..=7 => glyphs[code as usize],
+        0x20..=0x7e => ch,
+        _ => glass_char(ch).unwrap_or('?'),
+    }
+}
+
+/// Closest block character to a 5x8 CGRAM bitmap: left-aligned columns become partial-width
+/// blocks, rows filled from the bottom partial-height blocks, anything else a density shade.
+fn approximate_glyph(bitmap: &[u8; 8]) -> char {
+    let rows = bitmap.map(|row| row & 0x1f);
+    let lit: u32 = rows.iter().map(|row| row.count_ones()).sum();
+    if lit == 0 {
+        return ' ';
+    }
+    // Bit 4 is the leftmost pixel; set the three unused high bits so leading ones count from it.
+    let left_aligned = |row: u8| (row | 0xe0).leading_ones() - 3 == row.count_ones();
+    if rows.iter().all(|row| *row == rows[0]) && left_aligned(rows[0]) {
+        let eighths = (rows[0].count_ones() * 8 + CELL_WIDTH / 2) / CELL_WIDTH;
+        return LEFT_BLOCKS[eighths as usize];
+    }
+    let filled = rows.iter().rev().take_while(|row| **row == 0x1f).count();
+    if rows[..rows.len() - filled].iter().all(|row| *row == 0) {
+        return LOWER_BLOCKS[filled];
+    }
+    match lit * 4 / (CELL_WIDTH * CELL_HEIGHT) {
+        0 => '░',
+        1 => '▒',
+        2 => '▓',
+        _ => '█',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitmaps_map_to_the_nearest_block() {
+        assert_eq!(approximate_glyph(&[0; 8]), ' ');
+        assert_eq!(approximate_glyph(&[0x18; 8]), '▍');
+        assert_eq!(approximate_glyph(&[0x1f; 8]), '█');
+        assert_eq!(
+            approximate_glyph(&[0, 0, 0, 0, 0x1f, 0x1f, 0x1f, 0x1f]),
+            '▄'
+        );
+        // Heart: neither a bar nor a fill, so it falls back to a shade.
+        assert_eq!(
+            approximate_glyph(&[0x0a, 0x1f, 0x1f, 0x1f, 0x0e, 0x04, 0, 0]),
+            '▓'
+        );
+        assert_eq!(approximate_glyph(&[0, 0, 0, 0, 0, 0, 0, 0x0e]), '░');
+    }
+
+    #[test]
+    fn compose_boxes_the_rows_and_substitutes_glyphs() {
+        let mut terminal = TerminalLcd::with_color(false);
+        terminal.load_glyph(2, &[0x1f; 8]);
+        let rows = vec![
+            "CPU\u{2}\u{0}".chars().collect::<Vec<_>>(),
+            "é ok".chars().chain([' ']).collect(),
+        ];
+        assert_eq!(
+            terminal.compose(&rows, true, false),
+            "┌─────┐\n│CPU█ │\n│? ok │\n└─────┘\n backlight=on blink=off\n"
+        );
+    }
+}
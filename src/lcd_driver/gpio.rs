@@ -0,0 +1,254 @@
+//! HD44780 wired straight to GPIO in 4-bit mode, for glass without a PCF8574 backpack.
+//!
+//! [`GpioBus`] stands in for the backpack: it takes the same port bytes the driver would send
+//! over I2C and drives one pin per PCF8574 output (RS, E, D4-D7 and, optionally, the
+//! backlight), so [`Hd44780`](super::Hd44780) runs unchanged on top of it. R/W must be tied to
+//! ground; like the backpack path, the driver never reads the busy flag.
+
+use std::time::{Duration, Instant};
+
+use super::{I2cBus, SHIFT_BACKLIGHT};
+use crate::{config::LcdGpioConfig, Error, Result};
+
+/// E must stay high for at least 450 ns.
+const ENABLE_PULSE: Duration = Duration::from_micros(1);
+/// Most instructions take 37 µs after E falls; the backpack's 100 kHz bus gave us that for free.
+const EXECUTION_TIME: Duration = Duration::from_micros(50);
+
+const BIT_RS: u8 = 0;
+const BIT_E: u8 = 2;
+const BIT_D4: u8 = 4;
+
+/// One output line the bus can drive.
+pub trait OutputLine {
+    fn write(&mut self, high: bool);
+}
+
+#[cfg(target_os = "linux")]
+impl OutputLine for rppal::gpio::OutputPin {
+    fn write(&mut self, high: bool) {
+        if high {
+            self.set_high();
+        } else {
+            self.set_low();
+        }
+    }
+}
+
+/// The lines a backpack would drive, indexed like its output port.
+pub struct GpioBus<L: OutputLine> {
+    rs: L,
+    e: L,
+    data: [L; 4],
+    backlight: Option<L>,
+    enabled: bool,
+}
+
+impl<L: OutputLine> GpioBus<L> {
+    pub fn new(rs: L, e: L, data: [L; 4], backlight: Option<L>) -> Self {
+        Self {
+            rs,
+            e,
+            data,
+            backlight,
+            enabled: false,
+        }
+    }
+}
+
+/// Hands out output lines by pin number. A pin still held by an earlier line is refused, the
+/// way the kernel refuses a second claim, until that line is dropped.
+pub trait PinSource {
+    type Line: OutputLine;
+    fn output(&mut self, pin: u8, high: bool) -> Result<Self::Line>;
+}
+
+#[cfg(target_os = "linux")]
+fn gpio_err(e: rppal::gpio::Error) -> Error {
+    Error::Io(std::io::Error::other(e))
+}
+
+#[cfg(target_os = "linux")]
+impl PinSource for rppal::gpio::Gpio {
+    type Line = rppal::gpio::OutputPin;
+
+    fn output(&mut self, pin: u8, high: bool) -> Result<Self::Line> {
+        let pin = self.get(pin).map_err(gpio_err)?;
+        Ok(if high {
+            pin.into_output_high()
+        } else {
+            pin.into_output_low()
+        })
+    }
+}
+
+impl<L: OutputLine> GpioBus<L> {
+    /// Claim the pins in `[lcd.gpio]` from `source`; RS, E and D4-D7 are required.
+    pub fn claim<S: PinSource<Line = L>>(source: &mut S, pins: &LcdGpioConfig) -> Result<Self> {
+        let backlight = match pins.backlight_pin {
+            Some(pin) => Some(source.output(pin, true)?),
+            None => None,
+        };
+        let mut output = |name: &str, pin: Option<u8>| -> Result<L> {
+            let pin = pin.ok_or_else(|| {
+                Error::InvalidArgs(format!("{name} must be set with display_driver = \"gpio\""))
+            })?;
+            source.output(pin, false)
+        };
+        Ok(Self::new(
+            output("lcd.gpio.rs_pin", pins.rs_pin)?,
+            output("lcd.gpio.e_pin", pins.e_pin)?,
+            [
+                output("lcd.gpio.d4_pin", pins.d4_pin)?,
+                output("lcd.gpio.d5_pin", pins.d5_pin)?,
+                output("lcd.gpio.d6_pin", pins.d6_pin)?,
+                output("lcd.gpio.d7_pin", pins.d7_pin)?,
+            ],
+            backlight,
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl GpioBus<rppal::gpio::OutputPin> {
+    /// Claim the pins in `[lcd.gpio]` from the Pi's GPIO chip.
+    pub fn open(pins: &LcdGpioConfig) -> Result<Self> {
+        let mut gpio = rppal::gpio::Gpio::new().map_err(gpio_err)?;
+        Self::claim(&mut gpio, pins)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl GpioBus<NoLine> {
+    pub fn open(_pins: &LcdGpioConfig) -> Result<Self> {
+        Err(Error::InvalidArgs(
+            "GPIO-wired LCDs are unsupported on this platform".into(),
+        ))
+    }
+}
+
+/// Placeholder line type off Linux, where there are no pins to open.
+#[cfg(not(target_os = "linux"))]
+pub struct NoLine;
+
+#[cfg(not(target_os = "linux"))]
+impl OutputLine for NoLine {
+    fn write(&mut self, _high: bool) {}
+}
+
+impl<L: OutputLine> I2cBus for GpioBus<L> {
+    /// Set RS, D4-D7 and the backlight first, then E, so the data is stable before E moves.
+    /// The address is meaningless without a backpack and ignored.
+    fn write_byte(&mut self, _addr: u8, byte: u8) -> Result<()> {
+        let bit = |shift: u8| byte & (1 << shift) != 0;
+        self.rs.write(bit(BIT_RS));
+        for (idx, line) in self.data.iter_mut().enumerate() {
+            line.write(bit(BIT_D4 + idx as u8));
+        }
+        if let Some(line) = self.backlight.as_mut() {
+            line.write(bit(SHIFT_BACKLIGHT));
+        }
+        let enable = bit(BIT_E);
+        if enable == self.enabled {
+            return Ok(());
+        }
+        self.e.write(enable);
+        self.enabled = enable;
+        if enable {
+            spin(ENABLE_PULSE);
+        } else {
+            // The controller latches on the falling edge and is busy from there.
+            std::thread::sleep(EXECUTION_TIME);
+        }
+        Ok(())
+    }
+}
+
+/// Busy-wait for delays far below the scheduler's sleep granularity.
+fn spin(delay: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < delay {
+        std::hint::spin_loop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lcd_driver::Hd44780;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Shared log of `(line, level)` writes.
+    type Log = Rc<RefCell<Vec<(&'static str, bool)>>>;
+
+    struct Line {
+        name: &'static str,
+        log: Log,
+    }
+
+    impl OutputLine for Line {
+        fn write(&mut self, high: bool) {
+            self.log.borrow_mut().push((self.name, high));
+        }
+    }
+
+    fn bus(log: &Log) -> GpioBus<Line> {
+        let line = |name| Line {
+            name,
+            log: log.clone(),
+        };
+        GpioBus::new(
+            line("rs"),
+            line("e"),
+            [line("d4"), line("d5"), line("d6"), line("d7")],
+            Some(line("bl")),
+        )
+    }
+
+    /// Nibbles latched on each falling edge of E, with RS, reconstructed from the pin log.
+    fn latched(log: &[(&'static str, bool)]) -> Vec<(bool, u8)> {
+        let mut levels = std::collections::HashMap::new();
+        let mut out = Vec::new();
+        for (name, high) in log {
+            if *name == "e" && !high && levels.get("e") == Some(&true) {
+                let nibble = ["d4", "d5", "d6", "d7"]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, pin)| levels.get(*pin) == Some(&true))
+                    .fold(0u8, |acc, (idx, _)| acc | 1 << idx);
+                out.push((levels.get("rs") == Some(&true), nibble));
+            }
+            levels.insert(*name, *high);
+        }
+        out
+    }
+
+    #[test]
+    fn maps_backpack_bytes_onto_pins_and_strobes_e() {
+        let log = Log::default();
+        let mut bus = bus(&log);
+        // RS high, backlight on, data 0b1010, E high then low: one latched nibble.
+        bus.write_byte(0x27, 0b1010_1101).unwrap();
+        bus.write_byte(0x27, 0b1010_1001).unwrap();
+        let log = log.borrow();
+        assert_eq!(latched(&log), vec![(true, 0b1010)]);
+        // E only moves after the data lines, and only when it changes.
+        let e_writes: Vec<_> = log.iter().filter(|(name, _)| *name == "e").collect();
+        assert_eq!(e_writes, vec![&("e", true), &("e", false)]);
+        assert_eq!(log.iter().position(|(name, _)| *name == "e"), Some(6));
+        assert!(log.contains(&("bl", true)));
+    }
+
+    #[test]
+    fn hd44780_writes_characters_as_two_nibbles() {
+        let log = Log::default();
+        let mut lcd = Hd44780::new(bus(&log), 0, 16, 2).unwrap();
+        log.borrow_mut().clear();
+        lcd.write_at(0, 0, "A").unwrap();
+        // DDRAM address 0x80 as a command, then 'A' (0x41) as data.
+        assert_eq!(
+            latched(&log.borrow()),
+            vec![(false, 0x8), (false, 0x0), (true, 0x4), (true, 0x1)]
+        );
+    }
+}
@@ -6,7 +6,10 @@ use std::time::Duration;
 use crate::{Error, Result};
 
 pub mod external;
+pub mod gpio;
 pub mod pcf8574;
+pub mod sim;
+pub mod ssd1306;
 
 /// Backlight state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,6 +21,23 @@ pub enum Backlight {
 /// Minimal trait to allow swapping the I2C backend (for tests or rppal).
 pub trait I2cBus {
     fn write_byte(&mut self, addr: u8, byte: u8) -> Result<()>;
+
+    /// Send `bytes` as a single transaction. The PCF8574 backpack only ever needs single bytes;
+    /// OLED controllers take a control byte followed by a run of commands or pixel data.
+    fn write_bytes(&mut self, _addr: u8, _bytes: &[u8]) -> Result<()> {
+        Err(Error::InvalidArgs(
+            "this I2C bus only supports single-byte writes".into(),
+        ))
+    }
+
+    /// Latch `bytes` onto the PCF8574 outputs in order. The expander takes each byte of a
+    /// multi-byte write as its next output state, so buses that can send them in one
+    /// transaction override this; the default sends one write per byte.
+    fn write_batch(&mut self, addr: u8, bytes: &[u8]) -> Result<()> {
+        bytes
+            .iter()
+            .try_for_each(|byte| self.write_byte(addr, *byte))
+    }
 }
 
 /// HD44780 driver that targets a PCF8574 backpack in 4-bit mode.
@@ -30,8 +50,13 @@ pub struct Hd44780<B: I2cBus> {
     cursor_y: u8,
     implied_newline: bool,
     backlight: Backlight,
+    /// Nibble writes queued by [`Hd44780::batched`]; `None` writes straight to the bus.
+    pending: Option<Vec<u8>>,
 }
 
+/// Most bytes sent in one batched transaction; a 20-column row plus its cursor move fits.
+const MAX_BATCH_BYTES: usize = 128;
+
 // Bit masks from python_lcd.
 const MASK_RS: u8 = 0x01;
 #[allow(dead_code)]
@@ -69,6 +94,7 @@ impl<B: I2cBus> Hd44780<B> {
             cursor_y: 0,
             implied_newline: false,
             backlight: Backlight::On,
+            pending: None,
         };
 
         driver.bus.write_byte(driver.addr, 0)?;
@@ -103,6 +129,11 @@ impl<B: I2cBus> Hd44780<B> {
         Ok(driver)
     }
 
+    /// Underlying bus, e.g. to inspect a [`sim::Hd44780Sim`] in tests.
+    pub fn bus(&self) -> &B {
+        &self.bus
+    }
+
     /// Clear display and home cursor. Requires the longer delay.
     pub fn clear(&mut self) -> Result<()> {
         self.write_command(LCD_CLR)?;
@@ -148,8 +179,18 @@ impl<B: I2cBus> Hd44780<B> {
 
     /// Position cursor and write a line (wraps using putchar logic).
     pub fn write_line(&mut self, row: u8, text: &str) -> Result<()> {
-        self.move_to(0, row)?;
-        self.putstr(text)
+        self.batched(|lcd| {
+            lcd.move_to(0, row)?;
+            lcd.putstr(text)
+        })
+    }
+
+    /// Write `text` starting at an arbitrary cell, for partial row updates.
+    pub fn write_at(&mut self, col: u8, row: u8, text: &str) -> Result<()> {
+        self.batched(|lcd| {
+            lcd.move_to(col, row)?;
+            lcd.putstr(text)
+        })
     }
 
     pub fn move_to(&mut self, cursor_x: u8, cursor_y: u8) -> Result<()> {
@@ -195,14 +236,15 @@ impl<B: I2cBus> Hd44780<B> {
     }
 
     pub fn putstr(&mut self, text: &str) -> Result<()> {
-        for ch in text.chars() {
-            self.putchar(ch)?;
-        }
-        Ok(())
+        self.batched(|lcd| text.chars().try_for_each(|ch| lcd.putchar(ch)))
     }
 
     /// Extended string: supports `{0xNN}` placeholders to emit raw bytes (e.g., custom chars).
     pub fn putstr_extended(&mut self, text: &str) -> Result<()> {
+        self.batched(|lcd| lcd.putstr_extended_unbatched(text))
+    }
+
+    fn putstr_extended_unbatched(&mut self, text: &str) -> Result<()> {
         let mut idx = 0;
         let bytes = text.as_bytes();
         while idx < bytes.len() {
@@ -266,10 +308,37 @@ impl<B: I2cBus> Hd44780<B> {
         Ok(())
     }
 
+    /// Run `op` with its nibble writes queued, then send them in as few bus transactions as
+    /// the bus allows. Each write is still a full E-high/E-low pair; at the PCF8574's 100 kHz a
+    /// byte takes about 90 µs, so back-to-back strobes stay longer apart than the 37 µs most
+    /// instructions need, just as they do with one write per byte.
+    fn batched(&mut self, op: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        if self.pending.is_some() {
+            return op(self);
+        }
+        self.pending = Some(Vec::with_capacity(MAX_BATCH_BYTES));
+        let result = op(self);
+        let flushed = self.flush();
+        self.pending = None;
+        result.and(flushed)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self.pending.as_mut() {
+            Some(bytes) if !bytes.is_empty() => {
+                let result = self.bus.write_batch(self.addr, bytes);
+                bytes.clear();
+                result
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn write_command(&mut self, cmd: u8) -> Result<()> {
         self.write_nibble(cmd, false)?;
         self.write_nibble(cmd << 4, false)?;
         if cmd <= 3 {
+            self.flush()?;
             // HOME/CLEAR need extra delay.
             // We do not read the busy flag (RW is not wired on most PCF8574 backpacks),
             // so we use conservative fixed delays.
@@ -294,8 +363,18 @@ impl<B: I2cBus> Hd44780<B> {
         }
         byte |= (nibble >> 4) << SHIFT_DATA;
 
-        self.bus.write_byte(self.addr, byte | MASK_E)?;
-        self.bus.write_byte(self.addr, byte)?;
+        match self.pending.as_mut() {
+            Some(bytes) => {
+                bytes.extend([byte | MASK_E, byte]);
+                if bytes.len() >= MAX_BATCH_BYTES {
+                    self.flush()?;
+                }
+            }
+            None => {
+                self.bus.write_byte(self.addr, byte | MASK_E)?;
+                self.bus.write_byte(self.addr, byte)?;
+            }
+        }
         Ok(())
     }
 
@@ -350,6 +429,7 @@ mod tests {
         decoded: Vec<DecodedByte>,
         pending_enable: Option<(bool, u8)>,
         partial_byte: Option<(bool, u8)>,
+        batches: usize,
     }
 
     #[derive(Debug, Clone, Copy)]
@@ -369,6 +449,13 @@ mod tests {
             self.writes.push((addr, byte));
             Ok(())
         }
+
+        fn write_batch(&mut self, addr: u8, bytes: &[u8]) -> Result<()> {
+            self.batches += 1;
+            bytes
+                .iter()
+                .try_for_each(|byte| self.write_byte(addr, *byte))
+        }
     }
 
     impl MockBus {
@@ -499,4 +586,21 @@ mod tests {
         assert_eq!(driver.cursor_x, 3);
         assert_eq!(driver.cursor_y, 1);
     }
+
+    #[test]
+    fn write_line_sends_one_batch() {
+        let mut driver = Hd44780::new(MockBus::default(), 0x27, 16, 2).unwrap();
+        driver.bus.decoded.clear();
+        driver.write_line(1, "hello").unwrap();
+        assert_eq!(driver.bus.batches, 1);
+        let data: Vec<u8> = driver
+            .bus
+            .decoded
+            .iter()
+            .filter(|d| d.rs)
+            .map(|d| d.value)
+            .collect();
+        assert_eq!(data, b"hello");
+        assert_eq!(driver.bus.take_decoded_commands(), vec![LCD_DDRAM | 0x40]);
+    }
 }
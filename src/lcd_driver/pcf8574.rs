@@ -68,6 +68,17 @@ impl I2cBus for RppalBus {
             .map_err(map_i2c_err)?;
         self.inner.block_write(byte, &[]).map_err(map_i2c_err)
     }
+
+    fn write_bytes(&mut self, addr: u8, bytes: &[u8]) -> Result<()> {
+        self.inner
+            .set_slave_address(addr.into())
+            .map_err(map_i2c_err)?;
+        self.inner.write(bytes).map(|_| ()).map_err(map_i2c_err)
+    }
+
+    fn write_batch(&mut self, addr: u8, bytes: &[u8]) -> Result<()> {
+        self.write_bytes(addr, bytes)
+    }
 }
 
 /// Linux `I2cdev` implementation so non-Raspberry Pi hosts can exercise the LCD path.
@@ -108,6 +119,15 @@ impl I2cBus for I2cdevBus {
         EmbeddedHal1I2c::<SevenBitAddress>::write(&mut self.inner, addr, &[byte])
             .map_err(map_i2cdev_err)
     }
+
+    fn write_bytes(&mut self, addr: u8, bytes: &[u8]) -> Result<()> {
+        EmbeddedHal1I2c::<SevenBitAddress>::write(&mut self.inner, addr, bytes)
+            .map_err(map_i2cdev_err)
+    }
+
+    fn write_batch(&mut self, addr: u8, bytes: &[u8]) -> Result<()> {
+        self.write_bytes(addr, bytes)
+    }
 }
 
 /// Non-Linux stub to satisfy builds on dev hosts; returns errors at runtime.
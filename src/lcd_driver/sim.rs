@@ -0,0 +1,369 @@
+//! In-memory HD44780 behind a PCF8574 backpack, for tests that need to assert exactly which
+//! bytes land in which DDRAM cells.
+//!
+//! The model decodes the same I2C byte stream real glass sees (E falling edge, 8-bit until
+//! function set switches to 4-bit) and tracks DDRAM, CGRAM, the address counter with its
+//! 2-line wrap, entry mode, display shift and the display/cursor flags.
+
+use super::{I2cBus, MASK_E, MASK_RS, SHIFT_BACKLIGHT, SHIFT_DATA};
+use crate::Result;
+
+const DDRAM_BYTES: usize = 0x80;
+const CGRAM_BYTES: usize = 0x40;
+/// Characters per line in 2-line mode; 1-line mode uses one 80-character line.
+const LINE_LEN_2: u8 = 40;
+const LINE_LEN_1: u8 = 80;
+const LINE2_BASE: u8 = 0x40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Ddram,
+    Cgram,
+}
+
+/// Simulated controller; implements [`I2cBus`] so it can back a real [`super::Hd44780`].
+#[derive(Debug, Clone)]
+pub struct Hd44780Sim {
+    cols: u8,
+    rows: u8,
+    ddram: [u8; DDRAM_BYTES],
+    cgram: [u8; CGRAM_BYTES],
+    ac: u8,
+    target: Target,
+    increment: bool,
+    shift_on_write: bool,
+    display_shift: i16,
+    two_line: bool,
+    four_bit: bool,
+    display_on: bool,
+    cursor_on: bool,
+    blink_on: bool,
+    backlight: bool,
+    strobe: Option<u8>,
+    high_nibble: Option<(bool, u8)>,
+    invalid_addresses: usize,
+}
+
+impl Hd44780Sim {
+    /// `cols`/`rows` describe the glass, used only to map DDRAM onto visible cells.
+    pub fn new(cols: u8, rows: u8) -> Self {
+        Self {
+            cols,
+            rows,
+            // Power-on DDRAM is undefined; spaces keep snapshots readable.
+            ddram: [b' '; DDRAM_BYTES],
+            cgram: [0; CGRAM_BYTES],
+            ac: 0,
+            target: Target::Ddram,
+            increment: true,
+            shift_on_write: false,
+            display_shift: 0,
+            two_line: false,
+            four_bit: false,
+            display_on: false,
+            cursor_on: false,
+            blink_on: false,
+            backlight: false,
+            strobe: None,
+            high_nibble: None,
+            invalid_addresses: 0,
+        }
+    }
+
+    /// DDRAM byte behind a visible cell, after display shift.
+    pub fn cell(&self, col: u8, row: u8) -> u8 {
+        self.ddram[self.visible_addr(col, row) as usize]
+    }
+
+    /// Visible rows as strings; each byte maps to `char::from(byte)`, so CGRAM slots read back
+    /// as `'\u{0}'..='\u{7}'` like the display layer's glyph constants.
+    pub fn screen(&self) -> Vec<String> {
+        (0..self.rows)
+            .map(|row| {
+                (0..self.cols)
+                    .map(|col| char::from(self.cell(col, row)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Raw DDRAM indexed by address (`0x00..0x80`).
+    pub fn ddram(&self) -> &[u8; DDRAM_BYTES] {
+        &self.ddram
+    }
+
+    /// Eight rows of the 5x8 pattern stored in CGRAM slot `slot & 7`.
+    pub fn cgram(&self, slot: u8) -> [u8; 8] {
+        let start = (slot as usize & 7) * 8;
+        let mut out = [0u8; 8];
+        out.copy_from_slice(&self.cgram[start..start + 8]);
+        out
+    }
+
+    pub fn address_counter(&self) -> u8 {
+        self.ac
+    }
+
+    pub fn is_two_line(&self) -> bool {
+        self.two_line
+    }
+
+    pub fn display_on(&self) -> bool {
+        self.display_on
+    }
+
+    pub fn cursor_on(&self) -> bool {
+        self.cursor_on
+    }
+
+    pub fn blink_on(&self) -> bool {
+        self.blink_on
+    }
+
+    pub fn backlight_on(&self) -> bool {
+        self.backlight
+    }
+
+    /// Set-DDRAM-address commands that pointed outside the active line layout.
+    pub fn invalid_addresses(&self) -> usize {
+        self.invalid_addresses
+    }
+
+    fn visible_addr(&self, col: u8, row: u8) -> u8 {
+        // Rows 2/3 of 4-line glass continue lines 0/1 one panel-width further along.
+        let (base, offset) = match row {
+            0 => (0, 0),
+            1 => (LINE2_BASE, 0),
+            2 => (0, self.cols),
+            _ => (LINE2_BASE, self.cols),
+        };
+        let (base, len) = if self.two_line {
+            (base, LINE_LEN_2)
+        } else {
+            (0, LINE_LEN_1)
+        };
+        let pos =
+            (i16::from(offset) + i16::from(col) + self.display_shift).rem_euclid(i16::from(len));
+        base + pos as u8
+    }
+
+    fn on_strobe(&mut self, bus_byte: u8) {
+        let rs = bus_byte & MASK_RS != 0;
+        let nibble = bus_byte >> SHIFT_DATA;
+        if !self.four_bit {
+            // 8-bit interface: D0-D3 are not wired on a PCF8574 backpack and read as 0.
+            self.execute(rs, nibble << 4);
+            return;
+        }
+        match self.high_nibble.take() {
+            Some((high_rs, high)) => self.execute(high_rs, (high << 4) | nibble),
+            None => self.high_nibble = Some((rs, nibble)),
+        }
+    }
+
+    fn execute(&mut self, rs: bool, value: u8) {
+        if rs {
+            self.write_data(value);
+            return;
+        }
+        match value {
+            v if v & 0x80 != 0 => self.set_ddram_addr(v & 0x7f),
+            v if v & 0x40 != 0 => {
+                self.target = Target::Cgram;
+                self.ac = v & 0x3f;
+            }
+            v if v & 0x20 != 0 => {
+                self.four_bit = v & 0x10 == 0;
+                self.two_line = v & 0x08 != 0;
+            }
+            v if v & 0x10 != 0 => {
+                let right = v & 0x04 != 0;
+                if v & 0x08 != 0 {
+                    self.display_shift += if right { -1 } else { 1 };
+                } else {
+                    self.step_ac(right);
+                }
+            }
+            v if v & 0x08 != 0 => {
+                self.display_on = v & 0x04 != 0;
+                self.cursor_on = v & 0x02 != 0;
+                self.blink_on = v & 0x01 != 0;
+            }
+            v if v & 0x04 != 0 => {
+                self.increment = v & 0x02 != 0;
+                self.shift_on_write = v & 0x01 != 0;
+            }
+            v if v & 0x02 != 0 => {
+                self.target = Target::Ddram;
+                self.ac = 0;
+                self.display_shift = 0;
+            }
+            0x01 => {
+                self.ddram = [b' '; DDRAM_BYTES];
+                self.target = Target::Ddram;
+                self.ac = 0;
+                self.increment = true;
+                self.display_shift = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn set_ddram_addr(&mut self, addr: u8) {
+        let valid = if self.two_line {
+            addr < LINE_LEN_2 || (LINE2_BASE..LINE2_BASE + LINE_LEN_2).contains(&addr)
+        } else {
+            addr < LINE_LEN_1
+        };
+        if !valid {
+            self.invalid_addresses += 1;
+        }
+        self.target = Target::Ddram;
+        self.ac = addr;
+    }
+
+    fn write_data(&mut self, value: u8) {
+        match self.target {
+            Target::Ddram => self.ddram[self.ac as usize & (DDRAM_BYTES - 1)] = value,
+            Target::Cgram => self.cgram[self.ac as usize & (CGRAM_BYTES - 1)] = value & 0x1f,
+        }
+        self.step_ac(self.increment);
+        if self.shift_on_write && self.target == Target::Ddram {
+            self.display_shift += if self.increment { 1 } else { -1 };
+        }
+    }
+
+    fn step_ac(&mut self, forward: bool) {
+        if self.target == Target::Cgram {
+            self.ac = if forward {
+                (self.ac + 1) & 0x3f
+            } else {
+                self.ac.wrapping_sub(1) & 0x3f
+            };
+            return;
+        }
+        self.ac = match (self.two_line, forward) {
+            (true, true) if self.ac == LINE_LEN_2 - 1 => LINE2_BASE,
+            (true, true) if self.ac == LINE2_BASE + LINE_LEN_2 - 1 => 0,
+            (true, false) if self.ac == 0 => LINE2_BASE + LINE_LEN_2 - 1,
+            (true, false) if self.ac == LINE2_BASE => LINE_LEN_2 - 1,
+            (false, true) if self.ac == LINE_LEN_1 - 1 => 0,
+            (false, false) if self.ac == 0 => LINE_LEN_1 - 1,
+            (_, true) => self.ac + 1,
+            (_, false) => self.ac - 1,
+        };
+    }
+}
+
+impl I2cBus for Hd44780Sim {
+    fn write_byte(&mut self, _addr: u8, byte: u8) -> Result<()> {
+        self.backlight = byte & (1 << SHIFT_BACKLIGHT) != 0;
+        // The controller latches on the falling edge of E.
+        if byte & MASK_E != 0 {
+            self.strobe = Some(byte);
+        } else if let Some(latched) = self.strobe.take() {
+            self.on_strobe(latched);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lcd_driver::Hd44780;
+
+    fn driver(cols: u8, rows: u8) -> Hd44780<Hd44780Sim> {
+        Hd44780::new(Hd44780Sim::new(cols, rows), 0x27, cols, rows).unwrap()
+    }
+
+    /// Bit-bang one byte in 4-bit mode, as the driver would.
+    fn send(sim: &mut Hd44780Sim, rs: bool, value: u8) {
+        for nibble in [value >> 4, value & 0x0f] {
+            let byte = (nibble << SHIFT_DATA) | if rs { MASK_RS } else { 0 };
+            sim.write_byte(0x27, byte | MASK_E).unwrap();
+            sim.write_byte(0x27, byte).unwrap();
+        }
+    }
+
+    #[test]
+    fn init_leaves_display_on_cleared_and_two_line() {
+        let lcd = driver(16, 2);
+        let sim = lcd.bus();
+        assert!(sim.display_on() && sim.is_two_line() && sim.backlight_on());
+        assert!(!sim.cursor_on());
+        assert_eq!(sim.address_counter(), 0);
+        assert_eq!(sim.screen(), vec![" ".repeat(16), " ".repeat(16)]);
+    }
+
+    #[test]
+    fn rows_of_20x4_land_in_their_ddram_cells() {
+        let mut lcd = driver(20, 4);
+        for (row, text) in ["row zero", "row one", "row two", "row three"]
+            .iter()
+            .enumerate()
+        {
+            lcd.write_line(row as u8, text).unwrap();
+        }
+        let screen = lcd.bus().screen();
+        assert_eq!(screen[0], format!("{:<20}", "row zero"));
+        assert_eq!(screen[1], format!("{:<20}", "row one"));
+        assert_eq!(screen[2], format!("{:<20}", "row two"));
+        assert_eq!(screen[3], format!("{:<20}", "row three"));
+        // Row 2 continues line 0 at 0x14; row 3 continues line 1 at 0x54.
+        assert_eq!(lcd.bus().ddram()[0x14], b'r');
+        assert_eq!(lcd.bus().ddram()[0x54], b'r');
+        assert_eq!(lcd.bus().invalid_addresses(), 0);
+    }
+
+    #[test]
+    fn overlong_row_wraps_to_next_row_start() {
+        let mut lcd = driver(20, 4);
+        lcd.write_line(1, "abcdefghijklmnopqrstUV").unwrap();
+        let screen = lcd.bus().screen();
+        assert_eq!(screen[1], "abcdefghijklmnopqrst");
+        assert_eq!(&screen[2][..2], "UV");
+        assert_eq!(lcd.bus().cell(19, 1), b't');
+    }
+
+    #[test]
+    fn custom_char_fills_cgram_and_restores_ddram_cursor() {
+        let mut lcd = driver(16, 2);
+        let pattern = [0x1f, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1f];
+        lcd.move_to(5, 1).unwrap();
+        lcd.custom_char(3, &pattern).unwrap();
+        lcd.putchar('\u{3}').unwrap();
+        assert_eq!(lcd.bus().cgram(3), pattern);
+        assert_eq!(lcd.bus().cell(5, 1), 3);
+        assert_eq!(lcd.bus().screen()[1].chars().nth(5), Some('\u{3}'));
+    }
+
+    #[test]
+    fn address_counter_wraps_between_lines_in_two_line_mode() {
+        let mut sim = driver(16, 2).bus().clone();
+        send(&mut sim, false, 0x80 | 0x27);
+        send(&mut sim, true, b'A');
+        assert_eq!(sim.address_counter(), 0x40);
+        send(&mut sim, true, b'B');
+        send(&mut sim, false, 0x80 | 0x67);
+        send(&mut sim, true, b'C');
+        assert_eq!(sim.address_counter(), 0x00);
+        assert_eq!(sim.cell(0, 1), b'B');
+        send(&mut sim, false, 0x80 | 0x20);
+        assert_eq!(sim.invalid_addresses(), 0);
+        send(&mut sim, false, 0x80 | 0x28);
+        assert_eq!(sim.invalid_addresses(), 1);
+    }
+
+    #[test]
+    fn display_shift_moves_the_visible_window() {
+        let mut lcd = driver(16, 2);
+        lcd.write_line(0, "0123456789").unwrap();
+        let mut sim = lcd.bus().clone();
+        // Shift display left: column 0 now shows DDRAM 0x01.
+        send(&mut sim, false, 0x18);
+        assert_eq!(sim.cell(0, 0), b'1');
+        send(&mut sim, false, 0x02);
+        assert_eq!(sim.cell(0, 0), b'0');
+    }
+}
@@ -0,0 +1,490 @@
+//! SSD1306 / SH1106 128x64 I2C OLED driven as a character display.
+//!
+//! The panel is split into a `cols` x `rows` grid of 5x8 glyph cells so the rest of the crate
+//! keeps using the HD44780 line API. Each text row sits on one 8-pixel page, with rows spread
+//! evenly down the panel. The eight CGRAM slots are emulated: codes 0-7 draw the bitmaps loaded
+//! with [`Ssd1306::custom_char`], and reloading a slot redraws every cell showing it, as CGRAM
+//! would. A framebuffer mirrors the panel so partial writes only send the columns they touch.
+//!
+//! There is no backlight, so backlight off switches the panel off instead. Cursor blink has no
+//! equivalent and is ignored.
+
+use super::{parse_bitmap_row, I2cBus};
+use crate::{Error, Result};
+
+const WIDTH: usize = 128;
+const PAGES: usize = 8;
+const GLYPH_WIDTH: usize = 5;
+/// Narrowest cell: a glyph plus one column of spacing.
+const MIN_CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+
+pub const MAX_COLS: u8 = (WIDTH / MIN_CELL_WIDTH) as u8;
+pub const MAX_ROWS: u8 = PAGES as u8;
+/// Address of most modules; 0x3d when the address jumper is moved.
+pub const DEFAULT_I2C_ADDR: u8 = 0x3c;
+pub const ADDR_CANDIDATES: [u8; 2] = [0x3c, 0x3d];
+
+// First byte of every transaction: the rest is a command list or display RAM data.
+const CONTROL_COMMAND: u8 = 0x00;
+const CONTROL_DATA: u8 = 0x40;
+
+const DISPLAY_OFF: u8 = 0xae;
+const DISPLAY_ON: u8 = 0xaf;
+const SET_PAGE: u8 = 0xb0;
+const SET_COLUMN_LOW: u8 = 0x00;
+const SET_COLUMN_HIGH: u8 = 0x10;
+
+/// The font columns for `ch`: printable ASCII as is, anything else as `?`.
+pub(crate) fn font_glyph(ch: char) -> [u8; GLYPH_WIDTH] {
+    match ch as u32 {
+        code @ 0x20..=0x7e => FONT[(code - 0x20) as usize],
+        _ => FONT[usize::from(b'?' - 0x20)],
+    }
+}
+
+/// Columns 0x20-0x7e of the classic 5x7 font, one byte per column, bit 0 at the top.
+const FONT: [[u8; GLYPH_WIDTH]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5f, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], // #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x56, 0x20, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1c, 0x00], // )
+    [0x14, 0x08, 0x3e, 0x08, 0x14], // *
+    [0x08, 0x08, 0x3e, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], // 0
+    [0x00, 0x42, 0x7f, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4b, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1e], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], // @
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], // A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3e, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], // D
+    [0x7f, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7f, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3e, 0x41, 0x49, 0x49, 0x7a], // G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], // H
+    [0x00, 0x41, 0x7f, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], // J
+    [0x7f, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7f, 0x02, 0x0c, 0x02, 0x7f], // M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], // N
+    [0x3e, 0x41, 0x41, 0x41, 0x3e], // O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3e, 0x41, 0x51, 0x21, 0x5e], // Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], // T
+    [0x3f, 0x40, 0x40, 0x40, 0x3f], // U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], // V
+    [0x3f, 0x40, 0x38, 0x40, 0x3f], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7f, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7f, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], // f
+    [0x0c, 0x52, 0x52, 0x52, 0x3e], // g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7d, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], // j
+    [0x7f, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], // l
+    [0x7c, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7c], // q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], // t
+    [0x3c, 0x40, 0x40, 0x20, 0x7c], // u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], // v
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0c, 0x50, 0x50, 0x50, 0x3c], // y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x10, 0x08, 0x08, 0x10, 0x08], // ~
+];
+
+/// The two controllers differ in power-up commands and in where visible column 0 sits in RAM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Controller {
+    Ssd1306,
+    /// 132-column RAM with the 128 visible columns centred, and no charge pump command.
+    Sh1106,
+}
+
+impl Controller {
+    /// Everything up to display on: display off, clock, multiplex 64, offset 0, start line 0,
+    /// power, page addressing, segment/COM remap for the usual mounting, contrast and precharge.
+    fn init_sequence(self) -> &'static [u8] {
+        match self {
+            Controller::Ssd1306 => &[
+                0xae, 0xd5, 0x80, 0xa8, 0x3f, 0xd3, 0x00, 0x40, 0x8d, 0x14, 0x20, 0x02, 0xa1, 0xc8,
+                0xda, 0x12, 0x81, 0xcf, 0xd9, 0xf1, 0xdb, 0x40, 0xa4, 0xa6,
+            ],
+            Controller::Sh1106 => &[
+                0xae, 0xd5, 0x80, 0xa8, 0x3f, 0xd3, 0x00, 0x40, 0xad, 0x8b, 0xa1, 0xc8, 0xda, 0x12,
+                0x81, 0xcf, 0xd9, 0x1f, 0xdb, 0x40, 0xa4, 0xa6,
+            ],
+        }
+    }
+
+    fn column_offset(self) -> usize {
+        match self {
+            Controller::Ssd1306 => 0,
+            Controller::Sh1106 => 2,
+        }
+    }
+}
+
+pub struct Ssd1306<B: I2cBus> {
+    bus: B,
+    addr: u8,
+    controller: Controller,
+    cols: u8,
+    rows: u8,
+    cell_width: usize,
+    /// Pages between the tops of consecutive text rows.
+    row_pitch: usize,
+    /// Characters currently drawn, so reloading a custom glyph can redraw its cells.
+    cells: Vec<Vec<char>>,
+    framebuffer: Vec<[u8; WIDTH]>,
+    custom: [[u8; GLYPH_WIDTH]; 8],
+}
+
+impl<B: I2cBus> Ssd1306<B> {
+    /// Initialise the panel, blank it and switch it on.
+    pub fn new(bus: B, addr: u8, controller: Controller, cols: u8, rows: u8) -> Result<Self> {
+        if !(1..=MAX_COLS).contains(&cols) || !(1..=MAX_ROWS).contains(&rows) {
+            return Err(Error::InvalidArgs(format!(
+                "{cols}x{rows} does not fit a 128x64 OLED (at most {MAX_COLS}x{MAX_ROWS})"
+            )));
+        }
+        let mut driver = Self {
+            bus,
+            addr,
+            controller,
+            cols,
+            rows,
+            cell_width: WIDTH / cols as usize,
+            row_pitch: PAGES / rows as usize,
+            cells: vec![vec![' '; cols as usize]; rows as usize],
+            framebuffer: vec![[0; WIDTH]; PAGES],
+            custom: [[0; GLYPH_WIDTH]; 8],
+        };
+        driver.command(controller.init_sequence())?;
+        driver.clear()?;
+        driver.display_on()?;
+        Ok(driver)
+    }
+
+    /// Underlying bus, e.g. to inspect recorded transactions in tests.
+    pub fn bus(&self) -> &B {
+        &self.bus
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        for row in &mut self.cells {
+            row.fill(' ');
+        }
+        for page in &mut self.framebuffer {
+            page.fill(0);
+        }
+        for page in 0..PAGES {
+            self.flush(page, 0, WIDTH)?;
+        }
+        Ok(())
+    }
+
+    pub fn display_on(&mut self) -> Result<()> {
+        self.command(&[DISPLAY_ON])
+    }
+
+    pub fn display_off(&mut self) -> Result<()> {
+        self.command(&[DISPLAY_OFF])
+    }
+
+    pub fn write_line(&mut self, row: u8, text: &str) -> Result<()> {
+        self.write_at(0, row, text)
+    }
+
+    /// Draw `text` from `col` on `row`; characters past the last column are dropped.
+    pub fn write_at(&mut self, col: u8, row: u8, text: &str) -> Result<()> {
+        if row >= self.rows {
+            return Err(Error::InvalidArgs(format!(
+                "row {row} out of bounds for display with {} rows",
+                self.rows
+            )));
+        }
+        let first = col as usize;
+        let mut last = first;
+        for (idx, ch) in (first..self.cols as usize).zip(text.chars()) {
+            self.cells[row as usize][idx] = ch;
+            self.draw_cell(row, idx);
+            last = idx + 1;
+        }
+        if last == first {
+            return Ok(());
+        }
+        let page = self.page(row);
+        self.flush(page, first * self.cell_width, last * self.cell_width)
+    }
+
+    /// Load a 5x8 bitmap (one row per byte, bit 4 leftmost) into slot `location` (0-7).
+    pub fn custom_char(&mut self, location: u8, pattern: &[u8; 8]) -> Result<()> {
+        let slot = location & 0x7;
+        self.custom[slot as usize] = rows_to_columns(pattern);
+        for row in 0..self.rows {
+            let cols: Vec<usize> = (0..self.cols as usize)
+                .filter(|&idx| self.cells[row as usize][idx] as u32 == u32::from(slot))
+                .collect();
+            for idx in &cols {
+                self.draw_cell(row, *idx);
+            }
+            if let (Some(first), Some(last)) = (cols.first(), cols.last()) {
+                let page = self.page(row);
+                self.flush(page, first * self.cell_width, (last + 1) * self.cell_width)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Bitmaps written as strings of '1'/'0'/'#'/'.', loaded from slot 0 up.
+    pub fn load_custom_bitmaps(&mut self, bitmaps: &[[&str; 8]]) -> Result<()> {
+        for (idx, rows) in bitmaps.iter().enumerate().take(8) {
+            let mut pattern = [0u8; 8];
+            for (line, row) in pattern.iter_mut().zip(rows) {
+                *line = parse_bitmap_row(row)?;
+            }
+            self.custom_char(idx as u8, &pattern)?;
+        }
+        Ok(())
+    }
+
+    fn page(&self, row: u8) -> usize {
+        row as usize * self.row_pitch
+    }
+
+    fn draw_cell(&mut self, row: u8, col: usize) {
+        let glyph = self.glyph(self.cells[row as usize][col]);
+        let x = col * self.cell_width;
+        let page = self.page(row);
+        let cell = &mut self.framebuffer[page][x..x + self.cell_width];
+        cell.fill(0);
+        cell[..GLYPH_WIDTH].copy_from_slice(&glyph);
+    }
+
+    fn glyph(&self, ch: char) -> [u8; GLYPH_WIDTH] {
+        // Same rule as the HD44780: codes 0-7 select a custom slot, printable ASCII the font.
+        match ch as u32 {
+            code @ 0..=7 => self.custom[code as usize],
+            _ => font_glyph(ch),
+        }
+    }
+
+    /// Send columns `start..end` of `page` from the framebuffer.
+    fn flush(&mut self, page: usize, start: usize, end: usize) -> Result<()> {
+        let column = start + self.controller.column_offset();
+        self.command(&[
+            SET_PAGE | page as u8,
+            SET_COLUMN_LOW | (column & 0x0f) as u8,
+            SET_COLUMN_HIGH | (column >> 4) as u8,
+        ])?;
+        let mut data = Vec::with_capacity(end - start + 1);
+        data.push(CONTROL_DATA);
+        data.extend_from_slice(&self.framebuffer[page][start..end]);
+        self.bus.write_bytes(self.addr, &data)
+    }
+
+    fn command(&mut self, commands: &[u8]) -> Result<()> {
+        let mut data = Vec::with_capacity(commands.len() + 1);
+        data.push(CONTROL_COMMAND);
+        data.extend_from_slice(commands);
+        self.bus.write_bytes(self.addr, &data)
+    }
+}
+
+/// Turn a row-per-byte 5x8 bitmap into the column-per-byte layout the panel pages use.
+fn rows_to_columns(pattern: &[u8; 8]) -> [u8; GLYPH_WIDTH] {
+    let mut columns = [0u8; GLYPH_WIDTH];
+    for (col, out) in columns.iter_mut().enumerate() {
+        for (row, bits) in pattern.iter().enumerate() {
+            if (bits >> (GLYPH_WIDTH - 1 - col)) & 1 == 1 {
+                *out |= 1 << row;
+            }
+        }
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes the command/data stream into panel RAM, like the controller would.
+    #[derive(Debug)]
+    struct PanelBus {
+        ram: [[u8; WIDTH + 4]; PAGES],
+        page: usize,
+        column: usize,
+        on: bool,
+    }
+
+    impl PanelBus {
+        fn new() -> Self {
+            Self {
+                // Power-on RAM is noise; a pattern proves init blanks it.
+                ram: [[0xff; WIDTH + 4]; PAGES],
+                page: 0,
+                column: 0,
+                on: false,
+            }
+        }
+
+        fn visible(&self, page: usize, offset: usize) -> &[u8] {
+            &self.ram[page][offset..offset + WIDTH]
+        }
+    }
+
+    impl I2cBus for PanelBus {
+        fn write_byte(&mut self, _addr: u8, _byte: u8) -> Result<()> {
+            panic!("the OLED never uses single-byte writes");
+        }
+
+        fn write_bytes(&mut self, addr: u8, bytes: &[u8]) -> Result<()> {
+            assert_eq!(addr, DEFAULT_I2C_ADDR);
+            match bytes.split_first() {
+                Some((&CONTROL_DATA, data)) => {
+                    for byte in data {
+                        self.ram[self.page][self.column] = *byte;
+                        self.column += 1;
+                    }
+                }
+                Some((&CONTROL_COMMAND, commands)) => {
+                    for cmd in commands {
+                        match cmd {
+                            0xb0..=0xb7 => self.page = usize::from(cmd & 0x07),
+                            0x00..=0x0f => self.column = (self.column & 0xf0) | usize::from(*cmd),
+                            0x10..=0x1f => {
+                                self.column = (self.column & 0x0f) | usize::from(cmd & 0x0f) << 4
+                            }
+                            &DISPLAY_ON => self.on = true,
+                            &DISPLAY_OFF => self.on = false,
+                            // Init arguments and settings the model does not track.
+                            _ => {}
+                        }
+                    }
+                }
+                other => panic!("unexpected transaction {other:?}"),
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn text_lands_in_its_cell_and_rows_spread_down_the_panel() {
+        let mut oled = Ssd1306::new(
+            PanelBus::new(),
+            DEFAULT_I2C_ADDR,
+            Controller::Ssd1306,
+            16,
+            2,
+        )
+        .unwrap();
+        assert!(oled.bus().on);
+        assert!(oled.bus().visible(0, 0).iter().all(|byte| *byte == 0));
+
+        oled.write_line(0, "A").unwrap();
+        oled.write_at(2, 1, "1").unwrap();
+        // 16 columns leave 8-pixel cells; two rows sit four pages apart.
+        assert_eq!(
+            &oled.bus().visible(0, 0)[..8],
+            &[0x7e, 0x11, 0x11, 0x11, 0x7e, 0, 0, 0]
+        );
+        assert_eq!(
+            &oled.bus().visible(4, 0)[16..21],
+            &FONT[usize::from(b'1' - 0x20)]
+        );
+        assert!(oled.bus().visible(1, 0).iter().all(|byte| *byte == 0));
+
+        // Text past the last column is dropped rather than wrapped.
+        oled.write_at(15, 0, "XY").unwrap();
+        assert!(oled.bus().visible(4, 0)[..16].iter().all(|byte| *byte == 0));
+        assert!(oled.write_line(2, "nope").is_err());
+    }
+
+    #[test]
+    fn custom_slots_redraw_cells_already_showing_them() {
+        let mut oled =
+            Ssd1306::new(PanelBus::new(), DEFAULT_I2C_ADDR, Controller::Sh1106, 20, 4).unwrap();
+        oled.write_line(3, "\u{1}").unwrap();
+        let offset = Controller::Sh1106.column_offset();
+        assert_eq!(&oled.bus().visible(6, offset)[..5], &[0; 5]);
+
+        oled.load_custom_bitmaps(&[["11111"; 8], ["10000"; 8]])
+            .unwrap();
+        assert_eq!(&oled.bus().visible(6, offset)[..6], &[0xff, 0, 0, 0, 0, 0]);
+        // The SH1106 shows RAM from column 2; the hidden columns are never written.
+        assert_eq!(&oled.bus().ram[6][..offset], &[0xff; 2]);
+    }
+
+    #[test]
+    fn geometry_must_fit_the_panel() {
+        assert!(Ssd1306::new(
+            PanelBus::new(),
+            DEFAULT_I2C_ADDR,
+            Controller::Ssd1306,
+            22,
+            2
+        )
+        .is_err());
+        assert!(Ssd1306::new(
+            PanelBus::new(),
+            DEFAULT_I2C_ADDR,
+            Controller::Ssd1306,
+            21,
+            4
+        )
+        .is_ok());
+        assert_eq!(
+            rows_to_columns(&[0x10, 0, 0, 0, 0, 0, 0, 0x01]),
+            [0x01, 0, 0, 0, 0x80]
+        );
+    }
+}
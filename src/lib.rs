@@ -12,16 +12,219 @@ pub mod serial;
 pub mod serial_async;
 pub mod state;
 
+/// The embedding API; see [`display::sink`].
+pub use display::{backend::DisplayBackend, sink::FrameSink};
+pub use payload::RenderFrame;
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
 pub const CACHE_DIR: &str = "/run/serial_lcd_cache";
+/// Named instances keep their runtime files in `CACHE_DIR/instances/<name>`.
+const INSTANCES_DIR: &str = "instances";
+
+static INSTANCE: OnceLock<String> = OnceLock::new();
+
+/// Run this process as the named instance (`--instance`). Call once at startup, before the
+/// config is loaded or a logger is opened, since both resolve their paths from it.
+pub fn set_instance(name: &str) -> Result<()> {
+    validate_instance_name(name)?;
+    if INSTANCE.get_or_init(|| name.to_string()) != name {
+        return Err(Error::InvalidArgs("instance name is already set".into()));
+    }
+    Ok(())
+}
+
+/// The instance name, or `None` for the default instance.
+pub fn instance() -> Option<&'static str> {
+    INSTANCE.get().map(String::as_str)
+}
+
+/// Where this process keeps its runtime files: `CACHE_DIR`, or a per-instance directory below it.
+pub fn cache_dir() -> PathBuf {
+    cache_dir_for(instance())
+}
+
+fn cache_dir_for(instance: Option<&str>) -> PathBuf {
+    match instance {
+        Some(name) => Path::new(CACHE_DIR).join(INSTANCES_DIR).join(name),
+        None => PathBuf::from(CACHE_DIR),
+    }
+}
+
+/// Instance names become path components, so they are kept to a short, safe alphabet.
+pub fn validate_instance_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 32
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgs(format!(
+            "invalid instance name '{name}': use 1-32 letters, digits, '-' or '_'"
+        )))
+    }
+}
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Process exit status for each class of failure, so systemd conditions and scripts can
+/// branch on why a run failed. The table is documented in the README; keep the two in sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    /// Anything not covered below.
+    Failure = 1,
+    /// The command line could not be parsed.
+    Usage = 2,
+    /// The config file is unreadable or fails validation.
+    Config = 3,
+    /// The serial device could not be opened or read.
+    SerialUnavailable = 4,
+    /// The LCD did not initialize.
+    LcdInit = 5,
+    /// The peer handshake failed where one is required.
+    NegotiationFailed = 6,
+    /// The first-run wizard was declined before saving.
+    WizardAborted = 7,
+    /// A payload file could not be read or parsed.
+    InvalidPayload = 8,
+    /// The daemon panicked; a crash report was written under the cache dir.
+    Crashed = 9,
+}
+
+impl ExitCode {
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+}
+
+/// Stable codes for rejected frames, sent in `frame_nack` replies and written to
+/// `protocol_errors.log` so sender-side tooling can branch on them. The table is documented in
+/// the README; keep the two in sync and never give a code a new meaning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameErrorCode {
+    /// Not valid JSON or `key=value` syntax.
+    Json,
+    /// `schema_version` is missing or not an integer.
+    SchemaVersion,
+    /// A field the schema does not define, in strict mode.
+    UnknownField,
+    /// A line, label or row text is longer than its limit.
+    LineTooLong,
+    /// The frame is over its byte limit.
+    FrameTooLarge,
+    /// A field has the wrong type or a value out of range.
+    BadField,
+    /// The frame's `checksum` does not match its contents.
+    BadCrc,
+    /// A compressed envelope was refused or did not decode.
+    Compression,
+    /// A page set is empty or has too many pages.
+    BadPages,
+    /// A row update names a row the display does not have.
+    BadRow,
+    /// Any other parse failure.
+    Invalid,
+}
+
+impl FrameErrorCode {
+    pub const ALL: [FrameErrorCode; 11] = [
+        FrameErrorCode::Json,
+        FrameErrorCode::SchemaVersion,
+        FrameErrorCode::UnknownField,
+        FrameErrorCode::LineTooLong,
+        FrameErrorCode::FrameTooLarge,
+        FrameErrorCode::BadField,
+        FrameErrorCode::BadCrc,
+        FrameErrorCode::Compression,
+        FrameErrorCode::BadPages,
+        FrameErrorCode::BadRow,
+        FrameErrorCode::Invalid,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FrameErrorCode::Json => "E_JSON",
+            FrameErrorCode::SchemaVersion => "E_SCHEMA_VERSION",
+            FrameErrorCode::UnknownField => "E_UNKNOWN_FIELD",
+            FrameErrorCode::LineTooLong => "E_LINE_TOO_LONG",
+            FrameErrorCode::FrameTooLarge => "E_FRAME_TOO_LARGE",
+            FrameErrorCode::BadField => "E_BAD_FIELD",
+            FrameErrorCode::BadCrc => "E_BAD_CRC",
+            FrameErrorCode::Compression => "E_COMPRESSION",
+            FrameErrorCode::BadPages => "E_BAD_PAGES",
+            FrameErrorCode::BadRow => "E_BAD_ROW",
+            FrameErrorCode::Invalid => "E_INVALID",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|code| code.as_str() == name)
+    }
+
+    /// An [`Error::Parse`] for `msg`, tagged with this code as a trailing `[E_...]`.
+    pub fn error(self, msg: impl std::fmt::Display) -> Error {
+        Error::Parse(format!("{msg} [{}]", self.as_str()))
+    }
+}
+
+impl std::fmt::Display for FrameErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     InvalidArgs(String),
     Io(std::io::Error),
     Parse(String),
     ChecksumMismatch,
+    /// `source` tagged with the exit status the process should report for it.
+    Exit {
+        code: ExitCode,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Tag this error with an exit status. A tag already present wins, since it was added
+    /// closer to the cause.
+    pub fn exit_with(self, code: ExitCode) -> Self {
+        match self {
+            Error::Exit { .. } => self,
+            source => Error::Exit {
+                code,
+                source: Box::new(source),
+            },
+        }
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Error::Exit { code, .. } => *code,
+            _ => ExitCode::Failure,
+        }
+    }
+
+    /// Code to report when this error rejected a frame; parse errors without a tag count as
+    /// [`FrameErrorCode::Invalid`]. `None` for errors that are not about the frame itself.
+    pub fn frame_code(&self) -> Option<FrameErrorCode> {
+        match self {
+            Error::ChecksumMismatch => Some(FrameErrorCode::BadCrc),
+            Error::Parse(msg) => Some(
+                msg.strip_suffix(']')
+                    .and_then(|rest| rest.rsplit_once(" ["))
+                    .and_then(|(_, tag)| FrameErrorCode::from_name(tag))
+                    .unwrap_or(FrameErrorCode::Invalid),
+            ),
+            Error::Exit { source, .. } => source.frame_code(),
+            Error::InvalidArgs(_) | Error::Io(_) => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -31,6 +234,7 @@ impl std::fmt::Display for Error {
             Error::Io(err) => write!(f, "io error: {err}"),
             Error::Parse(msg) => write!(f, "parse error: {msg}"),
             Error::ChecksumMismatch => write!(f, "checksum mismatch"),
+            Error::Exit { source, .. } => source.fmt(f),
         }
     }
 }
@@ -42,3 +246,53 @@ impl From<std::io::Error> for Error {
         Error::Io(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_tags_keep_the_innermost_code_and_message() {
+        let err = Error::Parse("bad frame".into())
+            .exit_with(ExitCode::InvalidPayload)
+            .exit_with(ExitCode::Config);
+        assert_eq!(err.exit_code(), ExitCode::InvalidPayload);
+        assert_eq!(err.to_string(), "parse error: bad frame");
+        assert_eq!(Error::ChecksumMismatch.exit_code(), ExitCode::Failure);
+    }
+
+    #[test]
+    fn frame_codes_come_from_the_parse_tag() {
+        let err = FrameErrorCode::LineTooLong.error("line1 must be <= 40 chars");
+        assert_eq!(
+            err.to_string(),
+            "parse error: line1 must be <= 40 chars [E_LINE_TOO_LONG]"
+        );
+        assert_eq!(err.frame_code(), Some(FrameErrorCode::LineTooLong));
+        assert_eq!(
+            Error::Parse("bad frame".into()).frame_code(),
+            Some(FrameErrorCode::Invalid)
+        );
+        assert_eq!(
+            Error::ChecksumMismatch.frame_code(),
+            Some(FrameErrorCode::BadCrc)
+        );
+        assert_eq!(Error::InvalidArgs("x".into()).frame_code(), None);
+        for code in FrameErrorCode::ALL {
+            assert_eq!(FrameErrorCode::from_name(code.as_str()), Some(code));
+        }
+    }
+
+    #[test]
+    fn instances_get_their_own_cache_dir() {
+        assert_eq!(cache_dir_for(None), Path::new(CACHE_DIR));
+        assert_eq!(
+            cache_dir_for(Some("left")),
+            Path::new("/run/serial_lcd_cache/instances/left")
+        );
+        assert!(validate_instance_name("lcd-2_b").is_ok());
+        for bad in ["", "../x", "a/b", "-x", &"x".repeat(33)] {
+            assert!(validate_instance_name(bad).is_err(), "{bad}");
+        }
+    }
+}
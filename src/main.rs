@@ -1,25 +1,90 @@
-use lifelinetty::app::serial_shell;
+use lifelinetty::app::{
+    autobaud, completions, file_transfer, interlock, monitor, preview, provision, remote_config,
+    send, serial_shell, support_bundle, telemetry_export, tunnel_files,
+};
 use lifelinetty::{
     app::App,
-    cli::{Command, RunMode, RunOptions},
-    Result,
+    cli::{self, Command, RunMode, RunOptions},
+    config::{lint, loader, migrate, schema, Config},
+    ExitCode, Result,
 };
 
 fn main() {
     if let Err(err) = try_main() {
         eprintln!("error: {err}");
-        std::process::exit(1);
+        std::process::exit(err.exit_code().code());
     }
 }
 
 fn try_main() -> Result<()> {
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(name) =
+        cli::take_instance(&mut args).map_err(|err| err.exit_with(ExitCode::Usage))?
+    {
+        lifelinetty::set_instance(&name)?;
+    }
 
     match Command::parse(&args) {
         Ok(Command::ShowHelp) => {
             Command::print_help();
             Ok(())
         }
+        Ok(Command::ConfigSchema) => {
+            println!("{}", schema::to_json());
+            Ok(())
+        }
+        Ok(Command::ConfigMigrate { dry_run }) => {
+            let path = loader::default_config_path()?;
+            if !path.exists() {
+                println!("{} does not exist; nothing to migrate", path.display());
+                return Ok(());
+            }
+            let report = migrate::upgrade_file(&path, dry_run)
+                .map_err(|err| err.exit_with(ExitCode::Config))?;
+            print!("{}", report.describe(&path, dry_run));
+            Ok(())
+        }
+        Ok(Command::ConfigValidate { lint }) => {
+            let path = loader::default_config_path()?;
+            let cfg = if path.exists() {
+                loader::check_path(&path).map_err(|err| err.exit_with(ExitCode::Config))?
+            } else {
+                println!("{} does not exist; checking the defaults", path.display());
+                Config::default()
+            };
+            println!("{}: ok", path.display());
+            if lint {
+                for warning in lint::lint(&cfg) {
+                    println!("warning: {warning}");
+                }
+            }
+            Ok(())
+        }
+        Ok(Command::Provision { from }) => {
+            let report = provision::run(std::path::Path::new(&from))?;
+            println!("{}", report.to_json());
+            if let Some(code) = report.exit_code() {
+                std::process::exit(code.code());
+            }
+            Ok(())
+        }
+        Ok(Command::Render { payload, width }) => preview::run(&payload, width),
+        Ok(Command::Send(opts)) => {
+            let exit_code = send::run(*opts)?;
+            std::process::exit(exit_code);
+        }
+        Ok(Command::Transfer(opts)) => file_transfer::run(*opts),
+        Ok(Command::Push(opts)) => tunnel_files::run_push(*opts),
+        Ok(Command::Pull(opts)) => tunnel_files::run_pull(*opts),
+        Ok(Command::RemoteWizard { device, baud }) => remote_config::run(device, baud),
+        Ok(Command::Arm { ms, sign }) => interlock::run_arm(ms, sign),
+        Ok(Command::Disarm) => interlock::run_disarm(),
+        Ok(Command::Monitor { interval_ms }) => monitor::run(interval_ms),
+        Ok(Command::SupportBundle) => support_bundle::run(),
+        Ok(Command::TelemetryExport { since_ms, format }) => {
+            telemetry_export::run(since_ms, format)
+        }
+        Ok(Command::Completions { shell }) => completions::run(shell),
         Ok(Command::ShowVersion) => {
             println!("{}", env!("CARGO_PKG_VERSION"));
             Ok(())
@@ -32,11 +97,12 @@ fn try_main() -> Result<()> {
                     app.run()
                 }
                 RunMode::SerialShell => run_serial_shell(opts),
+                RunMode::AutobaudProbe => autobaud::run_probe(opts),
             }
         }
         Err(err) => {
             Command::print_help();
-            Err(err)
+            Err(err.exit_with(ExitCode::Usage))
         }
     }
 }
@@ -102,6 +102,10 @@ pub struct Capabilities {
     pub supports_tunnel: bool,
     pub supports_compression: bool,
     pub supports_heartbeat: bool,
+    pub supports_arq: bool,
+    pub supports_burst: bool,
+    pub supports_anomaly: bool,
+    pub supports_crypto: bool,
 }
 
 impl Capabilities {
@@ -110,6 +114,10 @@ impl Capabilities {
     pub const CMD_TUNNEL_V1: u32 = 0b0000_0010;
     pub const LCD_V2: u32 = 0b0000_0100;
     pub const HEARTBEAT_V1: u32 = 0b0000_1000;
+    pub const ARQ_V1: u32 = 0b0010_0000;
+    pub const BURST_V1: u32 = 0b0100_0000;
+    pub const ANOMALY_V1: u32 = 0b1000_0000;
+    pub const CRYPTO_V1: u32 = 0b1_0000_0000;
 
     pub fn bits(&self) -> u32 {
         let mut bits = Self::HANDSHAKE_V1;
@@ -122,6 +130,18 @@ impl Capabilities {
         if self.supports_heartbeat {
             bits |= Self::HEARTBEAT_V1;
         }
+        if self.supports_arq {
+            bits |= Self::ARQ_V1;
+        }
+        if self.supports_burst {
+            bits |= Self::BURST_V1;
+        }
+        if self.supports_anomaly {
+            bits |= Self::ANOMALY_V1;
+        }
+        if self.supports_crypto {
+            bits |= Self::CRYPTO_V1;
+        }
         bits
     }
 
@@ -130,12 +150,16 @@ impl Capabilities {
             supports_tunnel: bits & Self::CMD_TUNNEL_V1 != 0,
             supports_compression: bits & Self::COMPRESSION_V1 != 0,
             supports_heartbeat: bits & Self::HEARTBEAT_V1 != 0,
+            supports_arq: bits & Self::ARQ_V1 != 0,
+            supports_burst: bits & Self::BURST_V1 != 0,
+            supports_anomaly: bits & Self::ANOMALY_V1 != 0,
+            supports_crypto: bits & Self::CRYPTO_V1 != 0,
         }
     }
 }
 
-/// Control-plane frames exchanged during negotiation.
-#[derive(Serialize, Deserialize)]
+/// Control-plane frames exchanged during negotiation and link-rate changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ControlFrame {
     Hello {
@@ -143,16 +167,90 @@ pub enum ControlFrame {
         node_id: u32,
         caps: ControlCaps,
         pref: String,
+        /// Token the sender issued for this session; present only when it accepts `resume`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session: Option<String>,
+        /// Heartbeat interval the sender proposes; both sides adopt the smaller proposal.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        keepalive_ms: Option<u64>,
+        /// The sender's install UUID, so cloned installs can be told apart from ID clashes.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        install_id: Option<String>,
+        /// Random hex value mixed into the link keys; present only when a PSK is configured.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        link_nonce: Option<String>,
     },
     HelloAck {
         chosen_role: String,
         peer_caps: ControlCaps,
+        /// Proof that the sender holds the PSK, bound to both `link_nonce` values.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        link_proof: Option<String>,
+    },
+    /// Ask the peer to restore the session it issued `token` for instead of a full hello.
+    Resume {
+        token: String,
+    },
+    /// Reply to `resume`; when not accepted the sender falls back to `hello`.
+    ResumeAck {
+        accepted: bool,
     },
     LegacyFallback,
+    /// Ask the peer to move both ends to `baud` for a burst of traffic. `nonce` settles
+    /// crossing requests: the lower one yields.
+    Burst {
+        baud: u32,
+        nonce: u32,
+    },
+    /// Reply to `burst`; when accepted both sides switch right after this frame.
+    BurstAck {
+        baud: u32,
+        accepted: bool,
+    },
+    /// Sent at the burst rate by the initiator and echoed back to prove the faster link.
+    BurstProbe {
+        nonce: u32,
+    },
+    /// Both sides return to the base baud right after this frame.
+    BurstEnd,
+    /// Last frame before the sender closes the link on purpose, so the peer can tell a clean
+    /// exit from a dropped cable.
+    Goodbye {
+        reason: String,
+    },
+    /// Display defaults pushed by the server role; the receiver applies only the keys its
+    /// `[peer_defaults]` lists allow.
+    RenderDefaults(RenderDefaults),
+    /// A problem the sender noticed on its side, summarised over its watch window for the
+    /// receiver's LCD and log. Only sent to peers that advertise `ANOMALY_V1`.
+    AnomalySummary(AnomalySummary),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnomalySummary {
+    /// `reconnect_storm` or `temperature_spike`; receivers show unknown kinds as they are.
+    pub kind: String,
+    /// One LCD line, e.g. `7 reconnects in 5m`.
+    pub summary: String,
+    pub window_ms: u64,
+}
+
+/// Keys a `render_defaults` frame may set; absent keys leave the receiver's value alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scroll_speed_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blink_ms: Option<u64>,
+    /// Scheduler weight of local status pages, i.e. how much of the rotation they fill.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_weight: Option<u32>,
 }
 
 /// Serialized wrapper for capability bits.
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ControlCaps {
     pub bits: u32,
 }
@@ -167,6 +265,10 @@ mod tests {
             supports_tunnel: false,
             supports_compression: true,
             supports_heartbeat: false,
+            supports_arq: false,
+            supports_burst: false,
+            supports_anomaly: false,
+            supports_crypto: false,
         };
         let bits = caps.bits();
         assert!(bits & Capabilities::COMPRESSION_V1 != 0);
@@ -175,4 +277,40 @@ mod tests {
         assert!(!decoded.supports_tunnel);
         assert!(!decoded.supports_heartbeat);
     }
+
+    #[test]
+    fn goodbye_round_trips() {
+        let frame = ControlFrame::Goodbye {
+            reason: "shutdown".into(),
+        };
+        let line = serde_json::to_string(&frame).unwrap();
+        assert_eq!(line, r#"{"type":"goodbye","reason":"shutdown"}"#);
+        assert_eq!(serde_json::from_str::<ControlFrame>(&line).unwrap(), frame);
+    }
+
+    #[test]
+    fn render_defaults_skip_absent_keys() {
+        let frame = ControlFrame::RenderDefaults(RenderDefaults {
+            scroll_speed_ms: Some(400),
+            ..RenderDefaults::default()
+        });
+        let line = serde_json::to_string(&frame).unwrap();
+        assert_eq!(line, r#"{"type":"render_defaults","scroll_speed_ms":400}"#);
+        assert_eq!(serde_json::from_str::<ControlFrame>(&line).unwrap(), frame);
+    }
+
+    #[test]
+    fn anomaly_summary_round_trips() {
+        let frame = ControlFrame::AnomalySummary(AnomalySummary {
+            kind: "reconnect_storm".into(),
+            summary: "7 reconnects in 5m".into(),
+            window_ms: 300_000,
+        });
+        let line = serde_json::to_string(&frame).unwrap();
+        assert_eq!(
+            line,
+            r#"{"type":"anomaly_summary","kind":"reconnect_storm","summary":"7 reconnects in 5m","window_ms":300000}"#
+        );
+        assert_eq!(serde_json::from_str::<ControlFrame>(&line).unwrap(), frame);
+    }
 }
@@ -0,0 +1,63 @@
+//! Accessibility profile.
+//!
+//! With `[accessibility].enabled`, every incoming frame is constrained after it is parsed,
+//! whatever the sender asked for: rows scroll no faster than the profile's minimum, pages stay
+//! up at least its minimum timeout, nothing blinks or pulses the backlight, and bars use the
+//! whole-cell [`BarStyle::Block`] glyphs.
+
+use super::{BarStyle, RenderFrame};
+
+/// Floors the profile enforces on frame timings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessibilityProfile {
+    pub min_scroll_speed_ms: u64,
+    pub min_page_timeout_ms: u64,
+}
+
+impl AccessibilityProfile {
+    /// Rewrite `frame` to fit the profile. Frames already within it are left as they are.
+    pub fn apply(&self, frame: &mut RenderFrame) {
+        frame.scroll_speed_ms = frame.scroll_speed_ms.max(self.min_scroll_speed_ms);
+        frame.page_timeout_ms = frame.page_timeout_ms.max(self.min_page_timeout_ms);
+        frame.blink = false;
+        frame.backlight_pattern = None;
+        if frame.bar_percent.is_some() {
+            frame.bar_style = Some(BarStyle::Block);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::BacklightPattern;
+
+    #[test]
+    fn slows_frames_down_and_drops_blinking() {
+        let profile = AccessibilityProfile {
+            min_scroll_speed_ms: 600,
+            min_page_timeout_ms: 8_000,
+        };
+        let mut frame = RenderFrame::from_payload_json(
+            r#"{"schema_version":1,"line1":"CPU","line2":"","bar":40,"bar_style":"segmented",
+                "blink":true,"backlight_pattern":"breathe","scroll_speed_ms":150,
+                "page_timeout_ms":2000}"#,
+        )
+        .unwrap();
+        profile.apply(&mut frame);
+        assert_eq!((frame.scroll_speed_ms, frame.page_timeout_ms), (600, 8_000));
+        assert!(!frame.blink);
+        assert_eq!(frame.backlight_pattern, None::<BacklightPattern>);
+        assert_eq!(frame.bar_style, Some(BarStyle::Block));
+
+        // Slower settings than the floors survive, and frames without a bar keep no style.
+        let mut calm = RenderFrame::from_payload_json(
+            r#"{"schema_version":1,"line1":"up","line2":"","scroll_speed_ms":900,
+                "page_timeout_ms":20000}"#,
+        )
+        .unwrap();
+        profile.apply(&mut calm);
+        assert_eq!((calm.scroll_speed_ms, calm.page_timeout_ms), (900, 20_000));
+        assert_eq!(calm.bar_style, None);
+    }
+}
@@ -0,0 +1,162 @@
+//! Payload-defined CGRAM glyphs.
+//!
+//! A frame may ship its own 5x8 bitmaps in `custom_glyphs`: a map from a name to eight rows,
+//! top row first, each a 5-bit mask with bit 4 as the leftmost pixel. A name can then be listed
+//! in `icons` or placed inline in a line as `{glyph:name}`. Each placeholder is one cell: while
+//! parsing it becomes a private-use char ([`placeholder_char`]), so width checks and scrolling
+//! count it once, and the renderer swaps it for the CGRAM slot the icon bank loaded the bitmap
+//! into. Custom glyphs share the eight slots with bars, the heartbeat and icons; one that finds
+//! no slot is drawn as a blank.
+
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// Most glyphs one frame may define; the controller has no more CGRAM slots than this.
+pub const MAX_CUSTOM_GLYPHS: usize = 8;
+pub const MAX_GLYPH_NAME_LENGTH: usize = 16;
+const PLACEHOLDER_OPEN: &str = "{glyph:";
+/// Placeholders are carried as chars from the start of Supplementary Private Use Area-A.
+const PLACEHOLDER_BASE: u32 = 0xF0000;
+
+/// One named bitmap from a frame's `custom_glyphs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomGlyph {
+    pub name: String,
+    pub rows: [u8; 8],
+}
+
+/// Check and convert a payload's `custom_glyphs`, in name order.
+pub fn parse_custom_glyphs(raw: &BTreeMap<String, Vec<u8>>) -> Result<Vec<CustomGlyph>, String> {
+    if raw.len() > MAX_CUSTOM_GLYPHS {
+        return Err(format!("must be <= {MAX_CUSTOM_GLYPHS} glyphs"));
+    }
+    raw.iter()
+        .map(|(name, rows)| {
+            let valid_name = !name.is_empty()
+                && name.len() <= MAX_GLYPH_NAME_LENGTH
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_');
+            if !valid_name {
+                return Err(format!(
+                    "'{name}' must be 1-{MAX_GLYPH_NAME_LENGTH} chars of a-z, 0-9 or _"
+                ));
+            }
+            let rows: [u8; 8] = rows
+                .as_slice()
+                .try_into()
+                .map_err(|_| format!("'{name}' must have exactly 8 rows"))?;
+            if rows.iter().any(|row| *row > 0x1f) {
+                return Err(format!("'{name}' rows must be 5-bit values (0-31)"));
+            }
+            Ok(CustomGlyph {
+                name: name.clone(),
+                rows,
+            })
+        })
+        .collect()
+}
+
+/// Char standing in for `glyphs[idx]` in frame text.
+pub fn placeholder_char(idx: usize) -> char {
+    char::from_u32(PLACEHOLDER_BASE + idx as u32).unwrap_or('?')
+}
+
+/// Index of the glyph `ch` stands in for, if it is a placeholder.
+pub fn placeholder_index(ch: char) -> Option<usize> {
+    (ch as u32)
+        .checked_sub(PLACEHOLDER_BASE)
+        .map(|idx| idx as usize)
+        .filter(|idx| *idx < MAX_CUSTOM_GLYPHS)
+}
+
+/// `text` with each `{glyph:name}` naming one of `glyphs` replaced by its placeholder char.
+/// Unknown names are left as typed.
+pub fn expand_placeholders<'a>(text: &'a str, glyphs: &[CustomGlyph]) -> Cow<'a, str> {
+    if glyphs.is_empty() || !text.contains(PLACEHOLDER_OPEN) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(PLACEHOLDER_OPEN) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + PLACEHOLDER_OPEN.len()..];
+        let glyph = after.find('}').and_then(|end| {
+            glyphs
+                .iter()
+                .position(|glyph| glyph.name == after[..end])
+                .map(|idx| (idx, end))
+        });
+        match glyph {
+            Some((idx, end)) => {
+                out.push(placeholder_char(idx));
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(PLACEHOLDER_OPEN);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Inverse of [`expand_placeholders`], for turning a frame back into a payload.
+pub fn restore_placeholders(text: &str, glyphs: &[CustomGlyph]) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match placeholder_index(ch).and_then(|idx| glyphs.get(idx)) {
+            Some(glyph) => {
+                out.push_str(PLACEHOLDER_OPEN);
+                out.push_str(&glyph.name);
+                out.push('}');
+            }
+            None => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyphs(entries: &[(&str, Vec<u8>)]) -> Result<Vec<CustomGlyph>, String> {
+        parse_custom_glyphs(
+            &entries
+                .iter()
+                .map(|(name, rows)| (name.to_string(), rows.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn validates_names_and_rows() {
+        let ok = glyphs(&[("fan", vec![0, 0x0e, 0x15, 0x1f, 0x15, 0x0e, 0, 0])]).unwrap();
+        assert_eq!(ok[0].rows[3], 0x1f);
+        assert!(glyphs(&[("fan", vec![0; 7])]).is_err());
+        assert!(glyphs(&[("fan", vec![0x20; 8])]).is_err());
+        assert!(glyphs(&[("Fan!", vec![0; 8])]).is_err());
+        let too_many = (0..=MAX_CUSTOM_GLYPHS)
+            .map(|idx| (format!("g{idx}"), vec![0; 8]))
+            .collect();
+        assert!(parse_custom_glyphs(&too_many).is_err());
+    }
+
+    #[test]
+    fn placeholders_round_trip_as_one_char() {
+        let defined = glyphs(&[("fan", vec![0; 8]), ("plug", vec![0x1f; 8])]).unwrap();
+        let text = "{glyph:plug} 42W {glyph:fan}{glyph:nope}";
+        let expanded = expand_placeholders(text, &defined);
+        assert_eq!(
+            expanded.chars().count(),
+            "P 42W F{glyph:nope}".chars().count()
+        );
+        assert_eq!(expanded.chars().next().and_then(placeholder_index), Some(1));
+        assert_eq!(restore_placeholders(&expanded, &defined), text);
+        assert!(matches!(
+            expand_placeholders("plain", &defined),
+            Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,284 @@
+//! Small-frame fast path.
+//!
+//! Most traffic is a flat JSON object with two short lines. [`is_small_frame`] walks such a
+//! frame's top level once, without building anything: only known [`Payload`] keys, no `type`
+//! (compressed envelopes) or `pages`, a `schema_version`, and every `lineN` a string within
+//! [`MAX_LINE_LENGTH`] chars. A frame that passes is already in canonical form, so ingest can
+//! skip the normalization probes and the unknown-field strip and deserialize it once. Anything
+//! else, including every malformed frame, takes the full path, which reports the errors.
+//!
+//! The field spans land in a thread-local buffer that is cleared rather than freed, so after
+//! the first frame the scan allocates nothing.
+//!
+//! [`Payload`]: super::Payload
+
+use super::parser::{MAX_LINE_LENGTH, PAYLOAD_FIELDS};
+use std::{cell::RefCell, ops::Range};
+
+/// Frames longer than this go the full path; a two-line frame is well under it.
+const SMALL_FRAME_MAX_BYTES: usize = 512;
+
+/// One top-level member: where its key sits and, for strings, how many chars the value decodes
+/// to.
+struct Field {
+    key: Range<usize>,
+    value: Value,
+}
+
+enum Value {
+    Str {
+        chars: usize,
+    },
+    /// An unsigned integer literal that fits in `u8`.
+    SmallInt,
+    Other,
+}
+
+thread_local! {
+    static FIELDS: RefCell<Vec<Field>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Whether `raw` is a small single-page frame that can skip normalization.
+pub(crate) fn is_small_frame(raw: &str) -> bool {
+    let raw = raw.trim();
+    if raw.len() > SMALL_FRAME_MAX_BYTES || !raw.starts_with('{') {
+        return false;
+    }
+    FIELDS.with(|fields| {
+        let mut fields = fields.borrow_mut();
+        fields.clear();
+        scan_object(raw.as_bytes(), &mut fields) && accepts(raw, &fields)
+    })
+}
+
+fn accepts(raw: &str, fields: &[Field]) -> bool {
+    let mut seen_version = false;
+    let mut seen_lines = [false; 2];
+    for (idx, field) in fields.iter().enumerate() {
+        let key = &raw[field.key.clone()];
+        if key == "type" || !PAYLOAD_FIELDS.contains(&key) {
+            return false;
+        }
+        // serde rejects duplicates; leave that error to the full path.
+        if fields[..idx]
+            .iter()
+            .any(|other| &raw[other.key.clone()] == key)
+        {
+            return false;
+        }
+        match (key, &field.value) {
+            ("schema_version", Value::SmallInt) => seen_version = true,
+            ("schema_version", _) => return false,
+            ("line1" | "line2" | "line3" | "line4", Value::Str { chars }) => {
+                if *chars > MAX_LINE_LENGTH {
+                    return false;
+                }
+                match key {
+                    "line1" => seen_lines[0] = true,
+                    "line2" => seen_lines[1] = true,
+                    _ => {}
+                }
+            }
+            ("line1" | "line2" | "line3" | "line4", _) => return false,
+            _ => {}
+        }
+    }
+    seen_version && seen_lines == [true, true]
+}
+
+/// Record the members of the object that makes up all of `bytes`; `false` when it is not one
+/// well-formed object with plain ASCII keys.
+fn scan_object(bytes: &[u8], fields: &mut Vec<Field>) -> bool {
+    let mut idx = skip_ws(bytes, 1);
+    if bytes.get(idx) == Some(&b'}') {
+        return idx + 1 == bytes.len();
+    }
+    loop {
+        if bytes.get(idx) != Some(&b'"') {
+            return false;
+        }
+        let key_start = idx + 1;
+        let Some(key_end) = bytes[key_start..]
+            .iter()
+            .position(|&b| b == b'"')
+            .map(|len| key_start + len)
+        else {
+            return false;
+        };
+        if bytes[key_start..key_end]
+            .iter()
+            .any(|&b| b == b'\\' || !b.is_ascii())
+        {
+            return false;
+        }
+        idx = skip_ws(bytes, key_end + 1);
+        if bytes.get(idx) != Some(&b':') {
+            return false;
+        }
+        idx = skip_ws(bytes, idx + 1);
+        let Some((value, end)) = scan_value(bytes, idx) else {
+            return false;
+        };
+        fields.push(Field {
+            key: key_start..key_end,
+            value,
+        });
+        idx = skip_ws(bytes, end);
+        match bytes.get(idx) {
+            Some(b',') => idx = skip_ws(bytes, idx + 1),
+            Some(b'}') => return idx + 1 == bytes.len(),
+            _ => return false,
+        }
+    }
+}
+
+/// The value starting at `start` and the index just past it.
+fn scan_value(bytes: &[u8], start: usize) -> Option<(Value, usize)> {
+    match *bytes.get(start)? {
+        b'"' => {
+            let (chars, end) = scan_string(bytes, start)?;
+            Some((Value::Str { chars }, end))
+        }
+        b'{' | b'[' => Some((Value::Other, skip_nested(bytes, start)?)),
+        b'0'..=b'9' => {
+            let len = bytes[start..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+            let end = start + len;
+            if matches!(bytes.get(end), Some(b'.' | b'e' | b'E')) {
+                return Some((Value::Other, skip_scalar(bytes, end)));
+            }
+            let small = len <= 3
+                && (len == 1 || bytes[start] != b'0')
+                && std::str::from_utf8(&bytes[start..end])
+                    .ok()?
+                    .parse::<u8>()
+                    .is_ok();
+            Some((if small { Value::SmallInt } else { Value::Other }, end))
+        }
+        b'-' | b't' | b'f' | b'n' => Some((Value::Other, skip_scalar(bytes, start))),
+        _ => None,
+    }
+}
+
+/// Chars in the string starting at the quote at `start`, counted as they decode, and the
+/// index past its closing quote.
+fn scan_string(bytes: &[u8], start: usize) -> Option<(usize, usize)> {
+    let mut chars = 0;
+    let mut idx = start + 1;
+    loop {
+        match *bytes.get(idx)? {
+            b'"' => return Some((chars, idx + 1)),
+            b'\\' => {
+                if *bytes.get(idx + 1)? == b'u' {
+                    let hex = std::str::from_utf8(bytes.get(idx + 2..idx + 6)?).ok()?;
+                    let unit = u16::from_str_radix(hex, 16).ok()?;
+                    // The low half of a surrogate pair completes the char its high half
+                    // counted.
+                    if !(0xDC00..=0xDFFF).contains(&unit) {
+                        chars += 1;
+                    }
+                    idx += 6;
+                } else {
+                    chars += 1;
+                    idx += 2;
+                }
+            }
+            byte => {
+                // Count chars by their first byte; continuation bytes are 10xxxxxx.
+                if byte & 0xC0 != 0x80 {
+                    chars += 1;
+                }
+                idx += 1;
+            }
+        }
+    }
+}
+
+/// Index past the array or object starting at `start`.
+fn skip_nested(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut idx = start;
+    loop {
+        match *bytes.get(idx)? {
+            b'"' => {
+                idx = scan_string(bytes, idx)?.1;
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx + 1);
+                }
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+}
+
+/// Index past a number or literal; serde checks the spelling later.
+fn skip_scalar(bytes: &[u8], start: usize) -> usize {
+    start
+        + bytes[start..]
+            .iter()
+            .take_while(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace())
+            .count()
+}
+
+fn skip_ws(bytes: &[u8], mut idx: usize) -> usize {
+    while bytes.get(idx).is_some_and(u8::is_ascii_whitespace) {
+        idx += 1;
+    }
+    idx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_frames_take_the_fast_path() {
+        assert!(is_small_frame(
+            r#" {"schema_version":1,"line1":"CPU 42%","line2":"up 3d"} "#
+        ));
+        assert!(is_small_frame(
+            r#"{ "schema_version" : 1, "line1" : "café \"x\"", "line2" : "",
+                "icons" : ["wifi", "battery"], "bar" : 40, "backlight" : false }"#
+        ));
+        // Forty chars of a multi-byte glyph and a surrogate pair still fit.
+        let wide = format!(
+            r#"{{"schema_version":1,"line1":"{}","line2":"😀"}}"#,
+            "é".repeat(40)
+        );
+        assert!(is_small_frame(&wide));
+    }
+
+    #[test]
+    fn everything_else_takes_the_full_path() {
+        let cases = [
+            // Missing or unusable schema_version.
+            r#"{"line1":"a","line2":"b"}"#,
+            r#"{"schema_version":"1","line1":"a","line2":"b"}"#,
+            r#"{"schema_version":300,"line1":"a","line2":"b"}"#,
+            // Missing line2, a line that is too long or not a string.
+            r#"{"schema_version":1,"line1":"a"}"#,
+            r#"{"schema_version":1,"line1":"01234567890123456789012345678901234567890","line2":""}"#,
+            r#"{"schema_version":1,"line1":7,"line2":""}"#,
+            // Unknown, duplicate or envelope keys, and page sets.
+            r#"{"schema_version":1,"line1":"a","line2":"b","colour":"red"}"#,
+            r#"{"schema_version":1,"line1":"a","line1":"b","line2":""}"#,
+            r#"{"type":"compressed","schema_version":1,"codec":"lz4"}"#,
+            r#"{"schema_version":1,"pages":[{"line1":"a","line2":"b"}]}"#,
+            // Not JSON objects, or not well-formed ones.
+            "line1=a line2=b",
+            r#"{"schema_version":1,"line1":"a","line2":"b""#,
+            r#"{"schema_version":1,"line1":"a","line2":"b"} trailing"#,
+        ];
+        for case in cases {
+            assert!(!is_small_frame(case), "{case}");
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! Frame filters.
+//!
+//! `[filters].rules` lets the operator silence a noisy producer from this end of the link. Each
+//! rule is one string:
+//!
+//! - `drop:line1~REGEX` drops frames whose first line matches `REGEX` (`line2`-`line4` work
+//!   the same way, `any` tries every line);
+//! - `mute:remote` drops every queued frame, whether it came over serial, the control socket or
+//!   the spool dir;
+//! - `mute:polling` keeps local polling snapshots off the screen.
+//!
+//! Any rule may end in `@HH:MM-HH:MM` to apply only in that local-time window; a window whose
+//! end is before its start runs past midnight. Frames are filtered after they are parsed and
+//! deduplicated, so a replay or a repeat of the frame on screen is not counted as a hit. A row
+//! update is checked against the page it would leave on screen, and dropped if that page would
+//! be.
+
+use std::str::FromStr;
+
+use regex::Regex;
+
+use super::RenderFrame;
+use crate::{config::parse_time_of_day, Error, Result};
+
+/// Where a muted frame would have come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameSource {
+    Remote,
+    Polling,
+}
+
+impl FrameSource {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "remote" => Some(Self::Remote),
+            "polling" => Some(Self::Polling),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FilterAction {
+    /// Drop frames where line `line` (0-based; `None` for any) matches `pattern`.
+    Drop {
+        line: Option<usize>,
+        pattern: Regex,
+    },
+    Mute(FrameSource),
+}
+
+/// Start and end of a rule's active time, in minutes since local midnight.
+type Window = (u16, u16);
+
+/// One `[filters].rules` entry, e.g. `drop:line1~^DEBUG` or `mute:polling@09:00-17:00`.
+#[derive(Debug, Clone)]
+pub struct FilterRule {
+    pub action: FilterAction,
+    /// `None` applies the rule all day.
+    pub window: Option<Window>,
+}
+
+impl FromStr for FilterRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (body, window) = split_window(s)?;
+        let action = if let Some(rest) = body.strip_prefix("drop:") {
+            let (target, pattern) = rest
+                .split_once('~')
+                .ok_or_else(|| format!("filter rule '{s}' must look like drop:LINE~REGEX"))?;
+            let line = match target {
+                "any" => None,
+                "line1" => Some(0),
+                "line2" => Some(1),
+                "line3" => Some(2),
+                "line4" => Some(3),
+                _ => {
+                    return Err(format!(
+                        "unknown line '{target}' in filter rule '{s}', expected line1-line4 or any"
+                    ))
+                }
+            };
+            if pattern.is_empty() {
+                return Err(format!("filter rule '{s}' has an empty pattern"));
+            }
+            let pattern = Regex::new(pattern)
+                .map_err(|e| format!("invalid pattern in filter rule '{s}': {e}"))?;
+            FilterAction::Drop { line, pattern }
+        } else if let Some(name) = body.strip_prefix("mute:") {
+            FilterAction::Mute(FrameSource::from_name(name).ok_or_else(|| {
+                format!("unknown source '{name}' in filter rule '{s}', expected remote or polling")
+            })?)
+        } else {
+            return Err(format!("filter rule '{s}' must start with drop: or mute:"));
+        };
+        Ok(Self { action, window })
+    }
+}
+
+/// Split a trailing `@HH:MM-HH:MM` off `s`. An `@` followed by anything else is left alone so
+/// patterns can contain one.
+fn split_window(s: &str) -> std::result::Result<(&str, Option<Window>), String> {
+    let Some((body, window)) = s.rsplit_once('@') else {
+        return Ok((s, None));
+    };
+    let Some((start, end)) = window.split_once('-') else {
+        return Ok((s, None));
+    };
+    if !start.contains(':') || !end.contains(':') {
+        return Ok((s, None));
+    }
+    let parse = |raw: &str| {
+        parse_time_of_day(raw)
+            .ok_or_else(|| format!("invalid time '{raw}' in filter rule '{s}', expected HH:MM"))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start == end {
+        return Err(format!("filter rule '{s}' has an empty time window"));
+    }
+    Ok((body, Some((start, end))))
+}
+
+impl FilterRule {
+    fn active(&self, minute: u16) -> bool {
+        match self.window {
+            Some((start, end)) if start < end => (start..end).contains(&minute),
+            Some((start, end)) => minute >= start || minute < end,
+            None => true,
+        }
+    }
+
+    fn drops(&self, frame: &RenderFrame) -> bool {
+        match &self.action {
+            FilterAction::Drop { line, pattern } => {
+                let lines = [&frame.line1, &frame.line2, &frame.line3, &frame.line4];
+                match line {
+                    Some(idx) => pattern.is_match(lines[*idx]),
+                    None => lines.iter().any(|line| pattern.is_match(line)),
+                }
+            }
+            FilterAction::Mute(source) => *source == FrameSource::Remote,
+        }
+    }
+}
+
+/// The configured rules, with a hit count for each.
+#[derive(Debug, Clone, Default)]
+pub struct FrameFilter {
+    rules: Vec<(String, FilterRule, u64)>,
+    minute: u16,
+    dropped: u64,
+    muted_polls: u64,
+}
+
+impl FrameFilter {
+    pub fn new(rules: &[String]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|raw| {
+                raw.parse::<FilterRule>()
+                    .map(|rule| (raw.clone(), rule, 0))
+                    .map_err(Error::InvalidArgs)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            rules,
+            ..Self::default()
+        })
+    }
+
+    /// Local time for windowed rules, as minutes since midnight.
+    pub fn set_minute_of_day(&mut self, minute: u16) {
+        self.minute = minute;
+    }
+
+    pub fn minute_of_day(&self) -> u16 {
+        self.minute
+    }
+
+    /// Whether `frame` may be queued; a drop is counted against the first rule that caught it.
+    pub fn admits(&mut self, frame: &RenderFrame) -> bool {
+        let minute = self.minute;
+        let Some((_, _, hits)) = self
+            .rules
+            .iter_mut()
+            .find(|(_, rule, _)| rule.active(minute) && rule.drops(frame))
+        else {
+            return true;
+        };
+        *hits += 1;
+        self.dropped += 1;
+        false
+    }
+
+    /// Whether a polling snapshot should be kept off the screen right now; counted if so.
+    pub fn mutes_polling(&mut self) -> bool {
+        let minute = self.minute;
+        let Some((_, _, hits)) = self.rules.iter_mut().find(|(_, rule, _)| {
+            rule.active(minute) && matches!(rule.action, FilterAction::Mute(FrameSource::Polling))
+        }) else {
+            return false;
+        };
+        *hits += 1;
+        self.muted_polls += 1;
+        true
+    }
+
+    /// Frames dropped since the filter was built.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Polling snapshots muted since the filter was built.
+    pub fn muted_polls(&self) -> u64 {
+        self.muted_polls
+    }
+
+    /// Each rule as configured, with how often it fired.
+    pub fn hits(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.rules
+            .iter()
+            .map(|(raw, _, hits)| (raw.as_str(), *hits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(line1: &str, line2: &str) -> RenderFrame {
+        RenderFrame::from_payload_json(&format!(
+            r#"{{"schema_version":1,"line1":"{line1}","line2":"{line2}"}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_rules_and_windows() {
+        let rule: FilterRule = "mute:polling@22:00-06:30".parse().unwrap();
+        assert!(matches!(
+            rule.action,
+            FilterAction::Mute(FrameSource::Polling)
+        ));
+        assert_eq!(rule.window, Some((22 * 60, 6 * 60 + 30)));
+        assert!(rule.active(23 * 60) && rule.active(60) && !rule.active(12 * 60));
+        // An `@` that does not start a window belongs to the pattern.
+        let rule: FilterRule = "drop:any~user@host".parse().unwrap();
+        assert!(rule.window.is_none());
+        assert!(rule.drops(&frame("", "user@host")));
+        for bad in [
+            "drop:line5~x",
+            "drop:line1",
+            "drop:line1~",
+            "drop:line1~(",
+            "mute:serial",
+            "mute:polling@09:00-09:00",
+            "mute:polling@9:00-17:00",
+            "keep:line1~x",
+        ] {
+            assert!(bad.parse::<FilterRule>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn drops_matching_frames_and_counts_per_rule() {
+        let mut filter = FrameFilter::new(&[
+            "drop:line1~^DEBUG".into(),
+            "mute:remote@09:00-17:00".into(),
+            "mute:polling@09:00-17:00".into(),
+        ])
+        .unwrap();
+        filter.set_minute_of_day(8 * 60);
+        assert!(!filter.admits(&frame("DEBUG tick", "")));
+        assert!(filter.admits(&frame("CPU 12%", "DEBUG")));
+        assert!(!filter.mutes_polling());
+
+        filter.set_minute_of_day(10 * 60);
+        assert!(!filter.admits(&frame("CPU 12%", "")));
+        assert!(filter.mutes_polling());
+        assert_eq!((filter.dropped(), filter.muted_polls()), (2, 1));
+        let hits: Vec<_> = filter.hits().map(|(_, hits)| hits).collect();
+        assert_eq!(hits, [1, 1, 1]);
+        assert!(FrameFilter::new(&["drop:nope~x".into()]).is_err());
+    }
+}
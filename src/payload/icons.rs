@@ -4,6 +4,175 @@ pub enum DisplayMode {
     Normal,
     Dashboard,
     Banner,
+    /// `line1` drawn two rows tall in the CGRAM big-digit font, for clocks and readings.
+    BigDigits,
+}
+
+/// Frame severity, ordered so rules can match "at least warn".
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warn,
+    Crit,
+}
+
+impl Severity {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "info" => Some(Severity::Info),
+            "warn" | "warning" => Some(Severity::Warn),
+            "crit" | "critical" => Some(Severity::Crit),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Crit => "crit",
+        }
+    }
+}
+
+/// CGRAM glyph set used to draw a bar.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum BarStyle {
+    /// Fills the row column by column, five steps per cell.
+    #[default]
+    Solid,
+    /// Four steps per cell with a blank column between cells.
+    Segmented,
+    /// One cell that fills from the bottom in eight steps, beside the row's text.
+    Vertical,
+    /// Whole cells only, each fully lit or dark; the highest-contrast style.
+    Block,
+}
+
+impl BarStyle {
+    pub const ALL: [BarStyle; 4] = [
+        BarStyle::Solid,
+        BarStyle::Segmented,
+        BarStyle::Vertical,
+        BarStyle::Block,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BarStyle::Solid => "solid",
+            BarStyle::Segmented => "segmented",
+            BarStyle::Vertical => "vertical",
+            BarStyle::Block => "block",
+        }
+    }
+
+    /// Fill steps per cell; level 0 is an empty cell.
+    pub fn levels(&self) -> u8 {
+        match self {
+            BarStyle::Solid => 5,
+            BarStyle::Segmented => 4,
+            BarStyle::Vertical => 8,
+            BarStyle::Block => 1,
+        }
+    }
+}
+
+impl std::str::FromStr for BarStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        BarStyle::ALL
+            .into_iter()
+            .find(|style| style.as_str() == name)
+            .ok_or_else(|| {
+                format!("expected 'solid', 'segmented', 'vertical' or 'block', got '{name}'")
+            })
+    }
+}
+
+impl std::fmt::Display for BarStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How urgently a frame wants the display. Anything above `Normal` interrupts the page
+/// rotation as soon as it arrives; more urgent frames go first.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Normal, Priority::High, Priority::Urgent];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+
+    /// Whether a frame at this priority cuts into the rotation instead of queueing.
+    pub fn interrupts(&self) -> bool {
+        *self > Priority::Normal
+    }
+}
+
+impl std::str::FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        Priority::ALL
+            .into_iter()
+            .find(|priority| priority.as_str() == name)
+            .ok_or_else(|| format!("expected 'normal', 'high' or 'urgent', got '{name}'"))
+    }
+}
+
+/// Frame-selected backlight animation that replaces plain on/off and blink.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BacklightPattern {
+    /// Slow fade down and back up.
+    Breathe,
+    /// Two short flashes, then a pause.
+    DoublePulse,
+    /// `... --- ...` in Morse.
+    Sos,
+}
+
+impl BacklightPattern {
+    pub const ALL: [BacklightPattern; 3] = [
+        BacklightPattern::Breathe,
+        BacklightPattern::DoublePulse,
+        BacklightPattern::Sos,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BacklightPattern::Breathe => "breathe",
+            BacklightPattern::DoublePulse => "double_pulse",
+            BacklightPattern::Sos => "sos",
+        }
+    }
+}
+
+impl std::str::FromStr for BacklightPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase().replace('-', "_");
+        BacklightPattern::ALL
+            .into_iter()
+            .find(|pattern| pattern.as_str() == name)
+            .ok_or_else(|| format!("expected 'breathe', 'double_pulse' or 'sos', got '{name}'"))
+    }
 }
 
 /// The curated set of semantic icons that LifelineTTY understands.
@@ -68,6 +237,35 @@ impl Icon {
         }
     }
 
+    /// Canonical payload name; [`Icon::from_name`] maps it back.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Icon::Battery => "battery",
+            Icon::Heart => "heart",
+            Icon::Wifi => "wifi",
+            Icon::Arrow => "arrow",
+            Icon::Bell => "bell",
+            Icon::Note => "note",
+            Icon::Clockface => "clockface",
+            Icon::Duck => "duck",
+            Icon::Check => "check",
+            Icon::Cross => "cross",
+            Icon::Smile => "smile",
+            Icon::OpenHeart => "open_heart",
+            Icon::UpArrow => "up_arrow",
+            Icon::UpArrowRight => "up_arrow_right",
+            Icon::UpArrowLeft => "up_arrow_left",
+            Icon::DownArrow => "down_arrow",
+            Icon::DownArrowRight => "down_arrow_right",
+            Icon::DownArrowLeft => "down_arrow_left",
+            Icon::ReturnArrow => "return_arrow",
+            Icon::Hourglass => "hourglass",
+            Icon::DegreeSymbol => "degree_symbol",
+            Icon::DegreeC => "degree_c",
+            Icon::DegreeF => "degree_f",
+        }
+    }
+
     pub fn bitmap(&self) -> Option<[u8; 8]> {
         match self {
             Icon::Battery => Some([0x1f, 0x1f, 0x11, 0x11, 0x11, 0x11, 0x1f, 0x1f]),
@@ -104,9 +302,19 @@ impl DisplayMode {
         match raw.as_deref() {
             Some("dashboard") => DisplayMode::Dashboard,
             Some("banner") => DisplayMode::Banner,
+            Some("bigdigits") => DisplayMode::BigDigits,
             _ => DisplayMode::Normal,
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisplayMode::Normal => "normal",
+            DisplayMode::Dashboard => "dashboard",
+            DisplayMode::Banner => "banner",
+            DisplayMode::BigDigits => "bigdigits",
+        }
+    }
 }
 
 pub(crate) fn parse_icons(raw: Option<Vec<String>>) -> Vec<Icon> {
@@ -118,7 +326,7 @@ pub(crate) fn parse_icons(raw: Option<Vec<String>>) -> Vec<Icon> {
 
 #[cfg(test)]
 mod tests {
-    use super::{DisplayMode, Icon};
+    use super::{DisplayMode, Icon, Severity};
 
     #[test]
     fn parses_display_mode_variants() {
@@ -130,6 +338,10 @@ mod tests {
             DisplayMode::parse(Some("banner".into())),
             DisplayMode::Banner
         );
+        assert_eq!(
+            DisplayMode::parse(Some("bigdigits".into())),
+            DisplayMode::BigDigits
+        );
         assert_eq!(
             DisplayMode::parse(Some("unknown".into())),
             DisplayMode::Normal
@@ -144,6 +356,13 @@ mod tests {
         assert_eq!(Icon::from_name("degree_f"), Some(Icon::DegreeF));
     }
 
+    #[test]
+    fn severity_names_are_ordered() {
+        assert_eq!(Severity::from_name("Warning"), Some(Severity::Warn));
+        assert_eq!(Severity::from_name("nope"), None);
+        assert!(Severity::Crit > Severity::Warn && Severity::Warn > Severity::Info);
+    }
+
     #[test]
     fn icon_bitmap_matches_reference() {
         assert_eq!(
@@ -1,16 +1,28 @@
+mod accessibility;
+mod custom_glyphs;
+mod fast_path;
+mod filter;
 mod icons;
 mod parser;
 mod schema;
 
-pub use icons::{DisplayMode, Icon};
+pub use accessibility::AccessibilityProfile;
+pub use custom_glyphs::{expand_placeholders, placeholder_index, CustomGlyph, MAX_CUSTOM_GLYPHS};
+pub(crate) use fast_path::is_small_frame;
+pub use filter::{FilterAction, FilterRule, FrameFilter, FrameSource};
+pub use icons::{BacklightPattern, BarStyle, DisplayMode, Icon, Priority, Severity};
 pub use parser::{
-    decode_command_frame, encode_command_frame, encode_compressed_payload, normalize_payload_json,
-    normalize_payload_json_with_policy, CommandMessage, CommandStream, CompressionPolicy, Defaults,
-    Payload, RenderFrame, COMMAND_MAX_CHUNK_BYTES, COMMAND_MAX_COMMAND_CHARS,
-    COMMAND_MAX_FRAME_BYTES, COMMAND_MAX_SCRATCH_PATH_BYTES, COMMAND_SCHEMA_VERSION,
+    decode_command_frame, decode_command_frame_with_seq, encode_command_frame,
+    encode_command_frame_with_seq, encode_compressed_payload, is_page_set, is_zone_name,
+    normalize_payload_json, normalize_payload_json_permissive, normalize_payload_json_with_policy,
+    BarAmount, CommandMessage, CommandStream, CompressionPolicy, Defaults, Payload, RenderFrame,
+    RowUpdate, COMMAND_MAX_CHUNK_BYTES, COMMAND_MAX_COMMAND_CHARS, COMMAND_MAX_FRAME_BYTES,
+    COMMAND_MAX_SCRATCH_PATH_BYTES, COMMAND_SCHEMA_VERSION, MAX_TARGET_LENGTH,
 };
 pub use schema::{
-    decode_tunnel_frame, encode_tunnel_msg, TunnelMsg, TunnelMsgOwned, TUNNEL_MAX_FRAME_BYTES,
+    decode_frame_ack, decode_frame_nack, decode_tunnel_frame, decode_tunnel_frame_with_seq,
+    encode_frame_ack, encode_frame_nack, encode_tunnel_msg, encode_tunnel_msg_with_seq,
+    frame_crc32, FrameAck, FrameNack, TunnelMsg, TunnelMsgOwned, TUNNEL_MAX_FRAME_BYTES,
 };
 
 pub const DEFAULT_SCROLL_MS: u64 = 250;
@@ -1,15 +1,22 @@
 use crate::{
     compression::{compress, decompress, CompressionCodec},
     config::DEFAULT_PROTOCOL_SCHEMA_VERSION,
-    Error, Result, CACHE_DIR,
+    Error, FrameErrorCode, Result, CACHE_DIR,
 };
 use crc32fast::Hasher;
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
-use std::{borrow::Cow, path::Path};
+use std::{borrow::Cow, collections::BTreeMap, path::Path};
 
+use super::custom_glyphs::{
+    expand_placeholders, parse_custom_glyphs, restore_placeholders, CustomGlyph,
+};
+use super::fast_path::is_small_frame;
 use super::icons::parse_icons;
-use super::{DisplayMode, Icon, DEFAULT_PAGE_TIMEOUT_MS, DEFAULT_SCROLL_MS};
+use super::{
+    BacklightPattern, BarStyle, DisplayMode, Icon, Priority, Severity, DEFAULT_PAGE_TIMEOUT_MS,
+    DEFAULT_SCROLL_MS,
+};
 
 pub const COMMAND_SCHEMA_VERSION: u8 = 1;
 pub const COMMAND_MAX_FRAME_BYTES: usize = 4 * 1024;
@@ -31,6 +38,12 @@ pub enum CommandMessage {
         request_id: u32,
         cmd: String,
         scratch_path: Option<String>,
+        /// Code for the `totp` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<String>,
+        /// `<unix ms>.<hex HMAC-SHA256>` for the `hmac` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<String>,
     },
     Chunk {
         request_id: u32,
@@ -72,6 +85,8 @@ struct CommandFrame {
     channel: String,
     schema_version: u8,
     message: CommandMessage,
+    #[serde(default)]
+    seq: Option<u64>,
     crc32: u32,
 }
 
@@ -80,16 +95,24 @@ struct CommandFrameWriter<'a> {
     channel: &'a str,
     schema_version: u8,
     message: &'a CommandMessage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seq: Option<u64>,
     crc32: u32,
 }
 
 pub fn encode_command_frame(msg: &CommandMessage) -> Result<String> {
+    encode_command_frame_with_seq(msg, None)
+}
+
+/// Encode a command frame stamped with the sender's sequence number for replay checks.
+pub fn encode_command_frame_with_seq(msg: &CommandMessage, seq: Option<u64>) -> Result<String> {
     validate_command_message(msg)?;
     let crc32 = msg.crc32()?;
     let frame = CommandFrameWriter {
         channel: "command",
         schema_version: COMMAND_SCHEMA_VERSION,
         message: msg,
+        seq,
         crc32,
     };
     let json = serde_json::to_string(&frame).map_err(|e| Error::Parse(format!("json: {e}")))?;
@@ -102,6 +125,11 @@ pub fn encode_command_frame(msg: &CommandMessage) -> Result<String> {
 }
 
 pub fn decode_command_frame(raw: &str) -> Result<CommandMessage> {
+    decode_command_frame_with_seq(raw).map(|(msg, _)| msg)
+}
+
+/// Decode a command frame along with its sequence number, if the sender stamped one.
+pub fn decode_command_frame_with_seq(raw: &str) -> Result<(CommandMessage, Option<u64>)> {
     if raw.len() > COMMAND_MAX_FRAME_BYTES {
         return Err(Error::Parse(format!(
             "command frame exceeds {COMMAND_MAX_FRAME_BYTES} bytes"
@@ -123,7 +151,7 @@ pub fn decode_command_frame(raw: &str) -> Result<CommandMessage> {
         return Err(Error::ChecksumMismatch);
     }
     validate_command_message(&frame.message)?;
-    Ok(frame.message)
+    Ok((frame.message, frame.seq))
 }
 
 fn validate_command_message(msg: &CommandMessage) -> Result<()> {
@@ -265,13 +293,13 @@ fn parse_kv_pairs(raw: &str) -> Result<Vec<(String, String)>> {
             idx += 1;
         }
         if idx >= bytes.len() || bytes[idx] != b'=' {
-            return Err(Error::Parse("invalid key=value payload".into()));
+            return Err(FrameErrorCode::Json.error("invalid key=value payload"));
         }
         let key = raw[key_start..idx].trim();
         idx += 1; // '='
 
         if key.is_empty() {
-            return Err(Error::Parse("invalid key=value payload".into()));
+            return Err(FrameErrorCode::Json.error("invalid key=value payload"));
         }
 
         let value = if idx < bytes.len() && bytes[idx] == b'"' {
@@ -308,7 +336,7 @@ fn parse_kv_pairs(raw: &str) -> Result<Vec<(String, String)>> {
     Ok(pairs)
 }
 
-fn normalize_kv_payload_to_json(raw: &str) -> Result<Option<String>> {
+fn normalize_kv_payload_to_json(raw: &str, strict: bool) -> Result<Option<String>> {
     let trimmed = raw.trim();
     if trimmed.is_empty() || trimmed.starts_with('{') {
         return Ok(None);
@@ -331,9 +359,9 @@ fn normalize_kv_payload_to_json(raw: &str) -> Result<Option<String>> {
         let key = key.to_ascii_lowercase();
         match key.as_str() {
             "schema_version" => {
-                let v: u8 = value
-                    .parse()
-                    .map_err(|_| Error::Parse("schema_version must be an integer".into()))?;
+                let v: u8 = value.parse().map_err(|_| {
+                    FrameErrorCode::SchemaVersion.error("schema_version must be an integer")
+                })?;
                 schema_version = Some(v);
             }
             "line1" => {
@@ -342,71 +370,77 @@ fn normalize_kv_payload_to_json(raw: &str) -> Result<Option<String>> {
             "line2" => {
                 line2 = Some(value);
             }
+            "line3" | "line4" => {
+                obj.insert(key, serde_json::Value::String(value));
+            }
             "bar" => {
                 let v: u8 = value
                     .parse()
-                    .map_err(|_| Error::Parse("bar must be an integer".into()))?;
+                    .map_err(|_| FrameErrorCode::BadField.error("bar must be an integer"))?;
                 obj.insert("bar".into(), serde_json::Value::Number(v.into()));
             }
-            "bar_value" => {
-                let v: u32 = value
-                    .parse()
-                    .map_err(|_| Error::Parse("bar_value must be an integer".into()))?;
-                obj.insert("bar_value".into(), serde_json::Value::Number(v.into()));
+            "bar_value" | "bar_max" => {
+                // Unit-suffixed amounts ("1.5G") stay strings and are checked with the payload.
+                let v = match value.parse::<u32>() {
+                    Ok(v) => serde_json::Value::Number(v.into()),
+                    Err(_) => serde_json::Value::String(value),
+                };
+                obj.insert(key, v);
             }
-            "bar_max" => {
-                let v: u32 = value
-                    .parse()
-                    .map_err(|_| Error::Parse("bar_max must be an integer".into()))?;
-                obj.insert("bar_max".into(), serde_json::Value::Number(v.into()));
+            "bar_unit" => {
+                obj.insert("bar_unit".into(), serde_json::Value::String(value));
             }
             "bar_label" => {
                 obj.insert("bar_label".into(), serde_json::Value::String(value));
             }
+            "bar_style" => {
+                obj.insert("bar_style".into(), serde_json::Value::String(value));
+            }
             "bar_line1" => {
                 let v = parse_bool_kv(&value)
-                    .ok_or_else(|| Error::Parse("bar_line1 must be a boolean".into()))?;
+                    .ok_or_else(|| FrameErrorCode::BadField.error("bar_line1 must be a boolean"))?;
                 obj.insert("bar_line1".into(), serde_json::Value::Bool(v));
             }
-            "bar_line2" => {
-                let v = parse_bool_kv(&value)
-                    .ok_or_else(|| Error::Parse("bar_line2 must be a boolean".into()))?;
-                obj.insert("bar_line2".into(), serde_json::Value::Bool(v));
+            "bar_line2" | "bar_line3" | "bar_line4" => {
+                let v = parse_bool_kv(&value).ok_or_else(|| {
+                    FrameErrorCode::BadField.error(format_args!("{key} must be a boolean"))
+                })?;
+                obj.insert(key, serde_json::Value::Bool(v));
             }
             "backlight" => {
                 let v = parse_bool_kv(&value)
-                    .ok_or_else(|| Error::Parse("backlight must be a boolean".into()))?;
+                    .ok_or_else(|| FrameErrorCode::BadField.error("backlight must be a boolean"))?;
                 obj.insert("backlight".into(), serde_json::Value::Bool(v));
             }
             "blink" => {
                 let v = parse_bool_kv(&value)
-                    .ok_or_else(|| Error::Parse("blink must be a boolean".into()))?;
+                    .ok_or_else(|| FrameErrorCode::BadField.error("blink must be a boolean"))?;
                 obj.insert("blink".into(), serde_json::Value::Bool(v));
             }
             "scroll" => {
                 let v = parse_bool_kv(&value)
-                    .ok_or_else(|| Error::Parse("scroll must be a boolean".into()))?;
+                    .ok_or_else(|| FrameErrorCode::BadField.error("scroll must be a boolean"))?;
                 obj.insert("scroll".into(), serde_json::Value::Bool(v));
             }
             "scroll_speed_ms" => {
-                let v: u64 = value
-                    .parse()
-                    .map_err(|_| Error::Parse("scroll_speed_ms must be an integer".into()))?;
+                let v: u64 = value.parse().map_err(|_| {
+                    FrameErrorCode::BadField.error("scroll_speed_ms must be an integer")
+                })?;
                 obj.insert(
                     "scroll_speed_ms".into(),
                     serde_json::Value::Number(v.into()),
                 );
             }
             "duration_ms" => {
-                let v: u64 = value
-                    .parse()
-                    .map_err(|_| Error::Parse("duration_ms must be an integer".into()))?;
+                let v: u64 = value.parse().map_err(|_| {
+                    FrameErrorCode::BadField.error("duration_ms must be an integer")
+                })?;
                 obj.insert("duration_ms".into(), serde_json::Value::Number(v.into()));
             }
             "page_timeout_ms" => {
-                let v: u64 = value
-                    .parse()
-                    .map_err(|_| Error::Parse("page_timeout_ms must be an integer".into()))?;
+                let v: u64 = value.parse().map_err(|_| {
+                    FrameErrorCode::BadField.error("page_timeout_ms must be an integer")
+                })?;
                 obj.insert(
                     "page_timeout_ms".into(),
                     serde_json::Value::Number(v.into()),
@@ -414,17 +448,29 @@ fn normalize_kv_payload_to_json(raw: &str) -> Result<Option<String>> {
             }
             "clear" => {
                 let v = parse_bool_kv(&value)
-                    .ok_or_else(|| Error::Parse("clear must be a boolean".into()))?;
+                    .ok_or_else(|| FrameErrorCode::BadField.error("clear must be a boolean"))?;
                 obj.insert("clear".into(), serde_json::Value::Bool(v));
             }
             "test" => {
                 let v = parse_bool_kv(&value)
-                    .ok_or_else(|| Error::Parse("test must be a boolean".into()))?;
+                    .ok_or_else(|| FrameErrorCode::BadField.error("test must be a boolean"))?;
                 obj.insert("test".into(), serde_json::Value::Bool(v));
             }
             "mode" => {
                 obj.insert("mode".into(), serde_json::Value::String(value));
             }
+            "severity" => {
+                obj.insert("severity".into(), serde_json::Value::String(value));
+            }
+            "backlight_pattern" => {
+                obj.insert("backlight_pattern".into(), serde_json::Value::String(value));
+            }
+            "priority" => {
+                obj.insert("priority".into(), serde_json::Value::String(value));
+            }
+            "target" => {
+                obj.insert("target".into(), serde_json::Value::String(value));
+            }
             "icons" => {
                 let icons = value
                     .split(',')
@@ -437,12 +483,18 @@ fn normalize_kv_payload_to_json(raw: &str) -> Result<Option<String>> {
                 obj.insert("checksum".into(), serde_json::Value::String(value));
             }
             "config_reload" => {
-                let v = parse_bool_kv(&value)
-                    .ok_or_else(|| Error::Parse("config_reload must be a boolean".into()))?;
+                let v = parse_bool_kv(&value).ok_or_else(|| {
+                    FrameErrorCode::BadField.error("config_reload must be a boolean")
+                })?;
                 obj.insert("config_reload".into(), serde_json::Value::Bool(v));
             }
+            _ if strict => {
+                return Err(FrameErrorCode::UnknownField
+                    .error(format_args!("unknown key=value field '{key}'")));
+            }
+            // Kept so `strip_unknown_fields` reports it like any other unknown field.
             _ => {
-                return Err(Error::Parse(format!("unknown key=value field '{key}'")));
+                obj.insert(key, serde_json::Value::String(value));
             }
         }
     }
@@ -469,64 +521,119 @@ pub fn normalize_payload_json_with_policy<'a>(
     raw: &'a str,
     policy: CompressionPolicy,
 ) -> Result<Cow<'a, str>> {
+    normalize_payload(raw, policy, true)
+}
+
+/// `[protocol].strict = false`: normalize like [`normalize_payload_json_with_policy`], then drop
+/// top-level fields [`Payload`] does not know. Returns the cleaned frame and the dropped names.
+pub fn normalize_payload_json_permissive<'a>(
+    raw: &'a str,
+    policy: CompressionPolicy,
+) -> Result<(Cow<'a, str>, Vec<String>)> {
+    let normalized = normalize_payload(raw, policy, false)?;
+    strip_unknown_fields(normalized)
+}
+
+fn strip_unknown_fields(normalized: Cow<'_, str>) -> Result<(Cow<'_, str>, Vec<String>)> {
+    let mut value: serde_json::Value = serde_json::from_str(normalized.as_ref())
+        .map_err(|e| Error::Parse(format!("json: {e}")))?;
+    let Some(obj) = value.as_object_mut() else {
+        return Ok((normalized, Vec::new()));
+    };
+    let mut unknown = Vec::new();
+    if obj.contains_key("pages") {
+        remove_unknown(obj, PAGE_SET_FIELDS, &mut unknown);
+        if let Some(pages) = obj.get_mut("pages").and_then(|pages| pages.as_array_mut()) {
+            for page in pages.iter_mut().filter_map(|page| page.as_object_mut()) {
+                remove_unknown(page, PAYLOAD_FIELDS, &mut unknown);
+            }
+        }
+    } else {
+        remove_unknown(obj, PAYLOAD_FIELDS, &mut unknown);
+    }
+    if unknown.is_empty() {
+        return Ok((normalized, unknown));
+    }
+    let json = serde_json::to_string(&value).map_err(|e| Error::Parse(format!("json: {e}")))?;
+    Ok((Cow::Owned(json), unknown))
+}
+
+fn remove_unknown(
+    obj: &mut serde_json::Map<String, serde_json::Value>,
+    known: &[&str],
+    removed: &mut Vec<String>,
+) {
+    obj.retain(|key, _| {
+        let keep = known.contains(&key.as_str());
+        if !keep && !removed.contains(key) {
+            removed.push(key.clone());
+        }
+        keep
+    });
+}
+
+fn normalize_payload(raw: &str, policy: CompressionPolicy, strict: bool) -> Result<Cow<'_, str>> {
     let trimmed = raw.trim();
-    let normalized_input = match normalize_kv_payload_to_json(trimmed)? {
+    let normalized_input = match normalize_kv_payload_to_json(trimmed, strict)? {
         Some(json) => Cow::Owned(json),
         None => Cow::Borrowed(trimmed),
     };
 
-    let probe: FrameTypeProbe = serde_json::from_str(normalized_input.as_ref())
-        .map_err(|e| Error::Parse(format!("json: {e}")))?;
+    let probe: FrameTypeProbe =
+        serde_json::from_str(normalized_input.as_ref()).map_err(|e| json_error("json", e))?;
     if probe.kind.as_deref() != Some("compressed") {
         return Ok(normalized_input);
     }
 
     if !policy.enabled {
-        return Err(Error::Parse(
-            "compressed payload rejected: compression disabled".into(),
-        ));
+        return Err(
+            FrameErrorCode::Compression.error("compressed payload rejected: compression disabled")
+        );
     }
 
     let envelope: CompressionEnvelopeOwned = serde_json::from_str(normalized_input.as_ref())
-        .map_err(|e| Error::Parse(format!("compressed envelope: {e}")))?;
+        .map_err(|e| FrameErrorCode::Compression.error(format_args!("compressed envelope: {e}")))?;
     if envelope.schema_version != DEFAULT_PROTOCOL_SCHEMA_VERSION {
-        return Err(Error::Parse(format!(
+        return Err(FrameErrorCode::SchemaVersion.error(format_args!(
             "unsupported compressed schema_version={} expected={DEFAULT_PROTOCOL_SCHEMA_VERSION}",
             envelope.schema_version
         )));
     }
     if envelope.frame_type.as_str() != "compressed" {
-        return Err(Error::Parse(format!(
+        return Err(FrameErrorCode::Compression.error(format_args!(
             "unsupported envelope type '{}'",
             envelope.frame_type
         )));
     }
 
     let codec = CompressionCodec::from_name(&envelope.codec).ok_or_else(|| {
-        Error::Parse(format!(
+        FrameErrorCode::Compression.error(format_args!(
             "unsupported compression codec '{}'",
             envelope.codec
         ))
     })?;
     if let Some(expected) = policy.allowed_codec {
         if codec != expected {
-            return Err(Error::Parse(format!(
+            return Err(FrameErrorCode::Compression.error(format_args!(
                 "compression codec '{}' not allowed (expected {})",
                 envelope.codec,
                 expected.as_str()
             )));
         }
     }
-    let decompressed = decompress(envelope.data.as_ref(), codec)?;
+    let decompressed = decompress(envelope.data.as_ref(), codec).map_err(|err| match err {
+        Error::Parse(msg) => FrameErrorCode::Compression.error(msg),
+        other => other,
+    })?;
     if decompressed.len() != envelope.original_len as usize {
-        return Err(Error::Parse(format!(
+        return Err(FrameErrorCode::Compression.error(format_args!(
             "compressed original_len={} but decoded={}",
             envelope.original_len,
             decompressed.len()
         )));
     }
     let payload = String::from_utf8(decompressed)
-        .map_err(|_| Error::Parse("decompressed payload not utf-8".into()))?;
+        .map_err(|_| FrameErrorCode::Compression.error("decompressed payload not utf-8"))?;
     Ok(Cow::Owned(payload))
 }
 
@@ -548,6 +655,90 @@ pub struct Defaults {
     pub page_timeout_ms: u64,
 }
 
+/// Longest row a schema v1 frame may carry; the widest supported glass.
+pub(super) const MAX_LINE_LENGTH: usize = 40;
+const MAX_BAR_LABEL_LENGTH: usize = 40;
+const MAX_BAR_UNIT_LENGTH: usize = 8;
+/// Longest unit-suffixed `bar_value`/`bar_max` string.
+const MAX_BAR_AMOUNT_LENGTH: usize = 16;
+/// Longest zone name a `target` may carry.
+pub const MAX_TARGET_LENGTH: usize = 16;
+
+/// Whether `name` can name a `[[zones]]` entry and so appear as a payload `target`: 1 to
+/// [`MAX_TARGET_LENGTH`] lowercase letters, digits, `-` or `_`.
+pub fn is_zone_name(name: &str) -> bool {
+    (1..=MAX_TARGET_LENGTH).contains(&name.len())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+/// Every top-level key [`Payload`] accepts; anything else is an unknown field.
+pub(super) const PAYLOAD_FIELDS: &[&str] = &[
+    "type",
+    "line1",
+    "line2",
+    "line3",
+    "line4",
+    "schema_version",
+    "bar",
+    "bar_value",
+    "bar_max",
+    "bar_label",
+    "bar_line1",
+    "bar_line2",
+    "backlight",
+    "blink",
+    "scroll",
+    "scroll_speed_ms",
+    "duration_ms",
+    "page_timeout_ms",
+    "clear",
+    "test",
+    "mode",
+    "icons",
+    "checksum",
+    "config_reload",
+    "severity",
+    "bar_style",
+    "bar_line3",
+    "bar_line4",
+    "bar_unit",
+    "backlight_pattern",
+    "priority",
+    "custom_glyphs",
+    "target",
+];
+
+/// Top-level keys of a multi-page frame.
+const PAGE_SET_FIELDS: &[&str] = &["type", "schema_version", "pages"];
+
+/// Most pages one multi-page frame may define.
+pub const MAX_PAGES_PER_FRAME: usize = 8;
+
+/// `{"schema_version":1,"pages":[{...},{...}]}`: a whole rotation set in one frame. Each page is
+/// an ordinary payload object; pages without `schema_version` inherit the set's.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PageSet {
+    #[serde(rename = "type", default)]
+    _frame_type: Option<String>,
+    #[serde(default)]
+    schema_version: Option<u8>,
+    pages: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PageSetProbe {
+    #[serde(default)]
+    pages: Option<serde::de::IgnoredAny>,
+}
+
+/// True when a normalized frame carries a `pages` array rather than a single page.
+pub fn is_page_set(normalized: &str) -> bool {
+    serde_json::from_str::<PageSetProbe>(normalized).is_ok_and(|probe| probe.pages.is_some())
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub struct Payload {
@@ -556,15 +747,20 @@ pub struct Payload {
     pub frame_type: Option<String>,
     pub line1: String,
     pub line2: String,
+    /// Rows 3 and 4 of 20x4 glass; skipped when absent so older checksums still match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line3: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line4: Option<String>,
     #[serde(default)]
     pub schema_version: Option<u8>,
 
     #[serde(default)]
     pub bar: Option<u8>,
     #[serde(default)]
-    pub bar_value: Option<u32>,
+    pub bar_value: Option<BarAmount>,
     #[serde(default)]
-    pub bar_max: Option<u32>,
+    pub bar_max: Option<BarAmount>,
     #[serde(default)]
     pub bar_label: Option<String>,
     #[serde(default)]
@@ -592,21 +788,166 @@ pub struct Payload {
     pub mode: Option<String>,
     #[serde(default)]
     pub icons: Option<Vec<String>>,
+    /// Name to eight 5-bit bitmap rows; usable in `icons` and as `{glyph:name}` in lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_glyphs: Option<BTreeMap<String, Vec<u8>>>,
     #[serde(default)]
     pub checksum: Option<String>,
     #[serde(default)]
     pub config_reload: Option<bool>,
+    /// Skipped when absent so checksums from older senders still match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    /// Overrides `[render].bar_style` for this frame's bar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bar_style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bar_line3: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bar_line4: Option<bool>,
+    /// Unit shown after `bar_value`/`bar_max` in the automatic label, e.g. `"B"` for `"1.5GB/4GB"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bar_unit: Option<String>,
+    /// `breathe`, `double_pulse` or `sos`; takes over from `backlight` and `blink`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backlight_pattern: Option<String>,
+    /// `normal`, `high` or `urgent`; above `normal` the frame interrupts the page rotation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    /// `[[zones]]` name that shows this frame instead of the main rotation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+/// `bar_value` or `bar_max` as sent: a plain count, or a number with a scale suffix such as
+/// `"1.5G"` (k, M, G, T, P are powers of 1000; Ki, Mi, Gi, Ti, Pi powers of 1024).
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum BarAmount {
+    Count(u32),
+    Text(String),
+}
+
+impl BarAmount {
+    /// The amount as written, minus a trailing `unit` the sender may have repeated.
+    fn text(&self, unit: Option<&str>) -> String {
+        match self {
+            Self::Count(count) => count.to_string(),
+            Self::Text(text) => {
+                let text = text.trim();
+                unit.filter(|unit| !unit.is_empty())
+                    .and_then(|unit| text.strip_suffix(unit))
+                    .unwrap_or(text)
+                    .trim_end()
+                    .to_string()
+            }
+        }
+    }
+
+    /// The scaled value, or `None` when the text is not a number with a known suffix.
+    fn resolve(&self, unit: Option<&str>) -> Option<f64> {
+        let text = match self {
+            Self::Count(count) => return Some(*count as f64),
+            Self::Text(_) => self.text(unit),
+        };
+        let split = text
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(text.len());
+        let (number, suffix) = text.split_at(split);
+        let scale = match suffix.trim_start() {
+            "" => 1.0,
+            "k" | "K" => 1e3,
+            "M" => 1e6,
+            "G" => 1e9,
+            "T" => 1e12,
+            "P" => 1e15,
+            "Ki" => 1024f64,
+            "Mi" => 1024f64.powi(2),
+            "Gi" => 1024f64.powi(3),
+            "Ti" => 1024f64.powi(4),
+            "Pi" => 1024f64.powi(5),
+            _ => return None,
+        };
+        let number: f64 = number.parse().ok()?;
+        Some(number * scale).filter(|value| value.is_finite())
+    }
+}
+
+impl Payload {
+    /// A bare schema_v1 frame with two lines and nothing else set.
+    pub fn new(line1: impl Into<String>, line2: impl Into<String>) -> Self {
+        Self {
+            frame_type: None,
+            line1: line1.into(),
+            line2: line2.into(),
+            line3: None,
+            line4: None,
+            schema_version: Some(1),
+            bar: None,
+            bar_value: None,
+            bar_max: None,
+            bar_label: None,
+            bar_line1: None,
+            bar_line2: None,
+            backlight: None,
+            blink: None,
+            scroll: None,
+            scroll_speed_ms: None,
+            duration_ms: None,
+            page_timeout_ms: None,
+            clear: None,
+            test: None,
+            mode: None,
+            icons: None,
+            checksum: None,
+            config_reload: None,
+            severity: None,
+            bar_style: None,
+            bar_line3: None,
+            bar_line4: None,
+            bar_unit: None,
+            backlight_pattern: None,
+            priority: None,
+            custom_glyphs: None,
+            target: None,
+        }
+    }
+
+    /// CRC32 of the payload serialized without its `checksum` field.
+    pub fn compute_checksum(&self) -> Result<u32> {
+        let canonical = Payload {
+            checksum: None,
+            ..self.clone()
+        };
+        let bytes = serde_json::to_vec(&canonical)
+            .map_err(|e| Error::Parse(format!("serialize for checksum: {e}")))?;
+        let mut hasher = Hasher::new();
+        hasher.update(&bytes);
+        Ok(hasher.finalize())
+    }
+
+    /// Fill in `checksum` so the receiver can verify the frame.
+    pub fn seal(&mut self) -> Result<()> {
+        self.checksum = None;
+        self.checksum = Some(format!("{:08x}", self.compute_checksum()?));
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RenderFrame {
     pub line1: String,
     pub line2: String,
+    /// Empty unless the payload targets a 4-row display.
+    pub line3: String,
+    pub line4: String,
     pub backlight_on: bool,
     pub blink: bool,
     pub bar_percent: Option<u8>,
     pub bar_label: Option<String>,
-    pub bar_row: Option<u8>, // 0 = top, 1 = bottom
+    pub bar_row: Option<u8>, // 0-based row, 0 = top
+    /// Payload override for the bar glyphs; `None` uses `[render].bar_style`.
+    pub bar_style: Option<BarStyle>,
     pub scroll_speed_ms: u64,
     pub scroll_enabled: bool,
     pub duration_ms: Option<u64>,
@@ -616,6 +957,15 @@ pub struct RenderFrame {
     pub mode: DisplayMode,
     pub icons: Vec<Icon>,
     pub config_reload: bool,
+    pub severity: Option<Severity>,
+    pub backlight_pattern: Option<BacklightPattern>,
+    pub priority: Priority,
+    /// Bitmaps the payload defined; line text carries them as placeholder chars.
+    pub custom_glyphs: Vec<CustomGlyph>,
+    /// Indexes into `custom_glyphs` of the names listed in `icons`.
+    pub custom_icons: Vec<usize>,
+    /// Zone the frame was sent to; `None` for the main rotation.
+    pub target: Option<String>,
 }
 
 impl RenderFrame {
@@ -630,77 +980,177 @@ impl RenderFrame {
     }
 
     pub fn from_payload_json_with_defaults(raw: &str, defaults: Defaults) -> Result<Self> {
-        let normalized = normalize_payload_json(raw)?;
+        let normalized = if is_small_frame(raw) {
+            Cow::Borrowed(raw.trim())
+        } else {
+            normalize_payload_json(raw)?
+        };
         Self::from_normalized_payload_with_defaults(&normalized, defaults)
     }
 
+    /// Pages of a multi-page frame (see [`is_page_set`]), in rotation order.
+    pub fn pages_from_normalized_payload(raw: &str, defaults: Defaults) -> Result<Vec<Self>> {
+        let set: PageSet = serde_json::from_str(raw).map_err(|e| json_error("pages", e))?;
+        if set.pages.is_empty() {
+            return Err(FrameErrorCode::BadPages.error("pages must not be empty"));
+        }
+        if set.pages.len() > MAX_PAGES_PER_FRAME {
+            return Err(FrameErrorCode::BadPages
+                .error(format_args!("pages must be <= {MAX_PAGES_PER_FRAME} items")));
+        }
+        set.pages
+            .into_iter()
+            .enumerate()
+            .map(|(idx, mut page)| {
+                if let Some(version) = set.schema_version {
+                    page.entry("schema_version")
+                        .or_insert_with(|| serde_json::Value::from(version));
+                }
+                let json = serde_json::Value::Object(page).to_string();
+                let frame = Self::from_normalized_payload_with_defaults(&json, defaults).map_err(
+                    |err| match err {
+                        Error::Parse(msg) => Error::Parse(format!("pages[{idx}]: {msg}")),
+                        other => other,
+                    },
+                )?;
+                if frame.config_reload {
+                    return Err(FrameErrorCode::BadPages.error(format_args!(
+                        "pages[{idx}]: config_reload must be sent on its own"
+                    )));
+                }
+                if frame.target.is_some() {
+                    return Err(FrameErrorCode::BadPages.error(format_args!(
+                        "pages[{idx}]: a page set always replaces the main rotation; send target frames on their own"
+                    )));
+                }
+                Ok(frame)
+            })
+            .collect()
+    }
+
     pub fn from_normalized_payload_with_defaults(raw: &str, defaults: Defaults) -> Result<Self> {
-        let payload: Payload =
-            serde_json::from_str(raw).map_err(|e| Error::Parse(format!("json: {e}")))?;
+        let payload: Payload = serde_json::from_str(raw).map_err(|e| json_error("json", e))?;
 
         // Schema versioning: require schema_version to be present and enforce
         // strict bounds for lengths, icon counts and labels in version 1+.
-        const MAX_LINE_LENGTH: usize = 40; // hardware max columns
         const MAX_ICONS: usize = 4;
-        const MAX_BAR_LABEL_LENGTH: usize = 40;
 
         let schema_version = match payload.schema_version {
             Some(v) => v,
-            None => return Err(Error::Parse("schema_version is required".into())),
+            None => return Err(FrameErrorCode::SchemaVersion.error("schema_version is required")),
+        };
+        let custom_glyphs = match &payload.custom_glyphs {
+            Some(raw) => parse_custom_glyphs(raw)
+                .map_err(|e| FrameErrorCode::BadField.error(format_args!("custom_glyphs: {e}")))?,
+            None => Vec::new(),
         };
         if schema_version >= 1 {
-            if payload.line1.chars().count() > MAX_LINE_LENGTH {
-                return Err(Error::Parse(format!(
-                    "line1 must be <= {MAX_LINE_LENGTH} chars"
-                )));
-            }
-            if payload.line2.chars().count() > MAX_LINE_LENGTH {
-                return Err(Error::Parse(format!(
-                    "line2 must be <= {MAX_LINE_LENGTH} chars"
-                )));
+            let lines = [
+                ("line1", Some(&payload.line1)),
+                ("line2", Some(&payload.line2)),
+                ("line3", payload.line3.as_ref()),
+                ("line4", payload.line4.as_ref()),
+            ];
+            for (name, line) in lines {
+                // A `{glyph:name}` placeholder takes one cell.
+                if line.is_some_and(|line| {
+                    expand_placeholders(line, &custom_glyphs).chars().count() > MAX_LINE_LENGTH
+                }) {
+                    return Err(FrameErrorCode::LineTooLong
+                        .error(format_args!("{name} must be <= {MAX_LINE_LENGTH} chars")));
+                }
             }
             if let Some(icons) = &payload.icons {
                 if icons.len() > MAX_ICONS {
-                    return Err(Error::Parse(format!("icons must be <= {MAX_ICONS} items")));
+                    return Err(FrameErrorCode::BadField
+                        .error(format_args!("icons must be <= {MAX_ICONS} items")));
                 }
             }
             if let Some(label) = &payload.bar_label {
                 if label.chars().count() > MAX_BAR_LABEL_LENGTH {
-                    return Err(Error::Parse(format!(
+                    return Err(FrameErrorCode::LineTooLong.error(format_args!(
                         "bar_label must be <= {MAX_BAR_LABEL_LENGTH} chars"
                     )));
                 }
             }
         }
 
-        if let Some(bar_max) = payload.bar_max {
-            if bar_max < 1 {
-                return Err(Error::Parse("bar_max must be >= 1".into()));
+        let bar_unit = payload.bar_unit.as_deref();
+        if bar_unit.is_some_and(|unit| unit.chars().count() > MAX_BAR_UNIT_LENGTH) {
+            return Err(FrameErrorCode::LineTooLong.error(format_args!(
+                "bar_unit must be <= {MAX_BAR_UNIT_LENGTH} chars"
+            )));
+        }
+        let resolve = |name: &str, amount: Option<&BarAmount>| -> Result<Option<f64>> {
+            let Some(amount) = amount else {
+                return Ok(None);
+            };
+            if let BarAmount::Text(text) = amount {
+                if text.chars().count() > MAX_BAR_AMOUNT_LENGTH {
+                    return Err(FrameErrorCode::BadField.error(format_args!(
+                        "{name} must be <= {MAX_BAR_AMOUNT_LENGTH} chars"
+                    )));
+                }
             }
+            amount.resolve(bar_unit).map(Some).ok_or_else(|| {
+                FrameErrorCode::BadField.error(format_args!(
+                    "{name} must be a number with an optional k/M/G/T/P or Ki/Mi/Gi/Ti/Pi suffix"
+                ))
+            })
+        };
+        let bar_value = resolve("bar_value", payload.bar_value.as_ref())?;
+        let bar_max = resolve("bar_max", payload.bar_max.as_ref())?;
+        if bar_max.is_some_and(|max| max <= 0.0) {
+            return Err(FrameErrorCode::BadField.error("bar_max must be > 0"));
         }
-        if let (Some(value), Some(max)) = (payload.bar_value, payload.bar_max) {
+        if let (Some(value), Some(max)) = (bar_value, bar_max) {
             if value > max {
-                return Err(Error::Parse("bar_value must be <= bar_max".into()));
+                return Err(FrameErrorCode::BadField.error("bar_value must be <= bar_max"));
+            }
+        }
+        if matches!(payload.bar_value, Some(BarAmount::Text(_))) && bar_max.is_none() {
+            return Err(FrameErrorCode::BadField
+                .error("bar_max is required when bar_value has a unit suffix"));
+        }
+        if let Some(severity) = &payload.severity {
+            if Severity::from_name(severity).is_none() {
+                return Err(
+                    FrameErrorCode::BadField.error("severity must be one of info, warn, crit")
+                );
+            }
+        }
+        if let Some(style) = &payload.bar_style {
+            style
+                .parse::<BarStyle>()
+                .map_err(|e| FrameErrorCode::BadField.error(format_args!("bar_style: {e}")))?;
+        }
+        if let Some(pattern) = &payload.backlight_pattern {
+            pattern.parse::<BacklightPattern>().map_err(|e| {
+                FrameErrorCode::BadField.error(format_args!("backlight_pattern: {e}"))
+            })?;
+        }
+        if let Some(priority) = &payload.priority {
+            priority
+                .parse::<Priority>()
+                .map_err(|e| FrameErrorCode::BadField.error(format_args!("priority: {e}")))?;
+        }
+        if let Some(target) = &payload.target {
+            if !is_zone_name(target) {
+                return Err(FrameErrorCode::BadField.error(format_args!(
+                    "target must be 1-{MAX_TARGET_LENGTH} lowercase letters, digits, '-' or '_'"
+                )));
             }
         }
         if let Some(timeout) = payload.page_timeout_ms {
             if timeout == 0 {
-                return Err(Error::Parse("page_timeout_ms must be > 0".into()));
+                return Err(FrameErrorCode::BadField.error("page_timeout_ms must be > 0"));
             }
         }
 
         if let Some(checksum_hex) = &payload.checksum {
-            let canonical = Payload {
-                checksum: None,
-                ..payload.clone()
-            };
-            let mut hasher = Hasher::new();
-            let bytes = serde_json::to_vec(&canonical)
-                .map_err(|e| Error::Parse(format!("serialize for checksum: {e}")))?;
-            hasher.update(&bytes);
-            let computed = hasher.finalize();
+            let computed = payload.compute_checksum()?;
             let expected = u32::from_str_radix(checksum_hex.trim_start_matches("0x"), 16)
-                .map_err(|_| Error::Parse("invalid checksum hex".into()))?;
+                .map_err(|_| FrameErrorCode::BadCrc.error("invalid checksum hex"))?;
             if computed != expected {
                 return Err(Error::ChecksumMismatch);
             }
@@ -712,28 +1162,80 @@ impl RenderFrame {
     pub fn from_payload_with_defaults(payload: Payload, defaults: Defaults) -> Self {
         let backlight_on = payload.backlight.unwrap_or(true);
         let blink = payload.blink.unwrap_or(false);
-        let scroll_enabled = payload.scroll.unwrap_or(true);
+        let mut scroll_enabled = payload.scroll.unwrap_or(true);
         let scroll_speed_ms = payload.scroll_speed_ms.unwrap_or(defaults.scroll_speed_ms);
         let page_timeout_ms = payload.page_timeout_ms.unwrap_or(defaults.page_timeout_ms);
 
         let bar_percent = compute_bar_percent(&payload);
-        let bar_row = if bar_percent.is_some() {
-            if payload.bar_line1.unwrap_or(false) {
-                Some(0)
-            } else {
-                Some(1)
-            }
-        } else {
-            None
-        };
+        let bar_label = payload
+            .bar_label
+            .clone()
+            .or_else(|| unit_bar_label(&payload));
+        // The first row asking for the bar gets it; the second row is the historical default.
+        let bar_row = bar_percent.map(|_| {
+            [
+                payload.bar_line1,
+                payload.bar_line2,
+                payload.bar_line3,
+                payload.bar_line4,
+            ]
+            .iter()
+            .position(|flag| flag.unwrap_or(false))
+            .map_or(1, |row| row as u8)
+        });
 
         let mode = DisplayMode::parse(payload.mode.clone());
-        let icons = parse_icons(payload.icons.clone());
-
-        let line1 = payload.line1;
-        let mut line2 = payload.line2;
+        let custom_glyphs = payload
+            .custom_glyphs
+            .as_ref()
+            .and_then(|raw| parse_custom_glyphs(raw).ok())
+            .unwrap_or_default();
+        let custom_index = |name: &str| custom_glyphs.iter().position(|glyph| glyph.name == name);
+        let custom_icons = payload
+            .icons
+            .iter()
+            .flatten()
+            .filter_map(|name| custom_index(name))
+            .collect();
+        // A payload's own glyph wins over a built-in icon of the same name.
+        let icons = parse_icons(payload.icons.clone().map(|names| {
+            names
+                .into_iter()
+                .filter(|name| custom_index(name).is_none())
+                .collect()
+        }));
+        let expand = |line: String| match expand_placeholders(&line, &custom_glyphs) {
+            Cow::Borrowed(_) => line,
+            Cow::Owned(expanded) => expanded,
+        };
+        let severity = payload.severity.as_deref().and_then(Severity::from_name);
+        let bar_style = payload
+            .bar_style
+            .as_deref()
+            .and_then(|style| style.parse().ok());
+        let backlight_pattern = payload
+            .backlight_pattern
+            .as_deref()
+            .and_then(|pattern| pattern.parse().ok());
+        let priority = payload
+            .priority
+            .as_deref()
+            .and_then(|priority| priority.parse().ok())
+            .unwrap_or_default();
+
+        let line1 = expand(payload.line1);
+        let mut line2 = expand(payload.line2);
+        let mut line3 = expand(payload.line3.unwrap_or_default());
+        let mut line4 = expand(payload.line4.unwrap_or_default());
         if matches!(mode, DisplayMode::Banner) {
-            line2 = String::new();
+            line2.clear();
+            line3.clear();
+            line4.clear();
+        }
+        if matches!(mode, DisplayMode::BigDigits) {
+            // The big text fills the first two rows; it is clipped, never scrolled.
+            line2.clear();
+            scroll_enabled = false;
         }
 
         let bar_row = if matches!(mode, DisplayMode::Dashboard) && bar_percent.is_some() {
@@ -745,11 +1247,14 @@ impl RenderFrame {
         RenderFrame {
             line1,
             line2,
+            line3,
+            line4,
             backlight_on,
             blink,
             bar_percent,
-            bar_label: payload.bar_label,
+            bar_label,
             bar_row,
+            bar_style,
             scroll_speed_ms,
             scroll_enabled,
             duration_ms: payload.duration_ms,
@@ -759,24 +1264,202 @@ impl RenderFrame {
             mode,
             icons,
             config_reload: payload.config_reload.unwrap_or(false),
+            severity,
+            backlight_pattern,
+            priority,
+            custom_glyphs,
+            custom_icons,
+            target: payload.target,
+        }
+    }
+
+    /// Text of a 0-based row; rows past the fourth are always empty.
+    pub fn line(&self, row: u8) -> &str {
+        match row {
+            0 => &self.line1,
+            1 => &self.line2,
+            2 => &self.line3,
+            3 => &self.line4,
+            _ => "",
         }
     }
+
+    pub fn line_mut(&mut self, row: u8) -> Option<&mut String> {
+        match row {
+            0 => Some(&mut self.line1),
+            1 => Some(&mut self.line2),
+            2 => Some(&mut self.line3),
+            3 => Some(&mut self.line4),
+            _ => None,
+        }
+    }
+
+    /// Fit the frame to a `cols` x `rows` display after a geometry change: rows the display no
+    /// longer has are blanked, the bar moves to the last row if its row is gone, and lines that
+    /// will not scroll are cut to the width. A one-row display alternates between the first two
+    /// rows, so it keeps both. Returns whether anything changed.
+    pub fn clamp_to(&mut self, cols: u8, rows: u8) -> bool {
+        let rows = rows.max(2);
+        let mut changed = false;
+        for row in 0..4u8 {
+            let scroll = self.scroll_enabled;
+            let Some(line) = self.line_mut(row) else {
+                continue;
+            };
+            let keep = if row >= rows {
+                0
+            } else if scroll {
+                usize::MAX
+            } else {
+                cols as usize
+            };
+            if line.chars().count() > keep {
+                *line = line.chars().take(keep).collect();
+                changed = true;
+            }
+        }
+        if self.bar_row.is_some_and(|row| row >= rows) {
+            self.bar_row = Some(rows.saturating_sub(1));
+            changed = true;
+        }
+        changed
+    }
+
+    /// A schema_v1 payload that parses back into this frame. One-shot actions (`clear`, `test`,
+    /// `config_reload`) are left out, and an automatic unit label becomes a plain `bar_label`.
+    pub fn to_payload(&self) -> Payload {
+        let restore = |line: &str| restore_placeholders(line, &self.custom_glyphs);
+        let optional = |line: &str| (!line.is_empty()).then(|| restore(line));
+        let bar_row = self.bar_percent.and(self.bar_row);
+        let mut payload = Payload::new(restore(&self.line1), restore(&self.line2));
+        payload.line3 = optional(&self.line3);
+        payload.line4 = optional(&self.line4);
+        payload.bar = self.bar_percent;
+        payload.bar_label = self
+            .bar_label
+            .as_ref()
+            .map(|label| label.chars().take(MAX_BAR_LABEL_LENGTH).collect());
+        payload.bar_line1 = (bar_row == Some(0)).then_some(true);
+        payload.bar_line2 = (bar_row == Some(1)).then_some(true);
+        payload.bar_line3 = (bar_row == Some(2)).then_some(true);
+        payload.bar_line4 = (bar_row == Some(3)).then_some(true);
+        payload.bar_style = self.bar_style.map(|style| style.as_str().to_string());
+        payload.backlight = (!self.backlight_on).then_some(false);
+        payload.blink = self.blink.then_some(true);
+        payload.scroll = (!self.scroll_enabled).then_some(false);
+        payload.scroll_speed_ms = Some(self.scroll_speed_ms);
+        payload.duration_ms = self.duration_ms;
+        payload.page_timeout_ms = Some(self.page_timeout_ms);
+        payload.mode = (self.mode != DisplayMode::Normal).then(|| self.mode.as_str().to_string());
+        let custom_icons = self
+            .custom_icons
+            .iter()
+            .filter_map(|idx| self.custom_glyphs.get(*idx));
+        payload.icons = (!self.icons.is_empty() || !self.custom_icons.is_empty()).then(|| {
+            self.icons
+                .iter()
+                .map(|icon| icon.as_str().to_string())
+                .chain(custom_icons.map(|glyph| glyph.name.clone()))
+                .collect()
+        });
+        payload.custom_glyphs = (!self.custom_glyphs.is_empty()).then(|| {
+            self.custom_glyphs
+                .iter()
+                .map(|glyph| (glyph.name.clone(), glyph.rows.to_vec()))
+                .collect()
+        });
+        payload.severity = self.severity.map(|severity| severity.as_str().to_string());
+        payload.backlight_pattern = self
+            .backlight_pattern
+            .map(|pattern| pattern.as_str().to_string());
+        payload.priority =
+            (self.priority != Priority::Normal).then(|| self.priority.as_str().to_string());
+        payload.target = self.target.clone();
+        payload
+    }
 }
 
 fn compute_bar_percent(payload: &Payload) -> Option<u8> {
     if let Some(percent) = payload.bar {
         return Some(percent.clamp(0, 100));
     }
-    if let Some(value) = payload.bar_value {
-        let max = payload.bar_max.unwrap_or(100).max(1);
-        let percent = ((value as f64 / max as f64) * 100.0).round() as i32;
-        let clamped = percent.clamp(0, 100) as u8;
+    if let Some(value) = &payload.bar_value {
+        let unit = payload.bar_unit.as_deref();
+        let value = value.resolve(unit)?;
+        let max = match &payload.bar_max {
+            Some(max) => max.resolve(unit)?,
+            None => 100.0,
+        };
+        if max <= 0.0 {
+            return Some(0);
+        }
+        let percent = ((value / max) * 100.0).round();
+        let clamped = percent.clamp(0.0, 100.0) as u8;
         return Some(clamped);
     }
 
     None
 }
 
+/// `"1.5GB/4GB"` for a bar sent in human units (a `bar_unit` or a suffixed amount) without its
+/// own `bar_label`. Labels that would not fit are left off.
+fn unit_bar_label(payload: &Payload) -> Option<String> {
+    let value = payload.bar_value.as_ref()?;
+    let has_suffix = |amount: Option<&BarAmount>| matches!(amount, Some(BarAmount::Text(_)));
+    if payload.bar_unit.is_none()
+        && !has_suffix(Some(value))
+        && !has_suffix(payload.bar_max.as_ref())
+    {
+        return None;
+    }
+    let unit = payload.bar_unit.as_deref().unwrap_or("");
+    let mut label = format!("{}{unit}", value.text(Some(unit)));
+    if let Some(max) = &payload.bar_max {
+        label.push_str(&format!("/{}{unit}", max.text(Some(unit))));
+    }
+    (label.chars().count() <= MAX_BAR_LABEL_LENGTH).then_some(label)
+}
+
+/// Row-addressed partial update: `{"row":1,"text":"..."}` replaces one line of the page on
+/// screen without resending the whole frame. Rows are 0-based like `bar_row`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct RowUpdate {
+    pub row: u8,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u8>,
+}
+
+impl RowUpdate {
+    pub fn from_json(raw: &str) -> Result<Self> {
+        const MAX_TEXT_LENGTH: usize = 40;
+        let update: RowUpdate =
+            serde_json::from_str(raw.trim()).map_err(|e| json_error("json", e))?;
+        if update.row > 3 {
+            return Err(FrameErrorCode::BadRow.error("row must be between 0 and 3"));
+        }
+        if update.text.chars().count() > MAX_TEXT_LENGTH {
+            return Err(FrameErrorCode::LineTooLong
+                .error(format_args!("text must be <= {MAX_TEXT_LENGTH} chars")));
+        }
+        Ok(update)
+    }
+}
+
+/// Tag a serde failure: an unknown field or a bad value is a schema problem, anything else is
+/// malformed JSON.
+fn json_error(context: &str, err: serde_json::Error) -> Error {
+    let code = if !err.is_data() {
+        FrameErrorCode::Json
+    } else if err.to_string().starts_with("unknown field") {
+        FrameErrorCode::UnknownField
+    } else {
+        FrameErrorCode::BadField
+    };
+    code.error(format_args!("{context}: {err}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -816,6 +1499,59 @@ mod tests {
         assert!(format!("{err}").contains("unknown key=value"));
     }
 
+    #[test]
+    fn permissive_mode_drops_unknown_fields() {
+        let raw = r#"{"schema_version":1,"line1":"Hello","line2":"World","experiment":{"a":1}}"#;
+        assert!(RenderFrame::from_payload_json(raw).is_err());
+        let (cleaned, ignored) =
+            normalize_payload_json_permissive(raw, CompressionPolicy::allow_any()).unwrap();
+        assert_eq!(ignored, vec!["experiment".to_string()]);
+        let frame = RenderFrame::from_normalized_payload_with_defaults(
+            &cleaned,
+            Defaults {
+                scroll_speed_ms: DEFAULT_SCROLL_MS,
+                page_timeout_ms: DEFAULT_PAGE_TIMEOUT_MS,
+            },
+        )
+        .unwrap();
+        assert_eq!(frame.line1, "Hello");
+
+        let (_, ignored) = normalize_payload_json_permissive(
+            "schema_version=1 line1=Hello line2=World nope=1",
+            CompressionPolicy::allow_any(),
+        )
+        .unwrap();
+        assert_eq!(ignored, vec!["nope".to_string()]);
+    }
+
+    #[test]
+    fn payload_field_list_matches_the_struct() {
+        let mut payload = Payload::new("a", "b");
+        payload.frame_type = Some("display".into());
+        payload.line3 = Some(String::new());
+        payload.line4 = Some(String::new());
+        payload.severity = Some("info".into());
+        payload.bar_style = Some("vertical".into());
+        payload.bar_line3 = Some(true);
+        payload.bar_line4 = Some(true);
+        payload.bar_unit = Some("B".into());
+        payload.backlight_pattern = Some("sos".into());
+        payload.priority = Some("urgent".into());
+        payload.custom_glyphs = Some(BTreeMap::new());
+        payload.target = Some("alerts".into());
+        let value = serde_json::to_value(&payload).unwrap();
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let mut known = PAYLOAD_FIELDS.to_vec();
+        keys.sort_unstable();
+        known.sort_unstable();
+        assert_eq!(keys, known);
+    }
+
     #[test]
     fn from_payload_json_trims_whitespace_and_crlf() {
         let raw = "\r\n  {\"schema_version\":1,\"line1\":\"Hello\",\"line2\":\"World\"}  \r\n";
@@ -873,6 +1609,120 @@ mod tests {
         assert_eq!(frame.bar_percent, Some(42));
     }
 
+    #[test]
+    fn unit_suffixed_bar_amounts_scale_and_label_themselves() {
+        let frame = parse(
+            r#"{"schema_version":1,"line1":"RAM","line2":"","bar_value":"1.5GB","bar_max":"4G","bar_unit":"B"}"#,
+        );
+        assert_eq!(frame.bar_percent, Some(38));
+        assert_eq!(frame.bar_label.as_deref(), Some("1.5GB/4GB"));
+
+        let frame = parse("schema_version=1 line1=Swap line2= bar_value=512Mi bar_max=2Gi");
+        assert_eq!(frame.bar_percent, Some(25));
+        assert_eq!(frame.bar_label.as_deref(), Some("512Mi/2Gi"));
+
+        let frame = parse(
+            r#"{"schema_version":1,"line1":"","line2":"","bar_value":"750k","bar_max":"1M","bar_label":"NET"}"#,
+        );
+        assert_eq!(frame.bar_percent, Some(75));
+        assert_eq!(frame.bar_label.as_deref(), Some("NET"));
+
+        let frame =
+            parse(r#"{"schema_version":1,"line1":"","line2":"","bar_value":42,"bar_unit":"%"}"#);
+        assert_eq!(frame.bar_label.as_deref(), Some("42%"));
+    }
+
+    #[test]
+    fn rejects_bad_unit_suffixed_bar_amounts() {
+        for (raw, field) in [
+            (r#""bar_value":"1.5X","bar_max":"4G""#, "bar_value"),
+            (r#""bar_value":"5G","bar_max":"4G""#, "bar_value"),
+            (r#""bar_value":"1G","bar_max":"0k""#, "bar_max"),
+            (r#""bar_value":"1G""#, "bar_max"),
+            (r#""bar_value":1,"bar_unit":"megabytes""#, "bar_unit"),
+        ] {
+            let raw = format!(r#"{{"schema_version":1,"line1":"","line2":"",{raw}}}"#);
+            let err = RenderFrame::from_payload_json(&raw).unwrap_err();
+            assert!(format!("{err}").contains(field), "{raw}: {err}");
+        }
+    }
+
+    #[test]
+    fn row_update_parses_and_bounds_rows() {
+        let update = RowUpdate::from_json(r#"{"row":1,"text":"count 42"}"#).unwrap();
+        assert_eq!(update.row, 1);
+        assert_eq!(update.text, "count 42");
+        assert_eq!(
+            RowUpdate::from_json(r#"{"row":3,"text":"x"}"#).unwrap().row,
+            3
+        );
+        assert!(RowUpdate::from_json(r#"{"row":4,"text":"x"}"#).is_err());
+        assert!(RowUpdate::from_json(r#"{"row":0,"text":"x","line1":"y"}"#).is_err());
+        let long = format!(r#"{{"row":0,"text":"{}"}}"#, "x".repeat(41));
+        assert!(RowUpdate::from_json(&long).is_err());
+    }
+
+    #[test]
+    fn severity_parses_from_json_and_kv() {
+        let frame = parse(r#"{"schema_version":1,"line1":"a","line2":"b","severity":"crit"}"#);
+        assert_eq!(frame.severity, Some(Severity::Crit));
+        let frame = parse("schema_version=1 line1=a severity=warning");
+        assert_eq!(frame.severity, Some(Severity::Warn));
+        assert!(RenderFrame::from_payload_json(
+            r#"{"schema_version":1,"line1":"a","line2":"b","severity":"loud"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn backlight_pattern_parses_from_json_and_kv() {
+        let frame =
+            parse(r#"{"schema_version":1,"line1":"a","line2":"b","backlight_pattern":"sos"}"#);
+        assert_eq!(frame.backlight_pattern, Some(BacklightPattern::Sos));
+        let frame = parse("schema_version=1 line1=a backlight_pattern=double-pulse");
+        assert_eq!(frame.backlight_pattern, Some(BacklightPattern::DoublePulse));
+        assert_eq!(
+            parse(r#"{"schema_version":1,"line1":"a","line2":"b"}"#).backlight_pattern,
+            None
+        );
+        let err = RenderFrame::from_payload_json(
+            r#"{"schema_version":1,"line1":"a","line2":"b","backlight_pattern":"disco"}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("backlight_pattern"));
+    }
+
+    #[test]
+    fn priority_parses_from_json_and_kv() {
+        let frame = parse(r#"{"schema_version":1,"line1":"a","line2":"b","priority":"urgent"}"#);
+        assert_eq!(frame.priority, Priority::Urgent);
+        let frame = parse("schema_version=1 line1=a priority=high");
+        assert_eq!(frame.priority, Priority::High);
+        assert_eq!(
+            parse(r#"{"schema_version":1,"line1":"a","line2":"b"}"#).priority,
+            Priority::Normal
+        );
+        let err = RenderFrame::from_payload_json(
+            r#"{"schema_version":1,"line1":"a","line2":"b","priority":"asap"}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("priority"));
+    }
+
+    #[test]
+    fn to_payload_round_trips_the_frame() {
+        let frame = parse(
+            r#"{"schema_version":1,"line1":"disk","line2":"","line3":"c","bar_value":"1.5G","bar_max":"4G","bar_unit":"B","bar_line1":true,"bar_style":"segmented","icons":["wifi","open-heart"],"mode":"dashboard","severity":"warn","priority":"high","blink":true,"scroll":false,"page_timeout_ms":9000}"#,
+        );
+        let json = serde_json::to_string(&frame.to_payload()).unwrap();
+        assert_eq!(RenderFrame::from_payload_json(&json).unwrap(), frame);
+
+        let mut cleared = parse(r#"{"schema_version":1,"line1":"a","line2":"b","clear":true}"#);
+        let json = serde_json::to_string(&cleared.to_payload()).unwrap();
+        cleared.clear = false;
+        assert_eq!(RenderFrame::from_payload_json(&json).unwrap(), cleared);
+    }
+
     #[test]
     fn scroll_can_be_disabled() {
         let raw =
@@ -887,6 +1737,8 @@ mod tests {
             frame_type: None,
             line1: "Hi".into(),
             line2: "There".into(),
+            line3: None,
+            line4: None,
             bar: None,
             bar_value: None,
             bar_max: None,
@@ -905,6 +1757,15 @@ mod tests {
             icons: None,
             checksum: None,
             config_reload: None,
+            severity: None,
+            bar_style: None,
+            bar_line3: None,
+            bar_line4: None,
+            bar_unit: None,
+            backlight_pattern: None,
+            priority: None,
+            custom_glyphs: None,
+            target: None,
             schema_version: Some(1),
         };
         let mut hasher = Hasher::new();
@@ -926,6 +1787,8 @@ mod tests {
             frame_type: None,
             line1: "Hi".into(),
             line2: "There".into(),
+            line3: None,
+            line4: None,
             bar: None,
             bar_value: None,
             bar_max: None,
@@ -944,6 +1807,15 @@ mod tests {
             icons: None,
             checksum: None,
             config_reload: None,
+            severity: None,
+            bar_style: None,
+            bar_line3: None,
+            bar_line4: None,
+            bar_unit: None,
+            backlight_pattern: None,
+            priority: None,
+            custom_glyphs: None,
+            target: None,
             schema_version: Some(1),
         };
         let mut hasher = Hasher::new();
@@ -965,6 +1837,20 @@ mod tests {
         assert!(matches!(err, Error::ChecksumMismatch));
     }
 
+    #[test]
+    fn sealed_payloads_pass_checksum_validation() {
+        let mut payload = Payload::new("UP", "load 0.4");
+        payload.bar = Some(50);
+        payload.seal().unwrap();
+        let raw = serde_json::to_string(&payload).unwrap();
+        let frame = RenderFrame::from_payload_json(&raw).unwrap();
+        assert_eq!(frame.bar_percent, Some(50));
+
+        let tampered = raw.replace("load 0.4", "load 9.9");
+        let err = RenderFrame::from_payload_json(&tampered).unwrap_err();
+        assert!(matches!(err, Error::ChecksumMismatch));
+    }
+
     #[test]
     fn duration_ms_supports_new_name_only() {
         let raw_new = r#"{"schema_version":1,"line1":"","line2":"","duration_ms":1234}"#;
@@ -1023,6 +1909,19 @@ mod tests {
         assert!(format!("{err}").contains("bar_max"));
     }
 
+    #[test]
+    fn bar_style_override_is_parsed_and_validated() {
+        let raw = r#"{"schema_version":1,"line1":"","line2":"","bar":10,"bar_style":"Segmented"}"#;
+        assert_eq!(
+            RenderFrame::from_payload_json(raw).unwrap().bar_style,
+            Some(BarStyle::Segmented)
+        );
+        let raw = r#"{"schema_version":1,"line1":"","line2":"","bar":10}"#;
+        assert_eq!(RenderFrame::from_payload_json(raw).unwrap().bar_style, None);
+        let raw = r#"{"schema_version":1,"line1":"","line2":"","bar":10,"bar_style":"dotted"}"#;
+        assert!(RenderFrame::from_payload_json(raw).is_err());
+    }
+
     #[test]
     fn bar_row_defaults_to_bottom() {
         let raw = r#"{"schema_version":1,"line1":"","line2":"","bar":10}"#;
@@ -1037,6 +1936,46 @@ mod tests {
         assert_eq!(frame.bar_row, Some(0));
     }
 
+    #[test]
+    fn four_row_frames_carry_lines_and_bar_placement() {
+        let raw = r#"{"schema_version":1,"line1":"A","line2":"B","line3":"C","line4":"D","bar":40,"bar_line3":true}"#;
+        let frame = parse(raw);
+        assert_eq!((frame.line(2), frame.line(3)), ("C", "D"));
+        assert_eq!(frame.bar_row, Some(2));
+        assert_eq!(frame.line(4), "");
+
+        let kv =
+            RenderFrame::from_payload_json("line1=A line2=B line4=D bar=5 bar_line4=true").unwrap();
+        assert_eq!((kv.line3.as_str(), kv.line4.as_str()), ("", "D"));
+        assert_eq!(kv.bar_row, Some(3));
+
+        let banner =
+            parse(r#"{"schema_version":1,"line1":"A","line2":"B","line3":"C","mode":"banner"}"#);
+        assert_eq!(banner.line3, "");
+    }
+
+    #[test]
+    fn clamping_fits_frames_to_a_smaller_display() {
+        let mut fixed = parse(
+            r#"{"schema_version":1,"line1":"0123456789ABCDEFGHIJ","line2":"short","line3":"C","line4":"D","bar":40,"bar_line4":true,"scroll":false}"#,
+        );
+        assert!(fixed.clamp_to(16, 2));
+        assert_eq!(fixed.line1, "0123456789ABCDEF");
+        assert_eq!(fixed.line2, "short");
+        assert_eq!((fixed.line3.as_str(), fixed.line4.as_str()), ("", ""));
+        assert_eq!(fixed.bar_row, Some(1));
+        assert!(!fixed.clamp_to(16, 2));
+        // A one-row display alternates in line2, so it survives.
+        assert!(!fixed.clamp_to(16, 1));
+        assert_eq!(fixed.line2, "short");
+
+        // Scrolling lines keep their full text; the narrower window scrolls across it.
+        let mut scrolling =
+            parse(r#"{"schema_version":1,"line1":"0123456789ABCDEFGHIJ","line2":""}"#);
+        assert!(!scrolling.clamp_to(16, 2));
+        assert_eq!(scrolling.line1.len(), 20);
+    }
+
     #[test]
     fn dashboard_mode_forces_bar_bottom() {
         let raw = r#"{"schema_version":1,"line1":"","line2":"","bar":88,"bar_line1":true,"mode":"dashboard"}"#;
@@ -1051,6 +1990,15 @@ mod tests {
         assert_eq!(frame.line2, "");
     }
 
+    #[test]
+    fn bigdigits_mode_takes_the_top_two_rows() {
+        let raw = r#"{"schema_version":1,"line1":"12:34","line2":"ignored","line3":"kept","mode":"bigdigits"}"#;
+        let frame = parse(raw);
+        assert_eq!(frame.line2, "");
+        assert_eq!(frame.line3, "kept");
+        assert!(!frame.scroll_enabled);
+    }
+
     #[test]
     fn icons_parse_and_ignore_unknown() {
         let raw = r#"{"schema_version":1,"line1":"","line2":"","icons":["battery","unknown","heart","ARROW"]}"#;
@@ -1058,6 +2006,36 @@ mod tests {
         assert_eq!(frame.icons, vec![Icon::Battery, Icon::Heart, Icon::Arrow]);
     }
 
+    #[test]
+    fn custom_glyphs_back_icons_and_inline_placeholders() {
+        let raw = r#"{"schema_version":1,"line1":"{glyph:fan} 1200rpm","line2":"",
+            "icons":["fan","battery"],"custom_glyphs":{"fan":[0,14,21,31,21,14,0,0]}}"#;
+        let frame = parse(raw);
+        assert_eq!(frame.icons, vec![Icon::Battery]);
+        assert_eq!(frame.custom_icons, vec![0]);
+        assert_eq!(frame.line1.chars().count(), " 1200rpm".len() + 1);
+        let payload = frame.to_payload();
+        assert_eq!(payload.line1, "{glyph:fan} 1200rpm");
+        assert_eq!(
+            payload.icons,
+            Some(vec!["battery".to_string(), "fan".to_string()])
+        );
+        assert_eq!(
+            RenderFrame::from_payload_json(&serde_json::to_string(&payload).unwrap()).unwrap(),
+            frame
+        );
+
+        // Placeholders count as one cell against the line limit.
+        let line = format!("{}{{glyph:fan}}", "A".repeat(39));
+        let raw = format!(
+            r#"{{"schema_version":1,"line1":"{line}","line2":"","custom_glyphs":{{"fan":[0,0,0,0,0,0,0,0]}}}}"#
+        );
+        assert!(RenderFrame::from_payload_json(&raw).is_ok());
+        let bad = r#"{"schema_version":1,"line1":"","line2":"","custom_glyphs":{"fan":[32,0,0,0,0,0,0,0]}}"#;
+        let err = RenderFrame::from_payload_json(bad).unwrap_err();
+        assert!(format!("{err}").contains("custom_glyphs"));
+    }
+
     #[test]
     fn config_reload_flag_can_enable() {
         let raw_true = r#"{"schema_version":1,"line1":"","line2":"","config_reload":true}"#;
@@ -1165,6 +2143,8 @@ mod tests {
             request_id: 7,
             cmd: "uptime".into(),
             scratch_path: Some(format!("{}/tunnel/req7", crate::CACHE_DIR)),
+            otp: None,
+            sig: None,
         };
         let encoded = encode_command_frame(&msg).unwrap();
         let decoded = decode_command_frame(&encoded).unwrap();
@@ -1180,6 +2160,8 @@ mod tests {
             request_id: 7,
             cmd: "uptime".into(),
             scratch_path: Some(format!("{}/tunnel/req7", crate::CACHE_DIR)),
+            otp: None,
+            sig: None,
         };
         let encoded = encode_command_frame(&msg).unwrap();
         let mut value: serde_json::Value = serde_json::from_str(&encoded).unwrap();
@@ -1197,6 +2179,8 @@ mod tests {
             request_id: 7,
             cmd: "uptime".into(),
             scratch_path: Some(format!("{}/tunnel/req7", crate::CACHE_DIR)),
+            otp: None,
+            sig: None,
         };
         let encoded = encode_command_frame(&msg).unwrap();
         let mut value: serde_json::Value = serde_json::from_str(&encoded).unwrap();
@@ -1217,6 +2201,8 @@ mod tests {
             request_id: 7,
             cmd: "uptime".into(),
             scratch_path: Some(format!("{}/tunnel/req7", crate::CACHE_DIR)),
+            otp: None,
+            sig: None,
         };
         let encoded = encode_command_frame(&msg).unwrap();
         let mut value: serde_json::Value = serde_json::from_str(&encoded).unwrap();
@@ -1247,6 +2233,8 @@ mod tests {
             request_id: 9,
             cmd: "whoami".into(),
             scratch_path: Some("/tmp/out".into()),
+            otp: None,
+            sig: None,
         };
         let bytes = serde_json::to_vec(&msg).unwrap();
         let mut hasher = Hasher::new();
@@ -1270,6 +2258,8 @@ mod tests {
             request_id: 10,
             cmd: "   ".into(),
             scratch_path: Some(format!("{}/tunnel/req10", crate::CACHE_DIR)),
+            otp: None,
+            sig: None,
         };
         let bytes = serde_json::to_vec(&msg).unwrap();
         let mut hasher = Hasher::new();
@@ -1293,6 +2283,8 @@ mod tests {
             request_id: 1,
             cmd: "whoami".into(),
             scratch_path: Some("/tmp/out".into()),
+            otp: None,
+            sig: None,
         };
         let err = encode_command_frame(&msg).unwrap_err();
         assert!(format!("{err}").contains("scratch_path"));
@@ -1306,6 +2298,8 @@ mod tests {
             request_id: 2,
             cmd,
             scratch_path: None,
+            otp: None,
+            sig: None,
         };
         let err = encode_command_frame(&msg).unwrap_err();
         assert!(format!("{err}").contains("command length"));
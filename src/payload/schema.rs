@@ -8,23 +8,233 @@ pub const TUNNEL_MAX_FRAME_BYTES: usize = 4096;
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum TunnelMsg<'a> {
-    CmdRequest { cmd: Cow<'a, str> },
-    Stdout { chunk: Cow<'a, [u8]> },
-    Stderr { chunk: Cow<'a, [u8]> },
-    Exit { code: i32 },
+    CmdRequest {
+        cmd: Cow<'a, str>,
+        /// Code for the `totp` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<Cow<'a, str>>,
+        /// Signature for the `hmac` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<Cow<'a, str>>,
+        /// Keep the result this long and answer repeats of `cmd` from it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_ttl_ms: Option<u64>,
+        /// Run even when a cached result exists.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        no_cache: bool,
+    },
+    Stdout {
+        chunk: Cow<'a, [u8]>,
+    },
+    Stderr {
+        chunk: Cow<'a, [u8]>,
+    },
+    Exit {
+        code: i32,
+    },
     Busy,
     Heartbeat,
+    TailFile {
+        path: Cow<'a, str>,
+        lines: u16,
+        follow: bool,
+        /// Code for the `totp` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<Cow<'a, str>>,
+        /// Signature for the `hmac` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<Cow<'a, str>>,
+    },
+    TailStop,
+    DisplayTest,
+    DisplayTestResult {
+        ok: bool,
+        elapsed_ms: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<Cow<'a, str>>,
+    },
+    FilePush {
+        path: Cow<'a, str>,
+        size: u64,
+        crc32: u32,
+        /// Code for the `totp` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<Cow<'a, str>>,
+        /// Signature for the `hmac` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<Cow<'a, str>>,
+    },
+    FilePull {
+        path: Cow<'a, str>,
+        /// Code for the `totp` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<Cow<'a, str>>,
+        /// Signature for the `hmac` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<Cow<'a, str>>,
+    },
+    FileInfo {
+        size: u64,
+        crc32: u32,
+    },
+    FileResume {
+        offset: u64,
+    },
+    FileChunk {
+        offset: u64,
+        data: Cow<'a, str>,
+        crc32: u32,
+    },
+    FileAck {
+        offset: u64,
+    },
+    FileEnd {
+        ok: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<Cow<'a, str>>,
+    },
+    TermSize {
+        cols: u16,
+        rows: u16,
+    },
+    ConfigGet {
+        key: Cow<'a, str>,
+    },
+    ConfigSet {
+        key: Cow<'a, str>,
+        value: Cow<'a, str>,
+        /// Code for the `totp` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<Cow<'a, str>>,
+        /// Signature for the `hmac` `[tunnel_auth]` backend.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<Cow<'a, str>>,
+    },
+    ConfigValue {
+        key: Cow<'a, str>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<Cow<'a, str>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<Cow<'a, str>>,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum TunnelMsgOwned {
-    CmdRequest { cmd: String },
-    Stdout { chunk: Vec<u8> },
-    Stderr { chunk: Vec<u8> },
-    Exit { code: i32 },
+    CmdRequest {
+        cmd: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_ttl_ms: Option<u64>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        no_cache: bool,
+    },
+    Stdout {
+        chunk: Vec<u8>,
+    },
+    Stderr {
+        chunk: Vec<u8>,
+    },
+    Exit {
+        code: i32,
+    },
     Busy,
     Heartbeat,
+    /// Send the last `lines` of an allowlisted file, then keep streaming appends when `follow`.
+    TailFile {
+        path: String,
+        lines: u16,
+        follow: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<String>,
+    },
+    TailStop,
+    /// Run the display self-test on the far end and answer with `DisplayTestResult`.
+    DisplayTest,
+    /// Outcome of a `DisplayTest`; `error` says why the panel did not accept a step.
+    DisplayTestResult {
+        ok: bool,
+        elapsed_ms: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Offer `size` bytes for `path` on the far end; `crc32` covers the whole file.
+    FilePush {
+        path: String,
+        size: u64,
+        crc32: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<String>,
+    },
+    /// Ask the far end to send `path`; it answers with `FileInfo`.
+    FilePull {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<String>,
+    },
+    /// Size and CRC-32 of a file about to be pulled.
+    FileInfo {
+        size: u64,
+        crc32: u32,
+    },
+    /// Receiver's go-ahead: chunks start at `offset`, non-zero when resuming.
+    FileResume {
+        offset: u64,
+    },
+    /// Hex `data` for `offset`, with the CRC-32 of the decoded bytes.
+    FileChunk {
+        offset: u64,
+        data: String,
+        crc32: u32,
+    },
+    /// Cumulative ack: every byte before `offset` is on disk.
+    FileAck {
+        offset: u64,
+    },
+    /// The receiver verified the file (`ok`), or either end gave up with `error`.
+    FileEnd {
+        ok: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Size of the terminal the serial shell prints to; later commands see it as
+    /// `COLUMNS`/`LINES`.
+    TermSize {
+        cols: u16,
+        rows: u16,
+    },
+    /// Read one remote-editable key from the far end's config; it answers with `ConfigValue`.
+    ConfigGet {
+        key: String,
+    },
+    /// Write one remote-editable key into the far end's config file; it answers with
+    /// `ConfigValue` carrying the value now on disk.
+    ConfigSet {
+        key: String,
+        value: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otp: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sig: Option<String>,
+    },
+    /// The far end's value for `key`, or why it refused the get or set.
+    ConfigValue {
+        key: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
 }
 
 impl<'a> TunnelMsg<'a> {
@@ -37,8 +247,18 @@ impl<'a> TunnelMsg<'a> {
 
     pub fn into_owned(self) -> TunnelMsgOwned {
         match self {
-            TunnelMsg::CmdRequest { cmd } => TunnelMsgOwned::CmdRequest {
+            TunnelMsg::CmdRequest {
+                cmd,
+                otp,
+                sig,
+                cache_ttl_ms,
+                no_cache,
+            } => TunnelMsgOwned::CmdRequest {
                 cmd: cmd.into_owned(),
+                otp: otp.map(Cow::into_owned),
+                sig: sig.map(Cow::into_owned),
+                cache_ttl_ms,
+                no_cache,
             },
             TunnelMsg::Stdout { chunk } => TunnelMsgOwned::Stdout {
                 chunk: chunk.into_owned(),
@@ -49,6 +269,84 @@ impl<'a> TunnelMsg<'a> {
             TunnelMsg::Exit { code } => TunnelMsgOwned::Exit { code },
             TunnelMsg::Busy => TunnelMsgOwned::Busy,
             TunnelMsg::Heartbeat => TunnelMsgOwned::Heartbeat,
+            TunnelMsg::TailFile {
+                path,
+                lines,
+                follow,
+                otp,
+                sig,
+            } => TunnelMsgOwned::TailFile {
+                path: path.into_owned(),
+                lines,
+                follow,
+                otp: otp.map(Cow::into_owned),
+                sig: sig.map(Cow::into_owned),
+            },
+            TunnelMsg::TailStop => TunnelMsgOwned::TailStop,
+            TunnelMsg::DisplayTest => TunnelMsgOwned::DisplayTest,
+            TunnelMsg::DisplayTestResult {
+                ok,
+                elapsed_ms,
+                error,
+            } => TunnelMsgOwned::DisplayTestResult {
+                ok,
+                elapsed_ms,
+                error: error.map(Cow::into_owned),
+            },
+            TunnelMsg::FilePush {
+                path,
+                size,
+                crc32,
+                otp,
+                sig,
+            } => TunnelMsgOwned::FilePush {
+                path: path.into_owned(),
+                size,
+                crc32,
+                otp: otp.map(Cow::into_owned),
+                sig: sig.map(Cow::into_owned),
+            },
+            TunnelMsg::FilePull { path, otp, sig } => TunnelMsgOwned::FilePull {
+                path: path.into_owned(),
+                otp: otp.map(Cow::into_owned),
+                sig: sig.map(Cow::into_owned),
+            },
+            TunnelMsg::FileInfo { size, crc32 } => TunnelMsgOwned::FileInfo { size, crc32 },
+            TunnelMsg::FileResume { offset } => TunnelMsgOwned::FileResume { offset },
+            TunnelMsg::FileChunk {
+                offset,
+                data,
+                crc32,
+            } => TunnelMsgOwned::FileChunk {
+                offset,
+                data: data.into_owned(),
+                crc32,
+            },
+            TunnelMsg::FileAck { offset } => TunnelMsgOwned::FileAck { offset },
+            TunnelMsg::FileEnd { ok, error } => TunnelMsgOwned::FileEnd {
+                ok,
+                error: error.map(Cow::into_owned),
+            },
+            TunnelMsg::TermSize { cols, rows } => TunnelMsgOwned::TermSize { cols, rows },
+            TunnelMsg::ConfigGet { key } => TunnelMsgOwned::ConfigGet {
+                key: key.into_owned(),
+            },
+            TunnelMsg::ConfigSet {
+                key,
+                value,
+                otp,
+                sig,
+            } => TunnelMsgOwned::ConfigSet {
+                key: key.into_owned(),
+                value: value.into_owned(),
+                otp: otp.map(Cow::into_owned),
+                sig: sig.map(Cow::into_owned),
+            },
+            TunnelMsg::ConfigValue { key, value, error } => TunnelMsgOwned::ConfigValue {
+                key: key.into_owned(),
+                value: value.map(Cow::into_owned),
+                error: error.map(Cow::into_owned),
+            },
         }
     }
 }
@@ -65,18 +363,27 @@ impl TunnelMsgOwned {
 #[derive(Serialize)]
 struct TunnelFrameWriter<'a> {
     msg: &'a TunnelMsgOwned,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seq: Option<u64>,
     crc32: u32,
 }
 
 #[derive(Deserialize)]
 struct TunnelFrame<'a> {
     msg: TunnelMsg<'a>,
+    #[serde(default)]
+    seq: Option<u64>,
     crc32: u32,
 }
 
 pub fn encode_tunnel_msg(msg: &TunnelMsgOwned) -> Result<String> {
+    encode_tunnel_msg_with_seq(msg, None)
+}
+
+/// Encode a tunnel frame stamped with the sender's sequence number for replay checks.
+pub fn encode_tunnel_msg_with_seq(msg: &TunnelMsgOwned, seq: Option<u64>) -> Result<String> {
     let crc32 = msg.crc32()?;
-    let frame = TunnelFrameWriter { msg, crc32 };
+    let frame = TunnelFrameWriter { msg, seq, crc32 };
     let json = serde_json::to_string(&frame).map_err(|e| Error::Parse(format!("json: {e}")))?;
     if json.len() > TUNNEL_MAX_FRAME_BYTES {
         return Err(Error::Parse(format!(
@@ -87,6 +394,11 @@ pub fn encode_tunnel_msg(msg: &TunnelMsgOwned) -> Result<String> {
 }
 
 pub fn decode_tunnel_frame(raw: &str) -> Result<TunnelMsgOwned> {
+    decode_tunnel_frame_with_seq(raw).map(|(msg, _)| msg)
+}
+
+/// Decode a tunnel frame along with its sequence number, if the sender stamped one.
+pub fn decode_tunnel_frame_with_seq(raw: &str) -> Result<(TunnelMsgOwned, Option<u64>)> {
     if raw.len() > TUNNEL_MAX_FRAME_BYTES {
         return Err(Error::Parse(format!(
             "tunnel frame exceeds {TUNNEL_MAX_FRAME_BYTES} bytes"
@@ -98,7 +410,62 @@ pub fn decode_tunnel_frame(raw: &str) -> Result<TunnelMsgOwned> {
     if computed != frame.crc32 {
         return Err(Error::ChecksumMismatch);
     }
-    Ok(frame.msg.into_owned())
+    Ok((frame.msg.into_owned(), frame.seq))
+}
+
+/// Receipt for a rendered payload frame, sent when `protocol.ack_enabled` is set. `crc32` is the
+/// CRC of the frame line exactly as the sender wrote it (see [`frame_crc32`]); `seq` counts acks
+/// from the daemon so a sender can tell when one went missing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "frame_ack")]
+pub struct FrameAck {
+    pub crc32: u32,
+    pub seq: u64,
+}
+
+/// CRC a sender matches against `FrameAck::crc32`; surrounding whitespace is ignored.
+pub fn frame_crc32(line: &str) -> u32 {
+    let mut hasher = Hasher::new();
+    hasher.update(line.trim().as_bytes());
+    hasher.finalize()
+}
+
+pub fn encode_frame_ack(ack: &FrameAck) -> Result<String> {
+    serde_json::to_string(ack).map_err(|e| Error::Parse(format!("json: {e}")))
+}
+
+/// Decode a `frame_ack` line; `None` for anything else so callers can probe every line.
+pub fn decode_frame_ack(raw: &str) -> Option<FrameAck> {
+    let trimmed = raw.trim();
+    if !trimmed.starts_with('{') || !trimmed.contains("\"frame_ack\"") {
+        return None;
+    }
+    serde_json::from_str(trimmed).ok()
+}
+
+/// Rejection of a payload frame, sent in place of a [`FrameAck`] when `protocol.ack_enabled` is
+/// set. `code` is a stable [`crate::FrameErrorCode`] name such as `E_LINE_TOO_LONG`; `error` is
+/// the message for people. `seq` shares the ack counter.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "frame_nack")]
+pub struct FrameNack {
+    pub crc32: u32,
+    pub seq: u64,
+    pub code: String,
+    pub error: String,
+}
+
+pub fn encode_frame_nack(nack: &FrameNack) -> Result<String> {
+    serde_json::to_string(nack).map_err(|e| Error::Parse(format!("json: {e}")))
+}
+
+/// Decode a `frame_nack` line; `None` for anything else so callers can probe every line.
+pub fn decode_frame_nack(raw: &str) -> Option<FrameNack> {
+    let trimmed = raw.trim();
+    if !trimmed.starts_with('{') || !trimmed.contains("\"frame_nack\"") {
+        return None;
+    }
+    serde_json::from_str(trimmed).ok()
 }
 
 #[cfg(test)]
@@ -109,6 +476,10 @@ mod tests {
     fn round_trip_encoded_cmd_request() {
         let msg = TunnelMsgOwned::CmdRequest {
             cmd: "echo hello".into(),
+            otp: None,
+            sig: None,
+            cache_ttl_ms: None,
+            no_cache: false,
         };
         let encoded = encode_tunnel_msg(&msg).unwrap();
         let decoded = decode_tunnel_frame(&encoded).unwrap();
@@ -119,6 +490,10 @@ mod tests {
     fn checksum_mismatch_is_detected() {
         let msg = TunnelMsgOwned::CmdRequest {
             cmd: "uptime".into(),
+            otp: None,
+            sig: None,
+            cache_ttl_ms: None,
+            no_cache: false,
         };
         let encoded = encode_tunnel_msg(&msg).unwrap();
         let mut value: serde_json::Value = serde_json::from_str(&encoded).unwrap();
@@ -148,6 +523,86 @@ mod tests {
         assert!(format!("{err}").contains("tunnel frame exceeds"));
     }
 
+    #[test]
+    fn seq_round_trips_and_stays_optional() {
+        let msg = TunnelMsgOwned::CmdRequest {
+            cmd: "ls".into(),
+            otp: None,
+            sig: None,
+            cache_ttl_ms: None,
+            no_cache: false,
+        };
+        let encoded = encode_tunnel_msg_with_seq(&msg, Some(7)).unwrap();
+        assert_eq!(
+            decode_tunnel_frame_with_seq(&encoded).unwrap(),
+            (msg.clone(), Some(7))
+        );
+        let bare = encode_tunnel_msg(&msg).unwrap();
+        assert!(!bare.contains("seq"));
+        assert_eq!(decode_tunnel_frame_with_seq(&bare).unwrap(), (msg, None));
+    }
+
+    #[test]
+    fn term_size_round_trips() {
+        let msg = TunnelMsgOwned::TermSize { cols: 80, rows: 24 };
+        let encoded = encode_tunnel_msg(&msg).unwrap();
+        assert!(encoded.contains(r#""type":"term_size""#));
+        assert_eq!(decode_tunnel_frame(&encoded).unwrap(), msg);
+    }
+
+    #[test]
+    fn display_test_messages_round_trip() {
+        for msg in [
+            TunnelMsgOwned::DisplayTest,
+            TunnelMsgOwned::DisplayTestResult {
+                ok: true,
+                elapsed_ms: 1_050,
+                error: None,
+            },
+            TunnelMsgOwned::DisplayTestResult {
+                ok: false,
+                elapsed_ms: 3,
+                error: Some("i2c write failed".into()),
+            },
+        ] {
+            let encoded = encode_tunnel_msg(&msg).unwrap();
+            assert_eq!(decode_tunnel_frame(&encoded).unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn frame_ack_round_trips_and_ignores_other_lines() {
+        let ack = FrameAck {
+            crc32: frame_crc32(" {\"schema_version\":1,\"line1\":\"a\",\"line2\":\"b\"}\n"),
+            seq: 3,
+        };
+        let encoded = encode_frame_ack(&ack).unwrap();
+        assert!(encoded.starts_with("{\"type\":\"frame_ack\""));
+        assert_eq!(decode_frame_ack(&encoded), Some(ack));
+        assert_eq!(
+            ack.crc32,
+            frame_crc32("{\"schema_version\":1,\"line1\":\"a\",\"line2\":\"b\"}")
+        );
+        let tunnel = encode_tunnel_msg(&TunnelMsgOwned::Heartbeat).unwrap();
+        assert_eq!(decode_frame_ack(&tunnel), None);
+        assert_eq!(decode_frame_ack("line1=frame_ack"), None);
+    }
+
+    #[test]
+    fn frame_nack_round_trips_and_is_not_an_ack() {
+        let nack = FrameNack {
+            crc32: 0x1234_abcd,
+            seq: 4,
+            code: "E_LINE_TOO_LONG".into(),
+            error: "parse error: line1 must be <= 40 chars [E_LINE_TOO_LONG]".into(),
+        };
+        let encoded = encode_frame_nack(&nack).unwrap();
+        assert!(encoded.starts_with("{\"type\":\"frame_nack\""));
+        assert_eq!(decode_frame_nack(&encoded), Some(nack));
+        assert_eq!(decode_frame_ack(&encoded), None);
+        assert_eq!(decode_frame_nack("line1=frame_nack"), None);
+    }
+
     #[test]
     fn heartbeat_round_trips_with_crc() {
         let msg = TunnelMsgOwned::Heartbeat;
@@ -155,4 +610,50 @@ mod tests {
         let decoded = decode_tunnel_frame(&encoded).unwrap();
         assert_eq!(decoded, msg);
     }
+
+    #[test]
+    fn tail_file_round_trips_with_crc() {
+        let msg = TunnelMsgOwned::TailFile {
+            path: "/var/log/syslog".into(),
+            lines: 20,
+            follow: true,
+            otp: None,
+            sig: None,
+        };
+        let encoded = encode_tunnel_msg(&msg).unwrap();
+        assert!(encoded.contains("\"type\":\"tail_file\""));
+        assert_eq!(decode_tunnel_frame(&encoded).unwrap(), msg);
+    }
+    #[test]
+    fn file_messages_round_trip() {
+        for msg in [
+            TunnelMsgOwned::FilePush {
+                path: "fw.bin".into(),
+                size: 4_096,
+                crc32: 0xdead_beef,
+                otp: Some("287082".into()),
+                sig: None,
+            },
+            TunnelMsgOwned::FilePull {
+                path: "/var/log/syslog".into(),
+                otp: None,
+                sig: Some("1700000000000.00ff".into()),
+            },
+            TunnelMsgOwned::FileInfo { size: 10, crc32: 1 },
+            TunnelMsgOwned::FileResume { offset: 320 },
+            TunnelMsgOwned::FileChunk {
+                offset: 320,
+                data: "00ff".into(),
+                crc32: 7,
+            },
+            TunnelMsgOwned::FileAck { offset: 322 },
+            TunnelMsgOwned::FileEnd {
+                ok: false,
+                error: Some("checksum mismatch".into()),
+            },
+        ] {
+            let encoded = encode_tunnel_msg(&msg).unwrap();
+            assert_eq!(decode_tunnel_frame(&encoded).unwrap(), msg);
+        }
+    }
 }
@@ -0,0 +1,371 @@
+//! Stop-and-wait ARQ for noisy links: one outbound line in flight at a time, each wrapped
+//! with a sequence number and CRC32 and retransmitted until the peer acks it.
+
+use super::cipher::SEALED_MAX_WIRE_BYTES;
+use crate::state::MAX_FRAME_BYTES;
+use crc32fast::Hasher;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// Window over which CRC failures count towards automatic activation.
+const AUTO_WINDOW: Duration = Duration::from_secs(60);
+/// Lines waiting behind the in-flight one; the oldest is dropped past this.
+const MAX_QUEUED_LINES: usize = 64;
+/// Raw line cap while ARQ is available; the envelope escapes quotes in the body.
+pub const ARQ_MAX_WIRE_BYTES: usize = MAX_FRAME_BYTES * 2 + 64;
+const ARQ_PREFIX: &str = "{\"arq\":";
+
+/// When outbound lines are wrapped in ARQ envelopes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArqMode {
+    /// Never wrap; inbound ARQ frames from the peer are still acked.
+    Off,
+    /// Switch on after sustained CRC failures.
+    #[default]
+    Auto,
+    /// Wrap from the first line whenever the peer supports it.
+    On,
+}
+
+impl FromStr for ArqMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "auto" => Ok(Self::Auto),
+            "on" => Ok(Self::On),
+            other => Err(format!("invalid arq mode '{other}', expected off|auto|on")),
+        }
+    }
+}
+
+impl fmt::Display for ArqMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArqMode::Off => write!(f, "off"),
+            ArqMode::Auto => write!(f, "auto"),
+            ArqMode::On => write!(f, "on"),
+        }
+    }
+}
+
+/// ARQ knobs carried in `SerialOptions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArqSettings {
+    pub mode: ArqMode,
+    pub ack_timeout_ms: u64,
+    pub max_retries: u8,
+    /// CRC failures within a minute that switch `auto` mode on.
+    pub auto_crc_failures: u32,
+}
+
+impl Default for ArqSettings {
+    fn default() -> Self {
+        Self {
+            mode: ArqMode::Auto,
+            ack_timeout_ms: 500,
+            max_retries: 5,
+            auto_crc_failures: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "arq", rename_all = "snake_case")]
+enum ArqFrame {
+    Data { seq: u8, body: String, crc32: u32 },
+    Ack { seq: u8 },
+}
+
+/// Counters reported at shutdown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArqStats {
+    pub sent: u64,
+    pub retransmits: u64,
+    pub acked: u64,
+    pub dropped: u64,
+    pub received: u64,
+    pub duplicates: u64,
+}
+
+/// What the transport should do with an inbound line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inbound {
+    /// Not an ARQ frame; hand it to the caller unchanged.
+    Passthrough,
+    /// A data frame: write `ack`, then deliver `body` unless it was a retransmit.
+    Deliver { ack: String, body: Option<String> },
+    /// The in-flight line was acked.
+    Acked,
+    /// Corrupt, stale, or unexpected frame; the sender's retransmit covers it.
+    Ignored,
+}
+
+#[derive(Debug)]
+struct InFlight {
+    seq: u8,
+    wire: String,
+    sent_at: Instant,
+    retries: u8,
+}
+
+/// Sender and receiver state for one connection.
+#[derive(Debug)]
+pub struct ArqLink {
+    settings: ArqSettings,
+    active: bool,
+    next_seq: u8,
+    in_flight: Option<InFlight>,
+    queue: VecDeque<String>,
+    last_rx_seq: Option<u8>,
+    crc_failures: VecDeque<Instant>,
+    stats: ArqStats,
+}
+
+impl ArqLink {
+    pub fn new(settings: ArqSettings) -> Self {
+        Self {
+            settings,
+            active: settings.mode == ArqMode::On,
+            next_seq: 0,
+            in_flight: None,
+            queue: VecDeque::new(),
+            last_rx_seq: None,
+            crc_failures: VecDeque::new(),
+            stats: ArqStats::default(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn stats(&self) -> ArqStats {
+        self.stats
+    }
+
+    /// Count a CRC failure; returns `true` when this one switches `auto` mode on.
+    pub fn note_crc_failure(&mut self, now: Instant) -> bool {
+        if self.active || self.settings.mode != ArqMode::Auto {
+            return false;
+        }
+        self.crc_failures.push_back(now);
+        while self
+            .crc_failures
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > AUTO_WINDOW)
+        {
+            self.crc_failures.pop_front();
+        }
+        if self.crc_failures.len() >= self.settings.auto_crc_failures.max(1) as usize {
+            self.active = true;
+            self.crc_failures.clear();
+            return true;
+        }
+        false
+    }
+
+    /// Line to write now for `line`, or `None` when it was queued behind the in-flight one.
+    pub fn outbound(&mut self, line: &str, now: Instant) -> Option<String> {
+        if !self.active {
+            return Some(line.to_string());
+        }
+        if self.in_flight.is_some() {
+            if self.queue.len() >= MAX_QUEUED_LINES {
+                self.queue.pop_front();
+                self.stats.dropped += 1;
+            }
+            self.queue.push_back(line.to_string());
+            return None;
+        }
+        Some(self.transmit(line.to_string(), now))
+    }
+
+    /// Retransmit an overdue line, or give up on it and move to the next queued one.
+    pub fn poll(&mut self, now: Instant) -> Option<String> {
+        let timeout = Duration::from_millis(self.settings.ack_timeout_ms);
+        if let Some(in_flight) = self.in_flight.as_mut() {
+            if now.duration_since(in_flight.sent_at) < timeout {
+                return None;
+            }
+            if in_flight.retries < self.settings.max_retries {
+                in_flight.retries += 1;
+                in_flight.sent_at = now;
+                self.stats.retransmits += 1;
+                return Some(in_flight.wire.clone());
+            }
+            self.in_flight = None;
+            self.stats.dropped += 1;
+        }
+        let next = self.queue.pop_front()?;
+        Some(self.transmit(next, now))
+    }
+
+    pub fn inbound(&mut self, line: &str) -> Inbound {
+        if !line.starts_with(ARQ_PREFIX) {
+            return Inbound::Passthrough;
+        }
+        let Ok(frame) = serde_json::from_str::<ArqFrame>(line) else {
+            return Inbound::Ignored;
+        };
+        match frame {
+            ArqFrame::Ack { seq } => match self.in_flight.as_ref() {
+                Some(in_flight) if in_flight.seq == seq => {
+                    self.in_flight = None;
+                    self.stats.acked += 1;
+                    Inbound::Acked
+                }
+                _ => Inbound::Ignored,
+            },
+            ArqFrame::Data { seq, body, crc32 } => {
+                // Bodies may be sealed lines; the receiver caps the frame once it is opened.
+                if body_crc(&body) != crc32 || body.len() > SEALED_MAX_WIRE_BYTES {
+                    return Inbound::Ignored;
+                }
+                let ack = encode(&ArqFrame::Ack { seq });
+                if self.last_rx_seq == Some(seq) {
+                    // Our previous ack was lost; ack again without redelivering.
+                    self.stats.duplicates += 1;
+                    return Inbound::Deliver { ack, body: None };
+                }
+                self.last_rx_seq = Some(seq);
+                self.stats.received += 1;
+                Inbound::Deliver {
+                    ack,
+                    body: Some(body),
+                }
+            }
+        }
+    }
+
+    fn transmit(&mut self, body: String, now: Instant) -> String {
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        let wire = encode(&ArqFrame::Data {
+            seq,
+            crc32: body_crc(&body),
+            body,
+        });
+        self.in_flight = Some(InFlight {
+            seq,
+            wire: wire.clone(),
+            sent_at: now,
+            retries: 0,
+        });
+        self.stats.sent += 1;
+        wire
+    }
+}
+
+fn body_crc(body: &str) -> u32 {
+    let mut hasher = Hasher::new();
+    hasher.update(body.as_bytes());
+    hasher.finalize()
+}
+
+fn encode(frame: &ArqFrame) -> String {
+    // Only strings and integers; serialization cannot fail.
+    serde_json::to_string(frame).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(mode: ArqMode) -> ArqSettings {
+        ArqSettings {
+            mode,
+            ack_timeout_ms: 100,
+            max_retries: 2,
+            auto_crc_failures: 3,
+        }
+    }
+
+    fn ack_for(wire: &str) -> String {
+        let mut peer = ArqLink::new(settings(ArqMode::Off));
+        match peer.inbound(wire) {
+            Inbound::Deliver { ack, .. } => ack,
+            other => panic!("expected data frame, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn inactive_link_passes_lines_through() {
+        let mut link = ArqLink::new(settings(ArqMode::Auto));
+        let now = Instant::now();
+        assert_eq!(link.outbound("INIT", now).as_deref(), Some("INIT"));
+        assert_eq!(link.inbound("{\"line1\":\"hi\"}"), Inbound::Passthrough);
+    }
+
+    #[test]
+    fn waits_for_ack_before_sending_next_line() {
+        let mut link = ArqLink::new(settings(ArqMode::On));
+        let now = Instant::now();
+        let first = link.outbound("one", now).unwrap();
+        assert!(first.starts_with(ARQ_PREFIX));
+        assert!(link.outbound("two", now).is_none());
+        assert!(link.poll(now).is_none());
+
+        assert_eq!(link.inbound(&ack_for(&first)), Inbound::Acked);
+        let second = link.poll(now).unwrap();
+        let mut peer = ArqLink::new(settings(ArqMode::Off));
+        assert!(matches!(
+            peer.inbound(&second),
+            Inbound::Deliver { body: Some(body), .. } if body == "two"
+        ));
+    }
+
+    #[test]
+    fn retransmits_then_drops_after_retry_limit() {
+        let mut link = ArqLink::new(settings(ArqMode::On));
+        let start = Instant::now();
+        let wire = link.outbound("one", start).unwrap();
+        let step = Duration::from_millis(100);
+        assert_eq!(link.poll(start + step).as_deref(), Some(wire.as_str()));
+        assert_eq!(link.poll(start + step * 2).as_deref(), Some(wire.as_str()));
+        assert!(link.poll(start + step * 3).is_none());
+        let stats = link.stats();
+        assert_eq!((stats.retransmits, stats.dropped), (2, 1));
+    }
+
+    #[test]
+    fn duplicate_data_is_acked_but_not_redelivered() {
+        let mut sender = ArqLink::new(settings(ArqMode::On));
+        let wire = sender.outbound("payload", Instant::now()).unwrap();
+        let mut receiver = ArqLink::new(settings(ArqMode::Off));
+        assert!(matches!(
+            receiver.inbound(&wire),
+            Inbound::Deliver { body: Some(_), .. }
+        ));
+        assert!(matches!(
+            receiver.inbound(&wire),
+            Inbound::Deliver { body: None, .. }
+        ));
+        let corrupt = wire.replace("payload", "paylaod");
+        let mut fresh = ArqLink::new(settings(ArqMode::Off));
+        assert_eq!(fresh.inbound(&corrupt), Inbound::Ignored);
+    }
+
+    #[test]
+    fn auto_mode_activates_after_sustained_crc_failures() {
+        let mut link = ArqLink::new(settings(ArqMode::Auto));
+        let now = Instant::now();
+        assert!(!link.note_crc_failure(now));
+        assert!(!link.note_crc_failure(now + AUTO_WINDOW * 2));
+        assert!(!link.note_crc_failure(now + AUTO_WINDOW * 2));
+        assert!(link.note_crc_failure(now + AUTO_WINDOW * 2));
+        assert!(link.is_active());
+
+        let mut off = ArqLink::new(settings(ArqMode::Off));
+        for _ in 0..10 {
+            assert!(!off.note_crc_failure(now));
+        }
+        assert!(!off.is_active());
+    }
+}
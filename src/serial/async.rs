@@ -6,7 +6,7 @@ use crate::{
     Error, Result,
 };
 use std::{io, time::Duration};
-use tokio_serial::{DataBits, FlowControl, Parity, SerialPortBuilderExt, StopBits};
+use tokio_serial::{DataBits, FlowControl, Parity, SerialPort, SerialPortBuilderExt, StopBits};
 
 pub async fn connect(device: &str, options: SerialOptions) -> Result<tokio_serial::SerialStream> {
     if device.is_empty() {
@@ -27,7 +27,8 @@ pub async fn connect(device: &str, options: SerialOptions) -> Result<tokio_seria
         .map_err(|e| Error::Io(io::Error::from(e)))?;
 
     if let Some(level) = desired_dtr(options.dtr) {
-        port.set_data_terminal_ready(level).map_err(Error::Io)?;
+        port.write_data_terminal_ready(level)
+            .map_err(|e| Error::Io(io::Error::from(e)))?;
     }
 
     Ok(port)
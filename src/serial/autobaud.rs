@@ -0,0 +1,152 @@
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+use super::{SerialOptions, SerialPort};
+use crate::Result;
+
+/// Rates tried by the listen-only probe, slowest first.
+pub const CANDIDATE_BAUDS: [u32; 5] = [9_600, 19_200, 38_400, 57_600, 115_200];
+/// How long each candidate rate is sampled.
+pub const DEFAULT_SAMPLE_WINDOW_MS: u64 = 1_500;
+/// Samples shorter than this are too noisy to score.
+pub const MIN_SAMPLE_BYTES: usize = 8;
+/// Minimum printable ratio before a rate is suggested.
+pub const MIN_CONFIDENT_SCORE: f64 = 0.9;
+const MAX_SAMPLE_BYTES: usize = 512;
+const READ_TIMEOUT_MS: u64 = 100;
+
+/// Result of sampling the line at a single candidate rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaudScore {
+    pub baud: u32,
+    /// Bytes captured during the sample window.
+    pub bytes: usize,
+    /// Fraction of bytes that are printable ASCII or line endings (0.0–1.0).
+    pub score: f64,
+    /// Runs of NUL bytes, which is how a break (or a badly mismatched rate) reads back.
+    pub breaks: usize,
+    /// Sample contained a `{"` sequence, i.e. looks like a JSON payload.
+    pub json_hint: bool,
+}
+
+/// Score a raw byte sample captured at `baud`.
+pub fn score_sample(baud: u32, sample: &[u8]) -> BaudScore {
+    let printable = sample
+        .iter()
+        .filter(|b| matches!(b, 0x20..=0x7e | b'\r' | b'\n' | b'\t'))
+        .count();
+    let mut breaks = 0;
+    let mut in_break = false;
+    for &byte in sample {
+        if byte == 0 {
+            if !in_break {
+                breaks += 1;
+            }
+            in_break = true;
+        } else {
+            in_break = false;
+        }
+    }
+    let score = if sample.is_empty() {
+        0.0
+    } else {
+        printable as f64 / sample.len() as f64
+    };
+    BaudScore {
+        baud,
+        bytes: sample.len(),
+        score,
+        breaks,
+        json_hint: sample.windows(2).any(|pair| pair == b"{\""),
+    }
+}
+
+/// Pick the most plausible rate, or `None` when no candidate is confidently readable.
+pub fn best_guess(scores: &[BaudScore]) -> Option<BaudScore> {
+    scores
+        .iter()
+        .filter(|s| s.bytes >= MIN_SAMPLE_BYTES && s.score >= MIN_CONFIDENT_SCORE)
+        .max_by(|a, b| {
+            a.score
+                .total_cmp(&b.score)
+                .then(a.json_hint.cmp(&b.json_hint))
+                .then(b.breaks.cmp(&a.breaks))
+        })
+        .copied()
+}
+
+/// Listen at each candidate rate without transmitting and score what arrives.
+pub fn probe(
+    device: &str,
+    options: SerialOptions,
+    candidates: &[u32],
+    window_ms: u64,
+) -> Result<Vec<BaudScore>> {
+    let mut scores = Vec::with_capacity(candidates.len());
+    for &baud in candidates {
+        let mut port = SerialPort::connect(
+            device,
+            SerialOptions {
+                baud,
+                timeout_ms: READ_TIMEOUT_MS,
+                ..options
+            },
+        )?;
+        let sample = capture(&mut port, Duration::from_millis(window_ms))?;
+        scores.push(score_sample(baud, &sample));
+    }
+    Ok(scores)
+}
+
+fn capture(port: &mut SerialPort, window: Duration) -> Result<Vec<u8>> {
+    let mut reader = port.borrow_reader()?;
+    let deadline = Instant::now() + window;
+    let mut sample = Vec::with_capacity(MAX_SAMPLE_BYTES);
+    let mut buf = [0u8; 64];
+    while Instant::now() < deadline && sample.len() < MAX_SAMPLE_BYTES {
+        match reader.read(&mut buf) {
+            Ok(0) => {}
+            Ok(read) => sample.extend_from_slice(&buf[..read]),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    sample.truncate(MAX_SAMPLE_BYTES);
+    Ok(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_json_scores_high() {
+        let score = score_sample(9_600, br#"{"schema_version":1,"line1":"hi"}"#);
+        assert_eq!(score.score, 1.0);
+        assert!(score.json_hint);
+        assert_eq!(score.breaks, 0);
+    }
+
+    #[test]
+    fn garbage_and_breaks_score_low() {
+        let score = score_sample(115_200, &[0x00, 0x00, 0xf8, 0x80, 0x00, 0xfe, b'a', 0x00]);
+        assert!(score.score < 0.5);
+        assert_eq!(score.breaks, 3);
+    }
+
+    #[test]
+    fn best_guess_prefers_readable_rate() {
+        let scores = [
+            score_sample(9_600, &[0xf0; 32]),
+            score_sample(19_200, b"line1=Hello line2=World\n"),
+            score_sample(38_400, b"ab"),
+        ];
+        assert_eq!(best_guess(&scores).map(|s| s.baud), Some(19_200));
+    }
+
+    #[test]
+    fn best_guess_none_when_unreadable() {
+        let scores = [score_sample(9_600, &[0xf0; 32]), score_sample(19_200, b"")];
+        assert!(best_guess(&scores).is_none());
+    }
+}
@@ -37,6 +37,11 @@ impl BackoffController {
         now >= self.next_retry_at
     }
 
+    /// Time left before the next reconnect attempt is due.
+    pub fn until_retry(&self, now: Instant) -> Duration {
+        self.next_retry_at.saturating_duration_since(now)
+    }
+
     pub fn update(&mut self, initial_ms: u64, max_ms: u64) {
         let initial = Duration::from_millis(initial_ms.max(1));
         let max = Duration::from_millis(max_ms.max(initial_ms.max(1)));
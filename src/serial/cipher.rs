@@ -0,0 +1,187 @@
+//! Link encryption: ChaCha20-Poly1305 (RFC 8439) over whole lines once both peers have a
+//! pre-shared key.
+//!
+//! Each direction has its own session key, derived during the handshake, and a line counter
+//! that doubles as the nonce. A sealed line looks like `{"enc":7,"ct":"<base64>"}`, where `ct`
+//! is the ciphertext followed by the 16-byte tag. Counters only move forward, so a recorded
+//! line replayed later is rejected, and a tampered or foreign line fails the tag check.
+
+use crate::{state::MAX_FRAME_BYTES, Error, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use serde::Deserialize;
+
+const SEALED_PREFIX: &str = "{\"enc\":";
+const TAG_LEN: usize = 16;
+/// Raw line cap for a sealed line: a full frame plus tag in base64, and the envelope.
+pub const SEALED_MAX_WIRE_BYTES: usize = (MAX_FRAME_BYTES + TAG_LEN).div_ceil(3) * 4 + 48;
+
+/// Per-direction session keys agreed during the handshake.
+#[derive(Clone, PartialEq, Eq)]
+pub struct LinkKeys {
+    pub send: [u8; 32],
+    pub recv: [u8; 32],
+}
+
+impl std::fmt::Debug for LinkKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LinkKeys(..)")
+    }
+}
+
+#[derive(Deserialize)]
+struct Sealed {
+    enc: u64,
+    ct: String,
+}
+
+/// Seals outgoing lines and opens incoming ones for one session.
+#[derive(Debug)]
+pub struct LinkCipher {
+    keys: LinkKeys,
+    next_send: u64,
+    /// Counter of the last line opened; anything at or below it is a replay.
+    last_recv: Option<u64>,
+}
+
+impl LinkCipher {
+    pub fn new(keys: LinkKeys) -> Self {
+        Self {
+            keys,
+            next_send: 0,
+            last_recv: None,
+        }
+    }
+
+    pub fn seal(&mut self, line: &str) -> String {
+        let counter = self.next_send;
+        self.next_send += 1;
+        let sealed = ChaCha20Poly1305::new(Key::from_slice(&self.keys.send))
+            .encrypt(&nonce(counter), line.as_bytes())
+            .expect("ChaCha20-Poly1305 sealing only fails past 256 GiB");
+        format!(
+            "{SEALED_PREFIX}{counter},\"ct\":\"{}\"}}",
+            BASE64.encode(sealed)
+        )
+    }
+
+    /// The plaintext of a sealed line. Plaintext lines, failed tags and replays are errors.
+    pub fn open(&mut self, line: &str) -> Result<String> {
+        if !line.starts_with(SEALED_PREFIX) {
+            return Err(Error::Parse(
+                "unencrypted frame dropped while link encryption is on".into(),
+            ));
+        }
+        let sealed: Sealed = serde_json::from_str(line)
+            .map_err(|err| Error::Parse(format!("malformed sealed frame: {err}")))?;
+        if self.last_recv.is_some_and(|last| sealed.enc <= last) {
+            return Err(Error::Parse(format!(
+                "replayed sealed frame {} dropped",
+                sealed.enc
+            )));
+        }
+        let plaintext = BASE64
+            .decode(&sealed.ct)
+            .ok()
+            .and_then(|data| {
+                ChaCha20Poly1305::new(Key::from_slice(&self.keys.recv))
+                    .decrypt(&nonce(sealed.enc), data.as_slice())
+                    .ok()
+            })
+            .ok_or_else(|| Error::Parse("sealed frame failed authentication".into()))?;
+        if plaintext.len() > MAX_FRAME_BYTES {
+            return Err(Error::Parse(format!(
+                "frame exceeds {MAX_FRAME_BYTES} bytes"
+            )));
+        }
+        self.last_recv = Some(sealed.enc);
+        String::from_utf8(plaintext).map_err(|_| Error::Parse("sealed frame is not UTF-8".into()))
+    }
+}
+
+/// 96-bit nonce for line `counter`; keys are never reused across sessions, so it cannot repeat.
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[4..].copy_from_slice(&counter.to_le_bytes());
+    nonce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|idx| u8::from_str_radix(&text[idx..idx + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn matches_rfc8439_vectors() {
+        use chacha20poly1305::aead::Payload;
+
+        let key: [u8; 32] = (0x80..=0x9f).collect::<Vec<u8>>().try_into().unwrap();
+        let nonce = unhex("070000004041424344454647");
+        let aad = unhex("50515253c0c1c2c3c4c5c6c7");
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+        let payload = Payload {
+            msg: plaintext,
+            aad: &aad,
+        };
+        let sealed = cipher.encrypt(Nonce::from_slice(&nonce), payload).unwrap();
+        assert_eq!(sealed[..16], unhex("d31a8d34648e60db7b86afbc53ef7ec2"));
+        assert_eq!(
+            sealed[sealed.len() - TAG_LEN..],
+            unhex("1ae10b594f09e26a7e902ecbd0600691")
+        );
+    }
+
+    #[test]
+    fn sealed_lines_round_trip_and_reject_tampering_and_replays() {
+        let keys = LinkKeys {
+            send: [1; 32],
+            recv: [2; 32],
+        };
+        let mut ours = LinkCipher::new(keys.clone());
+        let mut theirs = LinkCipher::new(LinkKeys {
+            send: keys.recv,
+            recv: keys.send,
+        });
+        let first = ours.seal(r#"{"schema_version":1,"line1":"hi","line2":""}"#);
+        assert!(first.starts_with(SEALED_PREFIX));
+        assert!(!first.contains("line1"));
+        let second = ours.seal("x");
+        assert_eq!(theirs.open(&second).unwrap(), "x");
+        // The first line arriving after a later one counts as a replay.
+        assert!(theirs.open(&first).is_err());
+        assert!(theirs.open(&second).is_err());
+
+        let tampered = ours.seal("reboot").replace("\"enc\":2", "\"enc\":9");
+        assert!(theirs.open(&tampered).is_err());
+        assert!(theirs.open("{\"schema_version\":1}").is_err());
+        assert_eq!(theirs.open(&ours.seal("")).unwrap(), "");
+
+        let full = "x".repeat(MAX_FRAME_BYTES);
+        assert!(ours.seal(&full).len() <= SEALED_MAX_WIRE_BYTES);
+    }
+
+    #[test]
+    fn sealed_lines_carry_padded_standard_base64() {
+        let mut cipher = LinkCipher::new(LinkKeys {
+            send: [3; 32],
+            recv: [3; 32],
+        });
+        // An empty line seals to the bare 16-byte tag: 24 base64 characters with padding.
+        let sealed: Sealed = serde_json::from_str(&cipher.seal("")).unwrap();
+        assert_eq!(sealed.enc, 0);
+        assert_eq!(sealed.ct.len(), 24);
+        assert!(sealed.ct.ends_with("=="));
+        let unpadded = cipher.seal("").replace("==\"", "\"");
+        assert!(cipher.open(&unpadded).is_err());
+    }
+}
@@ -1,10 +1,10 @@
 use crate::Error;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::io::ErrorKind;
 
 /// High-level reason for a serial transport failure.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SerialFailureKind {
     PermissionDenied,
@@ -44,6 +44,7 @@ pub fn classify_error(err: &Error) -> SerialFailureKind {
         Error::InvalidArgs(_) => SerialFailureKind::Config,
         Error::Io(io_err) => classify_io_error(io_err),
         Error::Parse(_) | Error::ChecksumMismatch => SerialFailureKind::Framing,
+        Error::Exit { source, .. } => classify_error(source),
     }
 }
 
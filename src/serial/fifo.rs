@@ -0,0 +1,294 @@
+//! Named pipe stand-in for the UART: `--input fifo:<path>` reads payload lines from a FIFO on
+//! the same host, so a Pi that produces its own stats can feed the LCD without a serial hop.
+//!
+//! [`FifoPort`] implements `serialport::SerialPort`, which lets [`super::SerialPort`] and the
+//! whole render loop run unchanged on top of it. The FIFO is opened read-write so the daemon
+//! itself always holds a writer: producers can come and go without the read side seeing EOF.
+//! Nothing answers on a FIFO, so lines the daemon sends (INIT, heartbeats, acks) are dropped.
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, StopBits};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, RawFd};
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::time::Duration;
+
+use rustix::event::{poll, PollFd, PollFlags, Timespec};
+use rustix::fs::{mkfifoat, Mode, CWD};
+
+use crate::{Error, Result};
+
+/// Device prefix that selects a FIFO instead of a serial device.
+pub const FIFO_PREFIX: &str = "fifo:";
+const READ_CHUNK: usize = 512;
+
+/// The FIFO path when `device` names one.
+pub fn fifo_path(device: &str) -> Option<&str> {
+    device.strip_prefix(FIFO_PREFIX)
+}
+
+#[derive(Debug)]
+pub struct FifoPort {
+    path: String,
+    file: File,
+    timeout: Duration,
+    baud: u32,
+    buffer: Vec<u8>,
+    pos: usize,
+}
+
+impl FifoPort {
+    /// Open `path`, creating the FIFO (mode 0660) when nothing is there yet.
+    pub fn open(path: &str, timeout: Duration, baud: u32) -> Result<Self> {
+        if path.is_empty() {
+            return Err(Error::InvalidArgs("fifo path cannot be empty".into()));
+        }
+        match std::fs::metadata(path) {
+            Ok(meta) if meta.file_type().is_fifo() => {}
+            Ok(_) => {
+                return Err(Error::InvalidArgs(format!(
+                    "{path} exists but is not a FIFO"
+                )));
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                if let Some(parent) = Path::new(path).parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                mkfifoat(CWD, path, Mode::from_raw_mode(0o660)).map_err(io::Error::from)?;
+            }
+            Err(err) => return Err(Error::Io(err)),
+        }
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Self {
+            path: path.to_string(),
+            file,
+            timeout,
+            baud,
+            buffer: Vec::with_capacity(READ_CHUNK),
+            pos: 0,
+        })
+    }
+
+    fn buffered(&self) -> usize {
+        self.buffer.len() - self.pos
+    }
+
+    /// Wait up to the read timeout for the FIFO to turn readable.
+    fn wait_readable(&self) -> io::Result<bool> {
+        let timeout = Timespec::try_from(self.timeout).unwrap_or(Timespec {
+            tv_sec: i64::MAX,
+            tv_nsec: 0,
+        });
+        let mut fds = [PollFd::new(&self.file, PollFlags::IN)];
+        loop {
+            match poll(&mut fds, Some(&timeout)) {
+                Ok(ready) => return Ok(ready > 0),
+                Err(rustix::io::Errno::INTR) => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+impl Read for FifoPort {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.buffered() == 0 {
+            if !self.wait_readable()? {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "fifo read timed out",
+                ));
+            }
+            self.buffer.resize(READ_CHUNK, 0);
+            let read = self.file.read(&mut self.buffer)?;
+            self.buffer.truncate(read);
+            self.pos = 0;
+        }
+        let count = out.len().min(self.buffered());
+        out[..count].copy_from_slice(&self.buffer[self.pos..self.pos + count]);
+        self.pos += count;
+        Ok(count)
+    }
+}
+
+impl Write for FifoPort {
+    /// Replies have nowhere to go, and writing them into the FIFO would read them back.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRawFd for FifoPort {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_fd().as_raw_fd()
+    }
+}
+
+impl serialport::SerialPort for FifoPort {
+    fn name(&self) -> Option<String> {
+        Some(self.path.clone())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.baud)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Line settings mean nothing on a pipe; they are accepted so burst mode and reloads work.
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.baud = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    /// A pipe has no modem lines; report them up so the modem monitor stays quiet.
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.buffered() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, _buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn serialport::SerialPort>> {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Unknown,
+            "fifo input cannot be cloned",
+        ))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn fifo_path_needs_the_prefix() {
+        assert_eq!(fifo_path("fifo:/run/x.fifo"), Some("/run/x.fifo"));
+        assert_eq!(fifo_path("/dev/ttyUSB0"), None);
+    }
+
+    #[test]
+    fn creates_the_fifo_and_reads_what_producers_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frames.fifo");
+        let path = path.to_str().unwrap();
+        let mut port = FifoPort::open(path, Duration::from_millis(20), 9600).unwrap();
+        assert!(std::fs::metadata(path).unwrap().file_type().is_fifo());
+
+        // A producer that opens, writes and closes does not end the stream.
+        for line in ["{\"line1\":\"a\"}\n", "{\"line1\":\"b\"}\n"] {
+            let mut producer = OpenOptions::new().write(true).open(path).unwrap();
+            producer.write_all(line.as_bytes()).unwrap();
+        }
+        let mut seen = Vec::new();
+        let mut byte = [0u8; 1];
+        while seen.len() < 28 {
+            assert_eq!(port.read(&mut byte).unwrap(), 1);
+            seen.push(byte[0]);
+        }
+        assert_eq!(seen, b"{\"line1\":\"a\"}\n{\"line1\":\"b\"}\n");
+
+        let started = Instant::now();
+        let err = port.read(&mut byte).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() >= Duration::from_millis(20));
+
+        // Writes are swallowed rather than looped back into the read side.
+        port.write_all(b"INIT\n").unwrap();
+        assert_eq!(
+            port.read(&mut byte).unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+    }
+
+    #[test]
+    fn refuses_a_regular_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frames.json");
+        std::fs::write(&path, "{}").unwrap();
+        let err = FifoPort::open(path.to_str().unwrap(), Duration::ZERO, 9600).unwrap_err();
+        assert!(format!("{err}").contains("not a FIFO"));
+    }
+}
@@ -1,10 +1,19 @@
+pub mod arq;
 #[cfg(feature = "async-serial")]
 pub mod r#async;
+pub mod autobaud;
 pub mod backoff;
+pub mod cipher;
 pub mod errors;
 pub mod fake;
+#[cfg(unix)]
+pub mod fifo;
+pub mod shaper;
 pub mod sync;
+#[cfg(unix)]
+pub mod tcp;
 pub mod telemetry;
+pub mod tuning;
 
 use std::{fmt, str::FromStr};
 
@@ -157,6 +166,16 @@ pub struct SerialOptions {
     pub parity: ParityMode,
     pub stop_bits: StopBitsMode,
     pub dtr: DtrBehavior,
+    /// VMIN: bytes a read waits for once data starts arriving.
+    pub read_min_bytes: u8,
+    /// VTIME: inter-byte gap (tenths of a second) that ends a read early.
+    pub read_gap_ds: u8,
+    /// Shorten the USB adapter's latency timer for interactive sessions.
+    pub low_latency: bool,
+    /// Stop-and-wait ARQ behaviour once the peer advertises support.
+    pub arq: arq::ArqSettings,
+    /// Outbound cap as a percentage of the line's theoretical byte rate; 0 disables it.
+    pub tx_rate_percent: u8,
 }
 
 impl SerialOptions {
@@ -177,10 +196,58 @@ impl Default for SerialOptions {
             parity: ParityMode::None,
             stop_bits: StopBitsMode::One,
             dtr: DtrBehavior::Preserve,
+            read_min_bytes: tuning::DEFAULT_READ_MIN_BYTES,
+            read_gap_ds: tuning::DEFAULT_READ_GAP_DS,
+            low_latency: false,
+            arq: arq::ArqSettings::default(),
+            tx_rate_percent: 0,
         }
     }
 }
 
+/// Snapshot of the RS-232 modem status inputs sampled from an open port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ModemLines {
+    /// Clear To Send.
+    pub cts: bool,
+    /// Data Set Ready.
+    pub dsr: bool,
+    /// Carrier Detect.
+    pub cd: bool,
+    /// Ring Indicator.
+    pub ri: bool,
+}
+
+impl ModemLines {
+    /// Single ASCII marker for the LCD: `None` when CTS/DSR/CD are all asserted, otherwise
+    /// `c` (CTS low), `s` (DSR low) or `d` (CD low), in that priority order.
+    pub fn glyph(&self) -> Option<char> {
+        if !self.cts {
+            Some('c')
+        } else if !self.dsr {
+            Some('s')
+        } else if !self.cd {
+            Some('d')
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ModemLines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |on: bool| if on { "on" } else { "off" };
+        write!(
+            f,
+            "cts={} dsr={} cd={} ri={}",
+            flag(self.cts),
+            flag(self.dsr),
+            flag(self.cd),
+            flag(self.ri)
+        )
+    }
+}
+
 pub use errors::{classify_error, classify_io_error, SerialFailureKind};
 pub use sync::SerialPort;
 
@@ -189,3 +256,44 @@ pub trait LineIo {
     fn send_command_line(&mut self, line: &str) -> crate::Result<()>;
     fn read_message_line(&mut self, buf: &mut String) -> crate::Result<usize>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modem_glyph_reports_first_dropped_line() {
+        let all_up = ModemLines {
+            cts: true,
+            dsr: true,
+            cd: true,
+            ri: false,
+        };
+        assert_eq!(all_up.glyph(), None);
+        assert_eq!(
+            ModemLines {
+                cts: false,
+                ..all_up
+            }
+            .glyph(),
+            Some('c')
+        );
+        assert_eq!(
+            ModemLines {
+                dsr: false,
+                ..all_up
+            }
+            .glyph(),
+            Some('s')
+        );
+        assert_eq!(
+            ModemLines {
+                cd: false,
+                ..all_up
+            }
+            .glyph(),
+            Some('d')
+        );
+        assert_eq!(all_up.to_string(), "cts=on dsr=on cd=on ri=off");
+    }
+}
@@ -0,0 +1,232 @@
+//! Outbound rate cap: a token bucket in front of the UART with deficit round-robin
+//! across channels, so a chatty tunnel cannot starve control frames or overrun the adapter.
+
+use super::{ParityMode, SerialOptions, StopBitsMode};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Bytes a channel may send per round-robin visit.
+const QUANTUM_BYTES: usize = 256;
+/// Bytes held across all channels before the oldest line of the pushing channel is dropped.
+const MAX_QUEUED_BYTES: usize = 64 * 1024;
+/// Bucket depth, as time at the capped rate.
+const BURST: Duration = Duration::from_millis(100);
+
+/// Traffic class used for fair sharing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Control,
+    Command,
+    Tunnel,
+    Data,
+}
+
+impl Channel {
+    const COUNT: usize = 4;
+
+    /// Classify a wire line with the same markers the render loop uses to route inbound frames.
+    pub fn classify(line: &str) -> Self {
+        if line.starts_with("{\"arq\":\"ack\"")
+            || line.contains("\"type\":\"hello")
+            || line == "INIT"
+        {
+            Channel::Control
+        } else if line.contains("\"channel\":\"command\"") {
+            Channel::Command
+        } else if line.contains("\"msg\"") && line.contains("\"crc32\"") {
+            Channel::Tunnel
+        } else {
+            Channel::Data
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Channel::Control => 0,
+            Channel::Command => 1,
+            Channel::Tunnel => 2,
+            Channel::Data => 3,
+        }
+    }
+}
+
+/// Counters reported at shutdown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutboxStats {
+    pub sent_bytes: u64,
+    pub delayed_lines: u64,
+    pub dropped_lines: u64,
+}
+
+/// Rate-capped outbound queue; `pop_ready` yields lines (with trailing newline) as tokens allow.
+#[derive(Debug)]
+pub struct Outbox {
+    bytes_per_sec: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+    queues: [VecDeque<Vec<u8>>; Channel::COUNT],
+    deficits: [usize; Channel::COUNT],
+    cursor: usize,
+    queued_bytes: usize,
+    stats: OutboxStats,
+}
+
+impl Outbox {
+    /// `None` when `tx_rate_percent` is 0 (uncapped).
+    pub fn for_options(options: &SerialOptions, now: Instant) -> Option<Self> {
+        if options.tx_rate_percent == 0 {
+            return None;
+        }
+        let bits_per_char = 1
+            + 8
+            + u32::from(options.parity != ParityMode::None)
+            + match options.stop_bits {
+                StopBitsMode::One => 1,
+                StopBitsMode::Two => 2,
+            };
+        let line_rate = f64::from(options.baud) / f64::from(bits_per_char);
+        Some(Self::new(
+            line_rate * f64::from(options.tx_rate_percent.min(100)) / 100.0,
+            now,
+        ))
+    }
+
+    pub fn new(bytes_per_sec: f64, now: Instant) -> Self {
+        let burst = (bytes_per_sec * BURST.as_secs_f64()).max(1.0);
+        Self {
+            bytes_per_sec,
+            burst,
+            tokens: burst,
+            last_refill: now,
+            queues: Default::default(),
+            deficits: [0; Channel::COUNT],
+            cursor: 0,
+            queued_bytes: 0,
+            stats: OutboxStats::default(),
+        }
+    }
+
+    pub fn push(&mut self, line: &str) {
+        let mut bytes = line.as_bytes().to_vec();
+        bytes.push(b'\n');
+        let queue = &mut self.queues[Channel::classify(line).index()];
+        while self.queued_bytes + bytes.len() > MAX_QUEUED_BYTES {
+            let Some(dropped) = queue.pop_front() else {
+                break;
+            };
+            self.queued_bytes -= dropped.len();
+            self.stats.dropped_lines += 1;
+        }
+        self.queued_bytes += bytes.len();
+        queue.push_back(bytes);
+    }
+
+    /// Next line allowed on the wire now, picked round-robin by byte deficit.
+    pub fn pop_ready(&mut self, now: Instant) -> Option<Vec<u8>> {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.bytes_per_sec).min(self.burst);
+        self.last_refill = now;
+        if self.queued_bytes == 0 {
+            return None;
+        }
+        // Enough visits for the largest queued line to earn its deficit.
+        let max_visits = Channel::COUNT * (MAX_QUEUED_BYTES / QUANTUM_BYTES + 1);
+        for _ in 0..max_visits {
+            let idx = self.cursor;
+            let Some(len) = self.queues[idx].front().map(Vec::len) else {
+                self.deficits[idx] = 0;
+                self.cursor = (idx + 1) % Channel::COUNT;
+                continue;
+            };
+            if self.deficits[idx] < len {
+                self.deficits[idx] += QUANTUM_BYTES;
+                self.cursor = (idx + 1) % Channel::COUNT;
+                continue;
+            }
+            // A line longer than the bucket goes out once the bucket is full and runs it negative.
+            if self.tokens < len as f64 && self.tokens < self.burst {
+                self.stats.delayed_lines += 1;
+                return None;
+            }
+            let line = self.queues[idx].pop_front()?;
+            self.deficits[idx] -= len;
+            self.tokens -= len as f64;
+            self.queued_bytes -= len;
+            self.stats.sent_bytes += len as u64;
+            return Some(line);
+        }
+        None
+    }
+
+    pub fn stats(&self) -> OutboxStats {
+        self.stats
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued_bytes == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(outbox: &mut Outbox, now: Instant) -> Vec<String> {
+        std::iter::from_fn(|| outbox.pop_ready(now))
+            .map(|line| String::from_utf8(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn rate_follows_baud_framing_and_percent() {
+        let now = Instant::now();
+        let options = SerialOptions {
+            baud: 9_600,
+            tx_rate_percent: 70,
+            ..SerialOptions::default()
+        };
+        let outbox = Outbox::for_options(&options, now).unwrap();
+        assert!((outbox.bytes_per_sec - 672.0).abs() < 0.01);
+        let uncapped = SerialOptions::default();
+        assert!(Outbox::for_options(&uncapped, now).is_none());
+    }
+
+    #[test]
+    fn holds_lines_until_tokens_refill() {
+        let start = Instant::now();
+        let mut outbox = Outbox::new(100.0, start);
+        outbox.push("0123456789");
+        outbox.push("abcdefghij");
+        assert_eq!(drain(&mut outbox, start), vec!["0123456789\n"]);
+        assert!(outbox
+            .pop_ready(start + Duration::from_millis(50))
+            .is_none());
+        assert_eq!(
+            drain(&mut outbox, start + Duration::from_millis(120)),
+            vec!["abcdefghij\n"]
+        );
+    }
+
+    #[test]
+    fn channels_share_bandwidth_round_robin() {
+        let now = Instant::now();
+        let mut outbox = Outbox::new(1_000_000.0, now);
+        for idx in 0..3 {
+            outbox.push(&format!(
+                "{{\"msg\":{{\"type\":\"stdout\"}},\"crc32\":{idx}}}"
+            ));
+        }
+        outbox.push("{\"type\":\"hello\"}");
+        let order: Vec<Channel> = drain(&mut outbox, now)
+            .iter()
+            .map(|line| Channel::classify(line.trim_end()))
+            .collect();
+        assert_eq!(order[0], Channel::Control);
+        assert_eq!(order.len(), 4);
+    }
+}
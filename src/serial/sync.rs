@@ -1,18 +1,33 @@
 use crate::{state::MAX_FRAME_BYTES, Error, Result};
 use serialport::{DataBits, FlowControl, Parity, StopBits};
 use std::io;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use super::{DtrBehavior, FlowControlMode, ParityMode, SerialOptions, StopBitsMode};
+use super::{
+    arq::{ArqLink, ArqSettings, ArqStats, Inbound, ARQ_MAX_WIRE_BYTES},
+    cipher::{LinkCipher, LinkKeys, SEALED_MAX_WIRE_BYTES},
+    shaper::{Outbox, OutboxStats},
+    tuning, DtrBehavior, FlowControlMode, ModemLines, ParityMode, SerialOptions, StopBitsMode,
+};
 
 /// Lightweight serial placeholder. Replace with a real transport later.
 #[derive(Debug)]
 pub struct SerialPort {
-    #[allow(dead_code)]
     device: String,
-    #[allow(dead_code)]
     baud: u32,
     port: Option<Box<dyn serialport::SerialPort>>,
+    tuning_warning: Option<String>,
+    arq_settings: ArqSettings,
+    arq: Option<ArqLink>,
+    cipher: Option<LinkCipher>,
+    outbox: Option<Outbox>,
+    tx_bytes: u64,
+    read_timeout: Duration,
+    /// Bytes of a line still arriving when the last read timed out.
+    partial: Vec<u8>,
+    /// Descriptor of the open port, for callers that wait on it outside `read_message_line`.
+    #[cfg(unix)]
+    fd: std::os::fd::RawFd,
 }
 
 impl SerialPort {
@@ -22,6 +37,14 @@ impl SerialPort {
                 "device path cannot be empty".to_string(),
             ));
         }
+        #[cfg(unix)]
+        if let Some(path) = super::fifo::fifo_path(device) {
+            return Self::open_fifo(device, path, options);
+        }
+        #[cfg(unix)]
+        if let Some(addr) = super::tcp::tcp_addr(device) {
+            return Self::open_tcp(device, addr, options);
+        }
 
         let mut builder = serialport::new(device, options.baud)
             .data_bits(DataBits::Eight)
@@ -29,6 +52,7 @@ impl SerialPort {
             .stop_bits(to_serial_stop_bits(options.stop_bits))
             .flow_control(to_serial_flow(options.flow_control))
             .timeout(Duration::from_millis(options.timeout_ms));
+        let read_timeout = Duration::from_millis(options.timeout_ms);
 
         builder = match options.dtr {
             DtrBehavior::Preserve => builder,
@@ -36,70 +60,330 @@ impl SerialPort {
             DtrBehavior::Deassert => builder.dtr_on_open(false),
         };
 
+        #[cfg(unix)]
+        let (port, fd) = {
+            use std::os::fd::AsRawFd;
+            let native = builder.open_native().map_err(map_serial_error)?;
+            let fd = native.as_raw_fd();
+            (Box::new(native) as Box<dyn serialport::SerialPort>, fd)
+        };
+        #[cfg(not(unix))]
         let port = builder.open().map_err(map_serial_error)?;
+        tuning::apply_read_tuning(device, options.read_min_bytes, options.read_gap_ds)?;
+        // The latency timer lives in sysfs and usually needs root; a failure only costs latency.
+        let tuning_warning = if options.low_latency {
+            match tuning::set_low_latency(device) {
+                Ok(true) => None,
+                Ok(false) => Some(format!(
+                    "{device} has no latency timer; low_latency ignored"
+                )),
+                Err(err) => Some(format!("could not set low latency on {device}: {err}")),
+            }
+        } else {
+            None
+        };
 
         Ok(Self {
             device: device.to_string(),
             baud: options.baud,
             port: Some(port),
+            tuning_warning,
+            arq_settings: options.arq,
+            arq: None,
+            cipher: None,
+            outbox: Outbox::for_options(&options, Instant::now()),
+            tx_bytes: 0,
+            read_timeout,
+            partial: Vec::new(),
+            #[cfg(unix)]
+            fd,
         })
     }
 
-    /// Send a single newline-terminated command line to the serial port.
-    pub fn send_command_line(&mut self, line: &str) -> Result<()> {
+    /// `fifo:<path>`: read payload lines from a named pipe. Serial tuning and the shaper do not
+    /// apply; the FIFO port accepts and ignores line settings.
+    #[cfg(unix)]
+    fn open_fifo(device: &str, path: &str, options: SerialOptions) -> Result<Self> {
+        use std::os::fd::AsRawFd;
+        let read_timeout = Duration::from_millis(options.timeout_ms);
+        let port = super::fifo::FifoPort::open(path, read_timeout, options.baud)?;
+        let fd = port.as_raw_fd();
+        Ok(Self {
+            device: device.to_string(),
+            baud: options.baud,
+            port: Some(Box::new(port)),
+            tuning_warning: None,
+            arq_settings: options.arq,
+            arq: None,
+            cipher: None,
+            outbox: None,
+            tx_bytes: 0,
+            read_timeout,
+            partial: Vec::new(),
+            fd,
+        })
+    }
+
+    /// `tcp:<addr>`: wait up to the read timeout for a network client. Like a FIFO it has no
+    /// line settings to tune, but replies reach the client, so the handshake runs as usual.
+    #[cfg(unix)]
+    fn open_tcp(device: &str, addr: &str, options: SerialOptions) -> Result<Self> {
+        use std::os::fd::AsRawFd;
+        let read_timeout = Duration::from_millis(options.timeout_ms);
+        let port = super::tcp::TcpPort::accept(addr, read_timeout, options.baud)?;
+        let fd = port.as_raw_fd();
+        Ok(Self {
+            device: device.to_string(),
+            baud: options.baud,
+            port: Some(Box::new(port)),
+            tuning_warning: None,
+            arq_settings: options.arq,
+            arq: None,
+            cipher: None,
+            outbox: None,
+            tx_bytes: 0,
+            read_timeout,
+            partial: Vec::new(),
+            fd,
+        })
+    }
+
+    /// True when this link is a FIFO payload source rather than a UART; nothing answers on it.
+    pub fn is_fifo(&self) -> bool {
+        #[cfg(unix)]
+        {
+            super::fifo::fifo_path(&self.device).is_some()
+        }
+        #[cfg(not(unix))]
+        {
+            false
+        }
+    }
+
+    /// Address of the client on a `tcp:` link.
+    pub fn peer(&self) -> Option<String> {
+        #[cfg(unix)]
+        if super::tcp::tcp_addr(&self.device).is_some() {
+            return self.port.as_ref().and_then(|port| port.name());
+        }
+        None
+    }
+
+    /// Why a requested low-latency mode could not be applied, if it was not.
+    pub fn tuning_warning(&self) -> Option<&str> {
+        self.tuning_warning.as_deref()
+    }
+
+    /// Start framing with ARQ once the peer has advertised support for it. Inbound ARQ
+    /// frames are acked from here on; outbound wrapping follows the configured mode.
+    pub fn attach_arq(&mut self) {
+        self.arq = Some(ArqLink::new(self.arq_settings));
+    }
+
+    /// Seal every line sent from here on and accept only sealed lines, once both peers proved
+    /// they hold the same pre-shared key.
+    pub fn attach_cipher(&mut self, keys: LinkKeys) {
+        self.cipher = Some(LinkCipher::new(keys));
+    }
+
+    pub fn cipher_active(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    pub fn arq_active(&self) -> bool {
+        self.arq.as_ref().is_some_and(ArqLink::is_active)
+    }
+
+    pub fn arq_stats(&self) -> Option<ArqStats> {
+        self.arq.as_ref().map(ArqLink::stats)
+    }
+
+    pub fn outbox_stats(&self) -> Option<OutboxStats> {
+        self.outbox.as_ref().map(Outbox::stats)
+    }
+
+    /// Write out everything the shaper still holds, waiting for its rate cap, until `deadline`.
+    /// Returns `false` when lines were still queued at the deadline.
+    pub fn drain_outbox(&mut self, deadline: Instant) -> Result<bool> {
         let port = self
             .port
+            .as_deref_mut()
+            .ok_or_else(|| Error::InvalidArgs("serial port not connected".into()))?;
+        loop {
+            flush_outbox(port, &mut self.outbox)?;
+            if self.outbox.as_ref().is_none_or(Outbox::is_empty) {
+                return Ok(true);
+            }
+            if Instant::now() >= deadline {
+                return Ok(false);
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Feed a CRC failure to the ARQ supervisor; `true` when it just switched ARQ on.
+    pub fn note_crc_failure(&mut self, now: Instant) -> bool {
+        self.arq
             .as_mut()
+            .is_some_and(|arq| arq.note_crc_failure(now))
+    }
+
+    pub fn baud(&self) -> u32 {
+        self.baud
+    }
+
+    /// Switch the line rate in place once everything already written has left the UART.
+    pub fn set_baud(&mut self, baud: u32) -> Result<()> {
+        let port = self
+            .port
+            .as_deref_mut()
             .ok_or_else(|| Error::InvalidArgs("serial port not connected".into()))?;
+        io::Write::flush(port)?;
+        port.set_baud_rate(baud).map_err(map_serial_error)?;
+        self.baud = baud;
+        self.partial.clear();
+        Ok(())
+    }
 
-        let mut buf = line.as_bytes().to_vec();
-        buf.push(b'\n');
-        port.write_all(&buf)?;
-        port.flush()?;
+    /// How long `read_message_line` waits before returning 0. A line cut by the timeout is kept
+    /// and completed by the next read.
+    pub fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        if timeout == self.read_timeout {
+            return Ok(());
+        }
+        let port = self
+            .port
+            .as_deref_mut()
+            .ok_or_else(|| Error::InvalidArgs("serial port not connected".into()))?;
+        port.set_timeout(timeout).map_err(map_serial_error)?;
+        self.read_timeout = timeout;
         Ok(())
     }
 
+    /// Raw descriptor of the open port. It stays owned by this `SerialPort`.
+    #[cfg(unix)]
+    pub fn raw_fd(&self) -> std::os::fd::RawFd {
+        self.fd
+    }
+
+    /// Whether input is already waiting to be read. FIFO and TCP ports read ahead into their
+    /// own buffer, and bytes held there never show on the descriptor.
+    pub fn has_pending_input(&self) -> bool {
+        self.port
+            .as_deref()
+            .is_some_and(|port| port.bytes_to_read().is_ok_and(|count| count > 0))
+    }
+
+    /// Bytes handed to `send_command_line` since the port was opened.
+    pub fn tx_bytes(&self) -> u64 {
+        self.tx_bytes
+    }
+
+    /// Write a link-control line straight to the wire, bypassing ARQ and the shaper, so it
+    /// has left before a following `set_baud`.
+    pub fn send_control_line(&mut self, line: &str) -> Result<()> {
+        let port = self
+            .port
+            .as_deref_mut()
+            .ok_or_else(|| Error::InvalidArgs("serial port not connected".into()))?;
+        match self.cipher.as_mut() {
+            Some(cipher) => write_line(port, &cipher.seal(line)),
+            None => write_line(port, line),
+        }
+    }
+
+    /// Send a single newline-terminated command line to the serial port.
+    pub fn send_command_line(&mut self, line: &str) -> Result<()> {
+        let port = self
+            .port
+            .as_deref_mut()
+            .ok_or_else(|| Error::InvalidArgs("serial port not connected".into()))?;
+        self.tx_bytes += line.len() as u64 + 1;
+        let sealed = self.cipher.as_mut().map(|cipher| cipher.seal(line));
+        let line = sealed.as_deref().unwrap_or(line);
+        match self.arq.as_mut() {
+            Some(arq) => match arq.outbound(line, Instant::now()) {
+                Some(wire) => emit(port, &mut self.outbox, &wire),
+                // Queued behind the unacked line; `read_message_line` sends it later.
+                None => Ok(()),
+            },
+            None => emit(port, &mut self.outbox, line),
+        }
+    }
+
     /// Read a single newline-terminated message. Returns 0 on timeout.
     pub fn read_message_line(&mut self, line_buffer: &mut String) -> Result<usize> {
-        line_buffer.clear();
         let port = self
             .port
             .as_deref_mut()
             .ok_or_else(|| Error::InvalidArgs("serial port not connected".into()))?;
+        flush_outbox(port, &mut self.outbox)?;
+        // Sealed lines are longer than the frame they carry; the cap applies once opened.
+        let frame_limit = if self.cipher.is_some() {
+            SEALED_MAX_WIRE_BYTES
+        } else {
+            MAX_FRAME_BYTES
+        };
+        let Some(arq) = self.arq.as_mut() else {
+            let read = read_raw_line(port, &mut self.partial, line_buffer, frame_limit)?;
+            return unseal(&mut self.cipher, line_buffer, read);
+        };
 
-        let mut byte = [0u8; 1];
-        let mut total = 0;
-        // Read byte-by-byte until newline while enforcing a size guard.
-        loop {
-            match port.read(&mut byte) {
-                Ok(0) => return Ok(total),
-                Ok(_) => {
-                    total += 1;
-                    if total > MAX_FRAME_BYTES {
-                        // Drain until newline to avoid contaminating the next frame.
-                        while port.read(&mut byte).is_ok() {
-                            if byte[0] == b'\n' {
-                                break;
-                            }
-                        }
-                        return Err(Error::Parse(format!(
-                            "frame exceeds {MAX_FRAME_BYTES} bytes"
-                        )));
-                    }
-                    let b = byte[0];
-                    if b == b'\n' {
-                        return Ok(total);
+        if let Some(wire) = arq.poll(Instant::now()) {
+            emit(port, &mut self.outbox, &wire)?;
+        }
+        let read = read_raw_line(port, &mut self.partial, line_buffer, ARQ_MAX_WIRE_BYTES)?;
+        if read == 0 {
+            return Ok(0);
+        }
+        match arq.inbound(line_buffer.trim_end()) {
+            Inbound::Passthrough if read > frame_limit => {
+                Err(Error::Parse(format!("frame exceeds {frame_limit} bytes")))
+            }
+            Inbound::Passthrough => unseal(&mut self.cipher, line_buffer, read),
+            Inbound::Deliver { ack, body } => {
+                emit(port, &mut self.outbox, &ack)?;
+                line_buffer.clear();
+                match body {
+                    Some(body) if body.len() > frame_limit => {
+                        Err(Error::Parse(format!("frame exceeds {frame_limit} bytes")))
                     }
-                    if b != b'\r' {
-                        line_buffer.push(b as char);
+                    Some(body) => {
+                        line_buffer.push_str(&body);
+                        let read = line_buffer.len();
+                        unseal(&mut self.cipher, line_buffer, read)
                     }
+                    None => Ok(0),
                 }
-                Err(e) if e.kind() == io::ErrorKind::TimedOut => return Ok(0),
-                Err(e) => return Err(Error::Io(e)),
+            }
+            Inbound::Acked => {
+                line_buffer.clear();
+                if let Some(wire) = arq.poll(Instant::now()) {
+                    emit(port, &mut self.outbox, &wire)?;
+                }
+                Ok(0)
+            }
+            Inbound::Ignored => {
+                line_buffer.clear();
+                Ok(0)
             }
         }
     }
 
+    /// Sample the modem status inputs (CTS/DSR/CD/RI) of the open port.
+    pub fn read_modem_lines(&mut self) -> Result<ModemLines> {
+        let port = self
+            .port
+            .as_mut()
+            .ok_or_else(|| Error::InvalidArgs("serial port not connected".into()))?;
+        Ok(ModemLines {
+            cts: port.read_clear_to_send().map_err(map_serial_error)?,
+            dsr: port.read_data_set_ready().map_err(map_serial_error)?,
+            cd: port.read_carrier_detect().map_err(map_serial_error)?,
+            ri: port.read_ring_indicator().map_err(map_serial_error)?,
+        })
+    }
+
     /// Provide a temporary reader over the serial port.
     pub fn borrow_reader(&mut self) -> Result<SerialReader<'_>> {
         let port = self
@@ -108,6 +392,16 @@ impl SerialPort {
             .ok_or_else(|| Error::InvalidArgs("serial port not connected".into()))?;
         Ok(SerialReader { port })
     }
+
+    /// Borrow the port as a raw byte stream for protocols that are not line based, such as
+    /// YMODEM. Bypasses ARQ and the shaper.
+    pub fn borrow_stream(&mut self) -> Result<SerialStream<'_>> {
+        let port = self
+            .port
+            .as_deref_mut()
+            .ok_or_else(|| Error::InvalidArgs("serial port not connected".into()))?;
+        Ok(SerialStream { port })
+    }
 }
 
 impl super::LineIo for SerialPort {
@@ -130,6 +424,130 @@ impl<'a> std::io::Read for SerialReader<'a> {
     }
 }
 
+pub struct SerialStream<'a> {
+    port: &'a mut dyn serialport::SerialPort,
+}
+
+impl std::io::Read for SerialStream<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+impl std::io::Write for SerialStream<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.port.flush()
+    }
+}
+
+/// Read one newline-terminated line into `line_buffer`. Returns 0 on timeout, keeping what has
+/// arrived so far in `partial` for the next call.
+fn read_raw_line(
+    port: &mut dyn serialport::SerialPort,
+    partial: &mut Vec<u8>,
+    line_buffer: &mut String,
+    limit: usize,
+) -> Result<usize> {
+    line_buffer.clear();
+    let mut byte = [0u8; 1];
+    // Read byte-by-byte until newline while enforcing a size guard.
+    loop {
+        match port.read(&mut byte) {
+            Ok(0) => return Ok(take_line(partial, line_buffer)),
+            Ok(_) => {
+                if partial.len() >= limit {
+                    partial.clear();
+                    // Drain until newline to avoid contaminating the next frame.
+                    while port.read(&mut byte).is_ok() {
+                        if byte[0] == b'\n' {
+                            break;
+                        }
+                    }
+                    return Err(Error::Parse(format!("frame exceeds {limit} bytes")));
+                }
+                let b = byte[0];
+                if b == b'\n' {
+                    return Ok(take_line(partial, line_buffer) + 1);
+                }
+                if b != b'\r' {
+                    partial.push(b);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => return Ok(0),
+            Err(e) => {
+                partial.clear();
+                return Err(Error::Io(e));
+            }
+        }
+    }
+}
+
+/// Move the bytes gathered in `partial` into `line_buffer`, decoding them as UTF-8 only now
+/// that the line is whole so multi-byte characters survive; invalid sequences become U+FFFD.
+/// Returns the number of bytes taken.
+fn take_line(partial: &mut Vec<u8>, line_buffer: &mut String) -> usize {
+    line_buffer.push_str(&String::from_utf8_lossy(partial));
+    let read = partial.len();
+    partial.clear();
+    read
+}
+
+/// Replace a sealed line in `line_buffer` with its plaintext; a no-op without a cipher.
+fn unseal(cipher: &mut Option<LinkCipher>, line_buffer: &mut String, read: usize) -> Result<usize> {
+    let Some(cipher) = cipher.as_mut() else {
+        return Ok(read);
+    };
+    if read == 0 || line_buffer.trim().is_empty() {
+        return Ok(read);
+    }
+    let plaintext = cipher.open(line_buffer.trim_end());
+    line_buffer.clear();
+    line_buffer.push_str(&plaintext?);
+    Ok(line_buffer.len())
+}
+
+/// Write `line` now, or queue it behind the rate cap when one is configured.
+fn emit(
+    port: &mut dyn serialport::SerialPort,
+    outbox: &mut Option<Outbox>,
+    line: &str,
+) -> Result<()> {
+    match outbox.as_mut() {
+        Some(queue) => {
+            queue.push(line);
+            flush_outbox(port, outbox)
+        }
+        None => write_line(port, line),
+    }
+}
+
+fn flush_outbox(port: &mut dyn serialport::SerialPort, outbox: &mut Option<Outbox>) -> Result<()> {
+    let Some(queue) = outbox.as_mut() else {
+        return Ok(());
+    };
+    let mut wrote = false;
+    while let Some(bytes) = queue.pop_ready(Instant::now()) {
+        port.write_all(&bytes)?;
+        wrote = true;
+    }
+    if wrote {
+        port.flush()?;
+    }
+    Ok(())
+}
+
+fn write_line(port: &mut dyn serialport::SerialPort, line: &str) -> Result<()> {
+    let mut buf = line.as_bytes().to_vec();
+    buf.push(b'\n');
+    port.write_all(&buf)?;
+    port.flush()?;
+    Ok(())
+}
+
 fn map_serial_error(err: serialport::Error) -> Error {
     use serialport::ErrorKind;
 
@@ -190,4 +608,27 @@ mod tests {
             Err(other) => panic!("unexpected error: {other}"),
         }
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn decodes_multi_byte_utf8_lines() {
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frames.fifo");
+        let device = format!("fifo:{}", path.display());
+        let opts = SerialOptions {
+            timeout_ms: 20,
+            ..SerialOptions::default()
+        };
+        let mut port = SerialPort::connect(&device, opts).unwrap();
+        let mut producer = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        producer
+            .write_all("{\"line1\":\"21.5°C\"}\r\n".as_bytes())
+            .unwrap();
+
+        let mut line = String::new();
+        let read = port.read_message_line(&mut line).unwrap();
+        assert_eq!(line, "{\"line1\":\"21.5°C\"}");
+        assert_eq!(read, "{\"line1\":\"21.5°C\"}\n".len());
+    }
 }
@@ -0,0 +1,320 @@
+//! TCP stand-in for the UART: `--listen tcp:<addr>` takes payload, tunnel and command frames
+//! from a network client, for testing without hardware or driving the LCD while the serial
+//! link is down.
+//!
+//! [`TcpPort`] implements `serialport::SerialPort` like [`super::fifo::FifoPort`], so the
+//! handshake, ARQ and the render loop run unchanged over it. One client is served at a time:
+//! accepting it is the "open", and its hang-up reads as a disconnect, which sends the render
+//! loop back through its reconnect path to wait for the next client. The listening socket is
+//! kept for the life of the process so clients are never refused between attempts.
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, StopBits};
+use std::collections::hash_map::{Entry, HashMap};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::fd::{AsFd, AsRawFd, RawFd};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use rustix::event::{poll, PollFd, PollFlags, Timespec};
+
+use crate::{Error, Result};
+
+/// Device prefix that selects a TCP listener instead of a serial device.
+pub const TCP_PREFIX: &str = "tcp:";
+const READ_CHUNK: usize = 512;
+
+/// The listen address when `device` names one.
+pub fn tcp_addr(device: &str) -> Option<&str> {
+    device.strip_prefix(TCP_PREFIX)
+}
+
+/// Listening sockets by address, bound on first use.
+fn listeners() -> &'static Mutex<HashMap<SocketAddr, TcpListener>> {
+    static LISTENERS: OnceLock<Mutex<HashMap<SocketAddr, TcpListener>>> = OnceLock::new();
+    LISTENERS.get_or_init(Default::default)
+}
+
+#[derive(Debug)]
+pub struct TcpPort {
+    peer: SocketAddr,
+    stream: TcpStream,
+    timeout: Duration,
+    baud: u32,
+    buffer: Vec<u8>,
+    pos: usize,
+}
+
+impl TcpPort {
+    /// Listen on `addr` and wait up to `timeout` for a client; `TimedOut` when none arrives.
+    pub fn accept(addr: &str, timeout: Duration, baud: u32) -> Result<Self> {
+        let addr: SocketAddr = addr
+            .parse()
+            .map_err(|_| Error::InvalidArgs(format!("invalid listen address '{addr}'")))?;
+        let mut listeners = listeners().lock().unwrap_or_else(|e| e.into_inner());
+        let listener = match listeners.entry(addr) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let listener = TcpListener::bind(addr)?;
+                listener.set_nonblocking(true)?;
+                entry.insert(listener)
+            }
+        };
+        if !wait_readable(&*listener, timeout)? {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("no client connected to tcp:{addr}"),
+            )));
+        }
+        let (stream, peer) = listener.accept()?;
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            peer,
+            stream,
+            timeout,
+            baud,
+            buffer: Vec::with_capacity(READ_CHUNK),
+            pos: 0,
+        })
+    }
+
+    /// Address of the connected client.
+    pub fn peer(&self) -> SocketAddr {
+        self.peer
+    }
+
+    fn buffered(&self) -> usize {
+        self.buffer.len() - self.pos
+    }
+}
+
+/// Wait up to `timeout` for `fd` to turn readable.
+fn wait_readable(fd: impl AsFd, timeout: Duration) -> io::Result<bool> {
+    let timeout = Timespec::try_from(timeout).unwrap_or(Timespec {
+        tv_sec: i64::MAX,
+        tv_nsec: 0,
+    });
+    let mut fds = [PollFd::new(&fd, PollFlags::IN)];
+    loop {
+        match poll(&mut fds, Some(&timeout)) {
+            Ok(ready) => return Ok(ready > 0),
+            Err(rustix::io::Errno::INTR) => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+impl Read for TcpPort {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.buffered() == 0 {
+            if !wait_readable(&self.stream, self.timeout)? {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "tcp read timed out",
+                ));
+            }
+            self.buffer.resize(READ_CHUNK, 0);
+            let read = self.stream.read(&mut self.buffer)?;
+            self.buffer.truncate(read);
+            self.pos = 0;
+            if read == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    format!("tcp client {} disconnected", self.peer),
+                ));
+            }
+        }
+        let count = out.len().min(self.buffered());
+        out[..count].copy_from_slice(&self.buffer[self.pos..self.pos + count]);
+        self.pos += count;
+        Ok(count)
+    }
+}
+
+impl Write for TcpPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl AsRawFd for TcpPort {
+    fn as_raw_fd(&self) -> RawFd {
+        self.stream.as_raw_fd()
+    }
+}
+
+impl serialport::SerialPort for TcpPort {
+    fn name(&self) -> Option<String> {
+        Some(self.peer.to_string())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.baud)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Line settings mean nothing on a socket; they are accepted so burst mode and reloads work.
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.baud = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    /// A socket has no modem lines; report them up so the modem monitor stays quiet.
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.buffered() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, _buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn serialport::SerialPort>> {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Unknown,
+            "tcp input cannot be cloned",
+        ))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serialport::SerialPort;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn tcp_addr_needs_the_prefix() {
+        assert_eq!(tcp_addr("tcp:127.0.0.1:7777"), Some("127.0.0.1:7777"));
+        assert_eq!(tcp_addr("/dev/ttyUSB0"), None);
+    }
+
+    #[test]
+    fn serves_one_client_at_a_time_on_a_kept_listener() {
+        // Bind through an ephemeral port first so the test never collides with a real one.
+        let probe = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = probe.local_addr().unwrap().to_string();
+        drop(probe);
+
+        let err = TcpPort::accept(&addr, Duration::from_millis(20), 9600).unwrap_err();
+        assert!(format!("{err}").contains("no client connected"));
+
+        let client = TcpStream::connect(&addr).unwrap();
+        let mut port = TcpPort::accept(&addr, Duration::from_millis(500), 9600).unwrap();
+        (&client).write_all(b"{\"line1\":\"hi\"}\n").unwrap();
+        let mut line = Vec::new();
+        let mut byte = [0u8; 1];
+        while byte[0] != b'\n' {
+            assert_eq!(port.read(&mut byte).unwrap(), 1);
+            line.push(byte[0]);
+        }
+        assert_eq!(line, b"{\"line1\":\"hi\"}\n");
+
+        // Replies go back to the client, unlike on a FIFO.
+        port.write_all(b"INIT\n").unwrap();
+        let mut reply = String::new();
+        BufReader::new(&client).read_line(&mut reply).unwrap();
+        assert_eq!(reply, "INIT\n");
+
+        port.set_timeout(Duration::from_millis(20)).unwrap();
+        assert_eq!(
+            port.read(&mut byte).unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        drop(client);
+        assert_eq!(
+            port.read(&mut byte).unwrap_err().kind(),
+            io::ErrorKind::ConnectionAborted
+        );
+
+        // The listener stays bound, so the next client is accepted on the same address.
+        let _next = TcpStream::connect(&addr).unwrap();
+        TcpPort::accept(&addr, Duration::from_millis(500), 9600).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_bad_address() {
+        let err = TcpPort::accept("localhost", Duration::ZERO, 9600).unwrap_err();
+        assert!(format!("{err}").contains("invalid listen address"));
+    }
+}
@@ -1,12 +1,19 @@
-use crate::CACHE_DIR;
+use crate::cache_dir;
 use serde::Serialize;
+
+use super::ModemLines;
 use std::io::{self, Write};
-use std::path::PathBuf;
 use std::sync::{Mutex, OnceLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 const TELEMETRY_FILE: &str = "serial_backoff.log";
+const MODEM_TELEMETRY_FILE: &str = "serial_modem.log";
+const HANDSHAKE_TELEMETRY_FILE: &str = "serial_handshake.log";
+const LOOP_STATS_TELEMETRY_FILE: &str = "loop_stats.log";
 static FILE_HANDLE: OnceLock<io::Result<Mutex<std::fs::File>>> = OnceLock::new();
+static MODEM_FILE_HANDLE: OnceLock<io::Result<Mutex<std::fs::File>>> = OnceLock::new();
+static HANDSHAKE_FILE_HANDLE: OnceLock<io::Result<Mutex<std::fs::File>>> = OnceLock::new();
+static LOOP_STATS_FILE_HANDLE: OnceLock<io::Result<Mutex<std::fs::File>>> = OnceLock::new();
 
 #[derive(Debug, Clone, Copy, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -55,20 +62,107 @@ pub fn log_backoff_event(
     };
 
     let line = serde_json::to_string(&entry).map_err(io::Error::other)?;
-    let handle = get_file()?;
+    append_line(&FILE_HANDLE, TELEMETRY_FILE, &line)
+}
+
+#[derive(Serialize)]
+struct ModemEntry<'a> {
+    ts_ms: u128,
+    event: &'static str,
+    device: &'a str,
+    cts: bool,
+    dsr: bool,
+    cd: bool,
+    ri: bool,
+}
+
+/// Record a modem status line transition (CTS/DSR/CD/RI) for the given device.
+pub fn log_modem_event(device: &str, lines: ModemLines) -> io::Result<()> {
+    let entry = ModemEntry {
+        ts_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis(),
+        event: "serial_modem",
+        device,
+        cts: lines.cts,
+        dsr: lines.dsr,
+        cd: lines.cd,
+        ri: lines.ri,
+    };
+
+    let line = serde_json::to_string(&entry).map_err(io::Error::other)?;
+    append_line(&MODEM_FILE_HANDLE, MODEM_TELEMETRY_FILE, &line)
+}
+
+#[derive(Serialize)]
+struct HandshakeEntry<'a, T: Serialize> {
+    ts_ms: u128,
+    event: &'static str,
+    #[serde(flatten)]
+    report: &'a T,
+}
+
+/// Record one connection's handshake timing report.
+pub fn log_handshake_event<T: Serialize>(report: &T) -> io::Result<()> {
+    let entry = HandshakeEntry {
+        ts_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis(),
+        event: "serial_handshake",
+        report,
+    };
+
+    let line = serde_json::to_string(&entry).map_err(io::Error::other)?;
+    append_line(&HANDSHAKE_FILE_HANDLE, HANDSHAKE_TELEMETRY_FILE, &line)
+}
+
+#[derive(Serialize)]
+struct LoopStatsEntry<'a, T: Serialize> {
+    ts_ms: u128,
+    event: &'static str,
+    #[serde(flatten)]
+    stats: &'a T,
+}
+
+/// Record a snapshot of the render loop's running counters.
+pub fn log_loop_stats<T: Serialize>(stats: &T) -> io::Result<()> {
+    let entry = LoopStatsEntry {
+        ts_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis(),
+        event: "loop_stats",
+        stats,
+    };
+
+    let line = serde_json::to_string(&entry).map_err(io::Error::other)?;
+    append_line(&LOOP_STATS_FILE_HANDLE, LOOP_STATS_TELEMETRY_FILE, &line)
+}
+
+fn append_line(
+    lock: &'static OnceLock<io::Result<Mutex<std::fs::File>>>,
+    filename: &str,
+    line: &str,
+) -> io::Result<()> {
+    let handle = get_file(lock, filename)?;
     if let Ok(mut file) = handle.lock() {
         writeln!(file, "{line}")?;
     }
     Ok(())
 }
 
-fn get_file() -> io::Result<&'static Mutex<std::fs::File>> {
-    if FILE_HANDLE.get().is_none() {
-        let handle = create_file_handle()?;
-        let _ = FILE_HANDLE.set(Ok(handle));
+fn get_file(
+    lock: &'static OnceLock<io::Result<Mutex<std::fs::File>>>,
+    filename: &str,
+) -> io::Result<&'static Mutex<std::fs::File>> {
+    if lock.get().is_none() {
+        let handle = create_file_handle(filename)?;
+        let _ = lock.set(Ok(handle));
     }
 
-    let result_ref = FILE_HANDLE
+    let result_ref = lock
         .get()
         .ok_or_else(|| io::Error::other("failed to initialize serial telemetry log handle"))?;
 
@@ -78,8 +172,8 @@ fn get_file() -> io::Result<&'static Mutex<std::fs::File>> {
     }
 }
 
-fn create_file_handle() -> io::Result<Mutex<std::fs::File>> {
-    let path = PathBuf::from(CACHE_DIR).join(TELEMETRY_FILE);
+fn create_file_handle(filename: &str) -> io::Result<Mutex<std::fs::File>> {
+    let path = cache_dir().join(filename);
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
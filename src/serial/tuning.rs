@@ -0,0 +1,87 @@
+//! OS-level tty knobs that `serialport` does not expose: VMIN/VTIME and the FTDI latency timer.
+
+use rustix::{
+    fs::{Mode, OFlags},
+    termios::{tcgetattr, tcsetattr, OptionalActions, SpecialCodeIndex},
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// VMIN/VTIME as left by `cfmakeraw`; the port is only retuned when options differ.
+pub const DEFAULT_READ_MIN_BYTES: u8 = 1;
+pub const DEFAULT_READ_GAP_DS: u8 = 0;
+/// ftdi_sio latency timer in ms when low-latency mode is on (the driver default is 16).
+const LOW_LATENCY_TIMER_MS: u8 = 1;
+
+/// Set VMIN/VTIME on an already-open tty. Termios state is per device, so a second
+/// descriptor is enough and avoids borrowing the raw fd out of `serialport`.
+pub fn apply_read_tuning(device: &str, read_min_bytes: u8, read_gap_ds: u8) -> io::Result<()> {
+    if read_min_bytes == DEFAULT_READ_MIN_BYTES && read_gap_ds == DEFAULT_READ_GAP_DS {
+        return Ok(());
+    }
+    let fd = rustix::fs::open(
+        device,
+        OFlags::RDWR | OFlags::NOCTTY | OFlags::NONBLOCK | OFlags::CLOEXEC,
+        Mode::empty(),
+    )?;
+    let mut termios = tcgetattr(&fd)?;
+    termios.special_codes[SpecialCodeIndex::VMIN] = read_min_bytes;
+    termios.special_codes[SpecialCodeIndex::VTIME] = read_gap_ds;
+    tcsetattr(&fd, OptionalActions::Now, &termios)?;
+    Ok(())
+}
+
+/// Drop the FTDI latency timer so short interactive frames are not held for 16 ms.
+/// Returns `Ok(false)` when the adapter has no latency timer (not ftdi_sio).
+pub fn set_low_latency(device: &str) -> io::Result<bool> {
+    let Some(path) = latency_timer_path(Path::new(device)) else {
+        return Ok(false);
+    };
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::write(&path, LOW_LATENCY_TIMER_MS.to_string())?;
+    Ok(true)
+}
+
+/// `/sys/bus/usb-serial/devices/<tty>/latency_timer`, following `/dev/serial/by-id` links.
+fn latency_timer_path(device: &Path) -> Option<PathBuf> {
+    let resolved = fs::canonicalize(device).unwrap_or_else(|_| device.to_path_buf());
+    let name = resolved.file_name()?.to_str()?;
+    if !name.starts_with("ttyUSB") {
+        return None;
+    }
+    Some(
+        Path::new("/sys/bus/usb-serial/devices")
+            .join(name)
+            .join("latency_timer"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_timer_only_for_usb_serial() {
+        assert_eq!(
+            latency_timer_path(Path::new("/dev/ttyUSB3")),
+            Some(PathBuf::from(
+                "/sys/bus/usb-serial/devices/ttyUSB3/latency_timer"
+            ))
+        );
+        assert_eq!(latency_timer_path(Path::new("/dev/ttyAMA0")), None);
+    }
+
+    #[test]
+    fn default_read_tuning_is_a_no_op() {
+        apply_read_tuning(
+            "/dev/does-not-exist",
+            DEFAULT_READ_MIN_BYTES,
+            DEFAULT_READ_GAP_DS,
+        )
+        .unwrap();
+    }
+}
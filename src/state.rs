@@ -1,16 +1,20 @@
 use std::{
-    collections::VecDeque,
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, VecDeque},
     time::{Duration, Instant},
 };
 
 use crc32fast::Hasher;
 
 use crate::{
+    config::{DEFAULT_FRAME_CACHE_SIZE, MAX_ROWS},
+    display::overlays::{advance_offset, line_needs_scroll},
     payload::{
-        normalize_payload_json_with_policy, CompressionPolicy, Defaults, RenderFrame,
-        DEFAULT_PAGE_TIMEOUT_MS, DEFAULT_SCROLL_MS,
+        expand_placeholders, is_page_set, is_small_frame, normalize_payload_json_permissive,
+        normalize_payload_json_with_policy, AccessibilityProfile, CompressionPolicy, Defaults,
+        FrameFilter, RenderFrame, RowUpdate, DEFAULT_PAGE_TIMEOUT_MS, DEFAULT_SCROLL_MS,
     },
-    Error, Result,
+    FrameErrorCode, Result,
 };
 
 #[derive(Clone)]
@@ -19,14 +23,131 @@ struct FrameEntry {
     expires_at: Option<Instant>,
 }
 
+/// A queued interrupt frame; its hold time starts the first time it reaches the screen.
+#[derive(Clone)]
+struct InterruptEntry {
+    frame: RenderFrame,
+    shown_until: Option<Instant>,
+}
+
+/// What should own the display according to the interrupt queue; see
+/// [`RenderState::poll_interrupt`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterruptStep {
+    /// No interrupt is running; the normal rotation owns the display.
+    Idle,
+    /// This interrupt frame should be on screen.
+    Show(RenderFrame),
+    /// The last interrupt just ended; put this page back (`None` when the queue is empty).
+    Resume(Option<RenderFrame>),
+}
+
+/// A zone's own page rotation and scroll position; see [`RenderState::poll_zone`].
+#[derive(Clone, Default)]
+struct Zone {
+    /// The page on screen first.
+    pages: VecDeque<FrameEntry>,
+    next_page: Option<Instant>,
+    offsets: [usize; MAX_ROWS as usize],
+    next_scroll: Option<Instant>,
+}
+
+impl Zone {
+    /// Start over on the front page.
+    fn restart(&mut self) {
+        self.next_page = None;
+        self.next_scroll = None;
+        self.offsets = [0; MAX_ROWS as usize];
+    }
+}
+
 pub const MAX_FRAME_BYTES: usize = 512;
+/// Pages a zone keeps; the oldest is dropped past this.
+pub const MAX_ZONE_PAGES: usize = 16;
+/// Size limit for a multi-page frame after decompression.
+pub const MAX_PAGE_SET_BYTES: usize = 2048;
+/// How long after a reconnect cached frames count as replays.
+const REPLAY_GUARD_WINDOW: Duration = Duration::from_secs(2);
+/// Cached frames dropped at most per reconnect; past this a sender is rotating, not replaying.
+const REPLAY_GUARD_MAX_FRAMES: u32 = 8;
+
+/// Lookup counters for the recent-frame cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Bounded LRU of recent frame checksums; survives reconnects so replays can be recognised.
+#[derive(Debug, Clone)]
+struct FrameCache {
+    capacity: usize,
+    entries: VecDeque<u32>,
+}
+
+impl FrameCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn contains(&self, crc: u32) -> bool {
+        self.entries.contains(&crc)
+    }
+
+    fn touch(&mut self, crc: u32) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(pos) = self.entries.iter().position(|entry| *entry == crc) {
+            self.entries.remove(pos);
+        }
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(crc);
+    }
+
+    fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// Post-reconnect replay suppression, bounded in time and in frames dropped.
+#[derive(Clone, Copy)]
+struct ReplayGuard {
+    until: Instant,
+    remaining: u32,
+}
 
 /// Maintains a queue of render frames and deduplicates identical payloads.
 pub struct RenderState {
     pages: VecDeque<FrameEntry>,
+    /// Frames above normal priority, most urgent first and in arrival order within a level.
+    interrupts: Vec<InterruptEntry>,
+    /// Set while interrupts own the display: the page they cut off, if any.
+    resume: Option<Option<RenderFrame>>,
     last_crc: Option<u32>,
     defaults: Defaults,
     compression_policy: CompressionPolicy,
+    recent: FrameCache,
+    replay_guard: Option<ReplayGuard>,
+    cache_stats: FrameCacheStats,
+    strict: bool,
+    /// Unknown field names seen in permissive mode; each is reported once.
+    ignored_fields: BTreeSet<String>,
+    unreported_fields: Vec<String>,
+    /// Bumped whenever the queued pages change, so a snapshot knows when it is stale.
+    revision: u64,
+    accessibility: Option<AccessibilityProfile>,
+    filter: FrameFilter,
+    /// Frames sent with a `target`, by zone name; they never join the main rotation.
+    zones: BTreeMap<String, Zone>,
 }
 
 impl RenderState {
@@ -40,42 +161,264 @@ impl RenderState {
     ) -> Self {
         Self {
             pages: VecDeque::new(),
+            interrupts: Vec::new(),
+            resume: None,
             last_crc: None,
             defaults: defaults.unwrap_or(Defaults {
                 scroll_speed_ms: DEFAULT_SCROLL_MS,
                 page_timeout_ms: DEFAULT_PAGE_TIMEOUT_MS,
             }),
             compression_policy,
+            recent: FrameCache::new(DEFAULT_FRAME_CACHE_SIZE as usize),
+            replay_guard: None,
+            cache_stats: FrameCacheStats::default(),
+            strict: true,
+            ignored_fields: BTreeSet::new(),
+            unreported_fields: Vec::new(),
+            revision: 0,
+            accessibility: None,
+            filter: FrameFilter::default(),
+            zones: BTreeMap::new(),
         }
     }
 
-    /// Ingest a JSON frame string. Returns Some(frame) if it is new, None if duplicate.
+    /// Ingest a JSON frame string. Returns Some(frame) if it is new, None if duplicate or
+    /// dropped by the frame filter.
+    ///
+    /// A multi-page frame (`pages: [...]`) replaces the whole queue with its pages, in order, and
+    /// returns the first one; the rest follow on later [`RenderState::next_page`] calls. A frame
+    /// above normal priority joins the interrupt queue instead of the rotation, and one with a
+    /// `target` goes to that zone's own rotation and is shown there straight away.
     pub fn ingest(&mut self, raw: &str) -> Result<Option<RenderFrame>> {
-        self.prune_expired(Instant::now());
-        let normalized = normalize_payload_json_with_policy(raw, self.compression_policy)?;
+        let now = Instant::now();
+        self.prune_expired(now);
+        // Small plain frames are already canonical; skip the normalization probes.
+        let fast = is_small_frame(raw);
+        let normalized = if fast {
+            Cow::Borrowed(raw.trim())
+        } else if self.strict {
+            normalize_payload_json_with_policy(raw, self.compression_policy)?
+        } else {
+            let (normalized, ignored) =
+                normalize_payload_json_permissive(raw, self.compression_policy)?;
+            for field in ignored {
+                if self.ignored_fields.insert(field.clone()) {
+                    self.unreported_fields.push(field);
+                }
+            }
+            normalized
+        };
         let canonical = normalized.as_ref();
-        if canonical.len() > MAX_FRAME_BYTES {
-            return Err(Error::Parse(format!(
-                "frame exceeds {MAX_FRAME_BYTES} bytes"
-            )));
+        let page_set = !fast && is_page_set(canonical);
+        let limit = if page_set {
+            MAX_PAGE_SET_BYTES
+        } else {
+            MAX_FRAME_BYTES
+        };
+        if canonical.len() > limit {
+            return Err(
+                FrameErrorCode::FrameTooLarge.error(format_args!("frame exceeds {limit} bytes"))
+            );
         }
 
         let crc = checksum_raw(canonical);
         if self.last_crc == Some(crc) {
             return Ok(None);
         }
-        let frame = RenderFrame::from_normalized_payload_with_defaults(canonical, self.defaults)?;
+        if let Some(guard) = self.replay_guard.as_mut() {
+            // After a link flap the peer may resend frames we already showed; drop those until
+            // the first unseen frame proves the replay is over. A sender that simply keeps
+            // rotating cached pages outlasts the window or the frame budget and is shown again.
+            if now >= guard.until || guard.remaining == 0 {
+                self.replay_guard = None;
+            } else if self.recent.contains(crc) {
+                guard.remaining -= 1;
+                self.cache_stats.hits += 1;
+                self.recent.touch(crc);
+                return Ok(None);
+            } else {
+                self.cache_stats.misses += 1;
+                self.replay_guard = None;
+            }
+        }
+        if page_set {
+            return self.replace_pages(canonical, crc);
+        }
+        let mut frame =
+            RenderFrame::from_normalized_payload_with_defaults(canonical, self.defaults)?;
+        self.constrain(&mut frame);
+        if !self.filter.admits(&frame) {
+            return Ok(None);
+        }
+        if let Some(target) = frame.target.as_deref() {
+            if !self.zones.contains_key(target) {
+                return Err(FrameErrorCode::BadField
+                    .error(format_args!("target '{target}' is not a configured zone")));
+            }
+        }
         let expires_at = frame
             .duration_ms
             .map(|ms| Instant::now() + Duration::from_millis(ms));
         self.last_crc = Some(crc);
+        self.recent.touch(crc);
+        if let Some(zone) = frame
+            .target
+            .as_deref()
+            .and_then(|name| self.zones.get_mut(name))
+        {
+            zone.pages.push_front(FrameEntry {
+                frame: frame.clone(),
+                expires_at,
+            });
+            zone.pages.truncate(MAX_ZONE_PAGES);
+            zone.restart();
+            return Ok(Some(frame));
+        }
+        if frame.priority.interrupts() {
+            let at = self
+                .interrupts
+                .iter()
+                .position(|entry| entry.frame.priority < frame.priority)
+                .unwrap_or(self.interrupts.len());
+            self.interrupts.insert(
+                at,
+                InterruptEntry {
+                    frame: frame.clone(),
+                    shown_until: None,
+                },
+            );
+            return Ok(Some(frame));
+        }
         self.pages.push_back(FrameEntry {
             frame: frame.clone(),
             expires_at,
         });
+        self.revision += 1;
+        if let Some(resume) = self.resume.as_mut() {
+            // Newer than the page the interrupt cut off, so it is what comes back afterwards.
+            *resume = Some(frame.clone());
+        }
         Ok(Some(frame))
     }
 
+    /// Step the interrupt queue. Drops interrupts whose hold time (`duration_ms`, else
+    /// `page_timeout_ms`) has run out, then reports what should be on screen. The first
+    /// interrupt remembers `shown`, the page it cuts off, and hands it back once the queue
+    /// drains.
+    pub fn poll_interrupt(&mut self, now: Instant, shown: Option<&RenderFrame>) -> InterruptStep {
+        self.interrupts
+            .retain(|entry| entry.shown_until.is_none_or(|until| until > now));
+        let Some(head) = self.interrupts.first_mut() else {
+            return match self.resume.take() {
+                Some(resume) => InterruptStep::Resume(self.resume_page(resume)),
+                None => InterruptStep::Idle,
+            };
+        };
+        let hold = head.frame.duration_ms.unwrap_or(head.frame.page_timeout_ms);
+        head.shown_until
+            .get_or_insert(now + Duration::from_millis(hold));
+        let frame = head.frame.clone();
+        if self.resume.is_none() {
+            self.resume = Some(shown.cloned());
+        }
+        InterruptStep::Show(frame)
+    }
+
+    /// Whether interrupts own the display; the page rotation waits until they are done.
+    pub fn interrupting(&self) -> bool {
+        self.resume.is_some() || !self.interrupts.is_empty()
+    }
+
+    /// The page to put back after an interrupt: the one it cut off while it is still queued,
+    /// otherwise the next page in the rotation.
+    fn resume_page(&mut self, resume: Option<RenderFrame>) -> Option<RenderFrame> {
+        self.prune_expired(Instant::now());
+        resume
+            .filter(|frame| self.pages.iter().any(|entry| &entry.frame == frame))
+            .or_else(|| self.next_page())
+    }
+
+    /// Pages the filter drops are left out; a set with none left is ignored like a duplicate.
+    fn replace_pages(&mut self, canonical: &str, crc: u32) -> Result<Option<RenderFrame>> {
+        let mut frames = RenderFrame::pages_from_normalized_payload(canonical, self.defaults)?;
+        for frame in &mut frames {
+            self.constrain(frame);
+        }
+        frames.retain(|frame| self.filter.admits(frame));
+        if frames.is_empty() {
+            return Ok(None);
+        }
+        let now = Instant::now();
+        self.pages = frames
+            .into_iter()
+            .map(|frame| FrameEntry {
+                expires_at: frame.duration_ms.map(|ms| now + Duration::from_millis(ms)),
+                frame,
+            })
+            .collect();
+        self.revision += 1;
+        self.last_crc = Some(crc);
+        self.recent.touch(crc);
+        // The first page goes on screen now, so rotation continues with the second.
+        let first = self.pages.pop_front().expect("checked above");
+        let frame = first.frame.clone();
+        self.pages.push_back(first);
+        Ok(Some(frame))
+    }
+
+    /// Apply a row-addressed update to the page currently on screen (`shown`), falling back to
+    /// the newest page, or a blank one when the queue is empty. Returns the updated frame, or
+    /// `None` when the row already holds that text or the patched page is dropped by the frame
+    /// filter.
+    pub fn apply_row(
+        &mut self,
+        shown: Option<&RenderFrame>,
+        update: &RowUpdate,
+    ) -> Option<RenderFrame> {
+        self.prune_expired(Instant::now());
+        if let Some(entry) = shown.and_then(|shown| {
+            self.interrupts
+                .iter_mut()
+                .find(|entry| &entry.frame == shown)
+        }) {
+            return patch_row(
+                &mut entry.frame,
+                update,
+                &mut self.last_crc,
+                &mut self.filter,
+            );
+        }
+        let idx = shown
+            .and_then(|shown| self.pages.iter().position(|entry| &entry.frame == shown))
+            .or_else(|| self.pages.len().checked_sub(1));
+        let idx = match idx {
+            Some(idx) => idx,
+            None => {
+                let mut blank = RenderFrame::from_normalized_payload_with_defaults(
+                    r#"{"schema_version":1,"line1":"","line2":""}"#,
+                    self.defaults,
+                )
+                .ok()?;
+                self.constrain(&mut blank);
+                self.pages.push_back(FrameEntry {
+                    frame: blank,
+                    expires_at: None,
+                });
+                self.pages.len() - 1
+            }
+        };
+        let patched = patch_row(
+            &mut self.pages[idx].frame,
+            update,
+            &mut self.last_crc,
+            &mut self.filter,
+        );
+        if patched.is_some() {
+            self.revision += 1;
+        }
+        patched
+    }
+
     /// Advance to the next page/frame if available.
     pub fn next_page(&mut self) -> Option<RenderFrame> {
         self.prune_expired(Instant::now());
@@ -85,12 +428,33 @@ impl RenderState {
         Some(frame)
     }
 
+    /// Step back to the page before the last one returned by [`RenderState::next_page`].
+    pub fn prev_page(&mut self) -> Option<RenderFrame> {
+        self.prune_expired(Instant::now());
+        // The page on screen sits at the back; move it to the front so its predecessor is last.
+        let shown = self.pages.pop_back()?;
+        self.pages.push_front(shown);
+        self.pages.back().map(|entry| entry.frame.clone())
+    }
+
     /// Get the current frame without rotating.
     pub fn current(&mut self) -> Option<&RenderFrame> {
         self.prune_expired(Instant::now());
         self.pages.front().map(|f| &f.frame)
     }
 
+    /// When a queued copy of `frame` expires; `None` for frames without `duration_ms` or once
+    /// the frame has been pruned. An interrupt on screen expires when its hold time ends.
+    pub fn expires_at(&self, frame: &RenderFrame) -> Option<Instant> {
+        if let Some(entry) = self.interrupts.iter().find(|entry| &entry.frame == frame) {
+            return entry.shown_until;
+        }
+        self.pages
+            .iter()
+            .find(|entry| &entry.frame == frame)
+            .and_then(|entry| entry.expires_at)
+    }
+
     pub fn len(&mut self) -> usize {
         self.prune_expired(Instant::now());
         self.pages.len()
@@ -101,6 +465,113 @@ impl RenderState {
         self.pages.is_empty()
     }
 
+    /// Drop every queued page and pending interrupt. The last frame no longer counts as a
+    /// duplicate, so resending it queues it again.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+        self.revision += 1;
+        self.interrupts.clear();
+        if let Some(resume) = self.resume.as_mut() {
+            *resume = None;
+        }
+        for zone in self.zones.values_mut() {
+            zone.pages.clear();
+            zone.restart();
+        }
+        self.last_crc = None;
+    }
+
+    /// Accept `target`s naming these zones from now on. Zones that stay keep their pages;
+    /// removed ones are dropped.
+    pub fn set_zones<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        let mut zones = BTreeMap::new();
+        for name in names {
+            let zone = self.zones.remove(name).unwrap_or_default();
+            zones.insert(name.to_string(), zone);
+        }
+        self.zones = zones;
+    }
+
+    /// Step zone `name` to `now` and return its page with the scroll offset of each row, or
+    /// `None` while it has nothing to show. The zone moves to its next page every
+    /// `page_timeout_ms` and scrolls rows wider than `width` every `scroll_speed_ms`, the same
+    /// as the main rotation; priority has no effect inside a zone.
+    pub fn poll_zone(
+        &mut self,
+        name: &str,
+        now: Instant,
+        rows: u8,
+        width: usize,
+    ) -> Option<(&RenderFrame, &[usize])> {
+        let zone = self.zones.get_mut(name)?;
+        let before = zone.pages.len();
+        zone.pages
+            .retain(|entry| entry.expires_at.is_none_or(|expiry| expiry > now));
+        if zone.pages.len() != before {
+            zone.restart();
+        }
+        if zone.pages.len() > 1 && zone.next_page.is_some_and(|due| due <= now) {
+            zone.pages.rotate_left(1);
+            zone.restart();
+        }
+        let Zone {
+            pages,
+            next_page,
+            offsets,
+            next_scroll,
+        } = zone;
+        let front = &pages.front()?.frame;
+        next_page.get_or_insert(now + Duration::from_millis(front.page_timeout_ms));
+        let rows = rows.min(MAX_ROWS);
+        let scrolls = front.scroll_enabled
+            && (0..rows)
+                .filter(|row| front.bar_row != Some(*row))
+                .any(|row| line_needs_scroll(front.line(row), width));
+        if scrolls {
+            let step = Duration::from_millis(front.scroll_speed_ms);
+            match *next_scroll {
+                Some(due) if due <= now => {
+                    for row in 0..rows {
+                        let offset = &mut offsets[row as usize];
+                        *offset = advance_offset(front.line(row), width, *offset);
+                    }
+                    *next_scroll = Some(now + step);
+                }
+                Some(_) => {}
+                None => *next_scroll = Some(now + step),
+            }
+        }
+        Some((front, &offsets[..rows as usize]))
+    }
+
+    /// The earliest time a zone changes page, scrolls or loses an expiring page.
+    pub fn next_zone_step(&self) -> Option<Instant> {
+        self.zones
+            .values()
+            .flat_map(|zone| {
+                let rotates = zone.next_page.filter(|_| zone.pages.len() > 1);
+                let expiries = zone.pages.iter().filter_map(|entry| entry.expires_at);
+                rotates.into_iter().chain(zone.next_scroll).chain(expiries)
+            })
+            .min()
+    }
+
+    /// Fit every queued page to a new display geometry (see [`RenderFrame::clamp_to`]).
+    pub fn clamp_to(&mut self, cols: u8, rows: u8) {
+        let mut changed = false;
+        for entry in &mut self.pages {
+            changed |= entry.frame.clamp_to(cols, rows);
+        }
+        for entry in &mut self.interrupts {
+            changed |= entry.frame.clamp_to(cols, rows);
+        }
+        if changed {
+            // A resend of a clipped page must queue the full text again.
+            self.last_crc = None;
+            self.revision += 1;
+        }
+    }
+
     pub fn set_defaults(&mut self, defaults: Defaults) {
         self.defaults = defaults;
     }
@@ -109,12 +580,124 @@ impl RenderState {
         self.compression_policy = policy;
     }
 
+    /// Constrain every frame to `profile` from now on, including those already queued; `None`
+    /// lifts the constraint for new frames.
+    pub fn set_accessibility(&mut self, profile: Option<AccessibilityProfile>) {
+        self.accessibility = profile;
+        if let Some(profile) = profile {
+            for frame in self
+                .pages
+                .iter_mut()
+                .map(|entry| &mut entry.frame)
+                .chain(self.interrupts.iter_mut().map(|entry| &mut entry.frame))
+            {
+                profile.apply(frame);
+            }
+            self.revision += 1;
+        }
+    }
+
+    fn constrain(&self, frame: &mut RenderFrame) {
+        if let Some(profile) = &self.accessibility {
+            profile.apply(frame);
+        }
+    }
+
+    /// Filter frames through `filter` from now on; pages already queued stay.
+    pub fn set_filter(&mut self, mut filter: FrameFilter) {
+        filter.set_minute_of_day(self.filter.minute_of_day());
+        self.filter = filter;
+    }
+
+    pub fn filter(&self) -> &FrameFilter {
+        &self.filter
+    }
+
+    /// Local time for windowed filter rules, as minutes since midnight.
+    pub fn set_minute_of_day(&mut self, minute: u16) {
+        self.filter.set_minute_of_day(minute);
+    }
+
+    /// Whether the filter keeps polling snapshots off the screen right now.
+    pub fn mutes_polling(&mut self) -> bool {
+        self.filter.mutes_polling()
+    }
+
+    /// `false` drops unknown payload fields instead of rejecting the frame.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Unknown field names ignored since the last call, each returned only the first time it
+    /// is seen.
+    pub fn take_ignored_fields(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.unreported_fields)
+    }
+
+    /// Resize the recent-frame cache; 0 disables replay suppression.
+    pub fn set_frame_cache_size(&mut self, size: usize) {
+        self.recent.resize(size);
+    }
+
+    /// Call after the serial link is re-established so replayed frames are suppressed for a
+    /// short window.
+    pub fn arm_replay_guard(&mut self) {
+        self.replay_guard = (self.recent.capacity > 0).then(|| ReplayGuard {
+            until: Instant::now() + REPLAY_GUARD_WINDOW,
+            remaining: REPLAY_GUARD_MAX_FRAMES,
+        });
+    }
+
+    pub fn cache_stats(&self) -> FrameCacheStats {
+        self.cache_stats
+    }
+
+    /// Changes whenever the queued pages do; see [`RenderState::saved_pages`].
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Every queued page, starting with the one last handed out by [`RenderState::next_page`].
+    pub fn queued(&self) -> impl Iterator<Item = &RenderFrame> {
+        self.pages
+            .back()
+            .into_iter()
+            .chain(self.pages.iter().take(self.pages.len().saturating_sub(1)))
+            .map(|entry| &entry.frame)
+    }
+
+    /// Queued pages worth keeping across a restart, starting with the one last handed out by
+    /// [`RenderState::next_page`]. Pages with `duration_ms` are transient and left out.
+    pub fn saved_pages(&self) -> Vec<RenderFrame> {
+        self.pages
+            .back()
+            .into_iter()
+            .chain(self.pages.iter().take(self.pages.len().saturating_sub(1)))
+            .filter(|entry| entry.expires_at.is_none())
+            .map(|entry| entry.frame.clone())
+            .collect()
+    }
+
+    /// Queue pages from a saved snapshot behind anything already queued. They do not count as
+    /// duplicates, so the peer resending one simply queues it again.
+    pub fn restore_pages(&mut self, frames: Vec<RenderFrame>) {
+        for mut frame in frames {
+            self.constrain(&mut frame);
+            self.pages.push_back(FrameEntry {
+                frame,
+                expires_at: None,
+            });
+        }
+        self.revision += 1;
+    }
+
     fn prune_expired(&mut self, now: Instant) {
         // Drop expired frames so the queue reflects currently valid pages and CRC dedupe can reset.
         while let Some(front) = self.pages.front() {
             if let Some(expiry) = front.expires_at {
                 if expiry <= now {
                     self.pages.pop_front();
+                    self.revision += 1;
                     continue;
                 }
             }
@@ -126,6 +709,31 @@ impl RenderState {
     }
 }
 
+/// Set one row of `frame`; `None` when it already holds that text.
+fn patch_row(
+    frame: &mut RenderFrame,
+    update: &RowUpdate,
+    last_crc: &mut Option<u32>,
+    filter: &mut FrameFilter,
+) -> Option<RenderFrame> {
+    // Row updates may use the page's own glyphs.
+    let text = expand_placeholders(&update.text, &frame.custom_glyphs).into_owned();
+    let mut patched = frame.clone();
+    let line = patched.line_mut(update.row)?;
+    if *line == text {
+        return None;
+    }
+    *line = text;
+    // A row can carry what a full frame would have been dropped for; the page stays as it was.
+    if !filter.admits(&patched) {
+        return None;
+    }
+    // The page no longer matches the last full frame, so a resend of it must not be deduped.
+    *last_crc = None;
+    *frame = patched.clone();
+    Some(patched)
+}
+
 fn checksum_raw(raw: &str) -> u32 {
     let mut hasher = Hasher::new();
     hasher.update(raw.as_bytes());
@@ -147,6 +755,78 @@ mod tests {
         assert!(first.is_some());
         let second = state.ingest(raw).unwrap();
         assert!(second.is_none());
+
+        state.clear();
+        assert!(state.is_empty());
+        assert!(state.ingest(raw).unwrap().is_some());
+    }
+
+    #[test]
+    fn filtered_frames_are_dropped_without_becoming_the_last_frame() {
+        let mut state = RenderState::new(None);
+        state.set_filter(FrameFilter::new(&["drop:line1~^DEBUG".into()]).unwrap());
+        let noisy = r#"{"schema_version":1,"line1":"DEBUG 1","line2":""}"#;
+        assert!(state.ingest(noisy).unwrap().is_none());
+        assert!(state.ingest(noisy).unwrap().is_none());
+        assert_eq!(state.filter().dropped(), 2);
+        assert!(state.is_empty());
+
+        let pages = r#"{"schema_version":1,"pages":[{"line1":"DEBUG 2","line2":""},
+            {"line1":"CPU","line2":""}]}"#;
+        assert_eq!(state.ingest(pages).unwrap().unwrap().line1, "CPU");
+        assert_eq!(state.len(), 1);
+        let all_noise = r#"{"schema_version":1,"pages":[{"line1":"DEBUG 3","line2":""}]}"#;
+        assert!(state.ingest(all_noise).unwrap().is_none());
+        assert_eq!(state.len(), 1);
+    }
+
+    #[test]
+    fn row_updates_pass_through_the_filter() {
+        let mut state = RenderState::new(None);
+        state.set_filter(FrameFilter::new(&["drop:line2~^DEBUG".into()]).unwrap());
+        let shown = state
+            .ingest(r#"{"schema_version":1,"line1":"A","line2":"B"}"#)
+            .unwrap()
+            .unwrap();
+        let update = |text: &str| RowUpdate {
+            row: 1,
+            text: text.into(),
+            schema_version: None,
+        };
+        assert!(state
+            .apply_row(Some(&shown), &update("DEBUG tick"))
+            .is_none());
+        assert_eq!(state.filter().dropped(), 1);
+        // The page keeps its old row, so the next update still patches it.
+        let patched = state.apply_row(Some(&shown), &update("CPU 12%")).unwrap();
+        assert_eq!(patched.line2, "CPU 12%");
+        assert_eq!(state.filter().dropped(), 1);
+    }
+
+    #[test]
+    fn row_updates_patch_the_shown_page() {
+        let mut state = RenderState::new(None);
+        let raw = r#"{"schema_version":1,"line1":"A","line2":"B"}"#;
+        let shown = state.ingest(raw).unwrap().unwrap();
+        let update = RowUpdate {
+            row: 1,
+            text: "count 1".into(),
+            schema_version: None,
+        };
+        let patched = state.apply_row(Some(&shown), &update).unwrap();
+        assert_eq!(
+            (patched.line1.as_str(), patched.line2.as_str()),
+            ("A", "count 1")
+        );
+        assert!(state.apply_row(Some(&patched), &update).is_none());
+        assert_eq!(state.len(), 1);
+        // Resending the original full frame restores it instead of being deduped.
+        assert!(state.ingest(raw).unwrap().is_some());
+
+        let mut empty = RenderState::new(None);
+        let fresh = empty.apply_row(None, &update).unwrap();
+        assert_eq!(fresh.line2, "count 1");
+        assert_eq!(empty.len(), 1);
     }
 
     #[test]
@@ -166,6 +846,65 @@ mod tests {
         assert_eq!(third.line1, "A");
     }
 
+    #[test]
+    fn replayed_frames_after_reconnect_are_suppressed() {
+        let mut state = RenderState::new(None);
+        let a = r#"{"schema_version":1,"line1":"A","line2":"1"}"#;
+        let b = r#"{"schema_version":1,"line1":"B","line2":"2"}"#;
+        let c = r#"{"schema_version":1,"line1":"C","line2":"3"}"#;
+        assert!(state.ingest(a).unwrap().is_some());
+        assert!(state.ingest(b).unwrap().is_some());
+
+        state.arm_replay_guard();
+        assert!(state.ingest(a).unwrap().is_none());
+        assert!(state.ingest(c).unwrap().is_some());
+        // Guard is disarmed once new content arrives.
+        assert!(state.ingest(a).unwrap().is_some());
+        assert_eq!(state.cache_stats(), FrameCacheStats { hits: 1, misses: 1 });
+    }
+
+    #[test]
+    fn cached_rotation_resumes_after_reconnect() {
+        let mut state = RenderState::new(None);
+        let a = r#"{"schema_version":1,"line1":"A","line2":"1"}"#;
+        let b = r#"{"schema_version":1,"line1":"B","line2":"2"}"#;
+        assert!(state.ingest(a).unwrap().is_some());
+        assert!(state.ingest(b).unwrap().is_some());
+
+        // The peer keeps rotating A/B after the flap: only the first few count as a replay.
+        state.arm_replay_guard();
+        let budget = REPLAY_GUARD_MAX_FRAMES as usize;
+        let shown: Vec<bool> = [a, b]
+            .iter()
+            .cycle()
+            .take(2 * budget + 4)
+            .map(|raw| state.ingest(raw).unwrap().is_some())
+            .collect();
+        let resumed = shown
+            .iter()
+            .position(|shown| *shown)
+            .expect("rotation resumed");
+        assert!(resumed <= 2 * budget, "resumed at frame {resumed}");
+        assert!(shown[resumed..].iter().all(|shown| *shown));
+        assert_eq!(state.cache_stats().hits, u64::from(REPLAY_GUARD_MAX_FRAMES));
+    }
+
+    #[test]
+    fn frame_cache_evicts_oldest_and_can_be_disabled() {
+        let mut state = RenderState::new(None);
+        state.set_frame_cache_size(1);
+        let a = r#"{"schema_version":1,"line1":"A","line2":""}"#;
+        let b = r#"{"schema_version":1,"line1":"B","line2":""}"#;
+        state.ingest(a).unwrap();
+        state.ingest(b).unwrap();
+        state.arm_replay_guard();
+        assert!(state.ingest(a).unwrap().is_some(), "A was evicted");
+
+        state.set_frame_cache_size(0);
+        state.arm_replay_guard();
+        assert!(state.ingest(b).unwrap().is_some());
+    }
+
     #[test]
     fn rejects_oversize_frame() {
         let mut state = RenderState::new(None);
@@ -191,6 +930,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn expires_at_reports_the_ttl_deadline() {
+        let mut state = RenderState::new(None);
+        let before = Instant::now();
+        let timed = state
+            .ingest(r#"{"schema_version":1,"line1":"A","line2":"B","duration_ms":5000}"#)
+            .unwrap()
+            .unwrap();
+        let untimed = state
+            .ingest(r#"{"schema_version":1,"line1":"C","line2":"D"}"#)
+            .unwrap()
+            .unwrap();
+        let deadline = state.expires_at(&timed).unwrap();
+        assert!(deadline >= before + Duration::from_millis(5000));
+        assert_eq!(state.expires_at(&untimed), None);
+    }
+
     #[derive(Serialize)]
     struct TestEnvelope {
         #[serde(rename = "type")]
@@ -202,6 +958,18 @@ mod tests {
         data: ByteBuf,
     }
 
+    #[test]
+    fn clamping_queued_pages_lets_the_full_frame_back_in() {
+        let mut state = RenderState::new(None);
+        let raw =
+            r#"{"schema_version":1,"line1":"0123456789ABCDEFGHIJ","line2":"B","scroll":false}"#;
+        state.ingest(raw).unwrap().unwrap();
+        state.clamp_to(16, 2);
+        assert_eq!(state.current().unwrap().line1, "0123456789ABCDEF");
+        // The clipped page no longer matches what the peer sent, so a resend is not a duplicate.
+        assert!(state.ingest(raw).unwrap().is_some());
+    }
+
     #[test]
     fn compressed_payload_dedupes_with_plain() {
         let mut state = RenderState::new(None);
@@ -238,4 +1006,210 @@ mod tests {
         let err = state.ingest(&wrapped).unwrap_err();
         assert!(format!("{err}").contains("compression disabled"));
     }
+
+    #[test]
+    fn permissive_mode_reports_each_unknown_field_once() {
+        let mut state = RenderState::new(None);
+        let raw = r#"{"schema_version":1,"line1":"A","line2":"B","color":"red"}"#;
+        assert!(state.ingest(raw).is_err());
+
+        state.set_strict(false);
+        assert_eq!(state.ingest(raw).unwrap().unwrap().line1, "A");
+        assert_eq!(state.take_ignored_fields(), vec!["color".to_string()]);
+        let again = r#"{"schema_version":1,"line1":"C","line2":"D","color":"blue"}"#;
+        assert!(state.ingest(again).unwrap().is_some());
+        assert!(state.take_ignored_fields().is_empty());
+    }
+
+    #[test]
+    fn page_set_replaces_the_queue_and_rotates_in_order() {
+        let mut state = RenderState::new(None);
+        state
+            .ingest(r#"{"schema_version":1,"line1":"OLD","line2":""}"#)
+            .unwrap();
+        let raw = r#"{"schema_version":1,"pages":[
+            {"line1":"CPU","line2":"42%","page_timeout_ms":2000},
+            {"line1":"MEM","line2":"61%","page_timeout_ms":5000},
+            {"line1":"NET","line2":"up"}
+        ]}"#;
+        let first = state.ingest(raw).unwrap().unwrap();
+        assert_eq!(first.line1, "CPU");
+        assert_eq!(first.page_timeout_ms, 2000);
+        assert_eq!(state.len(), 3);
+
+        let second = state.next_page().unwrap();
+        assert_eq!(
+            (second.line1.as_str(), second.page_timeout_ms),
+            ("MEM", 5000)
+        );
+        let third = state.next_page().unwrap();
+        assert_eq!(third.line1, "NET");
+        assert_eq!(third.page_timeout_ms, DEFAULT_PAGE_TIMEOUT_MS);
+        assert_eq!(state.next_page().unwrap().line1, "CPU");
+
+        assert!(state.ingest(raw).unwrap().is_none());
+    }
+
+    #[test]
+    fn page_set_rejects_bad_pages_as_a_whole() {
+        let mut state = RenderState::new(None);
+        let too_long = format!(
+            r#"{{"schema_version":1,"pages":[{{"line1":"ok","line2":""}},{{"line1":"{}","line2":""}}]}}"#,
+            "x".repeat(41)
+        );
+        let err = state.ingest(&too_long).unwrap_err();
+        assert!(format!("{err}").contains("pages[1]"));
+        assert!(state.is_empty());
+        assert!(state.ingest(r#"{"schema_version":1,"pages":[]}"#).is_err());
+        assert!(state
+            .ingest(r#"{"schema_version":1,"pages":[{"line1":"a","line2":"b"}],"line1":"x"}"#)
+            .is_err());
+    }
+
+    #[test]
+    fn prev_page_walks_the_rotation_backwards() {
+        let mut state = RenderState::new(None);
+        for line in ["A", "B", "C"] {
+            let raw = format!(r#"{{"schema_version":1,"line1":"{line}","line2":""}}"#);
+            state.ingest(&raw).unwrap();
+        }
+        assert_eq!(state.next_page().unwrap().line1, "A");
+        assert_eq!(state.prev_page().unwrap().line1, "C");
+        assert_eq!(state.prev_page().unwrap().line1, "B");
+        assert_eq!(state.next_page().unwrap().line1, "C");
+        assert_eq!(state.next_page().unwrap().line1, "A");
+    }
+
+    #[test]
+    fn interrupts_run_most_urgent_first_then_resume_the_cut_off_page() {
+        let mut state = RenderState::new(None);
+        let shown = state
+            .ingest(r#"{"schema_version":1,"line1":"CPU","line2":""}"#)
+            .unwrap()
+            .unwrap();
+        let now = Instant::now();
+        assert_eq!(state.poll_interrupt(now, Some(&shown)), InterruptStep::Idle);
+
+        state
+            .ingest(r#"{"schema_version":1,"line1":"SYNC","line2":"","priority":"high","duration_ms":1000}"#)
+            .unwrap();
+        state
+            .ingest(r#"{"schema_version":1,"line1":"UPS ON BATTERY","line2":"","priority":"urgent","duration_ms":2000}"#)
+            .unwrap();
+        assert_eq!(state.len(), 1, "interrupts stay out of the rotation");
+        assert!(state.interrupting());
+
+        let InterruptStep::Show(urgent) = state.poll_interrupt(now, Some(&shown)) else {
+            panic!("urgent interrupt should be shown");
+        };
+        assert_eq!(urgent.line1, "UPS ON BATTERY");
+        assert_eq!(
+            state.expires_at(&urgent),
+            Some(now + Duration::from_millis(2000))
+        );
+
+        // A normal frame during the interrupt queues and becomes the page to come back to.
+        state
+            .ingest(r#"{"schema_version":1,"line1":"MEM","line2":""}"#)
+            .unwrap();
+        let later = now + Duration::from_millis(2000);
+        let InterruptStep::Show(high) = state.poll_interrupt(later, Some(&urgent)) else {
+            panic!("high interrupt should follow");
+        };
+        assert_eq!(high.line1, "SYNC");
+
+        let done = later + Duration::from_millis(1000);
+        let InterruptStep::Resume(Some(resumed)) = state.poll_interrupt(done, Some(&high)) else {
+            panic!("rotation should resume");
+        };
+        assert_eq!(resumed.line1, "MEM");
+        assert!(!state.interrupting());
+        assert_eq!(
+            state.poll_interrupt(done, Some(&resumed)),
+            InterruptStep::Idle
+        );
+    }
+
+    #[test]
+    fn zones_rotate_and_scroll_apart_from_the_main_queue() {
+        let mut state = RenderState::new(None);
+        state.set_zones(["alerts", "status"]);
+        assert!(state
+            .ingest(r#"{"schema_version":1,"line1":"x","line2":"","target":"nowhere"}"#)
+            .is_err());
+
+        state
+            .ingest(r#"{"schema_version":1,"line1":"UPS OK","line2":"","target":"alerts","page_timeout_ms":1000}"#)
+            .unwrap();
+        state
+            .ingest(r#"{"schema_version":1,"line1":"disk 91% on /var","line2":"","target":"alerts","page_timeout_ms":1000,"scroll_speed_ms":200}"#)
+            .unwrap();
+        assert!(
+            state.is_empty(),
+            "zone frames stay out of the main rotation"
+        );
+        assert!(state.poll_zone("status", Instant::now(), 1, 8).is_none());
+
+        // The newest frame goes on first, then scrolls every 200ms and rotates after a second.
+        let now = Instant::now();
+        let (frame, offsets) = state.poll_zone("alerts", now, 1, 8).unwrap();
+        assert_eq!(
+            (frame.line1.as_str(), offsets),
+            ("disk 91% on /var", &[0][..])
+        );
+        assert_eq!(
+            state.next_zone_step(),
+            Some(now + Duration::from_millis(200))
+        );
+        let (_, offsets) = state
+            .poll_zone("alerts", now + Duration::from_millis(200), 1, 8)
+            .unwrap();
+        assert_eq!(offsets, [1]);
+        let (frame, offsets) = state
+            .poll_zone("alerts", now + Duration::from_millis(1000), 1, 8)
+            .unwrap();
+        assert_eq!((frame.line1.as_str(), offsets), ("UPS OK", &[0][..]));
+
+        state.set_zones(["status"]);
+        assert!(state.poll_zone("alerts", now, 1, 8).is_none());
+    }
+
+    #[test]
+    fn row_updates_patch_the_interrupt_on_screen() {
+        let mut state = RenderState::new(None);
+        let alert = state
+            .ingest(r#"{"schema_version":1,"line1":"UPS","line2":"","priority":"urgent"}"#)
+            .unwrap()
+            .unwrap();
+        let update = RowUpdate {
+            row: 1,
+            text: "9 min left".into(),
+            schema_version: None,
+        };
+        let patched = state.apply_row(Some(&alert), &update).unwrap();
+        assert_eq!(patched.line2, "9 min left");
+        assert!(state.is_empty());
+        assert_eq!(
+            state.poll_interrupt(Instant::now(), None),
+            InterruptStep::Show(patched)
+        );
+    }
+
+    #[test]
+    fn accessibility_profile_constrains_new_and_queued_frames() {
+        let mut state = RenderState::new(None);
+        let queued = r#"{"schema_version":1,"line1":"A","line2":"","blink":true}"#;
+        assert!(state.ingest(queued).unwrap().unwrap().blink);
+        state.set_accessibility(Some(AccessibilityProfile {
+            min_scroll_speed_ms: 600,
+            min_page_timeout_ms: 8_000,
+        }));
+        assert!(!state.current().unwrap().blink);
+
+        let fast = state
+            .ingest(r#"{"schema_version":1,"line1":"B","line2":"","scroll_speed_ms":120}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!((fast.scroll_speed_ms, fast.page_timeout_ms), (600, 8_000));
+    }
 }
@@ -83,7 +83,7 @@ fn install_default_wizard_script(home: &Path) -> EnvVarGuard {
     install_wizard_script(
         home,
         "wizard_defaults.txt",
-        "standalone\ny\n/dev/ttyUSB0\n9600\nn\n16\n2\nauto\nn\n",
+        "standalone\ny\n/dev/ttyUSB0\n9600\nn\n16\n2\nauto\nn\nn\n",
     )
 }
 
@@ -128,6 +128,40 @@ fn prints_version() {
     assert!(!env!("CARGO_PKG_VERSION").is_empty());
 }
 
+#[test]
+fn exit_codes_follow_the_failure_class() {
+    let home = temp_home();
+    fs::create_dir_all(&home).expect("failed to create temp HOME");
+    let bad_config = home.join("bad.toml");
+    fs::write(&bad_config, "baud = \"fast\"\n").expect("failed to write config");
+    let bad_payload = home.join("bad.json");
+    fs::write(&bad_payload, "{not json").expect("failed to write payload");
+    let run = |args: &[&OsStr]| {
+        ProcessCommand::new(env!("CARGO_BIN_EXE_lifelinetty"))
+            .env("HOME", &home)
+            .args(args)
+            .output()
+            .expect("failed to spawn lifelinetty")
+            .status
+            .code()
+    };
+
+    assert_eq!(run(&[OsStr::new("--no-such-flag")]), Some(2));
+    assert_eq!(
+        run(&[OsStr::new("--config-file"), bad_config.as_os_str()]),
+        Some(3)
+    );
+    assert_eq!(
+        run(&[
+            OsStr::new("render"),
+            OsStr::new("--payload"),
+            bad_payload.as_os_str()
+        ]),
+        Some(8)
+    );
+    let _ = fs::remove_dir_all(home);
+}
+
 #[test]
 fn help_lists_core_flags() {
     let help = Command::help();
@@ -149,13 +183,14 @@ fn wizard_prints_helpers_and_ranks_device_list() {
         let script = install_wizard_script(
             home,
             "wizard_script_helpers.txt",
-            // Prompts consumed (in order): intent, lcd_present, device, baud, probe?, role, show_helpers?, save?
+            // Prompts consumed (in order): intent, lcd_present, device, baud, probe?, role, allowlist?, show_helpers?, save?
             "standalone\n\
 n\n\
 1\n\
 9600\n\
 n\n\
 auto\n\
+n\n\
 y\n\
 y\n",
         );
@@ -427,7 +462,7 @@ fn wizard_auto_runs_with_script_when_missing_config() {
         let _script_guard = install_wizard_script(
             home,
             "wizard_answers.txt",
-            "server\ny\n/dev/ttyS9\n19200\nn\n16\n2\nserver\nn\n",
+            "server\ny\n/dev/ttyS9\n19200\nn\n16\n2\nserver\nn\nn\n",
         );
 
         let app = App::from_options(RunOptions::default()).expect("wizard-driven app init failed");
@@ -603,7 +638,11 @@ mod serialsh_smoke {
             &[
                 "INIT".to_string(),
                 encoded(TunnelMsgOwned::CmdRequest {
-                    cmd: "echo hi".into()
+                    cmd: "echo hi".into(),
+                    otp: None,
+                    sig: None,
+                    cache_ttl_ms: None,
+                    no_cache: false,
                 })
             ]
         );
@@ -627,7 +666,13 @@ mod serialsh_smoke {
             serial.writes(),
             &[
                 "INIT".to_string(),
-                encoded(TunnelMsgOwned::CmdRequest { cmd: "list".into() })
+                encoded(TunnelMsgOwned::CmdRequest {
+                    cmd: "list".into(),
+                    otp: None,
+                    sig: None,
+                    cache_ttl_ms: None,
+                    no_cache: false,
+                })
             ]
         );
     }
@@ -100,9 +100,10 @@ fn smoke_lcd_write_lines_stub() {
         2,
         lifelinetty::config::DEFAULT_PCF8574_ADDR,
         lifelinetty::config::DEFAULT_DISPLAY_DRIVER,
+        lifelinetty::config::LcdGpioConfig::default(),
     )
     .unwrap();
-    lcd.write_lines("HELLO", "WORLD").unwrap();
+    lcd.write_lines(&["HELLO", "WORLD"]).unwrap();
 }
 
 #[test]
@@ -111,6 +112,8 @@ fn command_frame_detects_bad_crc() {
         request_id: 1,
         cmd: "echo hi".into(),
         scratch_path: None,
+        otp: None,
+        sig: None,
     };
     let encoded = encode_command_frame(&msg).expect("encode frame");
     let mut value: Value = serde_json::from_str(&encoded).expect("deserialize frame");
@@ -212,7 +215,7 @@ fn wizard_scripted_run_persists_config_to_home() {
     let script_path = dir.join("wizard_answers.txt");
     fs::write(
         &script_path,
-        // Prompts consumed (in order): intent, lcd_present, device, baud, probe?, role, show_helpers?, save?
+        // Prompts consumed (in order): intent, lcd_present, device, baud, probe?, role, allowlist?, show_helpers?, save?
         "standalone\n\
 n\n\
 /dev/ttyS42\n\
@@ -220,6 +223,7 @@ n\n\
 n\n\
 client\n\
 n\n\
+n\n\
 y\n",
     )
     .expect("failed to write wizard script");
@@ -14,7 +14,14 @@ fn tunnel_latency_allows_lcd_frames() {
             r#"{"schema_version":1,"line1":"FRAME 1","line2":"","scroll":false}"#.into(),
         )),
         FakeSerialEntry::with_delay(
-            Ok(encode_tunnel_msg(&TunnelMsgOwned::CmdRequest { cmd: "ls".into() }).unwrap()),
+            Ok(encode_tunnel_msg(&TunnelMsgOwned::CmdRequest {
+                cmd: "ls".into(),
+                otp: None,
+                sig: None,
+                cache_ttl_ms: None,
+                no_cache: false,
+            })
+            .unwrap()),
             Duration::from_millis(8),
         ),
         FakeSerialEntry::immediate(Ok(
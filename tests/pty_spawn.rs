@@ -364,7 +364,7 @@ fn pty_spawn_serialsh_tunnel_round_trip() {
         }
 
         // Serial shell only emits tunnel frames after INIT.
-        if let Ok(TunnelMsgOwned::CmdRequest { cmd }) = decode_tunnel_frame(&line) {
+        if let Ok(TunnelMsgOwned::CmdRequest { cmd, .. }) = decode_tunnel_frame(&line) {
             // Respond with a deterministic stdout+exit sequence.
             let stdout = if cmd == "echo hello" {
                 b"hello\n".to_vec()
@@ -461,6 +461,7 @@ fn pty_spawn_daemon_handshake_payload_and_command_frames() {
                     peer_caps: ControlCaps {
                         bits: Capabilities::default().bits(),
                     },
+                    link_proof: None,
                 };
                 let encoded = serde_json::to_string(&ack).unwrap();
                 write_line(&master, &encoded);
@@ -476,6 +477,8 @@ fn pty_spawn_daemon_handshake_payload_and_command_frames() {
                     request_id: 1,
                     cmd: "true".to_string(),
                     scratch_path: None,
+                    otp: None,
+                    sig: None,
                 };
                 let frame = encode_command_frame(&req).unwrap();
                 write_line(&master, &frame);